@@ -0,0 +1,280 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+#[cfg(test)]
+mod tests;
+
+use nuts_container::backend::{Backend, BlockId};
+
+use crate::entry::estimate_name_blocks;
+use crate::entry::immut::InnerEntry;
+use crate::error::ArchiveResult;
+use crate::pager::Pager;
+use crate::tree::{Tree, NUM_DIRECT};
+
+/// Block sizes, which are projected by default.
+const BLOCK_SIZES: [u32; 8] = [512, 1024, 2048, 4096, 8192, 16384, 32768, 65536];
+
+/// Number of entries with a size up to [`SizeClass::upper()`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SizeClass {
+    upper: u64,
+    count: u64,
+}
+
+impl SizeClass {
+    /// Returns the upper bound of the class (inclusive).
+    ///
+    /// The upper bound is a power of two or `0`. The class contains all
+    /// entries, which are larger than the upper bound of the previous class.
+    pub fn upper(&self) -> u64 {
+        self.upper
+    }
+
+    /// Returns the number of entries in the class.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+}
+
+/// Space needed to store the archive at a specific block size.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Projection {
+    block_size: u32,
+    metadata_blocks: u64,
+    content_blocks: u64,
+    overhead: u64,
+    fits: bool,
+}
+
+impl Projection {
+    /// Returns the block size of the projection.
+    pub fn block_size(&self) -> u32 {
+        self.block_size
+    }
+
+    /// Returns the number of blocks, which are needed for metadata.
+    ///
+    /// This covers the header block, a block for each entry and the nodes
+    /// of the tree.
+    pub fn metadata_blocks(&self) -> u64 {
+        self.metadata_blocks
+    }
+
+    /// Returns the number of blocks, which are needed for content.
+    pub fn content_blocks(&self) -> u64 {
+        self.content_blocks
+    }
+
+    /// Returns the total number of blocks.
+    pub fn blocks(&self) -> u64 {
+        self.metadata_blocks + self.content_blocks
+    }
+
+    /// Returns the number of bytes, which are not used for content.
+    ///
+    /// These are the metadata blocks and the unused space in the last
+    /// content block of each entry.
+    pub fn overhead(&self) -> u64 {
+        self.overhead
+    }
+
+    /// Tests whether the archive can be stored at this block size.
+    ///
+    /// The block size can be too small for the header of the archive, or the
+    /// tree cannot manage all the blocks.
+    pub fn fits(&self) -> bool {
+        self.fits
+    }
+}
+
+/// Statistics about the entries of an archive.
+///
+/// The analysis is created by
+/// [`Archive::analyze()`](crate::Archive::analyze). Besides the distribution
+/// of the entry sizes it calculates the space needed to store the archive
+/// at different block sizes. Use it to find a better block size before
+/// [rewriting](crate::Archive::rewrite_to) the archive.
+#[derive(Clone, Debug)]
+pub struct Analysis {
+    block_size: u32,
+    id_size: usize,
+    min_block_size: u32,
+    entries: Vec<(usize, u64)>,
+    sizes: Vec<SizeClass>,
+}
+
+impl Analysis {
+    pub(crate) fn create<B: Backend>(
+        pager: &mut Pager<B>,
+        tree: &mut Tree<B>,
+        min_block_size: u32,
+    ) -> ArchiveResult<Analysis, B> {
+        let block_size = pager.block_size();
+        let mut entries = vec![];
+        let mut entry_opt = InnerEntry::first(pager, tree);
+
+        while let Some(result) = entry_opt {
+            let entry = result?;
+            let inner = entry.inner();
+
+            entries.push((inner.name.len(), inner.size));
+            entry_opt = entry.next();
+        }
+
+        let mut sizes: Vec<SizeClass> = vec![];
+
+        for (_, size) in entries.iter() {
+            let upper = size.checked_next_power_of_two().unwrap_or(u64::MAX);
+            let upper = if *size == 0 { 0 } else { upper };
+
+            match sizes.iter_mut().find(|class| class.upper == upper) {
+                Some(class) => class.count += 1,
+                None => sizes.push(SizeClass { upper, count: 1 }),
+            }
+        }
+
+        sizes.sort_by_key(|class| class.upper);
+
+        Ok(Analysis {
+            block_size,
+            id_size: B::Id::size(),
+            min_block_size,
+            entries,
+            sizes,
+        })
+    }
+
+    /// Returns the number of entries in the archive.
+    ///
+    /// Internal entries (e.g. attachments) are included, they occupy blocks
+    /// as well.
+    pub fn entries(&self) -> u64 {
+        self.entries.len() as u64
+    }
+
+    /// Returns the total size of the content of all entries.
+    pub fn content_size(&self) -> u64 {
+        self.entries.iter().map(|(_, size)| size).sum()
+    }
+
+    /// Returns the distribution of the entry sizes.
+    ///
+    /// Only classes with at least one entry are returned, ordered by their
+    /// upper bound.
+    pub fn sizes(&self) -> &[SizeClass] {
+        &self.sizes
+    }
+
+    /// Returns the projection for the current block size of the archive.
+    pub fn current(&self) -> Projection {
+        self.project(self.block_size)
+    }
+
+    /// Returns the projections for the current and some common block sizes
+    /// (powers of two from 512 to 65536), ordered by block size.
+    pub fn projections(&self) -> Vec<Projection> {
+        let mut block_sizes = BLOCK_SIZES.to_vec();
+
+        block_sizes.push(self.block_size);
+        block_sizes.sort_unstable();
+        block_sizes.dedup();
+
+        block_sizes
+            .into_iter()
+            .map(|block_size| self.project(block_size))
+            .collect()
+    }
+
+    /// Returns the projection with the least overhead, which
+    /// [fits](Projection::fits).
+    pub fn recommended(&self) -> Option<Projection> {
+        self.projections()
+            .into_iter()
+            .filter(|p| p.fits())
+            .min_by_key(|p| p.overhead())
+    }
+
+    /// Calculates the space needed to store the archive at `block_size`.
+    pub fn project(&self, block_size: u32) -> Projection {
+        let bsize = block_size as u64;
+
+        let content_blocks = self
+            .entries
+            .iter()
+            .map(|(_, size)| size.div_ceil(bsize))
+            .sum::<u64>();
+        let name_blocks = self
+            .entries
+            .iter()
+            .map(|(len, _)| estimate_name_blocks(*len, bsize))
+            .sum::<u64>();
+        let leaves = self.entries.len() as u64 + name_blocks + content_blocks;
+        let nodes = tree_nodes(leaves, bsize / self.id_size as u64);
+
+        let fits = block_size >= self.min_block_size && nodes.is_some();
+
+        // header block + inodes + long names + nodes of the tree
+        let metadata_blocks = 1 + self.entries.len() as u64 + name_blocks + nodes.unwrap_or(0);
+        let total = (metadata_blocks + content_blocks) * bsize;
+
+        Projection {
+            block_size,
+            metadata_blocks,
+            content_blocks,
+            overhead: total.saturating_sub(self.content_size()),
+            fits,
+        }
+    }
+}
+
+/// Returns the number of nodes the tree needs to manage `leaves` blocks.
+///
+/// Returns [`None`] if the tree cannot manage that many blocks.
+fn tree_nodes(leaves: u64, ipn: u64) -> Option<u64> {
+    let mut remaining = leaves.saturating_sub(NUM_DIRECT as u64);
+    let mut nodes = 0;
+
+    if remaining > 0 {
+        // indirect
+        nodes += 1;
+        remaining -= remaining.min(ipn);
+    }
+
+    if remaining > 0 {
+        // double indirect
+        let n = remaining.min(ipn * ipn);
+
+        nodes += 1 + n.div_ceil(ipn);
+        remaining -= n;
+    }
+
+    if remaining > 0 {
+        // triple indirect
+        if remaining > ipn * ipn * ipn {
+            return None;
+        }
+
+        nodes += 1 + remaining.div_ceil(ipn * ipn) + remaining.div_ceil(ipn);
+    }
+
+    Some(nodes)
+}
@@ -0,0 +1,177 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+use nuts_container::memory::MemoryBackend;
+
+use crate::analyze::{tree_nodes, SizeClass};
+use crate::tests::setup_container_with_bsize;
+use crate::Archive;
+
+fn setup_archive(sizes: &[usize]) -> Archive<MemoryBackend> {
+    let container = setup_container_with_bsize(512);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    for (i, size) in sizes.iter().enumerate() {
+        let mut entry = archive.append_file(format!("f{}", i)).build().unwrap();
+        entry.write_all(&vec![b'x'; *size]).unwrap();
+    }
+
+    archive
+}
+
+#[test]
+fn nodes() {
+    assert_eq!(tree_nodes(0, 2), Some(0));
+    assert_eq!(tree_nodes(12, 2), Some(0));
+    assert_eq!(tree_nodes(13, 2), Some(1));
+    assert_eq!(tree_nodes(14, 2), Some(1));
+    assert_eq!(tree_nodes(15, 2), Some(3));
+    assert_eq!(tree_nodes(18, 2), Some(4));
+    assert_eq!(tree_nodes(19, 2), Some(7));
+    assert_eq!(tree_nodes(26, 2), Some(11));
+    assert_eq!(tree_nodes(27, 2), None);
+}
+
+#[test]
+fn empty() {
+    let mut archive = setup_archive(&[]);
+    let analysis = archive.analyze().unwrap();
+
+    assert_eq!(analysis.entries(), 0);
+    assert_eq!(analysis.content_size(), 0);
+    assert!(analysis.sizes().is_empty());
+
+    let current = analysis.current();
+    assert_eq!(current.block_size(), 512);
+    assert_eq!(current.metadata_blocks(), 1);
+    assert_eq!(current.content_blocks(), 0);
+    assert_eq!(current.overhead(), 512);
+    assert!(current.fits());
+}
+
+#[test]
+fn sizes() {
+    let mut archive = setup_archive(&[0, 100, 600, 5000, 120]);
+    let analysis = archive.analyze().unwrap();
+
+    assert_eq!(analysis.entries(), 5);
+    assert_eq!(analysis.content_size(), 5820);
+    assert_eq!(
+        analysis.sizes(),
+        [
+            SizeClass { upper: 0, count: 1 },
+            SizeClass {
+                upper: 128,
+                count: 2
+            },
+            SizeClass {
+                upper: 1024,
+                count: 1
+            },
+            SizeClass {
+                upper: 8192,
+                count: 1
+            },
+        ]
+    );
+}
+
+#[test]
+fn current() {
+    let mut archive = setup_archive(&[0, 100, 600, 5000]);
+    let analysis = archive.analyze().unwrap();
+    let current = analysis.current();
+
+    // 4 entries + 13 content blocks in the tree, this needs an indirect node
+    assert_eq!(archive.info().blocks, 17);
+    assert_eq!(current.content_blocks(), 13);
+    assert_eq!(current.metadata_blocks(), 1 + 4 + 1);
+    assert_eq!(current.blocks(), 19);
+    assert_eq!(current.overhead(), 19 * 512 - 5700);
+    assert!(current.fits());
+}
+
+#[test]
+fn project() {
+    let mut archive = setup_archive(&[0, 100, 600, 5000]);
+    let analysis = archive.analyze().unwrap();
+
+    let p = analysis.project(8192);
+    assert_eq!(p.content_blocks(), 3);
+    assert_eq!(p.metadata_blocks(), 5);
+    assert!(p.fits());
+
+    // smaller than the minimum block size
+    assert!(!analysis.project(64).fits());
+}
+
+#[test]
+fn projections() {
+    let mut archive = setup_archive(&[100, 200]);
+    let analysis = archive.analyze().unwrap();
+
+    let block_sizes = analysis
+        .projections()
+        .iter()
+        .map(|p| p.block_size())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        block_sizes,
+        [512, 1024, 2048, 4096, 8192, 16384, 32768, 65536]
+    );
+
+    // small files, the smallest block size wins
+    assert_eq!(analysis.recommended().unwrap().block_size(), 512);
+}
+
+#[test]
+fn projections_odd_block_size() {
+    let container = setup_container_with_bsize(600);
+    let mut archive = Archive::create(container, false).unwrap();
+    let analysis = archive.analyze().unwrap();
+
+    let block_sizes = analysis
+        .projections()
+        .iter()
+        .map(|p| p.block_size())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        block_sizes,
+        [512, 600, 1024, 2048, 4096, 8192, 16384, 32768, 65536]
+    );
+}
+
+#[test]
+fn long_name() {
+    let container = setup_container_with_bsize(512);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.append_file("x".repeat(400)).build().unwrap();
+
+    let analysis = archive.analyze().unwrap();
+    let p512 = analysis.project(512);
+    let p256 = analysis.project(256);
+
+    // the name needs two continuation blocks at 256 bytes
+    assert!(p512.fits());
+    assert!(p256.fits());
+    assert_eq!(p256.metadata_blocks(), p512.metadata_blocks() + 2);
+}
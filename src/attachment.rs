@@ -0,0 +1,118 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use nuts_bytes::{FromBytes, ToBytes};
+use nuts_container::backend::Backend;
+
+use crate::entry::immut::InnerEntry;
+use crate::entry::mode::Mode;
+use crate::entry::r#mut::InnerBuilder;
+use crate::entry::Inner;
+use crate::error::ArchiveResult;
+use crate::header::Header;
+use crate::pager::Pager;
+use crate::tree::Tree;
+
+/// A small blob attached to an entry.
+///
+/// Attachments are sidecar data of an entry like thumbnails or extracted
+/// text. They are created with
+/// [`Archive::attach()`](crate::Archive::attach) and stored as hidden
+/// entries behind the entry, they do not show up when traversing the
+/// archive. An attachment is linked to its entry by the name of the entry.
+///
+/// Use [`Archive::attachments()`](crate::Archive::attachments) or
+/// [`Entry::attachments()`](crate::Entry::attachments) to get them back.
+#[derive(Clone, Debug, FromBytes, PartialEq, ToBytes)]
+pub struct Attachment {
+    name: String,
+    data: Vec<u8>,
+}
+
+impl Attachment {
+    /// Returns the name of the attachment.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the data of the attachment.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Consumes the attachment and returns its data.
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    pub(crate) fn append<B: Backend>(
+        pager: &mut Pager<B>,
+        header_id: &B::Id,
+        header: &mut Header,
+        tree: &mut Tree<B>,
+        owner: &str,
+        name: &str,
+        data: &[u8],
+    ) -> ArchiveResult<(), B> {
+        let attachment = Attachment {
+            name: name.to_string(),
+            data: data.to_vec(),
+        };
+
+        let codec = pager.codec();
+        let inner = Inner::new(owner.to_string(), Mode::attachment());
+        let mut entry = InnerBuilder::with_inner(pager, header_id, header, tree, inner).build()?;
+
+        let mut writer = codec.writer(vec![]);
+        writer.write(&attachment)?;
+
+        entry.write_all(&writer.into_target())
+    }
+
+    /// Collects the attachments of `owner` starting at `entry_opt`.
+    pub(crate) fn load_all<B: Backend>(
+        mut entry_opt: Option<ArchiveResult<InnerEntry<'_, B>, B>>,
+        owner: &str,
+    ) -> ArchiveResult<Vec<Attachment>, B> {
+        let mut attachments = vec![];
+
+        loop {
+            let mut entry = match entry_opt {
+                Some(Ok(entry)) => entry,
+                Some(Err(err)) => return Err(err),
+                None => break,
+            };
+
+            if entry.inner().mode.is_attachment() && entry.inner().name == owner {
+                let buf = entry.read_content()?;
+                attachments.push(entry.codec().reader(buf.as_slice()).read()?);
+            }
+
+            entry_opt = entry.next();
+        }
+
+        Ok(attachments)
+    }
+}
@@ -0,0 +1,115 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use crate::error::Error;
+use crate::tests::setup_container_with_bsize;
+use crate::Archive;
+
+fn setup_archive() -> Archive<nuts_container::memory::MemoryBackend> {
+    let container = setup_container_with_bsize(512);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    for name in ["foo", "bar"] {
+        let mut entry = archive.append_file(name).build().unwrap();
+        entry.write_all(name.as_bytes()).unwrap();
+    }
+
+    archive
+}
+
+fn names(archive: &mut Archive<nuts_container::memory::MemoryBackend>) -> Vec<String> {
+    let mut names = vec![];
+    let mut entry_opt = archive.first();
+
+    while let Some(result) = entry_opt {
+        let entry = result.unwrap();
+
+        names.push(entry.name().to_string());
+        entry_opt = entry.next();
+    }
+
+    names
+}
+
+#[test]
+fn none() {
+    let mut archive = setup_archive();
+
+    assert!(archive.attachments("foo").unwrap().is_empty());
+}
+
+#[test]
+fn attach() {
+    let mut archive = setup_archive();
+
+    archive.attach("foo", "thumbnail", &[1, 2, 3]).unwrap();
+    archive.attach("bar", "text", b"bar").unwrap();
+    archive.attach("foo", "text", b"foo").unwrap();
+
+    let attachments = archive.attachments("foo").unwrap();
+    assert_eq!(attachments.len(), 2);
+    assert_eq!(attachments[0].name(), "thumbnail");
+    assert_eq!(attachments[0].data(), [1, 2, 3]);
+    assert_eq!(attachments[1].name(), "text");
+    assert_eq!(attachments[1].clone().into_data(), b"foo");
+
+    let attachments = archive.attachments("bar").unwrap();
+    assert_eq!(attachments.len(), 1);
+    assert_eq!(attachments[0].name(), "text");
+    assert_eq!(attachments[0].data(), b"bar");
+}
+
+#[test]
+fn hidden() {
+    let mut archive = setup_archive();
+
+    archive.attach("foo", "thumbnail", &[1, 2, 3]).unwrap();
+
+    assert_eq!(archive.info().files, 2);
+    assert_eq!(names(&mut archive), ["foo", "bar"]);
+}
+
+#[test]
+fn entry_attachments() {
+    let mut archive = setup_archive();
+
+    archive.attach("foo", "thumbnail", &[1, 2, 3]).unwrap();
+    archive.attach("bar", "text", b"bar").unwrap();
+
+    let mut entry = archive.lookup("bar").unwrap().unwrap();
+    let attachments = entry.attachments().unwrap();
+    assert_eq!(attachments.len(), 1);
+    assert_eq!(attachments[0].name(), "text");
+
+    let mut file = entry.into_file().unwrap();
+    assert_eq!(file.read_vec().unwrap(), b"bar");
+}
+
+#[test]
+fn no_such_owner() {
+    let mut archive = setup_archive();
+
+    let err = archive.attach("baz", "text", b"baz").unwrap_err();
+    assert!(matches!(err, Error::NotFound(name) if name == "baz"));
+
+    assert!(archive.attachments("baz").unwrap().is_empty());
+}
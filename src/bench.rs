@@ -0,0 +1,189 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+//! Synthetic workloads to measure the write path of an archive.
+//!
+//! The module is only available if the `bench` feature is enabled. A
+//! [`Workload`] appends generated entries to an archive and reports the
+//! elapsed time. Run the workloads against the backend and block size you
+//! plan to use, to compare the choices with realistic archive traffic.
+//!
+//! ```rust
+//! use nuts_archive::bench::Workload;
+//! use nuts_archive::Archive;
+//! use nuts_container::container::{Cipher, Container, CreateOptionsBuilder};
+//! use nuts_container::memory::MemoryBackend;
+//!
+//! let options = CreateOptionsBuilder::new(Cipher::None)
+//!     .build::<MemoryBackend>()
+//!     .unwrap();
+//! let container = Container::<MemoryBackend>::create(MemoryBackend::new(), options).unwrap();
+//! let mut archive = Archive::create(container, false).unwrap();
+//!
+//! let report = Workload::SmallFiles { count: 100, size: 100 }
+//!     .run(&mut archive)
+//!     .unwrap();
+//!
+//! assert_eq!(report.entries(), 100);
+//! assert_eq!(report.bytes(), 10_000);
+//! ```
+
+#[cfg(test)]
+mod tests;
+
+use nuts_container::backend::Backend;
+use std::cmp;
+use std::time::{Duration, Instant};
+
+use crate::error::ArchiveResult;
+use crate::Archive;
+
+/// Size of the buffer passed to [`EntryMut::write_all()`](crate::EntryMut::write_all).
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// A synthetic workload.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Workload {
+    /// Appends `count` files of `size` bytes each.
+    SmallFiles { count: u64, size: u64 },
+
+    /// Appends a single file of `size` bytes.
+    HugeFile { size: u64 },
+
+    /// Appends `count` files of different sizes.
+    ///
+    /// Most files are small (up to 4 KiB), some are medium sized (up to
+    /// 256 KiB) and a few are large (up to 4 MiB). The sizes are derived from
+    /// `seed`, the same seed produces the same workload.
+    Mixed { count: u64, seed: u64 },
+}
+
+impl Workload {
+    /// Appends the entries of the workload to `archive`.
+    ///
+    /// The files are named `bench-<n>` and appended at the end of the
+    /// archive.
+    pub fn run<B: Backend>(&self, archive: &mut Archive<B>) -> ArchiveResult<Report, B> {
+        let sizes = self.sizes();
+        let mut rng = Rng::new(0x6e75_7473);
+        let mut buf = vec![0; CHUNK_SIZE];
+        let mut report = Report {
+            entries: 0,
+            bytes: 0,
+            elapsed: Duration::ZERO,
+        };
+
+        let start = Instant::now();
+
+        for (n, size) in sizes.into_iter().enumerate() {
+            let mut entry = archive.append_file(format!("bench-{}", n)).build()?;
+            let mut remaining = size;
+
+            while remaining > 0 {
+                let nbytes = cmp::min(remaining, buf.len() as u64) as usize;
+
+                rng.fill(&mut buf[..nbytes]);
+                entry.write_all(&buf[..nbytes])?;
+
+                remaining -= nbytes as u64;
+            }
+
+            report.entries += 1;
+            report.bytes += size;
+        }
+
+        report.elapsed = start.elapsed();
+
+        Ok(report)
+    }
+
+    fn sizes(&self) -> Vec<u64> {
+        match *self {
+            Workload::SmallFiles { count, size } => (0..count).map(|_| size).collect(),
+            Workload::HugeFile { size } => vec![size],
+            Workload::Mixed { count, seed } => {
+                let mut rng = Rng::new(seed);
+
+                (0..count)
+                    .map(|_| match rng.next() % 100 {
+                        0..=79 => rng.next() % (4 * 1024 + 1),
+                        80..=97 => rng.next() % (256 * 1024 + 1),
+                        _ => rng.next() % (4 * 1024 * 1024 + 1),
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// The result of a [`Workload`].
+#[derive(Clone, Debug)]
+pub struct Report {
+    entries: u64,
+    bytes: u64,
+    elapsed: Duration,
+}
+
+impl Report {
+    /// Returns the number of appended entries.
+    pub fn entries(&self) -> u64 {
+        self.entries
+    }
+
+    /// Returns the number of appended content bytes.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Returns the time needed to append all entries.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns the number of content bytes appended per second.
+    pub fn bytes_per_sec(&self) -> f64 {
+        self.bytes as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// A xorshift generator, good enough for synthetic content.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        // the state must not be zero
+        Rng(seed | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn fill(&mut self, buf: &mut [u8]) {
+        for chunk in buf.chunks_mut(8) {
+            let bytes = self.next().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+use crate::bench::Workload;
+use crate::tests::setup_container_with_bsize;
+use crate::Archive;
+
+fn setup_archive() -> Archive<nuts_container::memory::MemoryBackend> {
+    let container = setup_container_with_bsize(512);
+    Archive::create(container, false).unwrap()
+}
+
+#[test]
+fn small_files() {
+    let mut archive = setup_archive();
+    let report = Workload::SmallFiles {
+        count: 10,
+        size: 700,
+    }
+    .run(&mut archive)
+    .unwrap();
+
+    assert_eq!(report.entries(), 10);
+    assert_eq!(report.bytes(), 7000);
+    assert_eq!(archive.info().files, 10);
+    assert_eq!(archive.info().blocks, 30);
+
+    let entry = archive.lookup("bench-9").unwrap().unwrap();
+    assert_eq!(entry.size(), 700);
+}
+
+#[test]
+fn huge_file() {
+    let mut archive = setup_archive();
+    let report = Workload::HugeFile { size: 200_000 }
+        .run(&mut archive)
+        .unwrap();
+
+    assert_eq!(report.entries(), 1);
+    assert_eq!(report.bytes(), 200_000);
+
+    let mut entry = archive.first().unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap().len(), 200_000);
+}
+
+#[test]
+fn mixed() {
+    let workload = Workload::Mixed { count: 50, seed: 7 };
+    let sizes = workload.sizes();
+
+    assert_eq!(sizes.len(), 50);
+    assert_eq!(sizes, workload.sizes());
+    assert_ne!(sizes, Workload::Mixed { count: 50, seed: 8 }.sizes());
+    assert!(sizes.iter().all(|size| *size <= 4 * 1024 * 1024));
+}
+
+#[test]
+fn mixed_run() {
+    let mut archive = setup_archive();
+    let workload = Workload::Mixed { count: 20, seed: 7 };
+    let report = workload.run(&mut archive).unwrap();
+
+    assert_eq!(report.entries(), 20);
+    assert_eq!(report.bytes(), workload.sizes().iter().sum::<u64>());
+    assert_eq!(archive.info().files, 20);
+}
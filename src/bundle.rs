@@ -0,0 +1,188 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use log::debug;
+use nuts_container::backend::Backend;
+use sha2::{Digest as _, Sha256};
+use std::convert::TryInto;
+use std::io::{self, Read, Write};
+
+use crate::error::{ArchiveResult, Error};
+use crate::manifest::{Digest, Manifest};
+use crate::stream::{EntryStreamReader, EntryStreamWriter};
+use crate::validate::{ImportReport, ReportBuilder};
+use crate::Archive;
+
+const BUNDLE_MAGIC: [u8; 11] = *b"nuts-bundle";
+const BUNDLE_REVISION: u16 = 1;
+
+/// Maximum size of a manifest accepted in a bundle.
+const MAX_MANIFEST_SIZE: u64 = 256 * 1024 * 1024;
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// Writes all entries of `archive` as a bundle into `writer`.
+///
+/// The bundle starts with a magic and a revision, followed by the
+/// [`Manifest`] of the archive (prefixed with its length, 8 bytes, big
+/// endian) and the SHA-256 digest of the manifest. The entries follow as an
+/// entry stream, see [`EntryStreamWriter`].
+///
+/// Returns the number of entries written into the bundle.
+pub(crate) fn export<B: Backend, W: Write>(
+    archive: &mut Archive<B>,
+    mut writer: W,
+) -> ArchiveResult<u64, B> {
+    let buf = archive.manifest()?.export();
+    let digest: Digest = Sha256::digest(&buf).into();
+
+    debug!("bundle manifest: {} bytes", buf.len());
+
+    writer.write_all(&BUNDLE_MAGIC)?;
+    writer.write_all(&BUNDLE_REVISION.to_be_bytes())?;
+    writer.write_all(&(buf.len() as u64).to_be_bytes())?;
+    writer.write_all(&buf)?;
+    writer.write_all(&digest)?;
+
+    let mut stream = EntryStreamWriter::new(writer)?;
+    let mut nentries = 0;
+    let mut entry_opt = archive.first();
+
+    while let Some(result) = entry_opt {
+        let mut entry = result?;
+
+        stream.write_entry(&mut entry)?;
+        nentries += 1;
+
+        entry_opt = entry.next();
+    }
+
+    stream.finish()?;
+
+    Ok(nentries)
+}
+
+/// Appends the entries of the bundle read from `reader` to `archive`.
+///
+/// Each entry is verified against the manifest of the bundle. The entries
+/// are appended in an [`AppendGroup`](crate::AppendGroup), if the
+/// verification fails none of them become part of the archive.
+///
+/// Returns the number of appended entries.
+pub(crate) fn import<B: Backend, R: Read>(
+    archive: &mut Archive<B>,
+    mut reader: R,
+) -> ArchiveResult<u64, B> {
+    let manifest = read_manifest(&mut reader)?;
+    let mut expected = manifest.entries().iter();
+    let mut stream = EntryStreamReader::new(reader)?;
+    let mut group = archive.append_group()?;
+    let mut nentries = 0;
+
+    while let Some(entry) = stream.import_entry(group.archive_mut())? {
+        debug!("bundle entry {}", entry.name());
+
+        if expected.next() != Some(&entry) {
+            return Err(Error::BundleMismatch(Some(entry.name().to_string())));
+        }
+
+        nentries += 1;
+    }
+
+    if expected.next().is_some() {
+        return Err(Error::BundleMismatch(None));
+    }
+
+    group.commit()?;
+
+    Ok(nentries)
+}
+
+/// Verifies the bundle read from `reader` like [`import()`] does, without
+/// modifying `archive`.
+pub(crate) fn validate<B: Backend, R: Read>(
+    archive: &mut Archive<B>,
+    mut reader: R,
+) -> ArchiveResult<ImportReport, B> {
+    archive.header.check_mutable()?;
+
+    let manifest = read_manifest(&mut reader)?;
+    let mut expected = manifest.entries().iter();
+    let mut stream = EntryStreamReader::new(reader)?;
+    let mut report = ReportBuilder::new(archive)?;
+
+    while let Some(entry) = stream.scan_entry()? {
+        if expected.next() != Some(&entry) {
+            return Err(Error::BundleMismatch(Some(entry.name().to_string())));
+        }
+
+        report.add(entry.name(), entry.size());
+    }
+
+    if expected.next().is_some() {
+        return Err(Error::BundleMismatch(None));
+    }
+
+    Ok(report.build())
+}
+
+fn read_manifest<B: Backend, R: Read>(mut reader: R) -> ArchiveResult<Manifest, B> {
+    let mut magic = [0; BUNDLE_MAGIC.len()];
+    let mut revision = [0; 2];
+    let mut len = [0; 8];
+
+    reader.read_exact(&mut magic)?;
+    reader.read_exact(&mut revision)?;
+
+    if magic != BUNDLE_MAGIC {
+        return Err(invalid_data("invalid bundle magic").into());
+    }
+
+    if u16::from_be_bytes(revision) != BUNDLE_REVISION {
+        return Err(invalid_data("unsupported bundle revision").into());
+    }
+
+    reader.read_exact(&mut len)?;
+
+    let len = u64::from_be_bytes(len);
+
+    if len > MAX_MANIFEST_SIZE {
+        return Err(invalid_data(format!("manifest too large: {} bytes", len)).into());
+    }
+
+    let mut buf = vec![0; len.try_into().map_err(invalid_data)?];
+    let mut digest = Digest::default();
+
+    reader.read_exact(&mut buf)?;
+    reader.read_exact(&mut digest)?;
+
+    if Sha256::digest(&buf).as_slice() != digest {
+        return Err(Error::BundleMismatch(None));
+    }
+
+    Manifest::import(&buf).map_err(Error::Bytes)
+}
@@ -0,0 +1,177 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use std::io::ErrorKind;
+
+use crate::error::Error;
+use crate::tests::setup_container_with_bsize;
+use crate::Archive;
+
+fn setup_archive() -> Archive<nuts_container::memory::MemoryBackend> {
+    let container = setup_container_with_bsize(512);
+    Archive::create(container, false).unwrap()
+}
+
+fn setup_source() -> Archive<nuts_container::memory::MemoryBackend> {
+    let mut archive = setup_archive();
+
+    archive.append_directory("d").build().unwrap();
+    archive
+        .append_file("d/f1")
+        .build()
+        .unwrap()
+        .write_all(&[1; 1000])
+        .unwrap();
+    archive.append_symlink("s", "d/f1").build().unwrap();
+    archive.append_hardlink("h", "d/f1").build().unwrap();
+    archive.append_file("f2").build().unwrap();
+
+    archive
+}
+
+fn export(archive: &mut Archive<nuts_container::memory::MemoryBackend>) -> Vec<u8> {
+    let mut buf = vec![];
+
+    assert_eq!(archive.export_bundle(&mut buf).unwrap(), 5);
+
+    buf
+}
+
+#[test]
+fn round_trip() {
+    let mut source = setup_source();
+    let mut target = setup_archive();
+    let buf = export(&mut source);
+
+    assert_eq!(target.import_bundle(buf.as_slice()).unwrap(), 5);
+    assert_eq!(target.manifest().unwrap(), source.manifest().unwrap());
+
+    let mut file = target.lookup("d/f1").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(file.read_vec().unwrap(), [1; 1000]);
+}
+
+#[test]
+fn empty() {
+    let mut source = setup_archive();
+    let mut target = setup_archive();
+    let mut buf = vec![];
+
+    assert_eq!(source.export_bundle(&mut buf).unwrap(), 0);
+    assert_eq!(target.import_bundle(buf.as_slice()).unwrap(), 0);
+    assert!(target.first().is_none());
+}
+
+#[test]
+fn invalid_magic() {
+    let mut target = setup_archive();
+    let mut buf = export(&mut setup_source());
+
+    buf[0] = b'x';
+
+    let err = target.import_bundle(buf.as_slice()).unwrap_err();
+    assert!(matches!(err, Error::Io(ref err) if err.kind() == ErrorKind::InvalidData));
+}
+
+#[test]
+fn corrupted_manifest() {
+    let mut target = setup_archive();
+    let mut buf = export(&mut setup_source());
+
+    // first byte of the manifest, after magic, revision and length
+    buf[21] ^= 0xFF;
+
+    let err = target.import_bundle(buf.as_slice()).unwrap_err();
+    assert!(matches!(err, Error::BundleMismatch(None)));
+    assert!(target.first().is_none());
+}
+
+#[test]
+fn corrupted_content() {
+    let mut target = setup_archive();
+    let mut buf = export(&mut setup_source());
+    let pos = buf.windows(3).rposition(|w| w == [1, 1, 1]).unwrap();
+
+    target.append_file("existing").build().unwrap();
+    buf[pos] = 2;
+
+    let err = target.import_bundle(buf.as_slice()).unwrap_err();
+    assert!(matches!(err, Error::BundleMismatch(Some(ref name)) if name == "d/f1"));
+
+    // entries of the bundle are rolled back
+    let entry = target.first().unwrap().unwrap();
+    assert_eq!(entry.name(), "existing");
+    assert!(entry.next().is_none());
+}
+
+#[test]
+fn truncated() {
+    let mut target = setup_archive();
+    let buf = export(&mut setup_source());
+
+    let err = target.import_bundle(&buf[..buf.len() - 10]).unwrap_err();
+    assert!(matches!(err, Error::Io(ref err) if err.kind() == ErrorKind::UnexpectedEof));
+    assert!(target.first().is_none());
+}
+
+#[test]
+fn frozen() {
+    let mut target = setup_archive();
+    let buf = export(&mut setup_source());
+
+    target.freeze(b"token").unwrap();
+
+    let err = target.import_bundle(buf.as_slice()).unwrap_err();
+    assert!(matches!(err, Error::Frozen));
+}
+
+#[test]
+fn validate() {
+    let mut target = setup_archive();
+    let buf = export(&mut setup_source());
+
+    target.append_file("f2").build().unwrap();
+
+    let report = target.validate_bundle(buf.as_slice()).unwrap();
+
+    assert_eq!(report.entries(), 5);
+    assert_eq!(report.content_size(), 1000 + 4 + 4);
+    assert_eq!(report.collisions(), ["f2"]);
+    assert!(report.unsupported().is_empty());
+    assert!(report.fits());
+
+    // the archive is not modified
+    let entry = target.first().unwrap().unwrap();
+    assert_eq!(entry.name(), "f2");
+    assert!(entry.next().is_none());
+}
+
+#[test]
+fn validate_corrupted() {
+    let mut target = setup_archive();
+    let mut buf = export(&mut setup_source());
+    let pos = buf.windows(3).rposition(|w| w == [1, 1, 1]).unwrap();
+
+    buf[pos] = 2;
+
+    let err = target.validate_bundle(buf.as_slice()).unwrap_err();
+    assert!(matches!(err, Error::BundleMismatch(Some(ref name)) if name == "d/f1"));
+}
@@ -0,0 +1,171 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use std::cmp;
+
+/// Random values of the gear hash, one for each byte value.
+const GEAR: [u64; 256] = gear_table();
+
+const fn gear_table() -> [u64; 256] {
+    let mut table = [0; 256];
+    let mut state: u64 = 0x6e75_7473_2d63_6463;
+    let mut i = 0;
+
+    while i < 256 {
+        // splitmix64
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = state;
+
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        table[i] = z ^ (z >> 31);
+
+        i += 1;
+    }
+
+    table
+}
+
+/// A mask with the `bits` most significant bits set.
+fn top_bits(bits: u32) -> u64 {
+    !0u64 << (64 - cmp::min(bits, 63))
+}
+
+/// Content-defined chunker.
+///
+/// The chunker splits a byte stream at positions, which are derived from
+/// the content itself (FastCDC with normalized chunking). Inserting or
+/// removing bytes only moves the boundaries close to the change, so similar
+/// streams share most of their chunks, even if the data is shifted.
+///
+/// Used by [`Archive::append_chunked()`](crate::Archive::append_chunked).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Chunker {
+    min_size: usize,
+    avg_size: usize,
+    max_size: usize,
+    mask_s: u64,
+    mask_l: u64,
+}
+
+impl Chunker {
+    /// Creates a chunker with the given chunk sizes.
+    ///
+    /// `avg_size` is rounded up to a power of two, `min_size` is limited to
+    /// `avg_size` and `max_size` is at least `avg_size`.
+    pub fn new(min_size: usize, avg_size: usize, max_size: usize) -> Chunker {
+        let avg_size = cmp::max(avg_size, 64).next_power_of_two();
+        let bits = avg_size.trailing_zeros();
+
+        Chunker {
+            min_size: cmp::min(min_size, avg_size),
+            avg_size,
+            max_size: cmp::max(max_size, avg_size),
+            mask_s: top_bits(bits + 2),
+            mask_l: top_bits(bits.saturating_sub(2)),
+        }
+    }
+
+    /// Returns the minimum size of a chunk.
+    pub fn min_size(&self) -> usize {
+        self.min_size
+    }
+
+    /// Returns the average size of a chunk.
+    pub fn avg_size(&self) -> usize {
+        self.avg_size
+    }
+
+    /// Returns the maximum size of a chunk.
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Returns the length of the chunk at the beginning of `buf`.
+    ///
+    /// The whole `buf` is returned, if no boundary is found. The result is
+    /// final only, if `buf` holds at least [`Chunker::max_size()`] bytes or
+    /// the end of the stream is reached.
+    pub fn cut(&self, buf: &[u8]) -> usize {
+        if buf.len() <= self.min_size {
+            return buf.len();
+        }
+
+        let len = cmp::min(buf.len(), self.max_size);
+        let normal = cmp::min(self.avg_size, len);
+        let mut hash = 0u64;
+
+        for (i, n) in buf.iter().enumerate().take(len).skip(self.min_size) {
+            hash = (hash << 1).wrapping_add(GEAR[*n as usize]);
+
+            // harder to match below the average size, easier above
+            let mask = if i < normal { self.mask_s } else { self.mask_l };
+
+            if hash & mask == 0 {
+                return i + 1;
+            }
+        }
+
+        len
+    }
+
+    /// Splits `buf` into chunks.
+    pub fn chunks<'a>(&self, buf: &'a [u8]) -> Chunks<'a> {
+        Chunks {
+            chunker: *self,
+            buf,
+        }
+    }
+}
+
+impl Default for Chunker {
+    /// Chunks between 2 KiB and 64 KiB, 8 KiB on average.
+    fn default() -> Chunker {
+        Chunker::new(2048, 8192, 65536)
+    }
+}
+
+/// Iterator over the chunks of a buffer, see [`Chunker::chunks()`].
+pub struct Chunks<'a> {
+    chunker: Chunker,
+    buf: &'a [u8],
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+        if self.buf.is_empty() {
+            return None;
+        }
+
+        let (chunk, rest) = self.buf.split_at(self.chunker.cut(self.buf));
+
+        self.buf = rest;
+
+        Some(chunk)
+    }
+}
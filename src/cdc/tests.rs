@@ -0,0 +1,108 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use crate::cdc::Chunker;
+
+fn random(len: usize, seed: u64) -> Vec<u8> {
+    let mut state = seed;
+
+    (0..len)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 56) as u8
+        })
+        .collect()
+}
+
+#[test]
+fn new() {
+    let chunker = Chunker::new(100, 1000, 10);
+
+    assert_eq!(chunker.min_size(), 100);
+    assert_eq!(chunker.avg_size(), 1024);
+    assert_eq!(chunker.max_size(), 1024);
+
+    let chunker = Chunker::new(5000, 10, 20000);
+
+    assert_eq!(chunker.min_size(), 64);
+    assert_eq!(chunker.avg_size(), 64);
+    assert_eq!(chunker.max_size(), 20000);
+}
+
+#[test]
+fn default() {
+    let chunker = Chunker::default();
+
+    assert_eq!(chunker.min_size(), 2048);
+    assert_eq!(chunker.avg_size(), 8192);
+    assert_eq!(chunker.max_size(), 65536);
+}
+
+#[test]
+fn cut_short() {
+    let chunker = Chunker::new(64, 256, 1024);
+
+    assert_eq!(chunker.cut(&[]), 0);
+    assert_eq!(chunker.cut(&[1; 64]), 64);
+}
+
+#[test]
+fn cut_max() {
+    let chunker = Chunker::new(64, 256, 1024);
+
+    // constant data never matches the mask
+    assert_eq!(chunker.cut(&[0; 4096]), 1024);
+}
+
+#[test]
+fn chunks() {
+    let chunker = Chunker::new(256, 1024, 4096);
+    let data = random(100_000, 1);
+    let chunks: Vec<&[u8]> = chunker.chunks(&data).collect();
+
+    assert_eq!(chunks.concat(), data);
+    assert!(chunks.len() > 50 && chunks.len() < 200);
+
+    for chunk in chunks.iter().take(chunks.len() - 1) {
+        assert!(chunk.len() >= 256 && chunk.len() <= 4096);
+    }
+}
+
+#[test]
+fn shifted() {
+    let chunker = Chunker::new(256, 1024, 4096);
+    let data = random(100_000, 2);
+    let mut shifted = random(100, 3);
+
+    shifted.extend_from_slice(&data);
+
+    let chunks: Vec<&[u8]> = chunker.chunks(&data).collect();
+    let common = chunker
+        .chunks(&shifted)
+        .filter(|chunk| chunks.contains(chunk))
+        .count();
+
+    // only the chunks close to the insertion differ
+    assert!(common + 2 >= chunks.len());
+}
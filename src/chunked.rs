@@ -0,0 +1,226 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use log::debug;
+use nuts_container::backend::Backend;
+use sha2::{Digest as _, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Read, Write};
+
+use crate::cdc::Chunker;
+use crate::entry::immut::InnerEntry;
+use crate::entry::mode::Mode;
+use crate::entry::r#mut::InnerBuilder;
+use crate::entry::Inner;
+use crate::error::{ArchiveResult, Error};
+use crate::header::Header;
+use crate::manifest::Digest;
+use crate::pager::Pager;
+use crate::tree::Tree;
+
+/// Statistics of [`Archive::append_chunked()`](crate::Archive::append_chunked).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChunkStats {
+    /// Number of bytes read from the source.
+    pub size: u64,
+
+    /// Number of chunks the content was split into.
+    pub chunks: u64,
+
+    /// Number of chunks, which were not stored in the archive yet.
+    pub new_chunks: u64,
+
+    /// Number of bytes of the new chunks.
+    pub new_bytes: u64,
+}
+
+/// The name of the hidden entry, which stores the chunk with `digest`.
+fn chunk_name(digest: &Digest) -> String {
+    digest.iter().map(|n| format!("{:02x}", n)).collect()
+}
+
+/// Collects the names of all chunk entries and their tree index.
+fn load_chunks<B: Backend>(
+    pager: &mut Pager<B>,
+    tree: &mut Tree<B>,
+) -> ArchiveResult<HashMap<String, usize>, B> {
+    let mut chunks = HashMap::new();
+    let mut entry_opt = InnerEntry::first(pager, tree);
+
+    loop {
+        let entry = match entry_opt {
+            Some(Ok(entry)) => entry,
+            Some(Err(err)) => return Err(err),
+            None => break,
+        };
+
+        if entry.inner().mode.is_chunk() {
+            chunks.insert(entry.inner().name.clone(), entry.idx());
+        }
+
+        entry_opt = entry.next();
+    }
+
+    Ok(chunks)
+}
+
+/// Reads from `reader` until `buf` holds `len` bytes.
+///
+/// Returns `false`, if the end of `reader` is reached before.
+fn fill<R: Read>(reader: &mut R, buf: &mut Vec<u8>, len: usize) -> io::Result<bool> {
+    while buf.len() < len {
+        let start = buf.len();
+
+        buf.resize(len, 0);
+
+        match reader.read(&mut buf[start..]) {
+            Ok(0) => {
+                buf.truncate(start);
+                return Ok(false);
+            }
+            Ok(n) => buf.truncate(start + n),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => buf.truncate(start),
+            Err(err) => {
+                buf.truncate(start);
+                return Err(err);
+            }
+        }
+    }
+
+    Ok(true)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn append<B: Backend, R: Read>(
+    pager: &mut Pager<B>,
+    header_id: &B::Id,
+    header: &mut Header,
+    tree: &mut Tree<B>,
+    chunker: &Chunker,
+    name: &str,
+    mut reader: R,
+) -> ArchiveResult<ChunkStats, B> {
+    let mut known: HashSet<String> = load_chunks(pager, tree)?.into_keys().collect();
+    let mut stats = ChunkStats::default();
+    let mut recipe: Vec<Digest> = vec![];
+    let mut buf = vec![];
+    let mut more = true;
+
+    loop {
+        if more {
+            more = fill(&mut reader, &mut buf, chunker.max_size())?;
+        }
+
+        if buf.is_empty() {
+            break;
+        }
+
+        let len = chunker.cut(&buf);
+        let chunk = &buf[..len];
+        let digest: Digest = Sha256::digest(chunk).into();
+        let chunk_name = chunk_name(&digest);
+
+        if !known.contains(&chunk_name) {
+            let inner = Inner::new(chunk_name.clone(), Mode::chunk());
+            let mut entry =
+                InnerBuilder::with_inner(pager, header_id, header, tree, inner).build()?;
+
+            entry.write_all(chunk)?;
+            known.insert(chunk_name);
+
+            stats.new_chunks += 1;
+            stats.new_bytes += len as u64;
+        }
+
+        recipe.push(digest);
+        stats.size += len as u64;
+        stats.chunks += 1;
+
+        buf.drain(..len);
+    }
+
+    let codec = pager.codec();
+    let inner = Inner::new(name.to_string(), Mode::chunked());
+    let mut entry = InnerBuilder::with_inner(pager, header_id, header, tree, inner).build()?;
+    let mut writer = codec.writer(vec![]);
+
+    writer.write(&recipe)?;
+    entry.write_all(&writer.into_target())?;
+
+    debug!(
+        "{} appended in {} chunks, {} new chunks with {} bytes",
+        name, stats.chunks, stats.new_chunks, stats.new_bytes
+    );
+
+    Ok(stats)
+}
+
+pub(crate) fn read<B: Backend, W: Write>(
+    pager: &mut Pager<B>,
+    tree: &mut Tree<B>,
+    name: &str,
+    mut writer: W,
+) -> ArchiveResult<u64, B> {
+    let chunks = load_chunks(pager, tree)?;
+    let codec = pager.codec();
+    let mut recipe: Option<Vec<Digest>> = None;
+    let mut entry_opt = InnerEntry::first(pager, tree);
+
+    loop {
+        let mut entry = match entry_opt {
+            Some(Ok(entry)) => entry,
+            Some(Err(err)) => return Err(err),
+            None => break,
+        };
+
+        if entry.inner().mode.is_chunked() && entry.inner().name == name {
+            let buf = entry.read_content()?;
+            recipe = Some(codec.reader(&buf).read()?);
+            break;
+        }
+
+        entry_opt = entry.next();
+    }
+
+    let recipe = recipe.ok_or_else(|| Error::NotFound(name.to_string()))?;
+    let mut nbytes = 0;
+
+    for digest in recipe.iter() {
+        let idx = match chunks.get(&chunk_name(digest)) {
+            Some(idx) => *idx,
+            None => return Err(Error::MissingChunk(name.to_string())),
+        };
+        let id = match tree.lookup(pager, idx) {
+            Some(result) => result?.clone(),
+            None => return Err(Error::UnexpectedEof),
+        };
+        let content = InnerEntry::load(pager, tree, idx, &id)?.read_content()?;
+
+        writer.write_all(&content)?;
+        nbytes += content.len() as u64;
+    }
+
+    Ok(nbytes)
+}
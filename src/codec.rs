@@ -0,0 +1,214 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use nuts_bytes::{FromBytes, PutBytes, TakeBytesError, Writer};
+use std::str;
+
+use crate::tree::NUM_DIRECT;
+
+/// The newest revision of the on-disk format supported by this version.
+///
+/// * Revision 1: the initial format, the tree has 12 direct blocks.
+/// * Revision 2: the number of direct blocks is stored in front of the
+///   direct blocks of the tree.
+///
+/// A new archive gets the oldest revision, which can store it, see
+/// [`Codec::for_num_direct()`].
+pub const REVISION: u16 = 2;
+
+/// An entry has a long name, which is stored in continuation blocks behind
+/// the entry block.
+pub const FEATURE_LONG_NAME: u16 = 0x0100;
+
+/// The content of an entry is compressed.
+pub const FEATURE_COMPRESSION: u16 = 0x0200;
+
+/// The content of an entry is [transformed](crate::Transform).
+pub const FEATURE_TRANSFORM: u16 = 0x0400;
+
+/// The archive has internal entries (jobs, pending uploads, attachments,
+/// chunks and trashed entries), which are hidden from the traversal.
+pub const FEATURE_INTERNAL: u16 = 0x0800;
+
+/// The features known by this version.
+const FEATURES: u16 =
+    FEATURE_LONG_NAME | FEATURE_COMPRESSION | FEATURE_TRANSFORM | FEATURE_INTERNAL;
+
+/// The low byte of the revision word is the revision of the layout.
+pub const MASK_REVISION: u16 = 0x00FF;
+
+/// The codec of the on-disk format of an archive.
+///
+/// The blocks of an archive are (de-) serialized with readers and writers
+/// created by the codec of the archive. The codec knows the revision of the
+/// archive, which is stored in the header and selected when the archive is
+/// opened, so a future revision can change the encoding without breaking
+/// archives of older revisions. Numbers are encoded in big-endian byte
+/// order.
+///
+/// The revision is stored in the header as a word: the low byte is the
+/// revision of the layout, the high byte flags the features of the entries,
+/// which are used by the archive. A feature is flagged, when the first entry
+/// using it is written. A version, which does not know the revision or one
+/// of the features, refuses to open the archive instead of misreading it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Codec {
+    revision: u16,
+    features: u16,
+}
+
+impl Codec {
+    /// Creates a codec for the given revision `word`.
+    ///
+    /// Returns [`None`] if the revision or one of the features is not
+    /// supported by this version.
+    pub fn new(word: u16) -> Option<Codec> {
+        let revision = word & MASK_REVISION;
+        let features = word & !MASK_REVISION;
+
+        if (1..=REVISION).contains(&revision) && features & !FEATURES == 0 {
+            Some(Codec { revision, features })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the codec of the current revision.
+    pub fn current() -> Codec {
+        Codec {
+            revision: REVISION,
+            features: 0,
+        }
+    }
+
+    /// Returns the codec for a new archive with `num_direct` direct blocks.
+    ///
+    /// Archives with the default number of direct blocks are written in
+    /// revision 1, the number of direct blocks is not stored then. The
+    /// features are flagged, when they are used.
+    pub fn for_num_direct(num_direct: u32) -> Codec {
+        if num_direct == NUM_DIRECT {
+            Codec {
+                revision: 1,
+                features: 0,
+            }
+        } else {
+            Codec::current()
+        }
+    }
+
+    /// Returns the revision of the layout.
+    pub fn revision(&self) -> u16 {
+        self.revision
+    }
+
+    /// Returns the flags of the features used by the archive.
+    pub fn features(&self) -> u16 {
+        self.features
+    }
+
+    /// Returns the revision word stored in the header.
+    pub fn word(&self) -> u16 {
+        self.revision | self.features
+    }
+
+    /// Returns a codec, which additionally flags the given `features`.
+    pub fn with_features(&self, features: u16) -> Codec {
+        Codec {
+            revision: self.revision,
+            features: self.features | features,
+        }
+    }
+
+    /// Tests whether the number of direct blocks of the tree is stored in
+    /// the header block.
+    pub fn has_num_direct(&self) -> bool {
+        self.revision >= 2
+    }
+
+    pub fn reader<'a>(&self, buf: &'a [u8]) -> BufReader<'a> {
+        BufReader::new(buf)
+    }
+
+    pub fn writer<PB: PutBytes>(&self, target: PB) -> Writer<PB> {
+        Writer::new(target)
+    }
+}
+
+/// A reader, which deserializes from a borrowed buffer.
+///
+/// Besides the [`FromBytes`] types, it reads byte fields without copying
+/// them: [`BufReader::read_bytes()`] and [`BufReader::read_str()`] return
+/// slices of the underlying buffer. Both expect the encoding of a
+/// `Vec<u8>` resp. a [`String`], a `u64` length followed by the bytes.
+pub struct BufReader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> BufReader<'a> {
+    pub fn new(buf: &'a [u8]) -> BufReader<'a> {
+        BufReader { buf }
+    }
+
+    pub fn read<T: FromBytes>(&mut self) -> Result<T, nuts_bytes::Error> {
+        T::from_bytes(&mut self.buf)
+    }
+
+    /// Reads a length-prefixed byte field without copying it.
+    pub fn read_bytes(&mut self) -> Result<&'a [u8], nuts_bytes::Error> {
+        let len = self.read::<usize>()?;
+
+        self.take(len)
+    }
+
+    /// Reads a length-prefixed string without copying it.
+    pub fn read_str(&mut self) -> Result<&'a str, nuts_bytes::Error> {
+        let bytes = self.read_bytes()?;
+
+        str::from_utf8(bytes).map_err(|_| {
+            // only the owned variant of the error can be reported
+            let err = String::from_utf8(bytes.to_vec()).unwrap_err();
+            nuts_bytes::Error::InvalidString(err)
+        })
+    }
+
+    /// Takes the next `len` bytes without copying them.
+    pub fn take(&mut self, len: usize) -> Result<&'a [u8], nuts_bytes::Error> {
+        if len > self.buf.len() {
+            return Err(nuts_bytes::Error::TakeBytes(TakeBytesError::Eof));
+        }
+
+        let (head, tail) = self.buf.split_at(len);
+
+        self.buf = tail;
+
+        Ok(head)
+    }
+
+    /// Returns the bytes, which were not read yet.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.buf
+    }
+}
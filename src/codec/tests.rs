@@ -0,0 +1,192 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use crate::codec::{
+    BufReader, Codec, FEATURE_COMPRESSION, FEATURE_INTERNAL, FEATURE_LONG_NAME, REVISION,
+};
+use crate::compression::Compression;
+use crate::error::Error;
+use crate::tests::{into_error, setup_container};
+use crate::Archive;
+
+#[test]
+fn new() {
+    assert!(Codec::new(0).is_none());
+    assert_eq!(Codec::new(1).unwrap().revision(), 1);
+    assert_eq!(Codec::new(REVISION).unwrap(), Codec::current());
+    assert!(Codec::new(REVISION + 1).is_none());
+}
+
+#[test]
+fn new_features() {
+    let codec = Codec::new(FEATURE_INTERNAL | 1).unwrap();
+
+    assert_eq!(codec.revision(), 1);
+    assert_eq!(codec.features(), FEATURE_INTERNAL);
+    assert_eq!(codec.word(), FEATURE_INTERNAL | 1);
+
+    assert!(Codec::new(FEATURE_INTERNAL).is_none());
+    assert!(Codec::new(0x8000 | 1).is_none());
+}
+
+#[test]
+fn with_features() {
+    let codec = Codec::for_num_direct(12).with_features(FEATURE_INTERNAL);
+
+    assert_eq!(codec.revision(), 1);
+    assert_eq!(codec.features(), FEATURE_INTERNAL);
+    assert_eq!(codec.with_features(FEATURE_INTERNAL), codec);
+}
+
+#[test]
+fn for_num_direct() {
+    assert_eq!(Codec::for_num_direct(12).revision(), 1);
+    assert!(!Codec::for_num_direct(12).has_num_direct());
+    assert_eq!(Codec::for_num_direct(4), Codec::current());
+    assert!(Codec::for_num_direct(4).has_num_direct());
+}
+
+#[test]
+fn round_trip() {
+    let codec = Codec::current();
+    let mut writer = codec.writer(vec![]);
+
+    writer.write(&1u32).unwrap();
+    writer.write(&"abc".to_string()).unwrap();
+
+    let buf = writer.into_target();
+    let mut reader = codec.reader(&buf);
+
+    assert_eq!(reader.read::<u32>().unwrap(), 1);
+    assert_eq!(reader.read_str().unwrap(), "abc");
+    assert!(reader.remaining().is_empty());
+}
+
+#[test]
+fn read_bytes_eof() {
+    let buf = [0, 0, 0, 0, 0, 0, 0, 3, 1, 2];
+    let mut reader = BufReader::new(&buf);
+
+    let err = reader.read_bytes().unwrap_err();
+    let err = into_error!(err, nuts_bytes::Error::TakeBytes);
+    assert!(matches!(err, nuts_bytes::TakeBytesError::Eof));
+}
+
+#[test]
+fn read_str_invalid() {
+    let buf = [0, 0, 0, 0, 0, 0, 0, 2, 0xc3, 0x28];
+    let mut reader = BufReader::new(&buf);
+
+    let err = reader.read_str().unwrap_err();
+    let err = into_error!(err, nuts_bytes::Error::InvalidString);
+    assert_eq!(err.into_bytes(), [0xc3, 0x28]);
+}
+
+#[test]
+fn unsupported_revision() {
+    let archive = Archive::create(setup_container(), false).unwrap();
+    let header_id = archive.header_id;
+    let mut container = archive.into_container();
+    let mut buf = vec![0; container.block_size() as usize];
+
+    // the revision follows the magic
+    container.read(&header_id, &mut buf).unwrap();
+    buf[12..14].copy_from_slice(&(REVISION + 1).to_be_bytes());
+    container.write(&header_id, &buf).unwrap();
+
+    let err = Archive::peek(&mut container).unwrap_err();
+    assert!(matches!(err, Error::UnsupportedRevision(n) if n == REVISION + 1));
+
+    let err = Archive::open(container).err().unwrap();
+    assert!(matches!(err, Error::UnsupportedRevision(n) if n == REVISION + 1));
+}
+
+#[test]
+fn unsupported_feature() {
+    let archive = Archive::create(setup_container(), false).unwrap();
+    let header_id = archive.header_id;
+    let mut container = archive.into_container();
+    let mut buf = vec![0; container.block_size() as usize];
+
+    container.read(&header_id, &mut buf).unwrap();
+    buf[12..14].copy_from_slice(&0x8001u16.to_be_bytes());
+    container.write(&header_id, &buf).unwrap();
+
+    let err = Archive::peek(&mut container).unwrap_err();
+    assert!(matches!(err, Error::UnsupportedRevision(0x8001)));
+
+    let err = Archive::open(container).err().unwrap();
+    assert!(matches!(err, Error::UnsupportedRevision(0x8001)));
+}
+
+#[test]
+fn internal_feature() {
+    let mut archive = Archive::create(setup_container(), false).unwrap();
+
+    archive.append_file("f").build().unwrap();
+    assert_eq!(archive.pager.codec().features(), 0);
+
+    archive.attach("f", "thumb", b"abc").unwrap();
+
+    let archive = Archive::open(archive.into_container()).unwrap();
+    assert_eq!(archive.header.revision(), FEATURE_INTERNAL | 1);
+    assert_eq!(archive.pager.codec().features(), FEATURE_INTERNAL);
+}
+
+#[test]
+fn long_name_feature() {
+    let mut archive = Archive::create(setup_container(), false).unwrap();
+    let name = "x".repeat(1000);
+
+    archive.append_file("f").build().unwrap();
+    assert_eq!(archive.pager.codec().features(), 0);
+
+    archive.append_file(&name).build().unwrap();
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+    assert_eq!(archive.header.revision(), FEATURE_LONG_NAME | 1);
+
+    let entry = archive.lookup(&name).unwrap().unwrap();
+    assert_eq!(entry.name(), name);
+}
+
+#[test]
+fn compression_feature() {
+    let mut archive = Archive::create(setup_container(), false).unwrap();
+
+    archive
+        .append_file("f1")
+        .with_content(b"abc")
+        .build()
+        .unwrap();
+    assert_eq!(archive.pager.codec().features(), 0);
+
+    let mut builder = archive.append_file("f2");
+    builder.set_compression(Compression::Deflate);
+    builder.with_content(&[1; 1000]).build().unwrap();
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+    assert_eq!(archive.header.revision(), FEATURE_COMPRESSION | 1);
+
+    let entry = archive.lookup("f2").unwrap().unwrap();
+    assert_eq!(entry.into_file().unwrap().read_vec().unwrap(), [1; 1000]);
+}
@@ -0,0 +1,216 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use flate2::{Compress, Decompress, FlushCompress, FlushDecompress};
+use nuts_bytes::{FromBytes, PutBytes, TakeBytes, ToBytes};
+use std::io;
+#[cfg(feature = "zstd")]
+use zstd::stream::raw::{Decoder, InBuffer, Operation, OutBuffer};
+
+/// The compression of the content of a file entry.
+///
+/// The compression is chosen when the entry is appended, see
+/// [`FileBuilder::set_compression()`](crate::FileBuilder::set_compression).
+/// The content is decompressed transparently when it is read.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Compression {
+    /// The content is stored as is.
+    #[default]
+    None,
+
+    /// The content is compressed with deflate (RFC 1951).
+    Deflate,
+
+    /// The content is compressed with zstd (RFC 8878) at the given level.
+    ///
+    /// A level of `0` selects the default level of zstd. Requires the
+    /// `zstd` feature to write or read the content. Without the feature the
+    /// entry is still listed, only accessing its content fails.
+    Zstd(i32),
+}
+
+impl Compression {
+    /// Tests whether the content is compressed.
+    pub fn is_compressed(&self) -> bool {
+        *self != Compression::None
+    }
+
+    /// Creates the compressor, [`None`] if the content is stored as is.
+    ///
+    /// Fails with an [`Unsupported`](io::ErrorKind::Unsupported) error, if
+    /// the compression is not available in this build.
+    pub(crate) fn compressor(&self) -> io::Result<Option<Compressor>> {
+        match self {
+            Compression::None => Ok(None),
+            Compression::Deflate => Ok(Some(Compressor::Deflate(Compress::new(
+                flate2::Compression::default(),
+                false,
+            )))),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd(level) => Ok(Some(Compressor::Zstd(*level, None))),
+            #[cfg(not(feature = "zstd"))]
+            Compression::Zstd(_) => Err(zstd_disabled()),
+        }
+    }
+
+    /// Creates the decompressor, [`None`] if the content is stored as is.
+    ///
+    /// Fails with an [`Unsupported`](io::ErrorKind::Unsupported) error, if
+    /// the compression is not available in this build.
+    pub(crate) fn decompressor(&self) -> io::Result<Option<Decompressor>> {
+        match self {
+            Compression::None => Ok(None),
+            Compression::Deflate => Ok(Some(Decompressor::Deflate(Decompress::new(false)))),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd(_) => Ok(Some(Decompressor::Zstd(None))),
+            #[cfg(not(feature = "zstd"))]
+            Compression::Zstd(_) => Err(zstd_disabled()),
+        }
+    }
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd_disabled() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "zstd compression requires the zstd feature",
+    )
+}
+
+impl FromBytes for Compression {
+    fn from_bytes<TB: TakeBytes>(source: &mut TB) -> Result<Compression, nuts_bytes::Error> {
+        match u8::from_bytes(source)? {
+            0 => Ok(Compression::None),
+            1 => Ok(Compression::Deflate),
+            2 => Ok(Compression::Zstd(i32::from_bytes(source)?)),
+            n => Err(nuts_bytes::Error::Custom(
+                format!("invalid compression: {}", n).into(),
+            )),
+        }
+    }
+}
+
+impl ToBytes for Compression {
+    fn to_bytes<PB: PutBytes>(&self, target: &mut PB) -> Result<usize, nuts_bytes::Error> {
+        match self {
+            Compression::None => 0u8.to_bytes(target),
+            Compression::Deflate => 1u8.to_bytes(target),
+            Compression::Zstd(level) => Ok(2u8.to_bytes(target)? + level.to_bytes(target)?),
+        }
+    }
+}
+
+/// Compresses the content of an entry.
+///
+/// Each chunk passed to [`Compressor::compress()`] is flushed, so the
+/// content written so far can be decompressed at any time. The stream is
+/// never finished, further chunks can be appended later, even with a new
+/// `Compressor`.
+///
+/// A zstd frame cannot be continued by another compressor, hence each chunk
+/// becomes a frame of its own. The decompressor reads the frames one after
+/// another.
+pub(crate) enum Compressor {
+    Deflate(Compress),
+    #[cfg(feature = "zstd")]
+    Zstd(i32, Option<zstd::bulk::Compressor<'static>>),
+}
+
+impl Compressor {
+    pub fn compress(&mut self, buf: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Compressor::Deflate(compress) => deflate(compress, buf),
+            #[cfg(feature = "zstd")]
+            Compressor::Zstd(level, zstd) => {
+                let compressor = match zstd {
+                    Some(compressor) => compressor,
+                    None => zstd.insert(zstd::bulk::Compressor::new(*level)?),
+                };
+
+                compressor.compress(buf)
+            }
+        }
+    }
+}
+
+fn deflate(compress: &mut Compress, buf: &[u8]) -> io::Result<Vec<u8>> {
+    let start = compress.total_in();
+    let mut out = Vec::with_capacity(buf.len() / 2 + 64);
+
+    loop {
+        let consumed = (compress.total_in() - start) as usize;
+
+        if consumed == buf.len() && out.len() < out.capacity() && !out.is_empty() {
+            break;
+        }
+
+        if out.len() == out.capacity() {
+            out.reserve(out.capacity());
+        }
+
+        compress.compress_vec(&buf[consumed..], &mut out, FlushCompress::Sync)?;
+    }
+
+    Ok(out)
+}
+
+/// Decompresses the content of an entry, see [`Compressor`].
+pub(crate) enum Decompressor {
+    Deflate(Decompress),
+    #[cfg(feature = "zstd")]
+    Zstd(Option<Decoder<'static>>),
+}
+
+impl Decompressor {
+    /// Decompresses some bytes from `input` into `output`.
+    ///
+    /// Returns the number of bytes consumed from `input`, the decompressed
+    /// bytes are appended to `output`.
+    pub fn decompress(&mut self, input: &[u8], output: &mut Vec<u8>) -> io::Result<usize> {
+        match self {
+            Decompressor::Deflate(decompress) => {
+                let start = decompress.total_in();
+
+                decompress.decompress_vec(input, output, FlushDecompress::None)?;
+
+                Ok((decompress.total_in() - start) as usize)
+            }
+            #[cfg(feature = "zstd")]
+            Decompressor::Zstd(zstd) => {
+                let decoder = match zstd {
+                    Some(decoder) => decoder,
+                    None => zstd.insert(Decoder::new()?),
+                };
+                let pos = output.len();
+                let mut src = InBuffer::around(input);
+                let mut dst = OutBuffer::around_pos(output, pos);
+
+                decoder.run(&mut src, &mut dst)?;
+
+                Ok(src.pos())
+            }
+        }
+    }
+}
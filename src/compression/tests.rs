@@ -0,0 +1,205 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use nuts_bytes::{Reader, Writer};
+#[cfg(not(feature = "zstd"))]
+use std::io::ErrorKind;
+
+use crate::compression::Compression;
+#[cfg(not(feature = "zstd"))]
+use crate::entry::Inner;
+#[cfg(not(feature = "zstd"))]
+use crate::error::Error;
+#[cfg(not(feature = "zstd"))]
+use crate::tests::{into_error, setup_container};
+#[cfg(not(feature = "zstd"))]
+use crate::Archive;
+
+fn inflate(compression: Compression, input: &[u8]) -> Vec<u8> {
+    let mut inflater = compression.decompressor().unwrap().unwrap();
+    let mut output = Vec::with_capacity(4096);
+    let mut consumed = 0;
+
+    while consumed < input.len() {
+        output.reserve(4096);
+        consumed += inflater
+            .decompress(&input[consumed..], &mut output)
+            .unwrap();
+    }
+
+    output
+}
+
+#[test]
+fn none() {
+    assert!(!Compression::None.is_compressed());
+    assert!(Compression::None.compressor().unwrap().is_none());
+    assert!(Compression::None.decompressor().unwrap().is_none());
+}
+
+#[test]
+fn deflate() {
+    let content = "hello world, ".repeat(100);
+    let mut deflater = Compression::Deflate.compressor().unwrap().unwrap();
+
+    let compressed = deflater.compress(content.as_bytes()).unwrap();
+    assert!(compressed.len() < content.len() / 10);
+
+    assert_eq!(
+        inflate(Compression::Deflate, &compressed),
+        content.as_bytes()
+    );
+}
+
+#[test]
+fn deflate_chunks() {
+    let mut deflater = Compression::Deflate.compressor().unwrap().unwrap();
+    let mut compressed = deflater.compress(b"abcabcabc").unwrap();
+
+    compressed.extend(deflater.compress(b"abc").unwrap());
+
+    // a new compressor continues the stream
+    let mut deflater = Compression::Deflate.compressor().unwrap().unwrap();
+    compressed.extend(deflater.compress(b"xyz").unwrap());
+
+    assert_eq!(
+        inflate(Compression::Deflate, &compressed),
+        b"abcabcabcabcxyz"
+    );
+}
+
+#[test]
+fn ser() {
+    for (compression, n) in [(Compression::None, 0), (Compression::Deflate, 1)] {
+        let mut writer = Writer::new(vec![]);
+        writer.write(&compression).unwrap();
+        assert_eq!(writer.into_target(), [n]);
+
+        let buf = [n];
+        let mut reader = Reader::new(buf.as_slice());
+        assert_eq!(reader.read::<Compression>().unwrap(), compression);
+    }
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn zstd() {
+    let content = "hello world, ".repeat(100);
+    let mut compressor = Compression::Zstd(0).compressor().unwrap().unwrap();
+
+    let compressed = compressor.compress(content.as_bytes()).unwrap();
+    assert!(compressed.len() < content.len() / 10);
+
+    assert_eq!(
+        inflate(Compression::Zstd(0), &compressed),
+        content.as_bytes()
+    );
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn zstd_chunks() {
+    let mut compressor = Compression::Zstd(3).compressor().unwrap().unwrap();
+    let mut compressed = compressor.compress(b"abcabcabc").unwrap();
+
+    compressed.extend(compressor.compress(b"abc").unwrap());
+
+    // a new compressor appends another frame
+    let mut compressor = Compression::Zstd(9).compressor().unwrap().unwrap();
+    compressed.extend(compressor.compress(b"xyz").unwrap());
+
+    assert_eq!(
+        inflate(Compression::Zstd(3), &compressed),
+        b"abcabcabcabcxyz"
+    );
+}
+
+#[test]
+fn ser_zstd() {
+    let mut writer = Writer::new(vec![]);
+    writer.write(&Compression::Zstd(-5)).unwrap();
+
+    let buf = writer.into_target();
+    assert_eq!(buf, [2, 0xff, 0xff, 0xff, 0xfb]);
+
+    let mut reader = Reader::new(buf.as_slice());
+    assert_eq!(reader.read::<Compression>().unwrap(), Compression::Zstd(-5));
+}
+
+#[cfg(not(feature = "zstd"))]
+#[test]
+fn zstd_disabled() {
+    let buf = [2, 0, 0, 0, 3];
+    let mut reader = Reader::new(buf.as_slice());
+    let compression = reader.read::<Compression>().unwrap();
+    assert_eq!(compression, Compression::Zstd(3));
+
+    for err in [
+        compression.compressor().err().unwrap(),
+        compression.decompressor().err().unwrap(),
+    ] {
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+        assert_eq!(
+            err.to_string(),
+            "zstd compression requires the zstd feature"
+        );
+    }
+}
+
+#[test]
+fn de_invalid() {
+    let buf = [3];
+    let mut reader = Reader::new(buf.as_slice());
+    let err = reader.read::<Compression>().unwrap_err();
+    assert_eq!(err.to_string(), "invalid compression: 3");
+}
+
+#[cfg(not(feature = "zstd"))]
+#[test]
+fn zstd_disabled_entry() {
+    let mut archive = Archive::create(setup_container(), false).unwrap();
+
+    let mut builder = archive.append_file("f1");
+    builder.set_compression(Compression::Deflate);
+    builder.with_content(b"abc").build().unwrap();
+    archive.append_file("f2").build().unwrap();
+
+    // f1 was written by a build with the zstd feature
+    let id = *archive.tree.lookup(&mut archive.pager, 0).unwrap().unwrap();
+    let mut inner = Inner::load(&mut archive.pager, &id).unwrap();
+    inner.compression = Compression::Zstd(3);
+    inner.flush(&mut archive.pager, &id).unwrap();
+
+    // the entry is listed, only its content cannot be read
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+    let entry = archive.first().unwrap().unwrap();
+    assert_eq!(entry.name(), "f1");
+
+    let entry = entry.next().unwrap().unwrap();
+    assert_eq!(entry.name(), "f2");
+    assert!(entry.next().is_none());
+
+    let mut entry = archive.lookup("f1").unwrap().unwrap().into_file().unwrap();
+    let err = entry.read_vec().unwrap_err();
+    let err = into_error!(err, Error::Io);
+    assert_eq!(err.kind(), ErrorKind::Unsupported);
+}
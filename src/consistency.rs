@@ -0,0 +1,117 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use nuts_container::backend::Backend;
+use std::collections::HashMap;
+
+use crate::entry::immut::InnerEntry;
+use crate::header::Header;
+use crate::pager::Pager;
+use crate::tree::Tree;
+
+fn check_tree<B: Backend>(pager: &mut Pager<B>, tree: &mut Tree<B>, report: &mut Vec<String>) {
+    let mut ids = HashMap::new();
+
+    for idx in 0..tree.nblocks() as usize {
+        match tree.lookup(pager, idx) {
+            Some(Ok(id)) => {
+                if let Some(other) = ids.insert(id.to_string(), idx) {
+                    report.push(format!("block {} referenced at {} and {}", id, other, idx));
+                }
+            }
+            Some(Err(err)) => report.push(format!("lookup of {} failed: {}", idx, err)),
+            None => {} // a hole of a sparse file
+        }
+    }
+}
+
+fn check_entries<B: Backend>(
+    pager: &mut Pager<B>,
+    header: &Header,
+    tree: &mut Tree<B>,
+    report: &mut Vec<String>,
+) {
+    let nblocks = tree.nblocks();
+    let mut nfiles = 0;
+    let mut end = 0;
+    let mut entry_opt = InnerEntry::first(pager, tree);
+
+    loop {
+        match entry_opt {
+            Some(Ok(entry)) => {
+                if !entry.inner().mode.is_internal() {
+                    nfiles += 1;
+                }
+
+                end = entry.end_idx();
+                entry_opt = entry.next();
+            }
+            Some(Err(err)) => {
+                report.push(format!("failed to load entry after {}: {}", end, err));
+                return;
+            }
+            None => break,
+        }
+    }
+
+    if nfiles != header.nfiles {
+        report.push(format!(
+            "header counts {} entries, found {}",
+            header.nfiles, nfiles
+        ));
+    }
+
+    if end as u64 != nblocks {
+        report.push(format!(
+            "entries cover {} blocks, tree has {} blocks",
+            end, nblocks
+        ));
+    }
+}
+
+/// Checks the invariants of the archive and panics if any of them is
+/// violated.
+///
+/// * Every index of the tree below `nblocks` refers to a block.
+/// * No block is referenced twice by the tree.
+/// * The number of entries matches the counter of the header.
+/// * The entries cover all blocks of the tree.
+pub fn assert_consistency<B: Backend>(pager: &mut Pager<B>, header: &Header, tree: &mut Tree<B>) {
+    let mut report = vec![];
+
+    check_tree(pager, tree, &mut report);
+    check_entries(pager, header, tree, &mut report);
+
+    if !report.is_empty() {
+        panic!(
+            "archive inconsistency detected:\n{}",
+            report
+                .iter()
+                .map(|s| format!("  * {}", s))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+}
@@ -0,0 +1,63 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use crate::consistency::assert_consistency;
+use crate::tests::setup_container_with_bsize;
+use crate::Archive;
+
+fn setup_archive() -> Archive<nuts_container::memory::MemoryBackend> {
+    let container = setup_container_with_bsize(106);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.append_directory("d").build().unwrap();
+    archive.append_symlink("s", "t").build().unwrap();
+
+    let mut entry = archive.append_file("f").build().unwrap();
+    entry.write_all(&[1; 213]).unwrap();
+
+    archive
+}
+
+#[test]
+fn consistent() {
+    let mut archive = setup_archive();
+
+    assert_consistency(&mut archive.pager, &archive.header, &mut archive.tree);
+}
+
+#[test]
+#[should_panic(expected = "header counts 4 entries, found 3")]
+fn nfiles() {
+    let mut archive = setup_archive();
+
+    archive.header.nfiles += 1;
+    assert_consistency(&mut archive.pager, &archive.header, &mut archive.tree);
+}
+
+#[test]
+#[should_panic(expected = "header counts 3 entries, found 4")]
+fn dangling_block() {
+    let mut archive = setup_archive();
+
+    archive.tree.aquire(&mut archive.pager).unwrap();
+    assert_consistency(&mut archive.pager, &archive.header, &mut archive.tree);
+}
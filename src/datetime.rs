@@ -28,7 +28,7 @@ pub fn from_bytes(millis: i64) -> Result<DateTime<Utc>, Infallible> {
     let dt = Utc
         .timestamp_millis_opt(millis)
         .earliest()
-        .unwrap_or_else(|| Utc::now());
+        .unwrap_or_else(Utc::now);
     Ok(dt)
 }
 
@@ -0,0 +1,344 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use nuts_container::backend::{Backend, BlockId};
+use std::fmt;
+
+use crate::entry::immut::InnerEntry;
+use crate::error::ArchiveResult;
+use crate::pager::Pager;
+use crate::raw;
+use crate::tree::Tree;
+
+/// Number of bytes shown in the hex dump of a [`BlockDump`].
+const HEXDUMP_LEN: usize = 256;
+
+/// The decoded content of a block, see [`BlockDump`].
+#[derive(Debug)]
+pub enum BlockKind<B: Backend> {
+    /// The header block of the archive.
+    Header(raw::Header<B>),
+
+    /// A node of the block tree. The value is the level of the node, `1`
+    /// for a node, which refers to blocks of the archive.
+    TreeNode(u8, raw::TreeNode<B>),
+
+    /// The entry block of the entry at tree index `idx`.
+    Entry {
+        /// Index of the block in the tree.
+        idx: usize,
+
+        /// The decoded entry.
+        inner: raw::Inner,
+    },
+
+    /// A continuation block of a long name.
+    Name {
+        /// Index of the block in the tree.
+        idx: usize,
+
+        /// Index of the entry block.
+        entry_idx: usize,
+    },
+
+    /// A content block.
+    Content {
+        /// Index of the block in the tree.
+        idx: usize,
+
+        /// Index of the entry block.
+        entry_idx: usize,
+
+        /// Name of the entry.
+        name: String,
+
+        /// Offset of the block in the stored content of the entry.
+        offset: u64,
+    },
+
+    /// A block, which is not part of the archive or which could not be
+    /// decoded.
+    Unknown,
+}
+
+/// A report about a single block, created by
+/// [`Archive::dump_block()`](crate::Archive::dump_block).
+///
+/// The [`Display`](fmt::Display) implementation formats the report with a
+/// hex dump of the beginning of the block.
+#[derive(Debug)]
+pub struct BlockDump<B: Backend> {
+    id: B::Id,
+    kind: BlockKind<B>,
+    data: Vec<u8>,
+}
+
+impl<B: Backend> BlockDump<B> {
+    pub(crate) fn create(
+        pager: &mut Pager<B>,
+        tree: &mut Tree<B>,
+        header_id: &B::Id,
+        id: &B::Id,
+    ) -> ArchiveResult<BlockDump<B>, B> {
+        let data = pager.read_buf_raw(id)?.to_vec();
+        let kind = if id == header_id {
+            raw::Header::from_block(&data).map_or(BlockKind::Unknown, BlockKind::Header)
+        } else {
+            match find_index(pager, tree, id)? {
+                Some(idx) => classify(pager, tree, idx, &data)?,
+                None => find_node(pager, header_id, id, &data)?,
+            }
+        };
+
+        Ok(BlockDump {
+            id: id.clone(),
+            kind,
+            data,
+        })
+    }
+
+    /// Returns the id of the block.
+    pub fn id(&self) -> &B::Id {
+        &self.id
+    }
+
+    /// Returns the decoded content of the block.
+    pub fn kind(&self) -> &BlockKind<B> {
+        &self.kind
+    }
+
+    /// Returns the raw content of the block.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Searches the tree index of the block `id`.
+fn find_index<B: Backend>(
+    pager: &mut Pager<B>,
+    tree: &mut Tree<B>,
+    id: &B::Id,
+) -> ArchiveResult<Option<usize>, B> {
+    for idx in 0..tree.nblocks() as usize {
+        match tree.lookup(pager, idx) {
+            Some(Ok(cur)) if cur == id => return Ok(Some(idx)),
+            Some(Ok(_)) => {}
+            Some(Err(err)) => return Err(err),
+            None => break,
+        }
+    }
+
+    Ok(None)
+}
+
+/// Determines the role of the block at tree index `idx`.
+fn classify<B: Backend>(
+    pager: &mut Pager<B>,
+    tree: &mut Tree<B>,
+    idx: usize,
+    data: &[u8],
+) -> ArchiveResult<BlockKind<B>, B> {
+    let block_size = pager.block_size() as u64;
+    let mut entry_opt = InnerEntry::first(pager, tree);
+
+    loop {
+        let entry = match entry_opt {
+            Some(Ok(entry)) => entry,
+            Some(Err(err)) => return Err(err),
+            None => return Ok(BlockKind::Unknown),
+        };
+
+        let entry_idx = entry.idx();
+        let content_idx = entry_idx + entry.inner().head_blocks();
+
+        if idx == entry_idx {
+            return Ok(raw::Inner::from_block(data)
+                .map_or(BlockKind::Unknown, |inner| BlockKind::Entry { idx, inner }));
+        } else if idx < content_idx {
+            return Ok(BlockKind::Name { idx, entry_idx });
+        } else if idx < entry.end_idx() {
+            return Ok(BlockKind::Content {
+                idx,
+                entry_idx,
+                name: entry.inner().name.clone(),
+                offset: (idx - content_idx) as u64 * block_size,
+            });
+        }
+
+        entry_opt = entry.next();
+    }
+}
+
+/// Searches the block `id` in the nodes of the tree.
+fn find_node<B: Backend>(
+    pager: &mut Pager<B>,
+    header_id: &B::Id,
+    id: &B::Id,
+    data: &[u8],
+) -> ArchiveResult<BlockKind<B>, B> {
+    let header = match raw::Header::<B>::from_block(pager.read_buf_raw(header_id)?) {
+        Ok(header) => header,
+        Err(_) => return Ok(BlockKind::Unknown),
+    };
+    let roots = [
+        (1, header.indirect()),
+        (2, header.d_indirect()),
+        (3, header.t_indirect()),
+    ];
+
+    for (level, root) in roots.iter() {
+        if let Some(level) = search_node(pager, root, *level, id)? {
+            return Ok(raw::TreeNode::from_block(data)
+                .map_or(BlockKind::Unknown, |node| BlockKind::TreeNode(level, node)));
+        }
+    }
+
+    Ok(BlockKind::Unknown)
+}
+
+/// Searches the block `id` below the node `node` of the given `level`.
+///
+/// Returns the level of the matching node.
+fn search_node<B: Backend>(
+    pager: &mut Pager<B>,
+    node: &B::Id,
+    level: u8,
+    id: &B::Id,
+) -> ArchiveResult<Option<u8>, B> {
+    if node.is_null() {
+        return Ok(None);
+    }
+
+    if node == id {
+        return Ok(Some(level));
+    }
+
+    if level == 1 {
+        return Ok(None);
+    }
+
+    let children = match raw::TreeNode::<B>::from_block(pager.read_buf_raw(node)?) {
+        Ok(node) => node.ids().to_vec(),
+        Err(_) => return Ok(None),
+    };
+
+    for child in children.iter().take_while(|child| !child.is_null()) {
+        if let Some(level) = search_node(pager, child, level - 1, id)? {
+            return Ok(Some(level));
+        }
+    }
+
+    Ok(None)
+}
+
+impl<B: Backend> fmt::Display for BlockDump<B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(fmt, "block {} ({} bytes)", self.id, self.data.len())?;
+
+        match &self.kind {
+            BlockKind::Header(header) => {
+                writeln!(fmt, "  kind:     header")?;
+                writeln!(fmt, "  revision: {}", header.revision())?;
+                writeln!(fmt, "  features: {:#06x}", header.features())?;
+                writeln!(fmt, "  created:  {}", header.created())?;
+                writeln!(fmt, "  modified: {}", header.modified())?;
+                writeln!(fmt, "  files:    {}", header.nfiles())?;
+                writeln!(fmt, "  blocks:   {}", header.nblocks())?;
+            }
+            BlockKind::TreeNode(level, node) => {
+                let used = node.ids().iter().filter(|id| !id.is_null()).count();
+
+                writeln!(fmt, "  kind:     tree node")?;
+                writeln!(fmt, "  level:    {}", level)?;
+                writeln!(fmt, "  ids:      {} of {}", used, node.ids().len())?;
+            }
+            BlockKind::Entry { idx, inner } => {
+                writeln!(fmt, "  kind:     entry")?;
+                writeln!(fmt, "  index:    {}", idx)?;
+                writeln!(fmt, "  name:     {:?}", inner.name())?;
+                writeln!(
+                    fmt,
+                    "  mode:     {:#06x} {:#06x}",
+                    inner.mode(),
+                    inner.ext()
+                )?;
+                writeln!(fmt, "  size:     {}", inner.size())?;
+
+                if inner.name_blocks() > 0 {
+                    writeln!(fmt, "  name blocks: {}", inner.name_blocks())?;
+                }
+
+                if inner.compression() != 0 {
+                    writeln!(
+                        fmt,
+                        "  compression: {} ({} bytes)",
+                        inner.compression(),
+                        inner.raw_size()
+                    )?;
+                }
+
+                if !inner.transforms().is_empty() {
+                    writeln!(fmt, "  transforms: {}", inner.transforms().join(", "))?;
+                }
+            }
+            BlockKind::Name { idx, entry_idx } => {
+                writeln!(fmt, "  kind:     name")?;
+                writeln!(fmt, "  index:    {}", idx)?;
+                writeln!(fmt, "  entry:    {}", entry_idx)?;
+            }
+            BlockKind::Content {
+                idx,
+                entry_idx,
+                name,
+                offset,
+            } => {
+                writeln!(fmt, "  kind:     content")?;
+                writeln!(fmt, "  index:    {}", idx)?;
+                writeln!(fmt, "  entry:    {} {:?}", entry_idx, name)?;
+                writeln!(fmt, "  offset:   {}", offset)?;
+            }
+            BlockKind::Unknown => writeln!(fmt, "  kind:     unknown")?,
+        }
+
+        for (n, line) in self.data.chunks(16).take(HEXDUMP_LEN / 16).enumerate() {
+            let hex: Vec<String> = line.iter().map(|n| format!("{:02x}", n)).collect();
+            let ascii: String = line
+                .iter()
+                .map(|n| match n {
+                    0x20..=0x7e => *n as char,
+                    _ => '.',
+                })
+                .collect();
+
+            writeln!(fmt, "  {:04x}  {:<47}  {}", n * 16, hex.join(" "), ascii)?;
+        }
+
+        if self.data.len() > HEXDUMP_LEN {
+            writeln!(fmt, "  ...")?;
+        }
+
+        Ok(())
+    }
+}
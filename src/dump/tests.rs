@@ -0,0 +1,135 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use nuts_container::memory::MemoryBackend;
+
+use crate::dump::BlockKind;
+use crate::raw::Header;
+use crate::tests::setup_container;
+use crate::Archive;
+
+#[test]
+fn header() {
+    let mut archive = Archive::create(setup_container(), false).unwrap();
+    let header_id = archive.header_id;
+
+    let dump = archive.dump_block(&header_id).unwrap();
+
+    assert_eq!(dump.id(), &header_id);
+    assert_eq!(dump.data().len(), 512);
+    assert!(matches!(dump.kind(), BlockKind::Header(header) if header.nfiles() == 0));
+
+    let report = dump.to_string();
+
+    assert!(report.contains("kind:     header"));
+    assert!(report.contains("nuts-archive"));
+    assert!(report.ends_with("  ...\n"));
+}
+
+#[test]
+fn entries() {
+    let mut archive = Archive::create(setup_container(), false).unwrap();
+    let long_name = "x".repeat(600);
+
+    let mut entry = archive.append_file("f").build().unwrap();
+    entry.write_all(&[1; 1024]).unwrap();
+
+    archive.append_file(&long_name).build().unwrap();
+
+    let mut lookup = |idx| {
+        *archive
+            .tree
+            .lookup(&mut archive.pager, idx)
+            .unwrap()
+            .unwrap()
+    };
+    let ids: Vec<_> = (0..6).map(&mut lookup).collect();
+
+    let dump = archive.dump_block(&ids[0]).unwrap();
+    assert!(
+        matches!(dump.kind(), BlockKind::Entry { idx: 0, inner } if inner.name() == "f" && inner.size() == 1024)
+    );
+    assert!(dump.to_string().contains("name:     \"f\""));
+
+    let dump = archive.dump_block(&ids[2]).unwrap();
+    assert!(matches!(
+        dump.kind(),
+        BlockKind::Content { idx: 2, entry_idx: 0, name, offset: 512 } if name == "f"
+    ));
+
+    let dump = archive.dump_block(&ids[3]).unwrap();
+    assert!(
+        matches!(dump.kind(), BlockKind::Entry { idx: 3, inner } if inner.name().is_empty() && inner.name_blocks() == 2)
+    );
+
+    let dump = archive.dump_block(&ids[5]).unwrap();
+    assert!(matches!(
+        dump.kind(),
+        BlockKind::Name {
+            idx: 5,
+            entry_idx: 3
+        }
+    ));
+}
+
+#[test]
+fn tree_node() {
+    let mut archive = Archive::create(setup_container(), false).unwrap();
+
+    let mut entry = archive.append_file("f").build().unwrap();
+    entry.write_all(&[1; 20 * 512]).unwrap();
+
+    let header_id = archive.header_id;
+    let buf = archive.pager.read_buf_raw(&header_id).unwrap().to_vec();
+    let header = Header::<MemoryBackend>::from_block(&buf).unwrap();
+
+    let dump = archive.dump_block(header.indirect()).unwrap();
+    assert!(matches!(dump.kind(), BlockKind::TreeNode(1, node) if node.ids().len() == 128));
+    assert!(dump.to_string().contains("ids:      9 of 128"));
+}
+
+#[test]
+fn unknown() {
+    let mut archive = Archive::create(setup_container(), false).unwrap();
+    let id = archive.pager.aquire().unwrap();
+
+    archive.pager.write(&id, b"garbage").unwrap();
+
+    let dump = archive.dump_block(&id).unwrap();
+
+    assert!(matches!(dump.kind(), BlockKind::Unknown));
+    assert_eq!(
+        dump.to_string(),
+        format!(
+            "block {} (512 bytes)\n  kind:     unknown\n  0000  {:<47}  garbage.........\n{}  ...\n",
+            id,
+            "67 61 72 62 61 67 65 00 00 00 00 00 00 00 00 00",
+            (1..16)
+                .map(|n| format!(
+                    "  {:04x}  {}  ................\n",
+                    n * 16,
+                    ["00"; 16].join(" ")
+                ))
+                .collect::<String>()
+        )
+    );
+}
@@ -23,73 +23,611 @@
 pub mod immut;
 pub mod mode;
 pub mod r#mut;
+pub mod special;
 pub(crate) mod tstamp;
 
-use nuts_bytes::{FromBytes, ToBytes, Writer};
+#[cfg(test)]
+mod tests;
+
+use nuts_bytes::{FromBytes, PutBytes, TakeBytes, TakeBytesError, ToBytes};
 use nuts_container::backend::Backend;
-use std::mem;
+use std::collections::{BTreeMap, HashSet};
+use std::{cmp, mem};
 
+use crate::codec::{
+    Codec, FEATURE_COMPRESSION, FEATURE_INTERNAL, FEATURE_LONG_NAME, FEATURE_TRANSFORM,
+};
+use crate::compression::Compression;
 use crate::entry::mode::Mode;
 use crate::entry::tstamp::Timestamps;
-use crate::error::ArchiveResult;
+use crate::error::{ArchiveResult, Error};
+use crate::manifest::Digest;
 use crate::pager::Pager;
+use crate::tree::Tree;
 
 #[cfg(test)]
 const HALF: u8 = 53;
 #[cfg(test)]
 const FULL: u8 = 106;
 
+// Flags in the extension word of the mode: the number of external
+// references, the section, the user metadata, the checksum, the previous
+// names, the number of name blocks, the compression and the
+// transformations follow the owner.
+// Entries without them do not pay for them.
+pub(crate) const EXT_METADATA: u16 = 0x8000;
+pub(crate) const EXT_REFS: u16 = 0x4000;
+pub(crate) const EXT_SECTION: u16 = 0x2000;
+pub(crate) const EXT_CHECKSUM: u16 = 0x1000;
+pub(crate) const EXT_HISTORY: u16 = 0x0800;
+pub(crate) const EXT_LONGNAME: u16 = 0x0400;
+pub(crate) const EXT_COMPRESSED: u16 = 0x0200;
+pub(crate) const EXT_TRANSFORMED: u16 = 0x0100;
+const EXT_FLAGS: u16 = EXT_METADATA
+    | EXT_REFS
+    | EXT_SECTION
+    | EXT_CHECKSUM
+    | EXT_HISTORY
+    | EXT_LONGNAME
+    | EXT_COMPRESSED
+    | EXT_TRANSFORMED;
+
 pub(crate) fn min_entry_size() -> usize {
     let name = mem::size_of::<u64>() + 1;
-    let mode = mem::size_of::<Mode>();
+    let mode = 2 * mem::size_of::<u16>();
     let tstamps = Timestamps::size();
     let size = mem::size_of::<u64>();
+    let children = mem::size_of::<u64>();
+    let owner = 2 * (1 + mem::size_of::<u32>());
 
-    name + mode + tstamps + size
+    name + mode + tstamps + size + children + owner
+}
+
+/// Returns the number of continuation blocks needed for a name of
+/// `name_len` bytes, which does not fit into the entry block.
+///
+/// This is an estimate, the name of an entry with a lot of other metadata
+/// is moved into continuation blocks earlier.
+pub(crate) fn estimate_name_blocks(name_len: usize, block_size: u64) -> u64 {
+    let max_name_len = (block_size as usize).saturating_sub(min_entry_size() - 1);
+
+    if name_len > max_name_len {
+        ((mem::size_of::<u64>() + name_len) as u64).div_ceil(block_size)
+    } else {
+        0
+    }
 }
 
-#[derive(Debug, FromBytes, ToBytes)]
-struct Inner {
-    name: String,
-    mode: Mode,
-    tstamps: Timestamps,
-    size: u64,
+#[derive(Clone, Debug)]
+pub(crate) struct Inner {
+    pub(crate) name: String,
+    pub(crate) mode: Mode,
+    pub(crate) tstamps: Timestamps,
+    pub(crate) size: u64,
+    pub(crate) children: u64,
+    pub(crate) uid: Option<u32>,
+    pub(crate) gid: Option<u32>,
+    pub(crate) refs: u32,
+    pub(crate) section: Option<String>,
+    pub(crate) metadata: BTreeMap<String, String>,
+    pub(crate) checksum: Option<Digest>,
+    pub(crate) previous_names: Vec<String>,
+    pub(crate) name_blocks: u32,
+    pub(crate) compression: Compression,
+    pub(crate) raw_size: u64,
+    pub(crate) transforms: Vec<String>,
 }
 
 impl Inner {
-    fn new(name: String, mode: Mode) -> Inner {
+    pub(crate) fn new(name: String, mode: Mode) -> Inner {
         Inner {
             name,
             mode,
             tstamps: Timestamps::new(),
             size: 0,
+            children: 0,
+            uid: None,
+            gid: None,
+            refs: 0,
+            section: None,
+            metadata: BTreeMap::new(),
+            checksum: None,
+            previous_names: vec![],
+            name_blocks: 0,
+            compression: Compression::None,
+            raw_size: 0,
+            transforms: vec![],
         }
     }
 
-    fn load<B: Backend>(pager: &mut Pager<B>, id: &B::Id) -> ArchiveResult<Inner, B> {
-        let mut reader = pager.read_buf(id)?;
-        let inner = reader.read()?;
+    /// Returns the size of the content.
+    ///
+    /// For a compressed or transformed entry this is the size before
+    /// encoding, `size` is the number of stored bytes.
+    pub(crate) fn content_size(&self) -> u64 {
+        if self.is_encoded() {
+            self.raw_size
+        } else {
+            self.size
+        }
+    }
 
-        Ok(inner)
+    /// Tests whether the content is compressed or
+    /// [transformed](crate::Transform) before it is stored.
+    pub(crate) fn is_encoded(&self) -> bool {
+        self.compression.is_compressed() || !self.transforms.is_empty()
+    }
+
+    /// Fails, if a part of the content cannot be replaced.
+    ///
+    /// The content of a compressed or transformed entry can only be
+    /// appended.
+    pub(crate) fn check_random_access<B: Backend>(&self) -> ArchiveResult<(), B> {
+        if self.compression.is_compressed() {
+            Err(Error::Compressed(self.name.clone()))
+        } else if !self.transforms.is_empty() {
+            Err(Error::Transformed(self.name.clone()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns the [features](Codec::features) of the format, which are
+    /// needed to read the entry.
+    pub(crate) fn features(&self) -> u16 {
+        let mut features = 0;
+
+        if self.name_blocks > 0 {
+            features |= FEATURE_LONG_NAME;
+        }
+
+        if self.compression.is_compressed() {
+            features |= FEATURE_COMPRESSION;
+        }
+
+        if !self.transforms.is_empty() {
+            features |= FEATURE_TRANSFORM;
+        }
+
+        if self.mode.is_internal() {
+            features |= FEATURE_INTERNAL;
+        }
+
+        features
+    }
+
+    /// Returns the number of blocks in front of the content: the entry
+    /// block itself and the continuation blocks of a long name.
+    pub(crate) fn head_blocks(&self) -> usize {
+        1 + self.name_blocks as usize
     }
 
-    fn flush<B: Backend>(&self, pager: &mut Pager<B>, id: &B::Id) -> ArchiveResult<(), B> {
+    /// Reads a long name from the continuation blocks behind the entry
+    /// block stored at `idx`.
+    ///
+    /// Nothing happens, if the name is stored in the entry block.
+    pub(crate) fn load_name<B: Backend>(
+        &mut self,
+        pager: &mut Pager<B>,
+        tree: &mut Tree<B>,
+        idx: usize,
+    ) -> ArchiveResult<(), B> {
+        let mut buf = vec![];
+
+        for n in 1..self.head_blocks() {
+            let id = match tree.lookup(pager, idx + n) {
+                Some(result) => result?.clone(),
+                None => return Err(Error::UnexpectedEof),
+            };
+
+            buf.extend_from_slice(pager.read_buf_raw(&id)?);
+        }
+
+        // borrowed, a corrupted length fails instead of allocating it
+        if !buf.is_empty() {
+            self.name = pager.codec().reader(buf.as_slice()).read_str()?.to_string();
+        }
+
+        Ok(())
+    }
+
+    /// Writes a long name into the continuation blocks behind the entry
+    /// block stored at `idx`.
+    ///
+    /// Nothing happens, if the name is stored in the entry block.
+    pub(crate) fn flush_name<B: Backend>(
+        &self,
+        pager: &mut Pager<B>,
+        tree: &mut Tree<B>,
+        idx: usize,
+    ) -> ArchiveResult<(), B> {
+        if self.name_blocks == 0 {
+            return Ok(());
+        }
+
         let buf = {
-            let mut writer = Writer::new(vec![]);
+            let mut writer = pager.codec().writer(vec![]);
 
-            writer.write(self)?;
+            writer.write(&self.name)?;
 
             writer.into_target()
         };
 
+        let block_size = pager.block_size() as usize;
+
+        for (n, chunk) in buf.chunks(block_size).enumerate() {
+            let id = match tree.lookup(pager, idx + 1 + n) {
+                Some(result) => result?.clone(),
+                None => return Err(Error::UnexpectedEof),
+            };
+
+            pager.write(&id, chunk)?;
+        }
+
+        Ok(())
+    }
+
+    /// Creates a copy of the metadata of this entry without any content.
+    ///
+    /// The child count is reset as well, it is increased again when the
+    /// children are appended. External references belong to the blocks of
+    /// this entry and are not copied. The checksum is kept, it is still
+    /// valid once the same content is written again.
+    pub(crate) fn without_content(&self) -> Inner {
+        Inner {
+            size: 0,
+            raw_size: 0,
+            children: 0,
+            refs: 0,
+            ..self.clone()
+        }
+    }
+
+    pub(crate) fn load<B: Backend>(pager: &mut Pager<B>, id: &B::Id) -> ArchiveResult<Inner, B> {
+        let mut reader = pager.read_buf(id)?;
+        let inner = reader.read()?;
+
+        Ok(inner)
+    }
+
+    pub(crate) fn flush<B: Backend>(
+        &self,
+        pager: &mut Pager<B>,
+        id: &B::Id,
+    ) -> ArchiveResult<(), B> {
+        pager.add_features(self.features());
+
+        let buf = self.to_block(pager.codec())?;
+
         pager.write(id, &buf)?;
 
         Ok(())
     }
+
+    /// Serializes the entry as stored in the entry block.
+    ///
+    /// A long name is stored in the continuation blocks, the entry block
+    /// keeps an empty name.
+    pub(crate) fn to_block(&self, codec: Codec) -> Result<Vec<u8>, nuts_bytes::Error> {
+        let mut writer = codec.writer(vec![]);
+
+        if self.name_blocks > 0 {
+            writer.write(&Inner {
+                name: String::new(),
+                ..self.clone()
+            })?;
+        } else {
+            writer.write(self)?;
+        }
+
+        Ok(writer.into_target())
+    }
+}
+
+// Fields added later are stored behind the other fields, so that entries
+// written before can still be read: the block is padded with zeros, which
+// decode to the defaults. If an old entry fills the whole block, the
+// fields are missing.
+pub(crate) fn read_trailing<T: Default + FromBytes, TB: TakeBytes>(
+    source: &mut TB,
+) -> Result<T, nuts_bytes::Error> {
+    match T::from_bytes(source) {
+        Ok(value) => Ok(value),
+        Err(nuts_bytes::Error::TakeBytes(TakeBytesError::Eof)) => Ok(T::default()),
+        Err(err) => Err(err),
+    }
+}
+
+// Reads a string like `String::from_bytes()`, but the buffer grows while
+// the bytes are taken. A corrupted length fails with an EOF error instead of
+// allocating the whole length up front.
+fn read_string<TB: TakeBytes>(source: &mut TB) -> Result<String, nuts_bytes::Error> {
+    const CHUNK_SIZE: usize = 512;
+
+    let len = usize::from_bytes(source)?;
+    let mut vec = vec![];
+    let mut chunk = [0; CHUNK_SIZE];
+
+    while vec.len() < len {
+        let n = cmp::min(len - vec.len(), CHUNK_SIZE);
+
+        source.take_bytes(&mut chunk[..n])?;
+        vec.extend_from_slice(&chunk[..n]);
+    }
+
+    String::from_utf8(vec).map_err(nuts_bytes::Error::InvalidString)
+}
+
+impl FromBytes for Inner {
+    fn from_bytes<TB: TakeBytes>(source: &mut TB) -> Result<Inner, nuts_bytes::Error> {
+        let name = read_string(source)?;
+        let word = u16::from_bytes(source)?;
+        let tstamps = Timestamps::from_bytes(source)?;
+        let size = u64::from_bytes(source)?;
+        let children = read_trailing(source)?;
+        let ext: u16 = read_trailing(source)?;
+        let uid = read_trailing(source)?;
+        let gid = read_trailing(source)?;
+        let refs = if ext & EXT_REFS != 0 {
+            u32::from_bytes(source)?
+        } else {
+            0
+        };
+        let section = if ext & EXT_SECTION != 0 {
+            Some(read_string(source)?)
+        } else {
+            None
+        };
+        let mut metadata = BTreeMap::new();
+
+        if ext & EXT_METADATA != 0 {
+            let count = u64::from_bytes(source)?;
+
+            for _ in 0..count {
+                let key = read_string(source)?;
+                let value = read_string(source)?;
+
+                metadata.insert(key, value);
+            }
+        }
+
+        let checksum = if ext & EXT_CHECKSUM != 0 {
+            Some(Digest::from_bytes(source)?)
+        } else {
+            None
+        };
+        let mut previous_names = vec![];
+
+        if ext & EXT_HISTORY != 0 {
+            let count = u64::from_bytes(source)?;
+
+            for _ in 0..count {
+                previous_names.push(read_string(source)?);
+            }
+        }
+
+        let name_blocks = if ext & EXT_LONGNAME != 0 {
+            u32::from_bytes(source)?
+        } else {
+            0
+        };
+        let (compression, raw_size) = if ext & EXT_COMPRESSED != 0 {
+            (Compression::from_bytes(source)?, u64::from_bytes(source)?)
+        } else {
+            (Compression::None, 0)
+        };
+        let mut transforms = vec![];
+        let raw_size = if ext & EXT_TRANSFORMED != 0 {
+            let count = u64::from_bytes(source)?;
+
+            for _ in 0..count {
+                transforms.push(read_string(source)?);
+            }
+
+            if ext & EXT_COMPRESSED != 0 {
+                raw_size
+            } else {
+                u64::from_bytes(source)?
+            }
+        } else {
+            raw_size
+        };
+
+        Ok(Inner {
+            name,
+            mode: Mode::from_words(word, ext & !EXT_FLAGS),
+            tstamps,
+            size,
+            children,
+            uid,
+            gid,
+            refs,
+            section,
+            metadata,
+            checksum,
+            previous_names,
+            name_blocks,
+            compression,
+            raw_size,
+            transforms,
+        })
+    }
+}
+
+impl ToBytes for Inner {
+    fn to_bytes<PB: PutBytes>(&self, target: &mut PB) -> Result<usize, nuts_bytes::Error> {
+        let (word, mut ext) = self.mode.words();
+        let mut n = 0;
+
+        if self.refs > 0 {
+            ext |= EXT_REFS;
+        }
+
+        if self.section.is_some() {
+            ext |= EXT_SECTION;
+        }
+
+        if !self.metadata.is_empty() {
+            ext |= EXT_METADATA;
+        }
+
+        if self.checksum.is_some() {
+            ext |= EXT_CHECKSUM;
+        }
+
+        if !self.previous_names.is_empty() {
+            ext |= EXT_HISTORY;
+        }
+
+        if self.name_blocks > 0 {
+            ext |= EXT_LONGNAME;
+        }
+
+        if self.compression.is_compressed() {
+            ext |= EXT_COMPRESSED;
+        }
+
+        if !self.transforms.is_empty() {
+            ext |= EXT_TRANSFORMED;
+        }
+
+        n += self.name.to_bytes(target)?;
+        n += word.to_bytes(target)?;
+        n += self.tstamps.to_bytes(target)?;
+        n += self.size.to_bytes(target)?;
+        n += self.children.to_bytes(target)?;
+        n += ext.to_bytes(target)?;
+        n += self.uid.to_bytes(target)?;
+        n += self.gid.to_bytes(target)?;
+
+        if ext & EXT_REFS != 0 {
+            n += self.refs.to_bytes(target)?;
+        }
+
+        if let Some(section) = self.section.as_ref() {
+            n += section.to_bytes(target)?;
+        }
+
+        if ext & EXT_METADATA != 0 {
+            n += (self.metadata.len() as u64).to_bytes(target)?;
+
+            for (key, value) in self.metadata.iter() {
+                n += key.to_bytes(target)?;
+                n += value.to_bytes(target)?;
+            }
+        }
+
+        if let Some(checksum) = self.checksum.as_ref() {
+            n += checksum.to_bytes(target)?;
+        }
+
+        if ext & EXT_HISTORY != 0 {
+            n += (self.previous_names.len() as u64).to_bytes(target)?;
+
+            for name in self.previous_names.iter() {
+                n += name.to_bytes(target)?;
+            }
+        }
+
+        if ext & EXT_LONGNAME != 0 {
+            n += self.name_blocks.to_bytes(target)?;
+        }
+
+        if ext & EXT_COMPRESSED != 0 {
+            n += self.compression.to_bytes(target)?;
+            n += self.raw_size.to_bytes(target)?;
+        }
+
+        if ext & EXT_TRANSFORMED != 0 {
+            n += (self.transforms.len() as u64).to_bytes(target)?;
+
+            for name in self.transforms.iter() {
+                n += name.to_bytes(target)?;
+            }
+
+            if ext & EXT_COMPRESSED == 0 {
+                n += self.raw_size.to_bytes(target)?;
+            }
+        }
+
+        Ok(n)
+    }
+}
+
+/// Returns the name of the directory, which contains the entry with the
+/// given `name`.
+///
+/// If the entry is located at the top level, [`None`] is returned.
+pub(crate) fn parent_name(name: &str) -> Option<&str> {
+    name.trim_end_matches('/')
+        .rsplit_once('/')
+        .map(|(parent, _)| parent.trim_end_matches('/'))
+        .filter(|parent| !parent.is_empty())
+}
+
+/// Synthesizes directories, which are not stored in the archive.
+///
+/// An archive is not required to contain an explicit directory entry for each
+/// parent of an entry (e.g. when imported from a tar file). Feed all entries
+/// in archive order into [`ImplicitDirs::missing()`] to get the directories,
+/// which needs to be created in front of the entry.
+pub(crate) struct ImplicitDirs {
+    known: HashSet<String>,
+}
+
+impl ImplicitDirs {
+    pub(crate) fn new() -> ImplicitDirs {
+        ImplicitDirs {
+            known: HashSet::new(),
+        }
+    }
+
+    /// Returns the parent directories of `inner`, which were not seen so far.
+    ///
+    /// The directories are returned top-down and inherit the timestamps of
+    /// `inner`.
+    pub(crate) fn missing(&mut self, inner: &Inner) -> Vec<Inner> {
+        let mut missing = vec![];
+        let mut name = parent_name(&inner.name);
+
+        while let Some(parent) = name {
+            if !self.known.insert(parent.to_string()) {
+                break;
+            }
+
+            missing.push(Inner {
+                tstamps: inner.tstamps.clone(),
+                ..Inner::new(parent.to_string(), Mode::directory())
+            });
+
+            name = parent_name(parent);
+        }
+
+        self.seen(inner);
+
+        missing.reverse();
+        missing
+    }
+
+    /// Records `inner` without synthesizing its parents.
+    ///
+    /// If `inner` is a directory, it is not synthesized anymore.
+    pub(crate) fn seen(&mut self, inner: &Inner) {
+        if inner.mode.is_directory() {
+            self.known
+                .insert(inner.name.trim_end_matches('/').to_string());
+        }
+    }
 }
 
 macro_rules! populate_mode_api {
     () => {
+        /// Returns the mode of the entry in the familiar `drwxr-xr-x`
+        /// notation.
+        pub fn to_string_rwx(&self) -> String {
+            self.inner().mode.to_string_rwx()
+        }
+
         /// Tests whether a member of the given `group` has read access.
         pub fn can_read(&self, group: crate::Group) -> bool {
             self.inner().mode.can_read(group)
@@ -104,9 +642,56 @@ macro_rules! populate_mode_api {
         pub fn can_execute(&self, group: crate::Group) -> bool {
             self.inner().mode.can_execute(group)
         }
+
+        /// Tests whether the entry is marked immutable.
+        ///
+        /// See [`Archive::set_immutable()`](crate::Archive::set_immutable).
+        pub fn is_immutable(&self) -> bool {
+            self.inner().mode.is_immutable()
+        }
+
+        /// Returns the number of external references to the entry.
+        ///
+        /// See [`Archive::register_reference()`](crate::Archive::register_reference).
+        pub fn external_refs(&self) -> u32 {
+            self.inner().refs
+        }
+
+        /// Returns the mode of the entry.
+        pub fn mode(&self) -> crate::Mode {
+            self.inner().mode
+        }
+
+        /// Returns the mode of the entry as a unix mode.
+        ///
+        /// The mode contains the file type, the access rights and the
+        /// setuid, setgid and sticky bits. Internal entries have no file
+        /// type.
+        pub fn unix_mode(&self) -> u32 {
+            self.inner().mode.to_unix()
+        }
+
+        /// Returns the user id of the owner of the entry, if recorded.
+        pub fn uid(&self) -> Option<u32> {
+            self.inner().uid
+        }
+
+        /// Returns the group id of the owner of the entry, if recorded.
+        pub fn gid(&self) -> Option<u32> {
+            self.inner().gid
+        }
     };
 
     (mut) => {
+        /// Updates all access attributes from a mode string.
+        ///
+        /// Either the nine access characters (`rwxr-xr-x`) or a complete mode
+        /// string (`drwxr-xr-x`) are accepted. In the latter case the type
+        /// character must match the type of the entry.
+        pub fn set_rwx(&mut self, rwx: &str) -> Result<(), crate::ParseModeError> {
+            self.inner_mut().mode.set_rwx(rwx)
+        }
+
         /// Updates the read access attribute.
         ///
         /// If `readable` is set to `true`, a member of the given `group` becomes
@@ -130,6 +715,37 @@ macro_rules! populate_mode_api {
         pub fn set_executable(&mut self, group: crate::Group, executable: bool) {
             self.inner_mut().mode.set_executable(group, executable)
         }
+
+        /// Updates the access rights and the setuid, setgid and sticky bits
+        /// from a unix mode.
+        ///
+        /// The file type of `mode` is ignored, the type of the entry does not
+        /// change.
+        pub fn set_unix_mode(&mut self, mode: u32) {
+            self.inner_mut().mode.set_unix_permissions(mode)
+        }
+
+        /// Records the user id of the owner of the entry.
+        ///
+        /// Pass [`None`] to remove a recorded user id.
+        pub fn set_uid(&mut self, uid: Option<u32>) {
+            self.inner_mut().uid = uid
+        }
+
+        /// Records the group id of the owner of the entry.
+        ///
+        /// Pass [`None`] to remove a recorded group id.
+        pub fn set_gid(&mut self, gid: Option<u32>) {
+            self.inner_mut().gid = gid
+        }
+
+        /// Marks the entry immutable.
+        ///
+        /// An immutable entry cannot be modified or removed, see
+        /// [`Archive::set_immutable()`](crate::Archive::set_immutable).
+        pub fn set_immutable(&mut self, immutable: bool) {
+            self.inner_mut().mode.set_immutable(immutable)
+        }
     };
 }
 
@@ -178,4 +794,84 @@ macro_rules! populate_tstamp_api {
     };
 }
 
-use {populate_mode_api, populate_tstamp_api};
+macro_rules! populate_metadata_api {
+    () => {
+        /// Returns the name of the section the entry belongs to.
+        ///
+        /// See [`Archive::set_section()`](crate::Archive::set_section).
+        pub fn section(&self) -> Option<&str> {
+            self.inner().section.as_deref()
+        }
+
+        /// Returns the value of the user metadata `key` attached to the entry.
+        pub fn metadata(&self, key: &str) -> Option<&str> {
+            self.inner().metadata.get(key).map(|value| value.as_str())
+        }
+
+        /// Returns an iterator over the user metadata attached to the entry.
+        ///
+        /// The key/value pairs are sorted by key.
+        pub fn metadata_iter(&self) -> impl Iterator<Item = (&str, &str)> {
+            self.inner()
+                .metadata
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.as_str()))
+        }
+
+        /// Returns the SHA-256 digest of the content, which was stored
+        /// together with the metadata of the entry.
+        ///
+        /// The checksum is stored, if the entry was created with
+        /// [`FileBuilder::set_hashed()`](crate::FileBuilder::set_hashed) or
+        /// as a [multipart entry](crate::MultipartEntry). Modifying the
+        /// content afterwards removes it. The content is not read, so the
+        /// checksum is not verified against it. This way archives can be
+        /// listed and compared by their metadata only. Use
+        /// [`FileEntry::verify()`](crate::FileEntry::verify) to verify the
+        /// content.
+        pub fn stored_checksum(&self) -> Option<&crate::Digest> {
+            self.inner().checksum.as_ref()
+        }
+
+        /// Returns the names the entry had before it was renamed.
+        ///
+        /// The names are only recorded, if enabled with
+        /// [`Archive::set_rename_history()`](crate::Archive::set_rename_history).
+        /// The most recent name comes last.
+        pub fn previous_names(&self) -> &[String] {
+            &self.inner().previous_names
+        }
+    };
+
+    (mut) => {
+        populate_metadata_api!();
+
+        /// Assigns the entry to the given `section`.
+        ///
+        /// The section is preset by
+        /// [`Archive::set_section()`](crate::Archive::set_section). Pass
+        /// [`None`] to append the entry outside of any section.
+        pub fn set_section(&mut self, section: Option<&str>) {
+            self.inner_mut().section = section.map(|s| s.to_string())
+        }
+
+        /// Attaches the user metadata `key` with the given `value` to the
+        /// entry.
+        ///
+        /// An existing value of `key` is replaced. The metadata is stored
+        /// together with the other attributes of the entry and must fit into
+        /// a single block, see [`Error::MetadataTooLarge`](crate::Error::MetadataTooLarge).
+        pub fn set_metadata<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) {
+            self.inner_mut().metadata.insert(key.into(), value.into());
+        }
+
+        /// Removes the user metadata `key` from the entry.
+        ///
+        /// Returns the removed value.
+        pub fn remove_metadata(&mut self, key: &str) -> Option<String> {
+            self.inner_mut().metadata.remove(key)
+        }
+    };
+}
+
+pub(crate) use {populate_metadata_api, populate_mode_api, populate_tstamp_api};
@@ -25,12 +25,21 @@ mod tests;
 
 use log::{debug, error, warn};
 use nuts_container::backend::Backend;
+use sha2::{Digest as _, Sha256};
 use std::cmp;
 use std::convert::{TryFrom, TryInto};
-
-use crate::entry::{populate_mode_api, populate_tstamp_api, Inner};
+use std::io::{self, SeekFrom};
+use std::mem;
+use std::path::Path;
+
+use crate::attachment::Attachment;
+use crate::codec::Codec;
+use crate::entry::special::SpecialKind;
+use crate::entry::{populate_metadata_api, populate_mode_api, populate_tstamp_api, Inner};
 use crate::error::{ArchiveResult, Error};
+use crate::extract;
 use crate::pager::Pager;
+use crate::transform::Decoder;
 use crate::tree::Tree;
 
 /// An entry of the archive.
@@ -49,6 +58,12 @@ pub enum Entry<'a, B: Backend> {
 
     /// The entry represents a symlink.
     Symlink(SymlinkEntry<'a, B>),
+
+    /// The entry represents a hard link.
+    HardLink(HardLinkEntry<'a, B>),
+
+    /// The entry represents a special file (FIFO, device node or socket).
+    Special(SpecialEntry<'a, B>),
 }
 
 impl<'a, B: Backend> Entry<'a, B> {
@@ -57,10 +72,46 @@ impl<'a, B: Backend> Entry<'a, B> {
     /// If this is the last entry [`None`] is returned, which means that there
     /// are no further entries available.
     pub fn next(self) -> Option<ArchiveResult<Entry<'a, B>, B>> {
-        match self.into_inner_entry().next() {
-            Some(Ok(entry)) => Some(entry.try_into()),
-            Some(Err(err)) => Some(Err(err)),
-            None => None,
+        Self::skip_internal(self.into_inner_entry().next())
+    }
+
+    /// Converts the given inner entry into an [`Entry`]. Internal entries
+    /// are skipped, the next visible entry is returned.
+    pub(crate) fn skip_internal(
+        mut entry_opt: Option<ArchiveResult<InnerEntry<'a, B>, B>>,
+    ) -> Option<ArchiveResult<Entry<'a, B>, B>> {
+        loop {
+            match entry_opt {
+                Some(Ok(entry)) if entry.inner.mode.is_internal() => entry_opt = entry.next(),
+                Some(Ok(entry)) => return Some(entry.try_into()),
+                Some(Err(err)) => return Some(Err(err)),
+                None => return None,
+            }
+        }
+    }
+
+    /// Returns the next entry, which belongs to the same section as this
+    /// entry.
+    ///
+    /// Entries of other sections are skipped. If this entry does not belong
+    /// to a section, the next entry without a section is returned. See
+    /// [`Archive::first_in_section()`](crate::Archive::first_in_section).
+    pub fn next_in_section(self) -> Option<ArchiveResult<Entry<'a, B>, B>> {
+        let section = self.inner_entry().inner.section.clone();
+
+        Self::skip_to_section(self.next(), section.as_deref())
+    }
+
+    /// Skips all entries, which do not belong to the given `section`.
+    pub(crate) fn skip_to_section(
+        mut entry_opt: Option<ArchiveResult<Entry<'a, B>, B>>,
+        section: Option<&str>,
+    ) -> Option<ArchiveResult<Entry<'a, B>, B>> {
+        loop {
+            match entry_opt {
+                Some(Ok(entry)) if entry.section() != section => entry_opt = entry.next(),
+                _ => return entry_opt,
+            }
         }
     }
 
@@ -71,11 +122,12 @@ impl<'a, B: Backend> Entry<'a, B> {
 
     /// Returns the size of the entry.
     pub fn size(&self) -> u64 {
-        self.inner_entry().inner.size
+        self.inner_entry().inner.content_size()
     }
 
     populate_mode_api!();
     populate_tstamp_api!();
+    populate_metadata_api!();
 
     /// Tests whether this entry represents a file.
     pub fn is_file(&self) -> bool {
@@ -83,6 +135,8 @@ impl<'a, B: Backend> Entry<'a, B> {
             Self::File(_) => true,
             Self::Directory(_) => false,
             Self::Symlink(_) => false,
+            Self::HardLink(_) => false,
+            Self::Special(_) => false,
         }
     }
 
@@ -97,6 +151,8 @@ impl<'a, B: Backend> Entry<'a, B> {
             Self::File(value) => Some(value),
             Self::Directory(_) => None,
             Self::Symlink(_) => None,
+            Self::HardLink(_) => None,
+            Self::Special(_) => None,
         }
     }
 
@@ -110,6 +166,8 @@ impl<'a, B: Backend> Entry<'a, B> {
             Self::File(value) => Some(value),
             Self::Directory(_) => None,
             Self::Symlink(_) => None,
+            Self::HardLink(_) => None,
+            Self::Special(_) => None,
         }
     }
 
@@ -119,6 +177,8 @@ impl<'a, B: Backend> Entry<'a, B> {
             Self::File(_) => false,
             Self::Directory(_) => true,
             Self::Symlink(_) => false,
+            Self::HardLink(_) => false,
+            Self::Special(_) => false,
         }
     }
 
@@ -133,6 +193,8 @@ impl<'a, B: Backend> Entry<'a, B> {
             Self::File(_) => None,
             Self::Directory(value) => Some(value),
             Self::Symlink(_) => None,
+            Self::HardLink(_) => None,
+            Self::Special(_) => None,
         }
     }
 
@@ -147,6 +209,8 @@ impl<'a, B: Backend> Entry<'a, B> {
             Self::File(_) => None,
             Self::Directory(value) => Some(value),
             Self::Symlink(_) => None,
+            Self::HardLink(_) => None,
+            Self::Special(_) => None,
         }
     }
 
@@ -156,6 +220,8 @@ impl<'a, B: Backend> Entry<'a, B> {
             Self::File(_) => false,
             Self::Directory(_) => false,
             Self::Symlink(_) => true,
+            Self::HardLink(_) => false,
+            Self::Special(_) => false,
         }
     }
 
@@ -170,6 +236,8 @@ impl<'a, B: Backend> Entry<'a, B> {
             Self::File(_) => None,
             Self::Directory(_) => None,
             Self::Symlink(value) => Some(value),
+            Self::HardLink(_) => None,
+            Self::Special(_) => None,
         }
     }
 
@@ -183,14 +251,126 @@ impl<'a, B: Backend> Entry<'a, B> {
             Self::File(_) => None,
             Self::Directory(_) => None,
             Self::Symlink(value) => Some(value),
+            Self::HardLink(_) => None,
+            Self::Special(_) => None,
+        }
+    }
+
+    /// Tests whether this entry represents a hard link.
+    pub fn is_hardlink(&self) -> bool {
+        match self {
+            Self::File(_) => false,
+            Self::Directory(_) => false,
+            Self::Symlink(_) => false,
+            Self::HardLink(_) => true,
+            Self::Special(_) => false,
+        }
+    }
+
+    /// Returns a reference to the inner [`HardLinkEntry`] instance if this
+    /// entry represents a hard link.
+    ///
+    /// If this entry is a hard link then a reference to the inner
+    /// [`HardLinkEntry`] wrapped into a [`Some`] is returned. If this is not
+    /// a hard link [`None`] is returned.
+    pub fn as_hardlink(&self) -> Option<&HardLinkEntry<'a, B>> {
+        match self {
+            Self::File(_) => None,
+            Self::Directory(_) => None,
+            Self::Symlink(_) => None,
+            Self::HardLink(value) => Some(value),
+            Self::Special(_) => None,
+        }
+    }
+
+    /// Returns the inner [`HardLinkEntry`] instance if this entry represents
+    /// a hard link.
+    ///
+    /// If this entry is a hard link then the inner [`HardLinkEntry`] wrapped
+    /// into a [`Some`] is returned. If this is not a hard link [`None`] is
+    /// returned.
+    pub fn into_hardlink(self) -> Option<HardLinkEntry<'a, B>> {
+        match self {
+            Self::File(_) => None,
+            Self::Directory(_) => None,
+            Self::Symlink(_) => None,
+            Self::HardLink(value) => Some(value),
+            Self::Special(_) => None,
+        }
+    }
+
+    /// Tests whether this entry represents a special file.
+    pub fn is_special(&self) -> bool {
+        match self {
+            Self::File(_) => false,
+            Self::Directory(_) => false,
+            Self::Symlink(_) => false,
+            Self::HardLink(_) => false,
+            Self::Special(_) => true,
+        }
+    }
+
+    /// Returns a reference to the inner [`SpecialEntry`] instance if this
+    /// entry represents a special file.
+    ///
+    /// If this entry is a special file then a reference to the inner
+    /// [`SpecialEntry`] wrapped into a [`Some`] is returned. If this is not
+    /// a special file [`None`] is returned.
+    pub fn as_special(&self) -> Option<&SpecialEntry<'a, B>> {
+        match self {
+            Self::File(_) => None,
+            Self::Directory(_) => None,
+            Self::Symlink(_) => None,
+            Self::HardLink(_) => None,
+            Self::Special(value) => Some(value),
         }
     }
 
-    fn inner_entry(&'a self) -> &InnerEntry<'a, B> {
+    /// Returns the inner [`SpecialEntry`] instance if this entry represents
+    /// a special file.
+    ///
+    /// If this entry is a special file then the inner [`SpecialEntry`]
+    /// wrapped into a [`Some`] is returned. If this is not a special file
+    /// [`None`] is returned.
+    pub fn into_special(self) -> Option<SpecialEntry<'a, B>> {
+        match self {
+            Self::File(_) => None,
+            Self::Directory(_) => None,
+            Self::Symlink(_) => None,
+            Self::HardLink(_) => None,
+            Self::Special(value) => Some(value),
+        }
+    }
+
+    /// Returns all attachments of this entry.
+    ///
+    /// Only the part of the archive behind this entry is scanned, because
+    /// attachments are always appended after their entry. See
+    /// [`Archive::attach()`](crate::Archive::attach) for details.
+    pub fn attachments(&mut self) -> ArchiveResult<Vec<Attachment>, B> {
+        let entry = self.inner_entry_mut();
+        let owner = entry.inner.name.clone();
+
+        Attachment::load_all(entry.following(), &owner)
+    }
+
+    fn inner_entry(&'a self) -> &'a InnerEntry<'a, B> {
         match self {
             Self::File(inner) => &inner.0,
             Self::Directory(inner) => &inner.0,
             Self::Symlink(inner) => &inner.shared,
+            Self::HardLink(inner) => &inner.shared,
+            Self::Special(inner) => &inner.shared,
+        }
+    }
+
+    fn inner_entry_mut(&mut self) -> &mut InnerEntry<'a, B> {
+        match self {
+            Self::File(inner) => &mut inner.0,
+            Self::Directory(inner) => &mut inner.0,
+            Self::Symlink(inner) => &mut inner.shared,
+            Self::HardLink(inner) => &mut inner.shared,
+            Self::Special(inner) => &mut inner.shared,
         }
     }
 
@@ -199,10 +379,12 @@ impl<'a, B: Backend> Entry<'a, B> {
             Self::File(inner) => inner.0,
             Self::Directory(inner) => inner.0,
             Self::Symlink(inner) => inner.shared,
+            Self::HardLink(inner) => inner.shared,
+            Self::Special(inner) => inner.shared,
         }
     }
 
-    fn inner(&self) -> &Inner {
+    pub(crate) fn inner(&self) -> &Inner {
         &self.inner_entry().inner
     }
 }
@@ -217,6 +399,10 @@ impl<'a, B: Backend> TryFrom<InnerEntry<'a, B>> for Entry<'a, B> {
             Ok(Self::Directory(DirectoryEntry(src)))
         } else if src.inner.mode.is_symlink() {
             Ok(Self::Symlink(SymlinkEntry::new(src)?))
+        } else if src.inner.mode.is_hardlink() {
+            Ok(Self::HardLink(HardLinkEntry::new(src)?))
+        } else if src.inner.mode.is_special() {
+            Ok(Self::Special(SpecialEntry::new(src)?))
         } else {
             error!(
                 "could not detect entry type at {} from mode {:?}",
@@ -254,11 +440,12 @@ impl<'a, B: Backend> FileEntry<'a, B> {
 
     /// Returns the size of the file.
     pub fn size(&self) -> u64 {
-        self.0.inner.size
+        self.0.inner.content_size()
     }
 
     populate_mode_api!();
     populate_tstamp_api!();
+    populate_metadata_api!();
 
     /// Reads data from the entry.
     ///
@@ -303,16 +490,108 @@ impl<'a, B: Backend> FileEntry<'a, B> {
 
     /// Reads all bytes until EOF and collects them into a [`Vec`] which is
     /// returned.
+    ///
+    /// Reads the remaining bytes behind the current position, if the entry
+    /// was [repositioned](Self::seek) before.
     pub fn read_vec(&mut self) -> ArchiveResult<Vec<u8>, B> {
-        let mut vec = vec![0; self.0.inner.size as usize];
+        let remaining = self.0.inner.content_size().saturating_sub(self.0.rpos);
+        let mut vec = vec![0; remaining as usize];
         self.read_all(&mut vec).map(|()| vec)
     }
 
+    /// Verifies the content of the file against its
+    /// [stored checksum](Self::stored_checksum).
+    ///
+    /// The whole content is read and its SHA-256 digest is compared with
+    /// the checksum. The read position of the entry is not changed. Returns
+    /// `true`, if the content was verified, and `false`, if the entry has
+    /// no stored checksum, which can be verified.
+    ///
+    /// # Errors
+    ///
+    /// If the digest does not match the checksum, an
+    /// [`Error::ChecksumMismatch`] error is returned.
+    pub fn verify(&mut self) -> ArchiveResult<bool, B> {
+        self.0.verify()
+    }
+
+    /// Enables the verification of the content while it is read.
+    ///
+    /// If enabled, the content returned by [`FileEntry::read()`] is passed
+    /// to a running SHA-256 digest. The read, which reaches the end of the
+    /// content, compares the digest with the
+    /// [stored checksum](Self::stored_checksum) and fails with an
+    /// [`Error::ChecksumMismatch`] error, if they differ. The data of this
+    /// read is already copied into the buffer and must be discarded.
+    ///
+    /// The digest covers the content only, if it is read sequentially from
+    /// the beginning. [Seeking](Self::seek) to another position stops the
+    /// verification until the content is read from the beginning again.
+    /// Entries without a stored checksum are not verified. By default the
+    /// verification is disabled.
+    pub fn set_verified(&mut self, verified: bool) {
+        self.0.set_verified(verified)
+    }
+
+    /// Reads data from the entry starting at the given `offset`.
+    ///
+    /// Reads up to [`buf.len()`] bytes and puts them into `buf`. Other than
+    /// [`FileEntry::read()`] the read position of the entry is neither used
+    /// nor updated, so positioned and sequential reads can be mixed.
+    ///
+    /// The methods returns the number of bytes actually read, which is less
+    /// than [`buf.len()`] if the end of the entry is reached. If `offset`
+    /// is located behind the end of the entry, `0` is returned.
+    ///
+    /// [`buf.len()`]: slice::len
+    pub fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> ArchiveResult<usize, B> {
+        self.0.read_at(offset, buf)
+    }
+
+    /// Moves the read position of the entry.
+    ///
+    /// The content block, which contains the new position, is looked up in
+    /// the tree, so the entry does not need to be read from the beginning.
+    /// Seeking behind the end of the entry is allowed, subsequent reads
+    /// simply return `0`.
+    ///
+    /// Returns the new position from the start of the entry.
+    ///
+    /// # Errors
+    ///
+    /// Seeking to a negative position returns an [`Error::Io`] error of kind
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput).
+    pub fn seek(&mut self, pos: SeekFrom) -> ArchiveResult<u64, B> {
+        self.0.seek(pos)
+    }
+
+    /// Extracts the file into `path` of the local filesystem.
+    ///
+    /// The whole content is written into `path`, an existing file or
+    /// symlink is replaced, the content is never written through a symlink.
+    /// Missing parent directories are created. The access
+    /// rights (on unix) and the modification time of the entry are applied.
+    ///
+    /// The read position is reset, so the content is extracted completely
+    /// regardless of previous reads.
+    pub fn unpack_to<P: AsRef<Path>>(&mut self, path: P) -> ArchiveResult<(), B> {
+        extract::unpack_file(self, path.as_ref())
+    }
+
     fn inner(&self) -> &Inner {
         &self.0.inner
     }
 }
 
+impl<'a, B: Backend> io::Seek for FileEntry<'a, B> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        FileEntry::seek(self, pos).map_err(|err| match err {
+            Error::Io(err) => err,
+            err => io::Error::other(err.to_string()),
+        })
+    }
+}
+
 /// A directory entry of the archive.
 ///
 /// An instance of this type is attached to the [`Entry::Directory`] variant
@@ -325,8 +604,32 @@ impl<'a, B: Backend> DirectoryEntry<'a, B> {
         &self.0.inner.name
     }
 
+    /// Returns the number of direct children of the directory.
+    ///
+    /// An entry is a child of the directory, if it is appended after the
+    /// directory and its name is the name of the directory followed by a
+    /// `/` and a single path component.
+    pub fn children(&self) -> u64 {
+        self.0.inner.children
+    }
+
+    /// Tests whether the directory has no children.
+    pub fn is_empty(&self) -> bool {
+        self.children() == 0
+    }
+
+    /// Creates the directory at `path` of the local filesystem.
+    ///
+    /// Missing parent directories are created as well. The access rights of
+    /// the entry are applied (on unix). The children of the directory are
+    /// not extracted.
+    pub fn unpack_to<P: AsRef<Path>>(&self, path: P) -> ArchiveResult<(), B> {
+        Ok(extract::unpack_directory(path.as_ref(), self.unix_mode())?)
+    }
+
     populate_mode_api!();
     populate_tstamp_api!();
+    populate_metadata_api!();
 
     fn inner(&self) -> &Inner {
         &self.0.inner
@@ -339,14 +642,20 @@ impl<'a, B: Backend> DirectoryEntry<'a, B> {
 /// provides symlink specific options.
 pub struct SymlinkEntry<'a, B: Backend> {
     shared: InnerEntry<'a, B>,
+    raw_target: Vec<u8>,
     target: String,
 }
 
 impl<'a, B: Backend> SymlinkEntry<'a, B> {
     fn new(mut shared: InnerEntry<'a, B>) -> ArchiveResult<SymlinkEntry<'a, B>, B> {
-        let target = Self::read_target(&mut shared)?;
+        let raw_target = shared.read_content()?;
+        let target = String::from_utf8_lossy(&raw_target).to_string();
 
-        Ok(SymlinkEntry { shared, target })
+        Ok(SymlinkEntry {
+            shared,
+            raw_target,
+            target,
+        })
     }
 
     /// Returns the name of the symlink.
@@ -357,34 +666,119 @@ impl<'a, B: Backend> SymlinkEntry<'a, B> {
     /// Returns the target of the symlink.
     ///
     /// This is the path, where the symlink points to.
+    ///
+    /// If the target is not valid UTF-8, invalid sequences are replaced with
+    /// `U+FFFD`. Use [`target_bytes()`](Self::target_bytes) to get the
+    /// original byte sequence.
     pub fn target(&self) -> &str {
         &self.target
     }
 
+    /// Returns the target of the symlink as it was stored in the archive.
+    pub fn target_bytes(&self) -> &[u8] {
+        &self.raw_target
+    }
+
+    /// Creates the symlink at `path` of the local filesystem.
+    ///
+    /// The symlink points to the [original target](Self::target_bytes). An
+    /// existing file or symlink at `path` is replaced, missing parent
+    /// directories are created.
+    ///
+    /// # Errors
+    ///
+    /// On platforms other than unix an [`Error::Io`] error of kind
+    /// [`Unsupported`](io::ErrorKind::Unsupported) is returned.
+    pub fn unpack_to<P: AsRef<Path>>(&self, path: P) -> ArchiveResult<(), B> {
+        Ok(extract::unpack_symlink(&self.raw_target, path.as_ref())?)
+    }
+
     populate_mode_api!();
     populate_tstamp_api!();
+    populate_metadata_api!();
 
-    fn read_target(shared: &mut InnerEntry<'a, B>) -> ArchiveResult<String, B> {
-        const CHUNK: usize = 64;
-        let mut vec = vec![];
-        let mut nbytes = 0;
+    fn inner(&self) -> &Inner {
+        &self.shared.inner
+    }
+}
 
-        loop {
-            vec.resize(vec.len() + CHUNK, 0);
+/// A hard link entry of the archive.
+///
+/// An instance of this type is attached to the [`Entry::HardLink`] variant
+/// and provides hard link specific options.
+///
+/// A hard link refers to a file entry of the same archive, it shares the
+/// content of the file instead of storing a copy. Use
+/// [`Archive::lookup()`](crate::Archive::lookup) with the
+/// [target](Self::target) to get the file.
+pub struct HardLinkEntry<'a, B: Backend> {
+    shared: InnerEntry<'a, B>,
+    target: String,
+}
 
-            let n = shared.read(&mut vec[nbytes..nbytes + CHUNK])?;
-            nbytes += n;
+impl<'a, B: Backend> HardLinkEntry<'a, B> {
+    fn new(mut shared: InnerEntry<'a, B>) -> ArchiveResult<HardLinkEntry<'a, B>, B> {
+        let buf = shared.read_content()?;
+        let target = String::from_utf8_lossy(&buf).to_string();
 
-            vec.resize(nbytes, 0);
+        Ok(HardLinkEntry { shared, target })
+    }
 
-            if n == 0 {
-                break;
-            }
+    /// Returns the name of the hard link.
+    pub fn name(&self) -> &str {
+        &self.shared.inner.name
+    }
+
+    /// Returns the name of the file entry, which the hard link refers to.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    populate_mode_api!();
+    populate_tstamp_api!();
+    populate_metadata_api!();
+
+    fn inner(&self) -> &Inner {
+        &self.shared.inner
+    }
+}
+
+/// A special file entry of the archive.
+///
+/// An instance of this type is attached to the [`Entry::Special`] variant
+/// and represents a FIFO, a device node or a socket. Special files have no
+/// content, only their [kind](Self::kind) is stored.
+pub struct SpecialEntry<'a, B: Backend> {
+    shared: InnerEntry<'a, B>,
+    kind: SpecialKind,
+}
+
+impl<'a, B: Backend> SpecialEntry<'a, B> {
+    fn new(mut shared: InnerEntry<'a, B>) -> ArchiveResult<SpecialEntry<'a, B>, B> {
+        let buf = shared.read_content()?;
+
+        match SpecialKind::from_entry(&shared.inner.mode, &buf)? {
+            Some(kind) => Ok(SpecialEntry { shared, kind }),
+            None => Err(Error::InvalidType(None)),
         }
+    }
 
-        Ok(String::from_utf8_lossy(&vec).to_string())
+    /// Returns the name of the special file.
+    pub fn name(&self) -> &str {
+        &self.shared.inner.name
     }
 
+    /// Returns the type of the special file.
+    ///
+    /// For a device the major and minor number is included.
+    pub fn kind(&self) -> SpecialKind {
+        self.kind
+    }
+
+    populate_mode_api!();
+    populate_tstamp_api!();
+    populate_metadata_api!();
+
     fn inner(&self) -> &Inner {
         &self.shared.inner
     }
@@ -397,6 +791,12 @@ pub struct InnerEntry<'a, B: Backend> {
     idx: usize,
     rcache: Vec<u8>,
     ridx: usize,
+    rpos: u64,
+    generation: u64,
+    inflater: Option<Decoder>,
+    zcache: Vec<u8>,
+    verified: bool,
+    verifier: Option<(Sha256, u64)>,
 }
 
 impl<'a, B: Backend> InnerEntry<'a, B> {
@@ -406,7 +806,10 @@ impl<'a, B: Backend> InnerEntry<'a, B> {
         idx: usize,
         id: &B::Id,
     ) -> ArchiveResult<InnerEntry<'a, B>, B> {
-        let inner = Inner::load(pager, id)?;
+        let mut inner = Inner::load(pager, id)?;
+        let generation = pager.generation();
+
+        inner.load_name(pager, tree, idx)?;
 
         Ok(InnerEntry {
             pager,
@@ -415,6 +818,12 @@ impl<'a, B: Backend> InnerEntry<'a, B> {
             idx,
             rcache: vec![],
             ridx: 0,
+            rpos: 0,
+            generation,
+            inflater: None,
+            zcache: vec![],
+            verified: false,
+            verifier: None,
         })
     }
 
@@ -439,9 +848,26 @@ impl<'a, B: Backend> InnerEntry<'a, B> {
         }
     }
 
-    fn next(self) -> Option<ArchiveResult<InnerEntry<'a, B>, B>> {
+    pub(crate) fn inner(&self) -> &Inner {
+        &self.inner
+    }
+
+    pub(crate) fn idx(&self) -> usize {
+        self.idx
+    }
+
+    /// Returns the codec of the archive.
+    pub(crate) fn codec(&self) -> Codec {
+        self.pager.codec()
+    }
+
+    pub fn next(self) -> Option<ArchiveResult<InnerEntry<'a, B>, B>> {
+        if let Err(err) = self.check_generation() {
+            return Some(Err(err));
+        }
+
         let content_blocks = self.content_blocks() as usize;
-        let next_idx = self.idx + content_blocks + 1;
+        let next_idx = self.end_idx();
 
         debug!(
             "next_idx={} (idx={}, size={}, content_blocks={})",
@@ -466,62 +892,462 @@ impl<'a, B: Backend> InnerEntry<'a, B> {
         }
     }
 
-    fn read(&mut self, buf: &mut [u8]) -> ArchiveResult<usize, B> {
-        if self.rcache.is_empty() {
-            let blocks = self.content_blocks();
+    /// Loads the entry behind this entry without consuming this entry.
+    pub fn following(&mut self) -> Option<ArchiveResult<InnerEntry<'_, B>, B>> {
+        if let Err(err) = self.check_generation() {
+            return Some(Err(err));
+        }
 
-            debug!("fill cache: idx={}, blocks={}", self.ridx, blocks);
+        let next_idx = self.end_idx();
 
-            if self.ridx >= blocks as usize {
-                return Ok(0);
+        match self.tree.lookup(self.pager, next_idx) {
+            Some(Ok(id)) => {
+                let id = id.clone();
+                Some(InnerEntry::load(self.pager, self.tree, next_idx, &id))
             }
+            Some(Err(err)) => Some(Err(err)),
+            None => None,
+        }
+    }
 
-            let block_size = self.pager.block_size() as usize;
-            let remaining = self.inner.size as usize - self.ridx * block_size;
-            let cache_size = cmp::min(remaining, block_size);
+    pub fn read(&mut self, buf: &mut [u8]) -> ArchiveResult<usize, B> {
+        self.check_generation()?;
 
-            debug!(
-                "fill cache: remaining={}, cache_size={}",
-                remaining, cache_size
-            );
+        if self.rcache.is_empty() && !self.fill_cache()? {
+            return Ok(0);
+        }
+
+        let len = cmp::min(self.rcache.len(), buf.len());
+        let rpos = self.rpos;
+
+        self.rcache
+            .drain(..len)
+            .enumerate()
+            .for_each(|(i, n)| buf[i] = n);
+        self.rpos += len as u64;
+
+        if self.verified {
+            self.verify_read(rpos, &buf[..len])?;
+        }
+
+        Ok(len)
+    }
+
+    pub fn set_verified(&mut self, verified: bool) {
+        self.verified = verified;
+        self.verifier = None;
+    }
+
+    /// Passes the content read at `rpos` to the running digest.
+    ///
+    /// The digest is started, when the content is read from the beginning,
+    /// and dropped, if a read is not in sequence. Once the end of the
+    /// content is reached, the digest is compared with the stored checksum.
+    fn verify_read(&mut self, rpos: u64, buf: &[u8]) -> ArchiveResult<(), B> {
+        let expected = match self.inner.checksum {
+            Some(checksum) => checksum,
+            None => return Ok(()),
+        };
+
+        if rpos == 0 {
+            self.verifier = Some((Sha256::new(), 0));
+        }
+
+        let end = match self.verifier.as_mut() {
+            Some((sha, pos)) if *pos == rpos => {
+                sha.update(buf);
+                *pos += buf.len() as u64;
+                *pos
+            }
+            _ => {
+                self.verifier = None;
+                return Ok(());
+            }
+        };
+
+        if end < self.inner.content_size() {
+            return Ok(());
+        }
+
+        let digest: [u8; 32] = match self.verifier.take() {
+            Some((sha, _)) => sha.finalize().into(),
+            None => return Ok(()),
+        };
+
+        if digest == expected {
+            debug!("content of {} verified", self.inner.name);
+            Ok(())
+        } else {
+            error!("checksum mismatch of {}", self.inner.name);
+            Err(Error::ChecksumMismatch(self.inner.name.clone()))
+        }
+    }
+
+    /// Reads the whole content and compares its digest with the stored
+    /// checksum. The read position is not changed.
+    pub fn verify(&mut self) -> ArchiveResult<bool, B> {
+        let expected = match self.inner.checksum {
+            Some(checksum) => checksum,
+            None => return Ok(false),
+        };
+
+        let rpos = self.rpos;
+        let verifier = self.verifier.take();
+        let verified = mem::replace(&mut self.verified, false);
+
+        let result = self.seek(SeekFrom::Start(0)).and_then(|_| {
+            let mut buf = vec![0; self.pager.block_size() as usize];
+            let mut sha = Sha256::new();
+
+            loop {
+                match self.read(&mut buf)? {
+                    0 => break Ok(sha.finalize()),
+                    n => sha.update(&buf[..n]),
+                }
+            }
+        });
+
+        self.verified = verified;
+        self.seek(SeekFrom::Start(rpos))?;
+        self.verifier = verifier;
+
+        if <[u8; 32]>::from(result?) == expected {
+            Ok(true)
+        } else {
+            error!("checksum mismatch of {}", self.inner.name);
+            Err(Error::ChecksumMismatch(self.inner.name.clone()))
+        }
+    }
 
-            self.rcache.resize(cache_size, 0);
+    pub fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> ArchiveResult<usize, B> {
+        self.check_generation()?;
 
-            let idx = self.idx + self.ridx + 1;
+        if self.inner.is_encoded() {
+            return self.inflate_at(offset, buf);
+        }
+
+        let block_size = self.pager.block_size() as u64;
+        let end = cmp::min(self.inner.size, offset.saturating_add(buf.len() as u64));
+        let mut pos = offset;
+
+        while pos < end {
+            let idx = self.idx + self.inner.head_blocks() + (pos / block_size) as usize;
+            let start = (pos % block_size) as usize;
+            let n = cmp::min(block_size - start as u64, end - pos) as usize;
+
+            let nbytes = (pos - offset) as usize;
+
+            let in_tree = idx < self.tree.nblocks() as usize;
 
             match self.tree.lookup(self.pager, idx) {
                 Some(Ok(id)) => {
-                    let n = self.pager.read(id, self.rcache.as_mut_slice())?;
+                    let id = id.clone();
+                    let block = self.pager.read_buf_raw(&id)?;
 
-                    assert_eq!(n, cache_size);
-
-                    self.ridx += 1;
+                    buf[nbytes..nbytes + n].copy_from_slice(&block[start..start + n]);
                 }
                 Some(Err(err)) => return Err(err),
+                None if in_tree => {
+                    // a hole of a sparse file
+                    buf[nbytes..nbytes + n].fill(0);
+                }
                 None => {
                     warn!("premature end of archive, no block at {}", idx);
-                    return Ok(0);
+                    break;
                 }
-            };
+            }
+
+            pos += n as u64;
         }
 
-        let len = cmp::min(self.rcache.len(), buf.len());
+        Ok(pos.saturating_sub(offset) as usize)
+    }
 
-        self.rcache
-            .drain(..len)
-            .enumerate()
-            .for_each(|(i, n)| buf[i] = n);
+    pub fn seek(&mut self, pos: SeekFrom) -> ArchiveResult<u64, B> {
+        self.check_generation()?;
 
-        Ok(len)
+        let rpos = match pos {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::End(n) => self.inner.content_size().checked_add_signed(n),
+            SeekFrom::Current(n) => self.rpos.checked_add_signed(n),
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        if self.inner.is_encoded() {
+            return self.inflate_seek(rpos);
+        }
+
+        let block_size = self.pager.block_size() as u64;
+
+        debug!("seek to {} (current {})", rpos, self.rpos);
+
+        self.rcache.clear();
+        self.ridx = (rpos / block_size) as usize;
+        self.rpos = rpos;
+
+        let offset = (rpos % block_size) as usize;
+
+        if offset > 0 && self.fill_cache()? {
+            self.rcache.drain(..cmp::min(offset, self.rcache.len()));
+        }
+
+        Ok(rpos)
+    }
+
+    fn fill_cache(&mut self) -> ArchiveResult<bool, B> {
+        if self.inner.is_encoded() {
+            return self.inflate_cache();
+        }
+
+        let mut cache = mem::take(&mut self.rcache);
+        let result = self.load_block(&mut cache);
+
+        self.rcache = cache;
+
+        result
+    }
+
+    /// Decodes the next part of the content into the read cache.
+    fn inflate_cache(&mut self) -> ArchiveResult<bool, B> {
+        let mut inflater = match self.inflater.take() {
+            Some(inflater) => inflater,
+            None => match Decoder::create(&self.inner, self.pager.transforms())? {
+                Some(inflater) => inflater,
+                None => return Ok(false),
+            },
+        };
+
+        let result = self.inflate_into(&mut inflater);
+
+        self.inflater = Some(inflater);
+
+        result
+    }
+
+    fn inflate_into(&mut self, inflater: &mut Decoder) -> ArchiveResult<bool, B> {
+        let block_size = self.pager.block_size() as usize;
+        let mut eof = false;
+
+        self.rcache.clear();
+        self.rcache.reserve(2 * block_size);
+
+        loop {
+            if self.zcache.is_empty() && !eof {
+                eof = !self.load_more()?;
+            }
+
+            let consumed = inflater.decode(&self.zcache, &mut self.rcache)?;
+
+            self.zcache.drain(..consumed);
+
+            if !self.rcache.is_empty() {
+                return Ok(true);
+            }
+
+            if eof {
+                return Ok(false);
+            }
+
+            if consumed == 0 {
+                // the inflater needs more input than available
+                eof = !self.load_more()?;
+            }
+        }
+    }
+
+    /// Appends the next stored content block to the input of the inflater.
+    fn load_more(&mut self) -> ArchiveResult<bool, B> {
+        let mut block = vec![];
+        let more = self.load_block(&mut block)?;
+
+        self.zcache.extend_from_slice(&block);
+
+        Ok(more)
+    }
+
+    /// Moves the read position of a compressed or transformed entry.
+    ///
+    /// The content is decoded up to the new position, from the
+    /// beginning, if the position is moved backwards.
+    fn inflate_seek(&mut self, rpos: u64) -> ArchiveResult<u64, B> {
+        debug!("inflate seek to {} (current {})", rpos, self.rpos);
+
+        if rpos < self.rpos {
+            self.rcache.clear();
+            self.zcache.clear();
+            self.inflater = None;
+            self.ridx = 0;
+            self.rpos = 0;
+        }
+
+        while self.rpos < rpos {
+            if self.rcache.is_empty() && !self.fill_cache()? {
+                break;
+            }
+
+            let n = cmp::min(self.rcache.len() as u64, rpos - self.rpos) as usize;
+
+            self.rcache.drain(..n);
+            self.rpos += n as u64;
+        }
+
+        self.rpos = rpos;
+
+        Ok(rpos)
+    }
+
+    /// Reads from a compressed entry at `offset`, the read position is
+    /// restored afterwards.
+    fn inflate_at(&mut self, offset: u64, buf: &mut [u8]) -> ArchiveResult<usize, B> {
+        let rpos = self.rpos;
+        let mut nbytes = 0;
+
+        self.inflate_seek(offset)?;
+
+        while nbytes < buf.len() {
+            match self.read(&mut buf[nbytes..])? {
+                0 => break,
+                n => nbytes += n,
+            }
+        }
+
+        self.inflate_seek(rpos)?;
+
+        Ok(nbytes)
+    }
+
+    /// Loads the next stored content block into `cache`.
+    fn load_block(&mut self, cache: &mut Vec<u8>) -> ArchiveResult<bool, B> {
+        let blocks = self.content_blocks();
+
+        debug!("fill cache: idx={}, blocks={}", self.ridx, blocks);
+
+        if self.ridx >= blocks as usize {
+            return Ok(false);
+        }
+
+        let block_size = self.pager.block_size() as usize;
+        let remaining = self.inner.size as usize - self.ridx * block_size;
+        let cache_size = cmp::min(remaining, block_size);
+
+        debug!(
+            "fill cache: remaining={}, cache_size={}",
+            remaining, cache_size
+        );
+
+        cache.resize(cache_size, 0);
+
+        let idx = self.idx + self.inner.head_blocks() + self.ridx;
+
+        let in_tree = idx < self.tree.nblocks() as usize;
+
+        match self.tree.lookup(self.pager, idx) {
+            Some(Ok(id)) => {
+                let n = self.pager.read(id, cache.as_mut_slice())?;
+
+                assert_eq!(n, cache_size);
+
+                self.ridx += 1;
+
+                Ok(true)
+            }
+            Some(Err(err)) => Err(err),
+            None if in_tree => {
+                // a hole of a sparse file
+                cache.fill(0);
+                self.ridx += 1;
+
+                Ok(true)
+            }
+            None => {
+                warn!("premature end of archive, no block at {}", idx);
+                cache.clear();
+                Ok(false)
+            }
+        }
+    }
+
+    fn check_generation(&self) -> ArchiveResult<(), B> {
+        if self.generation == self.pager.generation() {
+            Ok(())
+        } else {
+            error!(
+                "stale entry at {}, generation {} (current {})",
+                self.idx,
+                self.generation,
+                self.pager.generation()
+            );
+            Err(Error::Stale)
+        }
+    }
+
+    /// Reads the whole content of the entry into a vector.
+    pub fn read_content(&mut self) -> ArchiveResult<Vec<u8>, B> {
+        let mut buf = vec![0; self.inner.content_size() as usize];
+        let mut nbytes = 0;
+
+        while nbytes < buf.len() {
+            match self.read(&mut buf[nbytes..])? {
+                0 => break,
+                n => nbytes += n,
+            }
+        }
+
+        buf.truncate(nbytes);
+
+        Ok(buf)
+    }
+
+    /// Returns the tree index behind the last block of the entry.
+    pub fn end_idx(&self) -> usize {
+        self.idx + self.inner.head_blocks() + self.content_blocks() as usize
     }
 
     fn content_blocks(&self) -> u64 {
         let block_size = self.pager.block_size() as u64;
 
-        if self.inner.size % block_size == 0 {
+        if self.inner.size.is_multiple_of(block_size) {
             self.inner.size / block_size
         } else {
             self.inner.size / block_size + 1
         }
     }
 }
+
+/// Returns the tree index and name of all visible entries, which are stored
+/// at `start` or behind.
+///
+/// `start` must be the index of an entry (or the end of the archive).
+pub(crate) fn visible_names<B: Backend>(
+    pager: &mut Pager<B>,
+    tree: &mut Tree<B>,
+    start: usize,
+) -> ArchiveResult<Vec<(usize, String)>, B> {
+    let mut names = vec![];
+
+    let mut entry_opt = match tree.lookup(pager, start) {
+        Some(Ok(id)) => {
+            let id = id.clone();
+            Some(InnerEntry::load(pager, tree, start, &id))
+        }
+        Some(Err(err)) => return Err(err),
+        None => None,
+    };
+
+    while let Some(result) = entry_opt {
+        let entry = result?;
+
+        if !entry.inner().mode.is_internal() {
+            names.push((entry.idx(), entry.inner().name.clone()));
+        }
+
+        entry_opt = entry.next();
+    }
+
+    Ok(names)
+}
@@ -20,8 +20,11 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
 // IN THE SOFTWARE.
 
+mod directory;
 mod file_read_all;
+mod file_read_at;
 mod file_read_vec;
+mod file_seek;
 mod inner_first;
 mod inner_next;
 mod inner_read;
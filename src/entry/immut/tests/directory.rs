@@ -0,0 +1,179 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use nuts_container::memory::MemoryBackend;
+
+use crate::entry::FULL;
+use crate::tests::setup_container_with_bsize;
+use crate::Archive;
+
+fn children(archive: &mut Archive<MemoryBackend>, name: &str) -> u64 {
+    let mut entry_opt = archive.first();
+
+    while let Some(result) = entry_opt {
+        let entry = result.unwrap();
+
+        if entry.name() == name {
+            return entry.as_directory().unwrap().children();
+        }
+
+        entry_opt = entry.next();
+    }
+
+    panic!("no such directory: {}", name);
+}
+
+#[test]
+fn empty() {
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.append_directory("d1").build().unwrap();
+
+    let entry = archive.first().unwrap().unwrap();
+    let dir = entry.as_directory().unwrap();
+
+    assert_eq!(dir.children(), 0);
+    assert!(dir.is_empty());
+}
+
+#[test]
+fn direct_children() {
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.append_directory("d1").build().unwrap();
+    archive.append_file("d1/f1").build().unwrap();
+    archive.append_directory("d1/d2").build().unwrap();
+    archive.append_symlink("d1/d2/s1", "f1").build().unwrap();
+    archive.append_file("f2").build().unwrap();
+    archive.append_file("d3/f3").build().unwrap();
+
+    assert_eq!(children(&mut archive, "d1"), 2);
+    assert_eq!(children(&mut archive, "d1/d2"), 1);
+}
+
+#[test]
+fn child_with_content() {
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.append_directory("d1/").build().unwrap();
+
+    let mut entry = archive.append_file("d1/f1").build().unwrap();
+    entry.write_all(&[1; 300]).unwrap();
+
+    archive.append_file("d1/f2").build().unwrap();
+
+    assert_eq!(children(&mut archive, "d1/"), 2);
+}
+
+#[test]
+fn child_before_parent() {
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.append_file("d1/f1").build().unwrap();
+    archive.append_directory("d1").build().unwrap();
+
+    assert_eq!(children(&mut archive, "d1"), 0);
+}
+
+#[test]
+fn rewrite() {
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.append_directory("d1").build().unwrap();
+    archive.append_file("d1/f1").build().unwrap();
+
+    let mut target = archive
+        .rewrite_to(setup_container_with_bsize(FULL as u32))
+        .unwrap();
+
+    assert_eq!(children(&mut target, "d1"), 1);
+}
+
+#[test]
+fn last_directory_wins() {
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.append_directory("d1").build().unwrap();
+    archive.append_file("d1/f1").build().unwrap();
+    archive.append_directory("d1").build().unwrap();
+    archive.append_file("d1/f2").build().unwrap();
+    archive.append_file("d1/f3").build().unwrap();
+
+    let mut entry_opt = archive.first();
+    let mut counts = vec![];
+
+    while let Some(result) = entry_opt {
+        let entry = result.unwrap();
+
+        if let Some(dir) = entry.as_directory() {
+            counts.push(dir.children());
+        }
+
+        entry_opt = entry.next();
+    }
+
+    assert_eq!(counts, [1, 2]);
+}
+
+#[test]
+fn moved_directory() {
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.append_file("f0").build().unwrap();
+    archive.append_directory("d1").build().unwrap();
+    archive.append_file("d1/f1").build().unwrap();
+
+    // moves the directory to the front
+    archive.remove("f0").unwrap();
+    archive.append_file("d1/f2").build().unwrap();
+
+    assert_eq!(children(&mut archive, "d1"), 2);
+
+    archive.rename("d1", "d2").unwrap();
+    archive.append_file("d2/f3").build().unwrap();
+    archive.append_file("d1/f4").build().unwrap();
+
+    // the count of the renamed directory is kept, d1 does not exist anymore
+    assert_eq!(children(&mut archive, "d2"), 3);
+}
+
+#[test]
+fn reopen() {
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.append_directory("d1").build().unwrap();
+    archive.append_file("d1/f1").build().unwrap();
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    archive.append_file("d1/f2").build().unwrap();
+
+    assert_eq!(children(&mut archive, "d1"), 2);
+}
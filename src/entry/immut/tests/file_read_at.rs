@@ -0,0 +1,113 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use nuts_container::memory::MemoryBackend;
+
+use crate::entry::immut::tests::setup_archive;
+use crate::entry::immut::{FileEntry, InnerEntry};
+use crate::entry::FULL;
+use crate::Archive;
+
+const SIZE: u8 = 250;
+
+fn load_first<'a>(archive: &'a mut Archive<MemoryBackend>) -> FileEntry<'a, MemoryBackend> {
+    let inner = InnerEntry::first(&mut archive.pager, &mut archive.tree)
+        .unwrap()
+        .unwrap();
+
+    FileEntry(inner)
+}
+
+#[test]
+fn empty() {
+    let mut archive = setup_archive(0);
+    let mut entry = load_first(&mut archive);
+    let mut buf = [0; 3];
+
+    assert_eq!(entry.read_at(0, &mut buf).unwrap(), 0);
+}
+
+#[test]
+fn inside_block() {
+    let mut archive = setup_archive(SIZE);
+    let mut entry = load_first(&mut archive);
+    let mut buf = [0; 3];
+
+    assert_eq!(entry.read_at(10, &mut buf).unwrap(), 3);
+    assert_eq!(buf, [10, 11, 12]);
+}
+
+#[test]
+fn across_blocks() {
+    let mut archive = setup_archive(SIZE);
+    let mut entry = load_first(&mut archive);
+    let mut buf = [0; 120];
+
+    assert_eq!(entry.read_at(100, &mut buf).unwrap(), 120);
+    assert_eq!(buf, (100..220).collect::<Vec<u8>>().as_slice());
+}
+
+#[test]
+fn block_boundary() {
+    let mut archive = setup_archive(SIZE);
+    let mut entry = load_first(&mut archive);
+    let mut buf = [0; FULL as usize];
+
+    assert_eq!(entry.read_at(FULL as u64, &mut buf).unwrap(), FULL as usize);
+    assert_eq!(buf, (FULL..2 * FULL).collect::<Vec<u8>>().as_slice());
+}
+
+#[test]
+fn end() {
+    let mut archive = setup_archive(SIZE);
+    let mut entry = load_first(&mut archive);
+    let mut buf = [0; 10];
+
+    assert_eq!(entry.read_at(245, &mut buf).unwrap(), 5);
+    assert_eq!(buf[..5], [245, 246, 247, 248, 249]);
+}
+
+#[test]
+fn behind_end() {
+    let mut archive = setup_archive(SIZE);
+    let mut entry = load_first(&mut archive);
+    let mut buf = [0; 10];
+
+    assert_eq!(entry.read_at(250, &mut buf).unwrap(), 0);
+    assert_eq!(entry.read_at(u64::MAX, &mut buf).unwrap(), 0);
+}
+
+#[test]
+fn keeps_position() {
+    let mut archive = setup_archive(SIZE);
+    let mut entry = load_first(&mut archive);
+    let mut buf = [0; 5];
+
+    entry.read_all(&mut buf).unwrap();
+    assert_eq!(buf, [0, 1, 2, 3, 4]);
+
+    entry.read_at(200, &mut buf).unwrap();
+    assert_eq!(buf, [200, 201, 202, 203, 204]);
+
+    entry.read_all(&mut buf).unwrap();
+    assert_eq!(buf, [5, 6, 7, 8, 9]);
+}
@@ -0,0 +1,133 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use nuts_container::memory::MemoryBackend;
+use std::io::{ErrorKind, Seek, SeekFrom};
+
+use crate::entry::immut::tests::setup_archive;
+use crate::entry::immut::{FileEntry, InnerEntry};
+use crate::entry::FULL;
+use crate::error::Error;
+use crate::Archive;
+
+const SIZE: u8 = 250;
+
+fn load_first<'a>(archive: &'a mut Archive<MemoryBackend>) -> FileEntry<'a, MemoryBackend> {
+    let inner = InnerEntry::first(&mut archive.pager, &mut archive.tree)
+        .unwrap()
+        .unwrap();
+
+    FileEntry(inner)
+}
+
+fn read_byte(entry: &mut FileEntry<MemoryBackend>) -> Option<u8> {
+    let mut buf = [0; 1];
+
+    match entry.read(&mut buf).unwrap() {
+        0 => None,
+        _ => Some(buf[0]),
+    }
+}
+
+#[test]
+fn start() {
+    let mut archive = setup_archive(SIZE);
+    let mut entry = load_first(&mut archive);
+
+    assert_eq!(entry.seek(SeekFrom::Start(10)).unwrap(), 10);
+    assert_eq!(entry.read_vec().unwrap(), (10..SIZE).collect::<Vec<u8>>());
+}
+
+#[test]
+fn block_boundary() {
+    let mut archive = setup_archive(SIZE);
+    let mut entry = load_first(&mut archive);
+
+    assert_eq!(entry.seek(SeekFrom::Start(FULL as u64)).unwrap(), 106);
+    assert_eq!(read_byte(&mut entry), Some(FULL));
+}
+
+#[test]
+fn backwards() {
+    let mut archive = setup_archive(SIZE);
+    let mut entry = load_first(&mut archive);
+
+    entry.seek(SeekFrom::Start(200)).unwrap();
+    assert_eq!(read_byte(&mut entry), Some(200));
+
+    assert_eq!(entry.seek(SeekFrom::Current(-101)).unwrap(), 100);
+    assert_eq!(read_byte(&mut entry), Some(100));
+
+    entry.seek(SeekFrom::Start(0)).unwrap();
+    assert_eq!(entry.read_vec().unwrap(), (0..SIZE).collect::<Vec<u8>>());
+}
+
+#[test]
+fn current() {
+    let mut archive = setup_archive(SIZE);
+    let mut entry = load_first(&mut archive);
+    let mut buf = [0; 5];
+
+    entry.read_all(&mut buf).unwrap();
+    assert_eq!(entry.seek(SeekFrom::Current(110)).unwrap(), 115);
+    assert_eq!(read_byte(&mut entry), Some(115));
+}
+
+#[test]
+fn end() {
+    let mut archive = setup_archive(SIZE);
+    let mut entry = load_first(&mut archive);
+
+    assert_eq!(entry.seek(SeekFrom::End(-3)).unwrap(), 247);
+    assert_eq!(entry.read_vec().unwrap(), [247, 248, 249]);
+}
+
+#[test]
+fn behind_end() {
+    let mut archive = setup_archive(SIZE);
+    let mut entry = load_first(&mut archive);
+
+    assert_eq!(entry.seek(SeekFrom::End(10)).unwrap(), 260);
+    assert_eq!(read_byte(&mut entry), None);
+    assert_eq!(entry.read_vec().unwrap(), []);
+}
+
+#[test]
+fn negative() {
+    let mut archive = setup_archive(SIZE);
+    let mut entry = load_first(&mut archive);
+
+    let err = entry.seek(SeekFrom::Current(-1)).unwrap_err();
+    assert!(matches!(err, Error::Io(err) if err.kind() == ErrorKind::InvalidInput));
+
+    assert_eq!(read_byte(&mut entry), Some(0));
+}
+
+#[test]
+fn io_seek() {
+    let mut archive = setup_archive(SIZE);
+    let mut entry = load_first(&mut archive);
+
+    assert_eq!(Seek::seek(&mut entry, SeekFrom::Start(42)).unwrap(), 42);
+    assert_eq!(entry.stream_position().unwrap(), 42);
+    assert_eq!(read_byte(&mut entry), Some(42));
+}
@@ -25,9 +25,9 @@ use nuts_container::memory::MemoryBackend;
 use crate::entry::immut::tests::setup_archive;
 use crate::entry::immut::InnerEntry;
 use crate::entry::{FULL, HALF};
-use crate::Archive;
+use crate::{Archive, Error};
 
-fn load_first(archive: &mut Archive<MemoryBackend>) -> InnerEntry<MemoryBackend> {
+fn load_first(archive: &mut Archive<MemoryBackend>) -> InnerEntry<'_, MemoryBackend> {
     InnerEntry::first(&mut archive.pager, &mut archive.tree)
         .unwrap()
         .unwrap()
@@ -246,3 +246,16 @@ fn full_half_more() {
     );
     assert_eq!(&buf[HALF as usize..], [b'x']);
 }
+
+#[test]
+fn stale() {
+    let mut archive = setup_archive(HALF);
+    let mut entry = load_first(&mut archive);
+    let mut buf = [b'x'; 8];
+
+    entry.pager.inc_generation();
+
+    let err = entry.read(&mut buf).unwrap_err();
+    assert!(matches!(err, Error::Stale));
+    assert_eq!(buf, [b'x'; 8]);
+}
@@ -61,7 +61,7 @@ fn target_half_chunk() {
             acc
         });
 
-    assert_eq!(target.bytes().len(), 32);
+    assert_eq!(target.len(), 32);
     archive.append_symlink("f1", &target).build().unwrap();
 
     let entry = load_first(&mut archive);
@@ -81,7 +81,7 @@ fn target_full_chunk() {
             acc
         });
 
-    assert_eq!(target.bytes().len(), 64);
+    assert_eq!(target.len(), 64);
     archive.append_symlink("f1", &target).build().unwrap();
 
     let entry = load_first(&mut archive);
@@ -102,10 +102,23 @@ fn target_full_half_chunk() {
                 acc
             });
 
-    assert_eq!(target.bytes().len(), 64 + 32);
+    assert_eq!(target.len(), 64 + 32);
     archive.append_symlink("f1", &target).build().unwrap();
 
     let entry = load_first(&mut archive);
 
     assert_eq!(entry.target(), target);
 }
+
+#[test]
+fn target_non_utf8() {
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.append_symlink("f1", b"a\xffb").build().unwrap();
+
+    let entry = load_first(&mut archive);
+
+    assert_eq!(entry.target_bytes(), b"a\xffb");
+    assert_eq!(entry.target(), "a\u{fffd}b");
+}
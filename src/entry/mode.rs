@@ -20,27 +20,86 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
 // IN THE SOFTWARE.
 
-use nuts_bytes::{FromBytes, ToBytes};
+use crate::error::ParseModeError;
 
 #[cfg(test)]
 mod tests;
 
-const MASK_TYPE: u16 = 0x0600;
-const TYPE_FILE: u16 = 0x0000;
-const TYPE_DIR: u16 = 0x0200;
-const TYPE_SYMLINK: u16 = 0x0400;
-
-const MASK_USR_R: u16 = 0x0001;
-const MASK_USR_W: u16 = 0x0002;
-const MASK_USR_X: u16 = 0x0004;
-const MASK_GRP_R: u16 = 0x0008;
-const MASK_GRP_W: u16 = 0x0010;
-const MASK_GRP_X: u16 = 0x0020;
-const MASK_OTH_R: u16 = 0x0040;
-const MASK_OTH_W: u16 = 0x0080;
-const MASK_OTH_X: u16 = 0x0100;
-
-const DEFAULT_ACCESS_RIGHTS: u16 =
+const MASK_TYPE: u32 = 0x1E00;
+const TYPE_FILE: u32 = 0x0000;
+const TYPE_DIR: u32 = 0x0200;
+const TYPE_SYMLINK: u32 = 0x0400;
+const TYPE_JOB: u32 = 0x0600;
+const TYPE_PENDING: u32 = 0x0800;
+const TYPE_ATTACHMENT: u32 = 0x0A00;
+const TYPE_HARDLINK: u32 = 0x0C00;
+const TYPE_FIFO: u32 = 0x0E00;
+const TYPE_CHAR_DEVICE: u32 = 0x1000;
+const TYPE_BLOCK_DEVICE: u32 = 0x1200;
+const TYPE_SOCKET: u32 = 0x1400;
+const TYPE_CHUNK: u32 = 0x1600;
+const TYPE_CHUNKED: u32 = 0x1800;
+
+const MASK_USR_R: u32 = 0x0001;
+const MASK_USR_W: u32 = 0x0002;
+const MASK_USR_X: u32 = 0x0004;
+const MASK_GRP_R: u32 = 0x0008;
+const MASK_GRP_W: u32 = 0x0010;
+const MASK_GRP_X: u32 = 0x0020;
+const MASK_OTH_R: u32 = 0x0040;
+const MASK_OTH_W: u32 = 0x0080;
+const MASK_OTH_X: u32 = 0x0100;
+
+const MASK_IMMUTABLE: u32 = 0x2000;
+
+// The bits above are stored in the first word of the mode, the extension
+// word was added later and carries the special unix bits.
+const MASK_SETUID: u32 = 0x0001_0000;
+const MASK_SETGID: u32 = 0x0002_0000;
+const MASK_STICKY: u32 = 0x0004_0000;
+
+// Marks an entry, which was moved into the trash by Archive::remove().
+const MASK_TRASHED: u32 = 0x0008_0000;
+
+const UNIX_IFMT: u32 = 0o170000;
+const UNIX_IFREG: u32 = 0o100000;
+const UNIX_IFDIR: u32 = 0o040000;
+const UNIX_IFLNK: u32 = 0o120000;
+const UNIX_IFIFO: u32 = 0o010000;
+const UNIX_IFCHR: u32 = 0o020000;
+const UNIX_IFBLK: u32 = 0o060000;
+const UNIX_IFSOCK: u32 = 0o140000;
+
+/// Access masks in the order of a mode string (`rwxr-xr-x`).
+const RWX_MASKS: [(u32, char); 9] = [
+    (MASK_USR_R, 'r'),
+    (MASK_USR_W, 'w'),
+    (MASK_USR_X, 'x'),
+    (MASK_GRP_R, 'r'),
+    (MASK_GRP_W, 'w'),
+    (MASK_GRP_X, 'x'),
+    (MASK_OTH_R, 'r'),
+    (MASK_OTH_W, 'w'),
+    (MASK_OTH_X, 'x'),
+];
+
+/// Access masks and their counterparts in a unix mode.
+const UNIX_MASKS: [(u32, u32); 12] = [
+    (MASK_USR_R, 0o400),
+    (MASK_USR_W, 0o200),
+    (MASK_USR_X, 0o100),
+    (MASK_GRP_R, 0o040),
+    (MASK_GRP_W, 0o020),
+    (MASK_GRP_X, 0o010),
+    (MASK_OTH_R, 0o004),
+    (MASK_OTH_W, 0o002),
+    (MASK_OTH_X, 0o001),
+    (MASK_SETUID, 0o4000),
+    (MASK_SETGID, 0o2000),
+    (MASK_STICKY, 0o1000),
+];
+
+const DEFAULT_ACCESS_RIGHTS: u32 =
     MASK_USR_R | MASK_USR_W | MASK_USR_X | MASK_GRP_R | MASK_GRP_X | MASK_OTH_R | MASK_OTH_X;
 
 #[derive(Clone, Copy, PartialEq)]
@@ -51,22 +110,75 @@ pub enum Group {
 }
 
 /// Bitmask encoding entry type & access rights.
-#[derive(Clone, Copy, Debug, FromBytes, ToBytes)]
-pub(crate) struct Mode(u16);
+///
+/// The mode is stored in two words: the first word contains type, access
+/// rights and flags, the extension word contains the special unix bits
+/// (setuid, setgid and sticky).
+///
+/// The mode of an entry is returned by its `mode()` method, use
+/// [`Mode::from_unix()`] and [`Mode::to_unix()`] to convert it from and
+/// into a unix mode.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mode(u32);
 
 impl Mode {
-    pub fn file() -> Mode {
+    pub(crate) fn file() -> Mode {
         Mode(TYPE_FILE | DEFAULT_ACCESS_RIGHTS)
     }
 
-    pub fn directory() -> Mode {
+    pub(crate) fn directory() -> Mode {
         Mode(TYPE_DIR | DEFAULT_ACCESS_RIGHTS)
     }
 
-    pub fn symlink() -> Mode {
+    pub(crate) fn symlink() -> Mode {
         Mode(TYPE_SYMLINK | DEFAULT_ACCESS_RIGHTS)
     }
 
+    pub(crate) fn hardlink() -> Mode {
+        Mode(TYPE_HARDLINK | DEFAULT_ACCESS_RIGHTS)
+    }
+
+    pub(crate) fn fifo() -> Mode {
+        Mode(TYPE_FIFO | DEFAULT_ACCESS_RIGHTS)
+    }
+
+    pub(crate) fn char_device() -> Mode {
+        Mode(TYPE_CHAR_DEVICE | DEFAULT_ACCESS_RIGHTS)
+    }
+
+    pub(crate) fn block_device() -> Mode {
+        Mode(TYPE_BLOCK_DEVICE | DEFAULT_ACCESS_RIGHTS)
+    }
+
+    pub(crate) fn socket() -> Mode {
+        Mode(TYPE_SOCKET | DEFAULT_ACCESS_RIGHTS)
+    }
+
+    pub(crate) fn job() -> Mode {
+        Mode(TYPE_JOB)
+    }
+
+    pub(crate) fn attachment() -> Mode {
+        Mode(TYPE_ATTACHMENT)
+    }
+
+    pub(crate) fn chunk() -> Mode {
+        Mode(TYPE_CHUNK)
+    }
+
+    pub(crate) fn chunked() -> Mode {
+        Mode(TYPE_CHUNKED)
+    }
+
+    /// Returns a copy of this mode with the type replaced by the pending
+    /// type. The access rights are kept.
+    ///
+    /// A pending entry is still being uploaded, see
+    /// [`MultipartEntry`](crate::MultipartEntry).
+    pub(crate) fn pending(&self) -> Mode {
+        Mode((self.0 & !MASK_TYPE) | TYPE_PENDING)
+    }
+
     /// Tests whether this `Mode` instance represents a file.
     pub fn is_file(&self) -> bool {
         self.0 & MASK_TYPE == TYPE_FILE
@@ -82,6 +194,157 @@ impl Mode {
         self.0 & MASK_TYPE == TYPE_SYMLINK
     }
 
+    /// Tests whether this `Mode` instance represents a hard link.
+    pub fn is_hardlink(&self) -> bool {
+        self.0 & MASK_TYPE == TYPE_HARDLINK
+    }
+
+    /// Tests whether this `Mode` instance represents a FIFO.
+    pub fn is_fifo(&self) -> bool {
+        self.0 & MASK_TYPE == TYPE_FIFO
+    }
+
+    /// Tests whether this `Mode` instance represents a character device.
+    pub fn is_char_device(&self) -> bool {
+        self.0 & MASK_TYPE == TYPE_CHAR_DEVICE
+    }
+
+    /// Tests whether this `Mode` instance represents a block device.
+    pub fn is_block_device(&self) -> bool {
+        self.0 & MASK_TYPE == TYPE_BLOCK_DEVICE
+    }
+
+    /// Tests whether this `Mode` instance represents a socket.
+    pub fn is_socket(&self) -> bool {
+        self.0 & MASK_TYPE == TYPE_SOCKET
+    }
+
+    /// Tests whether this `Mode` instance represents a special file (FIFO,
+    /// device node or socket).
+    pub fn is_special(&self) -> bool {
+        self.is_fifo() || self.is_char_device() || self.is_block_device() || self.is_socket()
+    }
+
+    /// Tests whether this `Mode` instance represents a job record.
+    pub(crate) fn is_job(&self) -> bool {
+        self.0 & MASK_TYPE == TYPE_JOB
+    }
+
+    /// Tests whether this `Mode` instance represents an attachment.
+    pub(crate) fn is_attachment(&self) -> bool {
+        self.0 & MASK_TYPE == TYPE_ATTACHMENT
+    }
+
+    /// Tests whether this `Mode` instance represents a chunk of a chunked
+    /// file.
+    pub(crate) fn is_chunk(&self) -> bool {
+        self.0 & MASK_TYPE == TYPE_CHUNK
+    }
+
+    /// Tests whether this `Mode` instance represents a chunked file.
+    pub(crate) fn is_chunked(&self) -> bool {
+        self.0 & MASK_TYPE == TYPE_CHUNKED
+    }
+
+    /// Tests whether this `Mode` instance represents a pending entry.
+    pub(crate) fn is_pending(&self) -> bool {
+        self.0 & MASK_TYPE == TYPE_PENDING
+    }
+
+    /// Tests whether this `Mode` instance represents an internal entry.
+    ///
+    /// Internal entries are not visible when traversing the archive.
+    pub(crate) fn is_internal(&self) -> bool {
+        self.is_job()
+            || self.is_pending()
+            || self.is_attachment()
+            || self.is_chunk()
+            || self.is_chunked()
+            || self.is_trashed()
+    }
+
+    /// Creates a mode from its two stored words, see [`Mode::words()`].
+    pub(crate) fn from_words(word: u16, ext: u16) -> Mode {
+        Mode(word as u32 | (ext as u32) << 16)
+    }
+
+    /// Returns the first and the extension word of the mode.
+    pub(crate) fn words(&self) -> (u16, u16) {
+        (self.0 as u16, (self.0 >> 16) as u16)
+    }
+
+    /// Creates a mode from a unix mode.
+    ///
+    /// Files, directories, symlinks and special files are supported, for
+    /// any other file type [`None`] is returned.
+    pub fn from_unix(mode: u32) -> Option<Mode> {
+        let mut result = match mode & UNIX_IFMT {
+            UNIX_IFREG => Mode(TYPE_FILE),
+            UNIX_IFDIR => Mode(TYPE_DIR),
+            UNIX_IFLNK => Mode(TYPE_SYMLINK),
+            UNIX_IFIFO => Mode(TYPE_FIFO),
+            UNIX_IFCHR => Mode(TYPE_CHAR_DEVICE),
+            UNIX_IFBLK => Mode(TYPE_BLOCK_DEVICE),
+            UNIX_IFSOCK => Mode(TYPE_SOCKET),
+            _ => return None,
+        };
+
+        result.set_unix_permissions(mode);
+
+        Some(result)
+    }
+
+    /// Converts the mode into a unix mode.
+    ///
+    /// A hard link is a regular file, internal entries have no file type in
+    /// the unix mode.
+    pub fn to_unix(self) -> u32 {
+        let ftype = match self.0 & MASK_TYPE {
+            TYPE_FILE | TYPE_HARDLINK => UNIX_IFREG,
+            TYPE_DIR => UNIX_IFDIR,
+            TYPE_SYMLINK => UNIX_IFLNK,
+            TYPE_FIFO => UNIX_IFIFO,
+            TYPE_CHAR_DEVICE => UNIX_IFCHR,
+            TYPE_BLOCK_DEVICE => UNIX_IFBLK,
+            TYPE_SOCKET => UNIX_IFSOCK,
+            _ => 0,
+        };
+
+        UNIX_MASKS
+            .iter()
+            .filter(|(mask, _)| self.0 & mask > 0)
+            .fold(ftype, |acc, (_, unix)| acc | unix)
+    }
+
+    /// Updates access rights and special bits from a unix mode.
+    ///
+    /// The file type of `mode` is ignored.
+    pub(crate) fn set_unix_permissions(&mut self, mode: u32) {
+        for (mask, unix) in UNIX_MASKS {
+            self.update_mask(mode & unix > 0, mask);
+        }
+    }
+
+    /// Tests whether the immutable flag is set.
+    pub fn is_immutable(&self) -> bool {
+        self.0 & MASK_IMMUTABLE > 0
+    }
+
+    /// Updates the immutable flag.
+    pub(crate) fn set_immutable(&mut self, immutable: bool) {
+        self.update_mask(immutable, MASK_IMMUTABLE);
+    }
+
+    /// Tests whether the entry is in the trash.
+    pub(crate) fn is_trashed(&self) -> bool {
+        self.0 & MASK_TRASHED > 0
+    }
+
+    /// Updates the trashed flag.
+    pub(crate) fn set_trashed(&mut self, trashed: bool) {
+        self.update_mask(trashed, MASK_TRASHED);
+    }
+
     /// Tests whether a member of the given `group` has read access.
     pub fn can_read(&self, group: Group) -> bool {
         self.0 & Self::read_mask(group) > 0
@@ -91,7 +354,7 @@ impl Mode {
     ///
     /// If `readable` is set to `true`, a member of the given `group` becomes
     /// read access. If set to `false`, the read access is revoked.
-    pub fn set_readable(&mut self, group: Group, readable: bool) {
+    pub(crate) fn set_readable(&mut self, group: Group, readable: bool) {
         self.update_mask(readable, Self::read_mask(group));
     }
 
@@ -104,7 +367,7 @@ impl Mode {
     ///
     /// If `writable` is set to `true`, a member of the given `group` becomes
     /// write access. If set to `false`, the write access is revoked.
-    pub fn set_writable(&mut self, group: Group, writable: bool) {
+    pub(crate) fn set_writable(&mut self, group: Group, writable: bool) {
         self.update_mask(writable, Self::write_mask(group));
     }
 
@@ -117,11 +380,78 @@ impl Mode {
     ///
     /// If `executable` is set to `true`, a member of the given `group` becomes
     /// execute access. If set to `false`, the execute access is revoked.
-    pub fn set_executable(&mut self, group: Group, executable: bool) {
+    pub(crate) fn set_executable(&mut self, group: Group, executable: bool) {
         self.update_mask(executable, Self::execute_mask(group));
     }
 
-    fn read_mask(group: Group) -> u16 {
+    /// Returns the mode as a string in the familiar `drwxr-xr-x` notation.
+    ///
+    /// The first character encodes the type: `-` for a file, `d` for a
+    /// directory, `l` for a symlink, `h` for a hard link, `p` for a FIFO,
+    /// `c` for a character device, `b` for a block device, `s` for a socket
+    /// and `?` for an internal entry.
+    pub fn to_string_rwx(self) -> String {
+        let mut s = String::with_capacity(10);
+
+        s.push(self.type_char());
+
+        for (mask, c) in RWX_MASKS {
+            s.push(if self.0 & mask > 0 { c } else { '-' });
+        }
+
+        s
+    }
+
+    /// Updates the access rights from a mode string created by
+    /// [`Mode::to_string_rwx()`].
+    ///
+    /// Either the nine access characters (`rwxr-xr-x`) or a complete mode
+    /// string (`drwxr-xr-x`) are accepted. In the latter case the type
+    /// character must match the type of this mode.
+    pub(crate) fn set_rwx(&mut self, s: &str) -> Result<(), ParseModeError> {
+        let access = match s.chars().count() {
+            10 if s.starts_with(self.type_char()) => &s[1..],
+            9 => s,
+            _ => return Err(ParseModeError(s.to_string())),
+        };
+
+        let mask = RWX_MASKS.iter().fold(0, |acc, (mask, _)| acc | mask);
+
+        self.0 = (self.0 & !mask) | Self::parse_access(s, access)?;
+
+        Ok(())
+    }
+
+    fn type_char(&self) -> char {
+        match self.0 & MASK_TYPE {
+            TYPE_FILE => '-',
+            TYPE_DIR => 'd',
+            TYPE_SYMLINK => 'l',
+            TYPE_HARDLINK => 'h',
+            TYPE_FIFO => 'p',
+            TYPE_CHAR_DEVICE => 'c',
+            TYPE_BLOCK_DEVICE => 'b',
+            TYPE_SOCKET => 's',
+            _ => '?',
+        }
+    }
+
+    fn parse_access(s: &str, access: &str) -> Result<u32, ParseModeError> {
+        if access.chars().count() != RWX_MASKS.len() {
+            return Err(ParseModeError(s.to_string()));
+        }
+
+        access
+            .chars()
+            .zip(RWX_MASKS)
+            .try_fold(0, |acc, (c, (mask, expected))| match c {
+                '-' => Ok(acc),
+                c if c == expected => Ok(acc | mask),
+                _ => Err(ParseModeError(s.to_string())),
+            })
+    }
+
+    fn read_mask(group: Group) -> u32 {
         match group {
             Group::User => MASK_USR_R,
             Group::Group => MASK_GRP_R,
@@ -129,7 +459,7 @@ impl Mode {
         }
     }
 
-    fn write_mask(group: Group) -> u16 {
+    fn write_mask(group: Group) -> u32 {
         match group {
             Group::User => MASK_USR_W,
             Group::Group => MASK_GRP_W,
@@ -137,7 +467,7 @@ impl Mode {
         }
     }
 
-    fn execute_mask(group: Group) -> u16 {
+    fn execute_mask(group: Group) -> u32 {
         match group {
             Group::User => MASK_USR_X,
             Group::Group => MASK_GRP_X,
@@ -145,7 +475,7 @@ impl Mode {
         }
     }
 
-    fn update_mask(&mut self, enable: bool, mask: u16) {
+    fn update_mask(&mut self, enable: bool, mask: u32) {
         if enable {
             self.0 |= mask;
         } else {
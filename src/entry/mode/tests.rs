@@ -20,9 +20,8 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
 // IN THE SOFTWARE.
 
-use nuts_bytes::{Reader, Writer};
-
-use crate::entry::mode::{Group, Mode};
+use crate::entry::mode::{Group, Mode, TYPE_DIR, TYPE_JOB, TYPE_SYMLINK};
+use crate::error::ParseModeError;
 
 macro_rules! can_test {
     ($name:ident, $method: ident ( $group:ident ), $mask:literal) => {
@@ -69,20 +68,11 @@ macro_rules! unset_test {
 }
 
 #[test]
-fn ser() {
-    let mut writer = Writer::new(vec![]);
-
-    writer.write(&Mode(4711u16)).unwrap();
-
-    assert_eq!(writer.into_target(), [0x12, 0x67]);
-}
-
-#[test]
-fn de() {
-    let mut reader = Reader::new([0x12, 0x67].as_slice());
+fn words() {
+    let mode = Mode::from_words(4711, 0x0007);
 
-    let mode = reader.read::<Mode>().unwrap();
-    assert_eq!(mode.0, 4711);
+    assert_eq!(mode.0, 0x0007_1267);
+    assert_eq!(mode.words(), (4711, 0x0007));
 }
 
 #[test]
@@ -195,3 +185,208 @@ fn is_not_symlink() {
     assert!(!Mode(0b00000010_00000000).is_symlink());
     assert!(!Mode(0b00000110_00000000).is_symlink());
 }
+
+#[test]
+fn hardlink() {
+    let mode = Mode::hardlink();
+
+    assert!(mode.is_hardlink());
+    assert!(!mode.is_internal());
+    assert!(!mode.is_file());
+    assert!(!mode.is_symlink());
+    assert!(!Mode::file().is_hardlink());
+}
+
+#[test]
+fn special() {
+    for mode in [
+        Mode::fifo(),
+        Mode::char_device(),
+        Mode::block_device(),
+        Mode::socket(),
+    ] {
+        assert!(mode.is_special());
+        assert!(!mode.is_internal());
+        assert!(!mode.is_file());
+    }
+
+    assert!(!Mode::file().is_special());
+    assert!(!Mode::hardlink().is_special());
+}
+
+#[test]
+fn pending() {
+    let mode = Mode::file().pending();
+
+    assert!(mode.is_pending());
+    assert!(mode.is_internal());
+    assert!(!mode.is_file());
+    assert_eq!(mode.0 & 0x01FF, Mode::file().0 & 0x01FF);
+}
+
+#[test]
+fn attachment() {
+    let mode = Mode::attachment();
+
+    assert!(mode.is_attachment());
+    assert!(mode.is_internal());
+    assert!(!mode.is_file());
+}
+
+#[test]
+fn to_string_rwx() {
+    assert_eq!(Mode::file().to_string_rwx(), "-rwxr-xr-x");
+    assert_eq!(Mode::directory().to_string_rwx(), "drwxr-xr-x");
+    assert_eq!(Mode::symlink().to_string_rwx(), "lrwxr-xr-x");
+    assert_eq!(Mode::hardlink().to_string_rwx(), "hrwxr-xr-x");
+    assert_eq!(Mode::fifo().to_string_rwx(), "prwxr-xr-x");
+    assert_eq!(Mode::char_device().to_string_rwx(), "crwxr-xr-x");
+    assert_eq!(Mode::block_device().to_string_rwx(), "brwxr-xr-x");
+    assert_eq!(Mode::socket().to_string_rwx(), "srwxr-xr-x");
+    assert_eq!(Mode(TYPE_JOB).to_string_rwx(), "?---------");
+    assert_eq!(Mode(0x00ff).to_string_rwx(), "-rwxrwxrw-");
+}
+
+#[test]
+fn set_rwx_access() {
+    let mut mode = Mode::file();
+
+    mode.set_rwx("rw-r-----").unwrap();
+    assert_eq!(mode.0, 0b00000000_00001011);
+    assert_eq!(mode.to_string_rwx(), "-rw-r-----");
+}
+
+#[test]
+fn set_rwx_full() {
+    let mut mode = Mode::directory();
+
+    mode.set_rwx("dr-x------").unwrap();
+    assert_eq!(mode.0, 0b00000010_00000101);
+}
+
+#[test]
+fn set_rwx_round_trip() {
+    for bits in 0..0x01ff {
+        let mut mode = Mode::symlink();
+        let s = Mode(TYPE_SYMLINK | bits).to_string_rwx();
+
+        mode.set_rwx(&s).unwrap();
+        assert_eq!(mode.0, TYPE_SYMLINK | bits);
+    }
+}
+
+#[test]
+fn set_rwx_invalid() {
+    for s in [
+        "",
+        "rwx",
+        "rwxrwxrwxr",
+        "drwxr-xr-x",
+        "-rwxr-xr-xx",
+        "xwrr-xr-x",
+        "rwxr-xr-?",
+        "rwxr-xr-ä",
+    ] {
+        let mut mode = Mode::file();
+
+        assert_eq!(mode.set_rwx(s), Err(ParseModeError(s.to_string())), "{}", s);
+        assert_eq!(mode.0, Mode::file().0);
+    }
+}
+
+#[test]
+fn immutable() {
+    let mut mode = Mode::file();
+    assert!(!mode.is_immutable());
+
+    mode.set_immutable(true);
+    assert!(mode.is_immutable());
+    assert!(mode.is_file());
+    assert_eq!(mode.to_string_rwx(), Mode::file().to_string_rwx());
+
+    mode.set_immutable(false);
+    assert!(!mode.is_immutable());
+    assert_eq!(mode.0, Mode::file().0);
+}
+
+#[test]
+fn immutable_pending() {
+    let mut mode = Mode::file();
+
+    mode.set_immutable(true);
+    assert!(mode.pending().is_immutable());
+}
+
+#[test]
+fn trashed() {
+    let mut mode = Mode::directory();
+    assert!(!mode.is_trashed());
+    assert!(!mode.is_internal());
+
+    mode.set_trashed(true);
+    assert!(mode.is_trashed());
+    assert!(mode.is_internal());
+    assert!(mode.is_directory());
+    assert_eq!(mode.words(), (Mode::directory().words().0, 0x0008));
+
+    mode.set_trashed(false);
+    assert!(!mode.is_trashed());
+    assert_eq!(mode.0, Mode::directory().0);
+}
+
+#[test]
+fn from_unix() {
+    let mode = Mode::from_unix(0o100644).unwrap();
+    assert!(mode.is_file());
+    assert_eq!(mode.to_string_rwx(), "-rw-r--r--");
+
+    let mode = Mode::from_unix(0o041777).unwrap();
+    assert!(mode.is_directory());
+    assert_eq!(mode.0, TYPE_DIR | 0x01ff | 0x0004_0000);
+
+    let mode = Mode::from_unix(0o120777).unwrap();
+    assert!(mode.is_symlink());
+
+    assert!(Mode::from_unix(0o010644).unwrap().is_fifo());
+    assert!(Mode::from_unix(0o020644).unwrap().is_char_device());
+    assert!(Mode::from_unix(0o060644).unwrap().is_block_device());
+    assert!(Mode::from_unix(0o140644).unwrap().is_socket());
+
+    for unix in [0o000644, 0o170644] {
+        assert!(Mode::from_unix(unix).is_none(), "{:o}", unix);
+    }
+}
+
+#[test]
+fn to_unix() {
+    assert_eq!(Mode::file().to_unix(), 0o100755);
+    assert_eq!(Mode::directory().to_unix(), 0o040755);
+    assert_eq!(Mode::symlink().to_unix(), 0o120755);
+    assert_eq!(Mode::hardlink().to_unix(), 0o100755);
+    assert_eq!(Mode::job().to_unix(), 0);
+}
+
+#[test]
+fn unix_round_trip() {
+    for perm in 0..0o7777 {
+        for ftype in [
+            0o100000, 0o040000, 0o120000, 0o010000, 0o020000, 0o060000, 0o140000,
+        ] {
+            let unix = ftype | perm;
+
+            assert_eq!(Mode::from_unix(unix).unwrap().to_unix(), unix);
+        }
+    }
+}
+
+#[test]
+fn set_unix_permissions() {
+    let mut mode = Mode::file();
+
+    mode.set_immutable(true);
+    mode.set_unix_permissions(0o046750);
+
+    assert!(mode.is_file());
+    assert!(mode.is_immutable());
+    assert_eq!(mode.to_unix(), 0o106750);
+}
@@ -20,20 +20,39 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
 // IN THE SOFTWARE.
 
+mod multipart;
 #[cfg(test)]
 mod tests;
 
 use log::debug;
-use nuts_container::backend::Backend;
+use nuts_bytes::ToBytes;
+use nuts_container::backend::{Backend, BlockId};
+use sha2::{Digest as _, Sha256};
 use std::cmp;
+use std::collections::HashMap;
 
+use crate::codec::Codec;
+use crate::compression::Compression;
+use crate::entry::immut::InnerEntry;
 use crate::entry::mode::Mode;
-use crate::entry::{populate_mode_api, populate_tstamp_api, Inner};
-use crate::error::ArchiveResult;
-use crate::flush_header;
+use crate::entry::special::SpecialKind;
+use crate::entry::{
+    min_entry_size, parent_name, populate_metadata_api, populate_mode_api, populate_tstamp_api,
+    Inner,
+};
+use crate::error::{ArchiveResult, Error, TargetError};
 use crate::header::Header;
+use crate::manifest::Digest;
 use crate::pager::Pager;
+use crate::savepoint::Savepoint;
+use crate::transform::Encoder;
 use crate::tree::Tree;
+use crate::{flush_header, release_blocks, trash};
+
+pub use multipart::{MultipartEntry, MultipartPart};
+
+/// Default maximum length of a symlink target.
+pub(crate) const MAX_TARGET_LEN: usize = 4096;
 
 macro_rules! impl_new {
     ($type:ident, $mode:ident) => {
@@ -68,6 +87,62 @@ impl<'a, B: Backend> FileBuilder<'a, B> {
 
     populate_mode_api!(mut);
     populate_tstamp_api!(mut);
+    populate_metadata_api!(mut);
+
+    /// Enables the calculation of the SHA-256 digest of the content.
+    ///
+    /// If enabled, the digest is updated while content is appended to the
+    /// entry and is available with [`EntryMut::current_hash()`]. This way
+    /// the content can be verified against a checksum of the source without
+    /// reading it again.
+    pub fn set_hashed(&mut self, hashed: bool) {
+        self.0.hashed = hashed;
+    }
+
+    /// Sets the compression of the content.
+    ///
+    /// The content is compressed while it is appended to the entry and
+    /// decompressed transparently when it is read. The
+    /// [size](crate::FileEntry::size) of the entry is the size of the
+    /// uncompressed content. The content of a compressed entry can only be
+    /// appended: [`EntryMut::write_at()`], [`EntryMut::set_len()`] and
+    /// [`FileBuilder::build_multipart()`] fail with an
+    /// [`Error::Compressed`] error.
+    pub fn set_compression(&mut self, compression: Compression) {
+        self.0.entry.compression = compression;
+    }
+
+    /// Adds a [`Transform`](crate::Transform) of the content.
+    ///
+    /// The transformation with the given `name` must be
+    /// [registered](crate::Archive::register_transform), otherwise
+    /// [`FileBuilder::build()`] fails with an [`Error::UnknownTransform`]
+    /// error. Each chunk of content is compressed first, if a
+    /// [compression](Self::set_compression) is set, and then passed to the
+    /// transformations in the order they were added. Reading the entry
+    /// applies them in reverse order. Like for a compressed entry the
+    /// content can only be appended: [`EntryMut::write_at()`],
+    /// [`EntryMut::set_len()`] and [`FileBuilder::build_multipart()`] fail
+    /// with an [`Error::Transformed`] error.
+    pub fn add_transform<N: AsRef<str>>(&mut self, name: N) {
+        self.0.entry.transforms.push(name.as_ref().to_string());
+    }
+
+    /// Sets the content of the new file entry.
+    ///
+    /// [`FileBuilder::build()`] writes the whole `content` into the entry
+    /// in a single call. Compared to [`EntryMut::write_all()`] the header
+    /// of the archive is flushed only once instead of once per block.
+    ///
+    /// # Errors
+    ///
+    /// If the archive runs out of space, [`FileBuilder::build()`] returns
+    /// an [`Error::Full`] error, which contains the number of bytes, which
+    /// were already written into the entry.
+    pub fn with_content(mut self, content: &[u8]) -> FileBuilder<'a, B> {
+        self.0.content = Some(content.to_vec());
+        self
+    }
 
     /// Finally, creates the new file entry at the end of the archive.
     ///
@@ -77,6 +152,29 @@ impl<'a, B: Backend> FileBuilder<'a, B> {
         self.0.build()
     }
 
+    /// Creates the new file entry at the end of the archive, which receives
+    /// its content of `size` bytes in parts.
+    ///
+    /// See [`MultipartEntry`] for details.
+    ///
+    /// # Errors
+    ///
+    /// If the archive has not enough space for `size` bytes, an
+    /// [`Error::Full`] error is returned. If a
+    /// [compression](Self::set_compression) is set, an
+    /// [`Error::Compressed`] error is returned, if a
+    /// [transformation](Self::add_transform) is added, an
+    /// [`Error::Transformed`] error is returned.
+    pub fn build_multipart(self, size: u64) -> ArchiveResult<MultipartEntry<'a, B>, B> {
+        self.0.entry.check_random_access()?;
+
+        MultipartEntry::create(self.0, size)
+    }
+
+    pub(crate) fn evict_trash(&mut self, savepoints: &'a mut Vec<Savepoint>) {
+        self.0.evict = Some(savepoints);
+    }
+
     fn inner(&self) -> &Inner {
         &self.0.entry
     }
@@ -99,12 +197,34 @@ impl<'a, B: Backend> DirectoryBuilder<'a, B> {
 
     populate_mode_api!(mut);
     populate_tstamp_api!(mut);
+    populate_metadata_api!(mut);
 
     /// Finally, creates the new directory entry at the end of the archive.
+    ///
+    /// A directory has no content, there is no [`EntryMut`] returned which
+    /// could receive any content:
+    ///
+    /// ```rust,compile_fail
+    /// # use nuts_archive::Archive;
+    /// # use nuts_container::container::{Cipher, Container, CreateOptionsBuilder};
+    /// # use nuts_container::memory::MemoryBackend;
+    /// # let options = CreateOptionsBuilder::new(Cipher::None)
+    /// #     .build::<MemoryBackend>()
+    /// #     .unwrap();
+    /// # let container = Container::<MemoryBackend>::create(MemoryBackend::new(), options).unwrap();
+    /// let mut archive = Archive::create(container, false).unwrap();
+    /// let mut entry = archive.append_directory("dir").build().unwrap();
+    ///
+    /// entry.write_all(b"content").unwrap(); // does not compile
+    /// ```
     pub fn build(self) -> ArchiveResult<(), B> {
         self.0.build().map(|_| ())
     }
 
+    pub(crate) fn evict_trash(&mut self, savepoints: &'a mut Vec<Savepoint>) {
+        self.0.evict = Some(savepoints);
+    }
+
     fn inner(&self) -> &Inner {
         &self.0.entry
     }
@@ -120,9 +240,26 @@ impl<'a, B: Backend> DirectoryBuilder<'a, B> {
 /// [`Archive::append_symlink()`](crate::Archive::append_symlink). Calling
 /// [`SymlinkBuilder::build()`] will create the entry at the end of the
 /// archive.
+///
+/// The target of the symlink is passed to
+/// [`Archive::append_symlink()`](crate::Archive::append_symlink), you cannot
+/// create a symlink without a target:
+///
+/// ```rust,compile_fail
+/// # use nuts_archive::Archive;
+/// # use nuts_container::container::{Cipher, Container, CreateOptionsBuilder};
+/// # use nuts_container::memory::MemoryBackend;
+/// # let options = CreateOptionsBuilder::new(Cipher::None)
+/// #     .build::<MemoryBackend>()
+/// #     .unwrap();
+/// # let container = Container::<MemoryBackend>::create(MemoryBackend::new(), options).unwrap();
+/// let mut archive = Archive::create(container, false).unwrap();
+/// archive.append_symlink("link").build().unwrap(); // does not compile
+/// ```
 pub struct SymlinkBuilder<'a, B: Backend> {
     builder: InnerBuilder<'a, B>,
-    target: String,
+    target: Vec<u8>,
+    max_target_len: usize,
 }
 
 impl<'a, B: Backend> SymlinkBuilder<'a, B> {
@@ -132,18 +269,120 @@ impl<'a, B: Backend> SymlinkBuilder<'a, B> {
         header: &'a mut Header,
         tree: &'a mut Tree<B>,
         name: String,
-        target: String,
+        target: Vec<u8>,
+        max_target_len: usize,
     ) -> SymlinkBuilder<'a, B> {
         let builder = InnerBuilder::new(pager, header_id, header, tree, name, Mode::symlink());
 
-        SymlinkBuilder { builder, target }
+        SymlinkBuilder {
+            builder,
+            target,
+            max_target_len,
+        }
     }
 
     populate_mode_api!(mut);
     populate_tstamp_api!(mut);
+    populate_metadata_api!(mut);
 
     /// Finally, creates the new symlink entry at the end of the archive.
+    ///
+    /// # Errors
+    ///
+    /// If the target is too long or contains a NUL byte, an
+    /// [`Error::InvalidTarget`] error is returned and no entry is created.
+    pub fn build(self) -> ArchiveResult<(), B> {
+        if self.target.len() > self.max_target_len {
+            return Err(Error::InvalidTarget(TargetError::TooLong(
+                self.target.len(),
+                self.max_target_len,
+            )));
+        }
+
+        if self.target.contains(&0) {
+            return Err(Error::InvalidTarget(TargetError::Nul));
+        }
+
+        let mut entry = self.builder.build()?;
+
+        entry.write_all(&self.target)?;
+
+        Ok(())
+    }
+
+    pub(crate) fn evict_trash(&mut self, savepoints: &'a mut Vec<Savepoint>) {
+        self.builder.evict = Some(savepoints);
+    }
+
+    fn inner(&self) -> &Inner {
+        &self.builder.entry
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        &mut self.builder.entry
+    }
+}
+
+/// Builder for an new hard link entry.
+///
+/// A `HardLinkBuilder` instance is returned by
+/// [`Archive::append_hardlink()`](crate::Archive::append_hardlink). Calling
+/// [`HardLinkBuilder::build()`] will create the entry at the end of the
+/// archive.
+pub struct HardLinkBuilder<'a, B: Backend> {
+    builder: InnerBuilder<'a, B>,
+    target: String,
+}
+
+impl<'a, B: Backend> HardLinkBuilder<'a, B> {
+    pub(crate) fn new(
+        pager: &'a mut Pager<B>,
+        header_id: &'a B::Id,
+        header: &'a mut Header,
+        tree: &'a mut Tree<B>,
+        name: String,
+        target: String,
+    ) -> HardLinkBuilder<'a, B> {
+        let builder = InnerBuilder::new(pager, header_id, header, tree, name, Mode::hardlink());
+
+        HardLinkBuilder { builder, target }
+    }
+
+    populate_mode_api!(mut);
+    populate_tstamp_api!(mut);
+    populate_metadata_api!(mut);
+
+    /// Finally, creates the new hard link entry at the end of the archive.
+    ///
+    /// # Errors
+    ///
+    /// If the archive has no entry with the name of the target, an
+    /// [`Error::NotFound`] error is returned. If the target is not a file,
+    /// an [`Error::NotFile`] error is returned. No entry is created in both
+    /// cases.
     pub fn build(self) -> ArchiveResult<(), B> {
+        let mut entry_opt = InnerEntry::first(self.builder.pager, self.builder.tree);
+
+        loop {
+            let entry = match entry_opt {
+                Some(Ok(entry)) => entry,
+                Some(Err(err)) => return Err(err),
+                None => return Err(Error::NotFound(self.target)),
+            };
+
+            let inner = entry.inner();
+
+            if !inner.mode.is_internal() && inner.name == self.target {
+                if !inner.mode.is_file() {
+                    return Err(Error::NotFile(self.target));
+                }
+
+                break;
+            }
+
+            entry_opt = entry.next();
+        }
+
         let mut entry = self.builder.build()?;
 
         entry.write_all(self.target.as_bytes())?;
@@ -151,6 +390,10 @@ impl<'a, B: Backend> SymlinkBuilder<'a, B> {
         Ok(())
     }
 
+    pub(crate) fn evict_trash(&mut self, savepoints: &'a mut Vec<Savepoint>) {
+        self.builder.evict = Some(savepoints);
+    }
+
     fn inner(&self) -> &Inner {
         &self.builder.entry
     }
@@ -160,12 +403,66 @@ impl<'a, B: Backend> SymlinkBuilder<'a, B> {
     }
 }
 
-struct InnerBuilder<'a, B: Backend> {
+/// Builder for an new special file entry.
+///
+/// A `SpecialBuilder` instance is returned by
+/// [`Archive::append_special()`](crate::Archive::append_special). Calling
+/// [`SpecialBuilder::build()`] will create the entry at the end of the
+/// archive.
+pub struct SpecialBuilder<'a, B: Backend> {
+    builder: InnerBuilder<'a, B>,
+    kind: SpecialKind,
+}
+
+impl<'a, B: Backend> SpecialBuilder<'a, B> {
+    pub(crate) fn new(
+        pager: &'a mut Pager<B>,
+        header_id: &'a B::Id,
+        header: &'a mut Header,
+        tree: &'a mut Tree<B>,
+        name: String,
+        kind: SpecialKind,
+    ) -> SpecialBuilder<'a, B> {
+        let builder = InnerBuilder::new(pager, header_id, header, tree, name, kind.mode());
+
+        SpecialBuilder { builder, kind }
+    }
+
+    populate_mode_api!(mut);
+    populate_tstamp_api!(mut);
+    populate_metadata_api!(mut);
+
+    /// Finally, creates the new special file entry at the end of the
+    /// archive.
+    pub fn build(self) -> ArchiveResult<(), B> {
+        let content = self.kind.content()?;
+        let mut entry = self.builder.build()?;
+
+        entry.write_all(&content)
+    }
+
+    pub(crate) fn evict_trash(&mut self, savepoints: &'a mut Vec<Savepoint>) {
+        self.builder.evict = Some(savepoints);
+    }
+
+    fn inner(&self) -> &Inner {
+        &self.builder.entry
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        &mut self.builder.entry
+    }
+}
+
+pub(crate) struct InnerBuilder<'a, B: Backend> {
     pager: &'a mut Pager<B>,
     header_id: &'a B::Id,
     header: &'a mut Header,
     tree: &'a mut Tree<B>,
     entry: Inner,
+    hashed: bool,
+    content: Option<Vec<u8>>,
+    evict: Option<&'a mut Vec<Savepoint>>,
 }
 
 impl<'a, B: Backend> InnerBuilder<'a, B> {
@@ -176,35 +473,479 @@ impl<'a, B: Backend> InnerBuilder<'a, B> {
         tree: &'a mut Tree<B>,
         name: String,
         mode: Mode,
+    ) -> InnerBuilder<'a, B> {
+        Self::with_inner(pager, header_id, header, tree, Inner::new(name, mode))
+    }
+
+    pub(crate) fn with_inner(
+        pager: &'a mut Pager<B>,
+        header_id: &'a B::Id,
+        header: &'a mut Header,
+        tree: &'a mut Tree<B>,
+        entry: Inner,
     ) -> InnerBuilder<'a, B> {
         InnerBuilder {
             pager,
             header_id,
             header,
             tree,
-            entry: Inner::new(name, mode),
+            entry,
+            hashed: false,
+            content: None,
+            evict: None,
         }
     }
 
-    fn build(self) -> ArchiveResult<EntryMut<'a, B>, B> {
+    pub(crate) fn build(mut self) -> ArchiveResult<EntryMut<'a, B>, B> {
+        self.header.check_mutable()?;
+
+        // the checksum of the empty content, updated on each write
+        if self.hashed {
+            self.entry.checksum = Some(Sha256::new().finalize().into());
+        }
+
+        check_metadata_size(&mut self.entry, self.pager.codec(), self.pager.block_size())?;
+
+        let encoder = Encoder::create(&self.entry, self.pager.transforms())?;
+        let head_blocks = self.entry.head_blocks() as u64;
+
+        // the entry block and the name blocks are aquired together
+        while self.tree.nblocks() + head_blocks > self.tree.capacity(self.pager) {
+            let evicted = match self.evict.as_mut() {
+                Some(savepoints) => {
+                    let end = self.tree.nblocks() as usize;
+
+                    trash::evict(
+                        self.pager,
+                        self.header_id,
+                        self.header,
+                        self.tree,
+                        savepoints,
+                        end,
+                    )?
+                }
+                None => None,
+            };
+
+            if evicted.is_none() {
+                return Err(Error::Full(0));
+            }
+        }
+
+        // parents and name index of a deferred entry are updated on commit
+        let deferred = self.header.is_deferred();
+
+        let idx = self.tree.nblocks() as usize;
         let id = self.tree.aquire(self.pager)?.clone();
 
+        #[cfg(feature = "trace")]
+        self.pager.trace(idx, idx, &id);
+
+        for _ in 1..head_blocks {
+            self.tree.aquire(self.pager)?;
+        }
+
         self.entry.flush(self.pager, &id)?;
+        self.entry.flush_name(self.pager, self.tree, idx)?;
+
+        if self.entry.mode.is_directory() && !self.entry.mode.is_internal() {
+            self.tree.add_dir(&self.entry.name, idx);
+        }
+
+        if self.entry.mode.is_internal() {
+            self.header.touch();
+        } else {
+            // the entry exists, before its parent and the name index refer
+            // to it
+            if !deferred {
+                inc_parent_children(self.pager, self.tree, &self.entry.name)?;
+                index_name(self.pager, self.tree, &self.entry.name, idx)?;
+            }
+
+            self.header.inc_files();
+        }
 
-        self.header.inc_files();
         flush_header(self.pager, self.header_id, self.header, self.tree)?;
 
-        Ok(EntryMut::new(
+        let mut entry = EntryMut::new(
             self.pager,
             self.header_id,
             self.header,
             self.tree,
             self.entry,
+            idx,
             id,
-        ))
+        );
+
+        entry.sha = self.hashed.then(Sha256::new);
+        entry.encoder = encoder;
+        entry.evict = self.evict;
+
+        if let Some(content) = self.content {
+            entry.write_batch(&content)?;
+        }
+
+        Ok(entry)
     }
 }
 
+/// Cuts off the content of the entry stored at `idx` after `len` bytes.
+///
+/// The content blocks, which are not needed anymore, are removed from the
+/// tree and returned, the caller is responsible to release them. The unused
+/// part of the new last block is zeroed, so the entry can be extended
+/// later.
+pub(crate) fn shrink_content<B: Backend>(
+    pager: &mut Pager<B>,
+    tree: &mut Tree<B>,
+    inner: &mut Inner,
+    idx: usize,
+    len: u64,
+) -> ArchiveResult<Vec<B::Id>, B> {
+    let block_size = pager.block_size() as u64;
+    let old_blocks = inner.size.div_ceil(block_size) as usize;
+    let new_blocks = len.div_ceil(block_size) as usize;
+
+    let content_idx = idx + inner.head_blocks();
+    let removed = tree.remove(pager, content_idx + new_blocks, old_blocks - new_blocks)?;
+
+    if !len.is_multiple_of(block_size) {
+        let last_idx = content_idx + new_blocks - 1;
+
+        let in_tree = last_idx < tree.nblocks() as usize;
+
+        match tree.lookup(pager, last_idx) {
+            Some(result) => {
+                let id = result?.clone();
+                let mut block = pager.read_buf_raw(&id)?.to_vec();
+
+                block[(len % block_size) as usize..].fill(0);
+                pager.write(&id, &block)?;
+            }
+            None if in_tree => {
+                // the last block is partially filled and cannot be a hole
+                let id = tree.fill_hole(pager, last_idx)?.clone();
+                pager.write(&id, &vec![0; block_size as usize])?;
+            }
+            None => return Err(Error::UnexpectedEof),
+        }
+    }
+
+    let id = match tree.lookup(pager, idx) {
+        Some(result) => result?.clone(),
+        None => return Err(Error::UnexpectedEof),
+    };
+
+    inner.size = len;
+    inner.checksum = None;
+    inner.flush(pager, &id)?;
+
+    debug!(
+        "entry {} shrinked to {} bytes, {} blocks released",
+        inner.name,
+        len,
+        removed.len()
+    );
+
+    Ok(removed)
+}
+
+/// Extends the content of the entry `inner` stored at `idx` with zeros
+/// until it has `len` bytes.
+///
+/// The missing content blocks are inserted as holes behind the content,
+/// the entries behind the entry are moved to the back. Unlike
+/// [`EntryMut::set_len()`] this works for any entry of the archive.
+pub(crate) fn grow_content<B: Backend>(
+    pager: &mut Pager<B>,
+    tree: &mut Tree<B>,
+    inner: &mut Inner,
+    idx: usize,
+    len: u64,
+) -> ArchiveResult<(), B> {
+    let block_size = pager.block_size() as u64;
+    let old_blocks = inner.size.div_ceil(block_size) as usize;
+    let new_blocks = len.div_ceil(block_size) as usize;
+    let content_idx = idx + inner.head_blocks();
+
+    if tree.nblocks() + (new_blocks - old_blocks) as u64 > tree.capacity(pager) {
+        return Err(Error::Full(0));
+    }
+
+    if !inner.size.is_multiple_of(block_size) {
+        // zeros behind the old end, a hole is already zero
+        if let Some(result) = tree.lookup(pager, content_idx + old_blocks - 1) {
+            let id = result?.clone();
+            let mut block = pager.read_buf_raw(&id)?.to_vec();
+
+            block[(inner.size % block_size) as usize..].fill(0);
+            pager.write(&id, &block)?;
+        }
+    }
+
+    if new_blocks > old_blocks {
+        let start = content_idx + old_blocks;
+        let nblocks = tree.nblocks() as usize;
+        let mut ids = vec![B::Id::null(); new_blocks - old_blocks];
+
+        for n in start..nblocks {
+            match tree.lookup(pager, n) {
+                Some(result) => ids.push(result?.clone()),
+                None => ids.push(B::Id::null()), // a hole of a sparse file
+            }
+        }
+
+        for n in 0..new_blocks - old_blocks {
+            if let Err(err) = tree.aquire_hole(pager) {
+                // the holes aquired so far are not referenced yet
+                tree.remove(pager, nblocks, n)?;
+                return Err(err);
+            }
+        }
+
+        tree.replace(pager, start, ids)?;
+    }
+
+    let id = match tree.lookup(pager, idx) {
+        Some(result) => result?.clone(),
+        None => return Err(Error::UnexpectedEof),
+    };
+
+    inner.size = len;
+    inner.checksum = None;
+    inner.flush(pager, &id)?;
+
+    debug!(
+        "entry {} extended to {} bytes, {} holes inserted",
+        inner.name,
+        len,
+        new_blocks - old_blocks
+    );
+
+    Ok(())
+}
+
+/// Looks up the last block of an entry at `idx`.
+///
+/// The last block is a hole, if the entry ends with a hole of a sparse
+/// file. A null id is returned in this case, which never matches a block
+/// of the archive.
+fn lookup_last<B: Backend>(
+    pager: &mut Pager<B>,
+    tree: &mut Tree<B>,
+    idx: usize,
+) -> Option<ArchiveResult<B::Id, B>> {
+    let in_tree = idx < tree.nblocks() as usize;
+
+    match tree.lookup(pager, idx) {
+        Some(result) => Some(result.cloned()),
+        None if in_tree => Some(Ok(B::Id::null())),
+        None => None,
+    }
+}
+
+/// Changes the number of continuation blocks of the name of the entry
+/// stored at `idx` from `old` to `new`.
+///
+/// Additional blocks are inserted behind the existing continuation blocks,
+/// the blocks behind are moved to the back. Blocks, which are not needed
+/// anymore, are removed from the tree and returned, the caller is
+/// responsible to release them.
+pub(crate) fn resize_name<B: Backend>(
+    pager: &mut Pager<B>,
+    tree: &mut Tree<B>,
+    idx: usize,
+    old: u32,
+    new: u32,
+) -> ArchiveResult<Vec<B::Id>, B> {
+    let (old, new) = (old as usize, new as usize);
+
+    if new <= old {
+        return tree.remove(pager, idx + 1 + new, old - new);
+    }
+
+    if tree.nblocks() + (new - old) as u64 > tree.capacity(pager) {
+        return Err(Error::Full(0));
+    }
+
+    let start = idx + 1 + old;
+    let nblocks = tree.nblocks() as usize;
+    let mut ids = Vec::with_capacity(nblocks - start + new - old);
+
+    for _ in old..new {
+        ids.push(tree.aquire(pager)?.clone());
+    }
+
+    for n in start..nblocks {
+        match tree.lookup(pager, n) {
+            Some(result) => ids.push(result?.clone()),
+            None => return Err(Error::UnexpectedEof),
+        }
+    }
+
+    tree.replace(pager, start, ids)?;
+
+    debug!(
+        "name of entry at {} resized from {} to {} blocks",
+        idx, old, new
+    );
+
+    Ok(vec![])
+}
+
+/// Checks whether the metadata of the entry fits into a single block.
+///
+/// If the metadata is too large, the name is moved into continuation
+/// blocks behind the entry block, `inner.name_blocks` is updated
+/// accordingly.
+pub(crate) fn check_metadata_size<B: Backend>(
+    inner: &mut Inner,
+    codec: Codec,
+    block_size: u32,
+) -> ArchiveResult<(), B> {
+    inner.name_blocks = 0;
+
+    if inner.to_block(codec)?.len() > block_size as usize {
+        let name_len = inner.name.to_bytes(&mut vec![])?;
+        inner.name_blocks = name_len.div_ceil(block_size as usize) as u32;
+    }
+
+    let len = inner.to_block(codec)?.len();
+
+    if len > block_size as usize {
+        let max_name_len = block_size as usize - (min_entry_size() - 1);
+
+        if inner.name.len() > max_name_len {
+            return Err(Error::NameTooLong(max_name_len));
+        }
+
+        return Err(Error::MetadataTooLarge(len, block_size));
+    }
+
+    Ok(())
+}
+
+/// Records the entry `name` stored at `idx` in the name index of the
+/// archive. Nothing happens, if the archive has no name index.
+pub(crate) fn index_name<B: Backend>(
+    pager: &mut Pager<B>,
+    tree: &mut Tree<B>,
+    name: &str,
+    idx: usize,
+) -> ArchiveResult<(), B> {
+    match tree.names_mut(pager)? {
+        Some(names) => names.insert(pager, name, idx as u64),
+        None => Ok(()),
+    }
+}
+
+/// Increments the child count of the directory, which contains the entry
+/// with the given `name`.
+///
+/// If the directory was appended several times, the last one wins.
+pub(crate) fn inc_parent_children<B: Backend>(
+    pager: &mut Pager<B>,
+    tree: &mut Tree<B>,
+    name: &str,
+) -> ArchiveResult<(), B> {
+    update_parent_children(pager, tree, name, |children| children + 1)
+}
+
+/// Decrements the child count of the directory, which contains the entry
+/// with the given `name`.
+pub(crate) fn dec_parent_children<B: Backend>(
+    pager: &mut Pager<B>,
+    tree: &mut Tree<B>,
+    name: &str,
+) -> ArchiveResult<(), B> {
+    update_parent_children(pager, tree, name, |children| children.saturating_sub(1))
+}
+
+fn update_parent_children<B: Backend, F: FnOnce(u64) -> u64>(
+    pager: &mut Pager<B>,
+    tree: &mut Tree<B>,
+    name: &str,
+    f: F,
+) -> ArchiveResult<(), B> {
+    let parent = match parent_name(name) {
+        Some(parent) => parent,
+        None => return Ok(()),
+    };
+
+    let (id, mut inner) = match find_dir(pager, tree, parent)? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+
+    inner.children = f(inner.children);
+    inner.flush(pager, &id)
+}
+
+/// Searches for the last directory entry with the given `name`.
+///
+/// The positions of the directories are loaded with a single scan of the
+/// archive and kept in the tree. A position, which does not refer to the
+/// directory anymore, is detected and the positions are loaded again.
+fn find_dir<B: Backend>(
+    pager: &mut Pager<B>,
+    tree: &mut Tree<B>,
+    name: &str,
+) -> ArchiveResult<Option<(B::Id, Inner)>, B> {
+    for _ in 0..2 {
+        if tree.dirs().is_none() {
+            let dirs = scan_dirs(pager, tree)?;
+            tree.set_dirs(dirs);
+        }
+
+        let idx = match tree.dirs().and_then(|dirs| dirs.get(name)) {
+            Some(idx) => *idx,
+            None => return Ok(None),
+        };
+
+        if let Some(result) = tree.lookup(pager, idx) {
+            let id = result?.clone();
+            let mut inner = Inner::load(pager, &id)?;
+
+            inner.load_name(pager, tree, idx)?;
+
+            if inner.mode.is_directory()
+                && !inner.mode.is_internal()
+                && inner.name.trim_end_matches('/') == name
+            {
+                return Ok(Some((id, inner)));
+            }
+        }
+
+        debug!("position {} of directory {} is outdated", idx, name);
+        tree.forget_dirs();
+    }
+
+    Ok(None)
+}
+
+fn scan_dirs<B: Backend>(
+    pager: &mut Pager<B>,
+    tree: &mut Tree<B>,
+) -> ArchiveResult<HashMap<String, usize>, B> {
+    let mut dirs = HashMap::new();
+    let mut entry_opt = InnerEntry::first(pager, tree);
+
+    while let Some(result) = entry_opt {
+        let entry = result?;
+        let inner = entry.inner();
+
+        if inner.mode.is_directory() && !inner.mode.is_internal() {
+            dirs.insert(inner.name.trim_end_matches('/').to_string(), entry.idx());
+        }
+
+        entry_opt = entry.next();
+    }
+
+    debug!("{} directories loaded", dirs.len());
+
+    Ok(dirs)
+}
+
 /// A mutable entry of the archive.
 ///
 /// An `EntryMut` instance is returned by [`FileBuilder::build()`] and gives
@@ -215,9 +956,14 @@ pub struct EntryMut<'a, B: Backend> {
     header: &'a mut Header,
     tree: &'a mut Tree<B>,
     entry: Inner,
+    idx: usize,
     first: B::Id,
     last: B::Id,
     cache: Vec<u8>,
+    generation: u64,
+    sha: Option<Sha256>,
+    encoder: Option<Encoder>,
+    evict: Option<&'a mut Vec<Savepoint>>,
 }
 
 impl<'a, B: Backend> EntryMut<'a, B> {
@@ -227,32 +973,361 @@ impl<'a, B: Backend> EntryMut<'a, B> {
         header: &'a mut Header,
         tree: &'a mut Tree<B>,
         entry: Inner,
+        idx: usize,
         id: B::Id,
     ) -> EntryMut<'a, B> {
+        let generation = pager.generation();
+
         EntryMut {
             pager,
             header_id,
             header,
             tree,
             entry,
+            idx,
             first: id.clone(),
             last: id,
             cache: vec![],
+            generation,
+            sha: None,
+            encoder: None,
+            evict: None,
         }
     }
 
+    /// Reopens the entry stored at `idx`, the next write continues at the
+    /// end of its content.
+    pub(crate) fn reopen(
+        pager: &'a mut Pager<B>,
+        header_id: &'a B::Id,
+        header: &'a mut Header,
+        tree: &'a mut Tree<B>,
+        entry: Inner,
+        idx: usize,
+    ) -> ArchiveResult<EntryMut<'a, B>, B> {
+        let encoder = Encoder::create(&entry, pager.transforms())?;
+        let block_size = pager.block_size() as u64;
+        let nblocks = entry.size.div_ceil(block_size) as usize;
+
+        let first = match tree.lookup(pager, idx) {
+            Some(result) => result?.clone(),
+            None => return Err(Error::UnexpectedEof),
+        };
+        let last = match lookup_last(pager, tree, idx + entry.head_blocks() - 1 + nblocks) {
+            Some(result) => result?,
+            None => return Err(Error::UnexpectedEof),
+        };
+
+        let mut entry = EntryMut::new(pager, header_id, header, tree, entry, idx, first);
+
+        // the digest cannot be continued, appended content invalidates it
+        entry.entry.checksum = None;
+        entry.encoder = encoder;
+
+        if !entry.entry.size.is_multiple_of(block_size) {
+            entry.cache = entry.pager.read_buf_raw(&last)?.to_vec();
+        }
+
+        entry.last = last;
+
+        Ok(entry)
+    }
+
     /// Appends some content from `buf` at the end of the entry.
     ///
     /// Note that the entire buffer is not necessarily written. The method
     /// returns the number of bytes that were actually written.
+    ///
+    /// # Errors
+    ///
+    /// If the archive was modified by someone else since the entry was
+    /// created, an [`Error::Stale`] error is returned. If a written block
+    /// fails the [write verification](crate::Archive::set_write_verification),
+    /// an [`Error::WriteMismatch`] error with the name of the entry is
+    /// returned.
     pub fn write(&mut self, buf: &[u8]) -> ArchiveResult<usize, B> {
+        self.write_chunk(buf).map_err(|err| self.named_err(err))
+    }
+
+    /// Adds the name of the entry to a failed write verification.
+    fn named_err(&self, err: Error<B>) -> Error<B> {
+        match err {
+            Error::WriteMismatch(id, None) => {
+                Error::WriteMismatch(id, Some(self.entry.name.clone()))
+            }
+            err => err,
+        }
+    }
+
+    fn write_chunk(&mut self, buf: &[u8]) -> ArchiveResult<usize, B> {
+        if self.generation != self.pager.generation() {
+            return Err(Error::Stale);
+        }
+
+        let nbytes = self.fill(buf)?;
+
+        self.entry.flush(self.pager, &self.first)?;
+        flush_header(self.pager, self.header_id, self.header, self.tree)?;
+
+        self.generation = self.pager.generation();
+
+        Ok(nbytes)
+    }
+
+    /// Appends a whole block of content at the end of the entry.
+    ///
+    /// Unlike [`EntryMut::write()`] the block is written directly into a
+    /// new content block, it is not copied into the internal cache first.
+    /// Use it to append pre-chunked content efficiently. `block` must have
+    /// the block size of the archive and the entry must end at a block
+    /// boundary, which is the case as long as only whole blocks are
+    /// appended. For a [compressed](FileBuilder::set_compression) or
+    /// [transformed](FileBuilder::add_transform) entry the block is encoded
+    /// and appended like with [`EntryMut::write()`].
+    ///
+    /// # Errors
+    ///
+    /// If `block` has not the size of a block or the entry does not end at
+    /// a block boundary, an [`Error::Unaligned`] error is returned.
+    /// Otherwise the same errors as for [`EntryMut::write()`] are
+    /// returned.
+    pub fn write_block(&mut self, block: &[u8]) -> ArchiveResult<(), B> {
+        self.write_aligned(block).map_err(|err| self.named_err(err))
+    }
+
+    fn write_aligned(&mut self, block: &[u8]) -> ArchiveResult<(), B> {
+        if self.encoder.is_some() {
+            return self.write_chunk(block).map(|_| ());
+        }
+
+        if self.generation != self.pager.generation() {
+            return Err(Error::Stale);
+        }
+
+        let block_size = self.pager.block_size() as u64;
+
+        if block.len() as u64 != block_size || !self.entry.size.is_multiple_of(block_size) {
+            return Err(Error::Unaligned(block.len(), self.entry.size));
+        }
+
+        self.last = self.aquire_content()?;
+
+        // the block is not cached, overwrite() reads it from the container
+        self.cache.clear();
+        self.pager.write(&self.last, block)?;
+
+        if let Some(sha) = self.sha.as_mut() {
+            sha.update(block);
+            self.entry.checksum = Some(sha.clone().finalize().into());
+        }
+
+        self.entry.size += block_size;
+
+        self.entry.flush(self.pager, &self.first)?;
+        flush_header(self.pager, self.header_id, self.header, self.tree)?;
+
+        self.generation = self.pager.generation();
+
+        Ok(())
+    }
+
+    /// Appends `len` zeros at the end of the entry.
+    ///
+    /// Whole blocks of zeros are not stored, they are recorded as holes in
+    /// the archive, which do not occupy any block of the container. Reading
+    /// a hole returns zeros, overwriting it with
+    /// [`EntryMut::write_at()`] allocates the block. Zeros, which only fill
+    /// a part of a block, are stored like any other content. The content of
+    /// a [compressed](FileBuilder::set_compression) or
+    /// [transformed](FileBuilder::add_transform) entry has no holes, the
+    /// zeros are encoded and appended.
+    ///
+    /// Use it to archive sparse files like disk images, where large ranges
+    /// of the content are never written.
+    ///
+    /// # Errors
+    ///
+    /// A hole occupies a slot of the archive like a content block. If the
+    /// archive runs out of slots, an [`Error::Full`] error is returned,
+    /// which contains the number of zeros, which were already appended.
+    /// Otherwise the same errors as for [`EntryMut::write()`] are returned.
+    pub fn write_zeroes(&mut self, len: u64) -> ArchiveResult<(), B> {
+        self.append_zeroes(len).map_err(|err| self.named_err(err))
+    }
+
+    fn append_zeroes(&mut self, len: u64) -> ArchiveResult<(), B> {
+        if self.generation != self.pager.generation() {
+            return Err(Error::Stale);
+        }
+
+        let block_size = self.pager.block_size() as u64;
+        let zeros = vec![0; block_size as usize];
+        let mut remaining = len;
+        let mut nbytes = 0;
+
+        let result = loop {
+            if remaining == 0 {
+                break Ok(());
+            }
+
+            let aligned = self.entry.size.is_multiple_of(block_size);
+
+            let step = if self.encoder.is_none() && aligned && remaining >= block_size {
+                self.append_hole()
+            } else {
+                self.fill(&zeros[..cmp::min(remaining, block_size) as usize])
+            };
+
+            match step {
+                Ok(n) => {
+                    nbytes += n;
+                    remaining -= n as u64;
+                }
+                Err(Error::Full(_)) => break Err(Error::Full(nbytes)),
+                Err(err) => break Err(err),
+            }
+        };
+
+        // the zeros appended so far are kept, even on error
+        self.entry.flush(self.pager, &self.first)?;
+        flush_header(self.pager, self.header_id, self.header, self.tree)?;
+
+        self.generation = self.pager.generation();
+
+        result
+    }
+
+    /// Appends a hole of a whole block at the end of the entry. The entry
+    /// and the header are not flushed.
+    fn append_hole(&mut self) -> ArchiveResult<usize, B> {
+        let block_size = self.pager.block_size() as usize;
+
+        while let Err(err) = self.tree.aquire_hole(self.pager) {
+            if !self.evict_trash(&err)? {
+                return Err(err);
+            }
+        }
+
+        debug!("hole aquired for entry {}", self.idx);
+
+        self.last = B::Id::null();
+        self.cache.clear();
+
+        if let Some(sha) = self.sha.as_mut() {
+            sha.update(vec![0; block_size]);
+            self.entry.checksum = Some(sha.clone().finalize().into());
+        }
+
+        self.entry.size += block_size as u64;
+
+        Ok(block_size)
+    }
+
+    /// Aquires a new content block at the end of the archive.
+    fn aquire_content(&mut self) -> ArchiveResult<B::Id, B> {
+        let id = loop {
+            match self.tree.aquire_content(self.pager) {
+                Ok(id) => break id.clone(),
+                Err(err) => {
+                    if !self.evict_trash(&err)? {
+                        return Err(err);
+                    }
+                }
+            }
+        };
+
+        debug!("block aquired for entry {}: {}", self.idx, id);
+
+        #[cfg(feature = "trace")]
+        self.pager
+            .trace(self.idx, self.tree.nblocks() as usize - 1, &id);
+
+        Ok(id)
+    }
+
+    /// Evicts the oldest entry of the trash, if the archive is full and
+    /// [trashed entries can be evicted](crate::Archive::set_trash).
+    ///
+    /// Returns `true`, if the aquisition, which failed with `err`, should be
+    /// retried. The evicted entry is located in front of this entry, which
+    /// moves to the front.
+    fn evict_trash(&mut self, err: &Error<B>) -> ArchiveResult<bool, B> {
+        let savepoints = match (err, self.evict.as_mut()) {
+            (Error::Full(_), Some(savepoints)) => savepoints,
+            _ => return Ok(false),
+        };
+
+        // the archive is scanned for the trash, the entry must cover its
+        // blocks aquired so far
+        self.entry.flush(self.pager, &self.first)?;
+
+        let evicted = trash::evict(
+            self.pager,
+            self.header_id,
+            self.header,
+            self.tree,
+            savepoints,
+            self.idx,
+        )?;
+
+        match evicted {
+            Some(nslots) => {
+                self.idx -= nslots;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Appends the whole content of `buf` like [`EntryMut::write_all()`],
+    /// but flushes the entry and the header only once at the end.
+    pub(crate) fn write_batch(&mut self, buf: &[u8]) -> ArchiveResult<(), B> {
+        if self.generation != self.pager.generation() {
+            return Err(Error::Stale);
+        }
+
+        let mut nbytes = 0;
+
+        let result = loop {
+            if nbytes == buf.len() {
+                break Ok(());
+            }
+
+            match self.fill(&buf[nbytes..]) {
+                Ok(n) => nbytes += n,
+                Err(Error::Full(_)) => break Err(Error::Full(nbytes)),
+                Err(err) => break Err(self.named_err(err)),
+            }
+        };
+
+        // the blocks written so far are kept, even on error
+        self.entry.flush(self.pager, &self.first)?;
+        flush_header(self.pager, self.header_id, self.header, self.tree)?;
+
+        self.generation = self.pager.generation();
+
+        result
+    }
+
+    /// Appends some content from `buf`, which is encoded, if enabled for
+    /// the entry. The entry and the header are not flushed.
+    fn fill(&mut self, buf: &[u8]) -> ArchiveResult<usize, B> {
+        if self.encoder.is_some() {
+            self.fill_encoded(buf)
+        } else {
+            self.fill_block(buf)
+        }
+    }
+
+    /// Fills the last content block of the entry with `buf`, a new block is
+    /// aquired if the last one is full. The entry and the header are not
+    /// flushed.
+    fn fill_block(&mut self, buf: &[u8]) -> ArchiveResult<usize, B> {
         let block_size = self.pager.block_size() as u64;
         let pos = (self.entry.size % block_size) as usize;
 
         let available = if pos == 0 {
-            self.last = self.tree.aquire(self.pager)?.clone();
-
-            debug!("block aquired: {}", self.last);
+            self.last = self.aquire_content()?;
 
             self.cache.clear();
             self.cache.resize(block_size as usize, 0);
@@ -274,18 +1349,247 @@ impl<'a, B: Backend> EntryMut<'a, B> {
         self.cache[pos..pos + nbytes].copy_from_slice(&buf[..nbytes]);
         self.pager.write(&self.last, &self.cache)?;
 
+        // the digest of an encoded entry covers the content before encoding
+        let encoded = self.encoder.is_some();
+
+        if let Some(sha) = self.sha.as_mut().filter(|_| !encoded) {
+            sha.update(&buf[..nbytes]);
+            self.entry.checksum = Some(sha.clone().finalize().into());
+        }
+
         self.entry.size += nbytes as u64;
-        self.entry.flush(self.pager, &self.first)?;
-        flush_header(self.pager, self.header_id, self.header, self.tree)?;
 
         Ok(nbytes)
     }
 
-    pub fn write_all(&mut self, mut buf: &[u8]) -> ArchiveResult<(), B> {
-        while !buf.is_empty() {
-            let n = self.write(buf)?;
+    /// Encodes the whole `buf` and appends the encoded bytes at the end of
+    /// the entry. The entry and the header are not flushed.
+    ///
+    /// If the archive runs out of space, the bytes appended so far are cut
+    /// off again, so the encoded content stays readable.
+    fn fill_encoded(&mut self, buf: &[u8]) -> ArchiveResult<usize, B> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let encoded = match self.encoder.as_mut() {
+            Some(encoder) => encoder.encode(buf)?,
+            None => return self.fill_block(buf),
+        };
+
+        let size = self.entry.size;
+        let mut nbytes = 0;
+
+        while nbytes < encoded.len() {
+            match self.fill_block(&encoded[nbytes..]) {
+                Ok(n) => nbytes += n,
+                Err(err) => {
+                    // a new compressor does not refer to the cut off bytes
+                    if let Some(encoder) = self.encoder.as_mut() {
+                        encoder.restart()?;
+                    }
+
+                    self.cut_off(size)?;
+
+                    self.entry.checksum = self.current_hash();
+                    self.entry.flush(self.pager, &self.first)?;
+
+                    return Err(err);
+                }
+            }
+        }
+
+        if let Some(sha) = self.sha.as_mut() {
+            sha.update(buf);
+            self.entry.checksum = Some(sha.clone().finalize().into());
+        }
+
+        self.entry.raw_size += buf.len() as u64;
+
+        debug!(
+            "encoded {} bytes into {} bytes for entry {}",
+            buf.len(),
+            encoded.len(),
+            self.idx
+        );
+
+        Ok(buf.len())
+    }
+
+    /// Overwrites the content of the entry at position `offset` with `buf`.
+    ///
+    /// The size of the entry is not changed, bytes behind the end of the
+    /// entry are not written. Use [`EntryMut::write()`] to append content.
+    /// The affected content blocks are loaded, modified and written back.
+    ///
+    /// The methods returns the number of bytes actually written, which is
+    /// less than [`buf.len()`] if the end of the entry is reached. If
+    /// `offset` is located behind the end of the entry, `0` is returned.
+    ///
+    /// Overwriting content stops the calculation of the
+    /// [running digest](Self::current_hash).
+    ///
+    /// # Errors
+    ///
+    /// If the archive was modified by someone else since the entry was
+    /// created, an [`Error::Stale`] error is returned. If a written block
+    /// fails the [write verification](crate::Archive::set_write_verification),
+    /// an [`Error::WriteMismatch`] error with the name of the entry is
+    /// returned.
+    ///
+    /// [`buf.len()`]: slice::len
+    pub fn write_at(&mut self, offset: u64, buf: &[u8]) -> ArchiveResult<usize, B> {
+        self.overwrite(offset, buf)
+            .map_err(|err| self.named_err(err))
+    }
+
+    fn overwrite(&mut self, offset: u64, buf: &[u8]) -> ArchiveResult<usize, B> {
+        if self.generation != self.pager.generation() {
+            return Err(Error::Stale);
+        }
+
+        self.entry.check_random_access()?;
+
+        let block_size = self.pager.block_size() as u64;
+        let end = cmp::min(self.entry.size, offset.saturating_add(buf.len() as u64));
+        let mut pos = offset;
+
+        while pos < end {
+            let idx = self.idx + self.entry.head_blocks() + (pos / block_size) as usize;
+            let start = (pos % block_size) as usize;
+            let n = cmp::min(block_size - start as u64, end - pos) as usize;
+            let nbytes = (pos - offset) as usize;
+
+            let in_tree = idx < self.tree.nblocks() as usize;
+
+            let id = match self.tree.lookup(self.pager, idx) {
+                Some(result) => result?.clone(),
+                None if in_tree => {
+                    let id = self.tree.fill_hole(self.pager, idx)?.clone();
+
+                    debug!("hole at {} of entry {} filled: {}", idx, self.idx, id);
+
+                    self.pager.write(&id, &vec![0; block_size as usize])?;
+                    id
+                }
+                None => return Err(Error::UnexpectedEof),
+            };
+
+            if id == self.last && !self.cache.is_empty() {
+                self.cache[start..start + n].copy_from_slice(&buf[nbytes..nbytes + n]);
+                self.pager.write(&id, &self.cache)?;
+            } else {
+                let mut block = self.pager.read_buf_raw(&id)?.to_vec();
+
+                block[start..start + n].copy_from_slice(&buf[nbytes..nbytes + n]);
+                self.pager.write(&id, &block)?;
+            }
+
+            pos += n as u64;
+        }
+
+        if pos > offset {
+            self.sha = None;
+
+            if self.entry.checksum.take().is_some() {
+                self.entry.flush(self.pager, &self.first)?;
+            }
+
+            flush_header(self.pager, self.header_id, self.header, self.tree)?;
+            self.generation = self.pager.generation();
+        }
+
+        Ok(pos.saturating_sub(offset) as usize)
+    }
+
+    /// Changes the size of the entry to `len` bytes.
+    ///
+    /// If `len` is larger than the current size, the content is extended
+    /// with zeros like [`EntryMut::write_zeroes()`] does it. If `len` is
+    /// smaller, the content is cut off and the content blocks, which are not
+    /// needed anymore, are put into the list of free blocks. Further writes
+    /// continue at the new end of the entry.
+    ///
+    /// Cutting off content stops the calculation of the
+    /// [running digest](Self::current_hash).
+    ///
+    /// # Errors
+    ///
+    /// If the archive runs out of space while extending the entry, an
+    /// [`Error::Full`] error is returned, which contains the number of zeros,
+    /// which were already appended.
+    pub fn set_len(&mut self, len: u64) -> ArchiveResult<(), B> {
+        if self.generation != self.pager.generation() {
+            return Err(Error::Stale);
+        }
+
+        self.entry.check_random_access()?;
+
+        if len >= self.entry.size {
+            if self.sha.is_none() {
+                self.entry.checksum = None;
+            }
+
+            return self.write_zeroes(len - self.entry.size);
+        }
+
+        self.sha = None;
+        self.cut_off(len)
+    }
+
+    /// Cuts off the stored content of the entry after `len` bytes.
+    fn cut_off(&mut self, len: u64) -> ArchiveResult<(), B> {
+        let removed = shrink_content(self.pager, self.tree, &mut self.entry, self.idx, len)?;
+        let block_size = self.pager.block_size() as u64;
+
+        let last_idx = self.idx + self.entry.head_blocks() - 1 + len.div_ceil(block_size) as usize;
+
+        self.last = match lookup_last(self.pager, self.tree, last_idx) {
+            Some(result) => result?,
+            None => return Err(Error::UnexpectedEof),
+        };
+
+        self.cache = if len.is_multiple_of(block_size) {
+            vec![]
+        } else {
+            self.pager.read_buf_raw(&self.last)?.to_vec()
+        };
+
+        self.header.touch();
+
+        flush_header(self.pager, self.header_id, self.header, self.tree)?;
+        release_blocks(self.pager, self.tree, removed)?;
+
+        self.generation = self.pager.generation();
+
+        Ok(())
+    }
+
+    /// Returns the SHA-256 digest of the content appended so far.
+    ///
+    /// The digest is only calculated, if enabled with
+    /// [`FileBuilder::set_hashed()`], otherwise [`None`] is returned.
+    pub fn current_hash(&self) -> Option<Digest> {
+        self.sha.as_ref().map(|sha| sha.clone().finalize().into())
+    }
+
+    /// Appends the whole content of `buf` at the end of the entry.
+    ///
+    /// # Errors
+    ///
+    /// If the archive runs out of space, an [`Error::Full`] error is
+    /// returned, which contains the number of bytes from `buf`, which were
+    /// already appended. The entry stays usable, you can continue with the
+    /// remaining bytes once space becomes available.
+    pub fn write_all(&mut self, buf: &[u8]) -> ArchiveResult<(), B> {
+        let mut nbytes = 0;
 
-            buf = &buf[n..]
+        while nbytes < buf.len() {
+            match self.write(&buf[nbytes..]) {
+                Ok(n) => nbytes += n,
+                Err(Error::Full(_)) => return Err(Error::Full(nbytes)),
+                Err(err) => return Err(err),
+            }
         }
 
         Ok(())
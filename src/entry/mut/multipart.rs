@@ -0,0 +1,316 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use log::debug;
+use nuts_container::backend::Backend;
+use sha2::{Digest as _, Sha256};
+use std::cmp;
+
+use crate::entry::mode::Mode;
+use crate::entry::r#mut::{inc_parent_children, index_name, EntryMut, InnerBuilder};
+use crate::entry::Inner;
+use crate::error::{ArchiveResult, Error, MultipartError};
+use crate::flush_header;
+use crate::header::Header;
+use crate::manifest::Digest;
+use crate::pager::Pager;
+use crate::tree::Tree;
+
+/// A part of a [`MultipartEntry`]: a buffer tagged with its position in
+/// the entry.
+///
+/// A part owns its buffer, so it can be created by another thread and
+/// handed over to the owner of the entry, e.g. through a channel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultipartPart {
+    offset: u64,
+    buf: Vec<u8>,
+}
+
+impl MultipartPart {
+    /// Creates a part with the content `buf`, which is stored at position
+    /// `offset` of the entry.
+    pub fn new(offset: u64, buf: Vec<u8>) -> MultipartPart {
+        MultipartPart { offset, buf }
+    }
+
+    /// Returns the position of the part in the entry.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the content of the part.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf
+    }
+}
+
+/// An entry, which receives its content in parts.
+///
+/// A `MultipartEntry` is created by
+/// [`FileBuilder::build_multipart()`](crate::FileBuilder::build_multipart)
+/// with the final size of the entry. The space for the content is allocated
+/// up front, so that the parts can be written in any order with
+/// [`MultipartEntry::write_part()`].
+///
+/// The entry borrows the archive mutably, so only its owner writes into
+/// it. Parts received concurrently by several tasks are sent to the owner
+/// as [`MultipartPart`]s, which passes them to
+/// [`MultipartEntry::receive()`]. The order, in which the parts arrive,
+/// does not matter.
+///
+/// The entry is hidden until [`MultipartEntry::finish()`] validates the
+/// content. If the upload is not finished, the entry stays hidden until
+/// [`Archive::check_and_repair()`](crate::Archive::check_and_repair)
+/// discards it.
+pub struct MultipartEntry<'a, B: Backend> {
+    pager: &'a mut Pager<B>,
+    header_id: &'a B::Id,
+    header: &'a mut Header,
+    tree: &'a mut Tree<B>,
+    entry: Inner,
+    mode: Mode,
+    idx: usize,
+    id: B::Id,
+    received: Vec<(u64, u64)>,
+}
+
+impl<'a, B: Backend> MultipartEntry<'a, B> {
+    pub(crate) fn create(
+        mut builder: InnerBuilder<'a, B>,
+        size: u64,
+    ) -> ArchiveResult<MultipartEntry<'a, B>, B> {
+        let mode = builder.entry.mode;
+
+        builder.entry.mode = mode.pending();
+
+        let EntryMut {
+            pager,
+            header_id,
+            header,
+            tree,
+            mut entry,
+            idx,
+            first: id,
+            ..
+        } = builder.build()?;
+
+        let block_size = pager.block_size() as u64;
+        let nblocks = size.div_ceil(block_size);
+
+        for n in 0..nblocks {
+            if let Err(err) = tree.aquire_content(pager) {
+                // Keep the archive consistent, the entry covers all blocks,
+                // which were allocated so far.
+                entry.size = n * block_size;
+                entry.flush(pager, &id)?;
+                flush_header(pager, header_id, header, tree)?;
+
+                return Err(err);
+            }
+        }
+
+        entry.size = size;
+        entry.flush(pager, &id)?;
+        flush_header(pager, header_id, header, tree)?;
+
+        debug!("multipart entry {} at {}: {} bytes", entry.name, idx, size);
+
+        Ok(MultipartEntry {
+            pager,
+            header_id,
+            header,
+            tree,
+            entry,
+            mode,
+            idx,
+            id,
+            received: vec![],
+        })
+    }
+
+    /// Returns the declared size of the entry.
+    pub fn size(&self) -> u64 {
+        self.entry.size
+    }
+
+    /// Returns the number of bytes, which are still missing.
+    pub fn missing(&self) -> u64 {
+        let received: u64 = self.received.iter().map(|(start, end)| end - start).sum();
+
+        self.entry.size - received
+    }
+
+    /// Writes the part `buf` at position `offset` into the entry.
+    ///
+    /// Parts can be written in any order. Overlapping parts overwrite each
+    /// other.
+    ///
+    /// # Errors
+    ///
+    /// If the part does not fit into the declared size, an
+    /// [`Error::Multipart`] error is returned.
+    pub fn write_part(&mut self, offset: u64, buf: &[u8]) -> ArchiveResult<(), B> {
+        let end = offset
+            .checked_add(buf.len() as u64)
+            .filter(|end| *end <= self.entry.size)
+            .ok_or(Error::Multipart(MultipartError::OutOfRange(
+                offset,
+                buf.len(),
+                self.entry.size,
+            )))?;
+
+        let block_size = self.pager.block_size() as u64;
+        let mut block = vec![0; block_size as usize];
+        let mut pos = offset;
+
+        while pos < end {
+            let id = self.content_id(pos / block_size)?;
+            let start = (pos % block_size) as usize;
+            let n = cmp::min(block_size - start as u64, end - pos) as usize;
+            let part = &buf[(pos - offset) as usize..][..n];
+
+            if n < block_size as usize {
+                block.copy_from_slice(self.pager.read_buf_raw(&id)?);
+            }
+
+            block[start..start + n].copy_from_slice(part);
+            self.pager.write(&id, &block)?;
+
+            pos += n as u64;
+        }
+
+        self.add_received(offset, end);
+
+        Ok(())
+    }
+
+    /// Writes all `parts` into the entry.
+    ///
+    /// Stops at the first part, which cannot be written. Pass the receiving
+    /// end of a channel to write the parts sent by other threads, until all
+    /// senders are dropped.
+    ///
+    /// # Errors
+    ///
+    /// The same errors as for [`MultipartEntry::write_part()`] are
+    /// returned.
+    pub fn receive<I: IntoIterator<Item = MultipartPart>>(
+        &mut self,
+        parts: I,
+    ) -> ArchiveResult<(), B> {
+        for part in parts {
+            self.write_part(part.offset, &part.buf)?;
+        }
+
+        Ok(())
+    }
+
+    /// Finishes the upload.
+    ///
+    /// The entry becomes visible, if all parts were written and the SHA-256
+    /// digest of the content matches `digest`.
+    ///
+    /// # Errors
+    ///
+    /// If parts are missing or the digest does not match, an
+    /// [`Error::Multipart`] error is returned and the entry stays hidden.
+    pub fn finish(mut self, digest: &Digest) -> ArchiveResult<(), B> {
+        let missing = self.missing();
+
+        if missing > 0 {
+            return Err(Error::Multipart(MultipartError::Incomplete(missing)));
+        }
+
+        if &self.digest()? != digest {
+            return Err(Error::Multipart(MultipartError::DigestMismatch));
+        }
+
+        // parents and name index of a deferred entry are updated on commit
+        let deferred = self.header.is_deferred();
+
+        if !deferred {
+            inc_parent_children(self.pager, self.tree, &self.entry.name)?;
+        }
+
+        let mut entry = self.entry;
+
+        entry.mode = self.mode;
+        entry.checksum = Some(*digest);
+        entry.flush(self.pager, &self.id)?;
+
+        if !deferred {
+            index_name(self.pager, self.tree, &entry.name, self.idx)?;
+        }
+
+        self.header.inc_files();
+        flush_header(self.pager, self.header_id, self.header, self.tree)?;
+
+        debug!("multipart entry {} at {} finished", entry.name, self.idx);
+
+        Ok(())
+    }
+
+    fn digest(&mut self) -> ArchiveResult<Digest, B> {
+        let block_size = self.pager.block_size() as u64;
+        let mut sha = Sha256::new();
+        let mut remaining = self.entry.size;
+        let mut n = 0;
+
+        while remaining > 0 {
+            let id = self.content_id(n)?;
+            let len = cmp::min(block_size, remaining) as usize;
+
+            sha.update(&self.pager.read_buf_raw(&id)?[..len]);
+
+            remaining -= len as u64;
+            n += 1;
+        }
+
+        Ok(sha.finalize().into())
+    }
+
+    fn content_id(&mut self, n: u64) -> ArchiveResult<B::Id, B> {
+        let idx = self.idx + self.entry.head_blocks() + n as usize;
+
+        match self.tree.lookup(self.pager, idx) {
+            Some(result) => Ok(result?.clone()),
+            None => Err(Error::UnexpectedEof),
+        }
+    }
+
+    fn add_received(&mut self, start: u64, end: u64) {
+        self.received.push((start, end));
+        self.received.sort_unstable();
+
+        let mut merged: Vec<(u64, u64)> = vec![];
+
+        for (start, end) in self.received.drain(..) {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = cmp::max(last.1, end),
+                _ => merged.push((start, end)),
+            }
+        }
+
+        self.received = merged;
+    }
+}
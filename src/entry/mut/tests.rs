@@ -21,14 +21,24 @@
 // IN THE SOFTWARE.
 
 mod directory;
+mod hardlink;
+mod hash;
+mod multipart;
+mod reopen;
+mod set_len;
 mod symlink;
+mod with_content;
 mod write;
 mod write_all;
+mod write_at;
+mod write_block;
 
 use nuts_container::memory::{Id, MemoryBackend};
 
 use crate::entry::mode::Mode;
-use crate::entry::r#mut::{DirectoryBuilder, FileBuilder, InnerBuilder, SymlinkBuilder};
+use crate::entry::r#mut::{
+    DirectoryBuilder, FileBuilder, InnerBuilder, SymlinkBuilder, MAX_TARGET_LEN,
+};
 use crate::Archive;
 
 fn lookup(archive: &mut Archive<MemoryBackend>, idx: usize) -> Option<&Id> {
@@ -84,6 +94,7 @@ fn setup_symlink_builder<'a>(
         &mut archive.header,
         &mut archive.tree,
         "foo".to_string(),
-        "bar".to_string(),
+        b"bar".to_vec(),
+        MAX_TARGET_LEN,
     )
 }
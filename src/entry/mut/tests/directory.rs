@@ -30,10 +30,9 @@ fn ok() {
     let container = setup_container_with_bsize(FULL as u32);
     let mut archive = Archive::create(container, false).unwrap();
 
-    let tuple = setup_directory_builder(&mut archive).build().unwrap();
-    assert_eq!(tuple, ());
+    setup_directory_builder(&mut archive).build().unwrap();
 
-    let id = lookup(&mut archive, 0).unwrap().clone();
+    let id = *lookup(&mut archive, 0).unwrap();
     assert!(lookup(&mut archive, 1).is_none());
 
     let mut reader = archive.pager.read_buf(&id).unwrap();
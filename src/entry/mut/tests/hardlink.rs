@@ -0,0 +1,84 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use crate::entry::r#mut::tests::lookup;
+use crate::entry::{Inner, FULL};
+use crate::error::Error;
+use crate::tests::setup_container_with_bsize;
+use crate::Archive;
+
+#[test]
+fn ok() {
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive
+        .append_file("foo")
+        .build()
+        .unwrap()
+        .write_all(b"abc")
+        .unwrap();
+    archive.append_hardlink("bar", "foo").build().unwrap();
+
+    let id2 = *lookup(&mut archive, 2).unwrap();
+    let id3 = *lookup(&mut archive, 3).unwrap();
+    assert!(lookup(&mut archive, 4).is_none());
+
+    let mut reader = archive.pager.read_buf(&id2).unwrap();
+    let entry = reader.read::<Inner>().unwrap();
+
+    assert_eq!(entry.name, "bar");
+    assert_eq!(entry.size, 3);
+    assert!(entry.mode.is_hardlink());
+
+    let buf = archive.pager.read_buf_raw(&id3).unwrap();
+    assert_eq!(buf[..3], *b"foo");
+    assert_eq!(buf[3..], [0; FULL as usize - 3]);
+
+    assert_eq!(archive.info().files, 2);
+}
+
+#[test]
+fn target_not_found() {
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    let err = archive.append_hardlink("bar", "foo").build().unwrap_err();
+    assert!(matches!(err, Error::NotFound(name) if name == "foo"));
+
+    assert_eq!(archive.info().files, 0);
+    assert!(lookup(&mut archive, 0).is_none());
+}
+
+#[test]
+fn target_not_file() {
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.append_directory("foo").build().unwrap();
+
+    let err = archive.append_hardlink("bar", "foo").build().unwrap_err();
+    assert!(matches!(err, Error::NotFile(name) if name == "foo"));
+
+    assert_eq!(archive.info().files, 1);
+    assert!(lookup(&mut archive, 1).is_none());
+}
@@ -0,0 +1,146 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use sha2::{Digest as _, Sha256};
+
+use crate::manifest::Digest;
+use crate::tests::setup_container_with_bsize;
+use crate::Archive;
+
+fn setup_archive() -> Archive<nuts_container::memory::MemoryBackend> {
+    let container = setup_container_with_bsize(512);
+    Archive::create(container, false).unwrap()
+}
+
+fn digest(buf: &[u8]) -> Digest {
+    Sha256::digest(buf).into()
+}
+
+#[test]
+fn disabled() {
+    let mut archive = setup_archive();
+
+    let mut entry = archive.append_file("foo").build().unwrap();
+    entry.write_all(b"foo").unwrap();
+
+    assert!(entry.current_hash().is_none());
+}
+
+#[test]
+fn empty() {
+    let mut archive = setup_archive();
+    let mut builder = archive.append_file("foo");
+
+    builder.set_hashed(true);
+
+    let entry = builder.build().unwrap();
+    assert_eq!(entry.current_hash(), Some(digest(&[])));
+}
+
+#[test]
+fn running() {
+    let mut archive = setup_archive();
+    let content = (0..1300).map(|n| (n % 251) as u8).collect::<Vec<_>>();
+    let mut builder = archive.append_file("foo");
+
+    builder.set_hashed(true);
+
+    let mut entry = builder.build().unwrap();
+
+    entry.write_all(&content[..700]).unwrap();
+    assert_eq!(entry.current_hash(), Some(digest(&content[..700])));
+
+    entry.write_all(&content[700..]).unwrap();
+    assert_eq!(entry.current_hash(), Some(digest(&content)));
+
+    let manifest = archive.manifest().unwrap();
+    assert_eq!(manifest.entries()[0].digest(), &digest(&content));
+}
+
+#[test]
+fn stored() {
+    let mut archive = setup_archive();
+    let content = (0..1300).map(|n| (n % 251) as u8).collect::<Vec<_>>();
+    let mut builder = archive.append_file("foo");
+
+    builder.set_hashed(true);
+
+    let mut entry = builder.build().unwrap();
+    entry.write_all(&content).unwrap();
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+    let entry = archive.lookup("foo").unwrap().unwrap();
+    assert_eq!(entry.stored_checksum(), Some(&digest(&content)));
+
+    let mut buf = vec![];
+    archive.export_index(&mut buf).unwrap();
+
+    let index = crate::Index::import(buf.as_slice()).unwrap();
+    let entry = index.lookup("foo").unwrap();
+    assert_eq!(entry.stored_checksum(), Some(&digest(&content)));
+}
+
+#[test]
+fn stored_disabled() {
+    let mut archive = setup_archive();
+
+    let mut entry = archive.append_file("foo").build().unwrap();
+    entry.write_all(b"foo").unwrap();
+
+    let entry = archive.lookup("foo").unwrap().unwrap();
+    assert!(entry.stored_checksum().is_none());
+}
+
+#[test]
+fn stored_write_at() {
+    let mut archive = setup_archive();
+    let mut builder = archive.append_file("foo");
+
+    builder.set_hashed(true);
+
+    let mut entry = builder.build().unwrap();
+    entry.write_all(b"foo").unwrap();
+    entry.write_at(0, b"b").unwrap();
+
+    let entry = archive.lookup("foo").unwrap().unwrap();
+    assert!(entry.stored_checksum().is_none());
+}
+
+#[test]
+fn stored_reopen() {
+    let mut archive = setup_archive();
+    let mut builder = archive.append_file("foo");
+
+    builder.set_hashed(true);
+
+    let mut entry = builder.build().unwrap();
+    entry.write_all(b"foo").unwrap();
+
+    let mut entry = archive.open_mut("foo").unwrap();
+    entry.write_all(b"bar").unwrap();
+
+    let entry = archive.lookup("foo").unwrap().unwrap();
+    assert!(entry.stored_checksum().is_none());
+
+    let manifest = archive.manifest().unwrap();
+    assert_eq!(manifest.entries()[0].digest(), &digest(b"foobar"));
+}
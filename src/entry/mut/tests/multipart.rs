@@ -0,0 +1,225 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use sha2::{Digest as _, Sha256};
+use std::sync::mpsc;
+use std::thread;
+
+use crate::error::{Error, MultipartError};
+use crate::manifest::Digest;
+use crate::tests::setup_container_with_bsize;
+use crate::{Archive, MultipartPart};
+
+fn setup_archive() -> Archive<nuts_container::memory::MemoryBackend> {
+    let container = setup_container_with_bsize(512);
+    Archive::create(container, false).unwrap()
+}
+
+fn content() -> Vec<u8> {
+    (0..1300).map(|n| (n % 251) as u8).collect()
+}
+
+fn digest(buf: &[u8]) -> Digest {
+    Sha256::digest(buf).into()
+}
+
+fn read_first(archive: &mut Archive<nuts_container::memory::MemoryBackend>) -> Vec<u8> {
+    let entry = archive.first().unwrap().unwrap();
+    let mut file = entry.into_file().unwrap();
+
+    assert_eq!(file.name(), "foo");
+    file.read_vec().unwrap()
+}
+
+#[test]
+fn in_order() {
+    let mut archive = setup_archive();
+    let buf = content();
+
+    let mut entry = archive.append_file("foo").build_multipart(1300).unwrap();
+    assert_eq!(entry.size(), 1300);
+    assert_eq!(entry.missing(), 1300);
+
+    for (n, part) in buf.chunks(400).enumerate() {
+        entry.write_part(n as u64 * 400, part).unwrap();
+    }
+
+    assert_eq!(entry.missing(), 0);
+    entry.finish(&digest(&buf)).unwrap();
+
+    assert_eq!(archive.info().files, 1);
+    assert_eq!(read_first(&mut archive), buf);
+
+    let entry = archive.first().unwrap().unwrap();
+    assert_eq!(entry.stored_checksum(), Some(&digest(&buf)));
+}
+
+#[test]
+fn out_of_order() {
+    let mut archive = setup_archive();
+    let buf = content();
+
+    let mut entry = archive.append_file("foo").build_multipart(1300).unwrap();
+
+    entry.write_part(1000, &buf[1000..]).unwrap();
+    entry.write_part(0, &buf[..300]).unwrap();
+    entry.write_part(600, &buf[600..1000]).unwrap();
+    entry.write_part(200, &buf[200..700]).unwrap();
+
+    entry.finish(&digest(&buf)).unwrap();
+
+    assert_eq!(read_first(&mut archive), buf);
+}
+
+#[test]
+fn receive() {
+    let mut archive = setup_archive();
+    let buf = content();
+
+    let mut entry = archive.append_file("foo").build_multipart(1300).unwrap();
+    let (tx, rx) = mpsc::channel();
+
+    let handles: Vec<_> = buf
+        .chunks(400)
+        .enumerate()
+        .map(|(n, part)| {
+            let tx = tx.clone();
+            let part = MultipartPart::new(n as u64 * 400, part.to_vec());
+
+            thread::spawn(move || tx.send(part).unwrap())
+        })
+        .collect();
+
+    drop(tx);
+    entry.receive(rx).unwrap();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(entry.missing(), 0);
+    entry.finish(&digest(&buf)).unwrap();
+
+    assert_eq!(read_first(&mut archive), buf);
+}
+
+#[test]
+fn receive_out_of_range() {
+    let mut archive = setup_archive();
+
+    let mut entry = archive.append_file("foo").build_multipart(1300).unwrap();
+    let parts = vec![
+        MultipartPart::new(0, vec![1; 100]),
+        MultipartPart::new(1200, vec![2; 101]),
+        MultipartPart::new(100, vec![3; 100]),
+    ];
+
+    let err = entry.receive(parts).unwrap_err();
+
+    assert!(matches!(
+        err,
+        Error::Multipart(MultipartError::OutOfRange(1200, 101, 1300))
+    ));
+    assert_eq!(entry.missing(), 1200);
+}
+
+#[test]
+fn empty() {
+    let mut archive = setup_archive();
+
+    let entry = archive.append_file("foo").build_multipart(0).unwrap();
+    entry.finish(&digest(&[])).unwrap();
+
+    assert_eq!(archive.info().files, 1);
+    assert_eq!(read_first(&mut archive), []);
+}
+
+#[test]
+fn out_of_range() {
+    let mut archive = setup_archive();
+
+    let mut entry = archive.append_file("foo").build_multipart(1300).unwrap();
+    let err = entry.write_part(1000, &[0; 301]).unwrap_err();
+
+    assert!(matches!(
+        err,
+        Error::Multipart(MultipartError::OutOfRange(1000, 301, 1300))
+    ));
+    assert_eq!(entry.missing(), 1300);
+}
+
+#[test]
+fn incomplete() {
+    let mut archive = setup_archive();
+    let buf = content();
+
+    let mut entry = archive.append_file("foo").build_multipart(1300).unwrap();
+
+    entry.write_part(0, &buf[..500]).unwrap();
+    entry.write_part(700, &buf[700..]).unwrap();
+
+    let err = entry.finish(&digest(&buf)).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Multipart(MultipartError::Incomplete(200))
+    ));
+
+    assert_eq!(archive.info().files, 0);
+    assert!(archive.first().is_none());
+}
+
+#[test]
+fn digest_mismatch() {
+    let mut archive = setup_archive();
+    let buf = content();
+
+    let mut entry = archive.append_file("foo").build_multipart(1300).unwrap();
+    entry.write_part(0, &buf).unwrap();
+
+    let err = entry.finish(&digest(&buf[1..])).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Multipart(MultipartError::DigestMismatch)
+    ));
+
+    assert_eq!(archive.info().files, 0);
+    assert!(archive.first().is_none());
+}
+
+#[test]
+fn hidden_until_finished() {
+    let mut archive = setup_archive();
+    let buf = content();
+
+    let mut entry = archive.append_file("foo").build_multipart(1300).unwrap();
+    entry.write_part(0, &buf).unwrap();
+    drop(entry);
+
+    assert!(archive.first().is_none());
+    assert!(archive.lookup("foo").is_none());
+
+    let mut entry = archive.append_file("bar").build().unwrap();
+    entry.write_all(b"bar").unwrap();
+
+    let entry = archive.first().unwrap().unwrap();
+    assert_eq!(entry.name(), "bar");
+}
@@ -0,0 +1,136 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+use crate::error::Error;
+use crate::tests::setup_container_with_bsize;
+use crate::Archive;
+
+fn setup_archive() -> Archive<nuts_container::memory::MemoryBackend> {
+    let container = setup_container_with_bsize(512);
+    Archive::create(container, false).unwrap()
+}
+
+fn read_content(
+    archive: &mut Archive<nuts_container::memory::MemoryBackend>,
+    name: &str,
+) -> Vec<u8> {
+    let mut entry = archive.lookup(name).unwrap().unwrap().into_file().unwrap();
+    entry.read_vec().unwrap()
+}
+
+#[test]
+fn empty() {
+    let mut archive = setup_archive();
+
+    archive.append_file("foo").build().unwrap();
+
+    let mut entry = archive.open_mut("foo").unwrap();
+    entry.write_all(b"abc").unwrap();
+
+    assert_eq!(read_content(&mut archive, "foo"), b"abc");
+    assert_eq!(archive.info().blocks, 2);
+}
+
+#[test]
+fn partial_block() {
+    let mut archive = setup_archive();
+    let content = (0..1300).map(|n| (n % 251) as u8).collect::<Vec<_>>();
+
+    let mut entry = archive.append_file("foo").build().unwrap();
+    entry.write_all(&content[..700]).unwrap();
+
+    let mut entry = archive.open_mut("foo").unwrap();
+    entry.write_all(&content[700..]).unwrap();
+
+    assert_eq!(read_content(&mut archive, "foo"), content);
+    assert_eq!(archive.info().blocks, 4);
+    assert_eq!(archive.info().files, 1);
+}
+
+#[test]
+fn full_block() {
+    let mut archive = setup_archive();
+    let content = (0..1300).map(|n| (n % 251) as u8).collect::<Vec<_>>();
+
+    let mut entry = archive.append_file("foo").build().unwrap();
+    entry.write_all(&content[..1024]).unwrap();
+
+    let mut entry = archive.open_mut("foo").unwrap();
+    entry.write_all(&content[1024..]).unwrap();
+
+    assert_eq!(read_content(&mut archive, "foo"), content);
+    assert_eq!(archive.info().blocks, 4);
+}
+
+#[test]
+fn reopen_after_open() {
+    let mut archive = setup_archive();
+
+    let mut entry = archive.append_file("foo").build().unwrap();
+    entry.write_all(b"abc").unwrap();
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    let mut entry = archive.open_mut("foo").unwrap();
+    entry.write_all(b"def").unwrap();
+
+    assert_eq!(read_content(&mut archive, "foo"), b"abcdef");
+}
+
+#[test]
+fn not_last() {
+    let mut archive = setup_archive();
+
+    archive.append_file("foo").build().unwrap();
+    archive.append_file("bar").build().unwrap();
+
+    let err = archive.open_mut("foo").err().unwrap();
+    assert!(matches!(err, Error::NotAppendable(name) if name == "foo"));
+}
+
+#[test]
+fn not_file() {
+    let mut archive = setup_archive();
+
+    archive.append_directory("foo").build().unwrap();
+
+    let err = archive.open_mut("foo").err().unwrap();
+    assert!(matches!(err, Error::NotAppendable(name) if name == "foo"));
+}
+
+#[test]
+fn not_found() {
+    let mut archive = setup_archive();
+
+    let err = archive.open_mut("foo").err().unwrap();
+    assert!(matches!(err, Error::NotFound(name) if name == "foo"));
+}
+
+#[test]
+fn frozen() {
+    let mut archive = setup_archive();
+
+    archive.append_file("foo").build().unwrap();
+    archive.freeze("secret").unwrap();
+
+    let err = archive.open_mut("foo").err().unwrap();
+    assert!(matches!(err, Error::Frozen));
+}
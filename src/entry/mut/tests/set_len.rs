@@ -0,0 +1,137 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+use nuts_container::memory::MemoryBackend;
+
+use crate::tests::setup_container_with_bsize;
+use crate::Archive;
+
+fn setup_archive() -> Archive<MemoryBackend> {
+    let container = setup_container_with_bsize(512);
+    Archive::create(container, false).unwrap()
+}
+
+fn content(n: usize) -> Vec<u8> {
+    (0..n).map(|n| (n % 251) as u8).collect()
+}
+
+fn read_content(archive: &mut Archive<MemoryBackend>) -> Vec<u8> {
+    let mut entry = archive.first().unwrap().unwrap().into_file().unwrap();
+    entry.read_vec().unwrap()
+}
+
+#[test]
+fn extend() {
+    let mut archive = setup_archive();
+
+    let mut entry = archive.append_file("foo").build().unwrap();
+    entry.write_all(&content(100)).unwrap();
+    entry.set_len(1100).unwrap();
+
+    let mut expected = content(100);
+    expected.resize(1100, 0);
+
+    assert_eq!(read_content(&mut archive), expected);
+    assert_eq!(archive.info().blocks, 4);
+}
+
+#[test]
+fn same() {
+    let mut archive = setup_archive();
+
+    let mut entry = archive.append_file("foo").build().unwrap();
+    entry.write_all(&content(100)).unwrap();
+    entry.set_len(100).unwrap();
+
+    assert_eq!(read_content(&mut archive), content(100));
+}
+
+#[test]
+fn shrink() {
+    let mut archive = setup_archive();
+
+    let mut entry = archive.append_file("foo").build().unwrap();
+    entry.write_all(&content(1300)).unwrap();
+    entry.set_len(600).unwrap();
+
+    assert_eq!(read_content(&mut archive), content(600));
+    assert_eq!(archive.info().blocks, 3);
+}
+
+#[test]
+fn shrink_block_boundary() {
+    let mut archive = setup_archive();
+
+    let mut entry = archive.append_file("foo").build().unwrap();
+    entry.write_all(&content(1300)).unwrap();
+    entry.set_len(512).unwrap();
+    entry.write_all(b"abc").unwrap();
+
+    let mut expected = content(512);
+    expected.extend_from_slice(b"abc");
+
+    assert_eq!(read_content(&mut archive), expected);
+    assert_eq!(archive.info().blocks, 3);
+}
+
+#[test]
+fn shrink_empty() {
+    let mut archive = setup_archive();
+
+    let mut entry = archive.append_file("foo").build().unwrap();
+    entry.write_all(&content(1300)).unwrap();
+    entry.set_len(0).unwrap();
+
+    assert!(read_content(&mut archive).is_empty());
+    assert_eq!(archive.info().blocks, 1);
+}
+
+#[test]
+fn shrink_write() {
+    let mut archive = setup_archive();
+
+    let mut entry = archive.append_file("foo").build().unwrap();
+    entry.write_all(&content(1300)).unwrap();
+    entry.set_len(10).unwrap();
+    entry.set_len(20).unwrap();
+    entry.write_all(b"abc").unwrap();
+
+    let mut expected = content(10);
+    expected.resize(20, 0);
+    expected.extend_from_slice(b"abc");
+
+    assert_eq!(read_content(&mut archive), expected);
+}
+
+#[test]
+fn shrink_hash() {
+    let mut archive = setup_archive();
+    let mut builder = archive.append_file("foo");
+
+    builder.set_hashed(true);
+
+    let mut entry = builder.build().unwrap();
+    entry.write_all(&content(100)).unwrap();
+    assert!(entry.current_hash().is_some());
+
+    entry.set_len(50).unwrap();
+    assert!(entry.current_hash().is_none());
+}
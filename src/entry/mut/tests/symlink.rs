@@ -22,6 +22,7 @@
 
 use crate::entry::r#mut::tests::{lookup, setup_symlink_builder};
 use crate::entry::{Inner, FULL};
+use crate::error::{Error, TargetError};
 use crate::tests::setup_container_with_bsize;
 use crate::Archive;
 
@@ -30,11 +31,10 @@ fn ok() {
     let container = setup_container_with_bsize(FULL as u32);
     let mut archive = Archive::create(container, false).unwrap();
 
-    let tuple = setup_symlink_builder(&mut archive).build().unwrap();
-    assert_eq!(tuple, ());
+    setup_symlink_builder(&mut archive).build().unwrap();
 
-    let id0 = lookup(&mut archive, 0).unwrap().clone();
-    let id1 = lookup(&mut archive, 1).unwrap().clone();
+    let id0 = *lookup(&mut archive, 0).unwrap();
+    let id1 = *lookup(&mut archive, 1).unwrap();
     assert!(lookup(&mut archive, 2).is_none());
 
     let mut reader = archive.pager.read_buf(&id0).unwrap();
@@ -48,3 +48,33 @@ fn ok() {
     assert_eq!(buf[..3], *b"bar");
     assert_eq!(buf[3..], [0; FULL as usize - 3]);
 }
+
+#[test]
+fn target_too_long() {
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.set_max_target_len(3);
+    archive.append_symlink("f1", "abc").build().unwrap();
+
+    let err = archive.append_symlink("f2", "abcd").build().unwrap_err();
+    assert!(matches!(
+        err,
+        Error::InvalidTarget(TargetError::TooLong(4, 3))
+    ));
+
+    assert_eq!(archive.info().files, 1);
+    assert!(lookup(&mut archive, 2).is_none());
+}
+
+#[test]
+fn target_nul() {
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    let err = archive.append_symlink("f1", b"a\0b").build().unwrap_err();
+    assert!(matches!(err, Error::InvalidTarget(TargetError::Nul)));
+
+    assert_eq!(archive.info().files, 0);
+    assert!(lookup(&mut archive, 0).is_none());
+}
@@ -0,0 +1,143 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use sha2::{Digest as _, Sha256};
+
+use crate::entry::r#mut::tests::{lookup, setup_file_builder};
+use crate::entry::{Inner, FULL, HALF};
+use crate::error::Error;
+use crate::tests::setup_container_with_bsize;
+use crate::Archive;
+
+#[test]
+fn no_content() {
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    setup_file_builder(&mut archive)
+        .with_content(&[])
+        .build()
+        .unwrap();
+
+    let id = *lookup(&mut archive, 0).unwrap();
+    assert!(lookup(&mut archive, 1).is_none());
+
+    let mut reader = archive.pager.read_buf(&id).unwrap();
+    let entry = reader.read::<Inner>().unwrap();
+
+    assert_eq!(entry.name, "foo");
+    assert_eq!(entry.size, 0);
+}
+
+#[test]
+fn one_half_blocks() {
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+    let content = (0..FULL + HALF).collect::<Vec<u8>>();
+
+    setup_file_builder(&mut archive)
+        .with_content(&content)
+        .build()
+        .unwrap();
+
+    let id0 = *lookup(&mut archive, 0).unwrap();
+    let id1 = *lookup(&mut archive, 1).unwrap();
+    let id2 = *lookup(&mut archive, 2).unwrap();
+    assert!(lookup(&mut archive, 3).is_none());
+
+    let mut reader = archive.pager.read_buf(&id0).unwrap();
+    let entry = reader.read::<Inner>().unwrap();
+
+    assert_eq!(entry.name, "foo");
+    assert_eq!(entry.size, FULL as u64 + HALF as u64);
+
+    let buf = archive.pager.read_buf_raw(&id1).unwrap();
+    assert_eq!(buf, &content[..FULL as usize]);
+
+    let buf = archive.pager.read_buf_raw(&id2).unwrap();
+    assert_eq!(buf[..HALF as usize], content[FULL as usize..]);
+    assert_eq!(buf[HALF as usize..], [0; HALF as usize]);
+
+    assert_eq!(archive.info().blocks, 3);
+}
+
+#[test]
+fn continue_write() {
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    let mut entry = setup_file_builder(&mut archive)
+        .with_content(&[1; HALF as usize])
+        .build()
+        .unwrap();
+    entry.write_all(&[2; HALF as usize]).unwrap();
+
+    let mut file = archive.first().unwrap().unwrap().into_file().unwrap();
+    let buf = file.read_vec().unwrap();
+
+    assert_eq!(buf[..HALF as usize], [1; HALF as usize]);
+    assert_eq!(buf[HALF as usize..], [2; HALF as usize]);
+}
+
+#[test]
+fn hashed() {
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+    let content = (0..2 * FULL).collect::<Vec<u8>>();
+    let mut builder = setup_file_builder(&mut archive);
+
+    builder.set_hashed(true);
+
+    let entry = builder.with_content(&content).build().unwrap();
+    let digest: [u8; 32] = Sha256::digest(&content).into();
+
+    assert_eq!(entry.current_hash(), Some(digest));
+
+    let entry = archive.first().unwrap().unwrap();
+    assert_eq!(entry.stored_checksum(), Some(&digest));
+}
+
+#[test]
+#[cfg_attr(feature = "consistency", ignore)]
+fn full() {
+    // 12 direct + 26 indirect + 26^2 double indirect + 26^3 triple indirect
+    const CAPACITY: usize = 12 + 26 + 26 * 26 + 26 * 26 * 26;
+
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    let buf = vec![1; CAPACITY * FULL as usize];
+    let err = setup_file_builder(&mut archive)
+        .with_content(&buf)
+        .build()
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::Full(n) if n == (CAPACITY - 1) * FULL as usize));
+
+    assert_eq!(archive.info().blocks, CAPACITY as u64);
+
+    let id = *lookup(&mut archive, 0).unwrap();
+    let mut reader = archive.pager.read_buf(&id).unwrap();
+    let entry = reader.read::<Inner>().unwrap();
+
+    assert_eq!(entry.size, ((CAPACITY - 1) * FULL as usize) as u64);
+}
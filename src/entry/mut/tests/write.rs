@@ -344,6 +344,28 @@ macro_rules! make_tests {
             let buf = archive.pager.read_buf_raw(&id2).unwrap();
             assert_eq!(buf, (FULL..2 * FULL).collect::<Vec<u8>>());
         }
+
+        #[test]
+        fn stale() {
+            let container = setup_container_with_bsize(FULL as u32);
+            let mut archive = Archive::create(container, false).unwrap();
+            let mut entry = $setup(&mut archive).build().unwrap();
+
+            assert_eq!(entry.write(&[1]).unwrap(), 1);
+
+            entry.pager.inc_generation();
+
+            let err = entry.write(&[2]).unwrap_err();
+            assert!(matches!(err, Error::Stale));
+
+            let id0 = lookup(&mut archive, 0).unwrap().clone();
+            assert!(lookup(&mut archive, 2).is_none());
+
+            let mut reader = archive.pager.read_buf(&id0).unwrap();
+            let entry = reader.read::<Inner>().unwrap();
+
+            assert_eq!(entry.size, 1);
+        }
     };
 }
 
@@ -352,7 +374,7 @@ mod inner {
     use crate::entry::Inner;
     use crate::entry::{FULL, HALF};
     use crate::tests::setup_container_with_bsize;
-    use crate::Archive;
+    use crate::{Archive, Error};
 
     make_tests!(setup_inner_builder);
 }
@@ -362,7 +384,7 @@ mod file {
     use crate::entry::Inner;
     use crate::entry::{FULL, HALF};
     use crate::tests::setup_container_with_bsize;
-    use crate::Archive;
+    use crate::{Archive, Error};
 
     make_tests!(setup_file_builder);
 }
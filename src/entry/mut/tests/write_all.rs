@@ -166,3 +166,34 @@ mod file {
 
     make_tests!(setup_file_builder);
 }
+
+#[test]
+#[cfg_attr(feature = "consistency", ignore)]
+fn full() {
+    use crate::entry::r#mut::tests::{lookup, setup_file_builder};
+    use crate::entry::{Inner, FULL};
+    use crate::tests::setup_container_with_bsize;
+    use crate::{Archive, Error};
+
+    // 12 direct + 26 indirect + 26^2 double indirect + 26^3 triple indirect
+    const CAPACITY: usize = 12 + 26 + 26 * 26 + 26 * 26 * 26;
+
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+    let mut entry = setup_file_builder(&mut archive).build().unwrap();
+
+    let buf = vec![1; CAPACITY * FULL as usize];
+    let err = entry.write_all(&buf).unwrap_err();
+    assert!(matches!(err, Error::Full(n) if n == (CAPACITY - 1) * FULL as usize));
+
+    let err = entry.write(&[1]).unwrap_err();
+    assert!(matches!(err, Error::Full(0)));
+
+    assert_eq!(archive.info().blocks, CAPACITY as u64);
+
+    let id = *lookup(&mut archive, 0).unwrap();
+    let mut reader = archive.pager.read_buf(&id).unwrap();
+    let entry = reader.read::<Inner>().unwrap();
+
+    assert_eq!(entry.size, ((CAPACITY - 1) * FULL as usize) as u64);
+}
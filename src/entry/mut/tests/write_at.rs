@@ -0,0 +1,148 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+use nuts_container::memory::MemoryBackend;
+
+use crate::tests::setup_container_with_bsize;
+use crate::Archive;
+
+fn setup_archive() -> Archive<MemoryBackend> {
+    let container = setup_container_with_bsize(512);
+    Archive::create(container, false).unwrap()
+}
+
+fn content(n: usize) -> Vec<u8> {
+    (0..n).map(|n| (n % 251) as u8).collect()
+}
+
+fn read_content(archive: &mut Archive<MemoryBackend>) -> Vec<u8> {
+    let mut entry = archive.first().unwrap().unwrap().into_file().unwrap();
+    entry.read_vec().unwrap()
+}
+
+#[test]
+fn inside_block() {
+    let mut archive = setup_archive();
+
+    let mut entry = archive.append_file("foo").build().unwrap();
+    entry.write_all(&content(1300)).unwrap();
+    assert_eq!(entry.write_at(10, b"abc").unwrap(), 3);
+
+    let mut expected = content(1300);
+    expected[10..13].copy_from_slice(b"abc");
+
+    assert_eq!(read_content(&mut archive), expected);
+}
+
+#[test]
+fn across_blocks() {
+    let mut archive = setup_archive();
+    let buf = vec![0xff; 600];
+
+    let mut entry = archive.append_file("foo").build().unwrap();
+    entry.write_all(&content(1300)).unwrap();
+    assert_eq!(entry.write_at(500, &buf).unwrap(), 600);
+
+    let mut expected = content(1300);
+    expected[500..1100].copy_from_slice(&buf);
+
+    assert_eq!(read_content(&mut archive), expected);
+    assert_eq!(archive.info().blocks, 4);
+}
+
+#[test]
+fn last_block_then_append() {
+    let mut archive = setup_archive();
+
+    let mut entry = archive.append_file("foo").build().unwrap();
+    entry.write_all(&content(1100)).unwrap();
+    assert_eq!(entry.write_at(1050, b"abc").unwrap(), 3);
+    entry.write_all(b"xyz").unwrap();
+
+    let mut expected = content(1100);
+    expected[1050..1053].copy_from_slice(b"abc");
+    expected.extend_from_slice(b"xyz");
+
+    assert_eq!(read_content(&mut archive), expected);
+}
+
+#[test]
+fn full_last_block() {
+    let mut archive = setup_archive();
+
+    let mut entry = archive.append_file("foo").build().unwrap();
+    entry.write_all(&content(1300)).unwrap();
+    entry.set_len(1024).unwrap();
+    assert_eq!(entry.write_at(1020, b"abcdefgh").unwrap(), 4);
+    entry.write_all(b"xyz").unwrap();
+
+    let mut expected = content(1024);
+    expected[1020..].copy_from_slice(b"abcd");
+    expected.extend_from_slice(b"xyz");
+
+    assert_eq!(read_content(&mut archive), expected);
+}
+
+#[test]
+fn beyond_end() {
+    let mut archive = setup_archive();
+
+    let mut entry = archive.append_file("foo").build().unwrap();
+    entry.write_all(&content(100)).unwrap();
+    assert_eq!(entry.write_at(98, b"abcd").unwrap(), 2);
+    assert_eq!(entry.write_at(100, b"abcd").unwrap(), 0);
+    assert_eq!(entry.write_at(200, b"abcd").unwrap(), 0);
+
+    let mut expected = content(100);
+    expected[98..].copy_from_slice(b"ab");
+
+    assert_eq!(read_content(&mut archive), expected);
+}
+
+#[test]
+fn hash() {
+    let mut archive = setup_archive();
+    let mut builder = archive.append_file("foo");
+
+    builder.set_hashed(true);
+
+    let mut entry = builder.build().unwrap();
+    entry.write_all(&content(100)).unwrap();
+    entry.write_at(200, b"abc").unwrap();
+    assert!(entry.current_hash().is_some());
+
+    entry.write_at(0, b"abc").unwrap();
+    assert!(entry.current_hash().is_none());
+}
+
+#[test]
+fn reopened() {
+    let mut archive = setup_archive();
+
+    archive.append_file("foo").build().unwrap();
+
+    let mut entry = archive.open_mut("foo").unwrap();
+    entry.write_all(b"abc").unwrap();
+    entry.write_at(0, b"x").unwrap();
+    entry.write_all(b"def").unwrap();
+
+    assert_eq!(read_content(&mut archive), b"xbcdef");
+}
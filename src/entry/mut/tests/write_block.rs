@@ -0,0 +1,132 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use crate::entry::r#mut::tests::{lookup, setup_file_builder};
+use crate::entry::{Inner, FULL, HALF};
+use crate::tests::setup_container_with_bsize;
+use crate::{Archive, Error};
+
+#[test]
+fn two_blocks() {
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    let mut entry = setup_file_builder(&mut archive).build().unwrap();
+    entry.write_block(&(0..FULL).collect::<Vec<u8>>()).unwrap();
+    entry
+        .write_block(&(FULL..2 * FULL).collect::<Vec<u8>>())
+        .unwrap();
+
+    let id0 = *lookup(&mut archive, 0).unwrap();
+    let id1 = *lookup(&mut archive, 1).unwrap();
+    let id2 = *lookup(&mut archive, 2).unwrap();
+    assert!(lookup(&mut archive, 3).is_none());
+
+    let mut reader = archive.pager.read_buf(&id0).unwrap();
+    let entry = reader.read::<Inner>().unwrap();
+
+    assert_eq!(entry.name, "foo");
+    assert_eq!(entry.size, 2 * FULL as u64);
+
+    let buf = archive.pager.read_buf_raw(&id1).unwrap();
+    assert_eq!(buf, (0..FULL).collect::<Vec<u8>>());
+
+    let buf = archive.pager.read_buf_raw(&id2).unwrap();
+    assert_eq!(buf, (FULL..2 * FULL).collect::<Vec<u8>>());
+}
+
+#[test]
+fn mixed_with_write() {
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    let mut entry = setup_file_builder(&mut archive).build().unwrap();
+    entry.write_block(&[1; FULL as usize]).unwrap();
+    entry.write_all(&[2; HALF as usize]).unwrap();
+
+    let err = entry.write_block(&[3; FULL as usize]).unwrap_err();
+    assert!(matches!(err, Error::Unaligned(n, size)
+        if n == FULL as usize && size == FULL as u64 + HALF as u64));
+
+    entry.write_all(&[2; (FULL - HALF) as usize]).unwrap();
+    entry.write_block(&[3; FULL as usize]).unwrap();
+
+    let mut entry = archive.first().unwrap().unwrap().into_file().unwrap();
+    let content = entry.read_vec().unwrap();
+
+    assert_eq!(content.len(), 3 * FULL as usize);
+    assert_eq!(content[..FULL as usize], [1; FULL as usize]);
+    assert_eq!(
+        content[FULL as usize..2 * FULL as usize],
+        [2; FULL as usize]
+    );
+    assert_eq!(content[2 * FULL as usize..], [3; FULL as usize]);
+}
+
+#[test]
+fn wrong_size() {
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    let mut entry = setup_file_builder(&mut archive).build().unwrap();
+
+    let err = entry.write_block(&[1; HALF as usize]).unwrap_err();
+    assert!(matches!(err, Error::Unaligned(n, 0) if n == HALF as usize));
+
+    let err = entry.write_block(&[1; FULL as usize + 1]).unwrap_err();
+    assert!(matches!(err, Error::Unaligned(n, 0) if n == FULL as usize + 1));
+
+    // nothing was appended
+    assert!(lookup(&mut archive, 1).is_none());
+}
+
+#[test]
+fn overwrite() {
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    let mut entry = setup_file_builder(&mut archive).build().unwrap();
+    entry.write_block(&[1; FULL as usize]).unwrap();
+    assert_eq!(entry.write_at(1, &[2, 3]).unwrap(), 2);
+
+    let mut entry = archive.first().unwrap().unwrap().into_file().unwrap();
+    let content = entry.read_vec().unwrap();
+
+    assert_eq!(content[..4], [1, 2, 3, 1]);
+}
+
+#[test]
+fn hash() {
+    use sha2::{Digest, Sha256};
+
+    let container = setup_container_with_bsize(FULL as u32);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    let mut builder = archive.append_file("foo");
+    builder.set_hashed(true);
+
+    let mut entry = builder.build().unwrap();
+    entry.write_block(&[1; FULL as usize]).unwrap();
+
+    let expected: [u8; 32] = Sha256::digest([1; FULL as usize]).into();
+    assert_eq!(entry.current_hash(), Some(expected));
+}
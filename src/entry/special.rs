@@ -0,0 +1,104 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use nuts_bytes::{Reader, Writer};
+
+use crate::entry::mode::Mode;
+
+/// The type of a special file.
+///
+/// Special files are created with
+/// [`Archive::append_special()`](crate::Archive::append_special) and show
+/// up as [`Entry::Special`](crate::Entry::Special).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpecialKind {
+    /// A named pipe.
+    Fifo,
+
+    /// A character device with its major and minor number.
+    CharDevice { major: u32, minor: u32 },
+
+    /// A block device with its major and minor number.
+    BlockDevice { major: u32, minor: u32 },
+
+    /// A unix domain socket.
+    Socket,
+}
+
+impl SpecialKind {
+    pub(crate) fn mode(&self) -> Mode {
+        match self {
+            Self::Fifo => Mode::fifo(),
+            Self::CharDevice { .. } => Mode::char_device(),
+            Self::BlockDevice { .. } => Mode::block_device(),
+            Self::Socket => Mode::socket(),
+        }
+    }
+
+    /// Returns the content of the entry: the device numbers of a device,
+    /// nothing otherwise.
+    pub(crate) fn content(&self) -> Result<Vec<u8>, nuts_bytes::Error> {
+        let mut writer = Writer::new(vec![]);
+
+        match self {
+            Self::CharDevice { major, minor } | Self::BlockDevice { major, minor } => {
+                writer.write(major)?;
+                writer.write(minor)?;
+            }
+            Self::Fifo | Self::Socket => {}
+        }
+
+        Ok(writer.into_target())
+    }
+
+    /// Restores the kind from the `mode` and `content` of an entry.
+    ///
+    /// Returns [`None`] if `mode` is not a special file.
+    pub(crate) fn from_entry(
+        mode: &Mode,
+        content: &[u8],
+    ) -> Result<Option<SpecialKind>, nuts_bytes::Error> {
+        let device = || -> Result<(u32, u32), nuts_bytes::Error> {
+            let mut reader = Reader::new(content);
+            Ok((reader.read()?, reader.read()?))
+        };
+
+        let kind = if mode.is_fifo() {
+            Self::Fifo
+        } else if mode.is_char_device() {
+            let (major, minor) = device()?;
+            Self::CharDevice { major, minor }
+        } else if mode.is_block_device() {
+            let (major, minor) = device()?;
+            Self::BlockDevice { major, minor }
+        } else if mode.is_socket() {
+            Self::Socket
+        } else {
+            return Ok(None);
+        };
+
+        Ok(Some(kind))
+    }
+}
@@ -0,0 +1,65 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use crate::entry::mode::Mode;
+use crate::entry::special::SpecialKind;
+
+#[test]
+fn content() {
+    assert!(SpecialKind::Fifo.content().unwrap().is_empty());
+    assert!(SpecialKind::Socket.content().unwrap().is_empty());
+
+    let kind = SpecialKind::CharDevice { major: 1, minor: 3 };
+    assert_eq!(kind.content().unwrap(), [0, 0, 0, 1, 0, 0, 0, 3]);
+
+    let kind = SpecialKind::BlockDevice {
+        major: 8,
+        minor: 0x100,
+    };
+    assert_eq!(kind.content().unwrap(), [0, 0, 0, 8, 0, 0, 1, 0]);
+}
+
+#[test]
+fn round_trip() {
+    for kind in [
+        SpecialKind::Fifo,
+        SpecialKind::CharDevice { major: 1, minor: 3 },
+        SpecialKind::BlockDevice { major: 8, minor: 1 },
+        SpecialKind::Socket,
+    ] {
+        let content = kind.content().unwrap();
+        let restored = SpecialKind::from_entry(&kind.mode(), &content).unwrap();
+
+        assert_eq!(restored, Some(kind));
+    }
+}
+
+#[test]
+fn not_special() {
+    let kind = SpecialKind::from_entry(&Mode::file(), &[]).unwrap();
+    assert_eq!(kind, None);
+}
+
+#[test]
+fn truncated_device() {
+    SpecialKind::from_entry(&Mode::char_device(), &[0, 0, 0, 1]).unwrap_err();
+}
@@ -0,0 +1,347 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use nuts_bytes::{Reader, Writer};
+
+use crate::codec::Codec;
+use crate::compression::Compression;
+use crate::entry::mode::Mode;
+use crate::entry::Inner;
+
+#[test]
+fn ser_mode_words() {
+    let mut inner = Inner::new("x".to_string(), Mode::file());
+
+    inner.mode.set_unix_permissions(0o4755);
+
+    let mut writer = Writer::new(vec![]);
+    writer.write(&inner).unwrap();
+
+    let buf = writer.into_target();
+    assert_eq!(&buf[9..11], [0x01, 0x6F]);
+    assert_eq!(&buf[buf.len() - 4..], [0x00, 0x01, 0x00, 0x00]);
+
+    let mut reader = Reader::new(buf.as_slice());
+    let inner = reader.read::<Inner>().unwrap();
+    assert_eq!(inner.mode.to_unix(), 0o104755);
+}
+
+#[test]
+fn de_without_ext() {
+    let inner = Inner::new("x".to_string(), Mode::file());
+
+    let mut writer = Writer::new(vec![]);
+    writer.write(&inner).unwrap();
+
+    // extension word, uid and gid are missing
+    let mut buf = writer.into_target();
+    buf.truncate(buf.len() - 4);
+
+    let mut reader = Reader::new(buf.as_slice());
+    let inner = reader.read::<Inner>().unwrap();
+    assert_eq!(inner.mode.to_unix(), 0o100755);
+}
+
+#[test]
+fn de_without_children() {
+    let inner = Inner::new("x".to_string(), Mode::directory());
+
+    let mut writer = Writer::new(vec![]);
+    writer.write(&inner).unwrap();
+
+    // an old entry, which fills the block up to the size
+    let mut buf = writer.into_target();
+    buf.truncate(buf.len() - 12);
+
+    let mut reader = Reader::new(buf.as_slice());
+    let inner = reader.read::<Inner>().unwrap();
+    assert!(inner.mode.is_directory());
+    assert_eq!(inner.children, 0);
+}
+
+#[test]
+fn ser_owner() {
+    let mut inner = Inner::new("x".to_string(), Mode::file());
+
+    inner.uid = Some(1000);
+    inner.gid = Some(100);
+
+    let mut writer = Writer::new(vec![]);
+    writer.write(&inner).unwrap();
+
+    let buf = writer.into_target();
+    assert_eq!(
+        &buf[buf.len() - 10..],
+        [1, 0, 0, 0x03, 0xE8, 1, 0, 0, 0, 100]
+    );
+
+    let mut reader = Reader::new(buf.as_slice());
+    let inner = reader.read::<Inner>().unwrap();
+    assert_eq!(inner.uid, Some(1000));
+    assert_eq!(inner.gid, Some(100));
+}
+
+#[test]
+fn de_without_owner() {
+    let inner = Inner::new("x".to_string(), Mode::file());
+
+    let mut writer = Writer::new(vec![]);
+    writer.write(&inner).unwrap();
+
+    // zero padding of the block
+    let mut buf = writer.into_target();
+    buf.truncate(buf.len() - 2);
+    buf.extend_from_slice(&[0; 16]);
+
+    let mut reader = Reader::new(buf.as_slice());
+    let inner = reader.read::<Inner>().unwrap();
+    assert_eq!(inner.uid, None);
+    assert_eq!(inner.gid, None);
+}
+
+#[test]
+fn ser_metadata() {
+    let mut inner = Inner::new("x".to_string(), Mode::file());
+
+    inner.metadata.insert("k".to_string(), "v".to_string());
+
+    let mut writer = Writer::new(vec![]);
+    writer.write(&inner).unwrap();
+
+    let buf = writer.into_target();
+    assert_eq!(
+        &buf[buf.len() - 30..],
+        [
+            0x80, 0, 0, 0, // ext, uid, gid
+            0, 0, 0, 0, 0, 0, 0, 1, // count
+            0, 0, 0, 0, 0, 0, 0, 1, b'k', // key
+            0, 0, 0, 0, 0, 0, 0, 1, b'v', // value
+        ]
+    );
+
+    let mut reader = Reader::new(buf.as_slice());
+    let inner = reader.read::<Inner>().unwrap();
+    assert_eq!(inner.metadata.len(), 1);
+    assert_eq!(inner.metadata["k"], "v");
+    assert_eq!(inner.mode.to_unix(), 0o100755);
+}
+
+#[test]
+fn ser_without_metadata() {
+    let inner = Inner::new("x".to_string(), Mode::file());
+
+    let mut writer = Writer::new(vec![]);
+    writer.write(&inner).unwrap();
+
+    // no flag in the extension word, no count
+    let buf = writer.into_target();
+    assert_eq!(&buf[buf.len() - 4..], [0x00, 0x00, 0x00, 0x00]);
+
+    let mut reader = Reader::new(buf.as_slice());
+    let inner = reader.read::<Inner>().unwrap();
+    assert!(inner.metadata.is_empty());
+}
+
+#[test]
+fn ser_refs() {
+    let mut inner = Inner::new("x".to_string(), Mode::file());
+
+    inner.refs = 2;
+    inner.metadata.insert("k".to_string(), "v".to_string());
+
+    let mut writer = Writer::new(vec![]);
+    writer.write(&inner).unwrap();
+
+    let buf = writer.into_target();
+    assert_eq!(
+        &buf[buf.len() - 34..buf.len() - 26],
+        [0xC0, 0, 0, 0, 0, 0, 0, 2]
+    );
+
+    let mut reader = Reader::new(buf.as_slice());
+    let inner = reader.read::<Inner>().unwrap();
+    assert_eq!(inner.refs, 2);
+    assert_eq!(inner.metadata["k"], "v");
+    assert_eq!(inner.mode.to_unix(), 0o100755);
+}
+
+#[test]
+fn ser_section() {
+    let mut inner = Inner::new("x".to_string(), Mode::file());
+
+    inner.section = Some("s".to_string());
+
+    let mut writer = Writer::new(vec![]);
+    writer.write(&inner).unwrap();
+
+    let buf = writer.into_target();
+    assert_eq!(
+        &buf[buf.len() - 13..],
+        [0x20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, b's']
+    );
+
+    let mut reader = Reader::new(buf.as_slice());
+    let inner = reader.read::<Inner>().unwrap();
+    assert_eq!(inner.section.as_deref(), Some("s"));
+    assert_eq!(inner.mode.to_unix(), 0o100755);
+}
+
+#[test]
+fn ser_checksum() {
+    let mut inner = Inner::new("x".to_string(), Mode::file());
+
+    inner.checksum = Some([7; 32]);
+
+    let mut writer = Writer::new(vec![]);
+    writer.write(&inner).unwrap();
+
+    let buf = writer.into_target();
+    assert_eq!(&buf[buf.len() - 34..buf.len() - 32], [0, 0]);
+    assert_eq!(&buf[buf.len() - 32..], [7; 32]);
+
+    let mut reader = Reader::new(buf.as_slice());
+    let inner = reader.read::<Inner>().unwrap();
+    assert_eq!(inner.checksum, Some([7; 32]));
+    assert_eq!(inner.mode.to_unix(), 0o100755);
+}
+
+#[test]
+fn ser_previous_names() {
+    let mut inner = Inner::new("x".to_string(), Mode::file());
+
+    inner.previous_names = vec!["a".to_string(), "b".to_string()];
+
+    let mut writer = Writer::new(vec![]);
+    writer.write(&inner).unwrap();
+
+    let buf = writer.into_target();
+    assert_eq!(
+        &buf[buf.len() - 26..],
+        [0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 1, b'a', 0, 0, 0, 0, 0, 0, 0, 1, b'b']
+    );
+
+    let mut reader = Reader::new(buf.as_slice());
+    let inner = reader.read::<Inner>().unwrap();
+    assert_eq!(inner.previous_names, ["a", "b"]);
+    assert_eq!(inner.mode.to_unix(), 0o100755);
+}
+
+#[test]
+fn ser_name_blocks() {
+    let mut inner = Inner::new("x".repeat(600), Mode::file());
+
+    inner.name_blocks = 2;
+
+    let buf = inner.to_block(Codec::current()).unwrap();
+    assert_eq!(&buf[..8], [0; 8]);
+    assert_eq!(&buf[buf.len() - 4..], [0, 0, 0, 2]);
+
+    let mut reader = Reader::new(buf.as_slice());
+    let inner = reader.read::<Inner>().unwrap();
+    assert_eq!(inner.name, "");
+    assert_eq!(inner.name_blocks, 2);
+    assert_eq!(inner.mode.to_unix(), 0o100755);
+}
+
+#[test]
+fn ser_compression() {
+    let mut inner = Inner::new("x".to_string(), Mode::file());
+
+    inner.compression = Compression::Deflate;
+    inner.size = 3;
+    inner.raw_size = 7;
+
+    let mut writer = Writer::new(vec![]);
+    writer.write(&inner).unwrap();
+
+    let buf = writer.into_target();
+    assert_eq!(&buf[buf.len() - 9..], [1, 0, 0, 0, 0, 0, 0, 0, 7]);
+
+    let mut reader = Reader::new(buf.as_slice());
+    let inner = reader.read::<Inner>().unwrap();
+    assert_eq!(inner.compression, Compression::Deflate);
+    assert_eq!((inner.size, inner.raw_size), (3, 7));
+    assert_eq!(inner.content_size(), 7);
+    assert_eq!(inner.mode.to_unix(), 0o100755);
+}
+
+#[test]
+fn ser_transforms() {
+    let mut inner = Inner::new("x".to_string(), Mode::file());
+
+    inner.transforms = vec!["t".to_string()];
+    inner.size = 3;
+    inner.raw_size = 7;
+
+    let mut writer = Writer::new(vec![]);
+    writer.write(&inner).unwrap();
+
+    let buf = writer.into_target();
+    assert_eq!(
+        &buf[buf.len() - 25..],
+        [0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1, b't', 0, 0, 0, 0, 0, 0, 0, 7]
+    );
+
+    let mut reader = Reader::new(buf.as_slice());
+    let inner = reader.read::<Inner>().unwrap();
+    assert_eq!(inner.compression, Compression::None);
+    assert_eq!(inner.transforms, ["t"]);
+    assert_eq!((inner.size, inner.raw_size), (3, 7));
+    assert_eq!(inner.content_size(), 7);
+    assert_eq!(inner.mode.to_unix(), 0o100755);
+}
+
+#[test]
+fn ser_compression_transforms() {
+    let mut inner = Inner::new("x".to_string(), Mode::file());
+
+    inner.compression = Compression::Deflate;
+    inner.transforms = vec!["t".to_string(), "u".to_string()];
+    inner.size = 3;
+    inner.raw_size = 7;
+
+    let mut writer = Writer::new(vec![]);
+    writer.write(&inner).unwrap();
+
+    // the raw size is stored once, in the compression section
+    let buf = writer.into_target();
+    let mut reader = Reader::new(buf.as_slice());
+    let inner = reader.read::<Inner>().unwrap();
+    assert_eq!(inner.compression, Compression::Deflate);
+    assert_eq!(inner.transforms, ["t", "u"]);
+    assert_eq!((inner.size, inner.raw_size), (3, 7));
+    assert_eq!(inner.mode.to_unix(), 0o100755);
+}
+
+#[test]
+fn de_corrupted_name_len() {
+    let mut buf = vec![0xff; 8];
+    buf.extend_from_slice(b"garbage");
+
+    let mut reader = Reader::new(buf.as_slice());
+    let err = reader.read::<Inner>().unwrap_err();
+
+    assert!(matches!(
+        err,
+        nuts_bytes::Error::TakeBytes(nuts_bytes::TakeBytesError::Eof)
+    ));
+}
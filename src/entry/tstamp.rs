@@ -26,7 +26,7 @@ use std::mem;
 
 use crate::datetime;
 
-#[derive(Debug, FromBytes, ToBytes)]
+#[derive(Clone, Debug, FromBytes, ToBytes)]
 pub struct Timestamps {
     #[nuts_bytes(map = datetime)]
     appended: DateTime<Utc>,
@@ -24,6 +24,7 @@ use nuts_container::backend::Backend;
 use nuts_container::container;
 use thiserror::Error;
 
+use crate::unsupported::UnsupportedFeature;
 use crate::{header::HeaderMagicError, userdata::UserdataMagicError};
 
 /// Error type of this library.
@@ -33,6 +34,10 @@ pub enum Error<B: Backend> {
     #[error(transparent)]
     Bytes(nuts_bytes::Error),
 
+    /// An I/O error occured.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
     /// An error occured in a container operation.
     #[error(transparent)]
     Container(#[from] container::Error<B>),
@@ -54,15 +59,39 @@ pub enum Error<B: Backend> {
     #[error("could not parse the header of the archive")]
     InvalidHeader(nuts_bytes::Error),
 
+    /// The archive was written in a revision of the format, which is not
+    /// supported by this version.
+    #[error("unsupported revision {0} of the archive format")]
+    UnsupportedRevision(u16),
+
     /// Cannot aquire another block, the archive is full.
-    #[error("the archive is full")]
-    Full,
+    ///
+    /// The value is the number of bytes, which were successfully appended by
+    /// the failed operation before the archive ran out of space. The entry
+    /// is still consistent and contains all these bytes.
+    ///
+    /// The capacity is shared by all entries of the archive, a single entry
+    /// has no limit of its own. Splitting the content into several entries
+    /// does not help, release blocks (e.g. with
+    /// [`Archive::remove()`](crate::Archive::remove)) or use a larger block
+    /// size instead.
+    #[error("the archive is full, {0} bytes appended")]
+    Full(usize),
 
     /// The block size of the underlaying [container](container::Container) is
     /// too small.
     #[error("the block size is too small")]
     InvalidBlockSize,
 
+    /// The block size of the underlaying [container](container::Container) is
+    /// smaller than the [minimum](crate::Archive::min_block_size) required
+    /// by the archive.
+    ///
+    /// The values are the block size of the container and the minimum block
+    /// size.
+    #[error("the block size {0} is too small, at least {1} bytes are required")]
+    BlockSizeTooSmall(u32, u32),
+
     /// An error returned by
     /// [`FileEntry::read_all()`](crate::FileEntry::read_all) when the
     /// operation could not be completed because an “end of file” was reached
@@ -74,6 +103,182 @@ pub enum Error<B: Backend> {
     /// give block.
     #[error("could not detect the type of the entry {}", if let Some(id) = .0 { format!("stored in {}", id) } else { "in unknown block".to_string() })]
     InvalidType(Option<B::Id>),
+
+    /// A block read back after writing does not match the written data, see
+    /// [`Archive::set_write_verification()`](crate::Archive::set_write_verification).
+    ///
+    /// The values are the id of the block and the name of the entry, which
+    /// was written (if known).
+    #[error("verification of block {0} failed{}", .1.as_ref().map_or(String::new(), |name| format!(" (entry {})", name)))]
+    WriteMismatch(B::Id, Option<String>),
+
+    /// The name index of the archive is corrupted.
+    #[error("the name index is corrupted")]
+    InvalidNameIndex,
+
+    /// The block tree of the archive is corrupted: the block at the given
+    /// index should be aquired but is already in use.
+    ///
+    /// Only returned in [strict mode](crate::Archive::set_strict), otherwise
+    /// the anomaly is logged.
+    #[error("the block tree is inconsistent, block {0} is already in use")]
+    TreeInconsistent(u64),
+
+    /// The entry with the given name would be extracted outside of the
+    /// destination directory.
+    ///
+    /// The name is an absolute path, contains `..` components, the target
+    /// of a symlink escapes the destination (see
+    /// [`PathPolicy`](crate::PathPolicy)), a parent directory is a symlink
+    /// or the path of a directory is a symlink.
+    #[error("the path of {0} escapes the destination directory")]
+    UnsafePath(String),
+
+    /// The archive has no entry with the given name.
+    #[error("no such entry: {0}")]
+    NotFound(String),
+
+    /// The entry is not a file.
+    #[error("{0} is not a file")]
+    NotFile(String),
+
+    /// The entry cannot be reopened for appending, see
+    /// [`Archive::open_mut()`](crate::Archive::open_mut).
+    #[error("cannot append to {0}, only the last file entry can be reopened")]
+    NotAppendable(String),
+
+    /// The entry is marked immutable and cannot be modified or removed, see
+    /// [`Archive::set_immutable()`](crate::Archive::set_immutable).
+    #[error("{0} is immutable")]
+    Immutable(String),
+
+    /// The entry has external references (see
+    /// [`Archive::register_reference()`](crate::Archive::register_reference))
+    /// or is the target of a hard link, its blocks cannot be released.
+    #[error("{0} is still referenced")]
+    Referenced(String),
+
+    /// [`Archive::unregister_reference()`](crate::Archive::unregister_reference)
+    /// was called for an entry without external references.
+    #[error("{0} has no external references")]
+    NoReference(String),
+
+    /// The target of a symlink cannot be stored in the archive.
+    #[error("invalid symlink target: {0}")]
+    InvalidTarget(TargetError),
+
+    /// The name of an entry does not fit into a single block and the other
+    /// attributes leave no room to move it into continuation blocks.
+    ///
+    /// The value is the maximum length of a name at the block size of the
+    /// archive, which is stored in the entry block.
+    #[error("the name is too long, at most {0} bytes are allowed")]
+    NameTooLong(usize),
+
+    /// The attributes of an entry including its user metadata do not fit
+    /// into a single block.
+    ///
+    /// The values are the number of bytes needed by the entry and the block
+    /// size of the archive.
+    #[error("the entry needs {0} bytes, which exceeds the block size of {1} bytes")]
+    MetadataTooLarge(usize, u32),
+
+    /// A multipart upload could not be completed.
+    #[error("multipart upload failed: {0}")]
+    Multipart(MultipartError),
+
+    /// The archive is frozen and cannot be modified, see
+    /// [`Archive::freeze()`](crate::Archive::freeze).
+    #[error("the archive is frozen")]
+    Frozen,
+
+    /// The token passed to [`Archive::unfreeze()`](crate::Archive::unfreeze)
+    /// does not match the token, which froze the archive.
+    #[error("invalid token to unfreeze the archive")]
+    InvalidFreezeToken,
+
+    /// No savepoint with the given name exists, see
+    /// [`Archive::savepoint()`](crate::Archive::savepoint).
+    #[error("no such savepoint: {0}")]
+    NoSavepoint(String),
+
+    /// The entry was modified through a handle which was created before the
+    /// archive was modified the last time. The handle is stale and cannot be
+    /// used anymore.
+    #[error("the entry handle is stale")]
+    Stale,
+
+    /// A bundle does not match its manifest, see
+    /// [`Archive::import_bundle()`](crate::Archive::import_bundle).
+    ///
+    /// The value is the name of the entry, which differs from the manifest.
+    /// It is [`None`], if the manifest itself is corrupted or entries are
+    /// missing.
+    #[error("the bundle does not match its manifest{}", .0.as_ref().map(|n| format!(": {}", n)).unwrap_or_default())]
+    BundleMismatch(Option<String>),
+
+    /// An entry of an import source uses a feature, which cannot be
+    /// represented in the archive, see
+    /// [`Archive::set_unsupported_action()`](crate::Archive::set_unsupported_action).
+    ///
+    /// The values are the name of the entry and the feature.
+    #[error("{0}: unsupported {1}")]
+    Unsupported(String, UnsupportedFeature),
+
+    /// A block passed to
+    /// [`EntryMut::write_block()`](crate::EntryMut::write_block) is not
+    /// block-aligned.
+    ///
+    /// The values are the length of the block and the size of the entry,
+    /// where the block was about to be appended.
+    #[error("cannot append {0} bytes as a block at offset {1}")]
+    Unaligned(usize, u64),
+
+    /// The content of the entry is
+    /// [compressed](crate::FileBuilder::set_compression) and can only be
+    /// appended.
+    ///
+    /// The value is the name of the entry.
+    #[error("{0} is compressed, the content can only be appended")]
+    Compressed(String),
+
+    /// A chunk of a [chunked](crate::Archive::append_chunked) file is not
+    /// stored in the archive.
+    ///
+    /// The value is the name of the chunked file.
+    #[error("a chunk of {0} is missing")]
+    MissingChunk(String),
+
+    /// The content of a file does not match its
+    /// [stored checksum](crate::FileEntry::stored_checksum), see
+    /// [`FileEntry::verify()`](crate::FileEntry::verify).
+    ///
+    /// The value is the name of the file.
+    #[error("the content of {0} does not match its checksum")]
+    ChecksumMismatch(String),
+
+    /// The block size of the target container of
+    /// [`Archive::clone_to()`](crate::Archive::clone_to) differs from the
+    /// block size of the archive.
+    ///
+    /// The values are the block size of the target container and the block
+    /// size of the archive.
+    #[error("the block size {0} does not match the block size {1} of the archive")]
+    BlockSizeMismatch(u32, u32),
+
+    /// The content of a [transformed](crate::FileBuilder::add_transform)
+    /// entry cannot be changed in place, it can only be appended.
+    ///
+    /// The value is the name of the entry.
+    #[error("{0} is transformed, the content can only be appended")]
+    Transformed(String),
+
+    /// A [transformation](crate::Transform) of an entry is not
+    /// [registered](crate::Archive::register_transform) with the archive.
+    ///
+    /// The value is the name of the transformation.
+    #[error("the transformation {0} is not registered")]
+    UnknownTransform(String),
 }
 
 impl<B: Backend> From<nuts_bytes::Error> for Error<B> {
@@ -98,3 +303,59 @@ impl<B: Backend> From<nuts_bytes::Error> for Error<B> {
 }
 
 pub type ArchiveResult<T, B> = Result<T, Error<B>>;
+
+/// The reason why a symlink target was rejected.
+///
+/// Attached to the [`Error::InvalidTarget`] variant.
+#[derive(Debug, Error, PartialEq)]
+pub enum TargetError {
+    /// The target is longer than allowed. The first value is the length of
+    /// the target, the second value is the configured maximum length.
+    #[error("the target has {0} bytes, at most {1} bytes are allowed")]
+    TooLong(usize, usize),
+
+    /// The target contains a NUL byte.
+    #[error("the target contains a NUL byte")]
+    Nul,
+}
+
+/// Error type of [`Archive::rewrite_to()`](crate::Archive::rewrite_to).
+///
+/// The error is either raised by the source archive or by the target
+/// archive.
+#[derive(Debug, Error)]
+pub enum RewriteError<S: Backend, T: Backend> {
+    /// An error occured in the source archive.
+    #[error(transparent)]
+    Source(Error<S>),
+
+    /// An error occured in the target archive.
+    #[error(transparent)]
+    Target(Error<T>),
+}
+
+/// The reason why a [`MultipartEntry`](crate::MultipartEntry) failed.
+///
+/// Attached to the [`Error::Multipart`] variant.
+#[derive(Debug, Error, PartialEq)]
+pub enum MultipartError {
+    /// The part does not fit into the declared size of the entry. The values
+    /// are the offset and length of the part and the declared size.
+    #[error("the part at {0} with {1} bytes exceeds the declared size of {2} bytes")]
+    OutOfRange(u64, usize, u64),
+
+    /// Not all parts were written, the value is the number of missing bytes.
+    #[error("{0} bytes are missing")]
+    Incomplete(u64),
+
+    /// The digest of the content does not match the expected digest.
+    #[error("digest mismatch")]
+    DigestMismatch,
+}
+
+/// Error returned when parsing a mode string like `drwxr-xr-x` failed.
+///
+/// The value is the rejected mode string.
+#[derive(Debug, Error, PartialEq)]
+#[error("invalid mode string: {0}")]
+pub struct ParseModeError(pub String);
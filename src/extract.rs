@@ -0,0 +1,565 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use log::debug;
+use nuts_container::backend::Backend;
+use std::borrow::Cow;
+use std::fs::{self, OpenOptions};
+use std::io::{self, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::str;
+use std::time::SystemTime;
+
+use crate::entry::immut::{Entry, FileEntry, InnerEntry};
+use crate::entry::mode::Mode;
+use crate::entry::{ImplicitDirs, Inner};
+use crate::error::{ArchiveResult, Error};
+use crate::pager::Pager;
+use crate::tree::Tree;
+
+/// Size of the buffer used to copy the content of a file.
+const COPY_BUF_SIZE: usize = 64 * 1024;
+
+/// Tests whether `name` matches the glob `pattern`.
+///
+/// A `*` matches any sequence of characters (including `/`), a `?` matches
+/// any single character. All other characters must match exactly.
+pub(crate) fn matches(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((bp, bn)) = backtrack {
+            p = bp + 1;
+            n = bn + 1;
+            backtrack = Some((bp, bn + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn matches_any<P: AsRef<str>>(patterns: &[P], name: &str) -> bool {
+    patterns.is_empty() || patterns.iter().any(|p| matches(p.as_ref(), name))
+}
+
+/// How entry names and symlink targets, which would escape the destination
+/// directory, are handled when extracting entries.
+///
+/// An entry name escapes the destination, if it is an absolute path or
+/// contains `..` components. A symlink target escapes the destination, if
+/// it is an absolute path or its `..` components lead outside of the
+/// destination. See
+/// [`Archive::set_path_policy()`](crate::Archive::set_path_policy).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PathPolicy {
+    /// The extraction fails with an [`Error::UnsafePath`] error.
+    #[default]
+    Reject,
+
+    /// A leading `/` and all `..` components are removed from the name, so
+    /// `../../etc/passwd` is extracted as `etc/passwd` below the
+    /// destination.
+    ///
+    /// An escaping symlink target is rewritten into a relative target
+    /// below the destination: an absolute target is resolved against the
+    /// destination, `..` components, which would leave it, are dropped.
+    Sanitize,
+}
+
+/// Converts the entry `name` into a path below `dest` according to
+/// `policy`.
+pub(crate) fn entry_path<B: Backend>(
+    dest: &Path,
+    name: &str,
+    policy: PathPolicy,
+) -> ArchiveResult<PathBuf, B> {
+    let mut path = PathBuf::new();
+
+    for component in Path::new(name).components() {
+        match component {
+            Component::Normal(s) => path.push(s),
+            Component::CurDir => {}
+            Component::Prefix(_) | Component::RootDir | Component::ParentDir => {
+                if policy == PathPolicy::Reject {
+                    return Err(Error::UnsafePath(name.to_string()));
+                }
+            }
+        }
+    }
+
+    if path.as_os_str().is_empty() {
+        return Err(Error::UnsafePath(name.to_string()));
+    }
+
+    Ok(dest.join(path))
+}
+
+/// Converts the `target` of the symlink, which is extracted into `path`,
+/// according to `policy`.
+///
+/// A target, which is an absolute path or escapes `dest` with `..`
+/// components, is rejected or rewritten into a relative target below
+/// `dest`. Other targets are kept as they are.
+///
+/// A `..` component is resolved by the filesystem against the component
+/// before it. If that is a symlink (or becomes one later), `..` leaves the
+/// directory of the link's target, not the directory seen in the text. So
+/// a `..` behind a component, which is not an existing directory, counts as
+/// an escape.
+pub(crate) fn link_target<'a, B: Backend>(
+    dest: &Path,
+    path: &Path,
+    target: &'a [u8],
+    name: &str,
+    policy: PathPolicy,
+) -> ArchiveResult<Cow<'a, [u8]>, B> {
+    // number of directories between dest and the symlink
+    let depth = path
+        .strip_prefix(dest)
+        .map_or(0, |p| p.components().count().saturating_sub(1));
+    let absolute = target.starts_with(b"/");
+    let mut ups = if absolute { depth } else { 0 };
+    let mut parts: Vec<&[u8]> = vec![];
+    let mut escapes = absolute;
+
+    // the directory, the parts are relative to
+    let mut base = match path.parent() {
+        Some(parent) if !absolute => parent.to_path_buf(),
+        _ => dest.to_path_buf(),
+    };
+
+    for part in target.split(|c| *c == b'/') {
+        match part {
+            b"" | b"." => {}
+            b".." => {
+                if !parts.is_empty() {
+                    if !is_directory(&base, &parts) {
+                        escapes = true;
+                    }
+
+                    parts.pop();
+                } else if ups < depth {
+                    ups += 1;
+                    base.pop();
+                } else {
+                    escapes = true;
+                }
+            }
+            _ => parts.push(part),
+        }
+    }
+
+    if !escapes {
+        return Ok(Cow::Borrowed(target));
+    }
+
+    if policy == PathPolicy::Reject {
+        return Err(Error::UnsafePath(name.to_string()));
+    }
+
+    let mut sanitized = b"../".repeat(ups);
+
+    sanitized.extend_from_slice(&parts.join(&b'/'));
+
+    if sanitized.is_empty() {
+        sanitized.push(b'.');
+    } else if parts.is_empty() {
+        sanitized.pop();
+    }
+
+    Ok(Cow::Owned(sanitized))
+}
+
+/// Tests whether `parts` below `base` is an existing directory, which is not
+/// a symlink.
+fn is_directory(base: &Path, parts: &[&[u8]]) -> bool {
+    let mut path = base.to_path_buf();
+
+    for part in parts {
+        match str::from_utf8(part) {
+            Ok(s) => path.push(s),
+            Err(_) => return false,
+        }
+    }
+
+    match fs::symlink_metadata(path) {
+        Ok(meta) => meta.is_dir(),
+        Err(_) => false,
+    }
+}
+
+fn is_symlink(path: &Path) -> bool {
+    match fs::symlink_metadata(path) {
+        Ok(meta) => meta.file_type().is_symlink(),
+        Err(_) => false,
+    }
+}
+
+/// Ensures that no parent of `path` below `dest` is a symlink, otherwise
+/// the entry `name` would be extracted somewhere else.
+fn check_parents<B: Backend>(dest: &Path, path: &Path, name: &str) -> ArchiveResult<(), B> {
+    let symlink = path
+        .ancestors()
+        .skip(1)
+        .take_while(|p| p.starts_with(dest) && *p != dest)
+        .any(is_symlink);
+
+    if symlink {
+        Err(Error::UnsafePath(name.to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Ensures that `path` itself is not a symlink.
+///
+/// Files and links replace a symlink at their path, but a directory or the
+/// target of a hard link would follow it.
+fn check_leaf<B: Backend>(path: &Path, name: &str) -> ArchiveResult<(), B> {
+    if is_symlink(path) {
+        Err(Error::UnsafePath(name.to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+/// The action, which would be performed on the filesystem when extracting
+/// an entry.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlanAction {
+    /// The path does not exist and is created.
+    Create,
+
+    /// The path already exists and is overwritten.
+    Overwrite,
+
+    /// The directory already exists, nothing needs to be done.
+    Exists,
+
+    /// The path cannot be created, because it (or one of its parents) exists
+    /// with an incompatible type.
+    Conflict,
+}
+
+/// A single item of an [`ExtractPlan`].
+#[derive(Debug)]
+pub struct PlanItem {
+    /// Name of the entry in the archive
+    pub name: String,
+
+    /// Path of the entry on the filesystem
+    pub path: PathBuf,
+
+    /// Number of bytes which are written into the path
+    pub size: u64,
+
+    /// The action performed on the path
+    pub action: PlanAction,
+}
+
+impl PlanItem {
+    fn new(dest: &Path, path: PathBuf, inner: &Inner) -> PlanItem {
+        let action = plan_action(dest, &path, &inner.mode);
+
+        debug!("plan {} => {} ({:?})", inner.name, path.display(), action);
+
+        PlanItem {
+            name: inner.name.clone(),
+            path,
+            size: if inner.mode.is_directory()
+                || inner.mode.is_hardlink()
+                || inner.mode.is_special()
+            {
+                0
+            } else {
+                inner.content_size()
+            },
+            action,
+        }
+    }
+}
+
+/// A preview of an extraction, created by
+/// [`Archive::plan_extract()`](crate::Archive::plan_extract).
+///
+/// Parent directories, which are not stored in the archive, are added to the
+/// plan in front of their first selected child.
+#[derive(Debug)]
+pub struct ExtractPlan {
+    /// All entries selected for extraction, in archive order
+    pub items: Vec<PlanItem>,
+}
+
+impl ExtractPlan {
+    pub(crate) fn create<B: Backend, P: AsRef<str>>(
+        pager: &mut Pager<B>,
+        tree: &mut Tree<B>,
+        dest: &Path,
+        patterns: &[P],
+        policy: PathPolicy,
+    ) -> ArchiveResult<ExtractPlan, B> {
+        let mut items = vec![];
+        let mut implicit_dirs = ImplicitDirs::new();
+        let mut entry_opt = InnerEntry::first(pager, tree);
+
+        loop {
+            let entry = match entry_opt {
+                Some(Ok(entry)) => entry,
+                Some(Err(err)) => return Err(err),
+                None => break,
+            };
+
+            let inner = entry.inner();
+
+            if !inner.mode.is_internal() {
+                if matches_any(patterns, &inner.name) {
+                    let path = entry_path(dest, &inner.name, policy)?;
+
+                    // A sanitized parent like `..` maps to `dest` itself,
+                    // which needs no plan item.
+                    for dir in implicit_dirs.missing(inner) {
+                        if let Ok(path) = entry_path::<B>(dest, &dir.name, policy) {
+                            items.push(PlanItem::new(dest, path, &dir));
+                        }
+                    }
+
+                    items.push(PlanItem::new(dest, path, inner));
+                } else {
+                    implicit_dirs.seen(inner);
+                }
+            }
+
+            entry_opt = entry.next();
+        }
+
+        Ok(ExtractPlan { items })
+    }
+
+    /// Returns the number of bytes written by the extraction.
+    pub fn total_size(&self) -> u64 {
+        self.items.iter().map(|item| item.size).sum()
+    }
+
+    /// Returns all items which cannot be extracted.
+    pub fn conflicts(&self) -> impl Iterator<Item = &PlanItem> {
+        self.items
+            .iter()
+            .filter(|item| item.action == PlanAction::Conflict)
+    }
+
+    /// Tests whether the plan has conflicts.
+    pub fn has_conflicts(&self) -> bool {
+        self.conflicts().next().is_some()
+    }
+}
+
+fn parent_conflict(dest: &Path, path: &Path) -> bool {
+    path.ancestors()
+        .skip(1)
+        .take_while(|p| p.starts_with(dest) && *p != dest)
+        .any(|p| match fs::symlink_metadata(p) {
+            Ok(meta) => !meta.is_dir(),
+            Err(_) => false,
+        })
+}
+
+fn plan_action(dest: &Path, path: &Path, mode: &Mode) -> PlanAction {
+    if parent_conflict(dest, path) {
+        return PlanAction::Conflict;
+    }
+
+    match fs::symlink_metadata(path) {
+        Ok(meta) => {
+            if mode.is_directory() {
+                if meta.is_dir() {
+                    PlanAction::Exists
+                } else {
+                    PlanAction::Conflict
+                }
+            } else if meta.is_dir() {
+                PlanAction::Conflict
+            } else {
+                PlanAction::Overwrite
+            }
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => PlanAction::Create,
+        Err(_) => PlanAction::Conflict,
+    }
+}
+
+fn create_parent(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) => fs::create_dir_all(parent),
+        None => Ok(()),
+    }
+}
+
+/// Removes a file or symlink at `path`, so that a file or link can be
+/// created.
+fn remove_existing(path: &Path) -> io::Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if !meta.is_dir() => fs::remove_file(path),
+        Ok(_) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o7777))
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> io::Result<()> {
+    Ok(())
+}
+
+/// Writes the whole content of `file` into `path`.
+///
+/// An existing file or symlink at `path` is replaced, the content is never
+/// written through a symlink.
+pub(crate) fn unpack_file<B: Backend>(
+    file: &mut FileEntry<'_, B>,
+    path: &Path,
+) -> ArchiveResult<(), B> {
+    create_parent(path)?;
+    remove_existing(path)?;
+    file.seek(SeekFrom::Start(0))?;
+
+    let mut target = OpenOptions::new().write(true).create_new(true).open(path)?;
+    let mut buf = vec![0; COPY_BUF_SIZE];
+
+    loop {
+        let n = file.read(&mut buf)?;
+
+        if n == 0 {
+            break;
+        }
+
+        target.write_all(&buf[..n])?;
+    }
+
+    target.set_modified(SystemTime::from(*file.modified()))?;
+    set_mode(path, file.unix_mode())?;
+
+    Ok(())
+}
+
+/// Creates the directory `path` (and its parents) with the given `mode`.
+pub(crate) fn unpack_directory(path: &Path, mode: u32) -> io::Result<()> {
+    fs::create_dir_all(path)?;
+    set_mode(path, mode)
+}
+
+#[cfg(unix)]
+pub(crate) fn unpack_symlink(target: &[u8], path: &Path) -> io::Result<()> {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    create_parent(path)?;
+    remove_existing(path)?;
+
+    std::os::unix::fs::symlink(OsStr::from_bytes(target), path)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn unpack_symlink(_target: &[u8], path: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{}: symlinks are not supported", path.display()),
+    ))
+}
+
+fn unpack_hardlink(target: &Path, path: &Path) -> io::Result<()> {
+    create_parent(path)?;
+    remove_existing(path)?;
+
+    fs::hard_link(target, path)
+}
+
+/// Extracts all entries starting at `entry_opt` into the directory `dest`.
+pub(crate) fn unpack<B: Backend>(
+    mut entry_opt: Option<ArchiveResult<Entry<'_, B>, B>>,
+    dest: &Path,
+    policy: PathPolicy,
+) -> ArchiveResult<(), B> {
+    let mut dirs = vec![];
+
+    while let Some(result) = entry_opt {
+        let mut entry = result?;
+        let path = entry_path(dest, entry.name(), policy)?;
+
+        check_parents(dest, &path, entry.name())?;
+
+        debug!("unpack {} => {}", entry.name(), path.display());
+
+        match &mut entry {
+            Entry::File(file) => unpack_file(file, &path)?,
+            Entry::Directory(dir) => {
+                check_leaf(&path, dir.name())?;
+                fs::create_dir_all(&path)?;
+                dirs.push((path, dir.unix_mode()));
+            }
+            Entry::Symlink(symlink) => {
+                let target =
+                    link_target(dest, &path, symlink.target_bytes(), symlink.name(), policy)?;
+
+                unpack_symlink(&target, &path)?
+            }
+            Entry::HardLink(link) => {
+                let target = entry_path(dest, link.target(), policy)?;
+
+                check_parents(dest, &target, link.target())?;
+                check_leaf(&target, link.target())?;
+                unpack_hardlink(&target, &path)?
+            }
+            Entry::Special(special) => debug!("special file {} skipped", special.name()),
+        }
+
+        entry_opt = entry.next();
+    }
+
+    // a read-only directory would prevent the creation of its children, so
+    // the access rights of the directories are applied last
+    for (path, mode) in dirs.iter().rev() {
+        set_mode(path, *mode)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,316 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use nuts_container::memory::MemoryBackend;
+use std::fs;
+use std::path::Path;
+use tempdir::TempDir;
+
+use crate::error::Error;
+use crate::extract::{entry_path, link_target, matches, PathPolicy, PlanAction};
+use crate::tests::setup_container_with_bsize;
+use crate::Archive;
+
+fn setup_archive() -> Archive<nuts_container::memory::MemoryBackend> {
+    let container = setup_container_with_bsize(106);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.append_directory("d1").build().unwrap();
+
+    let mut entry = archive.append_file("d1/f1.txt").build().unwrap();
+    entry.write_all(b"12345").unwrap();
+
+    let mut entry = archive.append_file("f2.bin").build().unwrap();
+    entry.write_all(b"123").unwrap();
+
+    archive.append_symlink("s1", "f2.bin").build().unwrap();
+
+    archive
+}
+
+#[test]
+fn matches_exact() {
+    assert!(matches("", ""));
+    assert!(matches("abc", "abc"));
+    assert!(!matches("abc", "abd"));
+    assert!(!matches("abc", "ab"));
+    assert!(!matches("ab", "abc"));
+}
+
+#[test]
+fn matches_question_mark() {
+    assert!(matches("a?c", "abc"));
+    assert!(!matches("a?c", "ac"));
+    assert!(!matches("?", ""));
+}
+
+#[test]
+fn matches_star() {
+    assert!(matches("*", ""));
+    assert!(matches("*", "a/b/c"));
+    assert!(matches("*.txt", "a/b.txt"));
+    assert!(matches("a*b*c", "axxbyyc"));
+    assert!(matches("a*b*c", "abcbc"));
+    assert!(!matches("a*b*c", "axxbyy"));
+    assert!(!matches("*.txt", "a.txt.bin"));
+}
+
+#[test]
+fn all_create() {
+    let dir = TempDir::new("nuts-archive").unwrap();
+    let mut archive = setup_archive();
+    let plan = archive.plan_extract(dir.path(), &[] as &[&str]).unwrap();
+
+    assert_eq!(plan.items.len(), 4);
+    assert_eq!(plan.total_size(), 5 + 3 + 6);
+    assert!(!plan.has_conflicts());
+
+    let names = plan
+        .items
+        .iter()
+        .map(|i| i.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["d1", "d1/f1.txt", "f2.bin", "s1"]);
+
+    for item in plan.items.iter() {
+        assert_eq!(item.path, dir.path().join(&item.name));
+        assert_eq!(item.action, PlanAction::Create);
+    }
+}
+
+#[test]
+fn patterns() {
+    let dir = TempDir::new("nuts-archive").unwrap();
+    let mut archive = setup_archive();
+    let plan = archive.plan_extract(dir.path(), &["*.txt", "s?"]).unwrap();
+
+    let names = plan
+        .items
+        .iter()
+        .map(|i| i.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["d1/f1.txt", "s1"]);
+}
+
+#[test]
+fn existing() {
+    let dir = TempDir::new("nuts-archive").unwrap();
+
+    fs::create_dir(dir.path().join("d1")).unwrap();
+    fs::write(dir.path().join("f2.bin"), b"xxx").unwrap();
+    fs::create_dir(dir.path().join("s1")).unwrap();
+
+    let mut archive = setup_archive();
+    let plan = archive.plan_extract(dir.path(), &[] as &[&str]).unwrap();
+
+    let actions = plan.items.iter().map(|i| i.action).collect::<Vec<_>>();
+    assert_eq!(
+        actions,
+        [
+            PlanAction::Exists,
+            PlanAction::Create,
+            PlanAction::Overwrite,
+            PlanAction::Conflict
+        ]
+    );
+
+    let conflicts = plan
+        .conflicts()
+        .map(|i| i.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(conflicts, ["s1"]);
+}
+
+#[test]
+fn parent_conflict() {
+    let dir = TempDir::new("nuts-archive").unwrap();
+
+    fs::write(dir.path().join("d1"), b"xxx").unwrap();
+
+    let mut archive = setup_archive();
+    let plan = archive.plan_extract(dir.path(), &[] as &[&str]).unwrap();
+
+    let conflicts = plan
+        .conflicts()
+        .map(|i| i.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(conflicts, ["d1", "d1/f1.txt"]);
+}
+
+#[test]
+fn implicit_dirs() {
+    let dir = TempDir::new("nuts-archive").unwrap();
+    let mut archive = setup_archive();
+
+    archive.append_file("d2/d3/f3.txt").build().unwrap();
+    archive.append_file("d2/f4.bin").build().unwrap();
+
+    let plan = archive.plan_extract(dir.path(), &["d2/*"]).unwrap();
+
+    let names = plan
+        .items
+        .iter()
+        .map(|i| i.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["d2", "d2/d3", "d2/d3/f3.txt", "d2/f4.bin"]);
+
+    for item in plan.items.iter() {
+        assert_eq!(item.path, dir.path().join(&item.name));
+        assert_eq!(item.action, PlanAction::Create);
+    }
+}
+
+#[test]
+fn entry_path_relative() {
+    for policy in [PathPolicy::Reject, PathPolicy::Sanitize] {
+        let path = entry_path::<MemoryBackend>(Path::new("/dest"), "./a/b/", policy).unwrap();
+        assert_eq!(path, Path::new("/dest/a/b"));
+    }
+}
+
+#[test]
+fn entry_path_reject() {
+    for name in ["../a", "a/../../b", "/etc/passwd", "", ".", "/"] {
+        let err =
+            entry_path::<MemoryBackend>(Path::new("/dest"), name, PathPolicy::Reject).unwrap_err();
+        assert!(matches!(err, Error::UnsafePath(ref n) if n == name));
+    }
+}
+
+#[test]
+fn entry_path_sanitize() {
+    for (name, expected) in [
+        ("../a", "/dest/a"),
+        ("a/../../b", "/dest/a/b"),
+        ("/etc/passwd", "/dest/etc/passwd"),
+    ] {
+        let path =
+            entry_path::<MemoryBackend>(Path::new("/dest"), name, PathPolicy::Sanitize).unwrap();
+        assert_eq!(path, Path::new(expected));
+    }
+
+    for name in ["", "..", "/"] {
+        let err = entry_path::<MemoryBackend>(Path::new("/dest"), name, PathPolicy::Sanitize)
+            .unwrap_err();
+        assert!(matches!(err, Error::UnsafePath(_)));
+    }
+}
+
+fn target(path: &str, target: &str, policy: PathPolicy) -> Result<String, String> {
+    link_target::<MemoryBackend>(
+        Path::new("/dest"),
+        &Path::new("/dest").join(path),
+        target.as_bytes(),
+        path,
+        policy,
+    )
+    .map(|t| String::from_utf8(t.into_owned()).unwrap())
+    .map_err(|err| match err {
+        Error::UnsafePath(name) => name,
+        err => panic!("unexpected error: {}", err),
+    })
+}
+
+#[test]
+fn link_target_inside() {
+    for policy in [PathPolicy::Reject, PathPolicy::Sanitize] {
+        for (path, t) in [
+            ("s", "f"),
+            ("d/s", "../f"),
+            ("d/e/s", "../../f/"),
+            ("d/s", ".."),
+        ] {
+            assert_eq!(target(path, t, policy).unwrap(), t);
+        }
+    }
+}
+
+#[test]
+fn link_target_reject() {
+    for (path, t) in [
+        ("s", "/etc/passwd"),
+        ("s", ".."),
+        ("d/s", "../../f"),
+        ("s", "a/../../f"),
+        ("s", "./a/../f"),
+    ] {
+        assert_eq!(target(path, t, PathPolicy::Reject).unwrap_err(), path);
+    }
+}
+
+#[test]
+fn link_target_sanitize() {
+    for (path, t, expected) in [
+        ("s", "/etc/passwd", "etc/passwd"),
+        ("d/e/s", "/etc/passwd", "../../etc/passwd"),
+        ("s", "/", "."),
+        ("d/s", "/", ".."),
+        ("s", "..", "."),
+        ("d/s", "../../f", "../f"),
+        ("s", "a/../../f", "f"),
+        ("s", "./a/../f", "f"),
+    ] {
+        assert_eq!(target(path, t, PathPolicy::Sanitize).unwrap(), expected);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn link_target_parent_of_symlink() {
+    let dir = TempDir::new("nuts-archive").unwrap();
+    let dest = dir.path();
+
+    fs::create_dir(dest.join("a")).unwrap();
+    std::os::unix::fs::symlink("..", dest.join("b")).unwrap();
+
+    let target = |t: &str, policy| {
+        link_target::<MemoryBackend>(dest, &dest.join("s"), t.as_bytes(), "s", policy)
+            .map(|t| String::from_utf8(t.into_owned()).unwrap())
+    };
+
+    // a is a directory, b is a symlink, whose parent is outside dest
+    assert_eq!(target("a/../f", PathPolicy::Reject).unwrap(), "a/../f");
+    assert!(matches!(
+        target("b/../f", PathPolicy::Reject),
+        Err(Error::UnsafePath(ref name)) if name == "s"
+    ));
+    assert_eq!(target("b/../f", PathPolicy::Sanitize).unwrap(), "f");
+}
+
+#[test]
+fn unsafe_plan() {
+    let dir = TempDir::new("nuts-archive").unwrap();
+    let mut archive = setup_archive();
+
+    archive.append_file("../evil").build().unwrap();
+
+    let err = archive
+        .plan_extract(dir.path(), &[] as &[&str])
+        .unwrap_err();
+    assert!(matches!(err, Error::UnsafePath(ref name) if name == "../evil"));
+
+    archive.set_path_policy(PathPolicy::Sanitize);
+
+    let plan = archive.plan_extract(dir.path(), &["*evil"]).unwrap();
+    assert_eq!(plan.items.len(), 1);
+    assert_eq!(plan.items[0].path, dir.path().join("evil"));
+}
@@ -0,0 +1,437 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+//! A backend wrapper, which injects failures on schedule.
+//!
+//! The module is only available if the `faulty` feature is enabled. A
+//! [`FaultyBackend`] wraps another backend and passes all operations to it.
+//! Using the [`Faults`] handle you schedule [`Fault`]s for upcoming
+//! operations, which lets you exercise the error and recovery paths of an
+//! archive (or your application) deterministically.
+//!
+//! ```rust
+//! use nuts_archive::faulty::{Fault, Faults, FaultyBackend, FaultyCreateOptions, Op};
+//! use nuts_archive::{Archive, Error};
+//! use nuts_container::container::{Cipher, Container, CreateOptionsBuilder};
+//! use nuts_container::memory::MemoryBackend;
+//!
+//! let faults = Faults::new();
+//! let backend_options = FaultyCreateOptions::new(MemoryBackend::new(), faults.clone());
+//! let options = CreateOptionsBuilder::new(Cipher::None)
+//!     .build::<FaultyBackend<MemoryBackend>>()
+//!     .unwrap();
+//! let container =
+//!     Container::<FaultyBackend<MemoryBackend>>::create(backend_options, options).unwrap();
+//! let mut archive = Archive::create(container, false).unwrap();
+//!
+//! // The next write operation fails.
+//! faults.inject(Op::Write, 0, Fault::Fail);
+//!
+//! let err = archive.append_file("sample").build().err().unwrap();
+//! assert!(matches!(err, Error::Container(_)));
+//! ```
+
+#[cfg(test)]
+mod tests;
+
+use nuts_container::backend::{Backend, Create, HeaderGet, HeaderSet, Open, HEADER_MAX_SIZE};
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use thiserror::Error;
+
+/// An operation of the backend.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Op {
+    /// [`Backend::aquire()`]
+    Aquire,
+
+    /// [`Backend::release()`]
+    Release,
+
+    /// [`Backend::read()`]
+    Read,
+
+    /// [`Backend::write()`]
+    Write,
+}
+
+impl Op {
+    fn index(self) -> usize {
+        match self {
+            Op::Aquire => 0,
+            Op::Release => 1,
+            Op::Read => 2,
+            Op::Write => 3,
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Op::Aquire => "aquire",
+            Op::Release => "release",
+            Op::Read => "read",
+            Op::Write => "write",
+        };
+
+        fmt.write_str(s)
+    }
+}
+
+/// A fault injected into an operation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fault {
+    /// The operation fails with [`FaultyError::Injected`], the inner backend
+    /// is not called.
+    Fail,
+
+    /// Only the first half of the block is transferred.
+    ///
+    /// * A write keeps the previous content in the second half of the block.
+    /// * An aquired block is zeroed in its second half.
+    /// * A read zeroes the second half of the buffer.
+    ///
+    /// The operation reports the number of bytes actually transferred. A
+    /// release is passed to the inner backend unchanged.
+    Short,
+
+    /// The first byte of the block is inverted.
+    ///
+    /// Affects the data passed to the inner backend by a write or aquire
+    /// and the data returned by a read. A release is passed to the inner
+    /// backend unchanged.
+    Corrupt,
+}
+
+#[derive(Debug, Default)]
+struct Schedule {
+    counts: [u64; 4],
+    pending: Vec<(Op, u64, Fault)>,
+}
+
+impl Schedule {
+    fn next(&mut self, op: Op) -> Option<Fault> {
+        let n = self.counts[op.index()];
+        self.counts[op.index()] += 1;
+
+        let pos = self
+            .pending
+            .iter()
+            .position(|(o, at, _)| *o == op && *at == n)?;
+
+        Some(self.pending.remove(pos).2)
+    }
+}
+
+/// Handle to the fault schedule of a [`FaultyBackend`].
+///
+/// The handle is cheap to clone, all clones share the same schedule. Keep a
+/// clone before passing the backend options to the container, so that you
+/// can inject faults later.
+#[derive(Clone, Debug, Default)]
+pub struct Faults(Arc<Mutex<Schedule>>);
+
+impl Faults {
+    /// Creates a new handle with an empty schedule.
+    pub fn new() -> Faults {
+        Default::default()
+    }
+
+    /// Schedules a `fault` for an upcoming `op` operation.
+    ///
+    /// `skip` operations of the same kind pass unchanged, the operation
+    /// after them is hit by `fault`. Every scheduled fault is injected only
+    /// once.
+    pub fn inject(&self, op: Op, skip: u64, fault: Fault) {
+        let mut schedule = self.lock();
+        let at = schedule.counts[op.index()] + skip;
+
+        schedule.pending.push((op, at, fault));
+    }
+
+    /// Returns the number of `op` operations performed so far.
+    pub fn count(&self, op: Op) -> u64 {
+        self.lock().counts[op.index()]
+    }
+
+    /// Returns the number of faults, which were not injected yet.
+    pub fn pending(&self) -> usize {
+        self.lock().pending.len()
+    }
+
+    /// Removes all scheduled faults.
+    pub fn clear(&self) {
+        self.lock().pending.clear();
+    }
+
+    fn next(&self, op: Op) -> Option<Fault> {
+        self.lock().next(op)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Schedule> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// Error type of the [`FaultyBackend`].
+#[derive(Error)]
+pub enum FaultyError<B: Backend> {
+    /// An error of the inner backend.
+    #[error(transparent)]
+    Backend(B::Err),
+
+    /// A [`Fault::Fail`] was injected into the operation.
+    #[error("injected failure of {0} operation")]
+    Injected(Op),
+}
+
+impl<B: Backend> fmt::Debug for FaultyError<B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Backend(err) => fmt.debug_tuple("Backend").field(err).finish(),
+            Self::Injected(op) => fmt.debug_tuple("Injected").field(op).finish(),
+        }
+    }
+}
+
+fn half(buf: &[u8]) -> usize {
+    buf.len() / 2
+}
+
+fn corrupt(buf: &mut [u8]) {
+    if let Some(n) = buf.first_mut() {
+        *n = !*n;
+    }
+}
+
+/// Options used to create a [`FaultyBackend`].
+pub struct FaultyCreateOptions<B: Backend> {
+    inner: B::CreateOptions,
+    faults: Faults,
+}
+
+impl<B: Backend> FaultyCreateOptions<B> {
+    /// Wraps the create options of the `inner` backend, faults are taken
+    /// from `faults`.
+    pub fn new(inner: B::CreateOptions, faults: Faults) -> FaultyCreateOptions<B> {
+        FaultyCreateOptions { inner, faults }
+    }
+}
+
+impl<B: Backend> HeaderSet<FaultyBackend<B>> for FaultyCreateOptions<B> {
+    fn put_header_bytes(&mut self, bytes: &[u8; HEADER_MAX_SIZE]) -> Result<(), FaultyError<B>> {
+        self.inner
+            .put_header_bytes(bytes)
+            .map_err(FaultyError::Backend)
+    }
+}
+
+impl<B: Backend> Create<FaultyBackend<B>> for FaultyCreateOptions<B> {
+    fn settings(&self) -> B::Settings {
+        self.inner.settings()
+    }
+
+    fn build(self) -> Result<FaultyBackend<B>, FaultyError<B>> {
+        let inner = self.inner.build().map_err(FaultyError::Backend)?;
+
+        Ok(FaultyBackend::new(inner, self.faults))
+    }
+}
+
+/// Options used to open a [`FaultyBackend`].
+pub struct FaultyOpenOptions<B: Backend> {
+    inner: B::OpenOptions,
+    faults: Faults,
+}
+
+impl<B: Backend> FaultyOpenOptions<B> {
+    /// Wraps the open options of the `inner` backend, faults are taken from
+    /// `faults`.
+    pub fn new(inner: B::OpenOptions, faults: Faults) -> FaultyOpenOptions<B> {
+        FaultyOpenOptions { inner, faults }
+    }
+}
+
+impl<B: Backend> HeaderGet<FaultyBackend<B>> for FaultyOpenOptions<B> {
+    fn get_header_bytes(
+        &mut self,
+        bytes: &mut [u8; HEADER_MAX_SIZE],
+    ) -> Result<(), FaultyError<B>> {
+        self.inner
+            .get_header_bytes(bytes)
+            .map_err(FaultyError::Backend)
+    }
+}
+
+impl<B: Backend> Open<FaultyBackend<B>> for FaultyOpenOptions<B> {
+    fn build(self, settings: B::Settings) -> Result<FaultyBackend<B>, FaultyError<B>> {
+        let inner = self.inner.build(settings).map_err(FaultyError::Backend)?;
+
+        Ok(FaultyBackend::new(inner, self.faults))
+    }
+}
+
+/// A backend, which injects faults into the operations of another backend.
+///
+/// See the [module documentation](self) for an example.
+#[derive(Debug)]
+pub struct FaultyBackend<B> {
+    inner: B,
+    faults: Faults,
+}
+
+impl<B: Backend> FaultyBackend<B> {
+    /// Wraps the `inner` backend, faults are taken from `faults`.
+    pub fn new(inner: B, faults: Faults) -> FaultyBackend<B> {
+        FaultyBackend { inner, faults }
+    }
+
+    /// Returns the handle to the fault schedule.
+    pub fn faults(&self) -> &Faults {
+        &self.faults
+    }
+
+    /// Returns a reference to the inner backend.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Consumes the wrapper and returns the inner backend.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+impl<B: Backend> HeaderGet<Self> for FaultyBackend<B> {
+    fn get_header_bytes(
+        &mut self,
+        bytes: &mut [u8; HEADER_MAX_SIZE],
+    ) -> Result<(), FaultyError<B>> {
+        self.inner
+            .get_header_bytes(bytes)
+            .map_err(FaultyError::Backend)
+    }
+}
+
+impl<B: Backend> HeaderSet<Self> for FaultyBackend<B> {
+    fn put_header_bytes(&mut self, bytes: &[u8; HEADER_MAX_SIZE]) -> Result<(), FaultyError<B>> {
+        self.inner
+            .put_header_bytes(bytes)
+            .map_err(FaultyError::Backend)
+    }
+}
+
+impl<B: Backend> Backend for FaultyBackend<B> {
+    type CreateOptions = FaultyCreateOptions<B>;
+    type OpenOptions = FaultyOpenOptions<B>;
+    type Settings = B::Settings;
+    type Err = FaultyError<B>;
+    type Id = B::Id;
+    type Info = B::Info;
+
+    fn info(&self) -> Result<B::Info, FaultyError<B>> {
+        self.inner.info().map_err(FaultyError::Backend)
+    }
+
+    fn block_size(&self) -> u32 {
+        self.inner.block_size()
+    }
+
+    fn aquire(&mut self, buf: &[u8]) -> Result<B::Id, FaultyError<B>> {
+        let result = match self.faults.next(Op::Aquire) {
+            None => self.inner.aquire(buf),
+            Some(Fault::Fail) => return Err(FaultyError::Injected(Op::Aquire)),
+            Some(Fault::Short) => {
+                let mut data = buf.to_vec();
+                let n = half(&data);
+
+                data[n..].iter_mut().for_each(|n| *n = 0);
+                self.inner.aquire(&data)
+            }
+            Some(Fault::Corrupt) => {
+                let mut data = buf.to_vec();
+
+                corrupt(&mut data);
+                self.inner.aquire(&data)
+            }
+        };
+
+        result.map_err(FaultyError::Backend)
+    }
+
+    fn release(&mut self, id: B::Id) -> Result<(), FaultyError<B>> {
+        match self.faults.next(Op::Release) {
+            Some(Fault::Fail) => Err(FaultyError::Injected(Op::Release)),
+            _ => self.inner.release(id).map_err(FaultyError::Backend),
+        }
+    }
+
+    fn read(&mut self, id: &B::Id, buf: &mut [u8]) -> Result<usize, FaultyError<B>> {
+        let fault = self.faults.next(Op::Read);
+
+        if fault == Some(Fault::Fail) {
+            return Err(FaultyError::Injected(Op::Read));
+        }
+
+        let n = self.inner.read(id, buf).map_err(FaultyError::Backend)?;
+
+        match fault {
+            Some(Fault::Short) => {
+                let n = half(&buf[..n]);
+
+                buf[n..].iter_mut().for_each(|n| *n = 0);
+                Ok(n)
+            }
+            Some(Fault::Corrupt) => {
+                corrupt(&mut buf[..n]);
+                Ok(n)
+            }
+            _ => Ok(n),
+        }
+    }
+
+    fn write(&mut self, id: &B::Id, buf: &[u8]) -> Result<usize, FaultyError<B>> {
+        let result = match self.faults.next(Op::Write) {
+            None => self.inner.write(id, buf),
+            Some(Fault::Fail) => return Err(FaultyError::Injected(Op::Write)),
+            Some(Fault::Short) => {
+                let mut data = vec![0; buf.len()];
+                let n = half(buf);
+
+                self.inner
+                    .read(id, &mut data)
+                    .map_err(FaultyError::Backend)?;
+                data[..n].copy_from_slice(&buf[..n]);
+
+                self.inner.write(id, &data).map(|_| n)
+            }
+            Some(Fault::Corrupt) => {
+                let mut data = buf.to_vec();
+
+                corrupt(&mut data);
+                self.inner.write(id, &data)
+            }
+        };
+
+        result.map_err(FaultyError::Backend)
+    }
+}
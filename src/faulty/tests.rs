@@ -0,0 +1,288 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use nuts_container::container::{Cipher, Container, CreateOptionsBuilder, OpenOptionsBuilder};
+use nuts_container::memory::MemoryBackend;
+use std::io::SeekFrom;
+
+use crate::faulty::{
+    Fault, Faults, FaultyBackend, FaultyCreateOptions, FaultyError, FaultyOpenOptions, Op,
+};
+use crate::{Archive, Error, WriteVerification};
+
+fn setup_archive(faults: &Faults) -> Archive<FaultyBackend<MemoryBackend>> {
+    let backend_options = FaultyCreateOptions::new(MemoryBackend::new(), faults.clone());
+    let options = CreateOptionsBuilder::new(Cipher::None)
+        .build::<FaultyBackend<MemoryBackend>>()
+        .unwrap();
+    let container = Container::create(backend_options, options).unwrap();
+
+    Archive::create(container, false).unwrap()
+}
+
+fn injected(err: Error<FaultyBackend<MemoryBackend>>) -> Op {
+    match err {
+        Error::Container(nuts_container::container::Error::Backend(FaultyError::Injected(op))) => {
+            op
+        }
+        _ => panic!("invalid error: {:?}", err),
+    }
+}
+
+#[test]
+fn pass_through() {
+    let faults = Faults::new();
+    let mut archive = setup_archive(&faults);
+
+    archive
+        .append_file("f1")
+        .build()
+        .unwrap()
+        .write_all(b"abc")
+        .unwrap();
+
+    assert!(faults.count(Op::Aquire) > 0);
+    assert!(faults.count(Op::Write) > 0);
+
+    let mut entry = archive.first().unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), b"abc");
+    assert!(faults.count(Op::Read) > 0);
+}
+
+#[test]
+fn skip() {
+    let faults = Faults::new();
+    let mut archive = setup_archive(&faults);
+    let writes = faults.count(Op::Write);
+
+    // entry, header and backup of the header
+    faults.inject(Op::Write, 3, Fault::Fail);
+    assert_eq!(faults.pending(), 1);
+
+    archive.append_file("f1").build().unwrap();
+    assert_eq!(faults.count(Op::Write), writes + 3);
+    assert_eq!(faults.pending(), 1);
+
+    let err = archive.append_file("f2").build().err().unwrap();
+    assert_eq!(injected(err), Op::Write);
+    assert_eq!(faults.pending(), 0);
+}
+
+#[test]
+fn clear() {
+    let faults = Faults::new();
+    let mut archive = setup_archive(&faults);
+
+    faults.inject(Op::Write, 0, Fault::Fail);
+    faults.inject(Op::Aquire, 0, Fault::Fail);
+    faults.clear();
+    assert_eq!(faults.pending(), 0);
+
+    archive.append_file("f1").build().unwrap();
+}
+
+#[test]
+fn fail_aquire() {
+    let faults = Faults::new();
+    let mut archive = setup_archive(&faults);
+
+    faults.inject(Op::Aquire, 0, Fault::Fail);
+
+    let err = archive.append_file("f1").build().err().unwrap();
+    assert_eq!(injected(err), Op::Aquire);
+}
+
+#[test]
+fn fail_aquire_parent() {
+    let faults = Faults::new();
+    let mut archive = setup_archive(&faults);
+
+    archive.append_directory("d").build().unwrap();
+
+    faults.inject(Op::Aquire, 0, Fault::Fail);
+
+    let err = archive.append_file("d/f1").build().err().unwrap();
+    assert_eq!(injected(err), Op::Aquire);
+
+    // the parent is updated after the entry is written
+    let entry = archive.lookup("d").unwrap().unwrap();
+    assert_eq!(entry.as_directory().unwrap().children(), 0);
+}
+
+#[test]
+fn fail_read() {
+    let faults = Faults::new();
+    let mut archive = setup_archive(&faults);
+
+    archive
+        .append_file("f1")
+        .build()
+        .unwrap()
+        .write_all(b"abc")
+        .unwrap();
+
+    let mut entry = archive.first().unwrap().unwrap().into_file().unwrap();
+
+    faults.inject(Op::Read, 0, Fault::Fail);
+
+    let err = entry.read_vec().unwrap_err();
+    assert_eq!(injected(err), Op::Read);
+
+    let mut entry = archive.first().unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), b"abc");
+}
+
+#[test]
+fn corrupt_write() {
+    let faults = Faults::new();
+    let mut archive = setup_archive(&faults);
+
+    archive.set_write_verification(WriteVerification::All);
+
+    let mut entry = archive.append_file("f1").build().unwrap();
+
+    faults.inject(Op::Write, 0, Fault::Corrupt);
+
+    let err = entry.write_all(b"abc").unwrap_err();
+    assert!(matches!(err, Error::WriteMismatch(..)), "{:?}", err);
+}
+
+#[test]
+fn short_write() {
+    let faults = Faults::new();
+    let mut archive = setup_archive(&faults);
+
+    archive.set_write_verification(WriteVerification::All);
+
+    let mut entry = archive.append_file("f1").build().unwrap();
+    let buf = vec![1; 600];
+
+    faults.inject(Op::Write, 0, Fault::Short);
+
+    let err = entry.write_all(&buf).unwrap_err();
+    assert!(matches!(err, Error::WriteMismatch(..)), "{:?}", err);
+}
+
+#[test]
+fn corrupt_read() {
+    let faults = Faults::new();
+    let mut archive = setup_archive(&faults);
+
+    archive
+        .append_file("f1")
+        .build()
+        .unwrap()
+        .write_all(b"abc")
+        .unwrap();
+
+    let mut entry = archive.first().unwrap().unwrap().into_file().unwrap();
+
+    faults.inject(Op::Read, 0, Fault::Corrupt);
+    assert_eq!(entry.read_vec().unwrap(), [!b'a', b'b', b'c']);
+}
+
+#[test]
+fn reopen() {
+    let faults = Faults::new();
+    let mut archive = setup_archive(&faults);
+
+    archive
+        .append_file("f1")
+        .build()
+        .unwrap()
+        .write_all(b"abc")
+        .unwrap();
+
+    let backend = archive.into_container().into_backend();
+    let backend_options = FaultyOpenOptions::new(backend.into_inner(), faults.clone());
+    let options = OpenOptionsBuilder::new()
+        .build::<FaultyBackend<MemoryBackend>>()
+        .unwrap();
+    let container = Container::open(backend_options, options).unwrap();
+
+    // the header and its backup
+    faults.inject(Op::Read, 0, Fault::Fail);
+    faults.inject(Op::Read, 1, Fault::Fail);
+
+    let err = Archive::open(container).err().unwrap();
+    assert_eq!(injected(err), Op::Read);
+}
+
+#[test]
+fn reopen_backup() {
+    let faults = Faults::new();
+    let mut archive = setup_archive(&faults);
+
+    archive
+        .append_file("f1")
+        .build()
+        .unwrap()
+        .write_all(b"abc")
+        .unwrap();
+
+    let backend = archive.into_container().into_backend();
+    let backend_options = FaultyOpenOptions::new(backend.into_inner(), faults.clone());
+    let options = OpenOptionsBuilder::new()
+        .build::<FaultyBackend<MemoryBackend>>()
+        .unwrap();
+    let container =
+        Container::<FaultyBackend<MemoryBackend>>::open(backend_options, options).unwrap();
+
+    // the header block is unreadable, the backup is used
+    faults.inject(Op::Read, 0, Fault::Fail);
+
+    let mut archive = Archive::open(container).unwrap();
+    let mut entry = archive.first().unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), b"abc");
+}
+
+#[test]
+fn verify_corrupted() {
+    let faults = Faults::new();
+    let mut archive = setup_archive(&faults);
+    let content = (0..2000).map(|n| (n % 251) as u8).collect::<Vec<_>>();
+
+    let mut builder = archive.append_file("f");
+    builder.set_hashed(true);
+    builder.with_content(&content).build().unwrap();
+
+    let mut entry = archive.first().unwrap().unwrap().into_file().unwrap();
+
+    // the third content block is corrupted
+    faults.inject(Op::Read, 2, Fault::Corrupt);
+
+    let err = entry.verify().unwrap_err();
+    assert!(matches!(err, Error::ChecksumMismatch(ref name) if name == "f"));
+
+    assert!(entry.verify().unwrap());
+
+    entry.set_verified(true);
+    faults.inject(Op::Read, 0, Fault::Corrupt);
+
+    let err = entry.read_vec().unwrap_err();
+    assert!(matches!(err, Error::ChecksumMismatch(ref name) if name == "f"));
+
+    // not verified, if not read from the beginning
+    entry.seek(SeekFrom::Start(1)).unwrap();
+    faults.inject(Op::Read, 0, Fault::Corrupt);
+    assert_eq!(entry.read_vec().unwrap().len(), 1999);
+}
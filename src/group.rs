@@ -0,0 +1,244 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use chrono::{DateTime, Utc};
+use log::{debug, error};
+use nuts_container::backend::Backend;
+
+use crate::entry::immut::visible_names;
+use crate::entry::r#mut::{inc_parent_children, index_name};
+use crate::error::ArchiveResult;
+use crate::{
+    flush_header, release_blocks, Archive, DirectoryBuilder, FileBuilder, HardLinkBuilder,
+    SpecialBuilder, SpecialKind, SymlinkBuilder,
+};
+
+/// A group of entries, which are appended atomically.
+///
+/// An `AppendGroup` is created by
+/// [`Archive::append_group()`](crate::Archive::append_group). Entries
+/// appended through the group are written into the archive, but the header
+/// of the archive is not updated until [`AppendGroup::commit()`] is called.
+/// Until then the entries are not part of the archive: if the application
+/// crashes, the archive opened again does not contain any of them.
+///
+/// If the group is dropped without being committed, it is rolled back: the
+/// blocks of the entries are put into the list of free blocks. Call
+/// [`AppendGroup::rollback()`] to handle errors of the rollback.
+///
+/// The name index (see [`Archive::create_index()`](crate::Archive::create_index))
+/// and the child counts of the parent directories are updated when the group
+/// is committed.
+///
+/// Released blocks are not reused by the entries of the group, new blocks
+/// are aquired from the container instead. The list of released blocks is
+/// linked through the blocks itself, it would refer to blocks overwritten by
+/// the entries after a crash.
+pub struct AppendGroup<'a, B: Backend> {
+    archive: &'a mut Archive<B>,
+    start: u64,
+    nfiles: u64,
+    modified: DateTime<Utc>,
+    done: bool,
+}
+
+impl<'a, B: Backend> AppendGroup<'a, B> {
+    pub(crate) fn new(archive: &'a mut Archive<B>) -> ArchiveResult<AppendGroup<'a, B>, B> {
+        // The header block on disk must match the archive, it is the state
+        // restored on a crash.
+        flush_header(
+            &mut archive.pager,
+            &archive.header_id,
+            &archive.header,
+            &mut archive.tree,
+        )?;
+
+        archive.header.set_deferred(true);
+        archive.tree.pause_free_list(true);
+
+        let start = archive.tree.nblocks();
+        let nfiles = archive.header.nfiles;
+        let modified = archive.header.modified;
+
+        debug!("append group opened at {}", start);
+
+        Ok(AppendGroup {
+            archive,
+            start,
+            nfiles,
+            modified,
+            done: false,
+        })
+    }
+
+    /// Appends a new file entry with the given `name` to the group.
+    ///
+    /// See [`Archive::append_file()`](crate::Archive::append_file).
+    pub fn append_file<N: AsRef<str>>(&mut self, name: N) -> FileBuilder<'_, B> {
+        self.archive.append_file(name)
+    }
+
+    /// Appends a new directory entry with the given `name` to the group.
+    ///
+    /// See [`Archive::append_directory()`](crate::Archive::append_directory).
+    pub fn append_directory<N: AsRef<str>>(&mut self, name: N) -> DirectoryBuilder<'_, B> {
+        self.archive.append_directory(name)
+    }
+
+    /// Appends a new symlink entry with the given `name` and `target` to the
+    /// group.
+    ///
+    /// See [`Archive::append_symlink()`](crate::Archive::append_symlink).
+    pub fn append_symlink<N: AsRef<str>, T: AsRef<[u8]>>(
+        &mut self,
+        name: N,
+        target: T,
+    ) -> SymlinkBuilder<'_, B> {
+        self.archive.append_symlink(name, target)
+    }
+
+    /// Appends a new hard link entry with the given `name` and `target` to
+    /// the group.
+    ///
+    /// See [`Archive::append_hardlink()`](crate::Archive::append_hardlink).
+    pub fn append_hardlink<N: AsRef<str>, T: AsRef<str>>(
+        &mut self,
+        name: N,
+        target: T,
+    ) -> HardLinkBuilder<'_, B> {
+        self.archive.append_hardlink(name, target)
+    }
+
+    /// Appends a new special file entry with the given `name` to the group.
+    ///
+    /// See [`Archive::append_special()`](crate::Archive::append_special).
+    pub fn append_special<N: AsRef<str>>(
+        &mut self,
+        name: N,
+        kind: SpecialKind,
+    ) -> SpecialBuilder<'_, B> {
+        self.archive.append_special(name, kind)
+    }
+
+    /// Returns the archive, the group was opened for.
+    pub(crate) fn archive_mut(&mut self) -> &mut Archive<B> {
+        self.archive
+    }
+
+    /// Commits the group, all entries of the group become part of the
+    /// archive.
+    ///
+    /// The entries are committed with a single write of the header block.
+    ///
+    /// # Errors
+    ///
+    /// If the header block cannot be written, the group is rolled back and
+    /// the error is returned.
+    pub fn commit(mut self) -> ArchiveResult<(), B> {
+        self.archive.header.set_deferred(false);
+        self.archive.tree.pause_free_list(false);
+
+        let archive = &mut *self.archive;
+
+        flush_header(
+            &mut archive.pager,
+            &archive.header_id,
+            &archive.header,
+            &mut archive.tree,
+        )?;
+
+        self.done = true;
+
+        debug!(
+            "append group committed at {}..{}",
+            self.start,
+            self.archive.tree.nblocks()
+        );
+
+        self.update_entries()
+    }
+
+    /// Rolls back the group, none of the entries of the group become part of
+    /// the archive.
+    ///
+    /// This is also done when the group is dropped without being committed,
+    /// but errors are only logged then.
+    pub fn rollback(mut self) -> ArchiveResult<(), B> {
+        self.rollback_inner()
+    }
+
+    /// Updates the name index and the parent directories of the entries of
+    /// the group, which was deferred while the group was open.
+    fn update_entries(&mut self) -> ArchiveResult<(), B> {
+        let archive = &mut *self.archive;
+        let entries = visible_names(&mut archive.pager, &mut archive.tree, self.start as usize)?;
+
+        for (idx, name) in entries {
+            inc_parent_children(&mut archive.pager, &mut archive.tree, &name)?;
+            index_name(&mut archive.pager, &mut archive.tree, &name, idx)?;
+        }
+
+        Ok(())
+    }
+
+    fn rollback_inner(&mut self) -> ArchiveResult<(), B> {
+        self.done = true;
+
+        let archive = &mut *self.archive;
+        let nblocks = archive.tree.nblocks();
+
+        archive.header.set_deferred(false);
+        archive.tree.pause_free_list(false);
+        archive.header.nfiles = self.nfiles;
+        archive.header.modified = self.modified;
+
+        let removed = archive.tree.remove(
+            &mut archive.pager,
+            self.start as usize,
+            (nblocks - self.start) as usize,
+        )?;
+
+        flush_header(
+            &mut archive.pager,
+            &archive.header_id,
+            &archive.header,
+            &mut archive.tree,
+        )?;
+
+        debug!("append group rolled back at {}..{}", self.start, nblocks);
+
+        release_blocks(&mut archive.pager, &mut archive.tree, removed)
+    }
+}
+
+impl<'a, B: Backend> Drop for AppendGroup<'a, B> {
+    fn drop(&mut self) {
+        if !self.done {
+            if let Err(err) = self.rollback_inner() {
+                error!("failed to roll back append group: {}", err);
+            }
+        }
+    }
+}
@@ -0,0 +1,248 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use nuts_container::container::{Container, OpenOptionsBuilder};
+use nuts_container::memory::MemoryBackend;
+
+use crate::tests::setup_container_with_bsize;
+use crate::Archive;
+
+fn setup_archive() -> Archive<MemoryBackend> {
+    let container = setup_container_with_bsize(512);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.append_directory("dir").build().unwrap();
+
+    archive
+}
+
+fn names(archive: &mut Archive<MemoryBackend>) -> Vec<String> {
+    let mut names = vec![];
+    let mut entry_opt = archive.first();
+
+    while let Some(result) = entry_opt {
+        let entry = result.unwrap();
+
+        names.push(entry.name().to_string());
+        entry_opt = entry.next();
+    }
+
+    names
+}
+
+fn reopen(archive: Archive<MemoryBackend>) -> Archive<MemoryBackend> {
+    let backend = archive.into_container().into_backend();
+    let options = OpenOptionsBuilder::new().build::<MemoryBackend>().unwrap();
+    let container = Container::open(backend, options).unwrap();
+
+    Archive::open(container).unwrap()
+}
+
+fn append_group(archive: &mut Archive<MemoryBackend>) {
+    let mut group = archive.append_group().unwrap();
+
+    group
+        .append_file("dir/data")
+        .build()
+        .unwrap()
+        .write_all(&[1; 1300])
+        .unwrap();
+    group.append_symlink("dir/link", "data").build().unwrap();
+    group
+        .append_file("dir/sum")
+        .build()
+        .unwrap()
+        .write_all(b"sum")
+        .unwrap();
+
+    group.commit().unwrap();
+}
+
+#[test]
+fn commit() {
+    let mut archive = setup_archive();
+
+    append_group(&mut archive);
+
+    assert_eq!(
+        names(&mut archive),
+        ["dir", "dir/data", "dir/link", "dir/sum"]
+    );
+    assert_eq!(archive.info().files, 4);
+
+    let dir = archive.first().unwrap().unwrap().into_directory().unwrap();
+    assert_eq!(dir.children(), 3);
+
+    let mut archive = reopen(archive);
+
+    assert_eq!(
+        names(&mut archive),
+        ["dir", "dir/data", "dir/link", "dir/sum"]
+    );
+    assert_eq!(archive.info().files, 4);
+}
+
+#[test]
+fn commit_index() {
+    let mut archive = setup_archive();
+
+    archive.create_index().unwrap();
+    append_group(&mut archive);
+
+    let mut entry = archive
+        .lookup_indexed("dir/sum")
+        .unwrap()
+        .unwrap()
+        .into_file()
+        .unwrap();
+    assert_eq!(entry.read_vec().unwrap(), b"sum");
+}
+
+#[test]
+fn uncommitted_not_persisted() {
+    let mut archive = setup_archive();
+    let mut group = archive.append_group().unwrap();
+
+    group
+        .append_file("dir/data")
+        .build()
+        .unwrap()
+        .write_all(&[1; 1300])
+        .unwrap();
+
+    // simulate a crash: the group is neither committed nor rolled back
+    std::mem::forget(group);
+
+    let mut archive = reopen(archive);
+
+    assert_eq!(names(&mut archive), ["dir"]);
+    assert_eq!(archive.info().files, 1);
+    assert_eq!(archive.info().blocks, 1);
+}
+
+#[test]
+fn uncommitted_free_list() {
+    let mut archive = setup_archive();
+
+    archive
+        .append_file("f1")
+        .build()
+        .unwrap()
+        .write_all(&[1; 1300])
+        .unwrap();
+    archive.remove("f1").unwrap();
+    assert_eq!(archive.tree.free_list().unwrap().len(), 4);
+
+    let mut group = archive.append_group().unwrap();
+
+    group
+        .append_file("dir/data")
+        .build()
+        .unwrap()
+        .write_all(&[2; 1300])
+        .unwrap();
+
+    // simulate a crash: the group is neither committed nor rolled back
+    std::mem::forget(group);
+
+    // the blocks of the uncommitted entry were not taken from the list
+    let mut archive = reopen(archive);
+
+    archive.warm_up().unwrap();
+    assert_eq!(archive.tree.free_list().unwrap().len(), 4);
+
+    archive
+        .append_file("f2")
+        .build()
+        .unwrap()
+        .write_all(&[3; 1300])
+        .unwrap();
+    assert_eq!(archive.tree.free_list().unwrap().len(), 0);
+
+    let mut entry = archive.lookup("f2").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), [3; 1300]);
+}
+
+#[test]
+fn drop_rolls_back() {
+    let mut archive = setup_archive();
+    let blocks = archive.info().blocks;
+
+    {
+        let mut group = archive.append_group().unwrap();
+
+        group
+            .append_file("dir/data")
+            .build()
+            .unwrap()
+            .write_all(&[1; 1300])
+            .unwrap();
+        group.append_directory("dir/sub").build().unwrap();
+    }
+
+    assert_eq!(names(&mut archive), ["dir"]);
+    assert_eq!(archive.info().files, 1);
+    assert_eq!(archive.info().blocks, blocks);
+
+    let dir = archive.first().unwrap().unwrap().into_directory().unwrap();
+    assert_eq!(dir.children(), 0);
+
+    // the released blocks are reused
+    archive
+        .append_file("f1")
+        .build()
+        .unwrap()
+        .write_all(&[2; 1300])
+        .unwrap();
+    assert_eq!(archive.tree.free_list().unwrap().len(), 1);
+
+    let mut archive = reopen(archive);
+
+    assert_eq!(names(&mut archive), ["dir", "f1"]);
+}
+
+#[test]
+fn rollback() {
+    let mut archive = setup_archive();
+    let mut group = archive.append_group().unwrap();
+
+    group.append_file("dir/data").build().unwrap();
+    group.rollback().unwrap();
+
+    assert_eq!(names(&mut archive), ["dir"]);
+
+    append_group(&mut archive);
+
+    assert_eq!(
+        names(&mut archive),
+        ["dir", "dir/data", "dir/link", "dir/sum"]
+    );
+}
+
+#[test]
+fn empty() {
+    let mut archive = setup_archive();
+
+    archive.append_group().unwrap().commit().unwrap();
+
+    assert_eq!(names(&mut archive), ["dir"]);
+}
@@ -25,10 +25,13 @@ mod tests;
 
 use chrono::{DateTime, Utc};
 use nuts_bytes::{FromBytes, ToBytes};
+use nuts_container::backend::Backend;
 use std::mem;
 use thiserror::Error;
 
+use crate::codec::Codec;
 use crate::datetime;
+use crate::error::{ArchiveResult, Error};
 use crate::magic::{magic_size, validate_magic, Magic, MagicErrorFactory, MAGIC};
 
 #[derive(Debug, Error)]
@@ -41,7 +44,7 @@ impl MagicErrorFactory for HeaderMagicError {
     }
 }
 
-#[derive(Debug, FromBytes, ToBytes)]
+#[derive(Clone, Debug, FromBytes, ToBytes)]
 pub struct Header {
     #[nuts_bytes(map_from_bytes = validate_magic::<HeaderMagicError>)]
     magic: Magic,
@@ -51,6 +54,10 @@ pub struct Header {
     #[nuts_bytes(map = datetime)]
     pub modified: DateTime<Utc>,
     pub nfiles: u64,
+    #[nuts_bytes(skip)]
+    frozen: bool,
+    #[nuts_bytes(skip)]
+    deferred: bool,
 }
 
 impl Header {
@@ -63,20 +70,80 @@ impl Header {
         magic + revision + tstamps + nfiles
     }
 
-    pub fn create() -> Header {
+    pub fn create(codec: &Codec) -> Header {
         let now = Utc::now();
 
         Header {
             magic: MAGIC,
-            revision: 1,
+            revision: codec.word(),
             created: now,
             modified: now,
             nfiles: 0,
+            frozen: false,
+            deferred: false,
         }
     }
 
+    /// Returns the revision word, see [`Codec::word()`].
+    pub fn revision(&self) -> u16 {
+        self.revision
+    }
+
+    /// Returns a copy of the header, which stores the revision word of
+    /// `codec`.
+    ///
+    /// The features of the codec are flagged, while the archive is
+    /// modified, so the word is taken from the codec, when the header is
+    /// written.
+    pub fn for_codec(&self, codec: &Codec) -> Header {
+        Header {
+            revision: codec.word(),
+            ..self.clone()
+        }
+    }
+
+    /// Tests whether the archive is frozen.
+    ///
+    /// The flag is not part of the header block, it is loaded from the
+    /// userdata of the container.
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    /// Fails with an [`Error::Frozen`] error, if the archive is frozen.
+    ///
+    /// Every operation, which modifies the archive, calls this guard first.
+    pub fn check_mutable<B: Backend>(&self) -> ArchiveResult<(), B> {
+        if self.frozen {
+            Err(Error::Frozen)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn set_frozen(&mut self, frozen: bool) {
+        self.frozen = frozen;
+    }
+
+    /// Tests whether writing the header block is deferred.
+    ///
+    /// The flag is set while an [`AppendGroup`](crate::AppendGroup) is
+    /// open, the appended entries become visible when the group is
+    /// committed.
+    pub fn is_deferred(&self) -> bool {
+        self.deferred
+    }
+
+    pub fn set_deferred(&mut self, deferred: bool) {
+        self.deferred = deferred;
+    }
+
     pub fn inc_files(&mut self) {
         self.nfiles += 1;
+        self.touch();
+    }
+
+    pub fn touch(&mut self) {
         self.modified = Utc::now();
     }
 }
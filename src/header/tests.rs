@@ -37,6 +37,8 @@ fn ser() {
         created: Utc.timestamp_millis_opt(2).unwrap(),
         modified: Utc.timestamp_millis_opt(3).unwrap(),
         nfiles: 4,
+        frozen: true,
+        deferred: true,
     };
     let mut writer = Writer::new(vec![]);
 
@@ -92,6 +94,8 @@ fn inc_files() {
         created: Utc.timestamp_millis_opt(2).unwrap(),
         modified: Utc.timestamp_millis_opt(3).unwrap(),
         nfiles: 4,
+        frozen: false,
+        deferred: false,
     };
 
     header.inc_files();
@@ -0,0 +1,142 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use chrono::{DateTime, Utc};
+use log::debug;
+use nuts_container::backend::Backend;
+use std::fs::{self, File, Metadata};
+use std::io::{self, ErrorKind, Read};
+use std::path::{Component, Path};
+
+use crate::error::{ArchiveResult, Error};
+use crate::{Archive, FileBuilder};
+
+fn invalid_path(path: &Path, reason: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidInput,
+        format!("{}: {}", path.display(), reason),
+    )
+}
+
+/// Converts `path` into the name of an archive entry.
+///
+/// The components of the path are joined with `/`. Like `tar` does, a
+/// leading root (and prefix) is removed, `.` components are skipped. A path
+/// with `..` components or components, which are not valid UTF-8, is
+/// rejected.
+pub(crate) fn name_from_path(path: &Path) -> io::Result<String> {
+    let mut parts = vec![];
+
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+            Component::ParentDir => return Err(invalid_path(path, "contains ..")),
+            Component::Normal(s) => match s.to_str() {
+                Some(s) => parts.push(s),
+                None => return Err(invalid_path(path, "not valid UTF-8")),
+            },
+        }
+    }
+
+    if parts.is_empty() {
+        return Err(invalid_path(path, "empty name"));
+    }
+
+    Ok(parts.join("/"))
+}
+
+#[cfg(unix)]
+fn set_attributes<B: Backend>(builder: &mut FileBuilder<B>, metadata: &Metadata) {
+    use chrono::TimeZone;
+    use std::os::unix::fs::MetadataExt;
+
+    builder.set_unix_mode(metadata.mode());
+    builder.set_uid(Some(metadata.uid()));
+    builder.set_gid(Some(metadata.gid()));
+
+    if let Some(changed) = Utc
+        .timestamp_opt(metadata.ctime(), metadata.ctime_nsec() as u32)
+        .earliest()
+    {
+        builder.set_changed(changed);
+    }
+
+    set_tstamps(builder, metadata);
+}
+
+#[cfg(not(unix))]
+fn set_attributes<B: Backend>(builder: &mut FileBuilder<B>, metadata: &Metadata) {
+    if let Ok(modified) = metadata.modified() {
+        builder.set_changed(DateTime::from(modified));
+    }
+
+    set_tstamps(builder, metadata);
+}
+
+fn set_tstamps<B: Backend>(builder: &mut FileBuilder<B>, metadata: &Metadata) {
+    // not every platform/filesystem knows the creation time
+    if let Ok(created) = metadata.created() {
+        builder.set_created(DateTime::<Utc>::from(created));
+    }
+
+    if let Ok(modified) = metadata.modified() {
+        builder.set_modified(DateTime::<Utc>::from(modified));
+    }
+}
+
+pub(crate) fn append_from_path<B: Backend>(
+    archive: &mut Archive<B>,
+    path: &Path,
+) -> ArchiveResult<(), B> {
+    let name = name_from_path(path)?;
+    let metadata = fs::metadata(path)?;
+
+    if !metadata.is_file() {
+        return Err(Error::NotFile(name));
+    }
+
+    let mut file = File::open(path)?;
+    let mut buf = vec![0; archive.pager.block_size() as usize];
+
+    let mut builder = archive.append_file(&name);
+    set_attributes(&mut builder, &metadata);
+
+    let mut entry = builder.build()?;
+
+    loop {
+        let n = match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err.into()),
+        };
+
+        entry.write_all(&buf[..n])?;
+    }
+
+    debug!("{} appended from {}", name, path.display());
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use std::io::ErrorKind;
+use std::path::Path;
+
+use crate::import::name_from_path;
+
+#[test]
+fn name_relative() {
+    assert_eq!(name_from_path(Path::new("foo")).unwrap(), "foo");
+    assert_eq!(name_from_path(Path::new("foo/bar")).unwrap(), "foo/bar");
+    assert_eq!(
+        name_from_path(Path::new("./foo/./bar/")).unwrap(),
+        "foo/bar"
+    );
+}
+
+#[test]
+fn name_absolute() {
+    assert_eq!(name_from_path(Path::new("/foo/bar")).unwrap(), "foo/bar");
+}
+
+#[test]
+fn name_parent() {
+    let err = name_from_path(Path::new("foo/../bar")).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn name_empty() {
+    for path in ["", ".", "/"] {
+        let err = name_from_path(Path::new(path)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+}
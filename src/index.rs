@@ -0,0 +1,272 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use nuts_bytes::{FromBytes, Reader, ToBytes, Writer};
+use nuts_container::backend::Backend;
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+use crate::entry::immut::InnerEntry;
+use crate::entry::{
+    populate_metadata_api, populate_mode_api, populate_tstamp_api, ImplicitDirs, Inner,
+};
+use crate::error::ArchiveResult;
+use crate::pager::Pager;
+use crate::tree::Tree;
+
+/// An entry of the [`Index`].
+#[derive(Debug, FromBytes, ToBytes)]
+pub struct IndexEntry {
+    inner: Inner,
+    target: Option<String>,
+    implicit: bool,
+}
+
+impl IndexEntry {
+    /// Returns the name of the entry.
+    pub fn name(&self) -> &str {
+        &self.inner.name
+    }
+
+    /// Returns the size of the entry.
+    pub fn size(&self) -> u64 {
+        self.inner.content_size()
+    }
+
+    /// Tests whether this entry represents a file.
+    pub fn is_file(&self) -> bool {
+        self.inner.mode.is_file()
+    }
+
+    /// Tests whether this entry represents a directory.
+    pub fn is_directory(&self) -> bool {
+        self.inner.mode.is_directory()
+    }
+
+    /// Tests whether this entry represents a symlink.
+    pub fn is_symlink(&self) -> bool {
+        self.inner.mode.is_symlink()
+    }
+
+    /// Tests whether this entry represents a hard link.
+    pub fn is_hardlink(&self) -> bool {
+        self.inner.mode.is_hardlink()
+    }
+
+    /// Tests whether this entry represents a special file (FIFO, device node
+    /// or socket).
+    pub fn is_special(&self) -> bool {
+        self.inner.mode.is_special()
+    }
+
+    /// Tests whether this entry is an implicit directory.
+    ///
+    /// An implicit directory is not stored in the archive. It is synthesized
+    /// because the archive contains entries below the directory but no
+    /// explicit entry for the directory itself.
+    pub fn is_implicit(&self) -> bool {
+        self.implicit
+    }
+
+    /// Returns the target of a symlink or hard link.
+    ///
+    /// If this entry is neither a symlink nor a hard link, [`None`] is
+    /// returned.
+    pub fn target(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
+
+    populate_mode_api!();
+    populate_tstamp_api!();
+    populate_metadata_api!();
+
+    fn inner(&self) -> &Inner {
+        &self.inner
+    }
+}
+
+/// The metadata of all entries of an archive.
+///
+/// Directories, which are not stored in the archive but are parents of
+/// stored entries, are synthesized and added in front of their first child
+/// (see [`IndexEntry::is_implicit()`]).
+///
+/// The index is created by
+/// [`Archive::export_index()`](crate::Archive::export_index) and can be
+/// loaded with [`Index::import()`] somewhere else. This way a client can
+/// browse the entries of an archive without having to read the metadata
+/// blocks of the archive over a slow backend.
+///
+/// When exported, the names of the entries are prefix compressed: each name
+/// is stored as the length of the prefix shared with the name of the
+/// previous entry followed by the remaining suffix. This keeps the index
+/// small for archives with deep, repetitive paths.
+#[derive(Debug)]
+pub struct Index {
+    entries: Vec<IndexEntry>,
+}
+
+/// Binary representation of an [`IndexEntry`].
+///
+/// The name of `entry` only contains the suffix, which is not shared with
+/// the previous entry.
+#[derive(FromBytes, ToBytes)]
+struct PackedEntry {
+    shared: u64,
+    entry: IndexEntry,
+}
+
+/// Returns the number of bytes at the start of `a` and `b`, which are equal.
+///
+/// The result is always located at a character boundary.
+fn shared_prefix(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .find(|((_, c1), c2)| c1 != c2)
+        .map(|((idx, _), _)| idx)
+        .unwrap_or_else(|| a.len().min(b.len()))
+}
+
+impl Index {
+    pub(crate) fn create<B: Backend>(
+        pager: &mut Pager<B>,
+        tree: &mut Tree<B>,
+    ) -> ArchiveResult<Index, B> {
+        let mut entries = vec![];
+        let mut implicit_dirs = ImplicitDirs::new();
+        let mut entry_opt = InnerEntry::first(pager, tree);
+
+        loop {
+            let mut entry = match entry_opt {
+                Some(Ok(entry)) => entry,
+                Some(Err(err)) => return Err(err),
+                None => break,
+            };
+
+            if !entry.inner().mode.is_internal() {
+                for inner in implicit_dirs.missing(entry.inner()) {
+                    entries.push(IndexEntry {
+                        inner,
+                        target: None,
+                        implicit: true,
+                    });
+                }
+
+                let target = if entry.inner().mode.is_symlink() || entry.inner().mode.is_hardlink()
+                {
+                    let buf = entry.read_content()?;
+                    Some(String::from_utf8_lossy(&buf).to_string())
+                } else {
+                    None
+                };
+
+                entries.push(IndexEntry {
+                    inner: entry.inner().clone(),
+                    target,
+                    implicit: false,
+                });
+            }
+
+            entry_opt = entry.next();
+        }
+
+        Ok(Index { entries })
+    }
+
+    /// Returns all entries of the index, in archive order.
+    pub fn entries(&self) -> &[IndexEntry] {
+        &self.entries
+    }
+
+    /// Searches for an entry with the given `name`.
+    pub fn lookup<N: AsRef<str>>(&self, name: N) -> Option<&IndexEntry> {
+        self.entries.iter().find(|e| e.name() == name.as_ref())
+    }
+
+    pub(crate) fn export<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut prev = "";
+        let packed = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let shared = shared_prefix(prev, entry.name());
+                let mut inner = entry.inner.clone();
+
+                inner.name = entry.name()[shared..].to_string();
+                prev = entry.name();
+
+                PackedEntry {
+                    shared: shared as u64,
+                    entry: IndexEntry {
+                        inner,
+                        target: entry.target.clone(),
+                        implicit: entry.implicit,
+                    },
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut buf = Writer::new(vec![]);
+
+        buf.write(&packed)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        writer.write_all(&buf.into_target())
+    }
+
+    /// Imports an index, which was exported with
+    /// [`Archive::export_index()`](crate::Archive::export_index).
+    ///
+    /// Reads from `reader` until EOF.
+    pub fn import<R: Read>(mut reader: R) -> io::Result<Index> {
+        let mut buf = vec![];
+
+        reader.read_to_end(&mut buf)?;
+
+        let packed: Vec<PackedEntry> = Reader::new(buf.as_slice())
+            .read()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        let mut entries: Vec<IndexEntry> = Vec::with_capacity(packed.len());
+
+        for PackedEntry { shared, mut entry } in packed {
+            let prev = entries.last().map_or("", |prev| prev.name());
+            let prefix = usize::try_from(shared)
+                .ok()
+                .and_then(|shared| prev.get(..shared))
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("invalid shared prefix of {} bytes", shared),
+                    )
+                })?;
+
+            entry.inner.name.insert_str(0, prefix);
+            entries.push(entry);
+        }
+
+        Ok(Index { entries })
+    }
+}
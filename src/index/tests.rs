@@ -0,0 +1,204 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use chrono::{TimeZone, Utc};
+
+use crate::index::{shared_prefix, Index};
+use crate::tests::setup_container_with_bsize;
+use crate::{Archive, Group, JobRecord};
+
+fn setup_archive() -> Archive<nuts_container::memory::MemoryBackend> {
+    let container = setup_container_with_bsize(106);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    let mut builder = archive.append_file("f1");
+    builder.set_modified(Utc.timestamp_millis_opt(4711).unwrap());
+    builder.set_writable(Group::User, false);
+
+    let mut entry = builder.build().unwrap();
+    entry.write_all(&[1; 200]).unwrap();
+
+    archive.append_job(&JobRecord::new("host")).unwrap();
+    archive.append_directory("d1").build().unwrap();
+    archive.append_symlink("s1", "f1").build().unwrap();
+
+    archive
+}
+
+#[test]
+fn empty() {
+    let container = setup_container_with_bsize(106);
+    let mut archive = Archive::create(container, false).unwrap();
+    let mut buf = vec![];
+
+    archive.export_index(&mut buf).unwrap();
+
+    let index = Index::import(buf.as_slice()).unwrap();
+    assert!(index.entries().is_empty());
+}
+
+#[test]
+fn export_import() {
+    let mut archive = setup_archive();
+    let mut buf = vec![];
+
+    archive.export_index(&mut buf).unwrap();
+
+    let index = Index::import(buf.as_slice()).unwrap();
+    assert_eq!(index.entries().len(), 3);
+
+    let entry = &index.entries()[0];
+    assert_eq!(entry.name(), "f1");
+    assert_eq!(entry.size(), 200);
+    assert!(entry.is_file());
+    assert!(!entry.can_write(Group::User));
+    assert_eq!(entry.modified(), &Utc.timestamp_millis_opt(4711).unwrap());
+    assert!(entry.target().is_none());
+    assert!(!entry.is_implicit());
+
+    let entry = &index.entries()[1];
+    assert_eq!(entry.name(), "d1");
+    assert!(entry.is_directory());
+    assert!(entry.target().is_none());
+
+    let entry = &index.entries()[2];
+    assert_eq!(entry.name(), "s1");
+    assert!(entry.is_symlink());
+    assert_eq!(entry.target(), Some("f1"));
+}
+
+#[test]
+fn lookup() {
+    let mut archive = setup_archive();
+    let mut buf = vec![];
+
+    archive.export_index(&mut buf).unwrap();
+
+    let index = Index::import(buf.as_slice()).unwrap();
+    assert_eq!(index.lookup("d1").unwrap().name(), "d1");
+    assert!(index.lookup("xxx").is_none());
+}
+
+#[test]
+fn implicit_dirs() {
+    let container = setup_container_with_bsize(106);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.append_directory("a").build().unwrap();
+    archive.append_file("a/b/c/f1").build().unwrap();
+    archive.append_file("a/b/f2").build().unwrap();
+    archive.append_file("d/f3").build().unwrap();
+    archive.append_directory("d").build().unwrap();
+
+    let mut buf = vec![];
+    archive.export_index(&mut buf).unwrap();
+
+    let index = Index::import(buf.as_slice()).unwrap();
+    let entries = index
+        .entries()
+        .iter()
+        .map(|e| (e.name(), e.is_directory(), e.is_implicit()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        entries,
+        [
+            ("a", true, false),
+            ("a/b", true, true),
+            ("a/b/c", true, true),
+            ("a/b/c/f1", false, false),
+            ("a/b/f2", false, false),
+            ("d", true, true),
+            ("d/f3", false, false),
+            ("d", true, false),
+        ]
+    );
+}
+
+#[test]
+fn import_invalid() {
+    let err = Index::import([0, 0, 0].as_slice()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn shared_prefix_len() {
+    assert_eq!(shared_prefix("", "abc"), 0);
+    assert_eq!(shared_prefix("abc", ""), 0);
+    assert_eq!(shared_prefix("abc", "abd"), 2);
+    assert_eq!(shared_prefix("abc", "abc"), 3);
+    assert_eq!(shared_prefix("abc", "abcd"), 3);
+    assert_eq!(shared_prefix("abcd", "abc"), 3);
+    assert_eq!(shared_prefix("aä", "aö"), 1);
+}
+
+#[test]
+fn prefix_compression() {
+    let container = setup_container_with_bsize(106);
+    let mut archive = Archive::create(container, false).unwrap();
+    let names = [
+        "a/very/deep/path/f1",
+        "a/very/deep/path/f2",
+        "a/very/deep/other/f3",
+        "a/very/deep/other/ä",
+        "a/very/deep/other/ö",
+        "b",
+    ];
+
+    for name in names {
+        archive.append_file(name).build().unwrap();
+    }
+
+    let mut buf = vec![];
+    archive.export_index(&mut buf).unwrap();
+
+    let index = Index::import(buf.as_slice()).unwrap();
+    let imported = index
+        .entries()
+        .iter()
+        .filter(|e| !e.is_implicit())
+        .map(|e| e.name())
+        .collect::<Vec<_>>();
+    assert_eq!(imported, names);
+
+    for component in [b"very", b"deep"] {
+        let n = buf.windows(4).filter(|w| w == component).count();
+        assert_eq!(n, 1);
+    }
+}
+
+#[test]
+fn import_invalid_prefix() {
+    let container = setup_container_with_bsize(106);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.append_file("f1").build().unwrap();
+
+    let mut buf = vec![];
+    archive.export_index(&mut buf).unwrap();
+
+    // shared prefix of the first entry follows the number of entries
+    buf[15] = 1;
+
+    let err = Index::import(buf.as_slice()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,142 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use chrono::{DateTime, Utc};
+use nuts_bytes::{FromBytes, ToBytes};
+use nuts_container::backend::Backend;
+
+use crate::datetime;
+use crate::entry::immut::InnerEntry;
+use crate::entry::mode::Mode;
+use crate::entry::r#mut::InnerBuilder;
+use crate::entry::Inner;
+use crate::error::ArchiveResult;
+use crate::header::Header;
+use crate::pager::Pager;
+use crate::tree::Tree;
+
+/// The final state of a backup job.
+#[derive(Clone, Copy, Debug, FromBytes, PartialEq, ToBytes)]
+pub enum JobStatus {
+    /// The job finished successfully.
+    Succeeded,
+
+    /// The job finished, but some entries could not be stored.
+    Partial,
+
+    /// The job failed.
+    Failed,
+}
+
+/// A record documenting a backup job.
+///
+/// Tools writing into the archive can store a record for each run with
+/// [`Archive::append_job()`](crate::Archive::append_job). The records are
+/// stored as hidden entries, they do not show up when traversing the
+/// archive. Use [`Archive::jobs()`](crate::Archive::jobs) to query them.
+#[derive(Clone, Debug, FromBytes, PartialEq, ToBytes)]
+pub struct JobRecord {
+    /// Time when the job was started
+    #[nuts_bytes(map = datetime)]
+    pub started: DateTime<Utc>,
+
+    /// Time when the job was finished
+    #[nuts_bytes(map = datetime)]
+    pub finished: DateTime<Utc>,
+
+    /// Name of the host where the data came from
+    pub host: String,
+
+    /// Number of entries appended by the job
+    pub entries: u64,
+
+    /// Number of bytes appended by the job
+    pub bytes: u64,
+
+    /// Final state of the job
+    pub status: JobStatus,
+}
+
+impl JobRecord {
+    /// Creates a new record for a job running on `host`.
+    ///
+    /// The start and finish time is set to the current time, the counters
+    /// are set to `0` and the status is [`JobStatus::Succeeded`].
+    pub fn new<H: AsRef<str>>(host: H) -> JobRecord {
+        let now = Utc::now();
+
+        JobRecord {
+            started: now,
+            finished: now,
+            host: host.as_ref().to_string(),
+            entries: 0,
+            bytes: 0,
+            status: JobStatus::Succeeded,
+        }
+    }
+
+    pub(crate) fn append<B: Backend>(
+        &self,
+        pager: &mut Pager<B>,
+        header_id: &B::Id,
+        header: &mut Header,
+        tree: &mut Tree<B>,
+    ) -> ArchiveResult<(), B> {
+        let codec = pager.codec();
+        let inner = Inner::new(self.started.to_rfc3339(), Mode::job());
+        let mut entry = InnerBuilder::with_inner(pager, header_id, header, tree, inner).build()?;
+
+        let mut writer = codec.writer(vec![]);
+        writer.write(self)?;
+
+        entry.write_all(&writer.into_target())
+    }
+
+    pub(crate) fn load_all<B: Backend>(
+        pager: &mut Pager<B>,
+        tree: &mut Tree<B>,
+    ) -> ArchiveResult<Vec<JobRecord>, B> {
+        let codec = pager.codec();
+        let mut jobs = vec![];
+        let mut entry_opt = InnerEntry::first(pager, tree);
+
+        loop {
+            let mut entry = match entry_opt {
+                Some(Ok(entry)) => entry,
+                Some(Err(err)) => return Err(err),
+                None => break,
+            };
+
+            if entry.inner().mode.is_job() {
+                let buf = entry.read_content()?;
+                jobs.push(codec.reader(buf.as_slice()).read()?);
+            }
+
+            entry_opt = entry.next();
+        }
+
+        Ok(jobs)
+    }
+}
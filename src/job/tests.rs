@@ -0,0 +1,101 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use chrono::{TimeZone, Utc};
+
+use crate::job::{JobRecord, JobStatus};
+use crate::tests::setup_container_with_bsize;
+use crate::Archive;
+
+fn make_record(host: &str, status: JobStatus) -> JobRecord {
+    JobRecord {
+        started: Utc.timestamp_millis_opt(1000).unwrap(),
+        finished: Utc.timestamp_millis_opt(2000).unwrap(),
+        host: host.to_string(),
+        entries: 2,
+        bytes: 4711,
+        status,
+    }
+}
+
+#[test]
+fn new() {
+    let record = JobRecord::new("foo");
+
+    assert_eq!(record.started, record.finished);
+    assert_eq!(record.host, "foo");
+    assert_eq!(record.entries, 0);
+    assert_eq!(record.bytes, 0);
+    assert_eq!(record.status, JobStatus::Succeeded);
+}
+
+#[test]
+fn no_jobs() {
+    let container = setup_container_with_bsize(106);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.append_file("f1").build().unwrap();
+
+    assert!(archive.jobs().unwrap().is_empty());
+}
+
+#[test]
+fn jobs() {
+    let container = setup_container_with_bsize(106);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    let record1 = make_record("host1", JobStatus::Succeeded);
+    let record2 = make_record(
+        "a host with a very long name, which does not fit into one block of the archive",
+        JobStatus::Failed,
+    );
+
+    archive.append_file("f1").build().unwrap();
+    archive.append_job(&record1).unwrap();
+    archive.append_file("f2").build().unwrap();
+    archive.append_job(&record2).unwrap();
+
+    assert_eq!(archive.jobs().unwrap(), [record1, record2]);
+}
+
+#[test]
+fn hidden() {
+    let container = setup_container_with_bsize(106);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.append_job(&JobRecord::new("host")).unwrap();
+    archive.append_file("f1").build().unwrap();
+    archive.append_job(&JobRecord::new("host")).unwrap();
+    archive.append_job(&JobRecord::new("host")).unwrap();
+    archive.append_file("f2").build().unwrap();
+    archive.append_job(&JobRecord::new("host")).unwrap();
+
+    assert_eq!(archive.info().files, 2);
+
+    let entry = archive.first().unwrap().unwrap();
+    assert_eq!(entry.name(), "f1");
+
+    let entry = entry.next().unwrap().unwrap();
+    assert_eq!(entry.name(), "f2");
+
+    assert!(entry.next().is_none());
+}
@@ -216,70 +216,437 @@
 //!
 //! [nuts container]: nuts_container
 
+mod analyze;
+mod attachment;
+#[cfg(feature = "bench")]
+pub mod bench;
+mod bundle;
+mod cdc;
+mod chunked;
+mod codec;
+mod compression;
+#[cfg(feature = "consistency")]
+mod consistency;
 mod datetime;
+mod dump;
 mod entry;
 mod error;
+mod extract;
+#[cfg(feature = "faulty")]
+pub mod faulty;
+mod group;
 mod header;
+mod import;
+mod index;
+mod job;
 mod magic;
+mod maintain;
+mod manifest;
+mod name_index;
 mod pager;
+pub mod raw;
+mod reader;
+mod reorder;
+mod repair;
+#[cfg(any(feature = "tar", feature = "zip"))]
+mod resume;
+#[cfg(feature = "proptest")]
+pub mod roundtrip;
+mod salvage;
+mod savepoint;
+mod section;
+mod stats;
+mod stream;
+mod sync;
+#[cfg(feature = "tar")]
+mod tarball;
 #[cfg(test)]
 mod tests;
+#[cfg(feature = "trace")]
+mod trace;
+mod transform;
+mod trash;
 mod tree;
+mod unsupported;
 mod userdata;
+mod validate;
+mod verify;
+#[cfg(feature = "zip")]
+mod zipfile;
 
 use chrono::{DateTime, Utc};
-use log::debug;
-use nuts_container::backend::Backend;
+use log::{debug, warn};
+use nuts_container::backend::{Backend, BlockId};
 use nuts_container::container::Container;
+use sha2::{Digest as _, Sha256};
 use std::cmp;
-use std::convert::TryInto;
+use std::convert::TryFrom;
+#[cfg(any(feature = "tar", feature = "zip"))]
+use std::io;
+#[cfg(feature = "zip")]
+use std::io::Seek;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Arc;
 
-pub use entry::immut::{DirectoryEntry, Entry, FileEntry, SymlinkEntry};
-pub use entry::mode::Group;
-pub use entry::r#mut::{DirectoryBuilder, EntryMut, FileBuilder, SymlinkBuilder};
-pub use error::{ArchiveResult, Error};
+pub use analyze::{Analysis, Projection, SizeClass};
+pub use attachment::Attachment;
+pub use cdc::{Chunker, Chunks};
+pub use chunked::ChunkStats;
+pub use compression::Compression;
+pub use dump::{BlockDump, BlockKind};
+pub use entry::immut::{
+    DirectoryEntry, Entry, FileEntry, HardLinkEntry, SpecialEntry, SymlinkEntry,
+};
+pub use entry::mode::{Group, Mode};
+pub use entry::r#mut::{
+    DirectoryBuilder, EntryMut, FileBuilder, HardLinkBuilder, MultipartEntry, MultipartPart,
+    SpecialBuilder, SymlinkBuilder,
+};
+pub use entry::special::SpecialKind;
+pub use error::{ArchiveResult, Error, MultipartError, ParseModeError, RewriteError, TargetError};
+pub use extract::{ExtractPlan, PathPolicy, PlanAction, PlanItem};
+pub use group::AppendGroup;
+pub use index::{Index, IndexEntry};
+pub use job::{JobRecord, JobStatus};
+pub use maintain::{Budget, MaintainReport};
+pub use manifest::{Digest, Manifest, ManifestDiff, ManifestEntry};
+pub use pager::WriteVerification;
+pub use reader::ArchiveReader;
+pub use repair::{Fix, RepairReport};
+#[cfg(any(feature = "tar", feature = "zip"))]
+pub use resume::ImportCheckpoint;
+pub use salvage::SalvageReport;
+pub use section::Section;
+pub use stats::{ImportSummary, IoStats};
+pub use stream::{EntryStreamReader, EntryStreamWriter};
+#[cfg(feature = "trace")]
+pub use trace::Allocation;
+pub use transform::Transform;
+pub use unsupported::{FeatureAction, UnsupportedFeature};
+pub use validate::ImportReport;
+pub use verify::{Problem, VerifyReport};
 
-use crate::entry::immut::InnerEntry;
-use crate::entry::min_entry_size;
+use crate::codec::Codec;
+use crate::entry::immut::{visible_names, InnerEntry};
+use crate::entry::r#mut::{
+    check_metadata_size, dec_parent_children, grow_content, inc_parent_children, resize_name,
+    shrink_content, InnerBuilder, MAX_TARGET_LEN,
+};
+use crate::entry::{min_entry_size, Inner};
 use crate::header::Header;
+use crate::maintain::Scrubber;
+use crate::name_index::NameIndex;
 use crate::pager::Pager;
-use crate::tree::Tree;
-use crate::userdata::Userdata;
+use crate::savepoint::Savepoint;
+use crate::stats::Measure;
+use crate::tree::free::FreeList;
+use crate::tree::{Tree, NUM_DIRECT};
+use crate::unsupported::FeaturePolicy;
+use crate::userdata::{Userdata, FREEZE_DIGEST_SIZE};
+
+/// Selects the codec for the revision of the archive.
+///
+/// This is the only place, where the revision and the features of the
+/// archive are checked. An archive, which uses a feature unknown to this
+/// version, is refused with an [`Error::UnsupportedRevision`] error.
+fn header_codec<B: Backend>(header: &Header) -> ArchiveResult<Codec, B> {
+    Codec::new(header.revision()).ok_or(Error::UnsupportedRevision(header.revision()))
+}
+
+/// Reads the header and the tree from the header block.
+fn read_header_block<B: Backend>(
+    container: &mut Container<B>,
+    id: &B::Id,
+) -> ArchiveResult<(Header, Tree<B>), B> {
+    let mut buf = vec![0; container.block_size() as usize];
+
+    container.read(id, &mut buf)?;
+
+    let mut reader = Codec::current().reader(buf.as_slice());
+    let header = reader.read::<Header>()?;
+    let codec = header_codec(&header)?;
+    let tree = Tree::read(&codec, &mut codec.reader(reader.remaining()))?;
+
+    Ok((header, tree))
+}
+
+/// Reads the header and the tree of the archive.
+///
+/// Falls back to the backup copy, if the header block cannot be read. The
+/// error of the header block is returned, if the backup cannot be read
+/// either. An unsupported revision is no damage, the backup is not used
+/// then. Otherwise a newer archive would be overwritten with an older state.
+fn read_header<B: Backend>(
+    container: &mut Container<B>,
+    userdata: &Userdata<B>,
+) -> ArchiveResult<(Header, Tree<B>), B> {
+    let err = match read_header_block(container, &userdata.id) {
+        Ok(result) => return Ok(result),
+        Err(err @ Error::UnsupportedRevision(_)) => return Err(err),
+        Err(err) => err,
+    };
+
+    match userdata.backup.as_ref() {
+        Some(backup) => {
+            warn!(
+                "header at {} is damaged ({}), using backup",
+                userdata.id, err
+            );
+            read_header_block(container, backup).map_err(|_| err)
+        }
+        None => Err(err),
+    }
+}
+
+/// Aquires the block for the backup copy of the header.
+///
+/// The block is recorded in the userdata, archives created before the
+/// backup existed get one on the first flush.
+fn create_backup<B: Backend>(pager: &mut Pager<B>) -> ArchiveResult<B::Id, B> {
+    let mut userdata = Userdata::fetch(pager)?;
+
+    let id = match userdata.backup.as_ref() {
+        Some(id) => id.clone(),
+        None => {
+            let id = pager.aquire()?;
+
+            userdata.backup = Some(id.clone());
+            userdata.store(pager)?;
+
+            debug!("backup of header created at {}", id);
+
+            id
+        }
+    };
+
+    pager.set_backup(Some(id.clone()));
+
+    Ok(id)
+}
 
 fn flush_header<B: Backend>(
     pager: &mut Pager<B>,
     id: &B::Id,
     header: &Header,
-    tree: &Tree<B>,
+    tree: &mut Tree<B>,
 ) -> ArchiveResult<(), B> {
+    if header.is_deferred() {
+        pager.inc_generation();
+        return Ok(());
+    }
+
+    let backup = match pager.backup().cloned() {
+        Some(backup) => backup,
+        None => create_backup(pager)?,
+    };
+
+    let codec = pager.codec();
     let mut writer = pager.create_writer();
     let mut n = 0;
 
-    n += writer.write(header)?;
-    n += writer.write(tree)?;
+    n += writer.write(&header.for_codec(&codec))?;
+    n += tree.write(&codec, &mut writer)?;
 
+    // the backup is written last, one of both copies is always intact
     pager.write_buf(id)?;
+    pager.write_buf(&backup)?;
+    pager.inc_generation();
+
+    debug!(
+        "{} bytes written into header at {}, generation {}",
+        n,
+        id,
+        pager.generation()
+    );
+
+    #[cfg(feature = "consistency")]
+    consistency::assert_consistency(pager, header, tree);
+
+    Ok(())
+}
+
+/// Puts blocks, which were removed from the tree, into the free list.
+///
+/// The free list is created on first use.
+fn release_blocks<B: Backend>(
+    pager: &mut Pager<B>,
+    tree: &mut Tree<B>,
+    ids: Vec<B::Id>,
+) -> ArchiveResult<(), B> {
+    if !tree.has_free_list() {
+        let free = FreeList::create(pager)?;
+        let mut userdata = Userdata::fetch(pager)?;
+
+        userdata.free = Some(free.anchor().clone());
+        userdata.store(pager)?;
+
+        tree.set_free_list(free);
+    }
+
+    // holes of sparse files have no block to release
+    for id in ids.into_iter().filter(|id| !id.is_null()) {
+        tree.release(pager, id)?;
+    }
+
+    Ok(())
+}
+
+/// Entries, which depend on an entry of the archive.
+#[derive(Default)]
+struct Dependents {
+    /// Names of the hard links, which refer to the entry.
+    links: Vec<String>,
+    /// Positions (`idx..end_idx`) of the attachments of the entry.
+    attachments: Vec<(usize, usize)>,
+    /// Position of the next visible entry with the same name.
+    next: Option<usize>,
+}
+
+/// Collects the entries, which depend on the entry `name` at `idx`.
+///
+/// Hard links refer to the first entry with a name. The attachments of
+/// the entry are stored behind the entry, up to the next visible or
+/// trashed entry with the same name.
+fn dependents<B: Backend>(
+    pager: &mut Pager<B>,
+    tree: &mut Tree<B>,
+    name: &str,
+    idx: usize,
+) -> ArchiveResult<Dependents, B> {
+    let mut deps = Dependents {
+        links: vec![],
+        attachments: vec![],
+        next: None,
+    };
+    let mut attached = true;
+    let mut entry_opt = InnerEntry::first(pager, tree);
+
+    while let Some(result) = entry_opt {
+        let mut entry = result?;
+        let mode = entry.inner().mode;
+
+        if mode.is_hardlink() && !mode.is_internal() && entry.read_content()? == name.as_bytes() {
+            deps.links.push(entry.inner().name.clone());
+        }
+
+        if entry.idx() > idx && entry.inner().name == name {
+            if mode.is_attachment() {
+                if attached {
+                    deps.attachments.push((entry.idx(), entry.end_idx()));
+                }
+            } else if mode.is_trashed() {
+                attached = false;
+            } else if !mode.is_internal() && deps.next.is_none() {
+                attached = false;
+                deps.next = Some(entry.idx());
+            }
+        }
+
+        entry_opt = entry.next();
+    }
+
+    Ok(deps)
+}
+
+/// Removes the entry `name` located at `idx..end_idx` together with its
+/// attachments from the archive.
+///
+/// A `visible` entry is counted in the header and recorded in the name
+/// index and the child count of its parent, an entry in the trash is not.
+#[allow(clippy::too_many_arguments)]
+fn remove_entry<B: Backend>(
+    pager: &mut Pager<B>,
+    header_id: &B::Id,
+    header: &mut Header,
+    tree: &mut Tree<B>,
+    name: &str,
+    idx: usize,
+    end_idx: usize,
+    deps: Dependents,
+    visible: bool,
+) -> ArchiveResult<(), B> {
+    let mut removed = vec![];
+
+    // back to front, the positions of the attachments do not change
+    for (start, end) in deps.attachments.iter().rev() {
+        removed.extend(tree.remove(pager, *start, end - start)?);
+    }
+
+    removed.extend(tree.remove(pager, idx, end_idx - idx)?);
 
-    debug!("{} bytes written into header at {}", n, id);
+    if visible {
+        header.nfiles = header.nfiles.saturating_sub(1);
+    }
+
+    header.touch();
+
+    flush_header(pager, header_id, header, tree)?;
+
+    release_blocks(pager, tree, removed)?;
+
+    if visible {
+        dec_parent_children(pager, tree, name)?;
+    }
+
+    if let Some(mut names) = tree.take_names(pager)? {
+        if visible {
+            names.forget(name, deps.next.map(|n| n as u64));
+        }
+
+        for (start, end) in deps.attachments.iter().rev() {
+            names.shift(*start as u64, (end - start) as u64);
+        }
+
+        names.shift(idx as u64, (end_idx - idx) as u64);
+        names.store(pager)?;
+
+        tree.set_names(names);
+    }
+
+    debug!(
+        "entry {} removed at {}..{} with {} attachments",
+        name,
+        idx,
+        end_idx,
+        deps.attachments.len()
+    );
 
     Ok(())
 }
 
-fn min_block_size<B: Backend>() -> usize {
-    let header = Header::size();
-    let tree = Tree::<B>::size();
+// number of blocks, which are copied at once by Archive::rewrite_to()
+const REWRITE_BATCH: usize = 64;
+
+fn freeze_digest(token: &[u8]) -> [u8; FREEZE_DIGEST_SIZE] {
+    Sha256::digest(token).into()
+}
+
+fn min_block_size<B: Backend>(num_direct: u32) -> usize {
+    // header and tree are stored together in the header block
+    let header = Header::size() + Tree::<B>::size(num_direct);
     let entry = min_entry_size();
 
-    let min_size = cmp::max(cmp::max(header, tree), entry);
+    let min_size = cmp::max(header, entry);
 
     debug!(
-        "min_block_size = {} (header: {}, tree: {}, entry: {})",
-        min_size, header, tree, entry
+        "min_block_size = {} (header: {}, entry: {})",
+        min_size, header, entry
     );
 
     min_size
 }
 
+fn check_block_size<B: Backend>(container: &Container<B>, num_direct: u32) -> ArchiveResult<(), B> {
+    let min_size = min_block_size::<B>(num_direct) as u32;
+
+    if container.block_size() < min_size {
+        Err(Error::BlockSizeTooSmall(container.block_size(), min_size))
+    } else {
+        Ok(())
+    }
+}
+
 /// Information/statistics from the archive.
 #[derive(Debug)]
 pub struct Info {
@@ -289,22 +656,88 @@ pub struct Info {
     /// Time when the last entry was appended
     pub modified: DateTime<Utc>,
 
+    /// Number of blocks allocated for the archive
+    ///
+    /// Holes of sparse files (see [`EntryMut::write_zeroes()`]) are counted
+    /// like blocks, although they are not allocated in the container.
+    pub blocks: u64,
+
+    /// Number of files stored in the archive
+    pub files: u64,
+
+    /// Number of direct blocks, see [`Archive::create_with_num_direct()`]
+    pub num_direct: u32,
+}
+
+/// Summary of an archive, returned by [`Archive::peek()`].
+#[derive(Debug)]
+pub struct ArchiveSummary {
+    /// Revision of the archive format
+    pub revision: u16,
+
+    /// Block size of the underlaying container
+    pub block_size: u32,
+
+    /// Time when the archive was created
+    pub created: DateTime<Utc>,
+
+    /// Time when the last entry was appended
+    pub modified: DateTime<Utc>,
+
     /// Number of blocks allocated for the archive
     pub blocks: u64,
 
     /// Number of files stored in the archive
     pub files: u64,
+
+    /// Set to `true` if the archive is frozen
+    pub frozen: bool,
 }
 
+/// A writable archive.
+///
+/// This is just another name for [`Archive`], use it in signatures next to
+/// [`ArchiveReader`] to make clear that the archive is modified.
+pub type ArchiveWriter<B> = Archive<B>;
+
 /// The archive.
+///
+/// The archive provides read and write access. If you only need to read,
+/// convert it into an [`ArchiveReader`] with [`Archive::into_reader()`].
+///
+/// The state kept by the archive itself (header, tree and its caches) is
+/// [`Send`] and [`Sync`]. But the archive is neither [`Send`] nor [`Sync`]
+/// because the underlaying [`Container`] is not: it keeps its password
+/// callback in an [`Rc`](std::rc::Rc). Values detached from the archive
+/// (e.g. [`Index`], [`Manifest`] or [`ExtractPlan`]) can be moved into other
+/// threads.
 pub struct Archive<B: Backend> {
     pager: Pager<B>,
     header_id: B::Id,
     header: Header,
     tree: Tree<B>,
+    max_target_len: usize,
+    scrubber: Scrubber,
+    savepoints: Vec<Savepoint>,
+    section: Option<String>,
+    rename_history: usize,
+    trash: bool,
+    path_policy: PathPolicy,
+    feature_policy: FeaturePolicy,
+    chunker: Chunker,
 }
 
 impl<B: Backend> Archive<B> {
+    /// Returns the minimum block size of a container, which can hold an
+    /// archive.
+    ///
+    /// The header of the archive and the metadata of an entry must fit into
+    /// a single block. A name, which does not fit into the block, is stored
+    /// in continuation blocks behind it.
+    pub fn min_block_size() -> u32 {
+        min_block_size::<B>(NUM_DIRECT) as u32
+    }
+
     /// Creates a new archive in `container`.
     ///
     /// General initial information about the archive is stored in the
@@ -318,25 +751,102 @@ impl<B: Backend> Archive<B> {
     /// # Errors
     ///
     /// If user data of the container could be overwritten, an
-    /// [`Error::OverwriteUserdata`] error will be returned.
+    /// [`Error::OverwriteUserdata`] error will be returned. If the block size
+    /// of the container is smaller than [`Archive::min_block_size()`], an
+    /// [`Error::BlockSizeTooSmall`] error is returned.
     pub fn create(container: Container<B>, force: bool) -> ArchiveResult<Archive<B>, B> {
-        if (container.block_size() as usize) < min_block_size::<B>() {
-            return Err(Error::InvalidBlockSize);
-        }
+        Self::create_with_num_direct(container, force, NUM_DIRECT)
+    }
+
+    /// Creates a new archive in `container` with `num_direct` direct
+    /// blocks.
+    ///
+    /// The ids of the first `num_direct` blocks of the archive are stored
+    /// directly in the header block, all further blocks are reached through
+    /// one, two or three levels of tree nodes. [`Archive::create()`] uses
+    /// 12 direct blocks. Fewer direct blocks leave room in the header block
+    /// of containers with a small block size, more direct blocks save the
+    /// tree nodes of small archives and increase the maximum number of
+    /// blocks. The number is stored in the header and cannot be changed
+    /// later.
+    ///
+    /// # Errors
+    ///
+    /// If the header block cannot hold `num_direct` block ids, an
+    /// [`Error::BlockSizeTooSmall`] error is returned. Otherwise the same
+    /// errors as for [`Archive::create()`] are returned.
+    pub fn create_with_num_direct(
+        container: Container<B>,
+        force: bool,
+        num_direct: u32,
+    ) -> ArchiveResult<Archive<B>, B> {
+        check_block_size(&container, num_direct)?;
 
         let mut pager = Pager::new(container);
         let userdata = Userdata::create(&mut pager, force)?;
 
-        let header = Header::create();
-        let tree = Tree::<B>::new();
+        Self::create_with(pager, userdata, num_direct)
+    }
+
+    /// Creates a new anchored archive in `container`.
+    ///
+    /// Other than [`Archive::create()`] the general information about the
+    /// archive is not stored in the [user data](Container::userdata) of the
+    /// container but in a separate anchor block, which is aquired from the
+    /// container. The user data of the container is neither checked nor
+    /// modified. This way the archive can share the container with other
+    /// nuts-based data structures. All of them allocate their blocks with
+    /// [`Container::aquire()`], the archive only ever releases blocks it
+    /// aquired itself.
+    ///
+    /// The id of the anchor block is available with [`Archive::anchor()`].
+    /// It is up to the owner of the container to remember the id (e.g. in
+    /// its own user data). Pass it to [`Archive::open_anchored()`] to open
+    /// the archive again.
+    ///
+    /// # Errors
+    ///
+    /// If the block size of the container is smaller than
+    /// [`Archive::min_block_size()`], an [`Error::BlockSizeTooSmall`] error
+    /// is returned.
+    pub fn create_anchored(container: Container<B>) -> ArchiveResult<Archive<B>, B> {
+        check_block_size(&container, NUM_DIRECT)?;
+
+        let mut pager = Pager::new(container);
+        let (userdata, anchor) = Userdata::create_anchored(&mut pager)?;
+
+        pager.set_anchor(Some(anchor));
+
+        Self::create_with(pager, userdata, NUM_DIRECT)
+    }
+
+    fn create_with(
+        mut pager: Pager<B>,
+        userdata: Userdata<B>,
+        num_direct: u32,
+    ) -> ArchiveResult<Archive<B>, B> {
+        let codec = Codec::for_num_direct(num_direct);
+        let header = Header::create(&codec);
+        let mut tree = Tree::<B>::new(num_direct);
 
-        flush_header(&mut pager, &userdata.id, &header, &tree)?;
+        pager.set_codec(codec);
+
+        flush_header(&mut pager, &userdata.id, &header, &mut tree)?;
 
         let archive = Archive {
             pager,
             header_id: userdata.id,
             header,
             tree,
+            max_target_len: MAX_TARGET_LEN,
+            scrubber: Scrubber::new(),
+            savepoints: vec![],
+            section: None,
+            rename_history: 0,
+            trash: false,
+            path_policy: PathPolicy::default(),
+            feature_policy: FeaturePolicy::default(),
+            chunker: Chunker::default(),
         };
 
         debug!("archive created, header: {}", archive.header_id);
@@ -349,31 +859,85 @@ impl<B: Backend> Archive<B> {
     /// The initial information about the archive is loaded from the
     /// [user data](Container::userdata) of the container.
     ///
+    /// Only the header block of the archive is read, the remaining metadata
+    /// is loaded on first use. Call [`Archive::warm_up()`] if you prefer to
+    /// load it up front.
+    ///
+    /// A backup copy of the header block is written on every change of the
+    /// archive. If the header block is damaged, the archive is opened from
+    /// the backup and the header block is repaired with the next change.
+    ///
     /// # Errors
     ///
     /// If no user data is stored in the container, an
     /// [`Error::InvalidUserdata(None)`](Error::InvalidUserdata) error is
     /// returned; if it does not contain valid archive information, an
     /// [`Error::InvalidUserdata(Some(...))`](Error::InvalidUserdata) error is
-    /// returned.
+    /// returned. If the block size of the container is smaller than
+    /// [`Archive::min_block_size()`], an [`Error::BlockSizeTooSmall`] error
+    /// is returned.
     pub fn open(container: Container<B>) -> ArchiveResult<Archive<B>, B> {
-        if (container.block_size() as usize) < min_block_size::<B>() {
-            return Err(Error::InvalidBlockSize);
-        }
+        // the tree is checked when the header block is read
+        check_block_size(&container, 0)?;
 
         let mut pager = Pager::new(container);
         let userdata = Userdata::load(&mut pager)?;
 
-        let mut reader = pager.read_buf(&userdata.id)?;
+        Self::open_with(pager, userdata)
+    }
+
+    /// Opens an anchored archive from `container`.
+    ///
+    /// The initial information about the archive is loaded from the block
+    /// `anchor`, which was returned by [`Archive::anchor()`] when the
+    /// archive was [created](Archive::create_anchored). The
+    /// [user data](Container::userdata) of the container is not touched.
+    ///
+    /// # Errors
+    ///
+    /// If `anchor` does not contain valid archive information, an
+    /// [`Error::InvalidUserdata`] error is returned. Otherwise the same
+    /// errors as for [`Archive::open()`] are returned.
+    pub fn open_anchored(container: Container<B>, anchor: &B::Id) -> ArchiveResult<Archive<B>, B> {
+        check_block_size(&container, 0)?;
+
+        let mut pager = Pager::new(container);
+        let userdata = Userdata::load_anchored(&mut pager, anchor)?;
+
+        pager.set_anchor(Some(anchor.clone()));
+
+        Self::open_with(pager, userdata)
+    }
+
+    fn open_with(mut pager: Pager<B>, userdata: Userdata<B>) -> ArchiveResult<Archive<B>, B> {
+        let (mut header, mut tree) = read_header(&mut pager, &userdata)?;
+
+        pager.set_codec(header_codec(&header)?);
+        pager.set_backup(userdata.backup.clone());
+        header.set_frozen(userdata.freeze.is_some());
+
+        if let Some(id) = userdata.index.as_ref() {
+            tree.set_names_root(id.clone());
+        }
 
-        let header = reader.read::<Header>()?;
-        let tree = reader.read::<Tree<B>>()?;
+        if let Some(id) = userdata.free.as_ref() {
+            tree.set_free_anchor(id.clone());
+        }
 
         let archive = Archive {
             pager,
             header_id: userdata.id,
             header,
             tree,
+            max_target_len: MAX_TARGET_LEN,
+            scrubber: Scrubber::new(),
+            savepoints: vec![],
+            section: None,
+            rename_history: 0,
+            trash: false,
+            path_policy: PathPolicy::default(),
+            feature_policy: FeaturePolicy::default(),
+            chunker: Chunker::default(),
         };
 
         debug!("archive opened, header: {}", archive.header_id);
@@ -381,6 +945,62 @@ impl<B: Backend> Archive<B> {
         Ok(archive)
     }
 
+    /// Reads a summary of the archive stored in `container`.
+    ///
+    /// Only the header block of the archive is read, the archive itself is
+    /// not opened. This is a cheap way to inventory many containers.
+    ///
+    /// # Errors
+    ///
+    /// The same errors as for [`Archive::open()`] are returned.
+    pub fn peek(container: &mut Container<B>) -> ArchiveResult<ArchiveSummary, B> {
+        check_block_size(container, 0)?;
+
+        let userdata = Userdata::load(container)?;
+
+        Self::peek_with(container, userdata)
+    }
+
+    /// Reads a summary of the anchored archive stored in `container`.
+    ///
+    /// See [`Archive::peek()`] and [`Archive::open_anchored()`].
+    pub fn peek_anchored(
+        container: &mut Container<B>,
+        anchor: &B::Id,
+    ) -> ArchiveResult<ArchiveSummary, B> {
+        check_block_size(container, 0)?;
+
+        let userdata = Userdata::load_anchored(container, anchor)?;
+
+        Self::peek_with(container, userdata)
+    }
+
+    fn peek_with(
+        container: &mut Container<B>,
+        userdata: Userdata<B>,
+    ) -> ArchiveResult<ArchiveSummary, B> {
+        let (header, tree) = read_header(container, &userdata)?;
+
+        Ok(ArchiveSummary {
+            revision: header_codec::<B>(&header)?.revision(),
+            block_size: container.block_size(),
+            created: header.created,
+            modified: header.modified,
+            blocks: tree.nblocks(),
+            files: header.nfiles,
+            frozen: userdata.freeze.is_some(),
+        })
+    }
+
+    /// Returns the anchor block of an anchored archive.
+    ///
+    /// Returns [`None`] if the archive stores its general information in
+    /// the [user data](Container::userdata) of the container. See
+    /// [`Archive::create_anchored()`].
+    pub fn anchor(&self) -> Option<&B::Id> {
+        self.pager.anchor()
+    }
+
     /// Fetches statistics/information from the archive.
     pub fn info(&self) -> Info {
         Info {
@@ -388,20 +1008,186 @@ impl<B: Backend> Archive<B> {
             modified: self.header.modified,
             blocks: self.tree.nblocks(),
             files: self.header.nfiles,
+            num_direct: self.tree.num_direct(),
         }
     }
 
+    /// Reserves `percent` of the capacity of the archive for metadata.
+    ///
+    /// Content appended to an entry cannot use the reserved capacity. When
+    /// only reserved capacity is left, [`EntryMut::write()`] fails with an
+    /// [`Error::Full`] error, but new entries can still be appended. This
+    /// way you are still able to store some bookkeeping entries (e.g.
+    /// checksums or a manifest) after large content filled up the archive.
+    ///
+    /// The setting is not persisted, a value greater than `100` is treated
+    /// as `100`. By default no capacity is reserved.
+    pub fn set_reserved_capacity(&mut self, percent: u8) {
+        self.tree.set_reserved(percent)
+    }
+
+    /// Records up to `limit` previous names of an entry, when it is
+    /// [renamed](Archive::rename).
+    ///
+    /// The previous names are stored in the metadata of the entry, the
+    /// oldest name is dropped if the limit is exceeded. They are available
+    /// with [`Entry::previous_names()`] and used by
+    /// [`Archive::lookup_renamed()`]. A limit of `0` disables the history,
+    /// names recorded before are kept.
+    ///
+    /// The setting is not persisted, by default no history is recorded.
+    pub fn set_rename_history(&mut self, limit: usize) {
+        self.rename_history = limit;
+    }
+
+    /// Moves entries into a trash instead of removing them.
+    ///
+    /// If enabled, [`Archive::remove()`] hides the entry, but keeps its
+    /// metadata, content and attachments. A trashed entry is restored with
+    /// [`Archive::undelete()`]. Its blocks are kept until the trash is
+    /// emptied with [`Archive::empty_trash()`]. If the archive runs out of
+    /// capacity while an entry is appended, the oldest entries of the trash
+    /// are removed to make room for the new entry, which discards the
+    /// savepoints. Entries of an [append group](Archive::append_group) do
+    /// not remove trashed entries. An [`Error::Full`] error is returned
+    /// only, if the trash is empty.
+    ///
+    /// The setting is not persisted, by default entries are removed
+    /// immediately. Entries trashed before are kept in the trash.
+    pub fn set_trash(&mut self, enabled: bool) {
+        self.trash = enabled;
+    }
+
+    /// Sets how entry names and symlink targets, which would escape the
+    /// destination directory, are handled by [`Archive::unpack()`] and
+    /// [`Archive::plan_extract()`].
+    ///
+    /// Names and symlink targets like `../../etc/passwd` or `/etc/passwd`
+    /// are rejected by default, see [`PathPolicy`]. Independent of the
+    /// policy an entry is never extracted through a symlink below the
+    /// destination directory.
+    ///
+    /// The setting is not persisted.
+    pub fn set_path_policy(&mut self, policy: PathPolicy) {
+        self.path_policy = policy;
+    }
+
+    /// Sets what happens, if an imported entry uses a `feature`, which
+    /// cannot be represented in the archive.
+    ///
+    /// Applies to the import of tar streams (`tar` feature).
+    /// By default an unsupported feature is dropped and a warning is
+    /// logged, see [`FeatureAction`]. Choose [`FeatureAction::Error`] to
+    /// fail the import instead.
+    ///
+    /// The setting is not persisted.
+    pub fn set_unsupported_action(&mut self, feature: UnsupportedFeature, action: FeatureAction) {
+        self.feature_policy.set(feature, action);
+    }
+
+    /// Enables the strict mode of the archive.
+    ///
+    /// Some anomalies of the block tree (e.g. a block which is aquired
+    /// twice) are logged and otherwise ignored. In strict mode they are
+    /// reported with an [`Error::TreeInconsistent`] error instead, so that
+    /// nothing is built on top of a corrupted structure.
+    ///
+    /// The setting is not persisted, by default the strict mode is disabled.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.tree.set_strict(strict)
+    }
+
+    /// Enables the in-memory mapping of the index structures.
+    ///
+    /// Locating an entry (e.g. with [`Archive::lookup_indexed()`] or when
+    /// reading content) translates its position in the archive into the id
+    /// of a block by reading the nodes of the block tree. If enabled, the
+    /// ids of all blocks are loaded once into a compact list and all further
+    /// lookups are served from memory. The list is updated in place, when
+    /// the archive is modified, it is not loaded again. This pays off for
+    /// large archives. The name index is always kept in memory.
+    ///
+    /// The setting is not persisted, by default the mapping is disabled.
+    pub fn set_index_mapping(&mut self, enabled: bool) {
+        self.tree.set_mapped(enabled)
+    }
+
+    /// Enables the read-after-write verification of written blocks.
+    ///
+    /// Depending on `verification` written blocks are read back immediately
+    /// and compared with the written data. This catches flaky backends at
+    /// write time. A mismatch is reported with an [`Error::WriteMismatch`]
+    /// error. Verification costs an extra read for each verified block.
+    ///
+    /// The setting is not persisted, by default blocks are not verified.
+    pub fn set_write_verification(&mut self, verification: WriteVerification) {
+        self.pager.set_verification(verification)
+    }
+
+    /// Registers a [`Transform`] of the content of file entries.
+    ///
+    /// Enable the transformation for a new file with
+    /// [`FileBuilder::add_transform()`]. The registration is not persisted,
+    /// only the names of the transformations are stored with the entries.
+    /// Register the same transformations again after opening the archive,
+    /// otherwise the content of transformed entries cannot be read and an
+    /// [`Error::UnknownTransform`] error is returned.
+    ///
+    /// A transformation with the same [name](Transform::name) is replaced.
+    pub fn register_transform<T: Transform + 'static>(&mut self, transform: T) {
+        self.pager.transforms_mut().register(Arc::new(transform))
+    }
+
+    /// Sets the maximum length (in bytes) of a symlink target.
+    ///
+    /// [`SymlinkBuilder::build()`] rejects targets which are longer with an
+    /// [`Error::InvalidTarget`] error. The setting is not persisted, by
+    /// default a target can have up to 4096 bytes.
+    pub fn set_max_target_len(&mut self, len: usize) {
+        self.max_target_len = len;
+    }
+
+    /// Sets the section, which new entries are appended under.
+    ///
+    /// A section is a lightweight, named group of entries (e.g. `system`,
+    /// `home` or `db` of a backup job). It is recorded with each entry, the
+    /// entries of a section can be traversed with
+    /// [`Archive::first_in_section()`], summarized with
+    /// [`Archive::sections()`] and removed with [`Archive::prune_section()`].
+    ///
+    /// Pass [`None`] to append new entries outside of any section. The
+    /// setting is not persisted, it can be overwritten for a single entry
+    /// with [`FileBuilder::set_section()`] and friends.
+    pub fn set_section(&mut self, section: Option<&str>) {
+        self.section = section.map(|s| s.to_string());
+    }
+
+    /// Returns the section, which new entries are appended under.
+    ///
+    /// See [`Archive::set_section()`].
+    pub fn section(&self) -> Option<&str> {
+        self.section.as_deref()
+    }
+
     /// Returns the first entry in the archive.
     ///
     /// Next, you can use [`Entry::next()`] to traverse through the archive.
     ///
     /// If the archive is empty, [`None`] is returned.
     pub fn first<'a>(&'a mut self) -> Option<ArchiveResult<Entry<'a, B>, B>> {
-        match InnerEntry::first(&mut self.pager, &mut self.tree) {
-            Some(Ok(inner)) => Some(inner.try_into()),
-            Some(Err(err)) => Some(Err(err)),
-            None => None,
-        }
+        Entry::skip_internal(InnerEntry::first(&mut self.pager, &mut self.tree))
+    }
+
+    /// Returns the first entry of the section with the given `name`.
+    ///
+    /// Use [`Entry::next_in_section()`] to traverse through the remaining
+    /// entries of the section. If the section has no entries, [`None`] is
+    /// returned.
+    pub fn first_in_section<'a, N: AsRef<str>>(
+        &'a mut self,
+        name: N,
+    ) -> Option<ArchiveResult<Entry<'a, B>, B>> {
+        Entry::skip_to_section(self.first(), Some(name.as_ref()))
     }
 
     /// Searches for an entry with the given `name`.
@@ -432,59 +1218,2001 @@ impl<B: Backend> Archive<B> {
         None
     }
 
-    /// Appends a new file entry with the given `name` at the end of the
-    /// archive.
+    /// Creates a name index for the archive.
     ///
-    /// The method returns a [`FileBuilder`] instance, where you are able to
-    /// set some more properties for the new entry. Calling
-    /// [`FileBuilder::build()`] will finally create the entry.
-    pub fn append_file<'a, N: AsRef<str>>(&'a mut self, name: N) -> FileBuilder<'a, B> {
-        FileBuilder::new(
-            &mut self.pager,
-            &self.header_id,
-            &mut self.header,
-            &mut self.tree,
-            name.as_ref().to_string(),
-        )
+    /// The name index maps the names of the entries to their location in
+    /// the archive. It is stored in its own blocks and loaded into memory
+    /// when the archive is opened. Once created, it is kept up to date when
+    /// new entries are appended. Use [`Archive::lookup_indexed()`] to find
+    /// an entry without scanning the archive.
+    ///
+    /// Nothing happens, if the archive already has a name index.
+    ///
+    /// # Errors
+    ///
+    /// If the archive is frozen, an [`Error::Frozen`] error is returned.
+    pub fn create_index(&mut self) -> ArchiveResult<(), B> {
+        if self.tree.has_names() {
+            return Ok(());
+        }
+
+        self.header.check_mutable()?;
+
+        self.build_index()
     }
 
-    /// Appends a new directory entry with the given `name` at the end of the
-    /// archive.
-    ///
-    /// The method returns a [`DirectoryBuilder`] instance, where you are able
-    /// to set some more properties for the new entry. Calling
-    /// [`DirectoryBuilder::build()`] will finally create the entry.
-    pub fn append_directory<'a, N: AsRef<str>>(&'a mut self, name: N) -> DirectoryBuilder<'a, B> {
-        DirectoryBuilder::new(
-            &mut self.pager,
-            &self.header_id,
-            &mut self.header,
+    /// Builds the name index from scratch and replaces the current one.
+    fn build_index(&mut self) -> ArchiveResult<(), B> {
+        let mut names = NameIndex::create(&mut self.pager)?;
+        let visible = visible_names(&mut self.pager, &mut self.tree, 0)?;
+
+        for (idx, name) in visible {
+            names.insert(&mut self.pager, &name, idx as u64)?;
+        }
+
+        let mut userdata = Userdata::fetch(&mut self.pager)?;
+
+        userdata.index = Some(names.first().clone());
+        userdata.store(&mut self.pager)?;
+
+        if let Some(old) = self.tree.take_names(&mut self.pager)? {
+            old.release(&mut self.pager)?;
+        }
+
+        self.tree.set_names(names);
+
+        Ok(())
+    }
+
+    /// Tests whether the archive has a name index, see
+    /// [`Archive::create_index()`].
+    pub fn has_index(&self) -> bool {
+        self.tree.has_names()
+    }
+
+    /// Searches for an entry with the given `name` using the name index.
+    ///
+    /// Returns the same entry as [`Archive::lookup()`], but the entry is
+    /// located with the name index instead of scanning the archive. If the
+    /// archive has no name index, it falls back to [`Archive::lookup()`].
+    ///
+    /// # Errors
+    ///
+    /// If the index refers to an entry with another name, an
+    /// [`Error::InvalidNameIndex`] error is returned.
+    pub fn lookup_indexed<'a, N: AsRef<str>>(
+        &'a mut self,
+        name: N,
+    ) -> Option<ArchiveResult<Entry<'a, B>, B>> {
+        let name = name.as_ref();
+
+        let idx = match self.tree.names_mut(&mut self.pager) {
+            Ok(Some(names)) => names.get(name)? as usize,
+            Ok(None) => return self.lookup(name),
+            Err(err) => return Some(Err(err)),
+        };
+
+        let id = match self.tree.lookup(&mut self.pager, idx)? {
+            Ok(id) => id.clone(),
+            Err(err) => return Some(Err(err)),
+        };
+
+        let entry = match InnerEntry::load(&mut self.pager, &mut self.tree, idx, &id) {
+            Ok(entry) => entry,
+            Err(err) => return Some(Err(err)),
+        };
+
+        if entry.inner().name != name {
+            return Some(Err(Error::InvalidNameIndex));
+        }
+
+        Some(Entry::try_from(entry))
+    }
+
+    /// Searches for an entry with the given `name`, which might have been
+    /// renamed.
+    ///
+    /// Like [`Archive::lookup()`] the first entry with the given name is
+    /// returned. If there is no such entry, the first entry, which had the
+    /// name before it was [renamed](Archive::rename), is returned. This way
+    /// references from older manifests can still be resolved. Previous names
+    /// are only recorded, if enabled with [`Archive::set_rename_history()`].
+    pub fn lookup_renamed<'a, N: AsRef<str>>(
+        &'a mut self,
+        name: N,
+    ) -> Option<ArchiveResult<Entry<'a, B>, B>> {
+        let name = name.as_ref();
+        let mut entry_opt = InnerEntry::first(&mut self.pager, &mut self.tree);
+        let mut renamed = None;
+
+        let idx = loop {
+            let entry = match entry_opt {
+                Some(Ok(entry)) => entry,
+                Some(Err(err)) => return Some(Err(err)),
+                None => break renamed?,
+            };
+
+            let inner = entry.inner();
+
+            if !inner.mode.is_internal() {
+                if inner.name == name {
+                    break entry.idx();
+                }
+
+                if renamed.is_none() && inner.previous_names.iter().any(|n| n == name) {
+                    renamed = Some(entry.idx());
+                }
+            }
+
+            entry_opt = entry.next();
+        };
+
+        let id = match self.tree.lookup(&mut self.pager, idx)? {
+            Ok(id) => id.clone(),
+            Err(err) => return Some(Err(err)),
+        };
+
+        match InnerEntry::load(&mut self.pager, &mut self.tree, idx, &id) {
+            Ok(entry) => Some(Entry::try_from(entry)),
+            Err(err) => Some(Err(err)),
+        }
+    }
+
+    /// Searches for the first visible entry with the given `name`.
+    ///
+    /// Returns the tree index of the entry, the index behind its last
+    /// content block and its metadata.
+    fn locate(&mut self, name: &str) -> ArchiveResult<(usize, usize, Inner), B> {
+        let mut entry_opt = InnerEntry::first(&mut self.pager, &mut self.tree);
+
+        loop {
+            let entry = match entry_opt {
+                Some(Ok(entry)) => entry,
+                Some(Err(err)) => return Err(err),
+                None => return Err(Error::NotFound(name.to_string())),
+            };
+
+            if !entry.inner().mode.is_internal() && entry.inner().name == name {
+                return Ok((entry.idx(), entry.end_idx(), entry.inner().clone()));
+            }
+
+            entry_opt = entry.next();
+        }
+    }
+
+    /// Opens the file entry with the given `name` for appending more
+    /// content.
+    ///
+    /// The returned [`EntryMut`] is positioned at the end of the entry, a
+    /// partially used last block is filled up first. Because the content
+    /// blocks of an entry are stored one after another, only the last entry
+    /// of the archive can be reopened.
+    ///
+    /// # Errors
+    ///
+    /// If the archive has no entry with the given name, an
+    /// [`Error::NotFound`] error is returned. If the entry is not a file or
+    /// not the last entry of the archive, an [`Error::NotAppendable`] error
+    /// is returned. If the entry is marked
+    /// [immutable](Archive::set_immutable), an [`Error::Immutable`] error is
+    /// returned. If the entry has [external
+    /// references](Archive::register_reference), an [`Error::Referenced`]
+    /// error is returned, also by the `force_*` variant. If the archive is
+    /// frozen, an [`Error::Frozen`] error is returned.
+    pub fn open_mut<'a, N: AsRef<str>>(&'a mut self, name: N) -> ArchiveResult<EntryMut<'a, B>, B> {
+        self.open_mut_impl(name.as_ref(), false)
+    }
+
+    /// Like [`Archive::open_mut()`] but reopens the entry even if it is
+    /// marked [immutable](Archive::set_immutable).
+    pub fn force_open_mut<'a, N: AsRef<str>>(
+        &'a mut self,
+        name: N,
+    ) -> ArchiveResult<EntryMut<'a, B>, B> {
+        self.open_mut_impl(name.as_ref(), true)
+    }
+
+    fn open_mut_impl<'a>(
+        &'a mut self,
+        name: &str,
+        force: bool,
+    ) -> ArchiveResult<EntryMut<'a, B>, B> {
+        self.header.check_mutable()?;
+
+        let (idx, end_idx, inner) = self.locate(name)?;
+
+        if inner.mode.is_immutable() && !force {
+            return Err(Error::Immutable(name.to_string()));
+        }
+
+        if inner.refs > 0 {
+            return Err(Error::Referenced(name.to_string()));
+        }
+
+        if !inner.mode.is_file() || end_idx as u64 != self.tree.nblocks() {
+            return Err(Error::NotAppendable(name.to_string()));
+        }
+
+        // the entry is modified, the savepoints cannot be restored anymore
+        self.savepoints.clear();
+
+        EntryMut::reopen(
+            &mut self.pager,
+            &self.header_id,
+            &mut self.header,
+            &mut self.tree,
+            inner,
+            idx,
+        )
+    }
+
+    /// Removes the entry with the given `name` from the archive.
+    ///
+    /// The first entry with the given name is removed, like
+    /// [`Archive::lookup()`] would find it. The metadata and content blocks
+    /// of the entry are put into a list of free blocks, they are reused
+    /// before new blocks are aquired from the container. The entries behind
+    /// the removed entry are moved to the front, so traversing the archive
+    /// still works. Handles to entries, which were created before, become
+    /// [stale](Error::Stale).
+    ///
+    /// Removing a directory does not remove its children. The
+    /// [attachments](Archive::attach) of the entry are removed together with
+    /// the entry.
+    ///
+    /// If the [trash](Archive::set_trash) is enabled, the entry is moved
+    /// into the trash instead. It disappears from the archive, but its blocks
+    /// are not released.
+    ///
+    /// # Errors
+    ///
+    /// If the archive has no entry with the given name, an
+    /// [`Error::NotFound`] error is returned. If the entry is marked
+    /// [immutable](Archive::set_immutable), an [`Error::Immutable`] error is
+    /// returned. If the entry has [external
+    /// references](Archive::register_reference) or is the target of a
+    /// [hard link](Archive::append_hardlink), an [`Error::Referenced`]
+    /// error is returned, also by the `force_*` variant. If the archive is
+    /// frozen, an [`Error::Frozen`] error is returned.
+    pub fn remove<N: AsRef<str>>(&mut self, name: N) -> ArchiveResult<(), B> {
+        self.remove_impl(name.as_ref(), false)
+    }
+
+    /// Like [`Archive::remove()`] but removes the entry even if it is marked
+    /// [immutable](Archive::set_immutable).
+    pub fn force_remove<N: AsRef<str>>(&mut self, name: N) -> ArchiveResult<(), B> {
+        self.remove_impl(name.as_ref(), true)
+    }
+
+    fn remove_impl(&mut self, name: &str, force: bool) -> ArchiveResult<(), B> {
+        self.header.check_mutable()?;
+
+        let (idx, end_idx, inner) = self.locate(name)?;
+
+        if inner.mode.is_immutable() && !force {
+            return Err(Error::Immutable(name.to_string()));
+        }
+
+        if inner.refs > 0 {
+            return Err(Error::Referenced(name.to_string()));
+        }
+
+        let deps = dependents(&mut self.pager, &mut self.tree, name, idx)?;
+
+        if !deps.links.is_empty() {
+            return Err(Error::Referenced(name.to_string()));
+        }
+
+        // the entry is modified, the savepoints cannot be restored anymore
+        self.savepoints.clear();
+
+        if self.trash {
+            trash::trash(self, name, idx, inner, deps.next)
+        } else {
+            self.remove_at(name, idx, end_idx, deps, true)
+        }
+    }
+
+    /// Restores the entry with the given `name` from the trash.
+    ///
+    /// If the entry was [removed](Archive::remove) several times, the last
+    /// one is restored. The entry is restored at its old position, so if
+    /// another entry with the name was appended in the meantime, the
+    /// restored entry is found first again.
+    ///
+    /// # Errors
+    ///
+    /// If the trash has no entry with the given name, an
+    /// [`Error::NotFound`] error is returned. If the archive is frozen, an
+    /// [`Error::Frozen`] error is returned.
+    pub fn undelete<N: AsRef<str>>(&mut self, name: N) -> ArchiveResult<(), B> {
+        trash::undelete(self, name.as_ref())
+    }
+
+    /// Returns the names of the entries in the trash, see
+    /// [`Archive::set_trash()`].
+    pub fn trashed(&mut self) -> ArchiveResult<Vec<String>, B> {
+        trash::names(self)
+    }
+
+    /// Removes all entries in the trash.
+    ///
+    /// The entries are removed like [`Archive::remove()`] does without a
+    /// trash, their blocks are put into the list of free blocks. Returns the
+    /// number of removed entries.
+    ///
+    /// # Errors
+    ///
+    /// If the archive is frozen, an [`Error::Frozen`] error is returned.
+    pub fn empty_trash(&mut self) -> ArchiveResult<usize, B> {
+        trash::empty(self)
+    }
+
+    /// Removes the entry `name` located at `idx..end_idx` together with its
+    /// attachments from the archive, see [`remove_entry()`].
+    fn remove_at(
+        &mut self,
+        name: &str,
+        idx: usize,
+        end_idx: usize,
+        deps: Dependents,
+        visible: bool,
+    ) -> ArchiveResult<(), B> {
+        remove_entry(
+            &mut self.pager,
+            &self.header_id,
+            &mut self.header,
+            &mut self.tree,
+            name,
+            idx,
+            end_idx,
+            deps,
+            visible,
+        )
+    }
+
+    /// Changes the size of the file entry with the given `name` to `len`
+    /// bytes.
+    ///
+    /// If `len` is smaller than the current size, the content is cut off
+    /// and the content blocks, which are not needed anymore, are put into
+    /// the list of free blocks. Like [`Archive::remove()`] this moves the
+    /// entries behind the entry to the front. If `len` is larger, the
+    /// content is extended with zeros, see [`EntryMut::set_len()`]. The
+    /// additional blocks are inserted as holes, which do not occupy any
+    /// block of the container, the entries behind the entry are moved to
+    /// the back.
+    ///
+    /// # Errors
+    ///
+    /// If the archive has no entry with the given name, an
+    /// [`Error::NotFound`] error is returned, if the entry is not a file, an
+    /// [`Error::NotFile`] error is returned. If the archive has no slots
+    /// left for the additional blocks, an [`Error::Full`] error is returned.
+    /// If the entry is marked
+    /// [immutable](Archive::set_immutable), an [`Error::Immutable`] error is
+    /// returned. If the entry has [external
+    /// references](Archive::register_reference), an [`Error::Referenced`]
+    /// error is returned, also by the `force_*` variant. The content of a
+    /// [compressed](FileBuilder::set_compression) entry cannot be
+    /// truncated, an [`Error::Compressed`] error is returned, for a
+    /// [transformed](FileBuilder::add_transform) entry an
+    /// [`Error::Transformed`] error is returned. If the archive is frozen,
+    /// an [`Error::Frozen`] error is returned.
+    pub fn truncate<N: AsRef<str>>(&mut self, name: N, len: u64) -> ArchiveResult<(), B> {
+        self.truncate_impl(name.as_ref(), len, false)
+    }
+
+    /// Like [`Archive::truncate()`] but changes the size even if the entry is
+    /// marked [immutable](Archive::set_immutable).
+    pub fn force_truncate<N: AsRef<str>>(&mut self, name: N, len: u64) -> ArchiveResult<(), B> {
+        self.truncate_impl(name.as_ref(), len, true)
+    }
+
+    fn truncate_impl(&mut self, name: &str, len: u64, force: bool) -> ArchiveResult<(), B> {
+        self.header.check_mutable()?;
+
+        let (idx, _, mut inner) = self.locate(name)?;
+
+        if !inner.mode.is_file() {
+            return Err(Error::NotFile(name.to_string()));
+        }
+
+        if inner.mode.is_immutable() && !force {
+            return Err(Error::Immutable(name.to_string()));
+        }
+
+        if inner.refs > 0 {
+            return Err(Error::Referenced(name.to_string()));
+        }
+
+        inner.check_random_access()?;
+
+        // the entry is modified, the savepoints cannot be restored anymore
+        self.savepoints.clear();
+
+        let block_size = self.pager.block_size() as u64;
+        let old_blocks = inner.size.div_ceil(block_size);
+        let end_idx = (idx + inner.head_blocks()) as u64 + old_blocks;
+
+        if len == inner.size {
+            return Ok(());
+        } else if len > inner.size && end_idx == self.tree.nblocks() {
+            return self.open_mut_impl(name, force)?.set_len(len);
+        }
+
+        let (removed, moved) = if len > inner.size {
+            grow_content(&mut self.pager, &mut self.tree, &mut inner, idx, len)?;
+            (vec![], len.div_ceil(block_size) > old_blocks)
+        } else {
+            let removed = shrink_content(&mut self.pager, &mut self.tree, &mut inner, idx, len)?;
+            let moved = !removed.is_empty();
+            (removed, moved)
+        };
+
+        self.header.touch();
+
+        flush_header(
+            &mut self.pager,
+            &self.header_id,
+            &self.header,
+            &mut self.tree,
+        )?;
+
+        release_blocks(&mut self.pager, &mut self.tree, removed)?;
+
+        if moved && self.tree.has_names() {
+            self.build_index()?;
+        }
+
+        debug!("entry {} truncated to {} bytes", name, len);
+
+        Ok(())
+    }
+
+    /// Records a savepoint with the given `name`.
+    ///
+    /// Entries appended after the savepoint can be discarded with
+    /// [`Archive::rollback_to()`] or [`Archive::rollback()`], e.g. when a
+    /// step of a long ingestion fails. Savepoints only exist in memory,
+    /// they are lost when the archive is closed.
+    ///
+    /// Savepoints cover appended entries only. [`Archive::open_mut()`],
+    /// [`Archive::truncate()`] and [`Archive::remove()`] modify existing
+    /// entries and discard all savepoints.
+    pub fn savepoint<N: AsRef<str>>(&mut self, name: N) {
+        savepoint::create(self, name.as_ref())
+    }
+
+    /// Rolls back to the last savepoint with the given `name`.
+    ///
+    /// All entries appended after the savepoint are removed, their blocks are
+    /// put into the list of free blocks. Savepoints recorded after the
+    /// savepoint are discarded, the savepoint itself is kept and can be
+    /// rolled back to again.
+    ///
+    /// # Errors
+    ///
+    /// If there is no savepoint with the given name, an
+    /// [`Error::NoSavepoint`] error is returned. If one of the discarded
+    /// entries has [external references](Archive::register_reference), an
+    /// [`Error::Referenced`] error is returned. If the archive is frozen, an
+    /// [`Error::Frozen`] error is returned.
+    pub fn rollback_to<N: AsRef<str>>(&mut self, name: N) -> ArchiveResult<(), B> {
+        let pos = savepoint::position(self, name.as_ref())?;
+
+        savepoint::rollback(self, pos)
+    }
+
+    /// Rolls back to the last savepoint.
+    ///
+    /// See [`Archive::rollback_to()`] for details.
+    ///
+    /// # Errors
+    ///
+    /// If no savepoint was recorded, an [`Error::NoSavepoint`] error is
+    /// returned. If one of the discarded entries has [external
+    /// references](Archive::register_reference), an [`Error::Referenced`]
+    /// error is returned. If the archive is frozen, an [`Error::Frozen`]
+    /// error is returned.
+    pub fn rollback(&mut self) -> ArchiveResult<(), B> {
+        match self.savepoints.len().checked_sub(1) {
+            Some(pos) => savepoint::rollback(self, pos),
+            None => Err(Error::NoSavepoint(String::new())),
+        }
+    }
+
+    /// Discards the last savepoint with the given `name` and all savepoints
+    /// recorded after it. The entries are kept.
+    ///
+    /// # Errors
+    ///
+    /// If there is no savepoint with the given name, an
+    /// [`Error::NoSavepoint`] error is returned.
+    pub fn release_savepoint<N: AsRef<str>>(&mut self, name: N) -> ArchiveResult<(), B> {
+        let pos = savepoint::position(self, name.as_ref())?;
+
+        self.savepoints.truncate(pos);
+
+        Ok(())
+    }
+
+    /// Returns the names of all savepoints, the oldest first.
+    pub fn savepoints(&self) -> Vec<&str> {
+        savepoint::names(self)
+    }
+
+    /// Renames the entry with the given name `from` to `to`.
+    ///
+    /// The first entry with the name `from` is renamed, like
+    /// [`Archive::lookup()`] would find it. Only the entry itself is renamed,
+    /// if it is a directory, the names of the entries below the directory
+    /// are not changed. The child counts of the old and new parent
+    /// directories are updated. If the new name needs a different number
+    /// of continuation blocks, the entries behind are moved.
+    ///
+    /// If enabled with [`Archive::set_rename_history()`], the old name is
+    /// recorded in the entry, see [`Entry::previous_names()`].
+    ///
+    /// # Errors
+    ///
+    /// If the archive has no entry with the name `from`, an
+    /// [`Error::NotFound`] error is returned. If the entry is marked
+    /// [immutable](Archive::set_immutable), an [`Error::Immutable`] error is
+    /// returned. If the metadata does not fit into a single block, even
+    /// with the name moved into continuation blocks, an
+    /// [`Error::NameTooLong`] or [`Error::MetadataTooLarge`] error is
+    /// returned. If the archive is frozen, an [`Error::Frozen`] error is
+    /// returned.
+    pub fn rename<F: AsRef<str>, T: AsRef<str>>(&mut self, from: F, to: T) -> ArchiveResult<(), B> {
+        let (from, to) = (from.as_ref(), to.as_ref());
+
+        self.header.check_mutable()?;
+
+        let (idx, _, mut inner) = self.locate(from)?;
+
+        if inner.mode.is_immutable() {
+            return Err(Error::Immutable(from.to_string()));
+        }
+
+        inner.name = to.to_string();
+
+        if self.rename_history > 0 {
+            inner.previous_names.push(from.to_string());
+
+            let excess = inner
+                .previous_names
+                .len()
+                .saturating_sub(self.rename_history);
+            inner.previous_names.drain(..excess);
+        }
+
+        let name_blocks = inner.name_blocks;
+
+        check_metadata_size(&mut inner, self.pager.codec(), self.pager.block_size())?;
+
+        let id = match self.tree.lookup(&mut self.pager, idx) {
+            Some(result) => result?.clone(),
+            None => return Err(Error::NotFound(from.to_string())),
+        };
+
+        // the entry is modified, the savepoints cannot be restored anymore
+        self.savepoints.clear();
+
+        let removed = resize_name(
+            &mut self.pager,
+            &mut self.tree,
+            idx,
+            name_blocks,
+            inner.name_blocks,
+        )?;
+
+        inner.flush(&mut self.pager, &id)?;
+        inner.flush_name(&mut self.pager, &mut self.tree, idx)?;
+
+        self.header.touch();
+
+        flush_header(
+            &mut self.pager,
+            &self.header_id,
+            &self.header,
+            &mut self.tree,
+        )?;
+        release_blocks(&mut self.pager, &mut self.tree, removed)?;
+
+        // a renamed directory can become the last one with its new name
+        if inner.mode.is_directory() {
+            self.tree.forget_dirs();
+        }
+
+        dec_parent_children(&mut self.pager, &mut self.tree, from)?;
+        inc_parent_children(&mut self.pager, &mut self.tree, to)?;
+
+        if self.tree.has_names() {
+            self.build_index()?;
+        }
+
+        debug!("entry {} renamed to {}", from, to);
+
+        Ok(())
+    }
+
+    /// Changes the order of the entries.
+    ///
+    /// The entries with the given `names` are moved to the front of the
+    /// archive in the given order, all other entries keep their order
+    /// behind them. A name selects the first entry with the name, like
+    /// [`Archive::lookup()`] would find it; if a name is listed several
+    /// times, the next entry with the name is selected each time. The
+    /// content of the entries is not touched, only the sequence of the
+    /// entries is rewritten. Use it to curate the order, in which the
+    /// entries are [traversed](Archive::first).
+    ///
+    /// All savepoints are discarded, the name index is rebuilt.
+    ///
+    /// # Errors
+    ///
+    /// If there is no (further) entry with one of the names, an
+    /// [`Error::NotFound`] error is returned and the order is not changed.
+    /// If the archive is frozen, an [`Error::Frozen`] error is returned.
+    pub fn reorder<N: AsRef<str>>(&mut self, names: &[N]) -> ArchiveResult<(), B> {
+        let names = names.iter().map(|n| n.as_ref()).collect::<Vec<_>>();
+
+        reorder::reorder(self, &names)
+    }
+
+    /// Marks the entry with the given `name` immutable or lifts the mark.
+    ///
+    /// An immutable entry cannot be reopened with [`Archive::open_mut()`],
+    /// truncated with [`Archive::truncate()`] or removed with
+    /// [`Archive::remove()`], these operations fail with an
+    /// [`Error::Immutable`] error. Other than [freezing](Archive::freeze)
+    /// the whole archive, this protects individual entries. The `force_*`
+    /// variants of the operations ignore the mark.
+    ///
+    /// The mark can also be set when appending an entry, e.g. with
+    /// [`FileBuilder::set_immutable()`].
+    ///
+    /// # Errors
+    ///
+    /// If the archive has no entry with the given name, an
+    /// [`Error::NotFound`] error is returned. If the archive is frozen, an
+    /// [`Error::Frozen`] error is returned.
+    pub fn set_immutable<N: AsRef<str>>(
+        &mut self,
+        name: N,
+        immutable: bool,
+    ) -> ArchiveResult<(), B> {
+        let name = name.as_ref();
+
+        self.header.check_mutable()?;
+
+        let (idx, _, mut inner) = self.locate(name)?;
+
+        if inner.mode.is_immutable() == immutable {
+            return Ok(());
+        }
+
+        let id = match self.tree.lookup(&mut self.pager, idx) {
+            Some(result) => result?.clone(),
+            None => return Err(Error::NotFound(name.to_string())),
+        };
+
+        inner.mode.set_immutable(immutable);
+        inner.flush(&mut self.pager, &id)?;
+
+        self.header.touch();
+
+        flush_header(
+            &mut self.pager,
+            &self.header_id,
+            &self.header,
+            &mut self.tree,
+        )?;
+
+        debug!("entry {} immutable: {}", name, immutable);
+
+        Ok(())
+    }
+
+    /// Registers an external reference to the entry with the given `name`.
+    ///
+    /// Register a reference when something outside of the archive points
+    /// to the blocks of the entry, e.g. the deduplication table of another
+    /// archive. As long as the entry has external references its blocks are
+    /// not released: [`Archive::open_mut()`], [`Archive::truncate()`],
+    /// [`Archive::remove()`] and their `force_*` variants fail with an
+    /// [`Error::Referenced`] error. Rolling back to a
+    /// [savepoint](Archive::savepoint) behind the entry fails as well.
+    ///
+    /// References are counted and stored with the entry. Each reference must
+    /// be dropped again with [`Archive::unregister_reference()`]. Returns the
+    /// number of references after registering.
+    ///
+    /// # Errors
+    ///
+    /// If the archive has no entry with the given name, an
+    /// [`Error::NotFound`] error is returned. If the archive is frozen, an
+    /// [`Error::Frozen`] error is returned.
+    pub fn register_reference<N: AsRef<str>>(&mut self, name: N) -> ArchiveResult<u32, B> {
+        self.update_refs(name.as_ref(), true)
+    }
+
+    /// Drops an external reference of the entry with the given `name`.
+    ///
+    /// The counterpart of [`Archive::register_reference()`]. Once the last
+    /// reference is dropped, the blocks of the entry can be released again.
+    /// Returns the number of remaining references.
+    ///
+    /// # Errors
+    ///
+    /// If the archive has no entry with the given name, an
+    /// [`Error::NotFound`] error is returned. If the entry has no external
+    /// references, an [`Error::NoReference`] error is returned. If the
+    /// archive is frozen, an [`Error::Frozen`] error is returned.
+    pub fn unregister_reference<N: AsRef<str>>(&mut self, name: N) -> ArchiveResult<u32, B> {
+        self.update_refs(name.as_ref(), false)
+    }
+
+    fn update_refs(&mut self, name: &str, register: bool) -> ArchiveResult<u32, B> {
+        self.header.check_mutable()?;
+
+        let (idx, _, mut inner) = self.locate(name)?;
+
+        inner.refs = if register {
+            inner.refs.saturating_add(1)
+        } else {
+            match inner.refs.checked_sub(1) {
+                Some(refs) => refs,
+                None => return Err(Error::NoReference(name.to_string())),
+            }
+        };
+
+        let id = match self.tree.lookup(&mut self.pager, idx) {
+            Some(result) => result?.clone(),
+            None => return Err(Error::NotFound(name.to_string())),
+        };
+
+        inner.flush(&mut self.pager, &id)?;
+
+        self.header.touch();
+
+        flush_header(
+            &mut self.pager,
+            &self.header_id,
+            &self.header,
+            &mut self.tree,
+        )?;
+
+        debug!("entry {} external references: {}", name, inner.refs);
+
+        Ok(inner.refs)
+    }
+
+    /// Returns statistics of all sections of the archive.
+    ///
+    /// The sections are ordered by their first entry. Entries outside of
+    /// any section are not counted. See [`Archive::set_section()`].
+    pub fn sections(&mut self) -> ArchiveResult<Vec<Section>, B> {
+        section::collect(self)
+    }
+
+    /// Removes all entries of the section with the given `name`.
+    ///
+    /// The entries are removed like [`Archive::remove()`] would do it.
+    /// Returns the number of removed entries.
+    ///
+    /// # Errors
+    ///
+    /// If one of the entries is marked [immutable](Archive::set_immutable),
+    /// an [`Error::Immutable`] error is returned. If one of the entries has
+    /// [external references](Archive::register_reference) or is the target
+    /// of a hard link outside of the section, an [`Error::Referenced`] error
+    /// is returned. In both cases no entry is removed. If the archive is
+    /// frozen, an [`Error::Frozen`] error is returned.
+    pub fn prune_section<N: AsRef<str>>(&mut self, name: N) -> ArchiveResult<u64, B> {
+        section::prune(self, name.as_ref())
+    }
+
+    /// Tests whether the archive contains an entry with the given `name`.
+    ///
+    /// Other than [`Archive::lookup()`] only the metadata of the entries
+    /// are inspected, no [`Entry`] is constructed.
+    pub fn contains<N: AsRef<str>>(&mut self, name: N) -> ArchiveResult<bool, B> {
+        self.any_inner(|inner| inner.name == name.as_ref())
+    }
+
+    /// Tests whether the archive contains an entry, whose name starts with
+    /// the given `prefix`.
+    ///
+    /// Like [`Archive::contains()`] only the metadata of the entries are
+    /// inspected.
+    pub fn exists_prefix<P: AsRef<str>>(&mut self, prefix: P) -> ArchiveResult<bool, B> {
+        self.any_inner(|inner| inner.name.starts_with(prefix.as_ref()))
+    }
+
+    fn any_inner<F: FnMut(&Inner) -> bool>(&mut self, mut f: F) -> ArchiveResult<bool, B> {
+        let mut entry_opt = InnerEntry::first(&mut self.pager, &mut self.tree);
+
+        loop {
+            let entry = match entry_opt {
+                Some(Ok(entry)) => entry,
+                Some(Err(err)) => return Err(err),
+                None => return Ok(false),
+            };
+
+            if !entry.inner().mode.is_internal() && f(entry.inner()) {
+                return Ok(true);
+            }
+
+            entry_opt = entry.next();
+        }
+    }
+
+    /// Exports the [`Index`] of the archive into `writer`.
+    ///
+    /// The index contains the metadata of all entries but no content. Use
+    /// [`Index::import()`] to load it again.
+    pub fn export_index<W: Write>(&mut self, writer: W) -> ArchiveResult<(), B> {
+        let index = Index::create(&mut self.pager, &mut self.tree)?;
+
+        index.export(writer)?;
+
+        Ok(())
+    }
+
+    /// Creates a preview of extracting entries into the directory `dest`.
+    ///
+    /// Only entries whose names match one of the given glob `patterns` are
+    /// selected, if no pattern is given all entries are selected. In a
+    /// pattern a `*` matches any sequence of characters, a `?` matches a
+    /// single character.
+    ///
+    /// The filesystem is not modified. For each selected entry the returned
+    /// [`ExtractPlan`] tells whether the path is created or overwritten and
+    /// whether it conflicts with an existing path.
+    ///
+    /// # Errors
+    ///
+    /// If the name of a selected entry escapes `dest` and the
+    /// [path policy](Archive::set_path_policy) rejects it, an
+    /// [`Error::UnsafePath`] error is returned.
+    pub fn plan_extract<D: AsRef<Path>, P: AsRef<str>>(
+        &mut self,
+        dest: D,
+        patterns: &[P],
+    ) -> ArchiveResult<ExtractPlan, B> {
+        ExtractPlan::create(
+            &mut self.pager,
+            &mut self.tree,
+            dest.as_ref(),
+            patterns,
+            self.path_policy,
+        )
+    }
+
+    /// Extracts all entries of the archive into the directory `dest`.
+    ///
+    /// Directories are created, the content of files is written and
+    /// symlinks and hard links are created. Parent directories, which are
+    /// not stored in the archive, are created as well. The access rights
+    /// of the entries are applied (on unix), the access rights of
+    /// directories only after all entries were extracted. Files get their
+    /// modification time. Special files are skipped.
+    ///
+    /// Existing files are overwritten. Use [`Archive::plan_extract()`] to
+    /// preview the extraction.
+    ///
+    /// Returns the I/O statistics of the extraction.
+    ///
+    /// # Errors
+    ///
+    /// If the name of an entry or the target of a symlink escapes `dest`
+    /// and the [path policy](Archive::set_path_policy) rejects it, or if a
+    /// parent directory of the entry is a symlink, an [`Error::UnsafePath`]
+    /// error is returned. The same error is returned for a directory,
+    /// whose path is a symlink. The entries before were already extracted.
+    pub fn unpack<D: AsRef<Path>>(&mut self, dest: D) -> ArchiveResult<IoStats, B> {
+        let measure = Measure::start(&self.pager);
+        let policy = self.path_policy;
+
+        extract::unpack(self.first(), dest.as_ref(), policy)?;
+
+        Ok(measure.finish(&self.pager))
+    }
+
+    /// Stores the given job `record` in the archive.
+    ///
+    /// The record is appended as a hidden entry at the end of the archive.
+    /// It does not show up when traversing the archive and is not counted
+    /// in [`Info::files`]. Use [`Archive::jobs()`] to get them back.
+    pub fn append_job(&mut self, record: &JobRecord) -> ArchiveResult<(), B> {
+        record.append(
+            &mut self.pager,
+            &self.header_id,
+            &mut self.header,
+            &mut self.tree,
+        )
+    }
+
+    /// Returns all job records stored in the archive.
+    ///
+    /// The records are returned in the order they were appended.
+    pub fn jobs(&mut self) -> ArchiveResult<Vec<JobRecord>, B> {
+        JobRecord::load_all(&mut self.pager, &mut self.tree)
+    }
+
+    /// Attaches a small blob of `data` with the given `name` to the entry
+    /// `owner`.
+    ///
+    /// The [`Attachment`] is appended as a hidden entry at the end of the
+    /// archive. It does not show up when traversing the archive and is not
+    /// counted in [`Info::files`]. Use [`Archive::attachments()`] or
+    /// [`Entry::attachments()`] to get them back.
+    ///
+    /// # Errors
+    ///
+    /// If the archive has no entry with the name `owner`, an
+    /// [`Error::NotFound`] error is returned.
+    pub fn attach<O: AsRef<str>, N: AsRef<str>>(
+        &mut self,
+        owner: O,
+        name: N,
+        data: &[u8],
+    ) -> ArchiveResult<(), B> {
+        let owner = owner.as_ref();
+
+        match self.lookup(owner) {
+            Some(Ok(_)) => {}
+            Some(Err(err)) => return Err(err),
+            None => return Err(Error::NotFound(owner.to_string())),
+        }
+
+        Attachment::append(
+            &mut self.pager,
+            &self.header_id,
+            &mut self.header,
+            &mut self.tree,
+            owner,
+            name.as_ref(),
+            data,
+        )
+    }
+
+    /// Sets the [`Chunker`] used by [`Archive::append_chunked()`].
+    ///
+    /// The setting is not persisted. Files appended with different
+    /// chunkers hardly share any chunks.
+    pub fn set_chunker(&mut self, chunker: Chunker) {
+        self.chunker = chunker;
+    }
+
+    /// Appends the content of `reader` as a chunked file with the given
+    /// `name`.
+    ///
+    /// The content is split into chunks by the [`Chunker`] of the archive,
+    /// see [`Archive::set_chunker()`]. Each chunk is stored only once in
+    /// the archive, so files with similar content (e.g. several versions of
+    /// a VM image) share most of their chunks, even if the data is shifted.
+    ///
+    /// Chunks and chunked files are hidden entries, they do not show up
+    /// when traversing the archive. Use [`Archive::read_chunked()`] to get
+    /// the content back.
+    pub fn append_chunked<N: AsRef<str>, R: Read>(
+        &mut self,
+        name: N,
+        reader: R,
+    ) -> ArchiveResult<ChunkStats, B> {
+        self.header.check_mutable()?;
+
+        chunked::append(
+            &mut self.pager,
+            &self.header_id,
+            &mut self.header,
+            &mut self.tree,
+            &self.chunker,
+            name.as_ref(),
+            reader,
+        )
+    }
+
+    /// Writes the content of the chunked file `name` into `writer`.
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// # Errors
+    ///
+    /// If the archive has no chunked file `name`, an [`Error::NotFound`]
+    /// error is returned; if one of its chunks is missing, an
+    /// [`Error::MissingChunk`] error is returned.
+    pub fn read_chunked<N: AsRef<str>, W: Write>(
+        &mut self,
+        name: N,
+        writer: W,
+    ) -> ArchiveResult<u64, B> {
+        chunked::read(&mut self.pager, &mut self.tree, name.as_ref(), writer)
+    }
+
+    /// Returns all attachments of the entry `owner`.
+    ///
+    /// The attachments are returned in the order they were appended.
+    pub fn attachments<O: AsRef<str>>(&mut self, owner: O) -> ArchiveResult<Vec<Attachment>, B> {
+        let entry_opt = InnerEntry::first(&mut self.pager, &mut self.tree);
+        Attachment::load_all(entry_opt, owner.as_ref())
+    }
+
+    /// Decodes the block `id` for debugging purposes.
+    ///
+    /// The block is decoded as best as possible: the header block, a node
+    /// of the block tree, an entry block, a continuation block of a long
+    /// name or a content block. Any other block is reported as
+    /// [`BlockKind::Unknown`]. Format the returned [`BlockDump`] to get a
+    /// report with a hex dump of the block.
+    ///
+    /// The whole archive is scanned to locate the block, the call is
+    /// expensive for large archives.
+    pub fn dump_block(&mut self, id: &B::Id) -> ArchiveResult<BlockDump<B>, B> {
+        BlockDump::create(&mut self.pager, &mut self.tree, &self.header_id, id)
+    }
+
+    /// Analyzes the entries of the archive.
+    ///
+    /// The [`Analysis`] reports the distribution of the entry sizes and the
+    /// space needed at the current and alternative block sizes. Use it to
+    /// decide whether a [rewrite](Self::rewrite_to) into a container with
+    /// another block size pays off.
+    pub fn analyze(&mut self) -> ArchiveResult<Analysis, B> {
+        Analysis::create(
+            &mut self.pager,
+            &mut self.tree,
+            min_block_size::<B>(NUM_DIRECT) as u32,
+        )
+    }
+
+    /// Creates a [`Manifest`] of the archive.
+    ///
+    /// The content of all entries is read and digested, unless the entry
+    /// has a [stored checksum](Entry::stored_checksum). The resulting
+    /// manifest can be exported and compared later against another manifest
+    /// of the archive to detect modifications.
+    pub fn manifest(&mut self) -> ArchiveResult<Manifest, B> {
+        Manifest::create(&mut self.pager, &mut self.tree)
+    }
+
+    /// Exports all entries of the archive as a bundle into `writer`.
+    ///
+    /// A bundle is a single, self-describing stream, which contains the
+    /// [`Manifest`] of the archive and the metadata and content of all
+    /// entries. It transfers entries to another archive (e.g. on an
+    /// air-gapped machine) without exposing the format of the container.
+    /// Use [`Archive::import_bundle()`] to append the entries of the bundle
+    /// to an archive.
+    ///
+    /// Returns the number of exported entries.
+    pub fn export_bundle<W: Write>(&mut self, writer: W) -> ArchiveResult<u64, B> {
+        bundle::export(self, writer)
+    }
+
+    /// Appends the entries of a bundle read from `reader` to the archive.
+    ///
+    /// The bundle was created by [`Archive::export_bundle()`]. Name, size
+    /// and content digest of each entry are verified against the manifest
+    /// of the bundle. The entries are appended atomically like in an
+    /// [`AppendGroup`]: either all entries become part of the archive or
+    /// none.
+    ///
+    /// Returns the number of appended entries.
+    ///
+    /// # Errors
+    ///
+    /// If the bundle does not match its manifest, an
+    /// [`Error::BundleMismatch`] error is returned. If the archive is frozen,
+    /// an [`Error::Frozen`] error is returned.
+    pub fn import_bundle<R: Read>(&mut self, reader: R) -> ArchiveResult<u64, B> {
+        bundle::import(self, reader)
+    }
+
+    /// Validates a bundle read from `reader` without modifying the archive.
+    ///
+    /// The bundle is verified like [`Archive::import_bundle()`] does. The
+    /// returned [`ImportReport`] tells which entries would be appended,
+    /// whether they collide with existing entries and whether they fit into
+    /// the archive.
+    ///
+    /// # Errors
+    ///
+    /// If the bundle does not match its manifest, an
+    /// [`Error::BundleMismatch`] error is returned. If the archive is frozen,
+    /// an [`Error::Frozen`] error is returned.
+    pub fn validate_bundle<R: Read>(&mut self, reader: R) -> ArchiveResult<ImportReport, B> {
+        bundle::validate(self, reader)
+    }
+
+    /// Starts a synchronization, this archive receives entries from another
+    /// archive.
+    ///
+    /// Synchronization works in three steps:
+    ///
+    /// 1. The receiving archive writes a request with
+    ///    [`Archive::sync_request()`]. The request contains the
+    ///    [`Manifest`] of the receiving archive.
+    /// 2. The sending archive reads the request with
+    ///    [`Archive::sync_respond()`] and answers with all entries, which are
+    ///    missing in the receiving archive. An entry is missing, if the
+    ///    receiving archive has no entry with the same name and content.
+    /// 3. The receiving archive appends the entries of the response with
+    ///    [`Archive::sync_receive()`].
+    ///
+    /// Request and response can be transfered over any channel (pipe,
+    /// socket, ...), the response is an entry stream as written by an
+    /// [`EntryStreamWriter`].
+    pub fn sync_request<W: Write>(&mut self, writer: W) -> ArchiveResult<(), B> {
+        sync::request(self, writer)
+    }
+
+    /// Answers a sync `request` of another archive.
+    ///
+    /// All entries missing in the requesting archive are written into
+    /// `response`. Returns the number of written entries. See
+    /// [`Archive::sync_request()`] for details.
+    pub fn sync_respond<R: Read, W: Write>(
+        &mut self,
+        request: R,
+        response: W,
+    ) -> ArchiveResult<u64, B> {
+        sync::respond(self, request, response)
+    }
+
+    /// Appends the entries of a sync `response` to the archive.
+    ///
+    /// Returns the number of appended entries. See
+    /// [`Archive::sync_request()`] for details.
+    pub fn sync_receive<R: Read>(&mut self, response: R) -> ArchiveResult<u64, B> {
+        sync::receive(self, response)
+    }
+
+    /// Opens a group of entries, which are appended atomically.
+    ///
+    /// Entries appended through the returned [`AppendGroup`] become part of
+    /// the archive, when [`AppendGroup::commit()`] is called. Use it for
+    /// entries which belong together, e.g. data, checksum and manifest. If
+    /// the group is dropped or the application crashes before, none of the
+    /// entries are stored in the archive.
+    ///
+    /// # Errors
+    ///
+    /// If the archive is frozen, an [`Error::Frozen`] error is returned.
+    pub fn append_group(&mut self) -> ArchiveResult<AppendGroup<'_, B>, B> {
+        self.header.check_mutable()?;
+
+        AppendGroup::new(self)
+    }
+
+    /// Appends a new file entry with the given `name` at the end of the
+    /// archive.
+    ///
+    /// The method returns a [`FileBuilder`] instance, where you are able to
+    /// set some more properties for the new entry. Calling
+    /// [`FileBuilder::build()`] will finally create the entry.
+    pub fn append_file<'a, N: AsRef<str>>(&'a mut self, name: N) -> FileBuilder<'a, B> {
+        let mut builder = FileBuilder::new(
+            &mut self.pager,
+            &self.header_id,
+            &mut self.header,
+            &mut self.tree,
+            name.as_ref().to_string(),
+        );
+
+        if self.trash {
+            builder.evict_trash(&mut self.savepoints);
+        }
+
+        builder.set_section(self.section.as_deref());
+        builder
+    }
+
+    /// Appends a new directory entry with the given `name` at the end of the
+    /// archive.
+    ///
+    /// The method returns a [`DirectoryBuilder`] instance, where you are able
+    /// to set some more properties for the new entry. Calling
+    /// [`DirectoryBuilder::build()`] will finally create the entry.
+    pub fn append_directory<'a, N: AsRef<str>>(&'a mut self, name: N) -> DirectoryBuilder<'a, B> {
+        let mut builder = DirectoryBuilder::new(
+            &mut self.pager,
+            &self.header_id,
+            &mut self.header,
             &mut self.tree,
             name.as_ref().to_string(),
-        )
+        );
+
+        if self.trash {
+            builder.evict_trash(&mut self.savepoints);
+        }
+
+        builder.set_section(self.section.as_deref());
+        builder
     }
 
     /// Appends a new symlink entry with the given `name` at the end of the
     /// archive.
     ///
-    /// The symlink points to the given `target` name.
+    /// The symlink points to the given `target` name. The target is stored
+    /// as the given byte sequence, it is not required to be valid UTF-8.
     ///
     /// The method returns a [`SymlinkBuilder`] instance, where you are able to
     /// set some more properties for the new entry. Calling
     /// [`SymlinkBuilder::build()`] will finally create the entry.
-    pub fn append_symlink<'a, N: AsRef<str>, T: AsRef<str>>(
+    pub fn append_symlink<'a, N: AsRef<str>, T: AsRef<[u8]>>(
         &'a mut self,
         name: N,
         target: T,
     ) -> SymlinkBuilder<'a, B> {
-        SymlinkBuilder::new(
+        let mut builder = SymlinkBuilder::new(
+            &mut self.pager,
+            &self.header_id,
+            &mut self.header,
+            &mut self.tree,
+            name.as_ref().to_string(),
+            target.as_ref().to_vec(),
+            self.max_target_len,
+        );
+
+        if self.trash {
+            builder.evict_trash(&mut self.savepoints);
+        }
+
+        builder.set_section(self.section.as_deref());
+        builder
+    }
+
+    /// Appends a new hard link entry with the given `name` at the end of the
+    /// archive.
+    ///
+    /// The hard link refers to the file entry `target` of this archive, the
+    /// content of the file is not stored again. This way files with
+    /// multiple names can be imported without duplicating their data.
+    ///
+    /// The method returns a [`HardLinkBuilder`] instance, where you are able
+    /// to set some more properties for the new entry. Calling
+    /// [`HardLinkBuilder::build()`] will finally create the entry.
+    pub fn append_hardlink<'a, N: AsRef<str>, T: AsRef<str>>(
+        &'a mut self,
+        name: N,
+        target: T,
+    ) -> HardLinkBuilder<'a, B> {
+        let mut builder = HardLinkBuilder::new(
             &mut self.pager,
             &self.header_id,
             &mut self.header,
             &mut self.tree,
             name.as_ref().to_string(),
             target.as_ref().to_string(),
+        );
+
+        if self.trash {
+            builder.evict_trash(&mut self.savepoints);
+        }
+
+        builder.set_section(self.section.as_deref());
+        builder
+    }
+
+    /// Appends a new special file entry with the given `name` at the end of
+    /// the archive.
+    ///
+    /// A special file is a FIFO, a character or block device or a socket,
+    /// see [`SpecialKind`]. Only its type and for devices the major and
+    /// minor number are stored, so that system backups can be represented
+    /// completely.
+    ///
+    /// The method returns a [`SpecialBuilder`] instance, where you are able
+    /// to set some more properties for the new entry. Calling
+    /// [`SpecialBuilder::build()`] will finally create the entry.
+    pub fn append_special<'a, N: AsRef<str>>(
+        &'a mut self,
+        name: N,
+        kind: SpecialKind,
+    ) -> SpecialBuilder<'a, B> {
+        let mut builder = SpecialBuilder::new(
+            &mut self.pager,
+            &self.header_id,
+            &mut self.header,
+            &mut self.tree,
+            name.as_ref().to_string(),
+            kind,
+        );
+
+        if self.trash {
+            builder.evict_trash(&mut self.savepoints);
+        }
+
+        builder.set_section(self.section.as_deref());
+        builder
+    }
+
+    /// Appends the file at `path` of the local filesystem at the end of the
+    /// archive.
+    ///
+    /// The name of the entry is derived from `path`: its components are
+    /// joined with `/`, a leading `/` and `.` components are removed.
+    /// Access rights, owner (on unix) and timestamps are taken from the
+    /// file. The content is streamed into the archive in block-sized
+    /// chunks. Symlinks are followed.
+    ///
+    /// # Errors
+    ///
+    /// If `path` contains `..` components or is not valid UTF-8, an
+    /// [`Error::Io`] error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput)
+    /// is returned. If `path` is not a regular file, an [`Error::NotFile`]
+    /// error is returned.
+    pub fn append_from_path<P: AsRef<Path>>(&mut self, path: P) -> ArchiveResult<(), B> {
+        import::append_from_path(self, path.as_ref())
+    }
+
+    /// Appends all entries of the tar stream read from `reader` at the end
+    /// of the archive.
+    ///
+    /// Regular files, directories, symlinks, hard links, devices and FIFOs
+    /// are converted into archive entries. Other entry types, extended
+    /// attributes and owner ids beyond 32 bits cannot be represented in the
+    /// archive, they are handled as configured with
+    /// [`Archive::set_unsupported_action()`]. Name, size, access rights,
+    /// owner, modification time and the targets of links are preserved.
+    /// Like for [`Archive::append_from_path()`] a leading `/` is removed
+    /// from the names.
+    ///
+    /// Returns the number of appended entries and the I/O statistics of the
+    /// import.
+    ///
+    /// This method is only available with the `tar` feature.
+    ///
+    /// # Errors
+    ///
+    /// If the tar stream is malformed or an entry name contains `..`
+    /// components, an [`Error::Io`] error is returned. If an unsupported
+    /// feature is configured to fail the import, an [`Error::Unsupported`]
+    /// error is returned. Entries appended before the error stay in the
+    /// archive.
+    #[cfg(feature = "tar")]
+    pub fn append_tar<R: Read>(&mut self, reader: R) -> ArchiveResult<ImportSummary, B> {
+        let measure = Measure::start(&self.pager);
+        let entries = tarball::append_tar(self, reader)?;
+
+        Ok(ImportSummary {
+            entries,
+            stats: measure.finish(&self.pager),
+        })
+    }
+
+    /// Appends the entries of a tar stream at the end of the archive and
+    /// resumes the import, if the stream fails.
+    ///
+    /// The entries are converted like [`Archive::append_tar()`] does. The
+    /// stream is opened by calling `open` with a byte offset, which must
+    /// return a reader positioned at this offset of the tar stream, e.g. by
+    /// sending an HTTP range request. After each entry, `checkpoint` is
+    /// advanced. If reading from the stream fails, the partially imported
+    /// entry is removed and the stream is re-opened at the offset of the
+    /// checkpoint. The import gives up after `retries` failures in a row
+    /// without progress. Put a delay into `open` if the source needs some
+    /// time to recover.
+    ///
+    /// The checkpoint can also be used to resume an import after the
+    /// application was restarted. Start a new import with
+    /// [`ImportCheckpoint::default()`].
+    ///
+    /// Returns the number of entries appended by this call and the I/O
+    /// statistics of this call.
+    ///
+    /// This method is only available with the `tar` feature.
+    ///
+    /// # Errors
+    ///
+    /// Errors are reported like for [`Archive::append_tar()`], the error of
+    /// the last attempt is returned if the import gives up. Entries before
+    /// `checkpoint` stay in the archive. If the archive is frozen, an
+    /// [`Error::Frozen`] error is returned.
+    #[cfg(feature = "tar")]
+    pub fn append_tar_resumable<R: Read, F: FnMut(u64) -> io::Result<R>>(
+        &mut self,
+        checkpoint: &mut ImportCheckpoint,
+        retries: u32,
+        open: F,
+    ) -> ArchiveResult<ImportSummary, B> {
+        let measure = Measure::start(&self.pager);
+        let entries = tarball::append_tar_resumable(self, checkpoint, retries, open)?;
+
+        Ok(ImportSummary {
+            entries,
+            stats: measure.finish(&self.pager),
+        })
+    }
+
+    /// Validates the tar stream read from `reader` without modifying the
+    /// archive.
+    ///
+    /// The tar stream is parsed like [`Archive::append_tar()`] does. The
+    /// returned [`ImportReport`] tells which entries would be appended or
+    /// skipped, whether they collide with existing entries and whether they
+    /// fit into the archive.
+    ///
+    /// This method is only available with the `tar` feature.
+    ///
+    /// # Errors
+    ///
+    /// If the tar stream is malformed or an entry name contains `..`
+    /// components, an [`Error::Io`] error is returned. If the archive is
+    /// frozen, an [`Error::Frozen`] error is returned.
+    #[cfg(feature = "tar")]
+    pub fn validate_tar<R: Read>(&mut self, reader: R) -> ArchiveResult<ImportReport, B> {
+        tarball::validate_tar(self, reader)
+    }
+
+    /// Writes all entries of the archive as a tar stream into `writer`.
+    ///
+    /// Files, directories, symlinks, hard links, devices and FIFOs are
+    /// written as GNU tar entries with their access rights, owner and
+    /// modification time, so the stream can be processed by any tar
+    /// implementation. Sockets cannot be represented in a tar stream and
+    /// are skipped.
+    ///
+    /// Returns the number of entries written into the tar stream.
+    ///
+    /// This method is only available with the `tar` feature.
+    ///
+    /// # Errors
+    ///
+    /// If an entry name cannot be stored in a tar stream (e.g. it contains
+    /// `..` components), an [`Error::Io`] error is returned.
+    #[cfg(feature = "tar")]
+    pub fn write_tar<W: Write>(&mut self, writer: W) -> ArchiveResult<u64, B> {
+        tarball::write_tar(self, writer)
+    }
+
+    /// Appends all entries of the zip file read from `reader` at the end of
+    /// the archive.
+    ///
+    /// Files, directories and symlinks are converted into archive entries.
+    /// Name, size, access rights and modification time are preserved, the
+    /// modification time of a zip entry is taken as UTC. Like for
+    /// [`Archive::append_from_path()`] a leading `/` is removed from the
+    /// names.
+    ///
+    /// Returns the number of appended entries and the I/O statistics of the
+    /// import.
+    ///
+    /// This method is only available with the `zip` feature.
+    ///
+    /// # Errors
+    ///
+    /// If the zip file is malformed, uses an unsupported compression method
+    /// or encryption, or an entry name contains `..` components, an
+    /// [`Error::Io`] error is returned. Entries appended before the error
+    /// stay in the archive.
+    #[cfg(feature = "zip")]
+    pub fn append_zip<R: Read + Seek>(&mut self, reader: R) -> ArchiveResult<ImportSummary, B> {
+        let measure = Measure::start(&self.pager);
+        let entries = zipfile::append_zip(self, reader)?;
+
+        Ok(ImportSummary {
+            entries,
+            stats: measure.finish(&self.pager),
+        })
+    }
+
+    /// Appends the entries of a zip file at the end of the archive and
+    /// resumes the import, if reading the zip file fails.
+    ///
+    /// The entries are converted like [`Archive::append_zip()`] does. The
+    /// zip file is opened by calling `open`. After each entry, `checkpoint`
+    /// is advanced. If reading from the zip file fails, the partially
+    /// imported entry is removed, the zip file is re-opened and the import
+    /// continues with the entry after the checkpoint. The import gives up
+    /// after `retries` failures in a row without progress.
+    ///
+    /// Returns the number of entries appended by this call and the I/O
+    /// statistics of this call.
+    ///
+    /// This method is only available with the `zip` feature.
+    ///
+    /// # Errors
+    ///
+    /// Errors are reported like for [`Archive::append_zip()`], the error of
+    /// the last attempt is returned if the import gives up. Entries before
+    /// `checkpoint` stay in the archive. If the archive is frozen, an
+    /// [`Error::Frozen`] error is returned.
+    #[cfg(feature = "zip")]
+    pub fn append_zip_resumable<R: Read + Seek, F: FnMut() -> io::Result<R>>(
+        &mut self,
+        checkpoint: &mut ImportCheckpoint,
+        retries: u32,
+        open: F,
+    ) -> ArchiveResult<ImportSummary, B> {
+        let measure = Measure::start(&self.pager);
+        let entries = zipfile::append_zip_resumable(self, checkpoint, retries, open)?;
+
+        Ok(ImportSummary {
+            entries,
+            stats: measure.finish(&self.pager),
+        })
+    }
+
+    /// Validates the zip file read from `reader` without modifying the
+    /// archive.
+    ///
+    /// The zip file is parsed like [`Archive::append_zip()`] does, see
+    /// [`ImportReport`] for the result.
+    ///
+    /// This method is only available with the `zip` feature.
+    ///
+    /// # Errors
+    ///
+    /// If the zip file is malformed or an entry name contains `..`
+    /// components, an [`Error::Io`] error is returned. If the archive is
+    /// frozen, an [`Error::Frozen`] error is returned.
+    #[cfg(feature = "zip")]
+    pub fn validate_zip<R: Read + Seek>(&mut self, reader: R) -> ArchiveResult<ImportReport, B> {
+        zipfile::validate_zip(self, reader)
+    }
+
+    /// Writes all entries of the archive as a zip file into `writer`.
+    ///
+    /// Files, directories and symlinks are written with their access rights
+    /// and modification time, the content of files is deflated. Hard links
+    /// and special files cannot be represented in a zip file and are
+    /// skipped.
+    ///
+    /// Returns the number of entries written into the zip file.
+    ///
+    /// This method is only available with the `zip` feature.
+    #[cfg(feature = "zip")]
+    pub fn write_zip<W: Write + Seek>(&mut self, writer: W) -> ArchiveResult<u64, B> {
+        zipfile::write_zip(self, writer)
+    }
+
+    /// Returns the block allocations recorded since the archive was
+    /// created/opened or since the last call to
+    /// [`clear_allocation_trace()`](Self::clear_allocation_trace).
+    ///
+    /// Only available if the `trace` feature is enabled.
+    #[cfg(feature = "trace")]
+    pub fn allocation_trace(&self) -> &[Allocation<B>] {
+        self.pager.allocations()
+    }
+
+    /// Clears the recorded block allocations.
+    ///
+    /// Only available if the `trace` feature is enabled.
+    #[cfg(feature = "trace")]
+    pub fn clear_allocation_trace(&mut self) {
+        self.pager.clear_allocations()
+    }
+
+    /// Rewrites the whole archive into `container`.
+    ///
+    /// A new archive is created in `container` and all entries of this
+    /// archive are appended, including their metadata and content. The new
+    /// container is free to use another block size or backend, so this is
+    /// the way to migrate an archive if the original block size turned out
+    /// to be a poor choice.
+    ///
+    /// The number of direct blocks, the name index and a freeze are kept.
+    /// Blocks of zeros are stored as holes, so sparse files stay sparse.
+    /// Other than [`Archive::clone_to()`] the archive is not an exact copy:
+    /// zeroed blocks, which were allocated in this archive, become holes as
+    /// well and released blocks are dropped.
+    ///
+    /// On success the new archive is returned.
+    ///
+    /// # Errors
+    ///
+    /// If the block size of `container` cannot hold the direct blocks of
+    /// this archive, an [`Error::BlockSizeTooSmall`] error is returned.
+    /// Errors are wrapped into a [`RewriteError`], which tells you whether
+    /// the error happened in this or in the new archive.
+    pub fn rewrite_to<T: Backend>(
+        &mut self,
+        container: Container<T>,
+    ) -> Result<Archive<T>, RewriteError<B, T>> {
+        let mut target = Archive::create_with_num_direct(container, false, self.tree.num_direct())
+            .map_err(RewriteError::Target)?;
+        let bsize = target.pager.block_size() as usize;
+        let mut buf = vec![0; REWRITE_BATCH * bsize];
+
+        *target.pager.transforms_mut() = self.pager.transforms().clone();
+
+        let mut entry_opt = InnerEntry::first(&mut self.pager, &mut self.tree);
+
+        loop {
+            let mut entry = match entry_opt {
+                Some(Ok(entry)) => entry,
+                Some(Err(err)) => return Err(RewriteError::Source(err)),
+                None => break,
+            };
+
+            let mut entry_mut = InnerBuilder::with_inner(
+                &mut target.pager,
+                &target.header_id,
+                &mut target.header,
+                &mut target.tree,
+                entry.inner().without_content(),
+            )
+            .build()
+            .map_err(RewriteError::Target)?;
+
+            loop {
+                let mut n = 0;
+
+                while n < buf.len() {
+                    match entry.read(&mut buf[n..]).map_err(RewriteError::Source)? {
+                        0 => break,
+                        nread => n += nread,
+                    }
+                }
+
+                if n == 0 {
+                    break;
+                }
+
+                // runs of data are written at once, blocks of zeros as holes
+                let mut start = 0;
+
+                for (i, block) in buf[..n].chunks(bsize).enumerate() {
+                    let offs = i * bsize;
+
+                    if block.len() == bsize && block.iter().all(|b| *b == 0) {
+                        if start < offs {
+                            entry_mut
+                                .write_batch(&buf[start..offs])
+                                .map_err(RewriteError::Target)?;
+                        }
+
+                        entry_mut
+                            .write_zeroes(bsize as u64)
+                            .map_err(RewriteError::Target)?;
+
+                        start = offs + bsize;
+                    }
+                }
+
+                if start < n {
+                    entry_mut
+                        .write_batch(&buf[start..n])
+                        .map_err(RewriteError::Target)?;
+                }
+            }
+
+            entry_opt = entry.next();
+        }
+
+        if self.tree.has_names() {
+            target.build_index().map_err(RewriteError::Target)?;
+        }
+
+        if self.header.is_frozen() {
+            let freeze = Userdata::fetch(&mut self.pager)
+                .map_err(RewriteError::Source)?
+                .freeze;
+
+            target.update_freeze(freeze).map_err(RewriteError::Target)?;
+        }
+
+        debug!(
+            "archive rewritten, {} blocks, header: {}",
+            target.tree.nblocks(),
+            target.header_id
+        );
+
+        Ok(target)
+    }
+
+    /// Clones the archive block by block into `container`.
+    ///
+    /// Other than [`Archive::rewrite_to()`] the entries are not appended
+    /// again, but the blocks of the archive are copied in the order of the
+    /// tree. The clone is an exact copy of the archive, including the
+    /// timestamps of the archive and the holes of sparse files. Released
+    /// blocks are not copied, the name index is rebuilt and a freeze is
+    /// kept.
+    ///
+    /// Every block passes the archive layer, it is decrypted by the
+    /// container of this archive and encrypted again by `container`. Create
+    /// `container` with another key to rotate the key of archived data.
+    ///
+    /// The content is not rearranged, so the block size of `container` must
+    /// match the block size of this archive. Note that the block size of a
+    /// container depends on the cipher, choose the block size of the backend
+    /// accordingly, when switching to another cipher.
+    ///
+    /// On success the new archive is returned.
+    ///
+    /// # Errors
+    ///
+    /// If the block size of `container` does not match the block size of
+    /// this archive, an [`Error::BlockSizeMismatch`] error is returned.
+    /// Errors are wrapped into a [`RewriteError`], which tells you whether
+    /// the error happened in this or in the new archive.
+    pub fn clone_to<T: Backend>(
+        &mut self,
+        container: Container<T>,
+    ) -> Result<Archive<T>, RewriteError<B, T>> {
+        let bsize = self.pager.block_size();
+
+        if container.block_size() != bsize {
+            return Err(RewriteError::Target(Error::BlockSizeMismatch(
+                container.block_size(),
+                bsize,
+            )));
+        }
+
+        let mut target = Archive::create_with_num_direct(container, false, self.tree.num_direct())
+            .map_err(RewriteError::Target)?;
+
+        *target.pager.transforms_mut() = self.pager.transforms().clone();
+
+        for idx in 0..self.tree.nblocks() as usize {
+            let id = match self.tree.lookup(&mut self.pager, idx) {
+                Some(result) => result.map_err(RewriteError::Source)?.clone(),
+                None => {
+                    target
+                        .tree
+                        .aquire_hole(&mut target.pager)
+                        .map_err(RewriteError::Target)?;
+                    continue;
+                }
+            };
+
+            let buf = self.pager.read_buf_raw(&id).map_err(RewriteError::Source)?;
+            let target_id = target
+                .tree
+                .aquire(&mut target.pager)
+                .map_err(RewriteError::Target)?
+                .clone();
+
+            target
+                .pager
+                .write(&target_id, buf)
+                .map_err(RewriteError::Target)?;
+        }
+
+        target.header = self.header.clone();
+        target.header.set_deferred(false);
+        target.pager.set_codec(self.pager.codec());
+
+        flush_header(
+            &mut target.pager,
+            &target.header_id,
+            &target.header,
+            &mut target.tree,
         )
+        .map_err(RewriteError::Target)?;
+
+        if self.header.is_frozen() {
+            let freeze = Userdata::fetch(&mut self.pager)
+                .map_err(RewriteError::Source)?
+                .freeze;
+            let mut userdata = Userdata::fetch(&mut target.pager).map_err(RewriteError::Target)?;
+
+            userdata.freeze = freeze;
+            userdata
+                .store(&mut target.pager)
+                .map_err(RewriteError::Target)?;
+        }
+
+        if self.tree.has_names() {
+            target.build_index().map_err(RewriteError::Target)?;
+        }
+
+        debug!(
+            "archive cloned, {} blocks, header: {}",
+            target.tree.nblocks(),
+            target.header_id
+        );
+
+        Ok(target)
+    }
+
+    /// Recovers the entries of a damaged archive into a new archive.
+    ///
+    /// Use this, if the archive cannot be [opened](Archive::open) anymore,
+    /// because its header or the root of its block tree is damaged. The
+    /// blocks `ids` of the `source` container are scanned for the metadata
+    /// of entries. Backends cannot list their blocks, it is up to you to
+    /// pass the candidates (e.g. all files of a directory backend).
+    ///
+    /// Without the tree the order of the blocks is unknown. The content of
+    /// an entry is expected in the blocks behind its metadata, in the order
+    /// of `ids`. Blocks are aquired from the container in ascending order,
+    /// so pass them sorted by allocation. This does not hold for blocks
+    /// reused from the free list and for sparse files, whose holes have no
+    /// block. Indirect nodes of the lost tree, which are aquired between the
+    /// content blocks of large files, are recognized and skipped. Recovered
+    /// files with a stored checksum are verified, a mismatch is listed in
+    /// [`SalvageReport::damaged`].
+    ///
+    /// The blocks are read one after another, only the ids of the entry,
+    /// which is currently recovered, are kept in memory. If an entry turns
+    /// out to be incomplete, the blocks behind its metadata are scanned
+    /// again.
+    ///
+    /// The recovered entries are copied block by block into a new archive in
+    /// `container`, which is returned together with a [`SalvageReport`].
+    ///
+    /// # Errors
+    ///
+    /// Unreadable blocks are skipped. If the block size of `container` does
+    /// not match the block size of `source`, an
+    /// [`Error::BlockSizeMismatch`] error is returned. Errors are wrapped
+    /// into a [`RewriteError`], which tells you whether the error happened
+    /// in the source container or in the new archive.
+    pub fn salvage<T: Backend, I: IntoIterator<Item = B::Id>>(
+        source: &mut Container<B>,
+        ids: I,
+        container: Container<T>,
+    ) -> Result<(Archive<T>, SalvageReport), RewriteError<B, T>> {
+        salvage::salvage(source, ids, container)
+    }
+
+    /// Freezes the archive.
+    ///
+    /// A frozen archive cannot be modified, appending a new entry fails
+    /// with an [`Error::Frozen`] error. The archive stays frozen (even when
+    /// opened again) until [`Archive::unfreeze()`] is called with the same
+    /// `token`. Only a digest of the token is stored in the archive.
+    ///
+    /// # Errors
+    ///
+    /// If the archive is already frozen, an [`Error::Frozen`] error is
+    /// returned.
+    pub fn freeze<T: AsRef<[u8]>>(&mut self, token: T) -> ArchiveResult<(), B> {
+        self.header.check_mutable()?;
+
+        self.update_freeze(Some(freeze_digest(token.as_ref())))
+    }
+
+    /// Lifts the freeze of an archive, which was frozen with
+    /// [`Archive::freeze()`].
+    ///
+    /// Nothing happens, if the archive is not frozen.
+    ///
+    /// # Errors
+    ///
+    /// If `token` does not match the token passed to [`Archive::freeze()`],
+    /// an [`Error::InvalidFreezeToken`] error is returned.
+    pub fn unfreeze<T: AsRef<[u8]>>(&mut self, token: T) -> ArchiveResult<(), B> {
+        let userdata = Userdata::fetch(&mut self.pager)?;
+
+        match userdata.freeze {
+            Some(digest) if digest != freeze_digest(token.as_ref()) => {
+                Err(Error::InvalidFreezeToken)
+            }
+            Some(_) => self.update_freeze(None),
+            None => Ok(()),
+        }
+    }
+
+    /// Tests whether the archive is frozen.
+    pub fn is_frozen(&self) -> bool {
+        self.header.is_frozen()
+    }
+
+    fn update_freeze(&mut self, digest: Option<[u8; FREEZE_DIGEST_SIZE]>) -> ArchiveResult<(), B> {
+        let mut userdata = Userdata::fetch(&mut self.pager)?;
+
+        userdata.freeze = digest;
+        userdata.store(&mut self.pager)?;
+
+        self.header.set_frozen(digest.is_some());
+
+        debug!("archive frozen: {}", self.header.is_frozen());
+
+        Ok(())
+    }
+
+    /// Loads metadata of the archive, which is otherwise loaded lazily on
+    /// first use.
+    ///
+    /// Currently these are the nodes of the tree leading to the end of the
+    /// archive, where the next entry is appended, the name index and the
+    /// list of released blocks.
+    pub fn warm_up(&mut self) -> ArchiveResult<(), B> {
+        self.tree.warm_up(&mut self.pager)
+    }
+
+    /// Performs a bounded amount of maintenance work.
+    ///
+    /// Long-running services can call this method periodically to spread
+    /// the maintenance of the archive over time. Each call does at most the
+    /// work allowed by `budget` and continues where the previous call
+    /// stopped.
+    ///
+    /// The following work is done:
+    ///
+    /// * Blocks, which were freed (e.g. by [removing](Archive::remove) an
+    ///   entry) and are kept in the list of free blocks, are released to the
+    ///   container, so that the backend can reclaim their storage. This is
+    ///   skipped for a frozen archive.
+    /// * The blocks of the archive are scrubbed: each block is read so that
+    ///   the container verifies its integrity. Blocks which cannot be read
+    ///   are listed in the returned [`MaintainReport`].
+    ///
+    /// Both count against `budget`, free blocks are released first. The
+    /// entries are not compacted, the content of an entry is never moved.
+    pub fn maintain(&mut self, budget: Budget) -> ArchiveResult<MaintainReport, B> {
+        let measure = Measure::start(&self.pager);
+        let collect = !self.header.is_frozen();
+        let mut report = self
+            .scrubber
+            .run(&mut self.pager, &mut self.tree, budget, collect)?;
+
+        report.stats = measure.finish(&self.pager);
+
+        Ok(report)
+    }
+
+    /// Verifies the integrity of the whole archive.
+    ///
+    /// All blocks referenced by the tree are read, no block may be
+    /// referenced twice. All entries are visited, their sizes must fit into
+    /// the blocks of the tree and the header must count them correctly. The
+    /// content of files with a [stored checksum](FileEntry::stored_checksum)
+    /// is verified against it.
+    ///
+    /// The verification does not stop at the first problem, all problems
+    /// are collected in the returned [`VerifyReport`]. Note that the whole
+    /// archive is read, which can take a while for large archives, see
+    /// [`Archive::maintain()`] for an incremental alternative.
+    pub fn verify(&mut self) -> VerifyReport {
+        let measure = Measure::start(&self.pager);
+        let mut report = verify::verify(&mut self.pager, &self.header, &mut self.tree);
+
+        report.stats = measure.finish(&self.pager);
+
+        report
+    }
+
+    /// Checks the archive and repairs recoverable inconsistencies.
+    ///
+    /// Such inconsistencies are usually left behind by an interrupted
+    /// write:
+    ///
+    /// * Slots behind the last block of the tree, which still refer to a
+    ///   block, are cleared and the blocks released.
+    /// * A partially written last entry, whose content needs more blocks
+    ///   than available or whose metadata in the last block is cut off, is
+    ///   discarded. Blocks behind the last entry, which do not belong to any
+    ///   entry, are released.
+    /// * An entry in the middle of the archive, whose metadata cannot be
+    ///   loaded, is reported in [`RepairReport::damaged`]. The same applies
+    ///   to any entry, which was written completely but cannot be decoded,
+    ///   e.g. because it uses a feature unknown to this version. It is not
+    ///   repaired, the entries behind it are left alone and the number of
+    ///   entries and the name index are not checked.
+    /// * Entries of [multipart uploads](MultipartEntry), which were never
+    ///   finished, are discarded and their blocks released.
+    /// * The number of entries in the header is corrected.
+    /// * The [name index](Archive::create_index) is rebuilt, if it has
+    ///   dangling or missing records.
+    ///
+    /// The applied fixes are listed in the returned [`RepairReport`].
+    /// Savepoints are discarded, if something was fixed. Use
+    /// [`Archive::verify()`] to find problems, which cannot be repaired
+    /// (e.g. unreadable blocks or checksum mismatches).
+    ///
+    /// # Errors
+    ///
+    /// Errors of the container are passed to the caller, they are not
+    /// repaired. If the archive is frozen, an [`Error::Frozen`] error is
+    /// returned.
+    pub fn check_and_repair(&mut self) -> ArchiveResult<RepairReport, B> {
+        repair::check_and_repair(self)
+    }
+
+    /// Converts the archive into an [`ArchiveReader`], which provides
+    /// read-only access.
+    pub fn into_reader(self) -> ArchiveReader<B> {
+        ArchiveReader::new(self)
     }
 
     /// Consumes this `Archive`, returning the underlying [`Container`].
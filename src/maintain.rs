@@ -0,0 +1,152 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use log::{debug, error};
+use nuts_container::backend::Backend;
+use std::time::{Duration, Instant};
+
+use crate::error::ArchiveResult;
+use crate::pager::Pager;
+use crate::stats::IoStats;
+use crate::tree::Tree;
+
+/// Limits the work done by a single call of
+/// [`Archive::maintain()`](crate::Archive::maintain).
+#[derive(Clone, Copy, Debug)]
+pub enum Budget {
+    /// Process at most the given number of blocks.
+    Blocks(u64),
+
+    /// Stop after the given time has elapsed. At least one block is
+    /// processed.
+    Time(Duration),
+}
+
+/// The work done by [`Archive::maintain()`](crate::Archive::maintain).
+#[derive(Debug, Default)]
+pub struct MaintainReport {
+    /// Number of blocks which were scrubbed
+    pub scrubbed: u64,
+
+    /// Number of blocks which were taken from the list of free blocks and
+    /// released to the container
+    pub released: u64,
+
+    /// Indexes of the blocks which could not be read
+    pub failed: Vec<u64>,
+
+    /// Set to `true` if the end of the archive was reached. The next call
+    /// starts again with the first block.
+    pub completed: bool,
+
+    /// I/O statistics of the run
+    pub stats: IoStats,
+}
+
+/// Incrementally reads all blocks of the archive.
+///
+/// Reading a block lets the container verify it, so damaged blocks are
+/// detected. The position is kept between the runs. Before scrubbing, the
+/// blocks of the free list are released to the container, if `collect` is
+/// set.
+pub(crate) struct Scrubber {
+    cursor: u64,
+}
+
+impl Scrubber {
+    pub(crate) fn new() -> Scrubber {
+        Scrubber { cursor: 0 }
+    }
+
+    pub(crate) fn run<B: Backend>(
+        &mut self,
+        pager: &mut Pager<B>,
+        tree: &mut Tree<B>,
+        budget: Budget,
+        collect: bool,
+    ) -> ArchiveResult<MaintainReport, B> {
+        let start = Instant::now();
+        let mut report = MaintainReport::default();
+
+        while collect && !exhausted(budget, &report, start) {
+            // taken from the list first, a crash in between leaks the block
+            // instead of leaving a released block in the list
+            match tree.pop_free(pager)? {
+                Some(id) => {
+                    pager.release(id.clone())?;
+                    debug!("block {} released", id);
+
+                    report.released += 1;
+                }
+                None => break,
+            }
+        }
+
+        while !exhausted(budget, &report, start) {
+            if self.cursor >= tree.nblocks() {
+                self.cursor = 0;
+                report.completed = true;
+                break;
+            }
+
+            let idx = self.cursor;
+            self.cursor += 1;
+
+            match scrub_block(pager, tree, idx as usize) {
+                Ok(()) => debug!("scrub {}: ok", idx),
+                Err(err) => {
+                    error!("scrub {}: {}", idx, err);
+                    report.failed.push(idx);
+                }
+            }
+
+            report.scrubbed += 1;
+        }
+
+        Ok(report)
+    }
+}
+
+fn exhausted(budget: Budget, report: &MaintainReport, start: Instant) -> bool {
+    let processed = report.scrubbed + report.released;
+
+    match budget {
+        Budget::Blocks(n) => processed >= n,
+        Budget::Time(duration) => processed > 0 && start.elapsed() >= duration,
+    }
+}
+
+fn scrub_block<B: Backend>(
+    pager: &mut Pager<B>,
+    tree: &mut Tree<B>,
+    idx: usize,
+) -> ArchiveResult<(), B> {
+    if let Some(result) = tree.lookup(pager, idx) {
+        let id = result?.clone();
+        pager.read_buf_raw(&id)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,123 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use std::time::Duration;
+
+use crate::maintain::Budget;
+use crate::tests::setup_container_with_bsize;
+use crate::Archive;
+
+fn setup_archive() -> Archive<nuts_container::memory::MemoryBackend> {
+    let container = setup_container_with_bsize(106);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    let mut entry = archive.append_file("f1").build().unwrap();
+    entry.write_all(&[1; 200]).unwrap();
+
+    archive.append_directory("d1").build().unwrap();
+
+    archive
+}
+
+#[test]
+fn empty() {
+    let container = setup_container_with_bsize(106);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    let report = archive.maintain(Budget::Blocks(10)).unwrap();
+
+    assert_eq!(report.scrubbed, 0);
+    assert!(report.failed.is_empty());
+    assert!(report.completed);
+}
+
+#[test]
+fn blocks() {
+    let mut archive = setup_archive();
+    assert_eq!(archive.info().blocks, 4);
+
+    let report = archive.maintain(Budget::Blocks(3)).unwrap();
+    assert_eq!(report.scrubbed, 3);
+    assert!(report.failed.is_empty());
+    assert!(!report.completed);
+
+    let report = archive.maintain(Budget::Blocks(3)).unwrap();
+    assert_eq!(report.scrubbed, 1);
+    assert!(report.failed.is_empty());
+    assert!(report.completed);
+
+    let report = archive.maintain(Budget::Blocks(3)).unwrap();
+    assert_eq!(report.scrubbed, 3);
+    assert!(!report.completed);
+}
+
+#[test]
+fn time() {
+    let mut archive = setup_archive();
+
+    let report = archive.maintain(Budget::Time(Duration::ZERO)).unwrap();
+    assert_eq!(report.scrubbed, 1);
+    assert!(!report.completed);
+
+    let report = archive
+        .maintain(Budget::Time(Duration::from_secs(60)))
+        .unwrap();
+    assert_eq!(report.scrubbed, 3);
+    assert!(report.completed);
+}
+
+#[test]
+fn release() {
+    let mut archive = setup_archive();
+
+    archive.remove("f1").unwrap();
+    assert_eq!(archive.tree.free_list().unwrap().len(), 3);
+
+    let report = archive.maintain(Budget::Blocks(2)).unwrap();
+    assert_eq!(report.released, 2);
+    assert_eq!(report.scrubbed, 0);
+    assert_eq!(archive.tree.free_list().unwrap().len(), 1);
+
+    let report = archive.maintain(Budget::Blocks(2)).unwrap();
+    assert_eq!(report.released, 1);
+    assert_eq!(report.scrubbed, 1);
+    assert!(archive.tree.free_list().unwrap().is_empty());
+
+    let mut entry = archive.append_file("f2").build().unwrap();
+    entry.write_all(&[2; 200]).unwrap();
+
+    assert!(archive.verify().is_ok());
+}
+
+#[test]
+fn release_frozen() {
+    let mut archive = setup_archive();
+
+    archive.remove("f1").unwrap();
+    archive.freeze("token").unwrap();
+
+    let report = archive.maintain(Budget::Blocks(10)).unwrap();
+    assert_eq!(report.released, 0);
+    assert_eq!(report.scrubbed, 1);
+    assert!(report.completed);
+    assert_eq!(archive.tree.free_list().unwrap().len(), 3);
+}
@@ -0,0 +1,299 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use nuts_bytes::{FromBytes, Reader, ToBytes, Writer};
+use nuts_container::backend::Backend;
+use sha2::{Digest as _, Sha256};
+use std::collections::HashMap;
+
+use crate::entry::immut::InnerEntry;
+use crate::error::ArchiveResult;
+use crate::pager::Pager;
+use crate::tree::Tree;
+
+/// A SHA-256 digest.
+pub type Digest = [u8; 32];
+
+const LEAF: u8 = 0;
+const NODE: u8 = 1;
+
+/// An entry of the [`Manifest`].
+#[derive(Clone, Debug, FromBytes, PartialEq, ToBytes)]
+pub struct ManifestEntry {
+    name: String,
+    size: u64,
+    digest: Digest,
+}
+
+impl ManifestEntry {
+    pub(crate) fn new(name: String, size: u64, digest: Digest) -> ManifestEntry {
+        ManifestEntry { name, size, digest }
+    }
+
+    /// Returns the name of the entry.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the size of the entry.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the SHA-256 digest of the content of the entry.
+    pub fn digest(&self) -> &Digest {
+        &self.digest
+    }
+
+    fn leaf(&self) -> Digest {
+        let mut sha = Sha256::new();
+
+        sha.update([LEAF]);
+        sha.update((self.name.len() as u64).to_be_bytes());
+        sha.update(self.name.as_bytes());
+        sha.update(self.size.to_be_bytes());
+        sha.update(self.digest);
+
+        sha.finalize().into()
+    }
+}
+
+/// A list of digests of all entries in the archive.
+///
+/// The manifest is created by [`Archive::manifest()`](crate::Archive::manifest).
+/// It contains the name, size and content digest of each entry. All entries
+/// are combined into a single [Merkle root](Self::root).
+///
+/// The manifest can be [exported](Self::export) and passed to a third party,
+/// which does not have access to the archive. The owner of the archive can
+/// later create a manifest again (e.g. after the container was
+/// transported) and [compare](PartialEq) it with the exported one.
+#[derive(Clone, Debug, FromBytes, PartialEq, ToBytes)]
+pub struct Manifest {
+    entries: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    pub(crate) fn create<B: Backend>(
+        pager: &mut Pager<B>,
+        tree: &mut Tree<B>,
+    ) -> ArchiveResult<Manifest, B> {
+        let mut entries = vec![];
+        let mut buf = vec![0; pager.block_size() as usize];
+        let mut entry_opt = InnerEntry::first(pager, tree);
+
+        loop {
+            let mut entry = match entry_opt {
+                Some(Ok(entry)) => entry,
+                Some(Err(err)) => return Err(err),
+                None => break,
+            };
+
+            if entry.inner().mode.is_internal() {
+                entry_opt = entry.next();
+                continue;
+            }
+
+            // a stored checksum saves reading the content
+            let digest = match entry.inner().checksum {
+                Some(checksum) => checksum,
+                None => {
+                    let mut sha = Sha256::new();
+
+                    loop {
+                        let n = entry.read(&mut buf)?;
+
+                        if n == 0 {
+                            break;
+                        }
+
+                        sha.update(&buf[..n]);
+                    }
+
+                    sha.finalize().into()
+                }
+            };
+
+            entries.push(ManifestEntry {
+                name: entry.inner().name.clone(),
+                size: entry.inner().content_size(),
+                digest,
+            });
+
+            entry_opt = entry.next();
+        }
+
+        Ok(Manifest { entries })
+    }
+
+    /// Returns all entries of the manifest.
+    pub fn entries(&self) -> &[ManifestEntry] {
+        &self.entries
+    }
+
+    /// Calculates the Merkle root of the manifest.
+    ///
+    /// The leafs of the tree are the hashes of the entries, which covers the
+    /// name, size and content digest of each entry. Two archives with the
+    /// same entries (in the same order) have the same root.
+    pub fn root(&self) -> Digest {
+        let mut level = self
+            .entries
+            .iter()
+            .map(|entry| entry.leaf())
+            .collect::<Vec<_>>();
+
+        if level.is_empty() {
+            return Sha256::digest([LEAF]).into();
+        }
+
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => {
+                        let mut sha = Sha256::new();
+
+                        sha.update([NODE]);
+                        sha.update(left);
+                        sha.update(right);
+
+                        sha.finalize().into()
+                    }
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+        }
+
+        level[0]
+    }
+
+    /// Verifies whether the Merkle root of this manifest matches the
+    /// `expected` root.
+    pub fn verify(&self, expected: &Digest) -> bool {
+        &self.root() == expected
+    }
+
+    /// Compares this manifest with a `newer` manifest of the archive.
+    ///
+    /// Entries are matched by name, the first entry with a name counts (like
+    /// [`Archive::lookup()`](crate::Archive::lookup) would find it). An
+    /// entry is changed, if its size or content digest differ.
+    ///
+    /// Pass the current manifest as `self` and a previously exported
+    /// manifest as `newer` to find out, what restoring the old state would
+    /// change.
+    pub fn diff(&self, newer: &Manifest) -> ManifestDiff {
+        let old = first_by_name(&self.entries);
+        let new = first_by_name(&newer.entries);
+        let mut diff = ManifestDiff::default();
+
+        for entry in newer.entries.iter() {
+            if new.get(entry.name.as_str()) != Some(&entry) {
+                continue;
+            }
+
+            match old.get(entry.name.as_str()) {
+                None => diff.added.push(entry.clone()),
+                Some(prev) if prev.size != entry.size || prev.digest != entry.digest => {
+                    diff.changed.push(((*prev).clone(), entry.clone()))
+                }
+                Some(_) => {}
+            }
+        }
+
+        for entry in self.entries.iter() {
+            if old.get(entry.name.as_str()) == Some(&entry)
+                && !new.contains_key(entry.name.as_str())
+            {
+                diff.removed.push(entry.clone());
+            }
+        }
+
+        diff
+    }
+
+    /// Exports the manifest into a binary representation.
+    ///
+    /// Use [`Manifest::import()`] to convert it back.
+    pub fn export(&self) -> Vec<u8> {
+        let mut writer = Writer::new(vec![]);
+
+        // serialization into a vec cannot fail
+        writer.write(self).unwrap();
+
+        writer.into_target()
+    }
+
+    /// Imports a manifest from its binary representation, which was created
+    /// by [`Manifest::export()`].
+    pub fn import(buf: &[u8]) -> Result<Manifest, nuts_bytes::Error> {
+        Reader::new(buf).read()
+    }
+}
+
+fn first_by_name(entries: &[ManifestEntry]) -> HashMap<&str, &ManifestEntry> {
+    let mut map = HashMap::new();
+
+    for entry in entries {
+        map.entry(entry.name.as_str()).or_insert(entry);
+    }
+
+    map
+}
+
+/// Differences between two manifests, created by [`Manifest::diff()`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ManifestDiff {
+    added: Vec<ManifestEntry>,
+    removed: Vec<ManifestEntry>,
+    changed: Vec<(ManifestEntry, ManifestEntry)>,
+}
+
+impl ManifestDiff {
+    /// Returns the entries, which only exist in the newer manifest.
+    pub fn added(&self) -> &[ManifestEntry] {
+        &self.added
+    }
+
+    /// Returns the entries, which only exist in the older manifest.
+    pub fn removed(&self) -> &[ManifestEntry] {
+        &self.removed
+    }
+
+    /// Returns the entries, which exist in both manifests but with different
+    /// content.
+    ///
+    /// Each item is a pair of the old and the new entry.
+    pub fn changed(&self) -> &[(ManifestEntry, ManifestEntry)] {
+        &self.changed
+    }
+
+    /// Tests whether both manifests have the same entries.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
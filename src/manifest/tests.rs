@@ -0,0 +1,174 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use crate::manifest::Manifest;
+use crate::tests::setup_container_with_bsize;
+use crate::Archive;
+
+fn setup_archive(content: &[u8]) -> Archive<nuts_container::memory::MemoryBackend> {
+    let container = setup_container_with_bsize(106);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    let mut entry = archive.append_file("f1").build().unwrap();
+    entry.write_all(content).unwrap();
+
+    archive.append_directory("d1").build().unwrap();
+    archive.append_symlink("s1", "f1").build().unwrap();
+
+    archive
+}
+
+#[test]
+fn empty() {
+    let container = setup_container_with_bsize(106);
+    let mut archive = Archive::create(container, false).unwrap();
+    let manifest = archive.manifest().unwrap();
+
+    assert!(manifest.entries().is_empty());
+    assert!(manifest.verify(&manifest.root()));
+}
+
+#[test]
+fn entries() {
+    let mut archive = setup_archive(b"abc");
+    let manifest = archive.manifest().unwrap();
+
+    assert_eq!(manifest.entries().len(), 3);
+
+    assert_eq!(manifest.entries()[0].name(), "f1");
+    assert_eq!(manifest.entries()[0].size(), 3);
+    assert_eq!(
+        manifest.entries()[0].digest(),
+        &[
+            0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae,
+            0x22, 0x23, 0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61,
+            0xf2, 0x00, 0x15, 0xad
+        ]
+    );
+
+    assert_eq!(manifest.entries()[1].name(), "d1");
+    assert_eq!(manifest.entries()[1].size(), 0);
+
+    assert_eq!(manifest.entries()[2].name(), "s1");
+    assert_eq!(manifest.entries()[2].size(), 2);
+}
+
+#[test]
+fn root() {
+    let mut archive1 = setup_archive(b"abc");
+    let mut archive2 = setup_archive(b"abc");
+    let mut archive3 = setup_archive(b"abd");
+
+    let manifest1 = archive1.manifest().unwrap();
+    let manifest2 = archive2.manifest().unwrap();
+    let manifest3 = archive3.manifest().unwrap();
+
+    assert_eq!(manifest1, manifest2);
+    assert!(manifest1.verify(&manifest2.root()));
+
+    assert_ne!(manifest1, manifest3);
+    assert!(!manifest1.verify(&manifest3.root()));
+}
+
+#[test]
+fn export_import() {
+    let mut archive = setup_archive(b"abc");
+    let manifest = archive.manifest().unwrap();
+
+    let buf = manifest.export();
+    let imported = Manifest::import(&buf).unwrap();
+
+    assert_eq!(imported, manifest);
+    assert_eq!(imported.root(), manifest.root());
+}
+
+#[test]
+fn import_invalid() {
+    assert!(Manifest::import(&[0, 0, 0]).is_err());
+}
+
+#[test]
+fn diff_same() {
+    let mut archive = setup_archive(b"abc");
+    let manifest = archive.manifest().unwrap();
+
+    assert!(manifest.diff(&manifest).is_empty());
+}
+
+#[test]
+fn diff() {
+    let mut archive = setup_archive(b"abc");
+    let old = archive.manifest().unwrap();
+
+    archive.remove("d1").unwrap();
+    archive.truncate("f1", 1).unwrap();
+    archive.append_file("f2").build().unwrap();
+
+    let new = archive.manifest().unwrap();
+    let diff = old.diff(&new);
+
+    let names = |entries: &[crate::ManifestEntry]| {
+        entries
+            .iter()
+            .map(|e| e.name().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(names(diff.added()), ["f2"]);
+    assert_eq!(names(diff.removed()), ["d1"]);
+    assert_eq!(diff.changed().len(), 1);
+    assert_eq!(diff.changed()[0].0.size(), 3);
+    assert_eq!(diff.changed()[0].1.size(), 1);
+
+    // the other way round
+    let diff = new.diff(&old);
+
+    assert_eq!(names(diff.added()), ["d1"]);
+    assert_eq!(names(diff.removed()), ["f2"]);
+    assert_eq!(diff.changed()[0].0.size(), 1);
+}
+
+#[test]
+fn diff_first_wins() {
+    let mut archive = setup_archive(b"abc");
+    let old = archive.manifest().unwrap();
+
+    // shadowed by the first f1
+    let mut entry = archive.append_file("f1").build().unwrap();
+    entry.write_all(b"xyz").unwrap();
+
+    let new = archive.manifest().unwrap();
+    assert!(old.diff(&new).is_empty());
+}
+
+#[test]
+fn diff_import() {
+    let mut archive = setup_archive(b"abc");
+    let old = Manifest::import(&archive.manifest().unwrap().export()).unwrap();
+
+    archive.append_directory("d2").build().unwrap();
+
+    let diff = old.diff(&archive.manifest().unwrap());
+    assert_eq!(diff.added().len(), 1);
+    assert!(diff.removed().is_empty());
+    assert!(diff.changed().is_empty());
+}
@@ -0,0 +1,310 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use log::debug;
+use nuts_container::backend::{Backend, BlockId};
+use std::collections::HashMap;
+use std::{cmp, mem};
+
+use crate::error::{ArchiveResult, Error};
+use crate::pager::Pager;
+
+/// An index, which maps the names of the entries to their tree index.
+///
+/// The index is stored in a chain of blocks, which are not part of the
+/// tree. Each block starts with the id of the next block (or a null id for
+/// the last block) and the number of used bytes. The payload of all blocks
+/// is a stream of `(name, idx)` records. Only the first entry of a name is
+/// recorded, like [`Archive::lookup()`](crate::Archive::lookup) it returns
+/// the first matching entry.
+///
+/// The whole index is loaded into memory, when it is used for the first
+/// time after the archive is opened. New records are appended to the last
+/// block.
+#[derive(Debug)]
+pub struct NameIndex<B: Backend> {
+    first: B::Id,
+    last: B::Id,
+    used: usize,
+    map: HashMap<String, u64>,
+}
+
+impl<B: Backend> NameIndex<B> {
+    fn header_size() -> usize {
+        B::Id::size() + mem::size_of::<u32>()
+    }
+
+    /// Creates an empty index in a new block.
+    pub fn create(pager: &mut Pager<B>) -> ArchiveResult<NameIndex<B>, B> {
+        let id = pager.aquire()?;
+        let block = vec![0; pager.block_size() as usize];
+
+        write_block::<B>(pager, &id, block, &B::Id::null(), 0)?;
+
+        debug!("name index created at {}", id);
+
+        Ok(NameIndex {
+            first: id.clone(),
+            last: id,
+            used: 0,
+            map: HashMap::new(),
+        })
+    }
+
+    /// Loads the index, which starts at block `first`.
+    pub fn load(pager: &mut Pager<B>, first: &B::Id) -> ArchiveResult<NameIndex<B>, B> {
+        let capacity = pager.block_size() as usize - Self::header_size();
+        let mut payload = vec![];
+        let mut id = first.clone();
+
+        let used = loop {
+            let mut reader = pager.read_buf(&id)?;
+            let next = reader.read::<B::Id>()?;
+            let used = reader.read::<u32>()? as usize;
+
+            if used > capacity {
+                return Err(Error::InvalidNameIndex);
+            }
+
+            payload.extend_from_slice(reader.take(used)?);
+
+            if next.is_null() {
+                break used;
+            }
+
+            id = next;
+        };
+
+        let mut map = HashMap::new();
+        let mut reader = pager.codec().reader(payload.as_slice());
+
+        while !reader.remaining().is_empty() {
+            // borrowed, only the first record of a name is allocated
+            let name = reader.read_str()?;
+            let idx = reader.read::<u64>()?;
+
+            if !map.contains_key(name) {
+                map.insert(name.to_string(), idx);
+            }
+        }
+
+        debug!("name index loaded from {}: {} names", first, map.len());
+
+        Ok(NameIndex {
+            first: first.clone(),
+            last: id,
+            used,
+            map,
+        })
+    }
+
+    /// Releases all blocks of the index.
+    pub fn release(self, pager: &mut Pager<B>) -> ArchiveResult<(), B> {
+        let mut id = self.first;
+
+        while !id.is_null() {
+            let next = pager.read_buf(&id)?.read::<B::Id>()?;
+
+            pager.release(id)?;
+            id = next;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the id of the first block of the index.
+    pub fn first(&self) -> &B::Id {
+        &self.first
+    }
+
+    /// Returns the number of names in the index.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns the tree index of the first entry with the given `name`.
+    pub fn get(&self, name: &str) -> Option<u64> {
+        self.map.get(name).copied()
+    }
+
+    /// Records the entry `name` stored at tree index `idx`.
+    ///
+    /// Nothing is recorded, if the index already knows an entry with this
+    /// name.
+    pub fn insert(&mut self, pager: &mut Pager<B>, name: &str, idx: u64) -> ArchiveResult<(), B> {
+        if self.map.contains_key(name) {
+            return Ok(());
+        }
+
+        let record = {
+            let mut writer = pager.codec().writer(vec![]);
+
+            writer.write(&name.to_string())?;
+            writer.write(&idx)?;
+
+            writer.into_target()
+        };
+
+        let header_size = Self::header_size();
+        let capacity = pager.block_size() as usize - header_size;
+        let mut remaining = record.as_slice();
+
+        while !remaining.is_empty() {
+            if self.used == capacity {
+                let next = pager.aquire()?;
+                let block = pager.read_buf_raw(&self.last)?.to_vec();
+
+                write_block::<B>(pager, &self.last, block, &next, self.used)?;
+                write_block::<B>(
+                    pager,
+                    &next,
+                    vec![0; pager.block_size() as usize],
+                    &B::Id::null(),
+                    0,
+                )?;
+
+                self.last = next;
+                self.used = 0;
+            }
+
+            let n = cmp::min(capacity - self.used, remaining.len());
+            let offs = header_size + self.used;
+            let mut block = pager.read_buf_raw(&self.last)?.to_vec();
+
+            block[offs..offs + n].copy_from_slice(&remaining[..n]);
+
+            self.used += n;
+            write_block::<B>(pager, &self.last, block, &B::Id::null(), self.used)?;
+
+            remaining = &remaining[n..];
+        }
+
+        self.map.insert(name.to_string(), idx);
+
+        Ok(())
+    }
+
+    /// Forgets the record of the entry `name`, which is removed.
+    ///
+    /// If `next` is set, the name is recorded for the following entry with
+    /// this name instead. The change is written with [`NameIndex::store()`].
+    pub fn forget(&mut self, name: &str, next: Option<u64>) {
+        match next {
+            Some(idx) => self.map.insert(name.to_string(), idx),
+            None => self.map.remove(name),
+        };
+    }
+
+    /// Moves the records behind `count` removed blocks at `idx` to the
+    /// front. The change is written with [`NameIndex::store()`].
+    pub fn shift(&mut self, idx: u64, count: u64) {
+        for value in self.map.values_mut().filter(|value| **value > idx) {
+            *value -= count;
+        }
+    }
+
+    /// Writes all records into the blocks of the index.
+    ///
+    /// The blocks of the index are reused, missing blocks are aquired and
+    /// blocks, which are not needed anymore, are released.
+    pub fn store(&mut self, pager: &mut Pager<B>) -> ArchiveResult<(), B> {
+        let mut records = self.map.iter().collect::<Vec<_>>();
+        records.sort_by_key(|(_, idx)| **idx);
+
+        let payload = {
+            let mut writer = pager.codec().writer(vec![]);
+
+            for (name, idx) in records {
+                writer.write(name)?;
+                writer.write(idx)?;
+            }
+
+            writer.into_target()
+        };
+
+        let header_size = Self::header_size();
+        let capacity = pager.block_size() as usize - header_size;
+        let chunks = if payload.is_empty() {
+            vec![&payload[..]]
+        } else {
+            payload.chunks(capacity).collect()
+        };
+
+        let mut ids = vec![];
+        let mut id = self.first.clone();
+
+        while !id.is_null() {
+            let next = pager.read_buf(&id)?.read::<B::Id>()?;
+
+            ids.push(id);
+            id = next;
+        }
+
+        while ids.len() < chunks.len() {
+            ids.push(pager.aquire()?);
+        }
+
+        for id in ids.split_off(chunks.len()) {
+            pager.release(id)?;
+        }
+
+        for (n, chunk) in chunks.iter().enumerate() {
+            let next = ids.get(n + 1).cloned().unwrap_or_else(B::Id::null);
+            let mut block = vec![0; pager.block_size() as usize];
+
+            block[header_size..header_size + chunk.len()].copy_from_slice(chunk);
+            write_block::<B>(pager, &ids[n], block, &next, chunk.len())?;
+        }
+
+        self.last = ids[chunks.len() - 1].clone();
+        self.used = chunks[chunks.len() - 1].len();
+
+        debug!("name index stored in {} blocks", ids.len());
+
+        Ok(())
+    }
+}
+
+fn write_block<B: Backend>(
+    pager: &mut Pager<B>,
+    id: &B::Id,
+    mut block: Vec<u8>,
+    next: &B::Id,
+    used: usize,
+) -> ArchiveResult<(), B> {
+    let header = {
+        let mut writer = pager.codec().writer(vec![]);
+
+        writer.write(next)?;
+        writer.write(&(used as u32))?;
+
+        writer.into_target()
+    };
+
+    block[..header.len()].copy_from_slice(&header);
+    pager.write(id, &block)?;
+
+    Ok(())
+}
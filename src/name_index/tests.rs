@@ -0,0 +1,126 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use nuts_container::memory::MemoryBackend;
+
+use crate::error::Error;
+use crate::name_index::NameIndex;
+use crate::pager::Pager;
+use crate::tests::setup_container_with_bsize;
+
+fn setup_pager() -> Pager<MemoryBackend> {
+    Pager::new(setup_container_with_bsize(64))
+}
+
+#[test]
+fn empty() {
+    let mut pager = setup_pager();
+
+    let index = NameIndex::create(&mut pager).unwrap();
+    assert!(index.get("foo").is_none());
+
+    let index = NameIndex::load(&mut pager, &index.first().clone()).unwrap();
+    assert!(index.get("foo").is_none());
+    assert_eq!(index.used, 0);
+}
+
+#[test]
+fn insert() {
+    let mut pager = setup_pager();
+    let mut index = NameIndex::create(&mut pager).unwrap();
+
+    index.insert(&mut pager, "foo", 1).unwrap();
+    index.insert(&mut pager, "bar", 3).unwrap();
+
+    assert_eq!(index.get("foo"), Some(1));
+    assert_eq!(index.get("bar"), Some(3));
+    assert!(index.get("baz").is_none());
+    assert_eq!(index.first, index.last);
+}
+
+#[test]
+fn insert_duplicate() {
+    let mut pager = setup_pager();
+    let mut index = NameIndex::create(&mut pager).unwrap();
+
+    index.insert(&mut pager, "foo", 1).unwrap();
+    let used = index.used;
+
+    index.insert(&mut pager, "foo", 5).unwrap();
+    assert_eq!(index.used, used);
+    assert_eq!(index.get("foo"), Some(1));
+}
+
+#[test]
+fn load() {
+    let mut pager = setup_pager();
+    let mut index = NameIndex::create(&mut pager).unwrap();
+
+    // 64 bytes per block, 56 bytes of payload, 36 bytes per record
+    for n in 0..10 {
+        let name = format!("some/deep/path/{:05}", n);
+        index.insert(&mut pager, &name, n).unwrap();
+    }
+
+    assert_ne!(index.first, index.last);
+
+    let loaded = NameIndex::load(&mut pager, &index.first().clone()).unwrap();
+
+    for n in 0..10 {
+        let name = format!("some/deep/path/{:05}", n);
+        assert_eq!(loaded.get(&name), Some(n));
+    }
+
+    assert_eq!(loaded.last, index.last);
+    assert_eq!(loaded.used, index.used);
+}
+
+#[test]
+fn insert_after_load() {
+    let mut pager = setup_pager();
+    let mut index = NameIndex::create(&mut pager).unwrap();
+
+    index.insert(&mut pager, "some/deep/path/f1", 1).unwrap();
+
+    let mut index = NameIndex::load(&mut pager, &index.first().clone()).unwrap();
+
+    index.insert(&mut pager, "some/deep/path/f2", 2).unwrap();
+    index.insert(&mut pager, "some/deep/path/f3", 3).unwrap();
+
+    let index = NameIndex::load(&mut pager, &index.first().clone()).unwrap();
+    assert_eq!(index.get("some/deep/path/f1"), Some(1));
+    assert_eq!(index.get("some/deep/path/f2"), Some(2));
+    assert_eq!(index.get("some/deep/path/f3"), Some(3));
+}
+
+#[test]
+fn load_invalid_used() {
+    let mut pager = setup_pager();
+    let index = NameIndex::create(&mut pager).unwrap();
+    let mut block = pager.read_buf_raw(index.first()).unwrap().to_vec();
+
+    block[7] = 57;
+    pager.write(index.first(), &block).unwrap();
+
+    let err = NameIndex::load(&mut pager, &index.first().clone()).unwrap_err();
+    assert!(matches!(err, Error::InvalidNameIndex));
+}
@@ -23,35 +23,100 @@
 #[cfg(test)]
 mod tests;
 
-use nuts_bytes::{Reader, Writer};
+use log::error;
+use nuts_bytes::Writer;
 use nuts_container::{backend::Backend, container::Container};
 use std::ops::{Deref, DerefMut};
+use std::{cmp, mem};
 
-use crate::error::ArchiveResult;
+use crate::codec::{BufReader, Codec};
+use crate::error::{ArchiveResult, Error};
+use crate::stats::IoStats;
+#[cfg(feature = "trace")]
+use crate::trace::Allocation;
+use crate::transform::Transforms;
+
+/// Read-after-write verification of written blocks.
+///
+/// See [`Archive::set_write_verification()`](crate::Archive::set_write_verification).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum WriteVerification {
+    /// Written blocks are not verified.
+    #[default]
+    Off,
+
+    /// Each written block is read back and compared.
+    All,
+
+    /// Every n-th written block is read back and compared. A value of `0`
+    /// is treated as `1`.
+    Sampled(u32),
+}
 
 pub struct Pager<B: Backend> {
     container: Container<B>,
     buf: Vec<u8>,
+    codec: Codec,
+    generation: u64,
+    verification: WriteVerification,
+    nwrites: u64,
+    stats: IoStats,
+    anchor: Option<B::Id>,
+    backup: Option<B::Id>,
+    transforms: Transforms,
+    #[cfg(feature = "trace")]
+    trace: Vec<Allocation<B>>,
 }
 
 impl<B: Backend> Pager<B> {
     pub fn new(container: Container<B>) -> Pager<B> {
         let buf = vec![0; container.block_size() as usize];
 
-        Pager { container, buf }
+        Pager {
+            container,
+            buf,
+            codec: Codec::current(),
+            generation: 0,
+            verification: WriteVerification::Off,
+            nwrites: 0,
+            stats: IoStats::default(),
+            anchor: None,
+            backup: None,
+            transforms: Transforms::default(),
+            #[cfg(feature = "trace")]
+            trace: vec![],
+        }
+    }
+
+    pub fn codec(&self) -> Codec {
+        self.codec
+    }
+
+    /// Sets the codec used by [`Pager::create_reader()`] and
+    /// [`Pager::create_writer()`].
+    pub fn set_codec(&mut self, codec: Codec) {
+        self.codec = codec;
     }
 
-    pub fn create_reader(&self) -> Reader<&[u8]> {
-        Reader::new(self.buf.as_slice())
+    /// Flags the given `features` in the codec.
+    ///
+    /// The revision word of the codec is written with the next flush of the
+    /// header.
+    pub fn add_features(&mut self, features: u16) {
+        self.codec = self.codec.with_features(features);
+    }
+
+    pub fn create_reader(&self) -> BufReader<'_> {
+        self.codec.reader(self.buf.as_slice())
     }
 
     pub fn create_writer(&mut self) -> Writer<&mut [u8]> {
         self.whiteout();
 
-        Writer::new(self.buf.as_mut_slice())
+        self.codec.writer(self.buf.as_mut_slice())
     }
 
-    pub fn read_buf(&mut self, id: &B::Id) -> ArchiveResult<Reader<&[u8]>, B> {
+    pub fn read_buf(&mut self, id: &B::Id) -> ArchiveResult<BufReader<'_>, B> {
         self.read_buf_raw(id)?;
         Ok(self.create_reader())
     }
@@ -60,13 +125,141 @@ impl<B: Backend> Pager<B> {
         let n = self.container.read(id, &mut self.buf)?;
 
         assert_eq!(n, self.buf.len());
+        self.stats.read(n);
 
         Ok(&self.buf)
     }
 
+    /// Reads the block `id` into `buf`.
+    ///
+    /// Shadows [`Container::read()`], so that the read is counted in the
+    /// [I/O statistics](Pager::stats).
+    pub fn read(&mut self, id: &B::Id, buf: &mut [u8]) -> ArchiveResult<usize, B> {
+        let n = self.container.read(id, buf)?;
+
+        self.stats.read(n);
+
+        Ok(n)
+    }
+
+    /// Returns the block, which holds the userdata of an anchored archive.
+    ///
+    /// Returns [`None`] if the userdata is stored in the userdata record of
+    /// the container.
+    pub fn anchor(&self) -> Option<&B::Id> {
+        self.anchor.as_ref()
+    }
+
+    pub fn set_anchor(&mut self, anchor: Option<B::Id>) {
+        self.anchor = anchor;
+    }
+
+    /// Returns the block, which holds the backup copy of the header.
+    pub fn backup(&self) -> Option<&B::Id> {
+        self.backup.as_ref()
+    }
+
+    pub fn set_backup(&mut self, backup: Option<B::Id>) {
+        self.backup = backup;
+    }
+
+    /// Returns the registered content transformations.
+    pub fn transforms(&self) -> &Transforms {
+        &self.transforms
+    }
+
+    pub fn transforms_mut(&mut self) -> &mut Transforms {
+        &mut self.transforms
+    }
+
     pub fn write_buf(&mut self, id: &B::Id) -> ArchiveResult<(), B> {
-        self.container.write(id, &self.buf)?;
-        Ok(())
+        let n = self.container.write(id, &self.buf)?;
+
+        self.stats.written(n);
+
+        let buf = mem::take(&mut self.buf);
+        let result = self.verify(id, &buf);
+
+        self.buf = buf;
+
+        result
+    }
+
+    /// Writes `buf` into the block `id`.
+    ///
+    /// Shadows [`Container::write()`], so that the block is verified
+    /// according to the configured [`WriteVerification`].
+    pub fn write(&mut self, id: &B::Id, buf: &[u8]) -> ArchiveResult<usize, B> {
+        let n = self.container.write(id, buf)?;
+
+        self.stats.written(n);
+        self.verify(id, &buf[..n])?;
+
+        Ok(n)
+    }
+
+    pub fn set_verification(&mut self, verification: WriteVerification) {
+        self.verification = verification;
+        self.nwrites = 0;
+    }
+
+    fn verify(&mut self, id: &B::Id, buf: &[u8]) -> ArchiveResult<(), B> {
+        let verify = match self.verification {
+            WriteVerification::Off => false,
+            WriteVerification::All => true,
+            WriteVerification::Sampled(n) => self.nwrites.is_multiple_of(cmp::max(n, 1) as u64),
+        };
+
+        self.nwrites += 1;
+
+        if !verify {
+            return Ok(());
+        }
+
+        let mut read = vec![0; self.container.block_size() as usize];
+
+        let n = self.container.read(id, &mut read)?;
+
+        self.stats.read(n);
+
+        if read[..buf.len()] == *buf && read[buf.len()..].iter().all(|n| *n == 0) {
+            Ok(())
+        } else {
+            error!("verification of block {} failed", id);
+            Err(Error::WriteMismatch(id.clone(), None))
+        }
+    }
+
+    /// Returns the I/O counters since the pager was created.
+    pub fn stats(&self) -> &IoStats {
+        &self.stats
+    }
+
+    pub fn stats_mut(&mut self) -> &mut IoStats {
+        &mut self.stats
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn inc_generation(&mut self) {
+        self.generation += 1;
+    }
+
+    #[cfg(feature = "trace")]
+    pub fn trace(&mut self, entry: usize, idx: usize, id: &B::Id) {
+        self.trace.push(Allocation::new(entry, idx, id.clone()));
+    }
+
+    #[cfg(feature = "trace")]
+    pub fn allocations(&self) -> &[Allocation<B>] {
+        &self.trace
+    }
+
+    #[cfg(feature = "trace")]
+    pub fn clear_allocations(&mut self) {
+        self.trace.clear();
     }
 
     fn whiteout(&mut self) {
@@ -20,7 +20,7 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
 // IN THE SOFTWARE.
 
-use crate::pager::Pager;
+use crate::pager::{Pager, WriteVerification};
 use crate::tests::{into_error, setup_container_with_bsize};
 
 #[test]
@@ -47,6 +47,31 @@ fn read() {
     assert!(matches!(err, nuts_bytes::TakeBytesError::Eof));
 }
 
+#[test]
+fn read_borrowed() {
+    let mut container = setup_container_with_bsize(32);
+    let id = container.aquire().unwrap();
+    let buf = [
+        0, 0, 0, 0, 0, 0, 0, 3, b'a', b'b', b'c', 0, 0, 0, 0, 0, 0, 0, 2, 1, 2, 0, 0, 0, 7,
+    ];
+
+    container.write(&id, &buf).unwrap();
+
+    let mut pager = Pager::new(container);
+    let mut reader = pager.read_buf(&id).unwrap();
+
+    assert_eq!(reader.read_str().unwrap(), "abc");
+    assert_eq!(reader.read_bytes().unwrap(), [1, 2]);
+    assert_eq!(reader.read::<u32>().unwrap(), 7);
+    assert_eq!(reader.remaining(), [0; 7]);
+
+    let err = reader.take(8).unwrap_err();
+    let err = into_error!(err, nuts_bytes::Error::TakeBytes);
+    assert!(matches!(err, nuts_bytes::TakeBytesError::Eof));
+    assert_eq!(reader.take(7).unwrap(), [0; 7]);
+    assert!(reader.remaining().is_empty());
+}
+
 #[test]
 fn write() {
     let mut pager = Pager::new(setup_container_with_bsize(12));
@@ -68,3 +93,45 @@ fn write() {
     assert_eq!(pager.read(&id, &mut buf).unwrap(), 12);
     assert_eq!(buf, [0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3]);
 }
+
+#[test]
+fn write_verified() {
+    let mut pager = Pager::new(setup_container_with_bsize(12));
+    let id = pager.aquire().unwrap();
+    let mut buf = [0; 12];
+
+    pager.set_verification(WriteVerification::All);
+
+    assert_eq!(pager.write(&id, &[1, 2, 3]).unwrap(), 3);
+    assert_eq!(pager.nwrites, 1);
+
+    assert_eq!(pager.read(&id, &mut buf).unwrap(), 12);
+    assert_eq!(buf, [1, 2, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+    pager.create_writer().write(&1u32).unwrap();
+    pager.write_buf(&id).unwrap();
+    assert_eq!(pager.nwrites, 2);
+
+    assert_eq!(pager.read(&id, &mut buf).unwrap(), 12);
+    assert_eq!(buf, [0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0]);
+}
+
+#[test]
+fn write_sampled() {
+    let mut pager = Pager::new(setup_container_with_bsize(12));
+    let id = pager.aquire().unwrap();
+
+    pager.set_verification(WriteVerification::Sampled(3));
+
+    for n in 0..7 {
+        pager.write(&id, &[n]).unwrap();
+    }
+
+    assert_eq!(pager.nwrites, 7);
+
+    pager.set_verification(WriteVerification::Sampled(0));
+    assert_eq!(pager.nwrites, 0);
+
+    pager.write(&id, &[1]).unwrap();
+    assert_eq!(pager.nwrites, 1);
+}
@@ -0,0 +1,464 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+//! Read-only types, which mirror the on-disk format of an archive.
+//!
+//! The types are meant for forensic and recovery tools, which parse the
+//! blocks of an archive without opening it. Each type is decoded from a
+//! single block and exposes the fields exactly as they are stored. Beside
+//! the encoding itself nothing is validated, not even the magic of the
+//! [`Header`].
+//!
+//! All numbers are stored in big-endian byte order, timestamps are stored
+//! as milliseconds since the unix epoch. A string or a list is stored as a
+//! `u64` length followed by its elements, an optional value as a `u8` tag
+//! (`0` for none, `1` for some) followed by the value.
+//!
+//! The archive is organized as follows:
+//!
+//! * The user data of the container starts with the magic `nuts-archive`,
+//!   followed by the id of the header block.
+//! * The header block contains the [`Header`] with the root of the block
+//!   tree. The tree maps the index of a block of the archive to its id: the
+//!   first indexes are stored directly in the header, the others are
+//!   reached through one, two or three levels of [`TreeNode`]s.
+//! * Each entry starts with an entry block, which contains an [`Inner`],
+//!   followed by [`Inner::name_blocks()`] continuation blocks with a long
+//!   name and the content blocks.
+
+#[cfg(test)]
+mod tests;
+
+use nuts_bytes::{FromBytes, Reader, TakeBytes};
+use nuts_container::backend::{Backend, BlockId};
+
+use crate::codec::MASK_REVISION;
+use crate::entry::{self, read_trailing};
+use crate::magic::Magic;
+use crate::tree::NUM_DIRECT;
+
+/// Flag of [`Inner::ext()`]: the entry has external references.
+pub const EXT_REFS: u16 = entry::EXT_REFS;
+
+/// Flag of [`Inner::ext()`]: the entry belongs to a section.
+pub const EXT_SECTION: u16 = entry::EXT_SECTION;
+
+/// Flag of [`Inner::ext()`]: the entry has user metadata.
+pub const EXT_METADATA: u16 = entry::EXT_METADATA;
+
+/// Flag of [`Inner::ext()`]: the entry has a checksum.
+pub const EXT_CHECKSUM: u16 = entry::EXT_CHECKSUM;
+
+/// Flag of [`Inner::ext()`]: the entry has previous names.
+pub const EXT_HISTORY: u16 = entry::EXT_HISTORY;
+
+/// Flag of [`Inner::ext()`]: the name is stored in continuation blocks.
+pub const EXT_LONGNAME: u16 = entry::EXT_LONGNAME;
+
+/// Flag of [`Inner::ext()`]: the content is compressed.
+pub const EXT_COMPRESSED: u16 = entry::EXT_COMPRESSED;
+
+/// Flag of [`Inner::ext()`]: the content is transformed.
+pub const EXT_TRANSFORMED: u16 = entry::EXT_TRANSFORMED;
+
+/// The header of the archive, stored in the header block.
+///
+/// The header is followed by the root of the block tree. Since revision 2
+/// the number of direct blocks is stored in front of the direct blocks,
+/// revision 1 always has 12 direct blocks. The high byte of the revision
+/// word flags the features used by the entries.
+#[derive(Debug)]
+pub struct Header<B: Backend> {
+    magic: Magic,
+    revision: u16,
+    created: i64,
+    modified: i64,
+    nfiles: u64,
+    direct: Vec<B::Id>,
+    indirect: B::Id,
+    d_indirect: B::Id,
+    t_indirect: B::Id,
+    nblocks: u64,
+}
+
+impl<B: Backend> Header<B> {
+    /// Decodes the header from the content of the header block.
+    pub fn from_block(block: &[u8]) -> Result<Header<B>, nuts_bytes::Error> {
+        let mut reader = Reader::new(block);
+
+        let magic = reader.read()?;
+        let revision: u16 = reader.read()?;
+        let created = reader.read()?;
+        let modified = reader.read()?;
+        let nfiles = reader.read()?;
+
+        let num_direct = if revision & MASK_REVISION >= 2 {
+            reader.read::<u32>()? as usize
+        } else {
+            NUM_DIRECT as usize
+        };
+
+        if num_direct > block.len() {
+            return Err(nuts_bytes::Error::Custom(
+                format!("invalid number of direct blocks: {}", num_direct).into(),
+            ));
+        }
+
+        let direct = (0..num_direct)
+            .map(|_| reader.read())
+            .collect::<Result<_, _>>()?;
+
+        Ok(Header {
+            magic,
+            revision,
+            created,
+            modified,
+            nfiles,
+            direct,
+            indirect: reader.read()?,
+            d_indirect: reader.read()?,
+            t_indirect: reader.read()?,
+            nblocks: reader.read()?,
+        })
+    }
+
+    /// The magic, `nuts-archive`.
+    pub fn magic(&self) -> &[u8] {
+        &self.magic
+    }
+
+    /// The revision of the on-disk format.
+    pub fn revision(&self) -> u16 {
+        self.revision & MASK_REVISION
+    }
+
+    /// The flags of the features used by the entries of the archive.
+    pub fn features(&self) -> u16 {
+        self.revision & !MASK_REVISION
+    }
+
+    /// Creation time of the archive in milliseconds.
+    pub fn created(&self) -> i64 {
+        self.created
+    }
+
+    /// Time of the last modification in milliseconds.
+    pub fn modified(&self) -> i64 {
+        self.modified
+    }
+
+    /// Number of visible entries.
+    pub fn nfiles(&self) -> u64 {
+        self.nfiles
+    }
+
+    /// Ids of the first blocks of the archive.
+    pub fn direct(&self) -> &[B::Id] {
+        &self.direct
+    }
+
+    /// Id of the [`TreeNode`], which refers to the next blocks.
+    pub fn indirect(&self) -> &B::Id {
+        &self.indirect
+    }
+
+    /// Id of the [`TreeNode`], which refers to [`TreeNode`]s of the next
+    /// blocks.
+    pub fn d_indirect(&self) -> &B::Id {
+        &self.d_indirect
+    }
+
+    /// Id of the [`TreeNode`] of the third level.
+    pub fn t_indirect(&self) -> &B::Id {
+        &self.t_indirect
+    }
+
+    /// Number of blocks of the archive.
+    pub fn nblocks(&self) -> u64 {
+        self.nblocks
+    }
+}
+
+/// A node of the block tree.
+///
+/// A node fills a whole block with ids, unused slots contain a null id.
+#[derive(Debug)]
+pub struct TreeNode<B: Backend>(Vec<B::Id>);
+
+impl<B: Backend> TreeNode<B> {
+    /// Decodes the node from the content of a block.
+    pub fn from_block(block: &[u8]) -> Result<TreeNode<B>, nuts_bytes::Error> {
+        let mut reader = Reader::new(block);
+        let ids = (0..block.len() / B::Id::size())
+            .map(|_| reader.read())
+            .collect::<Result<_, _>>()?;
+
+        Ok(TreeNode(ids))
+    }
+
+    /// The ids stored in the node.
+    pub fn ids(&self) -> &[B::Id] {
+        &self.0
+    }
+}
+
+/// An entry, stored in the entry block.
+///
+/// The fields behind [`Inner::children()`] were added later, they are
+/// missing in the entry blocks of old archives. The fields behind
+/// [`Inner::gid()`] are only present, if the corresponding flag is set in
+/// [`Inner::ext()`].
+#[derive(Debug)]
+pub struct Inner {
+    name: String,
+    mode: u16,
+    appended: i64,
+    created: i64,
+    changed: i64,
+    modified: i64,
+    size: u64,
+    children: u64,
+    ext: u16,
+    uid: Option<u32>,
+    gid: Option<u32>,
+    refs: u32,
+    section: Option<String>,
+    metadata: Vec<(String, String)>,
+    checksum: Option<[u8; 32]>,
+    previous_names: Vec<String>,
+    name_blocks: u32,
+    compression: u8,
+    level: Option<i32>,
+    raw_size: u64,
+    transforms: Vec<String>,
+}
+
+impl Inner {
+    /// Decodes the entry from the content of an entry block.
+    pub fn from_block(block: &[u8]) -> Result<Inner, nuts_bytes::Error> {
+        Reader::new(block).read()
+    }
+
+    /// The name, empty if [`EXT_LONGNAME`] is set.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The first word of the mode: type, access rights and flags.
+    pub fn mode(&self) -> u16 {
+        self.mode
+    }
+
+    /// Time in milliseconds, when the entry was appended.
+    pub fn appended(&self) -> i64 {
+        self.appended
+    }
+
+    /// Creation time in milliseconds.
+    pub fn created(&self) -> i64 {
+        self.created
+    }
+
+    /// Time of the last status change in milliseconds.
+    pub fn changed(&self) -> i64 {
+        self.changed
+    }
+
+    /// Time of the last modification in milliseconds.
+    pub fn modified(&self) -> i64 {
+        self.modified
+    }
+
+    /// Number of stored content bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Number of entries of a directory.
+    pub fn children(&self) -> u64 {
+        self.children
+    }
+
+    /// The extension word: the special unix bits of the mode combined with
+    /// the `EXT_*` flags.
+    pub fn ext(&self) -> u16 {
+        self.ext
+    }
+
+    /// The owning user.
+    pub fn uid(&self) -> Option<u32> {
+        self.uid
+    }
+
+    /// The owning group.
+    pub fn gid(&self) -> Option<u32> {
+        self.gid
+    }
+
+    /// Number of external references, see [`EXT_REFS`].
+    pub fn refs(&self) -> u32 {
+        self.refs
+    }
+
+    /// The section, see [`EXT_SECTION`].
+    pub fn section(&self) -> Option<&str> {
+        self.section.as_deref()
+    }
+
+    /// The user metadata in stored order, see [`EXT_METADATA`].
+    pub fn metadata(&self) -> &[(String, String)] {
+        &self.metadata
+    }
+
+    /// The SHA-256 digest of the content, see [`EXT_CHECKSUM`].
+    pub fn checksum(&self) -> Option<&[u8; 32]> {
+        self.checksum.as_ref()
+    }
+
+    /// The previous names, see [`EXT_HISTORY`].
+    pub fn previous_names(&self) -> &[String] {
+        &self.previous_names
+    }
+
+    /// Number of continuation blocks of the name, see [`EXT_LONGNAME`].
+    pub fn name_blocks(&self) -> u32 {
+        self.name_blocks
+    }
+
+    /// The compression, `0` none, `1` deflate, `2` zstd, see
+    /// [`EXT_COMPRESSED`].
+    pub fn compression(&self) -> u8 {
+        self.compression
+    }
+
+    /// The compression level, stored for zstd only.
+    pub fn level(&self) -> Option<i32> {
+        self.level
+    }
+
+    /// Number of content bytes before compression and transformation, see
+    /// [`EXT_COMPRESSED`] and [`EXT_TRANSFORMED`].
+    pub fn raw_size(&self) -> u64 {
+        self.raw_size
+    }
+
+    /// The names of the transformations, see [`EXT_TRANSFORMED`].
+    pub fn transforms(&self) -> &[String] {
+        &self.transforms
+    }
+}
+
+impl FromBytes for Inner {
+    fn from_bytes<TB: TakeBytes>(source: &mut TB) -> Result<Inner, nuts_bytes::Error> {
+        let name = String::from_bytes(source)?;
+        let mode = u16::from_bytes(source)?;
+        let appended = i64::from_bytes(source)?;
+        let created = i64::from_bytes(source)?;
+        let changed = i64::from_bytes(source)?;
+        let modified = i64::from_bytes(source)?;
+        let size = u64::from_bytes(source)?;
+        let children = u64::from_bytes(source)?;
+        let ext: u16 = read_trailing(source)?;
+        let uid = read_trailing(source)?;
+        let gid = read_trailing(source)?;
+        let refs = if ext & EXT_REFS != 0 {
+            u32::from_bytes(source)?
+        } else {
+            0
+        };
+        let section = if ext & EXT_SECTION != 0 {
+            Some(String::from_bytes(source)?)
+        } else {
+            None
+        };
+        let mut metadata = vec![];
+
+        if ext & EXT_METADATA != 0 {
+            let count = u64::from_bytes(source)?;
+
+            for _ in 0..count {
+                metadata.push((String::from_bytes(source)?, String::from_bytes(source)?));
+            }
+        }
+
+        let checksum = if ext & EXT_CHECKSUM != 0 {
+            Some(<[u8; 32]>::from_bytes(source)?)
+        } else {
+            None
+        };
+        let previous_names = if ext & EXT_HISTORY != 0 {
+            Vec::<String>::from_bytes(source)?
+        } else {
+            vec![]
+        };
+        let name_blocks = if ext & EXT_LONGNAME != 0 {
+            u32::from_bytes(source)?
+        } else {
+            0
+        };
+        let (compression, level, raw_size) = if ext & EXT_COMPRESSED != 0 {
+            let compression = u8::from_bytes(source)?;
+            let level = match compression {
+                2 => Some(i32::from_bytes(source)?),
+                _ => None,
+            };
+
+            (compression, level, u64::from_bytes(source)?)
+        } else {
+            (0, None, 0)
+        };
+        let (transforms, raw_size) = if ext & EXT_TRANSFORMED != 0 {
+            let transforms = Vec::<String>::from_bytes(source)?;
+
+            if ext & EXT_COMPRESSED != 0 {
+                (transforms, raw_size)
+            } else {
+                (transforms, u64::from_bytes(source)?)
+            }
+        } else {
+            (vec![], raw_size)
+        };
+
+        Ok(Inner {
+            name,
+            mode,
+            appended,
+            created,
+            changed,
+            modified,
+            size,
+            children,
+            ext,
+            uid,
+            gid,
+            refs,
+            section,
+            metadata,
+            checksum,
+            previous_names,
+            name_blocks,
+            compression,
+            level,
+            raw_size,
+            transforms,
+        })
+    }
+}
@@ -0,0 +1,190 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use nuts_container::backend::BlockId;
+use nuts_container::memory::MemoryBackend;
+use std::io;
+
+use crate::raw::{
+    Header, Inner, TreeNode, EXT_CHECKSUM, EXT_METADATA, EXT_SECTION, EXT_TRANSFORMED,
+};
+use crate::tests::setup_container;
+use crate::tree::NUM_DIRECT;
+use crate::{Archive, Compression, Transform};
+
+#[test]
+fn header() {
+    let mut archive = Archive::create(setup_container(), false).unwrap();
+
+    // enough content blocks for an indirect node
+    let mut entry = archive.append_file("f").build().unwrap();
+    entry.write_all(&[1; 20 * 512]).unwrap();
+
+    let header_id = archive.header_id;
+    let id12 = *archive
+        .tree
+        .lookup(&mut archive.pager, 12)
+        .unwrap()
+        .unwrap();
+    let mut container = archive.into_container();
+    let mut buf = vec![0; 512];
+
+    container.read(&header_id, &mut buf).unwrap();
+
+    let header = Header::<MemoryBackend>::from_block(&buf).unwrap();
+
+    assert_eq!(header.magic(), b"nuts-archive");
+    assert_eq!(header.revision(), 1);
+    assert!(header.created() <= header.modified());
+    assert_eq!(header.nfiles(), 1);
+    assert_eq!(header.direct().len(), NUM_DIRECT as usize);
+    assert!(header.direct().iter().all(|id| !id.is_null()));
+    assert!(header.d_indirect().is_null());
+    assert!(header.t_indirect().is_null());
+    assert_eq!(header.nblocks(), 21);
+
+    container.read(header.indirect(), &mut buf).unwrap();
+
+    let node = TreeNode::<MemoryBackend>::from_block(&buf).unwrap();
+
+    assert_eq!(node.ids().len(), 128);
+    assert_eq!(node.ids()[0], id12);
+    assert_eq!(node.ids().iter().filter(|id| !id.is_null()).count(), 9);
+}
+
+#[test]
+fn header_num_direct() {
+    let container = setup_container();
+    let mut archive = Archive::create_with_num_direct(container, false, 2).unwrap();
+
+    archive.append_file("f").build().unwrap();
+
+    let header_id = archive.header_id;
+    let mut container = archive.into_container();
+    let mut buf = vec![0; 512];
+
+    container.read(&header_id, &mut buf).unwrap();
+
+    let header = Header::<MemoryBackend>::from_block(&buf).unwrap();
+
+    assert_eq!(header.revision(), 2);
+    assert_eq!(header.direct().len(), 2);
+    assert!(!header.direct()[0].is_null());
+    assert!(header.direct()[1].is_null());
+    assert!(header.indirect().is_null());
+    assert_eq!(header.nblocks(), 1);
+}
+
+#[test]
+fn inner() {
+    let mut archive = Archive::create(setup_container(), false).unwrap();
+
+    archive.set_section(Some("s"));
+
+    let mut builder = archive.append_file("f");
+    builder.set_hashed(true);
+    builder.set_compression(Compression::Deflate);
+    builder.set_metadata("k", "v");
+
+    let mut entry = builder.build().unwrap();
+    entry.write_all(b"hello").unwrap();
+
+    let id = *archive.tree.lookup(&mut archive.pager, 0).unwrap().unwrap();
+    let buf = archive.pager.read_buf_raw(&id).unwrap().to_vec();
+    let inner = Inner::from_block(&buf).unwrap();
+
+    assert_eq!(inner.name(), "f");
+    assert_eq!(inner.mode() & 0x1E00, 0);
+    assert!(inner.appended() > 0);
+    assert_eq!(inner.raw_size(), 5);
+    assert!(inner.size() > 0);
+    assert_eq!(inner.children(), 0);
+    assert_ne!(inner.ext() & EXT_SECTION, 0);
+    assert_ne!(inner.ext() & EXT_METADATA, 0);
+    assert_ne!(inner.ext() & EXT_CHECKSUM, 0);
+    assert_eq!(inner.uid(), None);
+    assert_eq!(inner.refs(), 0);
+    assert_eq!(inner.section(), Some("s"));
+    assert_eq!(inner.metadata(), [("k".to_string(), "v".to_string())]);
+    assert!(inner.checksum().is_some());
+    assert!(inner.previous_names().is_empty());
+    assert_eq!(inner.name_blocks(), 0);
+    assert_eq!(inner.compression(), 1);
+    assert_eq!(inner.level(), None);
+    assert!(inner.transforms().is_empty());
+}
+
+#[test]
+fn inner_transforms() {
+    struct Identity;
+
+    impl Transform for Identity {
+        fn name(&self) -> &str {
+            "identity"
+        }
+
+        fn encode(&self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+            Ok(chunk.to_vec())
+        }
+
+        fn decode(&self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+            Ok(chunk.to_vec())
+        }
+    }
+
+    let mut archive = Archive::create(setup_container(), false).unwrap();
+
+    archive.register_transform(Identity);
+
+    let mut builder = archive.append_file("f");
+    builder.add_transform("identity");
+
+    let mut entry = builder.build().unwrap();
+    entry.write_all(b"hello").unwrap();
+
+    let id = *archive.tree.lookup(&mut archive.pager, 0).unwrap().unwrap();
+    let buf = archive.pager.read_buf_raw(&id).unwrap().to_vec();
+    let inner = Inner::from_block(&buf).unwrap();
+
+    // the chunk is stored with its length
+    assert_eq!(inner.size(), 9);
+    assert_eq!(inner.raw_size(), 5);
+    assert_ne!(inner.ext() & EXT_TRANSFORMED, 0);
+    assert_eq!(inner.compression(), 0);
+    assert_eq!(inner.transforms(), ["identity"]);
+}
+
+#[test]
+fn inner_old() {
+    // an entry block without the trailing fields
+    let mut buf = vec![0, 0, 0, 0, 0, 0, 0, 1, b'x', 0x01, 0xff];
+
+    buf.extend_from_slice(&[0; 48]);
+
+    let inner = Inner::from_block(&buf).unwrap();
+
+    assert_eq!(inner.name(), "x");
+    assert_eq!(inner.mode(), 0x01ff);
+    assert_eq!(inner.ext(), 0);
+    assert_eq!(inner.uid(), None);
+    assert_eq!(inner.gid(), None);
+}
@@ -0,0 +1,141 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use nuts_container::backend::Backend;
+use nuts_container::container::Container;
+use std::io::Write;
+use std::path::Path;
+
+use crate::error::{ArchiveResult, RewriteError};
+use crate::{Archive, Entry, ExtractPlan, Info, JobRecord, Manifest, Transform};
+
+/// Read-only access to an archive.
+///
+/// An `ArchiveReader` provides all the methods of an [`Archive`], which do
+/// not modify the archive. Use it in signatures to make clear that the
+/// archive is only read. The reader is created by [`ArchiveReader::open()`]
+/// or [`Archive::into_reader()`] and can be turned back into a writable
+/// archive with [`ArchiveReader::into_writer()`].
+pub struct ArchiveReader<B: Backend>(Archive<B>);
+
+impl<B: Backend> ArchiveReader<B> {
+    pub(crate) fn new(archive: Archive<B>) -> ArchiveReader<B> {
+        ArchiveReader(archive)
+    }
+
+    /// Opens an archive from `container` for reading.
+    ///
+    /// See [`Archive::open()`] for details.
+    pub fn open(container: Container<B>) -> ArchiveResult<ArchiveReader<B>, B> {
+        Archive::open(container).map(ArchiveReader)
+    }
+
+    /// Fetches statistics/information from the archive.
+    pub fn info(&self) -> Info {
+        self.0.info()
+    }
+
+    /// Registers a [`Transform`] of the content of file entries.
+    ///
+    /// See [`Archive::register_transform()`] for details.
+    pub fn register_transform<T: Transform + 'static>(&mut self, transform: T) {
+        self.0.register_transform(transform)
+    }
+
+    /// Returns the first entry in the archive.
+    ///
+    /// See [`Archive::first()`] for details.
+    pub fn first<'a>(&'a mut self) -> Option<ArchiveResult<Entry<'a, B>, B>> {
+        self.0.first()
+    }
+
+    /// Searches for an entry with the given `name`.
+    ///
+    /// See [`Archive::lookup()`] for details.
+    pub fn lookup<'a, N: AsRef<str>>(
+        &'a mut self,
+        name: N,
+    ) -> Option<ArchiveResult<Entry<'a, B>, B>> {
+        self.0.lookup(name)
+    }
+
+    /// Exports the [`Index`](crate::Index) of the archive into `writer`.
+    ///
+    /// See [`Archive::export_index()`] for details.
+    pub fn export_index<W: Write>(&mut self, writer: W) -> ArchiveResult<(), B> {
+        self.0.export_index(writer)
+    }
+
+    /// Creates a preview of extracting entries into the directory `dest`.
+    ///
+    /// See [`Archive::plan_extract()`] for details.
+    pub fn plan_extract<D: AsRef<Path>, P: AsRef<str>>(
+        &mut self,
+        dest: D,
+        patterns: &[P],
+    ) -> ArchiveResult<ExtractPlan, B> {
+        self.0.plan_extract(dest, patterns)
+    }
+
+    /// Returns all job records stored in the archive.
+    pub fn jobs(&mut self) -> ArchiveResult<Vec<JobRecord>, B> {
+        self.0.jobs()
+    }
+
+    /// Creates a [`Manifest`] of the archive.
+    ///
+    /// See [`Archive::manifest()`] for details.
+    pub fn manifest(&mut self) -> ArchiveResult<Manifest, B> {
+        self.0.manifest()
+    }
+
+    /// Copies all entries of the archive into a new archive created in
+    /// `container`.
+    ///
+    /// See [`Archive::rewrite_to()`] for details.
+    pub fn rewrite_to<T: Backend>(
+        &mut self,
+        container: Container<T>,
+    ) -> Result<Archive<T>, RewriteError<B, T>> {
+        self.0.rewrite_to(container)
+    }
+
+    /// Converts the reader into a writable [`Archive`].
+    pub fn into_writer(self) -> Archive<B> {
+        self.0
+    }
+
+    /// Consumes this `ArchiveReader`, returning the underlying
+    /// [`Container`].
+    pub fn into_container(self) -> Container<B> {
+        self.0.into_container()
+    }
+}
+
+impl<B: Backend> AsRef<Container<B>> for ArchiveReader<B> {
+    fn as_ref(&self) -> &Container<B> {
+        self.0.as_ref()
+    }
+}
@@ -0,0 +1,63 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use crate::tests::setup_container_with_bsize;
+use crate::{Archive, ArchiveReader};
+
+#[test]
+fn into_reader() {
+    let container = setup_container_with_bsize(106);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.append_file("f1").build().unwrap();
+
+    let mut reader = archive.into_reader();
+
+    assert_eq!(reader.info().files, 1);
+    assert_eq!(reader.first().unwrap().unwrap().name(), "f1");
+    assert!(reader.lookup("f2").is_none());
+}
+
+#[test]
+fn open() {
+    let container = setup_container_with_bsize(106);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.append_file("f1").build().unwrap();
+
+    let mut reader = ArchiveReader::open(archive.into_container()).unwrap();
+
+    assert_eq!(reader.info().files, 1);
+    assert_eq!(reader.lookup("f1").unwrap().unwrap().name(), "f1");
+}
+
+#[test]
+fn into_writer() {
+    let container = setup_container_with_bsize(106);
+    let archive = Archive::create(container, false).unwrap();
+
+    let mut writer = archive.into_reader().into_writer();
+
+    writer.append_file("f1").build().unwrap();
+
+    assert_eq!(writer.into_reader().info().files, 1);
+}
@@ -0,0 +1,123 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use log::debug;
+use nuts_container::backend::{Backend, BlockId};
+
+use crate::entry::immut::InnerEntry;
+use crate::error::{ArchiveResult, Error};
+use crate::{flush_header, Archive};
+
+/// Blocks of an entry in the tree.
+struct Span {
+    name: String,
+    internal: bool,
+    start: usize,
+    end: usize,
+}
+
+fn spans<B: Backend>(archive: &mut Archive<B>) -> ArchiveResult<Vec<Span>, B> {
+    let mut spans = vec![];
+    let mut entry_opt = InnerEntry::first(&mut archive.pager, &mut archive.tree);
+
+    while let Some(result) = entry_opt {
+        let entry = result?;
+
+        spans.push(Span {
+            name: entry.inner().name.clone(),
+            internal: entry.inner().mode.is_internal(),
+            start: entry.idx(),
+            end: entry.end_idx(),
+        });
+
+        entry_opt = entry.next();
+    }
+
+    Ok(spans)
+}
+
+/// Moves the entries with the given `names` to the front of `archive`.
+///
+/// Each name selects the first entry with the name, which was not selected
+/// before. All other entries keep their order behind the selected entries.
+pub(crate) fn reorder<B: Backend>(
+    archive: &mut Archive<B>,
+    names: &[&str],
+) -> ArchiveResult<(), B> {
+    archive.header.check_mutable()?;
+
+    let spans = spans(archive)?;
+    let mut selected = vec![false; spans.len()];
+    let mut order = Vec::with_capacity(spans.len());
+
+    for name in names {
+        let pos = spans
+            .iter()
+            .enumerate()
+            .position(|(n, span)| !selected[n] && !span.internal && span.name == *name)
+            .ok_or_else(|| Error::NotFound(name.to_string()))?;
+
+        selected[pos] = true;
+        order.push(pos);
+    }
+
+    order.extend((0..spans.len()).filter(|n| !selected[*n]));
+
+    if order.iter().enumerate().all(|(n, pos)| n == *pos) {
+        debug!("reorder: order not changed");
+        return Ok(());
+    }
+
+    let nblocks = archive.tree.nblocks() as usize;
+    let mut ids = vec![];
+
+    for span in order.iter().map(|pos| &spans[*pos]) {
+        for idx in span.start..span.end {
+            match archive.tree.lookup(&mut archive.pager, idx) {
+                Some(result) => ids.push(result?.clone()),
+                None if idx < nblocks => ids.push(B::Id::null()),
+                None => return Err(Error::NotFound(span.name.clone())),
+            }
+        }
+    }
+
+    // positions of entries change, the savepoints cannot be restored anymore
+    archive.savepoints.clear();
+
+    archive.tree.replace(&mut archive.pager, 0, ids)?;
+    archive.header.touch();
+
+    flush_header(
+        &mut archive.pager,
+        &archive.header_id,
+        &archive.header,
+        &mut archive.tree,
+    )?;
+
+    if archive.tree.has_names() {
+        archive.build_index()?;
+    }
+
+    debug!("reorder: {} entries moved to the front", names.len());
+
+    Ok(())
+}
@@ -0,0 +1,350 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use log::{debug, warn};
+use nuts_container::backend::Backend;
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::entry::immut::{visible_names, InnerEntry};
+use crate::entry::r#mut::dec_parent_children;
+use crate::entry::Inner;
+use crate::error::{ArchiveResult, Error};
+use crate::{flush_header, release_blocks, Archive, Dependents};
+
+/// A fix applied by [`Archive::check_and_repair()`](crate::Archive::check_and_repair).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Fix {
+    /// The given number of blocks were still referenced behind the last
+    /// block of the tree. The references were cleared and the blocks
+    /// released.
+    StaleBlocks(u64),
+
+    /// The last entry was only partially written and discarded together
+    /// with the given number of blocks. The name is [`None`], if the
+    /// metadata of the entry could not be loaded.
+    PartialEntry(Option<String>, u64),
+
+    /// The given number of blocks behind the last entry did not belong to
+    /// any entry and were released.
+    Uncovered(u64),
+
+    /// The header counted the first value of entries, it was corrected to
+    /// the second value.
+    FileCount(u64, u64),
+
+    /// The name index had the given number of dangling or missing records
+    /// and was rebuilt.
+    NameIndex(u64),
+
+    /// The [multipart upload](crate::MultipartEntry) of the entry with the
+    /// given name was never finished. The entry was discarded together with
+    /// the given number of blocks.
+    PendingEntry(String, u64),
+}
+
+impl fmt::Display for Fix {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::StaleBlocks(n) => write!(fmt, "released {} stale blocks", n),
+            Self::PartialEntry(Some(name), n) => write!(
+                fmt,
+                "discarded partially written {} with {} blocks",
+                name, n
+            ),
+            Self::PartialEntry(None, n) => {
+                write!(fmt, "discarded unreadable last entry with {} blocks", n)
+            }
+            Self::Uncovered(n) => write!(fmt, "released {} blocks behind the last entry", n),
+            Self::FileCount(old, new) => {
+                write!(
+                    fmt,
+                    "corrected the number of entries from {} to {}",
+                    old, new
+                )
+            }
+            Self::NameIndex(n) => write!(fmt, "rebuilt the name index, {} records were wrong", n),
+            Self::PendingEntry(name, n) => write!(
+                fmt,
+                "discarded unfinished upload {} with {} blocks",
+                name, n
+            ),
+        }
+    }
+}
+
+/// The result of [`Archive::check_and_repair()`](crate::Archive::check_and_repair).
+#[derive(Debug, Default)]
+pub struct RepairReport {
+    /// The fixes applied to the archive
+    pub fixes: Vec<Fix>,
+
+    /// Tree index of an entry, whose metadata cannot be loaded. This is an
+    /// entry in the middle of the archive or an entry, which was written
+    /// completely but cannot be decoded, e.g. because it uses a feature
+    /// unknown to this version. The entries from there on are left alone,
+    /// they are not checked.
+    pub damaged: Option<u64>,
+}
+
+impl RepairReport {
+    /// Returns `true`, if the archive was consistent and nothing was fixed.
+    pub fn is_clean(&self) -> bool {
+        self.fixes.is_empty() && self.damaged.is_none()
+    }
+}
+
+/// The result of walking through the entries.
+struct Walk {
+    /// Tree index behind the last complete entry
+    end: usize,
+
+    /// Number of complete entries, which are not internal
+    nfiles: u64,
+
+    /// The incomplete last entry, if any. The name is [`None`], if the entry
+    /// cannot be loaded.
+    partial: Option<Option<String>>,
+
+    /// Tree index of an entry, which cannot be loaded and is followed by
+    /// other blocks or is not torn.
+    damaged: Option<usize>,
+
+    /// Positions (`idx..end_idx`) and names of the entries, whose multipart
+    /// upload was never finished.
+    pending: Vec<(usize, usize, String)>,
+}
+
+/// Returns the number of blocks in front of the content of the entry at
+/// `idx`, which cannot be loaded.
+///
+/// If only the name in the continuation blocks is broken, the entry block
+/// tells the number of blocks. Otherwise the entry occupies at least its
+/// entry block.
+fn head_blocks<B: Backend>(archive: &mut Archive<B>, idx: usize) -> ArchiveResult<u64, B> {
+    let id = match archive.tree.lookup(&mut archive.pager, idx) {
+        Some(result) => result?.clone(),
+        None => return Ok(1),
+    };
+
+    match Inner::load(&mut archive.pager, &id) {
+        Ok(inner) => Ok(inner.head_blocks() as u64),
+        Err(err @ Error::Container(_)) => Err(err),
+        Err(_) => Ok(1),
+    }
+}
+
+/// Tests whether `err` is caused by a block, which was not (completely)
+/// written.
+///
+/// The metadata of such an entry ends prematurely or its name is cut off.
+/// Any other error is raised by an entry, which was written completely but
+/// cannot be decoded, e.g. because it uses a codec unknown to this version.
+fn is_torn<B: Backend>(err: &Error<B>) -> bool {
+    matches!(
+        err,
+        Error::InvalidBlockSize
+            | Error::Bytes(nuts_bytes::Error::TakeBytes(_))
+            | Error::Bytes(nuts_bytes::Error::InvalidString(_))
+            | Error::UnexpectedEof
+    )
+}
+
+/// Walks through the entries until the end or the first incomplete entry.
+///
+/// Errors of the container are not caused by a partial write, they are
+/// passed to the caller. An entry, which cannot be loaded, is only
+/// considered partially written, if its blocks are [torn](is_torn) and no
+/// other blocks than its entry block and the continuation blocks of its
+/// name follow. Otherwise it is damaged, the size of the entry and the
+/// entries behind it are unknown.
+fn walk<B: Backend>(archive: &mut Archive<B>) -> ArchiveResult<Walk, B> {
+    let nblocks = archive.tree.nblocks();
+    let mut walk = Walk {
+        end: 0,
+        nfiles: 0,
+        partial: None,
+        damaged: None,
+        pending: vec![],
+    };
+    let mut unreadable = None;
+    let mut entry_opt = InnerEntry::first(&mut archive.pager, &mut archive.tree);
+
+    while let Some(result) = entry_opt {
+        let entry = match result {
+            Ok(entry) => entry,
+            Err(err @ Error::Container(_)) => return Err(err),
+            Err(err) => {
+                warn!("repair: invalid entry at {}: {}", walk.end, err);
+                unreadable = Some(is_torn(&err));
+                break;
+            }
+        };
+
+        if entry.end_idx() as u64 > nblocks {
+            let name = entry.inner().name.clone();
+
+            warn!("repair: {} is incomplete", name);
+            walk.partial = Some(Some(name));
+            break;
+        }
+
+        if entry.inner().mode.is_pending() {
+            let name = entry.inner().name.clone();
+
+            warn!("repair: upload of {} was not finished", name);
+            walk.pending.push((entry.idx(), entry.end_idx(), name));
+        } else if !entry.inner().mode.is_internal() {
+            walk.nfiles += 1;
+        }
+
+        walk.end = entry.end_idx();
+        entry_opt = entry.next();
+    }
+
+    if let Some(torn) = unreadable {
+        if torn && walk.end as u64 + head_blocks(archive, walk.end)? >= nblocks {
+            walk.partial = Some(None);
+        } else {
+            warn!("repair: damaged entry at {}", walk.end);
+            walk.damaged = Some(walk.end);
+        }
+    }
+
+    Ok(walk)
+}
+
+/// Counts the records of the name index, which do not match the entries.
+fn index_mismatches<B: Backend>(archive: &mut Archive<B>) -> ArchiveResult<u64, B> {
+    let len = match archive.tree.names_mut(&mut archive.pager)? {
+        Some(names) => names.len(),
+        None => return Ok(0),
+    };
+    let visible = visible_names(&mut archive.pager, &mut archive.tree, 0)?;
+    let mut seen = HashSet::new();
+    let mut mismatches = 0;
+
+    if let Some(names) = archive.tree.names() {
+        for (idx, name) in visible {
+            if seen.insert(name.clone()) && names.get(&name) != Some(idx as u64) {
+                mismatches += 1;
+            }
+        }
+    }
+
+    // records of names, which do not exist anymore
+    mismatches += len.saturating_sub(seen.len()) as u64;
+
+    Ok(mismatches)
+}
+
+pub(crate) fn check_and_repair<B: Backend>(
+    archive: &mut Archive<B>,
+) -> ArchiveResult<RepairReport, B> {
+    archive.header.check_mutable()?;
+
+    let mut report = RepairReport::default();
+    let mut released = archive.tree.clear_stale(&mut archive.pager)?;
+
+    if !released.is_empty() {
+        report.fixes.push(Fix::StaleBlocks(released.len() as u64));
+    }
+
+    let walk = walk(archive)?;
+    let nblocks = archive.tree.nblocks() as usize;
+
+    // nothing behind a damaged entry is touched
+    report.damaged = walk.damaged.map(|idx| idx as u64);
+
+    if walk.damaged.is_none() && walk.end < nblocks {
+        let count = nblocks - walk.end;
+        let mut removed = archive.tree.remove(&mut archive.pager, walk.end, count)?;
+
+        released.append(&mut removed);
+
+        report.fixes.push(match walk.partial.clone() {
+            Some(name) => Fix::PartialEntry(name, count as u64),
+            None => Fix::Uncovered(count as u64),
+        });
+    }
+
+    if walk.damaged.is_none() && walk.nfiles != archive.header.nfiles {
+        report
+            .fixes
+            .push(Fix::FileCount(archive.header.nfiles, walk.nfiles));
+        archive.header.nfiles = walk.nfiles;
+    }
+
+    if !report.fixes.is_empty() {
+        archive.savepoints.clear();
+        archive.header.touch();
+
+        flush_header(
+            &mut archive.pager,
+            &archive.header_id,
+            &archive.header,
+            &mut archive.tree,
+        )?;
+
+        release_blocks(&mut archive.pager, &mut archive.tree, released)?;
+
+        if let Some(Some(name)) = walk.partial.as_ref() {
+            dec_parent_children(&mut archive.pager, &mut archive.tree, name)?;
+        }
+    }
+
+    if walk.damaged.is_none() && !walk.pending.is_empty() {
+        // the entries are moved, the savepoints cannot be restored anymore
+        archive.savepoints.clear();
+
+        // back to front, the positions of the entries in front do not change
+        for (idx, end_idx, name) in walk.pending.iter().rev() {
+            let deps = Dependents::default();
+
+            archive.remove_at(name, *idx, *end_idx, deps, false)?;
+        }
+
+        for (idx, end_idx, name) in walk.pending {
+            report
+                .fixes
+                .push(Fix::PendingEntry(name, (end_idx - idx) as u64));
+        }
+    }
+
+    let mismatches = if walk.damaged.is_none() {
+        index_mismatches(archive)?
+    } else {
+        0
+    };
+
+    if mismatches > 0 {
+        archive.build_index()?;
+        report.fixes.push(Fix::NameIndex(mismatches));
+    }
+
+    debug!("repair: {} fixes", report.fixes.len());
+
+    Ok(report)
+}
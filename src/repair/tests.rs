@@ -0,0 +1,372 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use nuts_container::memory::MemoryBackend;
+
+use crate::compression::Compression;
+use crate::entry::Inner;
+use crate::error::Error;
+use crate::repair::Fix;
+use crate::tests::setup_container;
+use crate::Archive;
+
+fn setup_archive() -> Archive<MemoryBackend> {
+    let mut archive = Archive::create(setup_container(), false).unwrap();
+
+    archive.append_directory("d").build().unwrap();
+    archive
+        .append_file("d/f1")
+        .with_content(&[1; 3000])
+        .build()
+        .unwrap();
+    archive
+        .append_file("f2")
+        .with_content(&[2; 1000])
+        .build()
+        .unwrap();
+
+    archive
+}
+
+fn reopen(archive: Archive<MemoryBackend>) -> Archive<MemoryBackend> {
+    Archive::open(archive.into_container()).unwrap()
+}
+
+#[test]
+fn clean() {
+    let mut archive = setup_archive();
+    let report = archive.check_and_repair().unwrap();
+
+    assert!(report.is_clean(), "{:?}", report.fixes);
+}
+
+#[test]
+fn stale_blocks() {
+    let mut archive = setup_archive();
+
+    archive
+        .append_file("f3")
+        .with_content(&[3; 5000])
+        .build()
+        .unwrap();
+
+    // the header is lost, the nodes of the tree were written
+    let header_id = archive.header_id;
+    let snapshot = archive.pager.read_buf_raw(&header_id).unwrap().to_vec();
+
+    archive
+        .append_file("f4")
+        .with_content(&[4; 1000])
+        .build()
+        .unwrap();
+    archive.pager.write(&header_id, &snapshot).unwrap();
+
+    let mut archive = reopen(archive);
+    let nblocks = archive.info().blocks;
+    let report = archive.check_and_repair().unwrap();
+
+    assert_eq!(report.fixes, [Fix::StaleBlocks(3)]);
+    assert_eq!(archive.info().blocks, nblocks);
+    assert!(archive.verify().is_ok());
+
+    archive.append_file("f4").build().unwrap();
+
+    let mut archive = reopen(archive);
+    assert!(archive.verify().is_ok());
+    assert!(archive.check_and_repair().unwrap().is_clean());
+}
+
+#[test]
+fn partial_entry() {
+    let mut archive = setup_archive();
+    let nblocks = archive.info().blocks;
+
+    // the last content block of f2 is missing
+    let removed = archive
+        .tree
+        .remove(&mut archive.pager, nblocks as usize - 1, 1)
+        .unwrap();
+    archive.pager.release(removed[0]).unwrap();
+
+    let report = archive.check_and_repair().unwrap();
+
+    assert_eq!(
+        report.fixes,
+        [
+            Fix::PartialEntry(Some("f2".to_string()), 2),
+            Fix::FileCount(3, 2)
+        ]
+    );
+    assert_eq!(
+        report.fixes[0].to_string(),
+        "discarded partially written f2 with 2 blocks"
+    );
+
+    let mut archive = reopen(archive);
+
+    assert!(archive.verify().is_ok());
+    assert!(archive.lookup("f2").is_none());
+    assert_eq!(archive.info().blocks, nblocks - 3);
+}
+
+#[test]
+fn unreadable_entry() {
+    let mut archive = setup_archive();
+    let nblocks = archive.info().blocks;
+
+    // the name exceeds the block
+    let id = *archive.tree.aquire(&mut archive.pager).unwrap();
+    archive.pager.write(&id, &[0, 0, 0, 0, 0, 0, 4, 0]).unwrap();
+
+    let report = archive.check_and_repair().unwrap();
+    assert_eq!(report.fixes, [Fix::PartialEntry(None, 1)]);
+
+    let mut archive = reopen(archive);
+
+    assert!(archive.verify().is_ok());
+    assert_eq!(archive.info().blocks, nblocks);
+}
+
+#[test]
+fn unreadable_long_name() {
+    let mut archive = setup_archive();
+    let nblocks = archive.info().blocks;
+    let name = "x".repeat(1000);
+
+    archive.append_file(&name).build().unwrap();
+    assert_eq!(archive.info().blocks, nblocks + 3);
+
+    // the name blocks were not written
+    for idx in nblocks + 1..nblocks + 3 {
+        let id = *archive
+            .tree
+            .lookup(&mut archive.pager, idx as usize)
+            .unwrap()
+            .unwrap();
+        archive.pager.write(&id, &[0xFF; 8]).unwrap();
+    }
+
+    let report = archive.check_and_repair().unwrap();
+    assert_eq!(
+        report.fixes,
+        [Fix::PartialEntry(None, 3), Fix::FileCount(4, 3)]
+    );
+    assert!(report.damaged.is_none());
+
+    let mut archive = reopen(archive);
+
+    assert!(archive.verify().is_ok());
+    assert_eq!(archive.info().blocks, nblocks);
+}
+
+#[test]
+fn damaged_entry() {
+    let mut archive = setup_archive();
+    let nblocks = archive.info().blocks;
+
+    // the name of d/f1 exceeds the block
+    let id = *archive.tree.lookup(&mut archive.pager, 1).unwrap().unwrap();
+    let block = archive.pager.read_buf_raw(&id).unwrap().to_vec();
+    archive.pager.write(&id, &[0, 0, 0, 0, 0, 0, 4, 0]).unwrap();
+
+    let report = archive.check_and_repair().unwrap();
+
+    assert!(report.fixes.is_empty(), "{:?}", report.fixes);
+    assert_eq!(report.damaged, Some(1));
+    assert!(!report.is_clean());
+
+    // the entries behind the damaged entry are kept
+    let mut archive = reopen(archive);
+    assert_eq!(archive.info().blocks, nblocks);
+    assert_eq!(archive.info().files, 3);
+
+    archive.pager.write(&id, &block).unwrap();
+
+    let mut entry = archive.lookup("f2").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), [2; 1000]);
+}
+
+#[test]
+fn unsupported_entry() {
+    let mut archive = setup_archive();
+    let nblocks = archive.info().blocks;
+
+    let mut builder = archive.append_file("f3");
+    builder.set_compression(Compression::Deflate);
+    builder.with_content(&[3; 1000]).build().unwrap();
+
+    // f3 is compressed with a codec unknown to this version
+    let idx = nblocks as usize;
+    let id = *archive
+        .tree
+        .lookup(&mut archive.pager, idx)
+        .unwrap()
+        .unwrap();
+    let mut inner = Inner::load(&mut archive.pager, &id).unwrap();
+    let raw_size = 0x0102_0304_0506_0708u64;
+
+    inner.raw_size = raw_size;
+
+    let mut block = inner.to_block(archive.pager.codec()).unwrap();
+    let mut writer = archive.pager.codec().writer(vec![]);
+    writer.write(&raw_size).unwrap();
+    let pattern = writer.into_target();
+    let pos = block.windows(8).position(|w| w == pattern).unwrap();
+
+    assert_eq!(block[pos - 1], 1);
+    block[pos - 1] = 9;
+    archive.pager.write(&id, &block).unwrap();
+
+    let mut archive = reopen(archive);
+    let nblocks = archive.info().blocks;
+    let report = archive.check_and_repair().unwrap();
+
+    // the entry is complete, it is reported and kept
+    assert!(report.fixes.is_empty(), "{:?}", report.fixes);
+    assert_eq!(report.damaged, Some(idx as u64));
+
+    let archive = reopen(archive);
+    assert_eq!(archive.info().blocks, nblocks);
+    assert_eq!(archive.info().files, 4);
+}
+
+#[cfg(not(feature = "zstd"))]
+#[test]
+fn zstd_disabled_entry() {
+    let mut archive = setup_archive();
+    let nblocks = archive.info().blocks;
+
+    archive.append_file("f3").build().unwrap();
+
+    // f3 was written by a build with the zstd feature
+    let idx = nblocks as usize;
+    let id = *archive
+        .tree
+        .lookup(&mut archive.pager, idx)
+        .unwrap()
+        .unwrap();
+    let mut inner = Inner::load(&mut archive.pager, &id).unwrap();
+
+    inner.compression = Compression::Zstd(3);
+    inner.flush(&mut archive.pager, &id).unwrap();
+
+    let mut archive = reopen(archive);
+    let report = archive.check_and_repair().unwrap();
+
+    assert!(report.is_clean(), "{:?}", report.fixes);
+    assert_eq!(archive.info().files, 4);
+}
+
+#[test]
+fn uncovered() {
+    let mut archive = setup_archive();
+    let nblocks = archive.info().blocks;
+
+    archive.tree.aquire_hole(&mut archive.pager).unwrap();
+
+    let report = archive.check_and_repair().unwrap();
+
+    assert_eq!(report.fixes, [Fix::Uncovered(1)]);
+    assert_eq!(archive.info().blocks, nblocks);
+    assert!(archive.verify().is_ok());
+}
+
+#[test]
+fn file_count() {
+    let mut archive = setup_archive();
+
+    archive.header.nfiles += 1;
+
+    let report = archive.check_and_repair().unwrap();
+
+    assert_eq!(report.fixes, [Fix::FileCount(4, 3)]);
+    assert_eq!(reopen(archive).info().files, 3);
+}
+
+#[test]
+fn name_index() {
+    let mut archive = setup_archive();
+
+    archive.create_index().unwrap();
+
+    let names = archive.tree.names_mut(&mut archive.pager).unwrap().unwrap();
+    names.insert(&mut archive.pager, "gone", 1).unwrap();
+
+    let report = archive.check_and_repair().unwrap();
+    assert_eq!(report.fixes, [Fix::NameIndex(1)]);
+
+    let mut archive = reopen(archive);
+
+    let names = archive.tree.names_mut(&mut archive.pager).unwrap();
+    assert!(names.unwrap().get("gone").is_none());
+    assert!(archive.lookup_indexed("f2").is_some());
+    assert!(archive.check_and_repair().unwrap().is_clean());
+}
+
+#[test]
+fn pending_entry() {
+    let mut archive = setup_archive();
+    let nblocks = archive.info().blocks;
+
+    let mut entry = archive.append_file("p").build_multipart(3000).unwrap();
+    entry.write_part(0, &[5; 1000]).unwrap();
+    drop(entry);
+
+    let pending = archive.info().blocks - nblocks;
+
+    archive
+        .append_file("f3")
+        .with_content(&[3; 1000])
+        .build()
+        .unwrap();
+
+    let mut archive = reopen(archive);
+    let report = archive.check_and_repair().unwrap();
+
+    assert_eq!(report.fixes, [Fix::PendingEntry("p".to_string(), pending)]);
+    assert_eq!(archive.info().files, 4);
+    assert!(archive.verify().is_ok());
+    assert_eq!(
+        archive
+            .lookup("f3")
+            .unwrap()
+            .unwrap()
+            .into_file()
+            .unwrap()
+            .read_vec()
+            .unwrap(),
+        [3; 1000]
+    );
+
+    let mut archive = reopen(archive);
+    assert!(archive.check_and_repair().unwrap().is_clean());
+}
+
+#[test]
+fn frozen() {
+    let mut archive = setup_archive();
+
+    archive.freeze("token").unwrap();
+
+    let err = archive.check_and_repair().unwrap_err();
+    assert!(matches!(err, Error::Frozen));
+}
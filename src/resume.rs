@@ -0,0 +1,223 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use log::warn;
+use nuts_container::backend::Backend;
+use std::cell::Cell;
+use std::io::{self, ErrorKind, Read};
+#[cfg(feature = "zip")]
+use std::io::{Seek, SeekFrom};
+use std::rc::Rc;
+
+use crate::error::ArchiveResult;
+use crate::savepoint::{self, Savepoint};
+use crate::Archive;
+
+/// Position in the source of a resumable import.
+///
+/// [`Archive::append_tar_resumable()`] and
+/// [`Archive::append_zip_resumable()`] advance the checkpoint after each
+/// completely imported entry. Store it (e.g. together with the name of the
+/// source) to resume an import, which was interrupted by a failure of the
+/// application. A new import starts with [`ImportCheckpoint::default()`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ImportCheckpoint {
+    entries: u64,
+    offset: u64,
+}
+
+impl ImportCheckpoint {
+    /// Creates a checkpoint from values returned by
+    /// [`ImportCheckpoint::entries()`] and [`ImportCheckpoint::offset()`].
+    pub fn new(entries: u64, offset: u64) -> ImportCheckpoint {
+        ImportCheckpoint { entries, offset }
+    }
+
+    /// Returns the number of entries of the source, which are completely
+    /// processed. Skipped entries are included.
+    pub fn entries(&self) -> u64 {
+        self.entries
+    }
+
+    /// Returns the byte offset in the source, where the import continues.
+    ///
+    /// Only tar streams are resumed at an offset, for zip files the offset
+    /// is always `0`.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+/// Shared state of a [`Source`].
+#[derive(Default)]
+pub(crate) struct SourceState {
+    pos: Cell<u64>,
+    failed: Cell<bool>,
+}
+
+/// A reader, which records the number of bytes read and whether the
+/// underlaying reader failed.
+pub(crate) struct Source<'a, R> {
+    inner: R,
+    state: &'a SourceState,
+}
+
+impl<'a, R: Read> Read for Source<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.inner.read(buf) {
+            Ok(n) => {
+                self.state.pos.set(self.state.pos.get() + n as u64);
+                Ok(n)
+            }
+            Err(err) => {
+                if err.kind() != ErrorKind::Interrupted {
+                    self.state.failed.set(true);
+                }
+
+                Err(err)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "zip")]
+impl<'a, R: Seek> Seek for Source<'a, R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner
+            .seek(pos)
+            .inspect_err(|_| self.state.failed.set(true))
+    }
+}
+
+/// Progress of a single attempt of a resumable import.
+pub(crate) struct Progress {
+    checkpoint: ImportCheckpoint,
+    mark: Savepoint,
+    #[cfg(feature = "tar")]
+    state: Rc<SourceState>,
+    appended: u64,
+}
+
+impl Progress {
+    /// Returns the checkpoint of the last completely imported entry.
+    pub(crate) fn checkpoint(&self) -> &ImportCheckpoint {
+        &self.checkpoint
+    }
+
+    /// Returns the number of bytes read from the source in this attempt.
+    #[cfg(feature = "tar")]
+    pub(crate) fn position(&self) -> u64 {
+        self.state.pos.get()
+    }
+
+    /// Records that the next entry of the source was completely processed.
+    ///
+    /// `appended` tells whether the entry was appended to `archive` or
+    /// skipped, `offset` is the new offset in the source.
+    pub(crate) fn commit<B: Backend>(&mut self, archive: &Archive<B>, appended: bool, offset: u64) {
+        self.checkpoint.entries += 1;
+        self.checkpoint.offset = offset;
+        self.mark = savepoint::mark(archive);
+
+        if appended {
+            self.appended += 1;
+        }
+    }
+}
+
+/// Runs a resumable import.
+///
+/// `open` is called with `checkpoint` to (re-)open the source, `import`
+/// appends the entries of the source to `archive` and commits the progress
+/// after each entry. If the source fails, the partially imported entry is
+/// removed and the import is resumed at the last checkpoint. The import
+/// gives up after `retries` failures in a row without progress.
+///
+/// Returns the number of appended entries.
+pub(crate) fn import<B, R, O, F>(
+    archive: &mut Archive<B>,
+    checkpoint: &mut ImportCheckpoint,
+    retries: u32,
+    mut open: O,
+    mut import: F,
+) -> ArchiveResult<u64, B>
+where
+    B: Backend,
+    O: FnMut(&ImportCheckpoint) -> io::Result<R>,
+    F: FnMut(&mut Archive<B>, Source<'_, R>, &mut Progress) -> ArchiveResult<(), B>,
+{
+    archive.header.check_mutable()?;
+
+    let mut nentries = 0;
+    let mut failures = 0;
+
+    loop {
+        let state = Rc::new(SourceState::default());
+        let mut progress = Progress {
+            checkpoint: *checkpoint,
+            mark: savepoint::mark(archive),
+            #[cfg(feature = "tar")]
+            state: Rc::clone(&state),
+            appended: 0,
+        };
+
+        let result = match open(checkpoint) {
+            Ok(inner) => {
+                let source = Source {
+                    inner,
+                    state: &state,
+                };
+
+                import(archive, source, &mut progress)
+            }
+            Err(err) => {
+                state.failed.set(true);
+                Err(err.into())
+            }
+        };
+
+        nentries += progress.appended;
+
+        if progress.checkpoint != *checkpoint {
+            *checkpoint = progress.checkpoint;
+            failures = 0;
+        }
+
+        let err = match result {
+            Ok(()) => return Ok(nentries),
+            Err(err) => err,
+        };
+
+        savepoint::restore(archive, &progress.mark)?;
+
+        if !state.failed.get() || failures >= retries {
+            return Err(err);
+        }
+
+        failures += 1;
+
+        warn!(
+            "import: source failed ({}), resuming at entry {} (attempt {}/{})",
+            err, checkpoint.entries, failures, retries
+        );
+    }
+}
@@ -0,0 +1,289 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+//! Property-based round-trip tests.
+//!
+//! The module is only available if the `proptest` feature is enabled. It
+//! provides a [`proptest`] strategy, which generates random entry trees,
+//! and functions to verify, that the entries are read back (and would be
+//! extracted) unchanged. Integrators of custom backends can run the
+//! round-trip against their own backend implementation:
+//!
+//! ```rust
+//! use nuts_archive::roundtrip;
+//! use nuts_archive::Archive;
+//! use nuts_container::container::{Cipher, Container, CreateOptionsBuilder};
+//! use nuts_container::memory::MemoryBackend;
+//! use proptest::prelude::*;
+//!
+//! proptest! {
+//!     #![proptest_config(ProptestConfig::with_cases(8))]
+//!
+//!     fn roundtrip(specs in roundtrip::entries(20, 2048)) {
+//!         let options = CreateOptionsBuilder::new(Cipher::None)
+//!             .build::<MemoryBackend>()
+//!             .unwrap();
+//!         let container = Container::<MemoryBackend>::create(MemoryBackend::new(), options).unwrap();
+//!         let mut archive = Archive::create(container, false).unwrap();
+//!
+//!         roundtrip::check_roundtrip(&mut archive, &specs)?;
+//!     }
+//! }
+//! # roundtrip();
+//! ```
+
+#[cfg(test)]
+mod tests;
+
+use nuts_container::backend::Backend;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+
+use crate::error::{ArchiveResult, Error};
+use crate::{Archive, Entry, PlanAction, SpecialKind};
+
+/// Description of an entry generated by [`entries()`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum EntrySpec {
+    /// A file entry with its content.
+    File { name: String, content: Vec<u8> },
+
+    /// A directory entry.
+    Directory { name: String },
+
+    /// A symlink entry with its target.
+    Symlink { name: String, target: String },
+
+    /// A hard link entry with the name of the file it refers to.
+    HardLink { name: String, target: String },
+
+    /// A special file entry.
+    Special { name: String, kind: SpecialKind },
+}
+
+impl EntrySpec {
+    /// Returns the name of the entry.
+    pub fn name(&self) -> &str {
+        match self {
+            EntrySpec::File { name, .. } => name,
+            EntrySpec::Directory { name } => name,
+            EntrySpec::Symlink { name, .. } => name,
+            EntrySpec::HardLink { name, .. } => name,
+            EntrySpec::Special { name, .. } => name,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Kind {
+    File(Vec<u8>),
+    Directory,
+    Symlink(String),
+    HardLink,
+    Special(SpecialKind),
+}
+
+/// A strategy, which generates a random tree of up to `max_entries`
+/// entries.
+///
+/// Files receive up to `max_size` bytes of random content. The tree is at
+/// most three levels deep, each parent directory is stored in front of its
+/// children and all names are unique. A hard link refers to the file
+/// generated last before it.
+pub fn entries(max_entries: usize, max_size: usize) -> impl Strategy<Value = Vec<EntrySpec>> {
+    let kind = prop_oneof![
+        vec(any::<u8>(), 0..=max_size).prop_map(Kind::File),
+        Just(Kind::Directory),
+        "[a-z]{1,8}(/[a-z]{1,8}){0,2}".prop_map(Kind::Symlink),
+        Just(Kind::HardLink),
+        special_kind().prop_map(Kind::Special),
+    ];
+
+    vec((vec("[a-z]{1,8}", 1..=3), kind), 0..=max_entries).prop_map(make_tree)
+}
+
+fn special_kind() -> impl Strategy<Value = SpecialKind> {
+    prop_oneof![
+        Just(SpecialKind::Fifo),
+        (any::<u32>(), any::<u32>())
+            .prop_map(|(major, minor)| SpecialKind::CharDevice { major, minor }),
+        (any::<u32>(), any::<u32>())
+            .prop_map(|(major, minor)| SpecialKind::BlockDevice { major, minor }),
+        Just(SpecialKind::Socket),
+    ]
+}
+
+fn make_tree(items: Vec<(Vec<String>, Kind)>) -> Vec<EntrySpec> {
+    let mut specs = vec![];
+    let mut known = HashMap::new(); // name => is directory
+    let mut last_file = None;
+
+    for (segments, kind) in items {
+        let name = segments.join("/");
+        let parents = (1..segments.len())
+            .map(|n| segments[..n].join("/"))
+            .collect::<Vec<_>>();
+
+        if known.contains_key(&name) || parents.iter().any(|p| known.get(p) == Some(&false)) {
+            continue;
+        }
+
+        if matches!(kind, Kind::HardLink) && last_file.is_none() {
+            continue;
+        }
+
+        for parent in parents {
+            if known.insert(parent.clone(), true).is_none() {
+                specs.push(EntrySpec::Directory { name: parent });
+            }
+        }
+
+        known.insert(name.clone(), matches!(kind, Kind::Directory));
+
+        specs.push(match kind {
+            Kind::File(content) => {
+                last_file = Some(name.clone());
+                EntrySpec::File { name, content }
+            }
+            Kind::Directory => EntrySpec::Directory { name },
+            Kind::Symlink(target) => EntrySpec::Symlink { name, target },
+            Kind::HardLink => EntrySpec::HardLink {
+                name,
+                target: last_file.clone().unwrap(),
+            },
+            Kind::Special(kind) => EntrySpec::Special { name, kind },
+        });
+    }
+
+    specs
+}
+
+fn fail<E: fmt::Display>(err: E) -> TestCaseError {
+    TestCaseError::fail(err.to_string())
+}
+
+/// Appends all `specs` at the end of `archive`.
+pub fn append<B: Backend>(archive: &mut Archive<B>, specs: &[EntrySpec]) -> ArchiveResult<(), B> {
+    for spec in specs {
+        match spec {
+            EntrySpec::File { name, content } => {
+                archive.append_file(name).build()?.write_all(content)?
+            }
+            EntrySpec::Directory { name } => archive.append_directory(name).build()?,
+            EntrySpec::Symlink { name, target } => archive.append_symlink(name, target).build()?,
+            EntrySpec::HardLink { name, target } => {
+                archive.append_hardlink(name, target).build()?
+            }
+            EntrySpec::Special { name, kind } => archive.append_special(name, *kind).build()?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Verifies, that `archive` contains exactly the entries of `specs`.
+///
+/// The entries are read back in archive order and compared with `specs`.
+/// Then an [extraction is planned](Archive::plan_extract), which must cover
+/// all entries without any conflicts.
+pub fn check<B: Backend>(
+    archive: &mut Archive<B>,
+    specs: &[EntrySpec],
+) -> Result<(), TestCaseError> {
+    let mut found = vec![];
+    let mut entry_opt = archive.first();
+
+    while let Some(result) = entry_opt {
+        let entry = match result.map_err(fail)? {
+            Entry::File(mut file) => {
+                found.push(EntrySpec::File {
+                    name: file.name().to_string(),
+                    content: file.read_vec().map_err(fail)?,
+                });
+                Entry::File(file)
+            }
+            Entry::Directory(dir) => {
+                found.push(EntrySpec::Directory {
+                    name: dir.name().to_string(),
+                });
+                Entry::Directory(dir)
+            }
+            Entry::Symlink(symlink) => {
+                found.push(EntrySpec::Symlink {
+                    name: symlink.name().to_string(),
+                    target: symlink.target().to_string(),
+                });
+                Entry::Symlink(symlink)
+            }
+            Entry::HardLink(link) => {
+                found.push(EntrySpec::HardLink {
+                    name: link.name().to_string(),
+                    target: link.target().to_string(),
+                });
+                Entry::HardLink(link)
+            }
+            Entry::Special(special) => {
+                found.push(EntrySpec::Special {
+                    name: special.name().to_string(),
+                    kind: special.kind(),
+                });
+                Entry::Special(special)
+            }
+        };
+
+        entry_opt = entry.next();
+    }
+
+    prop_assert_eq!(&found, specs);
+
+    let dest = env::temp_dir().join("nuts-archive-roundtrip-does-not-exist");
+    let plan = archive.plan_extract(&dest, &[] as &[&str]).map_err(fail)?;
+
+    prop_assert_eq!(plan.items.len(), specs.len());
+
+    for (item, spec) in plan.items.iter().zip(specs) {
+        let size = match spec {
+            EntrySpec::File { content, .. } => content.len() as u64,
+            EntrySpec::Directory { .. } => 0,
+            EntrySpec::Symlink { target, .. } => target.len() as u64,
+            EntrySpec::HardLink { .. } => 0,
+            EntrySpec::Special { .. } => 0,
+        };
+
+        prop_assert_eq!(&item.name, spec.name());
+        prop_assert_eq!(item.size, size);
+        prop_assert_eq!(item.action, PlanAction::Create);
+    }
+
+    Ok(())
+}
+
+/// Appends `specs` to an empty `archive` and [checks](check) the result.
+pub fn check_roundtrip<B: Backend>(
+    archive: &mut Archive<B>,
+    specs: &[EntrySpec],
+) -> Result<(), TestCaseError> {
+    append(archive, specs).map_err(|err: Error<B>| fail(err))?;
+    check(archive, specs)
+}
@@ -0,0 +1,75 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+use proptest::prelude::*;
+
+use crate::roundtrip::{check_roundtrip, entries, make_tree, EntrySpec, Kind};
+use crate::tests::setup_container_with_bsize;
+use crate::Archive;
+
+fn names(specs: &[EntrySpec]) -> Vec<&str> {
+    specs.iter().map(|spec| spec.name()).collect()
+}
+
+fn segments(name: &str) -> Vec<String> {
+    name.split('/').map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn tree_parents() {
+    let specs = make_tree(vec![
+        (segments("a/b/c"), Kind::File(vec![1, 2, 3])),
+        (segments("a/d"), Kind::Symlink("c".to_string())),
+    ]);
+
+    assert_eq!(names(&specs), ["a", "a/b", "a/b/c", "a/d"]);
+    assert_eq!(
+        specs[2],
+        EntrySpec::File {
+            name: "a/b/c".to_string(),
+            content: vec![1, 2, 3]
+        }
+    );
+}
+
+#[test]
+fn tree_unique() {
+    let specs = make_tree(vec![
+        (segments("a"), Kind::Directory),
+        (segments("a"), Kind::File(vec![])),
+        (segments("b"), Kind::File(vec![])),
+        (segments("b/c"), Kind::File(vec![])),
+    ]);
+
+    assert_eq!(names(&specs), ["a", "b"]);
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    #[test]
+    fn roundtrip(specs in entries(30, 2048)) {
+        let container = setup_container_with_bsize(512);
+        let mut archive = Archive::create(container, false).unwrap();
+
+        check_roundtrip(&mut archive, &specs)?;
+    }
+}
@@ -0,0 +1,328 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use log::{debug, warn};
+use nuts_container::backend::{Backend, BlockId};
+use nuts_container::container::Container;
+use std::collections::VecDeque;
+
+use crate::codec::Codec;
+use crate::entry::immut::InnerEntry;
+use crate::entry::Inner;
+use crate::error::{ArchiveResult, Error, RewriteError};
+use crate::tree::ids_per_node;
+use crate::{flush_header, Archive};
+
+/// The result of [`Archive::salvage()`](crate::Archive::salvage).
+#[derive(Debug, Default)]
+pub struct SalvageReport {
+    /// Number of blocks, which were scanned
+    pub blocks: u64,
+
+    /// Number of blocks, which could not be read
+    pub unreadable: u64,
+
+    /// Names of the recovered entries in the order of the new archive
+    pub recovered: Vec<String>,
+
+    /// Names of recovered files, whose content does not match their stored
+    /// checksum
+    pub damaged: Vec<String>,
+}
+
+fn content_blocks(inner: &Inner, block_size: u64) -> u64 {
+    inner.size.div_ceil(block_size)
+}
+
+/// Tests whether `buf` holds the metadata of an entry.
+///
+/// Blocks are not tagged, so this is a guess: the block must decode into an
+/// entry of a known type with a valid name.
+fn recognize(codec: Codec, buf: &[u8]) -> Option<Inner> {
+    let inner = codec.reader(buf).read::<Inner>().ok()?;
+    let mode = &inner.mode;
+
+    let known = mode.is_file()
+        || mode.is_directory()
+        || mode.is_symlink()
+        || mode.is_hardlink()
+        || mode.is_special()
+        || mode.is_internal();
+    let named = if inner.name_blocks > 0 {
+        inner.name.is_empty()
+    } else {
+        !inner.name.is_empty() && !inner.name.contains('\0')
+    };
+
+    if known && named {
+        Some(inner)
+    } else {
+        None
+    }
+}
+
+/// Tests whether `buf` holds an indirect node of the lost tree.
+///
+/// A node is aquired right before the block, which is stored in its first
+/// slot, so its first id is the `next` id of the scan. The ids of a node
+/// are distinct and the slots behind the last used one are null.
+fn is_node<B: Backend>(codec: Codec, buf: &[u8], ipn: usize, next: Option<&B::Id>) -> bool {
+    let mut reader = codec.reader(buf);
+    let mut ids: Vec<B::Id> = vec![];
+
+    for _ in 0..ipn {
+        match reader.read::<B::Id>() {
+            Ok(id) if id.is_null() => break,
+            Ok(id) if !ids.contains(&id) => ids.push(id),
+            _ => return false,
+        }
+    }
+
+    let tail_null =
+        (ids.len() + 1..ipn).all(|_| matches!(reader.read::<B::Id>(), Ok(id) if id.is_null()));
+
+    next.is_some() && ids.first() == next && tail_null
+}
+
+/// An entry, whose blocks are copied into the new archive.
+struct Pending<B: Backend> {
+    inner: Inner,
+    name: Option<String>,
+    name_buf: Vec<u8>,
+
+    // index of the metadata block in the new archive
+    start: usize,
+
+    // ids of the blocks behind the metadata block, which are scanned again,
+    // if the entry is incomplete
+    ids: Vec<B::Id>,
+}
+
+impl<B: Backend> Pending<B> {
+    fn count(&self, block_size: u64) -> usize {
+        self.inner.head_blocks() + content_blocks(&self.inner, block_size) as usize
+    }
+}
+
+fn copy_block<T: Backend>(target: &mut Archive<T>, buf: &[u8]) -> ArchiveResult<(), T> {
+    let id = target.tree.aquire(&mut target.pager)?.clone();
+
+    target.pager.write(&id, buf)?;
+
+    Ok(())
+}
+
+/// Drops the blocks of an incomplete entry from the new archive.
+///
+/// The blocks behind its metadata are put in front of `rescan`, they can
+/// hold other entries.
+fn discard<B: Backend, T: Backend>(
+    target: &mut Archive<T>,
+    pending: Pending<B>,
+    rescan: &mut VecDeque<B::Id>,
+) -> ArchiveResult<(), T> {
+    let count = target.tree.nblocks() as usize - pending.start;
+    let removed = target
+        .tree
+        .remove(&mut target.pager, pending.start, count)?;
+
+    for id in removed {
+        target.tree.release(&mut target.pager, id)?;
+    }
+
+    warn!("salvage: incomplete entry at {}", pending.start);
+
+    for id in pending.ids.into_iter().rev() {
+        rescan.push_front(id);
+    }
+
+    Ok(())
+}
+
+pub(crate) fn salvage<B: Backend, T: Backend, I: IntoIterator<Item = B::Id>>(
+    source: &mut Container<B>,
+    ids: I,
+    container: Container<T>,
+) -> Result<(Archive<T>, SalvageReport), RewriteError<B, T>> {
+    let bsize = source.block_size();
+
+    if container.block_size() != bsize {
+        return Err(RewriteError::Target(Error::BlockSizeMismatch(
+            container.block_size(),
+            bsize,
+        )));
+    }
+
+    let mut target = Archive::create(container, false).map_err(RewriteError::Target)?;
+    let mut report = SalvageReport::default();
+
+    // the header is lost, entries are expected in the current format
+    let codec = Codec::current();
+    let ipn = ids_per_node(source) as usize;
+
+    // the blocks are streamed, only the ids of the current entry are kept
+    let mut ids = ids.into_iter().peekable();
+    let mut rescan = VecDeque::new();
+    let mut pending: Option<Pending<B>> = None;
+    let mut buf = vec![0; bsize as usize];
+
+    loop {
+        let (id, fresh) = match rescan.pop_front() {
+            Some(id) => (id, false),
+            None => match ids.next() {
+                Some(id) => (id, true),
+                None => match pending.take() {
+                    Some(p) => {
+                        discard(&mut target, p, &mut rescan).map_err(RewriteError::Target)?;
+                        continue;
+                    }
+                    None => break,
+                },
+            },
+        };
+
+        if fresh {
+            report.blocks += 1;
+        }
+
+        let readable = match source.read(&id, &mut buf) {
+            Ok(_) => true,
+            Err(err) => {
+                if fresh {
+                    warn!("salvage: cannot read {}: {}", id, err);
+                    report.unreadable += 1;
+                }
+
+                false
+            }
+        };
+
+        let next = match rescan.front() {
+            Some(next) => Some(next.clone()),
+            None => ids.peek().cloned(),
+        };
+
+        if readable && is_node::<B>(codec, &buf, ipn, next.as_ref()) {
+            debug!("salvage: tree node {} skipped", id);
+            continue;
+        }
+
+        let mut p = match pending.take() {
+            Some(p) if !readable => {
+                discard(&mut target, p, &mut rescan).map_err(RewriteError::Target)?;
+                continue;
+            }
+            Some(mut p) => {
+                if p.ids.len() < p.inner.name_blocks as usize {
+                    p.name_buf.extend_from_slice(&buf);
+                }
+
+                p.ids.push(id);
+                p
+            }
+            None => match readable.then(|| recognize(codec, &buf)).flatten() {
+                Some(inner) => Pending {
+                    name: (inner.name_blocks == 0).then(|| inner.name.clone()),
+                    inner,
+                    name_buf: vec![],
+                    start: target.tree.nblocks() as usize,
+                    ids: vec![],
+                },
+                None => continue,
+            },
+        };
+
+        copy_block(&mut target, &buf).map_err(RewriteError::Target)?;
+
+        if p.name.is_none() && p.ids.len() == p.inner.name_blocks as usize {
+            // a long name is complete
+            p.name = codec
+                .reader(p.name_buf.as_slice())
+                .read_str()
+                .ok()
+                .map(|s| s.to_string());
+
+            if p.name.is_none() {
+                discard(&mut target, p, &mut rescan).map_err(RewriteError::Target)?;
+                continue;
+            }
+        }
+
+        let count = p.count(bsize as u64);
+
+        if p.ids.len() + 1 < count {
+            pending = Some(p);
+            continue;
+        }
+
+        if !p.inner.mode.is_internal() {
+            target.header.nfiles += 1;
+        }
+
+        let name = p.name.unwrap_or_default();
+
+        debug!("salvage: {} recovered from {} blocks", name, count);
+
+        report.recovered.push(name);
+    }
+
+    target.header.touch();
+
+    flush_header(
+        &mut target.pager,
+        &target.header_id,
+        &target.header,
+        &mut target.tree,
+    )
+    .map_err(RewriteError::Target)?;
+
+    let mut entry_opt = InnerEntry::first(&mut target.pager, &mut target.tree);
+
+    while let Some(result) = entry_opt {
+        let mut entry = match result {
+            Ok(entry) => entry,
+            Err(err) => {
+                warn!("salvage: cannot verify the recovered entries: {}", err);
+                break;
+            }
+        };
+
+        if let Err(err) = entry.verify() {
+            warn!("salvage: {}", err);
+            report.damaged.push(entry.inner().name.clone());
+        }
+
+        entry_opt = entry.next();
+    }
+
+    debug!(
+        "salvage: {} blocks scanned, {} entries recovered, {} damaged",
+        report.blocks,
+        report.recovered.len(),
+        report.damaged.len()
+    );
+
+    Ok((target, report))
+}
@@ -0,0 +1,190 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use nuts_container::container::Container;
+use nuts_container::memory::{Id, MemoryBackend};
+
+use crate::error::{Error, RewriteError};
+use crate::tests::{setup_container, setup_container_with_bsize};
+use crate::{Archive, Compression};
+
+fn content(len: usize) -> Vec<u8> {
+    (0..len).map(|n| (n % 251) as u8).collect()
+}
+
+/// Creates an archive and destroys its header block and the backup.
+///
+/// Returns the container and the ids of all blocks of the container.
+fn setup_damaged() -> (Container<MemoryBackend>, Vec<Id>) {
+    let mut archive = Archive::create(setup_container(), false).unwrap();
+
+    archive.append_directory("d").build().unwrap();
+
+    let mut builder = archive.append_file("d/f1");
+    builder.set_hashed(true);
+    builder.with_content(&content(3000)).build().unwrap();
+
+    let mut builder = archive.append_file("f2");
+    builder.set_hashed(true);
+    builder.set_compression(Compression::Deflate);
+    builder.with_content(&content(5000)).build().unwrap();
+
+    archive.append_symlink("s1", "f2").build().unwrap();
+    archive.append_file("x".repeat(600)).build().unwrap();
+
+    damage(archive)
+}
+
+/// Destroys the header block and the backup of `archive`.
+///
+/// Returns the container and the ids of all blocks of the container.
+fn damage(archive: Archive<MemoryBackend>) -> (Container<MemoryBackend>, Vec<Id>) {
+    let header_id = archive.header_id;
+    let backup = *archive.pager.backup().unwrap();
+    let mut container = archive.into_container();
+
+    container.write(&header_id, &[0; 512]).unwrap();
+    container.write(&backup, &[0; 512]).unwrap();
+
+    let next = container.aquire().unwrap();
+    let ids = (1..next.to_string().parse::<u32>().unwrap())
+        .map(|n| n.to_string().parse::<Id>().unwrap())
+        .collect();
+
+    (container, ids)
+}
+
+#[test]
+fn header_lost() {
+    let (mut container, ids) = setup_damaged();
+
+    assert!(Archive::peek(&mut container).is_err());
+
+    let (mut target, report) =
+        Archive::salvage(&mut container, ids.clone(), setup_container()).unwrap();
+
+    assert_eq!(report.blocks, ids.len() as u64);
+    assert_eq!(report.unreadable, 0);
+    assert_eq!(
+        report.recovered,
+        ["d", "d/f1", "f2", "s1", &"x".repeat(600)]
+    );
+    assert!(report.damaged.is_empty());
+
+    assert_eq!(target.info().files, 5);
+    assert!(target.verify().is_ok());
+
+    let mut file = target.lookup("d/f1").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(file.read_vec().unwrap(), content(3000));
+
+    let mut file = target.lookup("f2").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(file.read_vec().unwrap(), content(5000));
+
+    let entry = target.lookup("s1").unwrap().unwrap();
+    assert_eq!(entry.into_symlink().unwrap().target(), "f2");
+}
+
+#[test]
+fn content_damaged() {
+    let (mut container, ids) = setup_damaged();
+
+    // the first content block of d/f1
+    container.write(&ids[4], b"garbage").unwrap();
+
+    let (_, report) = Archive::salvage(&mut container, ids, setup_container()).unwrap();
+
+    assert_eq!(report.recovered.len(), 5);
+    assert_eq!(report.damaged, ["d/f1"]);
+}
+
+#[test]
+fn unreadable() {
+    let (mut container, mut ids) = setup_damaged();
+
+    ids.push("4711".parse().unwrap());
+
+    let (_, report) = Archive::salvage(&mut container, ids, setup_container()).unwrap();
+
+    assert_eq!(report.unreadable, 1);
+    assert_eq!(report.recovered.len(), 5);
+}
+
+#[test]
+fn block_size_mismatch() {
+    let (mut container, ids) = setup_damaged();
+    let result = Archive::salvage(&mut container, ids, setup_container_with_bsize(1024));
+
+    assert!(matches!(
+        result,
+        Err(RewriteError::Target(Error::BlockSizeMismatch(1024, 512)))
+    ));
+}
+
+#[test]
+fn indirect() {
+    let mut archive = Archive::create(setup_container(), false).unwrap();
+
+    // crosses the direct blocks, an indirect node is aquired in between
+    archive
+        .append_file("f1")
+        .with_content(&content(20 * 512))
+        .build()
+        .unwrap();
+    archive
+        .append_file("f2")
+        .with_content(b"xyz")
+        .build()
+        .unwrap();
+
+    let (mut container, ids) = damage(archive);
+    let (mut target, report) = Archive::salvage(&mut container, ids, setup_container()).unwrap();
+
+    assert_eq!(report.recovered, ["f1", "f2"]);
+    assert!(report.damaged.is_empty());
+    assert!(target.verify().is_ok());
+
+    let mut file = target.lookup("f1").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(file.read_vec().unwrap(), content(20 * 512));
+
+    let mut file = target.lookup("f2").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(file.read_vec().unwrap(), b"xyz");
+}
+
+#[test]
+fn incomplete() {
+    let (mut container, ids) = setup_damaged();
+
+    // the second content block of d/f1 cannot be read
+    let ids = ids
+        .into_iter()
+        .map(|id| match id.to_string().as_str() {
+            "6" => "4711".parse().unwrap(),
+            _ => id,
+        })
+        .collect::<Vec<Id>>();
+
+    let (target, report) = Archive::salvage(&mut container, ids, setup_container()).unwrap();
+
+    assert_eq!(report.unreadable, 1);
+    assert_eq!(report.recovered, ["d", "f2", "s1", &"x".repeat(600)]);
+    assert_eq!(target.info().files, 4);
+}
@@ -0,0 +1,162 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use log::debug;
+use nuts_container::backend::Backend;
+
+use crate::entry::immut::visible_names;
+use crate::entry::r#mut::dec_parent_children;
+use crate::entry::Inner;
+use crate::error::{ArchiveResult, Error};
+use crate::{flush_header, release_blocks, Archive};
+
+/// State of the archive recorded by [`Archive::savepoint()`].
+#[derive(Clone, Debug)]
+pub(crate) struct Savepoint {
+    name: String,
+    nblocks: u64,
+    nfiles: u64,
+}
+
+/// Records a savepoint with the given `name` for `archive`.
+pub(crate) fn create<B: Backend>(archive: &mut Archive<B>, name: &str) {
+    let savepoint = Savepoint {
+        name: name.to_string(),
+        nblocks: archive.tree.nblocks(),
+        nfiles: archive.header.nfiles,
+    };
+
+    debug!("savepoint {:?}", savepoint);
+
+    archive.savepoints.push(savepoint);
+}
+
+/// Returns an unnamed savepoint for the current state of `archive`.
+///
+/// The savepoint is not recorded, pass it to [`restore()`] to discard the
+/// entries appended afterwards.
+#[cfg(any(feature = "tar", feature = "zip"))]
+pub(crate) fn mark<B: Backend>(archive: &Archive<B>) -> Savepoint {
+    Savepoint {
+        name: String::new(),
+        nblocks: archive.tree.nblocks(),
+        nfiles: archive.header.nfiles,
+    }
+}
+
+/// Rolls `archive` back to the savepoint at position `pos`.
+///
+/// Later savepoints are discarded, the savepoint itself is kept. Fails
+/// without any change, if one of the entries has external references.
+pub(crate) fn rollback<B: Backend>(archive: &mut Archive<B>, pos: usize) -> ArchiveResult<(), B> {
+    let savepoint = archive.savepoints[pos].clone();
+    let nentries = restore(archive, &savepoint)?;
+
+    archive.savepoints.truncate(pos + 1);
+
+    if nentries > 0 {
+        debug!(
+            "rolled back to savepoint {}, {} entries removed",
+            savepoint.name, nentries
+        );
+    }
+
+    Ok(())
+}
+
+/// Removes all entries appended to `archive` after `savepoint`.
+///
+/// Returns the number of removed entries. Fails without any change, if one
+/// of the entries has external references.
+pub(crate) fn restore<B: Backend>(
+    archive: &mut Archive<B>,
+    savepoint: &Savepoint,
+) -> ArchiveResult<usize, B> {
+    archive.header.check_mutable()?;
+
+    let start = savepoint.nblocks as usize;
+    let nblocks = archive.tree.nblocks() as usize;
+
+    if start == nblocks {
+        return Ok(0);
+    }
+
+    let names = visible_names(&mut archive.pager, &mut archive.tree, start)?;
+
+    for (idx, name) in names.iter() {
+        let id = match archive.tree.lookup(&mut archive.pager, *idx) {
+            Some(result) => result?.clone(),
+            None => return Err(Error::NotFound(name.clone())),
+        };
+
+        if Inner::load(&mut archive.pager, &id)?.refs > 0 {
+            return Err(Error::Referenced(name.clone()));
+        }
+    }
+
+    let removed = archive
+        .tree
+        .remove(&mut archive.pager, start, nblocks - start)?;
+
+    archive.header.nfiles = savepoint.nfiles;
+    archive.header.touch();
+
+    flush_header(
+        &mut archive.pager,
+        &archive.header_id,
+        &archive.header,
+        &mut archive.tree,
+    )?;
+
+    release_blocks(&mut archive.pager, &mut archive.tree, removed)?;
+
+    for (_, name) in names.iter() {
+        dec_parent_children(&mut archive.pager, &mut archive.tree, name)?;
+    }
+
+    if archive.tree.has_names() {
+        archive.build_index()?;
+    }
+
+    Ok(names.len())
+}
+
+/// Returns the position of the last savepoint with the given `name`.
+pub(crate) fn position<B: Backend>(archive: &Archive<B>, name: &str) -> ArchiveResult<usize, B> {
+    archive
+        .savepoints
+        .iter()
+        .rposition(|savepoint| savepoint.name == name)
+        .ok_or_else(|| Error::NoSavepoint(name.to_string()))
+}
+
+/// Returns the names of the savepoints of `archive`, oldest first.
+pub(crate) fn names<B: Backend>(archive: &Archive<B>) -> Vec<&str> {
+    archive
+        .savepoints
+        .iter()
+        .map(|savepoint| savepoint.name.as_str())
+        .collect()
+}
@@ -0,0 +1,193 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use nuts_container::memory::MemoryBackend;
+
+use crate::tests::setup_container_with_bsize;
+use crate::{Archive, Error};
+
+fn setup_archive() -> Archive<MemoryBackend> {
+    let container = setup_container_with_bsize(512);
+    Archive::create(container, false).unwrap()
+}
+
+fn append(archive: &mut Archive<MemoryBackend>, name: &str) {
+    archive
+        .append_file(name)
+        .build()
+        .unwrap()
+        .write_all(&[1; 700])
+        .unwrap();
+}
+
+fn names(archive: &mut Archive<MemoryBackend>) -> Vec<String> {
+    let mut names = vec![];
+    let mut entry_opt = archive.first();
+
+    while let Some(result) = entry_opt {
+        let entry = result.unwrap();
+
+        names.push(entry.name().to_string());
+        entry_opt = entry.next();
+    }
+
+    names
+}
+
+#[test]
+fn rollback() {
+    let mut archive = setup_archive();
+
+    archive.append_directory("dir").build().unwrap();
+    append(&mut archive, "dir/f1");
+    archive.savepoint("sp1");
+    append(&mut archive, "dir/f2");
+    append(&mut archive, "dir/f3");
+
+    archive.rollback().unwrap();
+
+    assert_eq!(names(&mut archive), ["dir", "dir/f1"]);
+    assert_eq!(archive.info().files, 2);
+    assert_eq!(archive.info().blocks, 4);
+    assert_eq!(archive.savepoints(), ["sp1"]);
+
+    let dir = archive.first().unwrap().unwrap().into_directory().unwrap();
+    assert_eq!(dir.children(), 1);
+
+    // the savepoint is kept and the released blocks are reused
+    append(&mut archive, "dir/f4");
+    archive.rollback().unwrap();
+    assert_eq!(names(&mut archive), ["dir", "dir/f1"]);
+
+    append(&mut archive, "dir/f5");
+    assert_eq!(archive.tree.free_list().unwrap().len(), 3);
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+    assert_eq!(names(&mut archive), ["dir", "dir/f1", "dir/f5"]);
+}
+
+#[test]
+fn rollback_to() {
+    let mut archive = setup_archive();
+
+    archive.savepoint("sp1");
+    append(&mut archive, "f1");
+    archive.savepoint("sp2");
+    append(&mut archive, "f2");
+    archive.savepoint("sp3");
+    append(&mut archive, "f3");
+
+    archive.rollback_to("sp2").unwrap();
+    assert_eq!(names(&mut archive), ["f1"]);
+    assert_eq!(archive.savepoints(), ["sp1", "sp2"]);
+
+    archive.rollback_to("sp1").unwrap();
+    assert!(names(&mut archive).is_empty());
+    assert_eq!(archive.info().files, 0);
+    assert_eq!(archive.info().blocks, 0);
+}
+
+#[test]
+fn rollback_nothing() {
+    let mut archive = setup_archive();
+
+    append(&mut archive, "f1");
+    archive.savepoint("sp1");
+    archive.rollback().unwrap();
+
+    assert_eq!(names(&mut archive), ["f1"]);
+}
+
+#[test]
+fn rollback_index() {
+    let mut archive = setup_archive();
+
+    append(&mut archive, "f1");
+    archive.create_index().unwrap();
+    archive.savepoint("sp1");
+    append(&mut archive, "f2");
+
+    archive.rollback().unwrap();
+    assert!(archive.lookup_indexed("f2").is_none());
+
+    append(&mut archive, "f3");
+    let entry = archive.lookup_indexed("f3").unwrap().unwrap();
+    assert_eq!(entry.name(), "f3");
+}
+
+#[test]
+fn no_savepoint() {
+    let mut archive = setup_archive();
+
+    let err = archive.rollback().unwrap_err();
+    assert!(matches!(err, Error::NoSavepoint(_)));
+
+    archive.savepoint("sp1");
+
+    let err = archive.rollback_to("sp2").unwrap_err();
+    assert!(matches!(err, Error::NoSavepoint(name) if name == "sp2"));
+}
+
+#[test]
+fn release() {
+    let mut archive = setup_archive();
+
+    archive.savepoint("sp1");
+    archive.savepoint("sp2");
+    archive.savepoint("sp3");
+
+    archive.release_savepoint("sp2").unwrap();
+    assert_eq!(archive.savepoints(), ["sp1"]);
+
+    let err = archive.release_savepoint("sp3").unwrap_err();
+    assert!(matches!(err, Error::NoSavepoint(name) if name == "sp3"));
+}
+
+#[test]
+fn discarded_by_remove() {
+    let mut archive = setup_archive();
+
+    append(&mut archive, "f1");
+    archive.savepoint("sp1");
+    append(&mut archive, "f2");
+
+    archive.remove("f1").unwrap();
+    assert!(archive.savepoints().is_empty());
+
+    assert!(matches!(
+        archive.rollback().unwrap_err(),
+        Error::NoSavepoint(_)
+    ));
+    assert_eq!(names(&mut archive), ["f2"]);
+}
+
+#[test]
+fn frozen() {
+    let mut archive = setup_archive();
+
+    archive.savepoint("sp1");
+    append(&mut archive, "f1");
+    archive.freeze("token").unwrap();
+
+    assert!(matches!(archive.rollback().unwrap_err(), Error::Frozen));
+    assert_eq!(names(&mut archive), ["f1"]);
+}
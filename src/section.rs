@@ -0,0 +1,155 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use log::debug;
+use nuts_container::backend::Backend;
+
+use crate::entry::immut::InnerEntry;
+use crate::error::{ArchiveResult, Error};
+use crate::{dependents, Archive};
+
+/// Statistics of a section, returned by
+/// [`Archive::sections()`](crate::Archive::sections).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Section {
+    name: String,
+    entries: u64,
+    size: u64,
+}
+
+impl Section {
+    /// Returns the name of the section.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the number of entries in the section.
+    pub fn entries(&self) -> u64 {
+        self.entries
+    }
+
+    /// Returns the accumulated size of all entries in the section.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+/// Collects the statistics of all sections of `archive`.
+///
+/// The sections are ordered by their first entry.
+pub(crate) fn collect<B: Backend>(archive: &mut Archive<B>) -> ArchiveResult<Vec<Section>, B> {
+    let mut sections: Vec<Section> = vec![];
+    let mut entry_opt = InnerEntry::first(&mut archive.pager, &mut archive.tree);
+
+    while let Some(result) = entry_opt {
+        let entry = result?;
+        let inner = entry.inner();
+
+        if let Some(name) = inner.section.as_ref().filter(|_| !inner.mode.is_internal()) {
+            let pos = match sections.iter().position(|s| &s.name == name) {
+                Some(pos) => pos,
+                None => {
+                    sections.push(Section {
+                        name: name.clone(),
+                        entries: 0,
+                        size: 0,
+                    });
+                    sections.len() - 1
+                }
+            };
+
+            sections[pos].entries += 1;
+            sections[pos].size += inner.content_size();
+        }
+
+        entry_opt = entry.next();
+    }
+
+    Ok(sections)
+}
+
+/// Removes all entries of the section `name` from `archive`.
+///
+/// All entries are validated first, either all entries are removed or
+/// none. Returns the number of removed entries.
+pub(crate) fn prune<B: Backend>(archive: &mut Archive<B>, name: &str) -> ArchiveResult<u64, B> {
+    archive.header.check_mutable()?;
+
+    let mut located = vec![];
+    let mut links = vec![];
+    let mut entry_opt = InnerEntry::first(&mut archive.pager, &mut archive.tree);
+
+    while let Some(result) = entry_opt {
+        let mut entry = result?;
+
+        if entry.inner().mode.is_hardlink() && !entry.inner().mode.is_internal() {
+            let pruned = entry.inner().section.as_deref() == Some(name);
+            links.push((pruned, entry.read_content()?));
+        }
+
+        let inner = entry.inner();
+
+        if !inner.mode.is_internal() && inner.section.as_deref() == Some(name) {
+            if inner.mode.is_immutable() {
+                return Err(Error::Immutable(inner.name.clone()));
+            }
+
+            if inner.refs > 0 {
+                return Err(Error::Referenced(inner.name.clone()));
+            }
+
+            located.push((entry.idx(), entry.end_idx(), inner.name.clone()));
+        }
+
+        entry_opt = entry.next();
+    }
+
+    if located.is_empty() {
+        return Ok(0);
+    }
+
+    // a hard link, which is not pruned, must not lose its target
+    for (_, target) in links.iter().filter(|(pruned, _)| !pruned) {
+        if let Some((_, _, entry_name)) = located
+            .iter()
+            .find(|(_, _, entry_name)| entry_name.as_bytes() == target.as_slice())
+        {
+            return Err(Error::Referenced(entry_name.clone()));
+        }
+    }
+
+    // the entries are modified, the savepoints cannot be restored anymore
+    archive.savepoints.clear();
+
+    // back to front, the positions of the remaining entries do not change
+    for (idx, end_idx, entry_name) in located.iter().rev() {
+        let deps = dependents(&mut archive.pager, &mut archive.tree, entry_name, *idx)?;
+        archive.remove_at(entry_name, *idx, *end_idx, deps, true)?;
+    }
+
+    debug!("section {} pruned, {} entries removed", name, located.len());
+
+    Ok(located.len() as u64)
+}
@@ -0,0 +1,213 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use nuts_container::memory::MemoryBackend;
+
+use crate::tests::setup_container_with_bsize;
+use crate::{Archive, Error};
+
+fn setup_archive() -> Archive<MemoryBackend> {
+    let container = setup_container_with_bsize(512);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.set_section(Some("system"));
+    append(&mut archive, "etc", 10);
+    append(&mut archive, "bin", 700);
+
+    archive.set_section(Some("home"));
+    append(&mut archive, "alice", 20);
+
+    archive.set_section(None);
+    append(&mut archive, "loose", 1);
+
+    archive.set_section(Some("system"));
+    append(&mut archive, "lib", 5);
+
+    archive
+}
+
+fn append(archive: &mut Archive<MemoryBackend>, name: &str, size: usize) {
+    archive
+        .append_file(name)
+        .build()
+        .unwrap()
+        .write_all(&vec![1; size])
+        .unwrap();
+}
+
+fn section_names(archive: &mut Archive<MemoryBackend>, name: Option<&str>) -> Vec<String> {
+    let mut names = vec![];
+    let mut entry_opt = match name {
+        Some(name) => archive.first_in_section(name),
+        None => archive.first(),
+    };
+
+    while let Some(result) = entry_opt {
+        let entry = result.unwrap();
+
+        names.push(entry.name().to_string());
+        entry_opt = match name {
+            Some(_) => entry.next_in_section(),
+            None => entry.next(),
+        };
+    }
+
+    names
+}
+
+#[test]
+fn iterate() {
+    let mut archive = setup_archive();
+
+    assert_eq!(
+        section_names(&mut archive, Some("system")),
+        ["etc", "bin", "lib"]
+    );
+    assert_eq!(section_names(&mut archive, Some("home")), ["alice"]);
+    assert!(section_names(&mut archive, Some("db")).is_empty());
+
+    let entry = archive.lookup("loose").unwrap().unwrap();
+    assert_eq!(entry.section(), None);
+    assert!(entry.next_in_section().is_none());
+}
+
+#[test]
+fn builder_overrides_section() {
+    let mut archive = setup_archive();
+
+    let mut builder = archive.append_file("f1");
+    builder.set_section(Some("db"));
+    builder.build().unwrap();
+
+    let entry = archive.lookup("f1").unwrap().unwrap();
+    assert_eq!(entry.section(), Some("db"));
+    assert_eq!(archive.section(), Some("system"));
+}
+
+#[test]
+fn reopen() {
+    let archive = setup_archive();
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    assert_eq!(archive.section(), None);
+    assert_eq!(
+        archive.lookup("alice").unwrap().unwrap().section(),
+        Some("home")
+    );
+}
+
+#[test]
+fn sections() {
+    let mut archive = setup_archive();
+
+    let sections = archive.sections().unwrap();
+    assert_eq!(sections.len(), 2);
+
+    assert_eq!(sections[0].name(), "system");
+    assert_eq!(sections[0].entries(), 3);
+    assert_eq!(sections[0].size(), 715);
+
+    assert_eq!(sections[1].name(), "home");
+    assert_eq!(sections[1].entries(), 1);
+    assert_eq!(sections[1].size(), 20);
+}
+
+#[test]
+fn prune() {
+    let mut archive = setup_archive();
+
+    assert_eq!(archive.prune_section("system").unwrap(), 3);
+    assert_eq!(section_names(&mut archive, None), ["alice", "loose"]);
+    assert_eq!(archive.info().files, 2);
+
+    assert_eq!(archive.prune_section("system").unwrap(), 0);
+
+    let sections = archive.sections().unwrap();
+    assert_eq!(sections.len(), 1);
+    assert_eq!(sections[0].name(), "home");
+}
+
+#[test]
+fn prune_reuses_blocks() {
+    let mut archive = setup_archive();
+    let nblocks = archive.tree.nblocks();
+
+    archive.prune_section("system").unwrap();
+    assert_eq!(archive.tree.nblocks(), nblocks - 7);
+
+    archive.set_section(Some("system"));
+    append(&mut archive, "bin", 700);
+
+    assert_eq!(archive.tree.free_list().unwrap().len(), 4);
+}
+
+#[test]
+fn prune_immutable() {
+    let mut archive = setup_archive();
+
+    archive.set_immutable("bin", true).unwrap();
+
+    let err = archive.prune_section("system").unwrap_err();
+    assert!(matches!(err, Error::Immutable(name) if name == "bin"));
+    assert_eq!(section_names(&mut archive, Some("system")).len(), 3);
+}
+
+#[test]
+fn prune_referenced() {
+    let mut archive = setup_archive();
+
+    archive.register_reference("lib").unwrap();
+
+    let err = archive.prune_section("system").unwrap_err();
+    assert!(matches!(err, Error::Referenced(name) if name == "lib"));
+    assert_eq!(section_names(&mut archive, Some("system")).len(), 3);
+}
+
+#[test]
+fn prune_linked() {
+    let mut archive = setup_archive();
+
+    archive.set_section(Some("system"));
+    archive.append_hardlink("etc.link", "etc").build().unwrap();
+
+    archive.set_section(None);
+    archive.append_hardlink("bin.link", "bin").build().unwrap();
+
+    // a link into the section from outside
+    let err = archive.prune_section("system").unwrap_err();
+    assert!(matches!(err, Error::Referenced(name) if name == "bin"));
+    assert_eq!(section_names(&mut archive, Some("system")).len(), 4);
+
+    // links inside the section are pruned with their targets
+    archive.remove("bin.link").unwrap();
+    assert_eq!(archive.prune_section("system").unwrap(), 4);
+}
+
+#[test]
+fn prune_frozen() {
+    let mut archive = setup_archive();
+
+    archive.freeze("token").unwrap();
+
+    let err = archive.prune_section("system").unwrap_err();
+    assert!(matches!(err, Error::Frozen));
+}
@@ -0,0 +1,139 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use nuts_container::backend::Backend;
+use std::time::{Duration, Instant};
+
+use crate::pager::Pager;
+
+/// I/O statistics of a single operation.
+///
+/// Returned by the long running operations of the archive, so that a
+/// caller can log and compare runs.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct IoStats {
+    /// Number of blocks read from the container.
+    pub blocks_read: u64,
+
+    /// Number of bytes read from the container.
+    pub bytes_read: u64,
+
+    /// Number of blocks written into the container.
+    pub blocks_written: u64,
+
+    /// Number of bytes written into the container.
+    pub bytes_written: u64,
+
+    /// Number of tree nodes, which were found in the node cache.
+    pub cache_hits: u64,
+
+    /// Number of tree nodes, which were loaded from the container.
+    pub cache_misses: u64,
+
+    /// Time spent in the operation.
+    pub duration: Duration,
+}
+
+impl IoStats {
+    /// Returns the ratio of tree nodes, which were found in the node cache.
+    ///
+    /// Returns [`None`] if no tree node was looked up.
+    pub fn cache_hit_rate(&self) -> Option<f64> {
+        let lookups = self.cache_hits + self.cache_misses;
+
+        if lookups > 0 {
+            Some(self.cache_hits as f64 / lookups as f64)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn read(&mut self, nbytes: usize) {
+        self.blocks_read += 1;
+        self.bytes_read += nbytes as u64;
+    }
+
+    pub(crate) fn written(&mut self, nbytes: usize) {
+        self.blocks_written += 1;
+        self.bytes_written += nbytes as u64;
+    }
+
+    /// Returns the counters, which were increased since `start`.
+    fn since(&self, start: &IoStats) -> IoStats {
+        IoStats {
+            blocks_read: self.blocks_read - start.blocks_read,
+            bytes_read: self.bytes_read - start.bytes_read,
+            blocks_written: self.blocks_written - start.blocks_written,
+            bytes_written: self.bytes_written - start.bytes_written,
+            cache_hits: self.cache_hits - start.cache_hits,
+            cache_misses: self.cache_misses - start.cache_misses,
+            duration: Duration::ZERO,
+        }
+    }
+
+    pub(crate) fn cache(&mut self, hit: bool) {
+        if hit {
+            self.cache_hits += 1;
+        } else {
+            self.cache_misses += 1;
+        }
+    }
+}
+
+/// The result of an import like `Archive::append_tar()` (`tar` feature) or
+/// `Archive::append_zip()` (`zip` feature).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ImportSummary {
+    /// Number of appended entries.
+    pub entries: u64,
+
+    /// I/O statistics of the import.
+    pub stats: IoStats,
+}
+
+/// Measures the I/O of an operation.
+///
+/// Takes a snapshot of the counters of the pager, which is subtracted from
+/// the counters at the end of the operation.
+pub(crate) struct Measure {
+    start: IoStats,
+    instant: Instant,
+}
+
+impl Measure {
+    pub(crate) fn start<B: Backend>(pager: &Pager<B>) -> Measure {
+        Measure {
+            start: *pager.stats(),
+            instant: Instant::now(),
+        }
+    }
+
+    pub(crate) fn finish<B: Backend>(self, pager: &Pager<B>) -> IoStats {
+        let mut stats = pager.stats().since(&self.start);
+
+        stats.duration = self.instant.elapsed();
+        stats
+    }
+}
@@ -0,0 +1,133 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use nuts_container::memory::MemoryBackend;
+use std::time::Duration;
+use tempdir::TempDir;
+
+use crate::maintain::Budget;
+use crate::stats::IoStats;
+use crate::tests::setup_container_with_bsize;
+use crate::Archive;
+
+fn setup_archive() -> Archive<MemoryBackend> {
+    let container = setup_container_with_bsize(512);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.append_directory("d").build().unwrap();
+    archive
+        .append_file("d/f")
+        .with_content(&[1; 40000])
+        .build()
+        .unwrap();
+
+    // starts with an empty node cache
+    Archive::open(archive.into_container()).unwrap()
+}
+
+#[test]
+fn cache_hit_rate() {
+    let mut stats = IoStats::default();
+    assert_eq!(stats.cache_hit_rate(), None);
+
+    stats.cache(true);
+    stats.cache(true);
+    stats.cache(true);
+    stats.cache(false);
+    assert_eq!(stats.cache_hit_rate(), Some(0.75));
+}
+
+#[test]
+fn verify() {
+    let mut archive = setup_archive();
+    let nblocks = archive.info().blocks;
+
+    let report = archive.verify();
+    let stats = report.stats;
+
+    assert!(report.is_ok());
+    assert!(stats.blocks_read >= nblocks);
+    assert!(stats.bytes_read > 0);
+    assert_eq!(stats.blocks_written, 0);
+    assert_eq!(stats.bytes_written, 0);
+    assert!(stats.cache_hits > 0);
+    assert!(stats.cache_misses > 0);
+}
+
+#[test]
+fn per_operation() {
+    let mut archive = setup_archive();
+
+    let first = archive.verify().stats;
+    let second = archive.verify().stats;
+
+    // the second run starts with a warm node cache
+    assert!(first.cache_misses > 0);
+    assert_eq!(
+        first.blocks_read - first.cache_misses,
+        second.blocks_read - second.cache_misses
+    );
+    assert!(second.cache_misses <= first.cache_misses);
+}
+
+#[test]
+fn maintain() {
+    let mut archive = setup_archive();
+
+    let report = archive.maintain(Budget::Blocks(10)).unwrap();
+
+    assert_eq!(report.scrubbed, 10);
+    assert!(report.stats.blocks_read >= 10);
+    assert_eq!(report.stats.blocks_written, 0);
+    assert!(report.stats.duration <= Duration::from_secs(60));
+}
+
+#[test]
+fn unpack() {
+    let mut archive = setup_archive();
+    let nblocks = archive.info().blocks;
+    let dir = TempDir::new("nuts-archive").unwrap();
+
+    let stats = archive.unpack(dir.path()).unwrap();
+
+    assert!(stats.blocks_read >= nblocks);
+    assert_eq!(stats.blocks_written, 0);
+}
+
+#[test]
+fn append() {
+    let mut archive = setup_archive();
+    let measure = super::Measure::start(&archive.pager);
+
+    archive
+        .append_file("f")
+        .with_content(&[2; 1024])
+        .build()
+        .unwrap();
+
+    let stats = measure.finish(&archive.pager);
+
+    // entry, two content blocks and the header
+    assert!(stats.blocks_written >= 4);
+    assert!(stats.bytes_written > 1024);
+    assert!(stats.bytes_written <= stats.blocks_written * 512);
+}
@@ -0,0 +1,287 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use log::debug;
+use nuts_bytes::{FromBytes, Reader, ToBytes, Writer};
+use nuts_container::backend::Backend;
+use sha2::{Digest as _, Sha256};
+use std::convert::TryInto;
+use std::io::{self, Read, Write};
+
+use crate::entry::r#mut::InnerBuilder;
+use crate::entry::Inner;
+use crate::error::ArchiveResult;
+use crate::manifest::ManifestEntry;
+use crate::{Archive, Entry};
+
+const STREAM_MAGIC: [u8; 11] = *b"nuts-stream";
+const STREAM_REVISION: u16 = 1;
+
+/// Maximum number of content bytes stored in a single frame.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Maximum size of a frame accepted by the reader.
+const MAX_FRAME_SIZE: u32 = 2 * CHUNK_SIZE as u32;
+
+// Frames are short-lived, boxing the entry is not worth it
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, FromBytes, ToBytes)]
+enum Frame {
+    Entry(Inner),
+    Data(Vec<u8>),
+    End,
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// Writes entries into a stream.
+///
+/// The stream can be sent over a pipe or socket to another process, which
+/// appends the entries to its own archive using an [`EntryStreamReader`].
+///
+/// The stream starts with a magic and a revision, followed by a sequence of
+/// frames. Each frame is prefixed with its length (4 bytes, big endian). An
+/// entry is encoded as a metadata frame followed by data frames with the
+/// content of the entry. A final end frame terminates the stream.
+pub struct EntryStreamWriter<W: Write> {
+    target: W,
+}
+
+impl<W: Write> EntryStreamWriter<W> {
+    /// Creates a new stream, which is written into `target`.
+    pub fn new(mut target: W) -> io::Result<EntryStreamWriter<W>> {
+        target.write_all(&STREAM_MAGIC)?;
+        target.write_all(&STREAM_REVISION.to_be_bytes())?;
+
+        Ok(EntryStreamWriter { target })
+    }
+
+    /// Writes the given `entry` into the stream.
+    ///
+    /// The entry should be freshly loaded from the archive, for a file
+    /// entry only the content not read so far is written.
+    pub fn write_entry<B: Backend>(&mut self, entry: &mut Entry<'_, B>) -> ArchiveResult<(), B> {
+        debug!("stream entry {}", entry.name());
+
+        self.write_frame(&Frame::Entry(entry.inner().clone()))?;
+
+        match entry {
+            Entry::File(file) => {
+                let mut buf = vec![0; CHUNK_SIZE];
+
+                loop {
+                    let n = file.read(&mut buf)?;
+
+                    if n == 0 {
+                        break;
+                    }
+
+                    self.write_frame(&Frame::Data(buf[..n].to_vec()))?;
+                }
+            }
+            Entry::Directory(_) => {}
+            Entry::Symlink(symlink) => {
+                self.write_frame(&Frame::Data(symlink.target_bytes().to_vec()))?;
+            }
+            Entry::HardLink(link) => {
+                self.write_frame(&Frame::Data(link.target().as_bytes().to_vec()))?;
+            }
+            Entry::Special(special) => {
+                let content = special.kind().content()?;
+
+                if !content.is_empty() {
+                    self.write_frame(&Frame::Data(content))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Terminates the stream and returns the underlaying target.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_frame(&Frame::End)?;
+        self.target.flush()?;
+
+        Ok(self.target)
+    }
+
+    fn write_frame(&mut self, frame: &Frame) -> io::Result<()> {
+        let mut writer = Writer::new(vec![]);
+
+        writer.write(frame).map_err(invalid_data)?;
+
+        let buf = writer.into_target();
+        let len: u32 = buf.len().try_into().map_err(invalid_data)?;
+
+        self.target.write_all(&len.to_be_bytes())?;
+        self.target.write_all(&buf)
+    }
+}
+
+/// Reads entries from a stream created by an [`EntryStreamWriter`].
+pub struct EntryStreamReader<R: Read> {
+    source: R,
+    next: Option<Frame>,
+}
+
+impl<R: Read> EntryStreamReader<R> {
+    /// Opens the stream, which is read from `source`.
+    ///
+    /// # Errors
+    ///
+    /// If `source` does not start with a valid stream header, an error of
+    /// kind [`io::ErrorKind::InvalidData`] is returned.
+    pub fn new(mut source: R) -> io::Result<EntryStreamReader<R>> {
+        let mut magic = [0; STREAM_MAGIC.len()];
+        let mut revision = [0; 2];
+
+        source.read_exact(&mut magic)?;
+        source.read_exact(&mut revision)?;
+
+        if magic != STREAM_MAGIC {
+            return Err(invalid_data("invalid stream magic"));
+        }
+
+        if u16::from_be_bytes(revision) != STREAM_REVISION {
+            return Err(invalid_data("unsupported stream revision"));
+        }
+
+        Ok(EntryStreamReader { source, next: None })
+    }
+
+    /// Appends all entries of the stream at the end of `archive`.
+    ///
+    /// Reads until the end of the stream and returns the number of imported
+    /// entries.
+    pub fn import<B: Backend>(&mut self, archive: &mut Archive<B>) -> ArchiveResult<u64, B> {
+        let mut nentries = 0;
+
+        while self.import_entry(archive)?.is_some() {
+            nentries += 1;
+        }
+
+        Ok(nentries)
+    }
+
+    /// Appends the next entry of the stream at the end of `archive`.
+    ///
+    /// Returns the name, size and digest of the imported content or [`None`]
+    /// at the end of the stream.
+    pub(crate) fn import_entry<B: Backend>(
+        &mut self,
+        archive: &mut Archive<B>,
+    ) -> ArchiveResult<Option<ManifestEntry>, B> {
+        let inner = match self.read_entry_frame()? {
+            Some(inner) => inner,
+            None => return Ok(None),
+        };
+
+        debug!("import entry {}", inner.name);
+
+        let name = inner.name.clone();
+
+        let mut entry = InnerBuilder::with_inner(
+            &mut archive.pager,
+            &archive.header_id,
+            &mut archive.header,
+            &mut archive.tree,
+            inner.without_content(),
+        )
+        .build()?;
+
+        self.read_content(name, |buf| entry.write_all(buf))
+            .map(Some)
+    }
+
+    /// Reads the next entry of the stream without importing it.
+    ///
+    /// Returns the name, size and digest of the content or [`None`] at the
+    /// end of the stream.
+    pub(crate) fn scan_entry<B: Backend>(&mut self) -> ArchiveResult<Option<ManifestEntry>, B> {
+        match self.read_entry_frame()? {
+            Some(inner) => self.read_content(inner.name, |_| Ok(())).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn read_entry_frame(&mut self) -> io::Result<Option<Inner>> {
+        match self.read_frame()? {
+            Frame::Entry(inner) => Ok(Some(inner)),
+            Frame::Data(_) => Err(invalid_data("unexpected data frame")),
+            Frame::End => Ok(None),
+        }
+    }
+
+    /// Passes the data frames of the current entry to `f`.
+    fn read_content<B: Backend, F: FnMut(&[u8]) -> ArchiveResult<(), B>>(
+        &mut self,
+        name: String,
+        mut f: F,
+    ) -> ArchiveResult<ManifestEntry, B> {
+        let mut sha = Sha256::new();
+        let mut size = 0;
+
+        loop {
+            match self.read_frame()? {
+                Frame::Data(buf) => {
+                    f(&buf)?;
+                    sha.update(&buf);
+                    size += buf.len() as u64;
+                }
+                frame => {
+                    self.next = Some(frame);
+                    break;
+                }
+            }
+        }
+
+        Ok(ManifestEntry::new(name, size, sha.finalize().into()))
+    }
+
+    fn read_frame(&mut self) -> io::Result<Frame> {
+        if let Some(frame) = self.next.take() {
+            return Ok(frame);
+        }
+
+        let mut len = [0; 4];
+
+        self.source.read_exact(&mut len)?;
+
+        let len = u32::from_be_bytes(len);
+
+        if len > MAX_FRAME_SIZE {
+            return Err(invalid_data(format!("frame too large: {} bytes", len)));
+        }
+
+        let mut buf = vec![0; len as usize];
+
+        self.source.read_exact(&mut buf)?;
+
+        Reader::new(buf.as_slice()).read().map_err(invalid_data)
+    }
+}
@@ -0,0 +1,164 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use std::io::ErrorKind;
+
+use crate::error::Error;
+use crate::stream::{EntryStreamReader, EntryStreamWriter};
+use crate::tests::setup_container_with_bsize;
+use crate::{Archive, Entry, Group};
+
+fn setup_archive() -> Archive<nuts_container::memory::MemoryBackend> {
+    let container = setup_container_with_bsize(512);
+    Archive::create(container, false).unwrap()
+}
+
+fn export(archive: &mut Archive<nuts_container::memory::MemoryBackend>) -> Vec<u8> {
+    let mut writer = EntryStreamWriter::new(vec![]).unwrap();
+    let mut entry_opt = archive.first();
+
+    while let Some(result) = entry_opt {
+        let mut entry = result.unwrap();
+
+        writer.write_entry(&mut entry).unwrap();
+        entry_opt = entry.next();
+    }
+
+    writer.finish().unwrap()
+}
+
+#[test]
+fn empty() {
+    let mut source = setup_archive();
+    let mut target = setup_archive();
+
+    let buf = export(&mut source);
+    assert_eq!(buf.len(), 11 + 2 + 4 + 4);
+
+    let mut reader = EntryStreamReader::new(buf.as_slice()).unwrap();
+    assert_eq!(reader.import(&mut target).unwrap(), 0);
+    assert!(target.first().is_none());
+}
+
+#[test]
+fn round_trip() {
+    let mut source = setup_archive();
+    let content = (0..150_000).map(|n| (n % 251) as u8).collect::<Vec<_>>();
+
+    let mut builder = source.append_file("f1");
+    builder.set_writable(Group::Other, true);
+
+    let mut entry = builder.build().unwrap();
+    entry.write_all(&content).unwrap();
+
+    source.append_directory("d1").build().unwrap();
+    source.append_symlink("d1/s1", b"f\xff1").build().unwrap();
+    source.append_file("f2").build().unwrap();
+
+    let buf = export(&mut source);
+
+    let mut target = setup_archive();
+    let mut reader = EntryStreamReader::new(buf.as_slice()).unwrap();
+    assert_eq!(reader.import(&mut target).unwrap(), 4);
+    assert_eq!(target.info().files, 4);
+
+    let mut entry = target.first().unwrap().unwrap();
+
+    match entry {
+        Entry::File(ref mut file) => {
+            assert_eq!(file.name(), "f1");
+            assert!(file.can_write(Group::Other));
+            assert_eq!(file.read_vec().unwrap(), content);
+        }
+        _ => panic!("not a file"),
+    }
+
+    let entry = entry.next().unwrap().unwrap();
+    let dir = entry.as_directory().unwrap();
+    assert_eq!(dir.name(), "d1");
+    assert_eq!(dir.children(), 1);
+
+    let entry = entry.next().unwrap().unwrap();
+    let symlink = entry.as_symlink().unwrap();
+    assert_eq!(symlink.name(), "d1/s1");
+    assert_eq!(symlink.target_bytes(), b"f\xff1");
+
+    let entry = entry.next().unwrap().unwrap();
+    assert_eq!(entry.name(), "f2");
+    assert_eq!(entry.size(), 0);
+
+    assert!(entry.next().is_none());
+}
+
+#[test]
+fn invalid_magic() {
+    let mut buf = export(&mut setup_archive());
+    buf[0] += 1;
+
+    let err = EntryStreamReader::new(buf.as_slice()).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn invalid_revision() {
+    let mut buf = export(&mut setup_archive());
+    buf[12] += 1;
+
+    let err = EntryStreamReader::new(buf.as_slice()).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn truncated() {
+    let mut source = setup_archive();
+
+    let mut entry = source.append_file("f1").build().unwrap();
+    entry.write_all(&[1; 100]).unwrap();
+
+    let buf = export(&mut source);
+
+    let mut target = setup_archive();
+    let mut reader = EntryStreamReader::new(&buf[..buf.len() - 8]).unwrap();
+
+    let err = reader.import(&mut target).unwrap_err();
+    let err = match err {
+        Error::Io(err) => err,
+        _ => panic!("invalid error"),
+    };
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn frame_too_large() {
+    let mut buf = export(&mut setup_archive());
+    buf[13..17].copy_from_slice(&u32::MAX.to_be_bytes());
+
+    let mut target = setup_archive();
+    let mut reader = EntryStreamReader::new(buf.as_slice()).unwrap();
+
+    let err = reader.import(&mut target).unwrap_err();
+    let err = match err {
+        Error::Io(err) => err,
+        _ => panic!("invalid error"),
+    };
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
@@ -0,0 +1,137 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use log::debug;
+use nuts_container::backend::Backend;
+use std::collections::HashSet;
+use std::convert::TryInto;
+use std::io::{self, Read, Write};
+
+use crate::error::ArchiveResult;
+use crate::manifest::{Digest, Manifest};
+use crate::stream::{EntryStreamReader, EntryStreamWriter};
+use crate::Archive;
+
+/// Maximum size of a manifest accepted in a sync request.
+const MAX_REQUEST_SIZE: u64 = 256 * 1024 * 1024;
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// Writes the sync request of the receiving `archive` into `writer`.
+///
+/// The request is the manifest of the archive, prefixed with its length
+/// (8 bytes, big endian).
+pub(crate) fn request<B: Backend, W: Write>(
+    archive: &mut Archive<B>,
+    mut writer: W,
+) -> ArchiveResult<(), B> {
+    let buf = archive.manifest()?.export();
+
+    debug!("sync request: {} bytes", buf.len());
+
+    writer.write_all(&(buf.len() as u64).to_be_bytes())?;
+    writer.write_all(&buf)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Reads a sync request from `request` and writes all entries of the sending
+/// `archive`, which are missing in the receiving archive, into `response`.
+///
+/// Returns the number of entries written into `response`.
+pub(crate) fn respond<B: Backend, R: Read, W: Write>(
+    archive: &mut Archive<B>,
+    request: R,
+    response: W,
+) -> ArchiveResult<u64, B> {
+    let remote = read_request(request)?;
+    let present = remote
+        .entries()
+        .iter()
+        .map(|e| (e.name().to_string(), *e.digest()))
+        .collect::<HashSet<(String, Digest)>>();
+
+    let local = archive.manifest()?;
+    let mut writer = EntryStreamWriter::new(response)?;
+    let mut nentries = 0;
+
+    // The manifest lists the entries in archive order, exactly as they are
+    // visited by the traversal.
+    let mut digests = local.entries().iter();
+    let mut entry_opt = archive.first();
+
+    while let Some(result) = entry_opt {
+        let mut entry = result?;
+        let digest = match digests.next() {
+            Some(manifest_entry) => *manifest_entry.digest(),
+            None => break,
+        };
+
+        if present.contains(&(entry.name().to_string(), digest)) {
+            debug!("sync {}: present", entry.name());
+        } else {
+            debug!("sync {}: wanted", entry.name());
+            writer.write_entry(&mut entry)?;
+            nentries += 1;
+        }
+
+        entry_opt = entry.next();
+    }
+
+    writer.finish()?;
+
+    Ok(nentries)
+}
+
+/// Appends the entries of a sync `response` to the receiving `archive`.
+///
+/// Returns the number of appended entries.
+pub(crate) fn receive<B: Backend, R: Read>(
+    archive: &mut Archive<B>,
+    response: R,
+) -> ArchiveResult<u64, B> {
+    EntryStreamReader::new(response)?.import(archive)
+}
+
+fn read_request<R: Read>(mut request: R) -> io::Result<Manifest> {
+    let mut len = [0; 8];
+
+    request.read_exact(&mut len)?;
+
+    let len = u64::from_be_bytes(len);
+
+    if len > MAX_REQUEST_SIZE {
+        return Err(invalid_data(format!("request too large: {} bytes", len)));
+    }
+
+    let mut buf = vec![0; len.try_into().map_err(invalid_data)?];
+
+    request.read_exact(&mut buf)?;
+
+    Manifest::import(&buf).map_err(invalid_data)
+}
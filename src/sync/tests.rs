@@ -0,0 +1,146 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use std::io::ErrorKind;
+
+use crate::error::Error;
+use crate::tests::setup_container_with_bsize;
+use crate::Archive;
+
+fn setup_archive() -> Archive<nuts_container::memory::MemoryBackend> {
+    let container = setup_container_with_bsize(512);
+    Archive::create(container, false).unwrap()
+}
+
+fn append_file(
+    archive: &mut Archive<nuts_container::memory::MemoryBackend>,
+    name: &str,
+    content: &[u8],
+) {
+    let mut entry = archive.append_file(name).build().unwrap();
+    entry.write_all(content).unwrap();
+}
+
+fn names(archive: &mut Archive<nuts_container::memory::MemoryBackend>) -> Vec<String> {
+    let mut names = vec![];
+    let mut entry_opt = archive.first();
+
+    while let Some(result) = entry_opt {
+        let entry = result.unwrap();
+
+        names.push(entry.name().to_string());
+        entry_opt = entry.next();
+    }
+
+    names
+}
+
+fn sync(
+    source: &mut Archive<nuts_container::memory::MemoryBackend>,
+    target: &mut Archive<nuts_container::memory::MemoryBackend>,
+) -> u64 {
+    let mut request = vec![];
+    let mut response = vec![];
+
+    target.sync_request(&mut request).unwrap();
+
+    let sent = source
+        .sync_respond(request.as_slice(), &mut response)
+        .unwrap();
+    let received = target.sync_receive(response.as_slice()).unwrap();
+
+    assert_eq!(sent, received);
+
+    sent
+}
+
+#[test]
+fn into_empty() {
+    let mut source = setup_archive();
+    let mut target = setup_archive();
+
+    append_file(&mut source, "f1", b"123");
+    source.append_directory("d1").build().unwrap();
+    source.append_symlink("s1", "f1").build().unwrap();
+
+    assert_eq!(sync(&mut source, &mut target), 3);
+    assert_eq!(names(&mut target), ["f1", "d1", "s1"]);
+
+    // nothing left to do
+    assert_eq!(sync(&mut source, &mut target), 0);
+}
+
+#[test]
+fn partial() {
+    let mut source = setup_archive();
+    let mut target = setup_archive();
+
+    append_file(&mut source, "f1", b"123");
+    append_file(&mut source, "f2", b"456");
+    append_file(&mut source, "f3", b"789");
+
+    append_file(&mut target, "f1", b"123");
+    append_file(&mut target, "f2", b"xxx");
+
+    assert_eq!(sync(&mut source, &mut target), 2);
+    assert_eq!(names(&mut target), ["f1", "f2", "f2", "f3"]);
+
+    let mut entry = target.lookup("f3").unwrap().unwrap();
+    let file = match entry {
+        crate::Entry::File(ref mut file) => file,
+        _ => panic!("not a file"),
+    };
+    assert_eq!(file.read_vec().unwrap(), b"789");
+}
+
+#[test]
+fn invalid_request() {
+    let mut source = setup_archive();
+    let mut response = vec![];
+
+    let err = source
+        .sync_respond([0, 0, 0, 0, 0, 0, 0, 1, 9].as_slice(), &mut response)
+        .unwrap_err();
+    let err = match err {
+        Error::Io(err) => err,
+        _ => panic!("invalid error"),
+    };
+
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(response.is_empty());
+}
+
+#[test]
+fn request_too_large() {
+    let mut source = setup_archive();
+    let mut response = vec![];
+
+    let err = source
+        .sync_respond(u64::MAX.to_be_bytes().as_slice(), &mut response)
+        .unwrap_err();
+    let err = match err {
+        Error::Io(err) => err,
+        _ => panic!("invalid error"),
+    };
+
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
@@ -0,0 +1,432 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use ::tar::{Builder, EntryType, Header};
+use chrono::{TimeZone, Utc};
+use log::debug;
+use nuts_container::backend::Backend;
+use std::convert::TryInto;
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::PathBuf;
+
+use crate::entry::Inner;
+use crate::error::{ArchiveResult, Error};
+use crate::import::name_from_path;
+use crate::resume::{self, ImportCheckpoint};
+use crate::unsupported::{FeaturePolicy, UnsupportedFeature};
+use crate::validate::{ImportReport, ReportBuilder};
+use crate::{Archive, Entry, FileEntry, SpecialKind};
+
+/// Size of a block in a tar stream.
+const TAR_BLOCK_SIZE: u64 = 512;
+
+/// Prefixes of pax keys, which store extended attributes.
+const XATTR_PREFIXES: [&[u8]; 2] = [b"SCHILY.xattr.", b"LIBARCHIVE.xattr."];
+
+/// Applies mode, owner and modification time of the tar `header` to
+/// `builder`.
+macro_rules! set_attributes {
+    ($builder:ident, $header:ident) => {
+        if let Ok(mode) = $header.mode() {
+            $builder.set_unix_mode(mode);
+        }
+
+        $builder.set_uid($header.uid().ok().and_then(|uid| uid.try_into().ok()));
+        $builder.set_gid($header.gid().ok().and_then(|gid| gid.try_into().ok()));
+
+        if let Some(modified) = $header
+            .mtime()
+            .ok()
+            .and_then(|secs| secs.try_into().ok())
+            .and_then(|secs| Utc.timestamp_opt(secs, 0).earliest())
+        {
+            $builder.set_modified(modified);
+        }
+    };
+}
+
+/// Returns the kind of a special file described by `header`.
+fn special_kind(header: &Header) -> Option<SpecialKind> {
+    let major = header.device_major().ok().flatten().unwrap_or(0);
+    let minor = header.device_minor().ok().flatten().unwrap_or(0);
+
+    match header.entry_type() {
+        EntryType::Char => Some(SpecialKind::CharDevice { major, minor }),
+        EntryType::Block => Some(SpecialKind::BlockDevice { major, minor }),
+        EntryType::Fifo => Some(SpecialKind::Fifo),
+        _ => None,
+    }
+}
+
+/// Tests whether the entry type of `header` can be represented in the
+/// archive.
+fn is_supported(header: &Header) -> bool {
+    match header.entry_type() {
+        EntryType::Regular
+        | EntryType::Continuous
+        | EntryType::Directory
+        | EntryType::Symlink
+        | EntryType::Link => true,
+        _ => special_kind(header).is_some(),
+    }
+}
+
+/// Applies `policy` to the attributes of `tar_entry`, which cannot be
+/// represented in the archive.
+fn check_attributes<B: Backend, R: Read>(
+    policy: &FeaturePolicy,
+    tar_entry: &mut ::tar::Entry<R>,
+    name: &str,
+) -> ArchiveResult<(), B> {
+    let header = tar_entry.header();
+    let too_large = |id: io::Result<u64>| id.is_ok_and(|id| id > u32::MAX as u64);
+
+    if too_large(header.uid()) || too_large(header.gid()) {
+        policy.handle(UnsupportedFeature::Owner, name)?;
+    }
+
+    if let Some(extensions) = tar_entry.pax_extensions()? {
+        let xattrs = extensions.filter_map(Result::ok).any(|ext| {
+            let key = ext.key_bytes();
+            XATTR_PREFIXES.iter().any(|prefix| key.starts_with(prefix))
+        });
+
+        if xattrs {
+            policy.handle(UnsupportedFeature::ExtendedAttributes, name)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn link_target<R: Read>(tar_entry: &::tar::Entry<R>) -> io::Result<String> {
+    match tar_entry.link_name()? {
+        Some(path) => name_from_path(&path),
+        None => Err(io::Error::new(ErrorKind::InvalidData, "missing link name")),
+    }
+}
+
+/// Appends `tar_entry` to `archive`.
+///
+/// Returns `false` if the entry was skipped because of `policy`.
+fn append_entry<B: Backend, R: Read>(
+    archive: &mut Archive<B>,
+    policy: &FeaturePolicy,
+    tar_entry: &mut ::tar::Entry<R>,
+    buf: &mut [u8],
+) -> ArchiveResult<bool, B> {
+    let header = tar_entry.header().clone();
+    let name = name_from_path(&tar_entry.path()?)?;
+
+    if !is_supported(&header) {
+        debug!("tar: {} has type {:?}", name, header.entry_type());
+        policy.handle(UnsupportedFeature::EntryType, &name)?;
+        return Ok(false);
+    }
+
+    check_attributes(policy, tar_entry, &name)?;
+
+    match header.entry_type() {
+        EntryType::Regular | EntryType::Continuous => {
+            let mut builder = archive.append_file(&name);
+            set_attributes!(builder, header);
+
+            let mut entry = builder.build()?;
+
+            loop {
+                let n = match tar_entry.read(buf) {
+                    Ok(0) => break,
+                    Ok(n) => n,
+                    Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                    Err(err) => return Err(err.into()),
+                };
+
+                entry.write_all(&buf[..n])?;
+            }
+        }
+        EntryType::Directory => {
+            let mut builder = archive.append_directory(&name);
+            set_attributes!(builder, header);
+            builder.build()?;
+        }
+        EntryType::Symlink => {
+            let target = tar_entry.link_name_bytes().unwrap_or_default().into_owned();
+            let mut builder = archive.append_symlink(&name, target);
+            set_attributes!(builder, header);
+            builder.build()?;
+        }
+        EntryType::Link => {
+            let target = link_target(tar_entry)?;
+            let mut builder = archive.append_hardlink(&name, target);
+            set_attributes!(builder, header);
+            builder.build()?;
+        }
+        _ => {
+            if let Some(kind) = special_kind(&header) {
+                let mut builder = archive.append_special(&name, kind);
+                set_attributes!(builder, header);
+                builder.build()?;
+            }
+        }
+    }
+
+    debug!("tar: {} appended", name);
+
+    Ok(true)
+}
+
+/// Appends all entries of the tar stream read from `reader` to `archive`.
+///
+/// Returns the number of appended entries.
+pub(crate) fn append_tar<B: Backend, R: Read>(
+    archive: &mut Archive<B>,
+    reader: R,
+) -> ArchiveResult<u64, B> {
+    let mut tar = ::tar::Archive::new(reader);
+    let mut buf = vec![0; archive.pager.block_size() as usize];
+    let policy = archive.feature_policy;
+    let mut nentries = 0;
+
+    for result in tar.entries()? {
+        if append_entry(archive, &policy, &mut result?, &mut buf)? {
+            nentries += 1;
+        }
+    }
+
+    Ok(nentries)
+}
+
+/// Appends the entries of the tar stream returned by `open` to `archive`,
+/// starting at `checkpoint`.
+///
+/// `open` is called with the offset of the checkpoint and must return a
+/// reader positioned at this offset of the tar stream. See
+/// [`resume::import()`] for the retry logic.
+///
+/// Returns the number of appended entries.
+pub(crate) fn append_tar_resumable<B, R, F>(
+    archive: &mut Archive<B>,
+    checkpoint: &mut ImportCheckpoint,
+    retries: u32,
+    mut open: F,
+) -> ArchiveResult<u64, B>
+where
+    B: Backend,
+    R: Read,
+    F: FnMut(u64) -> io::Result<R>,
+{
+    let mut buf = vec![0; archive.pager.block_size() as usize];
+    let policy = archive.feature_policy;
+
+    resume::import(
+        archive,
+        checkpoint,
+        retries,
+        |checkpoint| open(checkpoint.offset()),
+        |archive, source, progress| {
+            let base = progress.checkpoint().offset();
+            let mut tar = ::tar::Archive::new(source);
+
+            for result in tar.entries()? {
+                let mut tar_entry = result?;
+                let appended = append_entry(archive, &policy, &mut tar_entry, &mut buf)?;
+
+                // Consume the rest of the entry, the next header starts at
+                // the following block.
+                io::copy(&mut tar_entry, &mut io::sink())?;
+
+                let offset = base + progress.position().div_ceil(TAR_BLOCK_SIZE) * TAR_BLOCK_SIZE;
+
+                progress.commit(archive, appended, offset);
+            }
+
+            Ok(())
+        },
+    )
+}
+
+/// Parses the tar stream read from `reader` like [`append_tar()`] does,
+/// without modifying `archive`.
+pub(crate) fn validate_tar<B: Backend, R: Read>(
+    archive: &mut Archive<B>,
+    reader: R,
+) -> ArchiveResult<ImportReport, B> {
+    archive.header.check_mutable()?;
+
+    let mut tar = ::tar::Archive::new(reader);
+    let mut report = ReportBuilder::new(archive)?;
+    let policy = archive.feature_policy;
+
+    for result in tar.entries()? {
+        let mut tar_entry = result?;
+        let header = tar_entry.header().clone();
+        let name = name_from_path(&tar_entry.path()?)?;
+
+        if !is_supported(&header) {
+            policy.handle(UnsupportedFeature::EntryType, &name)?;
+            report.skip(&name);
+            continue;
+        }
+
+        check_attributes(&policy, &mut tar_entry, &name)?;
+
+        let size = match header.entry_type() {
+            EntryType::Regular | EntryType::Continuous => tar_entry.size(),
+            EntryType::Directory => 0,
+            EntryType::Symlink => tar_entry
+                .link_name_bytes()
+                .map_or(0, |target| target.len() as u64),
+            EntryType::Link => link_target(&tar_entry)?.len() as u64,
+            _ => special_kind(&header)
+                .and_then(|kind| kind.content().ok())
+                .map_or(0, |content| content.len() as u64),
+        };
+
+        report.add(&name, size);
+    }
+
+    Ok(report.build())
+}
+
+/// Adapts the content of a [`FileEntry`] to [`Read`].
+///
+/// An error of the archive is kept, so that it can be returned instead of
+/// the [`io::Error`] passed through the tar builder.
+struct ContentReader<'a, 'b, B: Backend> {
+    entry: &'b mut FileEntry<'a, B>,
+    err: Option<Error<B>>,
+}
+
+impl<'a, 'b, B: Backend> Read for ContentReader<'a, 'b, B> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.entry.read(buf).map_err(|err| {
+            let io_err = io::Error::other(err.to_string());
+            self.err = Some(err);
+            io_err
+        })
+    }
+}
+
+/// Creates a tar header with the access rights, owner and modification time
+/// of `inner`.
+fn tar_header(inner: &Inner) -> Header {
+    let mut header = Header::new_gnu();
+
+    header.set_size(0);
+    header.set_mode(inner.mode.to_unix() & 0o7777);
+    header.set_uid(inner.uid.unwrap_or(0) as u64);
+    header.set_gid(inner.gid.unwrap_or(0) as u64);
+    header.set_mtime(inner.tstamps.modified().timestamp().max(0) as u64);
+
+    header
+}
+
+#[cfg(unix)]
+fn symlink_target(target: &[u8]) -> PathBuf {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    PathBuf::from(OsStr::from_bytes(target))
+}
+
+#[cfg(not(unix))]
+fn symlink_target(target: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(target).into_owned())
+}
+
+/// Writes all entries of `archive` as a tar stream into `writer`.
+///
+/// Returns the number of entries written into the tar stream.
+pub(crate) fn write_tar<B: Backend, W: Write>(
+    archive: &mut Archive<B>,
+    writer: W,
+) -> ArchiveResult<u64, B> {
+    let mut builder = Builder::new(writer);
+    let mut nentries = 0;
+    let mut entry_opt = archive.first();
+
+    while let Some(result) = entry_opt {
+        let mut entry = result?;
+        let name = entry.name().to_string();
+        let mut header = tar_header(entry.inner());
+
+        match &mut entry {
+            Entry::File(file) => {
+                header.set_entry_type(EntryType::Regular);
+                header.set_size(file.size());
+
+                let mut reader = ContentReader {
+                    entry: file,
+                    err: None,
+                };
+
+                if let Err(err) = builder.append_data(&mut header, &name, &mut reader) {
+                    return Err(reader.err.take().unwrap_or_else(|| err.into()));
+                }
+            }
+            Entry::Directory(_) => {
+                header.set_entry_type(EntryType::Directory);
+                builder.append_data(&mut header, &name, io::empty())?;
+            }
+            Entry::Symlink(symlink) => {
+                let target = symlink_target(symlink.target_bytes());
+
+                header.set_entry_type(EntryType::Symlink);
+                builder.append_link(&mut header, &name, target)?;
+            }
+            Entry::HardLink(link) => {
+                header.set_entry_type(EntryType::Link);
+                builder.append_link(&mut header, &name, link.target())?;
+            }
+            Entry::Special(special) => {
+                let (entry_type, major, minor) = match special.kind() {
+                    SpecialKind::Fifo => (EntryType::Fifo, 0, 0),
+                    SpecialKind::CharDevice { major, minor } => (EntryType::Char, major, minor),
+                    SpecialKind::BlockDevice { major, minor } => (EntryType::Block, major, minor),
+                    SpecialKind::Socket => {
+                        // tar has no type for sockets
+                        debug!("tar: skip socket {}", name);
+                        entry_opt = entry.next();
+                        continue;
+                    }
+                };
+
+                header.set_entry_type(entry_type);
+                header.set_device_major(major)?;
+                header.set_device_minor(minor)?;
+                builder.append_data(&mut header, &name, io::empty())?;
+            }
+        }
+
+        debug!("tar: {} written", name);
+        nentries += 1;
+
+        entry_opt = entry.next();
+    }
+
+    builder.into_inner()?.flush()?;
+
+    Ok(nentries)
+}
@@ -0,0 +1,468 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use ::tar::{Builder, EntryType, Header};
+use std::io::{self, ErrorKind, Read};
+
+use crate::error::Error;
+use crate::tests::setup_container_with_bsize;
+use crate::{Archive, Entry, FeatureAction, ImportCheckpoint, SpecialKind, UnsupportedFeature};
+
+fn setup_archive() -> Archive<nuts_container::memory::MemoryBackend> {
+    let container = setup_container_with_bsize(512);
+    Archive::create(container, false).unwrap()
+}
+
+fn header(entry_type: EntryType, mode: u32, size: u64) -> Header {
+    let mut header = Header::new_gnu();
+
+    header.set_entry_type(entry_type);
+    header.set_mode(mode);
+    header.set_uid(1000);
+    header.set_gid(100);
+    header.set_mtime(1_700_000_000);
+    header.set_size(size);
+
+    header
+}
+
+fn tar_builder() -> Builder<Vec<u8>> {
+    let mut builder = Builder::new(vec![]);
+    let content = [7; 1500];
+
+    let mut dir = header(EntryType::Directory, 0o750, 0);
+    builder.append_data(&mut dir, "d/", &[][..]).unwrap();
+
+    let mut file = header(EntryType::Regular, 0o640, content.len() as u64);
+    builder
+        .append_data(&mut file, "./d/f", &content[..])
+        .unwrap();
+
+    let mut symlink = header(EntryType::Symlink, 0o777, 0);
+    builder.append_link(&mut symlink, "s", "d/f").unwrap();
+
+    let mut hardlink = header(EntryType::Link, 0o640, 0);
+    builder.append_link(&mut hardlink, "h", "d/f").unwrap();
+
+    let mut device = header(EntryType::Char, 0o600, 0);
+    device.set_device_major(1).unwrap();
+    device.set_device_minor(3).unwrap();
+    builder.append_data(&mut device, "null", &[][..]).unwrap();
+
+    builder
+}
+
+fn setup_tar() -> Vec<u8> {
+    tar_builder().into_inner().unwrap()
+}
+
+/// A reader, which fails like a dropped connection after `limit` bytes.
+struct Dropping<'a> {
+    data: &'a [u8],
+    limit: usize,
+}
+
+impl<'a> Dropping<'a> {
+    fn new(data: &'a [u8], limit: usize) -> Dropping<'a> {
+        Dropping { data, limit }
+    }
+}
+
+impl<'a> Read for Dropping<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.limit == 0 && !self.data.is_empty() {
+            return Err(io::Error::from(ErrorKind::ConnectionReset));
+        }
+
+        let n = buf.len().min(self.limit).min(self.data.len());
+
+        buf[..n].copy_from_slice(&self.data[..n]);
+
+        self.data = &self.data[n..];
+        self.limit -= n;
+
+        Ok(n)
+    }
+}
+
+fn names(archive: &mut Archive<nuts_container::memory::MemoryBackend>) -> Vec<String> {
+    let mut names = vec![];
+    let mut entry_opt = archive.first();
+
+    while let Some(entry) = entry_opt {
+        let entry = entry.unwrap();
+        names.push(entry.name().to_string());
+        entry_opt = entry.next();
+    }
+
+    names
+}
+
+fn append_socket(builder: &mut Builder<Vec<u8>>) {
+    let mut socket = header(EntryType::new(b'9'), 0o600, 0);
+    builder.append_data(&mut socket, "sock", &[][..]).unwrap();
+}
+
+#[test]
+fn entries() {
+    let mut archive = setup_archive();
+
+    let summary = archive.append_tar(setup_tar().as_slice()).unwrap();
+    assert_eq!(summary.entries, 5);
+    assert!(summary.stats.blocks_written >= 5);
+
+    let entry = archive.lookup("d").unwrap().unwrap();
+    assert!(entry.is_directory());
+    assert_eq!(entry.unix_mode() & 0o7777, 0o750);
+
+    let mut entry = archive.lookup("d/f").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.size(), 1500);
+    assert_eq!(entry.unix_mode() & 0o7777, 0o640);
+    assert_eq!(entry.uid(), Some(1000));
+    assert_eq!(entry.gid(), Some(100));
+    assert_eq!(entry.modified().timestamp(), 1_700_000_000);
+    assert_eq!(entry.read_vec().unwrap(), [7; 1500]);
+
+    let entry = archive
+        .lookup("s")
+        .unwrap()
+        .unwrap()
+        .into_symlink()
+        .unwrap();
+    assert_eq!(entry.target(), "d/f");
+
+    let entry = archive
+        .lookup("h")
+        .unwrap()
+        .unwrap()
+        .into_hardlink()
+        .unwrap();
+    assert_eq!(entry.target(), "d/f");
+
+    match archive.lookup("null").unwrap().unwrap() {
+        Entry::Special(entry) => {
+            assert_eq!(entry.kind(), SpecialKind::CharDevice { major: 1, minor: 3 })
+        }
+        _ => panic!("not a special file"),
+    }
+}
+
+#[test]
+fn empty() {
+    let mut archive = setup_archive();
+    let tar = Builder::new(vec![]).into_inner().unwrap();
+
+    assert_eq!(archive.append_tar(tar.as_slice()).unwrap().entries, 0);
+    assert!(archive.first().is_none());
+}
+
+#[test]
+fn parent_dir() {
+    let mut archive = setup_archive();
+    let mut tar = vec![];
+    let mut header = header(EntryType::Regular, 0o644, 0);
+
+    // Builder::append_data() rejects `..`, so the name is set directly
+    header.as_old_mut().name[..6].copy_from_slice(b"../bad");
+    header.set_cksum();
+    tar.extend_from_slice(header.as_bytes());
+    tar.extend_from_slice(&[0; 1024]);
+
+    let err = archive.append_tar(tar.as_slice()).unwrap_err();
+    assert!(matches!(err, Error::Io(ref err) if err.kind() == ErrorKind::InvalidInput));
+    assert!(archive.first().is_none());
+}
+
+#[test]
+fn validate() {
+    let mut archive = setup_archive();
+    let mut builder = tar_builder();
+
+    append_socket(&mut builder);
+
+    let tar = builder.into_inner().unwrap();
+
+    archive.append_file("s").build().unwrap();
+
+    let report = archive.validate_tar(tar.as_slice()).unwrap();
+
+    assert_eq!(report.entries(), 5);
+    assert_eq!(report.content_size(), 1500 + 3 + 3 + 8);
+    assert_eq!(report.blocks(), 5 + 3 + 1 + 1 + 1);
+    assert_eq!(report.collisions(), ["s"]);
+    assert_eq!(report.unsupported(), ["sock"]);
+    assert!(report.fits());
+
+    // the archive is not modified
+    let entry = archive.first().unwrap().unwrap();
+    assert_eq!(entry.name(), "s");
+    assert!(entry.next().is_none());
+}
+
+#[test]
+fn write() {
+    let mut archive = setup_archive();
+    let mut tar = vec![];
+
+    archive.append_tar(setup_tar().as_slice()).unwrap();
+    archive
+        .append_special("sock", SpecialKind::Socket)
+        .build()
+        .unwrap();
+
+    assert_eq!(archive.write_tar(&mut tar).unwrap(), 5);
+
+    let mut entries = ::tar::Archive::new(tar.as_slice());
+    let mut entries = entries.entries().unwrap();
+
+    let entry = entries.next().unwrap().unwrap();
+    assert_eq!(entry.header().entry_type(), EntryType::Directory);
+    assert_eq!(entry.path().unwrap().to_str(), Some("d"));
+    assert_eq!(entry.header().mode().unwrap(), 0o750);
+
+    let mut entry = entries.next().unwrap().unwrap();
+    let mut content = vec![];
+    assert_eq!(entry.header().entry_type(), EntryType::Regular);
+    assert_eq!(entry.path().unwrap().to_str(), Some("d/f"));
+    assert_eq!(entry.header().mode().unwrap(), 0o640);
+    assert_eq!(entry.header().uid().unwrap(), 1000);
+    assert_eq!(entry.header().gid().unwrap(), 100);
+    assert_eq!(entry.header().mtime().unwrap(), 1_700_000_000);
+    entry.read_to_end(&mut content).unwrap();
+    assert_eq!(content, [7; 1500]);
+
+    let entry = entries.next().unwrap().unwrap();
+    assert_eq!(entry.header().entry_type(), EntryType::Symlink);
+    assert_eq!(entry.path().unwrap().to_str(), Some("s"));
+    assert_eq!(entry.link_name().unwrap().unwrap().to_str(), Some("d/f"));
+
+    let entry = entries.next().unwrap().unwrap();
+    assert_eq!(entry.header().entry_type(), EntryType::Link);
+    assert_eq!(entry.path().unwrap().to_str(), Some("h"));
+    assert_eq!(entry.link_name().unwrap().unwrap().to_str(), Some("d/f"));
+
+    let entry = entries.next().unwrap().unwrap();
+    assert_eq!(entry.header().entry_type(), EntryType::Char);
+    assert_eq!(entry.path().unwrap().to_str(), Some("null"));
+    assert_eq!(entry.header().device_major().unwrap(), Some(1));
+    assert_eq!(entry.header().device_minor().unwrap(), Some(3));
+
+    assert!(entries.next().is_none());
+}
+
+#[test]
+fn write_long_name() {
+    let mut archive = setup_archive();
+    let name = "x".repeat(200);
+    let mut tar = vec![];
+
+    archive.append_file(&name).build().unwrap();
+
+    assert_eq!(archive.write_tar(&mut tar).unwrap(), 1);
+
+    let mut other = setup_archive();
+
+    assert_eq!(other.append_tar(tar.as_slice()).unwrap().entries, 1);
+    assert!(other.lookup(&name).is_some());
+}
+
+#[test]
+fn unsupported_entry_type() {
+    for action in [FeatureAction::Skip, FeatureAction::Warn] {
+        let mut archive = setup_archive();
+        let mut builder = tar_builder();
+
+        append_socket(&mut builder);
+        archive.set_unsupported_action(UnsupportedFeature::EntryType, action);
+
+        let tar = builder.into_inner().unwrap();
+        assert_eq!(archive.append_tar(tar.as_slice()).unwrap().entries, 5);
+        assert!(archive.lookup("sock").is_none());
+    }
+}
+
+#[test]
+fn unsupported_entry_type_error() {
+    let mut archive = setup_archive();
+    let mut builder = tar_builder();
+
+    append_socket(&mut builder);
+    archive.set_unsupported_action(UnsupportedFeature::EntryType, FeatureAction::Error);
+
+    let tar = builder.into_inner().unwrap();
+
+    let err = archive.validate_tar(tar.as_slice()).unwrap_err();
+    assert!(
+        matches!(err, Error::Unsupported(ref name, UnsupportedFeature::EntryType) if name == "sock")
+    );
+
+    let err = archive.append_tar(tar.as_slice()).unwrap_err();
+    assert!(
+        matches!(err, Error::Unsupported(ref name, UnsupportedFeature::EntryType) if name == "sock")
+    );
+}
+
+#[test]
+fn unsupported_xattrs() {
+    let mut archive = setup_archive();
+    let mut builder = Builder::new(vec![]);
+    let mut file = header(EntryType::Regular, 0o644, 0);
+
+    builder
+        .append_pax_extensions([("SCHILY.xattr.user.foo", &b"bar"[..])])
+        .unwrap();
+    builder.append_data(&mut file, "f", &[][..]).unwrap();
+
+    let tar = builder.into_inner().unwrap();
+
+    assert_eq!(archive.append_tar(tar.as_slice()).unwrap().entries, 1);
+
+    archive.set_unsupported_action(UnsupportedFeature::ExtendedAttributes, FeatureAction::Error);
+
+    let err = archive.append_tar(tar.as_slice()).unwrap_err();
+    assert!(
+        matches!(err, Error::Unsupported(ref name, UnsupportedFeature::ExtendedAttributes) if name == "f")
+    );
+}
+
+#[test]
+fn unsupported_owner() {
+    let mut archive = setup_archive();
+    let mut builder = Builder::new(vec![]);
+    let mut file = header(EntryType::Regular, 0o644, 0);
+
+    file.set_uid(u32::MAX as u64 + 1);
+    builder.append_data(&mut file, "f", &[][..]).unwrap();
+
+    let tar = builder.into_inner().unwrap();
+
+    archive.set_unsupported_action(UnsupportedFeature::Owner, FeatureAction::Skip);
+    assert_eq!(archive.append_tar(tar.as_slice()).unwrap().entries, 1);
+
+    let entry = archive.lookup("f").unwrap().unwrap();
+    assert_eq!(entry.uid(), None);
+    assert_eq!(entry.gid(), Some(100));
+
+    archive.set_unsupported_action(UnsupportedFeature::Owner, FeatureAction::Error);
+
+    let err = archive.append_tar(tar.as_slice()).unwrap_err();
+    assert!(matches!(err, Error::Unsupported(ref name, UnsupportedFeature::Owner) if name == "f"));
+}
+
+#[test]
+fn resume() {
+    let mut archive = setup_archive();
+    let tar = setup_tar();
+    let mut checkpoint = ImportCheckpoint::default();
+    let mut offsets = vec![];
+
+    // The connection drops in the middle of the content of d/f.
+    let n = archive
+        .append_tar_resumable(&mut checkpoint, 1, |offset| {
+            let limit = if offsets.is_empty() { 1800 } else { usize::MAX };
+
+            offsets.push(offset);
+            Ok(Dropping::new(&tar[offset as usize..], limit))
+        })
+        .unwrap();
+
+    assert_eq!(n.entries, 5);
+    assert_eq!(offsets, [0, 512]);
+    assert_eq!(checkpoint, ImportCheckpoint::new(5, 4096));
+    assert_eq!(names(&mut archive), ["d", "d/f", "s", "h", "null"]);
+
+    let mut entry = archive.lookup("d/f").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), [7; 1500]);
+}
+
+#[test]
+fn resume_give_up() {
+    let mut archive = setup_archive();
+    let tar = setup_tar();
+    let mut checkpoint = ImportCheckpoint::default();
+    let mut offsets = vec![];
+
+    let err = archive
+        .append_tar_resumable(&mut checkpoint, 2, |offset| {
+            offsets.push(offset);
+            Ok(Dropping::new(&tar[offset as usize..], 1800))
+        })
+        .unwrap_err();
+
+    assert!(matches!(err, Error::Io(ref err) if err.kind() == ErrorKind::ConnectionReset));
+    assert_eq!(offsets, [0, 512, 512]);
+    assert_eq!(checkpoint, ImportCheckpoint::new(1, 512));
+    assert_eq!(names(&mut archive), ["d"]);
+
+    // Resume later with a working source.
+    let n = archive
+        .append_tar_resumable(&mut checkpoint, 0, |offset| Ok(&tar[offset as usize..]))
+        .unwrap();
+
+    assert_eq!(n.entries, 4);
+    assert_eq!(checkpoint, ImportCheckpoint::new(5, 4096));
+    assert_eq!(names(&mut archive), ["d", "d/f", "s", "h", "null"]);
+}
+
+#[test]
+fn resume_open_error() {
+    let mut archive = setup_archive();
+    let tar = setup_tar();
+    let mut checkpoint = ImportCheckpoint::default();
+    let mut nopen = 0;
+
+    let n = archive
+        .append_tar_resumable(&mut checkpoint, 3, |offset| {
+            nopen += 1;
+
+            if nopen < 3 {
+                Err(io::Error::from(ErrorKind::ConnectionRefused))
+            } else {
+                Ok(&tar[offset as usize..])
+            }
+        })
+        .unwrap();
+
+    assert_eq!(n.entries, 5);
+    assert_eq!(nopen, 3);
+}
+
+#[test]
+fn resume_malformed() {
+    let mut archive = setup_archive();
+    let mut tar = setup_tar();
+    let mut nopen = 0;
+
+    // Breaks the checksum of the header of d/f.
+    tar[512] = b'x';
+
+    let err = archive
+        .append_tar_resumable(&mut ImportCheckpoint::default(), 3, |offset| {
+            nopen += 1;
+            Ok(&tar[offset as usize..])
+        })
+        .unwrap_err();
+
+    assert!(matches!(err, Error::Io(_)));
+    assert_eq!(nopen, 1);
+    assert_eq!(names(&mut archive), ["d"]);
+}
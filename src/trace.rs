@@ -0,0 +1,49 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use nuts_container::backend::Backend;
+
+/// A block allocation recorded by the allocation trace.
+///
+/// Every block aquired by the tree of the archive is recorded together with
+/// the entry it was aquired for. The trace is only available, if the
+/// `trace` feature is enabled.
+#[derive(Debug)]
+pub struct Allocation<B: Backend> {
+    /// Tree index of the entry the block was aquired for.
+    pub entry: usize,
+
+    /// Tree index of the aquired block.
+    pub idx: usize,
+
+    /// Id of the aquired block.
+    pub id: B::Id,
+}
+
+impl<B: Backend> Allocation<B> {
+    pub(crate) fn new(entry: usize, idx: usize, id: B::Id) -> Allocation<B> {
+        Allocation { entry, idx, id }
+    }
+}
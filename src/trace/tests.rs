@@ -0,0 +1,71 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use crate::tests::setup_container_with_bsize;
+use crate::Archive;
+
+#[test]
+fn empty() {
+    let container = setup_container_with_bsize(106);
+    let archive = Archive::create(container, false).unwrap();
+
+    assert!(archive.allocation_trace().is_empty());
+}
+
+#[test]
+fn entries() {
+    let container = setup_container_with_bsize(106);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.append_directory("d").build().unwrap();
+
+    let mut entry = archive.append_file("f").build().unwrap();
+    entry.write_all(&[1; 107]).unwrap();
+
+    let trace = archive
+        .allocation_trace()
+        .iter()
+        .map(|a| (a.entry, a.idx, a.id))
+        .collect::<Vec<_>>();
+
+    assert_eq!(trace.len(), 4);
+
+    for (i, (entry, idx, id)) in trace.into_iter().enumerate() {
+        let expected = archive.tree.lookup(&mut archive.pager, i).unwrap().unwrap();
+
+        assert_eq!(entry, if i == 0 { 0 } else { 1 });
+        assert_eq!(idx, i);
+        assert_eq!(&id, expected);
+    }
+}
+
+#[test]
+fn clear() {
+    let container = setup_container_with_bsize(106);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.append_directory("d").build().unwrap();
+    assert_eq!(archive.allocation_trace().len(), 1);
+
+    archive.clear_allocation_trace();
+    assert!(archive.allocation_trace().is_empty());
+}
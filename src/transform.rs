@@ -0,0 +1,256 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use nuts_container::backend::Backend;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{self, ErrorKind};
+use std::sync::Arc;
+
+use crate::compression::{Compression, Compressor, Decompressor};
+use crate::entry::Inner;
+use crate::error::{ArchiveResult, Error};
+
+/// Size of the length in front of each transformed chunk.
+const FRAME_LEN_SIZE: usize = 4;
+
+/// A transformation of the content of file entries.
+///
+/// Register the transformation with
+/// [`Archive::register_transform()`](crate::Archive::register_transform)
+/// and enable it for an entry with
+/// [`FileBuilder::add_transform()`](crate::FileBuilder::add_transform).
+/// Each chunk passed to [`EntryMut::write()`](crate::EntryMut::write) is
+/// encoded before it is stored and decoded again, when the entry is read.
+/// An entry remembers the names of its transformations, so the same
+/// transformations must be registered to read it again.
+///
+/// A transformation works on whole chunks, it is free to change their
+/// size, e.g. by adding a nonce and a tag for an extra layer of encryption
+/// or by replacing sensitive parts of the content. Each encoded chunk is
+/// stored with its length and passed to [`Transform::decode()`] as a whole.
+pub trait Transform: Send + Sync {
+    /// Returns the name of the transformation.
+    ///
+    /// The name is stored with each entry, which uses the transformation.
+    /// It must not change as long as such entries exist.
+    fn name(&self) -> &str;
+
+    /// Encodes a chunk of content, before it is stored.
+    fn encode(&self, chunk: &[u8]) -> io::Result<Vec<u8>>;
+
+    /// Decodes a chunk, which was encoded by [`Transform::encode()`].
+    fn decode(&self, chunk: &[u8]) -> io::Result<Vec<u8>>;
+}
+
+/// The transformations registered with an archive.
+#[derive(Clone, Default)]
+pub struct Transforms(Vec<Arc<dyn Transform>>);
+
+impl Transforms {
+    /// Registers `transform`, a transformation with the same name is
+    /// replaced.
+    pub fn register(&mut self, transform: Arc<dyn Transform>) {
+        self.0.retain(|t| t.name() != transform.name());
+        self.0.push(transform);
+    }
+
+    /// Returns the registered transformations with the given `names`.
+    ///
+    /// Fails with an [`Error::UnknownTransform`] error, if one of the
+    /// transformations is not registered.
+    pub fn resolve<B: Backend>(
+        &self,
+        names: &[String],
+    ) -> ArchiveResult<Vec<Arc<dyn Transform>>, B> {
+        names
+            .iter()
+            .map(|name| {
+                self.0
+                    .iter()
+                    .find(|t| t.name() == name)
+                    .cloned()
+                    .ok_or_else(|| Error::UnknownTransform(name.clone()))
+            })
+            .collect()
+    }
+}
+
+impl fmt::Debug for Transforms {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_list()
+            .entries(self.0.iter().map(|t| t.name()))
+            .finish()
+    }
+}
+
+/// Encodes the content of an entry: the chunks are compressed and
+/// transformed.
+///
+/// A transformed chunk is stored as a `u32` length followed by the chunk.
+pub(crate) struct Encoder {
+    compression: Compression,
+    compressor: Option<Compressor>,
+    transforms: Vec<Arc<dyn Transform>>,
+}
+
+impl Encoder {
+    /// Creates the encoder for the content of `inner`.
+    ///
+    /// Returns [`None`] if the content is stored as is.
+    pub fn create<B: Backend>(
+        inner: &Inner,
+        transforms: &Transforms,
+    ) -> ArchiveResult<Option<Encoder>, B> {
+        if !inner.is_encoded() {
+            return Ok(None);
+        }
+
+        Ok(Some(Encoder {
+            compression: inner.compression,
+            compressor: inner.compression.compressor()?,
+            transforms: transforms.resolve(&inner.transforms)?,
+        }))
+    }
+
+    /// Starts over, the next chunk does not refer to the chunks encoded so
+    /// far.
+    pub fn restart(&mut self) -> io::Result<()> {
+        self.compressor = self.compression.compressor()?;
+        Ok(())
+    }
+
+    pub fn encode(&mut self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        let mut buf = match self.compressor.as_mut() {
+            Some(compressor) => compressor.compress(chunk)?,
+            None => chunk.to_vec(),
+        };
+
+        if self.transforms.is_empty() {
+            return Ok(buf);
+        }
+
+        for transform in self.transforms.iter() {
+            buf = transform.encode(&buf)?;
+        }
+
+        let len = u32::try_from(buf.len())
+            .map_err(|_| io::Error::new(ErrorKind::InvalidData, "transformed chunk too large"))?;
+        let mut frame = Vec::with_capacity(FRAME_LEN_SIZE + buf.len());
+
+        frame.extend_from_slice(&len.to_be_bytes());
+        frame.extend_from_slice(&buf);
+
+        Ok(frame)
+    }
+}
+
+/// Decodes the content of an entry, see [`Encoder`].
+pub(crate) struct Decoder {
+    decompressor: Option<Decompressor>,
+    transforms: Vec<Arc<dyn Transform>>,
+    pending: Vec<u8>,
+}
+
+impl Decoder {
+    /// Creates the decoder for the content of `inner`.
+    ///
+    /// Returns [`None`] if the content is stored as is.
+    pub fn create<B: Backend>(
+        inner: &Inner,
+        transforms: &Transforms,
+    ) -> ArchiveResult<Option<Decoder>, B> {
+        if !inner.is_encoded() {
+            return Ok(None);
+        }
+
+        Ok(Some(Decoder {
+            decompressor: inner.compression.decompressor()?,
+            transforms: transforms.resolve(&inner.transforms)?,
+            pending: vec![],
+        }))
+    }
+
+    /// Decodes some bytes from `input` into `output`.
+    ///
+    /// Returns the number of bytes consumed from `input`, the decoded bytes
+    /// are appended to `output`. A transformed chunk is consumed only, if
+    /// `input` contains the whole chunk and the previous chunk was passed to
+    /// the decompressor completely.
+    pub fn decode(&mut self, input: &[u8], output: &mut Vec<u8>) -> io::Result<usize> {
+        if self.transforms.is_empty() {
+            return match self.decompressor.as_mut() {
+                Some(decompressor) => decompressor.decompress(input, output),
+                None => {
+                    output.extend_from_slice(input);
+                    Ok(input.len())
+                }
+            };
+        }
+
+        let consumed = if self.pending.is_empty() {
+            self.unframe(input)?
+        } else {
+            0
+        };
+
+        match self.decompressor.as_mut() {
+            Some(decompressor) => {
+                let n = decompressor.decompress(&self.pending, output)?;
+                self.pending.drain(..n);
+            }
+            None => output.append(&mut self.pending),
+        }
+
+        Ok(consumed)
+    }
+
+    /// Decodes the next transformed chunk of `input` into the pending
+    /// bytes.
+    fn unframe(&mut self, input: &[u8]) -> io::Result<usize> {
+        if input.len() < FRAME_LEN_SIZE {
+            return Ok(0);
+        }
+
+        let mut len = [0; FRAME_LEN_SIZE];
+        len.copy_from_slice(&input[..FRAME_LEN_SIZE]);
+
+        let end = FRAME_LEN_SIZE + u32::from_be_bytes(len) as usize;
+
+        if input.len() < end {
+            return Ok(0);
+        }
+
+        let mut buf = input[FRAME_LEN_SIZE..end].to_vec();
+
+        for transform in self.transforms.iter().rev() {
+            buf = transform.decode(&buf)?;
+        }
+
+        self.pending.extend_from_slice(&buf);
+
+        Ok(end)
+    }
+}
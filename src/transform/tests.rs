@@ -0,0 +1,238 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use nuts_container::memory::MemoryBackend;
+use std::io;
+use std::sync::Arc;
+
+use crate::codec::FEATURE_TRANSFORM;
+use crate::compression::Compression;
+use crate::entry::mode::Mode;
+use crate::entry::Inner;
+use crate::error::Error;
+use crate::tests::setup_container;
+use crate::transform::{Decoder, Encoder, Transform, Transforms};
+use crate::Archive;
+
+struct Xor(&'static str, u8);
+
+impl Transform for Xor {
+    fn name(&self) -> &str {
+        self.0
+    }
+
+    fn encode(&self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        Ok(chunk.iter().map(|b| b ^ self.1).collect())
+    }
+
+    fn decode(&self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        self.encode(chunk)
+    }
+}
+
+struct Reverse;
+
+impl Transform for Reverse {
+    fn name(&self) -> &str {
+        "reverse"
+    }
+
+    fn encode(&self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        Ok(chunk.iter().rev().copied().chain([0xff]).collect())
+    }
+
+    fn decode(&self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        match chunk.split_last() {
+            Some((0xff, rest)) => Ok(rest.iter().rev().copied().collect()),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, "no trailer")),
+        }
+    }
+}
+
+fn setup(compression: Compression, names: &[&str]) -> (Inner, Transforms) {
+    let mut inner = Inner::new("f".to_string(), Mode::file());
+    let mut transforms = Transforms::default();
+
+    inner.compression = compression;
+    inner.transforms = names.iter().map(|name| name.to_string()).collect();
+
+    transforms.register(Arc::new(Xor("xor", 0x5a)));
+    transforms.register(Arc::new(Reverse));
+
+    (inner, transforms)
+}
+
+fn decode(decoder: &mut Decoder, input: &[u8], step: usize) -> Vec<u8> {
+    let mut available = 0;
+    let mut consumed = 0;
+    let mut output = vec![];
+
+    loop {
+        let len = output.len();
+
+        output.reserve(64);
+
+        let n = decoder
+            .decode(&input[consumed..available], &mut output)
+            .unwrap();
+
+        consumed += n;
+
+        if n == 0 && output.len() == len {
+            if available == input.len() {
+                break;
+            }
+
+            available = (available + step).min(input.len());
+        }
+    }
+
+    output
+}
+
+#[test]
+fn register() {
+    let mut transforms = Transforms::default();
+
+    transforms.register(Arc::new(Xor("xor", 1)));
+    transforms.register(Arc::new(Reverse));
+    transforms.register(Arc::new(Xor("xor", 2)));
+
+    assert_eq!(format!("{:?}", transforms), r#"["reverse", "xor"]"#);
+
+    let resolved = transforms
+        .resolve::<MemoryBackend>(&["xor".to_string()])
+        .unwrap();
+    assert_eq!(resolved[0].encode(&[0]).unwrap(), [2]);
+}
+
+#[test]
+fn resolve_unknown() {
+    let transforms = Transforms::default();
+    let err = transforms
+        .resolve::<MemoryBackend>(&["xor".to_string()])
+        .err()
+        .unwrap();
+
+    assert!(matches!(err, Error::UnknownTransform(name) if name == "xor"));
+}
+
+#[test]
+fn plain() {
+    let (inner, transforms) = setup(Compression::None, &[]);
+
+    assert!(Encoder::create::<MemoryBackend>(&inner, &transforms)
+        .unwrap()
+        .is_none());
+    assert!(Decoder::create::<MemoryBackend>(&inner, &transforms)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn framed() {
+    let (inner, transforms) = setup(Compression::None, &["xor", "reverse"]);
+    let mut encoder = Encoder::create::<MemoryBackend>(&inner, &transforms)
+        .unwrap()
+        .unwrap();
+
+    let encoded = encoder.encode(b"abc").unwrap();
+    assert_eq!(
+        encoded,
+        [0, 0, 0, 4, b'c' ^ 0x5a, b'b' ^ 0x5a, b'a' ^ 0x5a, 0xff]
+    );
+}
+
+#[test]
+fn round_trip() {
+    let (inner, transforms) = setup(Compression::None, &["xor", "reverse"]);
+    let mut encoder = Encoder::create::<MemoryBackend>(&inner, &transforms)
+        .unwrap()
+        .unwrap();
+    let mut encoded = vec![];
+
+    for chunk in [&b"hello"[..], b" ", b"world"] {
+        encoded.extend(encoder.encode(chunk).unwrap());
+    }
+
+    for step in [1, 3, encoded.len()] {
+        let mut decoder = Decoder::create::<MemoryBackend>(&inner, &transforms)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(decode(&mut decoder, &encoded, step), b"hello world");
+    }
+}
+
+#[test]
+fn round_trip_compressed() {
+    let content = "hello world, ".repeat(100);
+    let (inner, transforms) = setup(Compression::Deflate, &["reverse"]);
+    let mut encoder = Encoder::create::<MemoryBackend>(&inner, &transforms)
+        .unwrap()
+        .unwrap();
+    let mut encoded = vec![];
+
+    for chunk in content.as_bytes().chunks(100) {
+        encoded.extend(encoder.encode(chunk).unwrap());
+    }
+
+    assert!(encoded.len() < content.len());
+
+    let mut decoder = Decoder::create::<MemoryBackend>(&inner, &transforms)
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(decode(&mut decoder, &encoded, 16), content.as_bytes());
+}
+
+#[test]
+fn decode_error() {
+    let (inner, transforms) = setup(Compression::None, &["reverse"]);
+    let mut decoder = Decoder::create::<MemoryBackend>(&inner, &transforms)
+        .unwrap()
+        .unwrap();
+    let mut output = vec![];
+
+    let err = decoder.decode(&[0, 0, 0, 1, 0], &mut output).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn feature() {
+    let mut archive = Archive::create(setup_container(), false).unwrap();
+
+    archive.register_transform(Xor("xor", 0x5a));
+    archive
+        .append_file("f1")
+        .with_content(b"abc")
+        .build()
+        .unwrap();
+    assert_eq!(archive.pager.codec().features(), 0);
+
+    let mut builder = archive.append_file("f2");
+    builder.add_transform("xor");
+    builder.with_content(b"abc").build().unwrap();
+
+    let archive = Archive::open(archive.into_container()).unwrap();
+    assert_eq!(archive.header.revision(), FEATURE_TRANSFORM | 1);
+}
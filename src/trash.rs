@@ -0,0 +1,255 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use log::debug;
+use nuts_container::backend::Backend;
+
+use crate::entry::immut::InnerEntry;
+use crate::entry::r#mut::{dec_parent_children, inc_parent_children};
+use crate::entry::Inner;
+use crate::error::{ArchiveResult, Error};
+use crate::header::Header;
+use crate::pager::Pager;
+use crate::savepoint::Savepoint;
+use crate::tree::Tree;
+use crate::{dependents, flush_header, remove_entry, Archive};
+
+/// Moves the entry `name` at `idx` into the trash.
+///
+/// The entry is flagged as trashed, which hides it like an internal entry.
+/// Its blocks and attachments stay where they are. `next` is the position
+/// of the next visible entry with the same name, which is recorded in the
+/// name index instead.
+pub(crate) fn trash<B: Backend>(
+    archive: &mut Archive<B>,
+    name: &str,
+    idx: usize,
+    mut inner: Inner,
+    next: Option<usize>,
+) -> ArchiveResult<(), B> {
+    let id = match archive.tree.lookup(&mut archive.pager, idx) {
+        Some(result) => result?.clone(),
+        None => return Err(Error::NotFound(name.to_string())),
+    };
+
+    inner.mode.set_trashed(true);
+    inner.flush(&mut archive.pager, &id)?;
+
+    archive.header.nfiles = archive.header.nfiles.saturating_sub(1);
+    archive.header.touch();
+
+    flush_header(
+        &mut archive.pager,
+        &archive.header_id,
+        &archive.header,
+        &mut archive.tree,
+    )?;
+
+    dec_parent_children(&mut archive.pager, &mut archive.tree, name)?;
+
+    if inner.mode.is_directory() {
+        archive.tree.forget_dirs();
+    }
+
+    if let Some(mut names) = archive.tree.take_names(&mut archive.pager)? {
+        names.forget(name, next.map(|n| n as u64));
+        names.store(&mut archive.pager)?;
+
+        archive.tree.set_names(names);
+    }
+
+    debug!("entry {} moved into the trash at {}", name, idx);
+
+    Ok(())
+}
+
+/// Restores the last entry with the given `name` from the trash.
+pub(crate) fn undelete<B: Backend>(archive: &mut Archive<B>, name: &str) -> ArchiveResult<(), B> {
+    archive.header.check_mutable()?;
+
+    let (idx, mut inner) = match trashed(archive)?
+        .into_iter()
+        .rfind(|(_, _, inner)| inner.name == name)
+    {
+        Some((idx, _, inner)) => (idx, inner),
+        None => return Err(Error::NotFound(name.to_string())),
+    };
+
+    let id = match archive.tree.lookup(&mut archive.pager, idx) {
+        Some(result) => result?.clone(),
+        None => return Err(Error::NotFound(name.to_string())),
+    };
+
+    inner.mode.set_trashed(false);
+    inner.flush(&mut archive.pager, &id)?;
+
+    // the number of entries changes, the savepoints cannot be restored anymore
+    archive.savepoints.clear();
+
+    archive.header.nfiles += 1;
+    archive.header.touch();
+
+    flush_header(
+        &mut archive.pager,
+        &archive.header_id,
+        &archive.header,
+        &mut archive.tree,
+    )?;
+
+    inc_parent_children(&mut archive.pager, &mut archive.tree, name)?;
+
+    if inner.mode.is_directory() {
+        archive.tree.forget_dirs();
+    }
+
+    if let Some(mut names) = archive.tree.take_names(&mut archive.pager)? {
+        let result = match names.get(name) {
+            None => names.insert(&mut archive.pager, name, idx as u64),
+            Some(first) if first > idx as u64 => {
+                names.forget(name, Some(idx as u64));
+                names.store(&mut archive.pager)
+            }
+            Some(_) => Ok(()),
+        };
+
+        archive.tree.set_names(names);
+        result?;
+    }
+
+    debug!("entry {} restored from the trash at {}", name, idx);
+
+    Ok(())
+}
+
+/// Returns the names of the entries in the trash.
+pub(crate) fn names<B: Backend>(archive: &mut Archive<B>) -> ArchiveResult<Vec<String>, B> {
+    let entries = trashed(archive)?;
+
+    Ok(entries
+        .into_iter()
+        .map(|(_, _, inner)| inner.name)
+        .collect())
+}
+
+/// Removes all entries in the trash together with their attachments.
+///
+/// Returns the number of removed entries.
+pub(crate) fn empty<B: Backend>(archive: &mut Archive<B>) -> ArchiveResult<usize, B> {
+    archive.header.check_mutable()?;
+
+    let entries = trashed(archive)?;
+
+    if !entries.is_empty() {
+        // the entries are moved, the savepoints cannot be restored anymore
+        archive.savepoints.clear();
+    }
+
+    // back to front, the positions of the entries in front do not change
+    for (idx, end_idx, inner) in entries.iter().rev() {
+        let deps = dependents(&mut archive.pager, &mut archive.tree, &inner.name, *idx)?;
+
+        archive.remove_at(&inner.name, *idx, *end_idx, deps, false)?;
+    }
+
+    debug!("trash emptied, {} entries removed", entries.len());
+
+    Ok(entries.len())
+}
+
+/// Removes the oldest entry in the trash together with its attachments.
+///
+/// Called, when the archive runs out of capacity while an entry is
+/// appended at `end`. Only the entries in front of `end` are scanned, the
+/// metadata of the new entry is not flushed yet. The entries are moved, so
+/// the `savepoints` are discarded. Returns the number of slots removed from
+/// the tree or [`None`], if the trash is empty or an append group is open.
+pub(crate) fn evict<B: Backend>(
+    pager: &mut Pager<B>,
+    header_id: &B::Id,
+    header: &mut Header,
+    tree: &mut Tree<B>,
+    savepoints: &mut Vec<Savepoint>,
+    end: usize,
+) -> ArchiveResult<Option<usize>, B> {
+    // the entries of an append group would be moved behind its back
+    if header.is_deferred() {
+        return Ok(None);
+    }
+
+    let mut oldest = None;
+    let mut entry_opt = InnerEntry::first(pager, tree);
+
+    while let Some(result) = entry_opt {
+        let entry = result?;
+
+        if entry.idx() >= end {
+            break;
+        }
+
+        if entry.inner().mode.is_trashed() {
+            oldest = Some((entry.idx(), entry.end_idx(), entry.inner().name.clone()));
+            break;
+        }
+
+        entry_opt = entry.next();
+    }
+
+    let (idx, end_idx, name) = match oldest {
+        Some(oldest) => oldest,
+        None => return Ok(None),
+    };
+
+    let deps = dependents(pager, tree, &name, idx)?;
+    let attached: usize = deps
+        .attachments
+        .iter()
+        .map(|(start, end)| end - start)
+        .sum();
+
+    savepoints.clear();
+    remove_entry(
+        pager, header_id, header, tree, &name, idx, end_idx, deps, false,
+    )?;
+
+    debug!("entry {} evicted from the trash at {}", name, idx);
+
+    Ok(Some(end_idx - idx + attached))
+}
+
+/// Collects the positions (`idx..end_idx`) and the metadata of the entries
+/// in the trash.
+fn trashed<B: Backend>(archive: &mut Archive<B>) -> ArchiveResult<Vec<(usize, usize, Inner)>, B> {
+    let mut entries = vec![];
+    let mut entry_opt = InnerEntry::first(&mut archive.pager, &mut archive.tree);
+
+    while let Some(result) = entry_opt {
+        let entry = result?;
+
+        if entry.inner().mode.is_trashed() {
+            entries.push((entry.idx(), entry.end_idx(), entry.inner().clone()));
+        }
+
+        entry_opt = entry.next();
+    }
+
+    Ok(entries)
+}
@@ -21,94 +21,593 @@
 // IN THE SOFTWARE.
 
 mod cache;
+pub mod free;
 mod node;
 #[cfg(test)]
 mod tests;
 
 use log::{debug, warn};
-use nuts_bytes::{FromBytes, ToBytes};
+use nuts_bytes::{PutBytes, Writer};
 use nuts_container::backend::{Backend, BlockId};
 use nuts_container::container::Container;
-use std::mem;
+use std::collections::HashMap;
+use std::{cmp, mem};
 
+use crate::codec::{BufReader, Codec};
 use crate::error::{ArchiveResult, Error};
+use crate::name_index::NameIndex;
 use crate::pager::Pager;
 use crate::tree::cache::Cache;
+use crate::tree::free::FreeList;
 use crate::tree::node::Node;
 
-fn ids_per_node<B: Backend>(container: &Container<B>) -> u32 {
+pub(crate) fn ids_per_node<B: Backend>(container: &Container<B>) -> u32 {
     container.block_size() / B::Id::size() as u32
 }
 
-const NUM_DIRECT: u32 = 12;
+/// The default number of direct blocks, the only one of revision 1.
+pub(crate) const NUM_DIRECT: u32 = 12;
+
+/// Aquires a leaf block, previously released blocks are preferred.
+fn aquire_leaf<B: Backend>(
+    free: Option<&mut FreeList<B>>,
+    pager: &mut Pager<B>,
+) -> ArchiveResult<B::Id, B> {
+    if let Some(id) = free.map_or(Ok(None), |free| free.pop(pager))? {
+        debug!("aquire_leaf: reused {}", id);
+        return Ok(id);
+    }
+
+    Ok(pager.aquire()?)
+}
+
+/// How [`Tree::lookup_cache()`] treats the looked up slot.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Aquire {
+    /// The slot is only looked up.
+    No,
+    /// A leaf block is aquired for the slot.
+    Leaf,
+    /// The slot is aquired as a hole, the nodes leading to the slot are
+    /// created but the slot itself stays null.
+    Hole,
+}
+
+impl Aquire {
+    fn leaf<B: Backend>(
+        self,
+        free: Option<&mut FreeList<B>>,
+        pager: &mut Pager<B>,
+    ) -> ArchiveResult<B::Id, B> {
+        match self {
+            Aquire::Hole => Ok(B::Id::null()),
+            _ => aquire_leaf(free, pager),
+        }
+    }
+}
 
 fn make_cache<B: Backend>() -> Vec<Cache<B>> {
     vec![]
 }
 
-#[derive(Debug, FromBytes, ToBytes)]
+/// The ids of all blocks of the tree, see [`Tree::set_mapped()`].
+#[derive(Debug)]
+struct Mapping<B: Backend> {
+    ids: Vec<B::Id>,
+}
+
+#[derive(Debug)]
 pub struct Tree<B: Backend> {
-    direct: [B::Id; NUM_DIRECT as usize],
+    direct: Vec<B::Id>,
     indirect: B::Id,
     d_indirect: B::Id,
     t_indirect: B::Id,
     nblocks: u64,
-    #[nuts_bytes(skip, default = make_cache)]
     cache: Vec<Cache<B>>,
+    reserved: u8,
+    strict: bool,
+    mapped: bool,
+    mapping: Option<Mapping<B>>,
+    names: Option<NameIndex<B>>,
+    names_root: Option<B::Id>,
+    free: Option<FreeList<B>>,
+    free_anchor: Option<B::Id>,
+    free_paused: bool,
+    dirs: Option<HashMap<String, usize>>,
 }
 
 impl<B: Backend> Tree<B> {
-    pub fn size() -> usize {
-        let id_size = B::Id::size() as usize;
+    /// Returns the size of a tree with `num_direct` direct blocks in the
+    /// header block.
+    pub fn size(num_direct: u32) -> usize {
+        let id_size = B::Id::size();
 
-        let direct = NUM_DIRECT as usize * id_size;
+        let num = if Codec::for_num_direct(num_direct).has_num_direct() {
+            mem::size_of::<u32>()
+        } else {
+            0
+        };
+        let direct = num_direct as usize * id_size;
         let indirect = 3 * id_size;
         let nblocks = mem::size_of::<u64>();
 
-        direct + indirect + nblocks
+        num + direct + indirect + nblocks
     }
 
-    pub fn new() -> Tree<B> {
+    /// Creates an empty tree with `num_direct` direct blocks.
+    pub fn new(num_direct: u32) -> Tree<B> {
         Tree {
-            direct: [
-                B::Id::null(),
-                B::Id::null(),
-                B::Id::null(),
-                B::Id::null(),
-                B::Id::null(),
-                B::Id::null(),
-                B::Id::null(),
-                B::Id::null(),
-                B::Id::null(),
-                B::Id::null(),
-                B::Id::null(),
-                B::Id::null(),
-            ],
+            direct: vec![B::Id::null(); num_direct as usize],
             indirect: B::Id::null(),
             d_indirect: B::Id::null(),
             t_indirect: B::Id::null(),
             nblocks: 0,
-            cache: vec![],
+            cache: make_cache(),
+            reserved: 0,
+            strict: false,
+            mapped: false,
+            mapping: None,
+            names: None,
+            names_root: None,
+            free: None,
+            free_anchor: None,
+            free_paused: false,
+            dirs: None,
         }
     }
 
+    /// Decodes the tree from `reader` with the given `codec`.
+    pub fn read(codec: &Codec, reader: &mut BufReader) -> Result<Tree<B>, nuts_bytes::Error> {
+        let num_direct = if codec.has_num_direct() {
+            reader.read::<u32>()?
+        } else {
+            NUM_DIRECT
+        };
+
+        // every id takes at least one byte, which protects from allocating a
+        // huge vector for a corrupted number
+        if num_direct as usize > reader.remaining().len() {
+            return Err(nuts_bytes::Error::Custom(
+                format!("invalid number of direct blocks: {}", num_direct).into(),
+            ));
+        }
+
+        let mut tree = Tree::new(0);
+
+        for _ in 0..num_direct {
+            tree.direct.push(reader.read()?);
+        }
+
+        tree.indirect = reader.read()?;
+        tree.d_indirect = reader.read()?;
+        tree.t_indirect = reader.read()?;
+        tree.nblocks = reader.read()?;
+
+        Ok(tree)
+    }
+
+    /// Encodes the tree into `writer` with the given `codec`.
+    ///
+    /// Returns the number of bytes written.
+    pub fn write<PB: PutBytes>(
+        &self,
+        codec: &Codec,
+        writer: &mut Writer<PB>,
+    ) -> Result<usize, nuts_bytes::Error> {
+        let mut n = 0;
+
+        if codec.has_num_direct() {
+            n += writer.write(&self.num_direct())?;
+        } else if self.num_direct() != NUM_DIRECT {
+            return Err(nuts_bytes::Error::Custom(
+                format!(
+                    "revision {} needs {} direct blocks",
+                    codec.revision(),
+                    NUM_DIRECT
+                )
+                .into(),
+            ));
+        }
+
+        for id in self.direct.iter() {
+            n += writer.write(id)?;
+        }
+
+        n += writer.write(&self.indirect)?;
+        n += writer.write(&self.d_indirect)?;
+        n += writer.write(&self.t_indirect)?;
+        n += writer.write(&self.nblocks)?;
+
+        Ok(n)
+    }
+
     pub fn nblocks(&self) -> u64 {
         self.nblocks
     }
 
-    pub fn aquire(&mut self, pager: &mut Pager<B>) -> ArchiveResult<&B::Id, B> {
+    /// Returns the number of direct blocks.
+    pub fn num_direct(&self) -> u32 {
+        self.direct.len() as u32
+    }
+
+    /// Returns the maximum number of blocks the tree is able to manage.
+    pub fn capacity(&self, pager: &Pager<B>) -> u64 {
         let ipn = ids_per_node(pager) as u64; // ids per node
 
-        if self.nblocks < NUM_DIRECT as u64 + ipn + ipn * ipn + ipn * ipn * ipn {
-            self.lookup_cache(pager, self.nblocks as usize, true)
+        self.num_direct() as u64 + ipn + ipn * ipn + ipn * ipn * ipn
+    }
+
+    /// Reserves `percent` of the capacity of the tree for metadata.
+    ///
+    /// The reserved blocks cannot be aquired with
+    /// [`aquire_content()`](Self::aquire_content) but only with
+    /// [`aquire()`](Self::aquire).
+    pub fn set_reserved(&mut self, percent: u8) {
+        self.reserved = cmp::min(percent, 100);
+    }
+
+    /// Enables the strict mode of the tree.
+    ///
+    /// Aquiring a block, which is already aquired, means that the tree is
+    /// corrupted. Usually this is logged and the existing block is reused.
+    /// In strict mode an [`Error::TreeInconsistent`] error is returned
+    /// instead.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Enables the in-memory mapping of the tree.
+    ///
+    /// If enabled, the ids of all blocks are loaded once into a flat list
+    /// and [`lookup()`](Self::lookup) is served from the list instead of
+    /// reading the nodes of the tree. All modifications of the tree go
+    /// through the tree itself, they update the list in place, so it is
+    /// loaded only once.
+    pub fn set_mapped(&mut self, mapped: bool) {
+        self.mapped = mapped;
+        self.mapping = None;
+    }
+
+    /// Loads the ids of all blocks, if the mapping is missing.
+    fn refresh_mapping(&mut self, pager: &mut Pager<B>) -> ArchiveResult<(), B> {
+        if self.mapping.is_some() {
+            return Ok(());
+        }
+
+        let mut ids = Vec::with_capacity(self.nblocks as usize);
+
+        for idx in 0..self.nblocks as usize {
+            ids.push(self.lookup_cache(pager, idx, Aquire::No)?.clone());
+        }
+
+        debug!("tree mapped: {} ids", ids.len());
+
+        self.mapping = Some(Mapping { ids });
+
+        Ok(())
+    }
+
+    /// Records the id of the slot `idx` in the mapping, if loaded.
+    ///
+    /// A slot behind the end of the tree is appended, the mapping always
+    /// covers all blocks of the tree.
+    fn map(&mut self, idx: usize, id: &B::Id) {
+        if let Some(mapping) = self.mapping.as_mut() {
+            if idx < mapping.ids.len() {
+                mapping.ids[idx] = id.clone();
+            } else if idx == mapping.ids.len() && idx < self.nblocks as usize {
+                mapping.ids.push(id.clone());
+            }
+        }
+    }
+
+    /// Aquires the slot behind the last block.
+    fn aquire_slot(&mut self, pager: &mut Pager<B>, aquire: Aquire) -> ArchiveResult<&B::Id, B> {
+        let idx = self.nblocks as usize;
+        let id = self.lookup_cache(pager, idx, aquire)?.clone();
+
+        self.map(idx, &id);
+        self.lookup_cache(pager, idx, Aquire::No)
+    }
+
+    /// Reports a block at `idx`, which should be aquired but is already
+    /// aquired.
+    fn already_aquired(&self, func: &str, idx: usize) -> ArchiveResult<(), B> {
+        if self.strict {
+            Err(Error::TreeInconsistent(self.nblocks))
         } else {
-            Err(Error::Full)
+            warn!("{}: already aquired at {}", func, idx);
+            Ok(())
+        }
+    }
+
+    /// Returns the name index attached to the tree, if it is loaded.
+    ///
+    /// The index is not part of the tree, but attached here because the
+    /// tree is passed to everyone, who appends entries. Use
+    /// [`names_mut()`](Self::names_mut) to load the index on first use.
+    pub fn names(&self) -> Option<&NameIndex<B>> {
+        self.names.as_ref()
+    }
+
+    /// Returns the name index attached to the tree.
+    ///
+    /// The index is loaded from its [root](Self::set_names_root), when it
+    /// is accessed for the first time.
+    pub fn names_mut(
+        &mut self,
+        pager: &mut Pager<B>,
+    ) -> ArchiveResult<Option<&mut NameIndex<B>>, B> {
+        if let Some(id) = self.names_root.take() {
+            self.names = Some(NameIndex::load(pager, &id)?);
+        }
+
+        Ok(self.names.as_mut())
+    }
+
+    /// Tests whether a name index is attached to the tree, no matter if it
+    /// is already loaded.
+    pub fn has_names(&self) -> bool {
+        self.names.is_some() || self.names_root.is_some()
+    }
+
+    pub fn set_names(&mut self, names: NameIndex<B>) {
+        self.names = Some(names);
+        self.names_root = None;
+    }
+
+    /// Attaches the name index starting at block `id`, the index is loaded
+    /// on first use.
+    pub fn set_names_root(&mut self, id: B::Id) {
+        self.names = None;
+        self.names_root = Some(id);
+    }
+
+    pub fn take_names(&mut self, pager: &mut Pager<B>) -> ArchiveResult<Option<NameIndex<B>>, B> {
+        self.names_mut(pager)?;
+
+        Ok(self.names.take())
+    }
+
+    /// Returns the list of released blocks attached to the tree, if it is
+    /// loaded.
+    ///
+    /// Like the name index, the list is not part of the tree. Its anchor is
+    /// stored in the userdata of the container. The list is loaded, when a
+    /// block is aquired or released for the first time.
+    pub fn free_list(&self) -> Option<&FreeList<B>> {
+        self.free.as_ref()
+    }
+
+    /// Tests whether a free list is attached to the tree, no matter if it
+    /// is already loaded.
+    pub fn has_free_list(&self) -> bool {
+        self.free.is_some() || self.free_anchor.is_some()
+    }
+
+    pub fn set_free_list(&mut self, free: FreeList<B>) {
+        self.free = Some(free);
+        self.free_anchor = None;
+    }
+
+    /// Attaches the free list stored at block `anchor`, the list is loaded
+    /// on first use.
+    pub fn set_free_anchor(&mut self, anchor: B::Id) {
+        self.free = None;
+        self.free_anchor = Some(anchor);
+    }
+
+    /// Stops reusing the blocks of the free list for new blocks.
+    ///
+    /// Released blocks are still put into the list.
+    pub fn pause_free_list(&mut self, paused: bool) {
+        self.free_paused = paused;
+    }
+
+    fn load_free_list(&mut self, pager: &mut Pager<B>) -> ArchiveResult<(), B> {
+        if let Some(anchor) = self.free_anchor.take() {
+            self.free = Some(FreeList::load(pager, &anchor)?);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the positions of the directory entries, if they are loaded.
+    ///
+    /// Maps the name of a directory (without a trailing `/`) to the index of
+    /// the last directory entry with this name. Like the name index, the map
+    /// is attached here, so that the parent of a new entry is found without
+    /// scanning the archive. It only lives in memory and is dropped, when
+    /// entries are moved.
+    pub fn dirs(&self) -> Option<&HashMap<String, usize>> {
+        self.dirs.as_ref()
+    }
+
+    pub fn set_dirs(&mut self, dirs: HashMap<String, usize>) {
+        self.dirs = Some(dirs);
+    }
+
+    /// Records the directory `name` appended at `idx`, if the positions of
+    /// the directories are loaded.
+    pub fn add_dir(&mut self, name: &str, idx: usize) {
+        if let Some(dirs) = self.dirs.as_mut() {
+            dirs.insert(name.trim_end_matches('/').to_string(), idx);
+        }
+    }
+
+    pub fn forget_dirs(&mut self) {
+        self.dirs = None;
+    }
+
+    /// Releases a block, which was removed from the tree.
+    ///
+    /// If the tree has a [free list](Self::free_list), the block is put
+    /// into the list, otherwise it is released back to the container.
+    pub fn release(&mut self, pager: &mut Pager<B>, id: B::Id) -> ArchiveResult<(), B> {
+        self.load_free_list(pager)?;
+
+        match self.free.as_mut() {
+            Some(free) => free.push(pager, id),
+            None => Ok(pager.release(id)?),
+        }
+    }
+
+    /// Takes a block from the [free list](Self::free_list).
+    ///
+    /// Returns [`None`], if there is no free list or the list is empty.
+    pub fn pop_free(&mut self, pager: &mut Pager<B>) -> ArchiveResult<Option<B::Id>, B> {
+        self.load_free_list(pager)?;
+
+        match self.free.as_mut() {
+            Some(free) => free.pop(pager),
+            None => Ok(None),
+        }
+    }
+
+    /// Aquires a block for metadata, this can use the reserved capacity.
+    pub fn aquire(&mut self, pager: &mut Pager<B>) -> ArchiveResult<&B::Id, B> {
+        if self.nblocks < self.capacity(pager) {
+            self.aquire_slot(pager, Aquire::Leaf)
+        } else {
+            Err(Error::Full(0))
+        }
+    }
+
+    /// Aquires a block for content, this will not use the reserved capacity.
+    pub fn aquire_content(&mut self, pager: &mut Pager<B>) -> ArchiveResult<&B::Id, B> {
+        let capacity = self.capacity(pager);
+        let reserved = capacity * self.reserved as u64 / 100;
+
+        if self.nblocks + reserved < capacity {
+            self.aquire_slot(pager, Aquire::Leaf)
+        } else {
+            debug!(
+                "aquire_content: nblocks={}, reserved={}, capacity={}",
+                self.nblocks, reserved, capacity
+            );
+            Err(Error::Full(0))
+        }
+    }
+
+    /// Aquires a hole for content.
+    ///
+    /// A hole occupies a slot of the tree like a content block, but no block
+    /// is allocated, the slot keeps a null id. [`lookup()`](Self::lookup)
+    /// returns [`None`] for a hole, which is read as a block of zeros. Like
+    /// [`aquire_content()`](Self::aquire_content) the reserved capacity is
+    /// not used.
+    pub fn aquire_hole(&mut self, pager: &mut Pager<B>) -> ArchiveResult<(), B> {
+        let capacity = self.capacity(pager);
+        let reserved = capacity * self.reserved as u64 / 100;
+
+        if self.nblocks + reserved < capacity {
+            self.aquire_slot(pager, Aquire::Hole).map(|_| ())
+        } else {
+            Err(Error::Full(0))
+        }
+    }
+
+    /// Replaces the hole at `idx` with a newly aquired block.
+    ///
+    /// The block is not initialized, the caller is responsible to write
+    /// zeros or content into the block.
+    pub fn fill_hole(&mut self, pager: &mut Pager<B>, idx: usize) -> ArchiveResult<&B::Id, B> {
+        assert!(idx < self.nblocks as usize);
+
+        let id = self.lookup_cache(pager, idx, Aquire::Leaf)?.clone();
+
+        // the slot was already counted when the hole was aquired
+        self.nblocks -= 1;
+        self.map(idx, &id);
+
+        debug!("hole at {} filled", idx);
+
+        self.lookup_cache(pager, idx, Aquire::No)
+    }
+
+    /// Clears the slots behind the last block, which still refer to a block.
+    ///
+    /// Such slots are left behind, if the nodes of the tree were written but
+    /// the header with the number of blocks was not. [`aquire()`](Self::aquire)
+    /// would reuse them without counting them. The slots are set to a null
+    /// id, the ids of the blocks are returned, they are not released.
+    pub fn clear_stale(&mut self, pager: &mut Pager<B>) -> ArchiveResult<Vec<B::Id>, B> {
+        let ipn = ids_per_node(pager) as usize; // ids per node
+        let ndirect = self.direct.len();
+        let capacity = self.capacity(pager) as usize;
+        let mut stale = vec![];
+
+        for idx in self.nblocks as usize..capacity {
+            let root = if idx < ndirect {
+                None
+            } else if idx < ndirect + ipn {
+                Some(&self.indirect)
+            } else if idx < ndirect + ipn + ipn * ipn {
+                Some(&self.d_indirect)
+            } else {
+                Some(&self.t_indirect)
+            };
+
+            // do not aquire the root node of a level while scanning
+            if matches!(root, Some(id) if id.is_null()) {
+                break;
+            }
+
+            let id = self.lookup_cache(pager, idx, Aquire::No)?.clone();
+
+            if id.is_null() {
+                break;
+            }
+
+            stale.push(id);
+        }
+
+        if !stale.is_empty() {
+            let start = self.nblocks as usize;
+
+            for idx in start..start + stale.len() {
+                self.set(pager, idx, B::Id::null())?;
+            }
+
+            for cache in self.cache.iter_mut() {
+                cache.flush(pager)?;
+            }
+
+            warn!("{} stale blocks cleared behind {}", stale.len(), start);
+        }
+
+        Ok(stale)
+    }
+
+    /// Loads the nodes leading to the last block into the cache.
+    ///
+    /// This is where the next block is appended. An attached name index and
+    /// free list are loaded as well.
+    pub fn warm_up(&mut self, pager: &mut Pager<B>) -> ArchiveResult<(), B> {
+        self.names_mut(pager)?;
+        self.load_free_list(pager)?;
+
+        match self.nblocks.checked_sub(1) {
+            Some(idx) => self
+                .lookup(pager, idx as usize)
+                .map_or(Ok(()), |r| r.map(|_| ())),
+            None => Ok(()),
         }
     }
 
     pub fn lookup(&mut self, pager: &mut Pager<B>, idx: usize) -> Option<ArchiveResult<&B::Id, B>> {
-        if idx < self.nblocks as usize {
-            match self.lookup_cache(pager, idx, false) {
+        if idx < self.nblocks as usize && self.mapped {
+            if let Err(err) = self.refresh_mapping(pager) {
+                return Some(Err(err));
+            }
+
+            self.mapping
+                .as_ref()
+                .map(|mapping| &mapping.ids[idx])
+                .filter(|id| !id.is_null())
+                .map(Ok)
+        } else if idx < self.nblocks as usize {
+            match self.lookup_cache(pager, idx, Aquire::No) {
                 Ok(id) => {
                     if id.is_null() {
                         None
@@ -123,22 +622,137 @@ impl<B: Backend> Tree<B> {
         }
     }
 
+    /// Removes `count` blocks starting at `idx` from the tree.
+    ///
+    /// The blocks behind the removed range are moved to the front, so the
+    /// tree has no gaps. The ids of the removed blocks are returned, they are
+    /// not released.
+    pub fn remove(
+        &mut self,
+        pager: &mut Pager<B>,
+        idx: usize,
+        count: usize,
+    ) -> ArchiveResult<Vec<B::Id>, B> {
+        let nblocks = self.nblocks as usize;
+
+        assert!(idx + count <= nblocks);
+
+        self.dirs = None;
+
+        let mut removed = Vec::with_capacity(count);
+
+        for n in idx..idx + count {
+            removed.push(self.lookup_cache(pager, n, Aquire::No)?.clone());
+        }
+
+        for n in idx..nblocks - count {
+            let id = self.lookup_cache(pager, n + count, Aquire::No)?.clone();
+            self.set(pager, n, id)?;
+        }
+
+        for n in nblocks - count..nblocks {
+            self.set(pager, n, B::Id::null())?;
+        }
+
+        for cache in self.cache.iter_mut() {
+            cache.flush(pager)?;
+        }
+
+        self.nblocks -= count as u64;
+
+        if let Some(mapping) = self.mapping.as_mut() {
+            mapping.ids.truncate(self.nblocks as usize);
+        }
+
+        debug!(
+            "removed {} blocks at {}, nblocks={}",
+            count, idx, self.nblocks
+        );
+
+        Ok(removed)
+    }
+
+    /// Replaces the blocks starting at `idx` with the given `ids`.
+    ///
+    /// The number of blocks does not change, `ids` is usually a permutation
+    /// of the replaced blocks.
+    pub fn replace(
+        &mut self,
+        pager: &mut Pager<B>,
+        idx: usize,
+        ids: Vec<B::Id>,
+    ) -> ArchiveResult<(), B> {
+        assert!(idx + ids.len() <= self.nblocks as usize);
+
+        self.dirs = None;
+
+        let count = ids.len();
+
+        for (n, id) in ids.into_iter().enumerate() {
+            self.set(pager, idx + n, id)?;
+        }
+
+        for cache in self.cache.iter_mut() {
+            cache.flush(pager)?;
+        }
+
+        debug!("replaced {} blocks at {}", count, idx);
+
+        Ok(())
+    }
+
+    fn set(&mut self, pager: &mut Pager<B>, idx: usize, id: B::Id) -> ArchiveResult<(), B> {
+        self.map(idx, &id);
+
+        let ipn = ids_per_node(pager) as usize; // ids per node
+        let ndirect = self.direct.len();
+
+        if idx < ndirect {
+            self.direct[idx] = id;
+            return Ok(());
+        }
+
+        // loads the nodes leading to idx into the cache
+        self.lookup_cache(pager, idx, Aquire::No)?;
+
+        let level = if idx < ndirect + ipn {
+            0
+        } else if idx < ndirect + ipn + ipn * ipn {
+            1
+        } else {
+            2
+        };
+
+        self.cache[level].set((idx - ndirect) % ipn, id);
+
+        Ok(())
+    }
+
     fn lookup_cache(
         &mut self,
         pager: &mut Pager<B>,
         idx: usize,
-        aquire: bool,
+        aquire: Aquire,
     ) -> ArchiveResult<&B::Id, B> {
         let ipn = ids_per_node(pager) as usize; // ids per node
+        let ndirect = self.direct.len();
+
+        if aquire != Aquire::No {
+            self.load_free_list(pager)?;
+        }
 
-        if idx < NUM_DIRECT as usize {
+        if aquire != Aquire::No {
+            self.load_free_list(pager)?;
+        }
+
+        if idx < ndirect {
             self.lookup_direct(pager, idx, aquire)
-        } else if idx < NUM_DIRECT as usize + ipn {
-            self.lookup_indirect(pager, idx - NUM_DIRECT as usize, aquire)
-        } else if idx < NUM_DIRECT as usize + ipn + ipn * ipn {
-            self.lookup_d_indirect(pager, idx - NUM_DIRECT as usize - ipn, aquire)
+        } else if idx < ndirect + ipn {
+            self.lookup_indirect(pager, idx - ndirect, aquire)
+        } else if idx < ndirect + ipn + ipn * ipn {
+            self.lookup_d_indirect(pager, idx - ndirect - ipn, aquire)
         } else {
-            self.lookup_t_indirect(pager, idx - NUM_DIRECT as usize - ipn - ipn * ipn, aquire)
+            self.lookup_t_indirect(pager, idx - ndirect - ipn - ipn * ipn, aquire)
         }
     }
 
@@ -146,19 +760,22 @@ impl<B: Backend> Tree<B> {
         &mut self,
         pager: &mut Pager<B>,
         idx: usize,
-        aquire: bool,
+        aquire: Aquire,
     ) -> ArchiveResult<&B::Id, B> {
-        if aquire {
+        if aquire != Aquire::No {
             if self.direct[idx].is_null() {
-                self.direct[idx] = pager.aquire()?;
+                let paused = self.free_paused;
+                let free = self.free.as_mut().filter(|_| !paused);
+
+                self.direct[idx] = aquire.leaf(free, pager)?;
                 self.nblocks += 1;
             } else {
-                warn!("lookup_direct: already aquired at {}", idx);
+                self.already_aquired("lookup_direct", idx)?;
             }
         }
 
         debug!(
-            "lookup_direct: idx={}, aquire={}, nblocks={}, id={}",
+            "lookup_direct: idx={}, aquire={:?}, nblocks={}, id={}",
             idx, aquire, self.nblocks, self.direct[idx]
         );
 
@@ -169,7 +786,7 @@ impl<B: Backend> Tree<B> {
         &mut self,
         pager: &mut Pager<B>,
         idx: usize,
-        aquire: bool,
+        aquire: Aquire,
     ) -> ArchiveResult<&B::Id, B> {
         if self.indirect.is_null() {
             self.indirect = Node::aquire(pager)?;
@@ -180,16 +797,19 @@ impl<B: Backend> Tree<B> {
 
         debug!("lookup_indirect: cache={}", self.cache[0].id());
 
-        if aquire {
-            if self.cache[0].aquire(pager, idx, true)? {
+        if aquire != Aquire::No {
+            let paused = self.free_paused;
+            let free = self.free.as_mut().filter(|_| !paused);
+
+            if self.cache[0].aquire(pager, idx, |pager| aquire.leaf(free, pager))? {
                 self.nblocks += 1;
             } else {
-                warn!("lookup_indirect: already aquired at {}", idx);
+                self.already_aquired("lookup_indirect", idx)?;
             }
         }
 
         debug!(
-            "loopup_indirect: idx={}, aquire={}, nblocks={}, id={}",
+            "loopup_indirect: idx={}, aquire={:?}, nblocks={}, id={}",
             idx, aquire, self.nblocks, self.cache[0][idx]
         );
 
@@ -200,7 +820,7 @@ impl<B: Backend> Tree<B> {
         &mut self,
         pager: &mut Pager<B>,
         idx: usize,
-        aquire: bool,
+        aquire: Aquire,
     ) -> ArchiveResult<&B::Id, B> {
         let ipn = ids_per_node(pager) as usize; // ids per node
 
@@ -217,8 +837,8 @@ impl<B: Backend> Tree<B> {
         self.cache[0].refresh(pager, &self.d_indirect)?;
         debug!("lookup_d_indirect: cache[0]={}", self.cache[0].id());
 
-        if aquire {
-            self.cache[0].aquire(pager, d_idx.0, false)?;
+        if aquire != Aquire::No {
+            self.cache[0].aquire(pager, d_idx.0, Node::aquire)?;
         } else if self.cache[0][d_idx.0].is_null() {
             return Ok(&self.cache[0][d_idx.0]);
         }
@@ -229,16 +849,19 @@ impl<B: Backend> Tree<B> {
         self.cache[1].refresh(pager, &id)?;
         debug!("lookup_d_indirect: cache[1]={}", self.cache[1].id());
 
-        if aquire {
-            if self.cache[1].aquire(pager, d_idx.1, true)? {
+        if aquire != Aquire::No {
+            let paused = self.free_paused;
+            let free = self.free.as_mut().filter(|_| !paused);
+
+            if self.cache[1].aquire(pager, d_idx.1, |pager| aquire.leaf(free, pager))? {
                 self.nblocks += 1;
             } else {
-                warn!("lookup_d_indirect: already aquired at {}", d_idx.1);
+                self.already_aquired("lookup_d_indirect", d_idx.1)?;
             }
         }
 
         debug!(
-            "loopup_d_indirect: idx={} => ({}, {}), aquire={}, nblocks={}, id={}",
+            "loopup_d_indirect: idx={} => ({}, {}), aquire={:?}, nblocks={}, id={}",
             idx, d_idx.0, d_idx.1, aquire, self.nblocks, self.cache[1][d_idx.1]
         );
 
@@ -249,7 +872,7 @@ impl<B: Backend> Tree<B> {
         &mut self,
         pager: &mut Pager<B>,
         idx: usize,
-        aquire: bool,
+        aquire: Aquire,
     ) -> ArchiveResult<&B::Id, B> {
         let ipn = ids_per_node(pager) as usize; // ids per node
 
@@ -266,8 +889,8 @@ impl<B: Backend> Tree<B> {
         self.cache[0].refresh(pager, &self.t_indirect)?;
         debug!("lookup_t_indirect: cache[0]={}", self.cache[0].id());
 
-        if aquire {
-            self.cache[0].aquire(pager, t_idx.0, false)?;
+        if aquire != Aquire::No {
+            self.cache[0].aquire(pager, t_idx.0, Node::aquire)?;
         } else if self.cache[0][t_idx.0].is_null() {
             return Ok(&self.cache[0][t_idx.0]);
         }
@@ -278,8 +901,8 @@ impl<B: Backend> Tree<B> {
         self.cache[1].refresh(pager, &id)?;
         debug!("lookup_t_indirect: cache[1]={}", self.cache[1].id());
 
-        if aquire {
-            self.cache[1].aquire(pager, t_idx.1, false)?;
+        if aquire != Aquire::No {
+            self.cache[1].aquire(pager, t_idx.1, Node::aquire)?;
         } else if self.cache[1][t_idx.1].is_null() {
             return Ok(&self.cache[1][t_idx.1]);
         }
@@ -290,16 +913,19 @@ impl<B: Backend> Tree<B> {
         self.cache[2].refresh(pager, &id)?;
         debug!("lookup_t_indirect: cache[2]={}", self.cache[2].id());
 
-        if aquire {
-            if self.cache[2].aquire(pager, t_idx.2, true)? {
+        if aquire != Aquire::No {
+            let paused = self.free_paused;
+            let free = self.free.as_mut().filter(|_| !paused);
+
+            if self.cache[2].aquire(pager, t_idx.2, |pager| aquire.leaf(free, pager))? {
                 self.nblocks += 1;
             } else {
-                warn!("lookup_t_indirect: already aquired at {}", t_idx.2);
+                self.already_aquired("lookup_t_indirect", t_idx.2)?;
             }
         }
 
         debug!(
-            "loopup_t_indirect: idx={} => ({}, {}, {}), aquire={}, nblocks={}, id={}",
+            "loopup_t_indirect: idx={} => ({}, {}, {}), aquire={:?}, nblocks={}, id={}",
             idx, t_idx.0, t_idx.1, t_idx.2, aquire, self.nblocks, self.cache[2][t_idx.2]
         );
 
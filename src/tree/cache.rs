@@ -34,13 +34,15 @@ use crate::tree::node::Node;
 pub struct Cache<B: Backend> {
     id: B::Id,
     node: Node<B>,
+    dirty: bool,
 }
 
-impl<'a, B: Backend> Cache<B> {
+impl<B: Backend> Cache<B> {
     pub fn new(pager: &Pager<B>) -> Cache<B> {
         Cache {
             id: B::Id::null(),
             node: Node::new(pager),
+            dirty: false,
         }
     }
 
@@ -50,28 +52,46 @@ impl<'a, B: Backend> Cache<B> {
 
     pub fn refresh(&mut self, pager: &mut Pager<B>, id: &B::Id) -> ArchiveResult<bool, B> {
         if &self.id != id {
+            self.flush(pager)?;
+
             self.id = id.clone();
             self.node.fill(pager, id)?;
+            pager.stats_mut().cache(false);
 
             Ok(true)
         } else {
+            pager.stats_mut().cache(true);
+
             Ok(false)
         }
     }
 
-    pub fn aquire(
-        &mut self,
-        pager: &mut Pager<B>,
-        idx: usize,
-        leaf: bool,
-    ) -> ArchiveResult<bool, B> {
-        if self.node[idx].is_null() {
-            self.node[idx] = if leaf {
-                pager.aquire()?
-            } else {
-                Node::aquire(pager)?
-            };
+    /// Replaces the id at `idx`.
+    ///
+    /// The node is not written immediately, but when the cache is flushed or
+    /// refreshed with another node.
+    pub fn set(&mut self, idx: usize, id: B::Id) {
+        self.node[idx] = id;
+        self.dirty = true;
+    }
+
+    /// Writes the node back, if it was modified with [`Cache::set()`].
+    pub fn flush(&mut self, pager: &mut Pager<B>) -> ArchiveResult<(), B> {
+        if self.dirty {
+            self.node.flush(pager, &self.id)?;
+            self.dirty = false;
+        }
 
+        Ok(())
+    }
+
+    /// Aquires a block at `idx` with `f`, if the slot is still empty.
+    pub fn aquire<F>(&mut self, pager: &mut Pager<B>, idx: usize, f: F) -> ArchiveResult<bool, B>
+    where
+        F: FnOnce(&mut Pager<B>) -> ArchiveResult<B::Id, B>,
+    {
+        if self.node[idx].is_null() {
+            self.node[idx] = f(pager)?;
             self.node.flush(pager, &self.id)?;
 
             Ok(true)
@@ -26,6 +26,7 @@ use nuts_container::memory::MemoryBackend;
 use crate::pager::Pager;
 use crate::tests::setup_container_with_bsize;
 use crate::tree::cache::Cache;
+use crate::tree::node::Node;
 
 #[test]
 fn new() {
@@ -88,7 +89,7 @@ fn aquire_null_no_leaf() {
     let mut cache = Cache::<MemoryBackend>::new(&pager);
 
     assert!(cache.refresh(&mut pager, &id).unwrap());
-    assert!(cache.aquire(&mut pager, 0, false).unwrap());
+    assert!(cache.aquire(&mut pager, 0, Node::aquire).unwrap());
 
     let mut buf = [0; 12];
     let mut container = pager.into_container();
@@ -117,7 +118,7 @@ fn aquire_not_null_no_leaf() {
     let mut cache = Cache::<MemoryBackend>::new(&pager);
 
     assert!(cache.refresh(&mut pager, &id).unwrap());
-    assert!(!cache.aquire(&mut pager, 1, false).unwrap());
+    assert!(!cache.aquire(&mut pager, 1, Node::aquire).unwrap());
 
     let mut buf = [0; 12];
     let mut container = pager.into_container();
@@ -143,7 +144,9 @@ fn aquire_null_leaf() {
     let mut cache = Cache::<MemoryBackend>::new(&pager);
 
     assert!(cache.refresh(&mut pager, &id).unwrap());
-    assert!(cache.aquire(&mut pager, 0, true).unwrap());
+    assert!(cache
+        .aquire(&mut pager, 0, |pager| Ok(pager.aquire()?))
+        .unwrap());
 
     let mut buf = [0; 12];
     let mut container = pager.into_container();
@@ -172,7 +175,9 @@ fn aquire_not_null_leaf() {
     let mut cache = Cache::<MemoryBackend>::new(&pager);
 
     assert!(cache.refresh(&mut pager, &id).unwrap());
-    assert!(!cache.aquire(&mut pager, 1, true).unwrap());
+    assert!(!cache
+        .aquire(&mut pager, 1, |pager| Ok(pager.aquire()?))
+        .unwrap());
 
     let mut buf = [0; 12];
     let mut container = pager.into_container();
@@ -0,0 +1,140 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+#[cfg(test)]
+mod tests;
+
+use log::debug;
+use nuts_container::backend::{Backend, BlockId};
+use std::mem;
+
+use crate::error::ArchiveResult;
+use crate::pager::Pager;
+
+/// A list of blocks, which were released from the tree.
+///
+/// The list is a stack, which is linked through the released blocks itself:
+/// each released block stores the id of the block released before. The top
+/// of the stack and the length of the list are stored in an anchor block,
+/// which never moves. So, the anchor needs to be recorded only once.
+#[derive(Debug)]
+pub struct FreeList<B: Backend> {
+    anchor: B::Id,
+    head: B::Id,
+    len: u64,
+}
+
+impl<B: Backend> FreeList<B> {
+    /// Creates an empty list with a new anchor block.
+    pub fn create(pager: &mut Pager<B>) -> ArchiveResult<FreeList<B>, B> {
+        let list = FreeList {
+            anchor: pager.aquire()?,
+            head: B::Id::null(),
+            len: 0,
+        };
+
+        list.flush(pager)?;
+
+        debug!("free list created at {}", list.anchor);
+
+        Ok(list)
+    }
+
+    /// Loads the list from the given `anchor` block.
+    pub fn load(pager: &mut Pager<B>, anchor: &B::Id) -> ArchiveResult<FreeList<B>, B> {
+        let mut reader = pager.read_buf(anchor)?;
+        let head = reader.read::<B::Id>()?;
+        let len = reader.read::<u64>()?;
+
+        debug!("free list loaded from {}: {} blocks", anchor, len);
+
+        Ok(FreeList {
+            anchor: anchor.clone(),
+            head,
+            len,
+        })
+    }
+
+    /// Returns the id of the anchor block.
+    pub fn anchor(&self) -> &B::Id {
+        &self.anchor
+    }
+
+    /// Returns the number of blocks in the list.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Tests whether the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Puts the block `id` on top of the list.
+    pub fn push(&mut self, pager: &mut Pager<B>, id: B::Id) -> ArchiveResult<(), B> {
+        let buf = {
+            let mut writer = pager.codec().writer(vec![]);
+
+            writer.write(&self.head)?;
+
+            writer.into_target()
+        };
+
+        pager.write(&id, &buf)?;
+
+        self.head = id;
+        self.len += 1;
+
+        self.flush(pager)
+    }
+
+    /// Takes the block from the top of the list.
+    ///
+    /// Returns [`None`] if the list is empty.
+    pub fn pop(&mut self, pager: &mut Pager<B>) -> ArchiveResult<Option<B::Id>, B> {
+        if self.head.is_null() {
+            return Ok(None);
+        }
+
+        let next = pager.read_buf(&self.head)?.read::<B::Id>()?;
+        let id = mem::replace(&mut self.head, next);
+
+        self.len = self.len.saturating_sub(1);
+        self.flush(pager)?;
+
+        Ok(Some(id))
+    }
+
+    fn flush(&self, pager: &mut Pager<B>) -> ArchiveResult<(), B> {
+        let buf = {
+            let mut writer = pager.codec().writer(vec![]);
+
+            writer.write(&self.head)?;
+            writer.write(&self.len)?;
+
+            writer.into_target()
+        };
+
+        pager.write(&self.anchor, &buf)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+use nuts_container::backend::BlockId;
+use nuts_container::memory::MemoryBackend;
+
+use crate::pager::Pager;
+use crate::tests::setup_container_with_bsize;
+use crate::tree::free::FreeList;
+
+#[test]
+fn create() {
+    let mut pager = Pager::new(setup_container_with_bsize(16));
+    let list = FreeList::<MemoryBackend>::create(&mut pager).unwrap();
+
+    assert!(list.head.is_null());
+    assert!(list.is_empty());
+
+    let list = FreeList::<MemoryBackend>::load(&mut pager, list.anchor()).unwrap();
+    assert!(list.head.is_null());
+    assert!(list.is_empty());
+}
+
+#[test]
+fn pop_empty() {
+    let mut pager = Pager::new(setup_container_with_bsize(16));
+    let mut list = FreeList::<MemoryBackend>::create(&mut pager).unwrap();
+
+    assert!(list.pop(&mut pager).unwrap().is_none());
+    assert_eq!(list.len(), 0);
+}
+
+#[test]
+fn push_pop() {
+    let mut pager = Pager::new(setup_container_with_bsize(16));
+    let mut list = FreeList::<MemoryBackend>::create(&mut pager).unwrap();
+    let id1 = pager.aquire().unwrap();
+    let id2 = pager.aquire().unwrap();
+
+    list.push(&mut pager, id1).unwrap();
+    list.push(&mut pager, id2).unwrap();
+    assert_eq!(list.len(), 2);
+
+    assert_eq!(list.pop(&mut pager).unwrap(), Some(id2));
+    assert_eq!(list.pop(&mut pager).unwrap(), Some(id1));
+    assert!(list.pop(&mut pager).unwrap().is_none());
+    assert!(list.is_empty());
+}
+
+#[test]
+fn persistent() {
+    let mut pager = Pager::new(setup_container_with_bsize(16));
+    let mut list = FreeList::<MemoryBackend>::create(&mut pager).unwrap();
+    let id1 = pager.aquire().unwrap();
+    let id2 = pager.aquire().unwrap();
+
+    list.push(&mut pager, id1).unwrap();
+    list.push(&mut pager, id2).unwrap();
+
+    let mut list = FreeList::<MemoryBackend>::load(&mut pager, list.anchor()).unwrap();
+    assert_eq!(list.len(), 2);
+    assert_eq!(list.pop(&mut pager).unwrap(), Some(id2));
+
+    let mut list = FreeList::<MemoryBackend>::load(&mut pager, list.anchor()).unwrap();
+    assert_eq!(list.len(), 1);
+    assert_eq!(list.pop(&mut pager).unwrap(), Some(id1));
+    assert!(list.is_empty());
+}
@@ -30,6 +30,14 @@ use crate::error::ArchiveResult;
 use crate::pager::Pager;
 use crate::tree::ids_per_node;
 
+/// An indirect node of the [`Tree`](crate::tree::Tree).
+///
+/// A node occupies a whole block and is filled with as many ids as fit into
+/// the block (see [`ids_per_node()`]). The remaining bytes are smaller than
+/// an id, there is no room for further data like a generation stamp. Adding
+/// such data would change the number of ids per node and with it the
+/// position of every block in the tree, so existing archives could not be
+/// read anymore.
 #[derive(Debug, PartialEq)]
 pub struct Node<B: Backend>(Vec<B::Id>);
 
@@ -25,10 +25,13 @@ use nuts_container::backend::BlockId;
 use nuts_container::container::Container;
 use nuts_container::memory::{Id, MemoryBackend};
 
+use crate::codec::Codec;
 use crate::error::Error;
+use crate::name_index::NameIndex;
 use crate::pager::Pager;
-use crate::tests::setup_container_with_bsize;
-use crate::tree::Tree;
+use crate::tests::{setup_container, setup_container_with_bsize};
+use crate::tree::free::FreeList;
+use crate::tree::{Tree, NUM_DIRECT};
 
 const BSIZE: u32 = 8;
 
@@ -58,83 +61,114 @@ fn read_node(container: &mut Container<MemoryBackend>, id: &Id) -> Vec<Id> {
     vec
 }
 
+fn setup_ser_tree() -> Tree<MemoryBackend> {
+    let mut tree = Tree::<MemoryBackend>::new(NUM_DIRECT);
+
+    for (n, id) in tree.direct.iter_mut().enumerate() {
+        *id = (n + 1).to_string().parse().unwrap();
+    }
+
+    tree.indirect = "13".parse().unwrap();
+    tree.d_indirect = "14".parse().unwrap();
+    tree.t_indirect = "15".parse().unwrap();
+    tree.nblocks = 16;
+
+    tree
+}
+
+const SER_REV1: [u8; 68] = [
+    0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4, 0, 0, 0, 5, 0, 0, 0, 6, 0, 0, 0, 7, 0, 0, 0, 8,
+    0, 0, 0, 9, 0, 0, 0, 10, 0, 0, 0, 11, 0, 0, 0, 12, 0, 0, 0, 13, 0, 0, 0, 14, 0, 0, 0, 15, 0, 0,
+    0, 0, 0, 0, 0, 16,
+];
+
 #[test]
 fn ser() {
-    let tree = Tree::<MemoryBackend> {
-        direct: [
-            "1".parse().unwrap(),
-            "2".parse().unwrap(),
-            "3".parse().unwrap(),
-            "4".parse().unwrap(),
-            "5".parse().unwrap(),
-            "6".parse().unwrap(),
-            "7".parse().unwrap(),
-            "8".parse().unwrap(),
-            "9".parse().unwrap(),
-            "10".parse().unwrap(),
-            "11".parse().unwrap(),
-            "12".parse().unwrap(),
-        ],
-        indirect: "13".parse().unwrap(),
-        d_indirect: "14".parse().unwrap(),
-        t_indirect: "15".parse().unwrap(),
-        nblocks: 16,
-        cache: vec![],
-    };
+    let tree = setup_ser_tree();
+    let codec = Codec::current();
     let mut writer = Writer::new(vec![]);
 
-    assert_eq!(writer.write(&tree).unwrap(), 68);
-    assert_eq!(
-        writer.into_target(),
-        [
-            0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4, 0, 0, 0, 5, 0, 0, 0, 6, 0, 0, 0, 7, 0,
-            0, 0, 8, 0, 0, 0, 9, 0, 0, 0, 10, 0, 0, 0, 11, 0, 0, 0, 12, 0, 0, 0, 13, 0, 0, 0, 14,
-            0, 0, 0, 15, 0, 0, 0, 0, 0, 0, 0, 16
-        ]
-    );
+    assert_eq!(tree.write(&codec, &mut writer).unwrap(), 72);
+
+    let buf = writer.into_target();
+    assert_eq!(buf[..4], [0, 0, 0, 12]);
+    assert_eq!(buf[4..], SER_REV1);
+}
+
+#[test]
+fn ser_rev1() {
+    let tree = setup_ser_tree();
+    let codec = Codec::new(1).unwrap();
+    let mut writer = Writer::new(vec![]);
+
+    assert_eq!(tree.write(&codec, &mut writer).unwrap(), 68);
+    assert_eq!(writer.into_target(), SER_REV1);
+}
+
+#[test]
+fn ser_rev1_num_direct() {
+    let tree = Tree::<MemoryBackend>::new(4);
+    let codec = Codec::new(1).unwrap();
+    let mut writer = Writer::new(vec![]);
+
+    let err = tree.write(&codec, &mut writer).unwrap_err();
+    assert!(matches!(err, nuts_bytes::Error::Custom(_)));
 }
 
 #[test]
 fn de() {
-    let mut reader = Reader::new(
-        [
-            0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4, 0, 0, 0, 5, 0, 0, 0, 6, 0, 0, 0, 7, 0,
-            0, 0, 8, 0, 0, 0, 9, 0, 0, 0, 10, 0, 0, 0, 11, 0, 0, 0, 12, 0, 0, 0, 13, 0, 0, 0, 14,
-            0, 0, 0, 15, 0, 0, 0, 0, 0, 0, 0, 16,
-        ]
-        .as_slice(),
-    );
-    let tree = reader.read::<Tree<MemoryBackend>>().unwrap();
+    let mut buf = vec![0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2];
+    buf.extend_from_slice(&SER_REV1[48..]);
+
+    let codec = Codec::current();
+    let tree = Tree::<MemoryBackend>::read(&codec, &mut codec.reader(&buf)).unwrap();
 
+    assert_eq!(tree.num_direct(), 2);
     assert_eq!(tree.direct[0], "1".parse().unwrap());
     assert_eq!(tree.direct[1], "2".parse().unwrap());
-    assert_eq!(tree.direct[2], "3".parse().unwrap());
-    assert_eq!(tree.direct[3], "4".parse().unwrap());
-    assert_eq!(tree.direct[4], "5".parse().unwrap());
-    assert_eq!(tree.direct[5], "6".parse().unwrap());
-    assert_eq!(tree.direct[6], "7".parse().unwrap());
-    assert_eq!(tree.direct[7], "8".parse().unwrap());
-    assert_eq!(tree.direct[8], "9".parse().unwrap());
-    assert_eq!(tree.direct[9], "10".parse().unwrap());
-    assert_eq!(tree.direct[10], "11".parse().unwrap());
-    assert_eq!(tree.direct[11], "12".parse().unwrap());
     assert_eq!(tree.indirect, "13".parse().unwrap());
     assert_eq!(tree.d_indirect, "14".parse().unwrap());
     assert_eq!(tree.t_indirect, "15".parse().unwrap());
     assert_eq!(tree.nblocks, 16);
 }
 
+#[test]
+fn de_rev1() {
+    let codec = Codec::new(1).unwrap();
+    let tree = Tree::<MemoryBackend>::read(&codec, &mut codec.reader(&SER_REV1)).unwrap();
+
+    assert_eq!(tree.num_direct(), NUM_DIRECT);
+
+    for (n, id) in tree.direct.iter().enumerate() {
+        assert_eq!(*id, (n + 1).to_string().parse().unwrap());
+    }
+
+    assert_eq!(tree.indirect, "13".parse().unwrap());
+    assert_eq!(tree.d_indirect, "14".parse().unwrap());
+    assert_eq!(tree.t_indirect, "15".parse().unwrap());
+    assert_eq!(tree.nblocks, 16);
+}
+
+#[test]
+fn de_num_direct_too_large() {
+    let buf = [0xff, 0xff, 0xff, 0xff, 0, 0, 0, 1];
+    let codec = Codec::current();
+
+    let err = Tree::<MemoryBackend>::read(&codec, &mut codec.reader(&buf)).unwrap_err();
+    assert!(matches!(err, nuts_bytes::Error::Custom(_)));
+}
+
 #[test]
 fn aquire() {
     let mut pager = Pager::new(setup_container_with_bsize(BSIZE));
-    let mut tree = Tree::<MemoryBackend>::new();
+    let mut tree = Tree::<MemoryBackend>::new(NUM_DIRECT);
 
     // direct
 
     let mut direct = [Id::null(); 12];
 
     for i in 0..12 {
-        direct[i] = tree.aquire(&mut pager).unwrap().clone();
+        direct[i] = *tree.aquire(&mut pager).unwrap();
         assert_direct!(tree, i as u64 + 1, direct);
         assert!(tree.indirect.is_null());
         assert!(tree.d_indirect.is_null());
@@ -146,7 +180,7 @@ fn aquire() {
     let mut indirect = [Id::null(); 2];
 
     for i in 0..2 {
-        indirect[i] = tree.aquire(&mut pager).unwrap().clone();
+        indirect[i] = *tree.aquire(&mut pager).unwrap();
 
         assert_direct!(tree, 12 + i as u64 + 1, direct);
         assert_eq!(&indirect[..], read_node(&mut pager, &tree.indirect));
@@ -159,7 +193,7 @@ fn aquire() {
     let mut d_indirect = [Id::null(); 4];
 
     for i in 0..4 {
-        d_indirect[i] = tree.aquire(&mut pager).unwrap().clone();
+        d_indirect[i] = *tree.aquire(&mut pager).unwrap();
 
         assert_direct!(tree, 12 + 2 + i as u64 + 1, direct);
         assert_eq!(&indirect[..], read_node(&mut pager, &tree.indirect));
@@ -181,7 +215,7 @@ fn aquire() {
     let mut t_indirect = [Id::null(); 8];
 
     for i in 0..8 {
-        t_indirect[i] = tree.aquire(&mut pager).unwrap().clone();
+        t_indirect[i] = *tree.aquire(&mut pager).unwrap();
 
         assert_direct!(tree, 12 + 2 + 4 + i as u64 + 1, direct);
         assert_eq!(&indirect[..], read_node(&mut pager, &tree.indirect));
@@ -190,14 +224,13 @@ fn aquire() {
 
         let d_leafs: Vec<Id> = d_node
             .iter()
-            .map(|id| {
+            .flat_map(|id| {
                 if id.is_null() {
                     vec![Id::null(); 2]
                 } else {
                     read_node(&mut pager, id)
                 }
             })
-            .flatten()
             .collect();
 
         assert_eq!(d_leafs, d_indirect);
@@ -205,48 +238,505 @@ fn aquire() {
         let t_node = read_node(&mut pager, &tree.t_indirect);
         let t_leafs: Vec<Id> = t_node
             .iter()
-            .map(|id| {
+            .flat_map(|id| {
                 if id.is_null() {
                     vec![Id::null(); 2]
                 } else {
                     read_node(&mut pager, id)
                 }
             })
-            .flatten()
             .collect::<Vec<Id>>()
             .iter()
-            .map(|id| {
+            .flat_map(|id| {
                 if id.is_null() {
                     vec![Id::null(); 2]
                 } else {
                     read_node(&mut pager, id)
                 }
             })
-            .flatten()
             .collect();
 
         assert_eq!(t_leafs, t_indirect);
     }
 
     let err = tree.aquire(&mut pager).unwrap_err();
-    assert!(matches!(err, Error::Full));
+    assert!(matches!(err, Error::Full(0)));
 }
 
 #[test]
 fn lookup() {
     let mut pager = Pager::new(setup_container_with_bsize(BSIZE));
-    let mut tree = Tree::<MemoryBackend>::new();
+    let mut tree = Tree::<MemoryBackend>::new(NUM_DIRECT);
+    let mut id_vec = vec![];
+
+    for _ in 0..26 {
+        let id = *tree.aquire(&mut pager).unwrap();
+        id_vec.push(id);
+    }
+
+    for (i, expected) in id_vec.iter().enumerate() {
+        let id = tree.lookup(&mut pager, i).unwrap().unwrap();
+        assert_eq!(expected, id);
+    }
+
+    assert!(tree.lookup(&mut pager, 26).is_none());
+}
+
+#[test]
+fn lookup_mapped() {
+    let mut pager = Pager::new(setup_container_with_bsize(BSIZE));
+    let mut tree = Tree::<MemoryBackend>::new(NUM_DIRECT);
     let mut id_vec = vec![];
 
+    tree.set_mapped(true);
+
     for _ in 0..26 {
-        let id = tree.aquire(&mut pager).unwrap().clone();
+        let id = *tree.aquire(&mut pager).unwrap();
         id_vec.push(id);
     }
 
-    for i in 0..26 {
+    for (i, expected) in id_vec.iter().enumerate() {
         let id = tree.lookup(&mut pager, i).unwrap().unwrap();
-        assert_eq!(&id_vec[i], id);
+        assert_eq!(expected, id);
     }
 
     assert!(tree.lookup(&mut pager, 26).is_none());
+    assert_eq!(tree.mapping.as_ref().unwrap().ids, id_vec);
+}
+
+#[test]
+fn lookup_mapped_generation() {
+    let mut pager = Pager::new(setup_container_with_bsize(BSIZE));
+    let mut tree = Tree::<MemoryBackend>::new(NUM_DIRECT);
+    let mut id_vec = vec![];
+
+    tree.set_mapped(true);
+
+    for _ in 0..14 {
+        let id = *tree.aquire(&mut pager).unwrap();
+        id_vec.push(id);
+    }
+
+    assert_eq!(tree.lookup(&mut pager, 13).unwrap().unwrap(), &id_vec[13]);
+
+    // modify the indirect node behind the back of the tree, the lookup is
+    // still served from the mapping
+    let mut writer = Writer::new(vec![]);
+    writer.write(&id_vec[0]).unwrap();
+    writer.write(&id_vec[0]).unwrap();
+    pager.write(&tree.indirect, &writer.into_target()).unwrap();
+
+    assert_eq!(tree.lookup(&mut pager, 13).unwrap().unwrap(), &id_vec[13]);
+
+    // only the tree modifies its nodes, a new generation keeps the mapping
+    pager.inc_generation();
+    tree.cache.clear();
+
+    assert_eq!(tree.lookup(&mut pager, 13).unwrap().unwrap(), &id_vec[13]);
+
+    // the mapping is loaded again, when enabled again
+    tree.set_mapped(true);
+
+    assert_eq!(tree.lookup(&mut pager, 13).unwrap().unwrap(), &id_vec[0]);
+}
+
+#[test]
+fn aquire_updates_mapping() {
+    let mut pager = Pager::new(setup_container_with_bsize(BSIZE));
+    let mut tree = Tree::<MemoryBackend>::new(NUM_DIRECT);
+
+    tree.set_mapped(true);
+
+    let id0 = *tree.aquire(&mut pager).unwrap();
+    assert_eq!(tree.lookup(&mut pager, 0).unwrap().unwrap(), &id0);
+    assert!(tree.mapping.is_some());
+
+    let id1 = *tree.aquire(&mut pager).unwrap();
+    tree.aquire_hole(&mut pager).unwrap();
+    assert_eq!(tree.mapping.as_ref().unwrap().ids, [id0, id1, Id::null()]);
+    assert_eq!(tree.lookup(&mut pager, 1).unwrap().unwrap(), &id1);
+    assert!(tree.lookup(&mut pager, 2).is_none());
+}
+
+#[test]
+fn modify_updates_mapping() {
+    let mut pager = Pager::new(setup_container_with_bsize(BSIZE));
+    let mut tree = Tree::<MemoryBackend>::new(NUM_DIRECT);
+
+    for idx in 0..24 {
+        if idx == 20 {
+            tree.aquire_hole(&mut pager).unwrap();
+        } else {
+            tree.aquire(&mut pager).unwrap();
+        }
+    }
+
+    tree.set_mapped(true);
+    assert!(tree.lookup(&mut pager, 0).is_some());
+
+    tree.remove(&mut pager, 2, 5).unwrap();
+    tree.fill_hole(&mut pager, 15).unwrap();
+
+    let mut ids = tree.mapping.as_ref().unwrap().ids.clone();
+    ids.swap(0, 10);
+    tree.replace(&mut pager, 0, ids[..12].to_vec()).unwrap();
+
+    let mapped = tree.mapping.as_ref().unwrap().ids.clone();
+    assert_eq!(mapped, ids);
+
+    // the mapping matches the nodes of the tree
+    tree.set_mapped(false);
+    assert_eq!(lookup_all(&mut pager, &mut tree), mapped);
+}
+
+#[test]
+fn aquire_content_reserved() {
+    let mut pager = Pager::new(setup_container_with_bsize(BSIZE));
+    let mut tree = Tree::<MemoryBackend>::new(NUM_DIRECT);
+
+    assert_eq!(tree.capacity(&pager), 26);
+
+    tree.set_reserved(10);
+
+    for _ in 0..24 {
+        tree.aquire_content(&mut pager).unwrap();
+    }
+
+    let err = tree.aquire_content(&mut pager).unwrap_err();
+    assert!(matches!(err, Error::Full(0)));
+
+    for _ in 0..2 {
+        tree.aquire(&mut pager).unwrap();
+    }
+
+    let err = tree.aquire(&mut pager).unwrap_err();
+    assert!(matches!(err, Error::Full(0)));
+}
+
+#[test]
+fn aquire_content_unreserved() {
+    let mut pager = Pager::new(setup_container_with_bsize(BSIZE));
+    let mut tree = Tree::<MemoryBackend>::new(NUM_DIRECT);
+
+    for _ in 0..26 {
+        tree.aquire_content(&mut pager).unwrap();
+    }
+
+    let err = tree.aquire_content(&mut pager).unwrap_err();
+    assert!(matches!(err, Error::Full(0)));
+}
+
+#[test]
+fn set_reserved_max() {
+    let mut pager = Pager::new(setup_container_with_bsize(BSIZE));
+    let mut tree = Tree::<MemoryBackend>::new(NUM_DIRECT);
+
+    tree.set_reserved(200);
+
+    let err = tree.aquire_content(&mut pager).unwrap_err();
+    assert!(matches!(err, Error::Full(0)));
+
+    tree.aquire(&mut pager).unwrap();
+}
+
+fn lookup_all(pager: &mut Pager<MemoryBackend>, tree: &mut Tree<MemoryBackend>) -> Vec<Id> {
+    (0..tree.nblocks as usize)
+        .map(|idx| *tree.lookup(pager, idx).unwrap().unwrap())
+        .collect()
+}
+
+#[test]
+fn remove() {
+    let mut pager = Pager::new(setup_container_with_bsize(BSIZE));
+    let mut tree = Tree::<MemoryBackend>::new(NUM_DIRECT);
+
+    for _ in 0..26 {
+        tree.aquire(&mut pager).unwrap();
+    }
+
+    let mut ids = lookup_all(&mut pager, &mut tree);
+
+    let removed = tree.remove(&mut pager, 5, 10).unwrap();
+    assert_eq!(removed, ids.drain(5..15).collect::<Vec<_>>());
+    assert_eq!(tree.nblocks, 16);
+    assert_eq!(lookup_all(&mut pager, &mut tree), ids);
+    assert!(tree.lookup(&mut pager, 16).is_none());
+
+    // the nodes were written back
+
+    let codec = Codec::current();
+    let mut writer = codec.writer(vec![]);
+    tree.write(&codec, &mut writer).unwrap();
+
+    let buf = writer.into_target();
+    let mut tree = Tree::<MemoryBackend>::read(&codec, &mut codec.reader(&buf)).unwrap();
+    assert_eq!(lookup_all(&mut pager, &mut tree), ids);
+
+    // the tail is reused
+
+    ids.push(*tree.aquire(&mut pager).unwrap());
+    assert!(!ids[16].is_null());
+    assert_eq!(tree.nblocks, 17);
+    assert_eq!(lookup_all(&mut pager, &mut tree), ids);
+}
+
+#[test]
+fn remove_tail() {
+    let mut pager = Pager::new(setup_container_with_bsize(BSIZE));
+    let mut tree = Tree::<MemoryBackend>::new(NUM_DIRECT);
+
+    for _ in 0..20 {
+        tree.aquire(&mut pager).unwrap();
+    }
+
+    let mut ids = lookup_all(&mut pager, &mut tree);
+
+    let removed = tree.remove(&mut pager, 18, 2).unwrap();
+    assert_eq!(removed, ids.drain(18..).collect::<Vec<_>>());
+    assert_eq!(lookup_all(&mut pager, &mut tree), ids);
+
+    let removed = tree.remove(&mut pager, 0, 18).unwrap();
+    assert_eq!(removed, ids);
+    assert_eq!(tree.nblocks, 0);
+    assert!(tree.lookup(&mut pager, 0).is_none());
+}
+
+#[test]
+fn replace() {
+    let mut pager = Pager::new(setup_container_with_bsize(BSIZE));
+    let mut tree = Tree::<MemoryBackend>::new(NUM_DIRECT);
+
+    for _ in 0..26 {
+        tree.aquire(&mut pager).unwrap();
+    }
+
+    let mut ids = lookup_all(&mut pager, &mut tree);
+
+    ids.reverse();
+    tree.replace(&mut pager, 0, ids.clone()).unwrap();
+    assert_eq!(tree.nblocks, 26);
+    assert_eq!(lookup_all(&mut pager, &mut tree), ids);
+
+    // the nodes were written back
+
+    let codec = Codec::current();
+    let mut writer = codec.writer(vec![]);
+    tree.write(&codec, &mut writer).unwrap();
+
+    let buf = writer.into_target();
+    let mut tree = Tree::<MemoryBackend>::read(&codec, &mut codec.reader(&buf)).unwrap();
+    assert_eq!(lookup_all(&mut pager, &mut tree), ids);
+}
+
+#[test]
+fn release_reuse() {
+    let mut pager = Pager::new(setup_container_with_bsize(BSIZE));
+    let mut tree = Tree::<MemoryBackend>::new(NUM_DIRECT);
+
+    tree.set_free_list(FreeList::create(&mut pager).unwrap());
+
+    for _ in 0..20 {
+        tree.aquire(&mut pager).unwrap();
+    }
+
+    let ids = lookup_all(&mut pager, &mut tree);
+
+    for id in tree.remove(&mut pager, 2, 16).unwrap() {
+        tree.release(&mut pager, id).unwrap();
+    }
+
+    assert_eq!(tree.free_list().unwrap().len(), 16);
+
+    // released blocks are reused in reverse order on all levels
+    for n in 0..16 {
+        assert_eq!(*tree.aquire(&mut pager).unwrap(), ids[17 - n]);
+    }
+
+    assert!(tree.free_list().unwrap().is_empty());
+
+    // then new blocks are aquired from the container
+    let id = *tree.aquire(&mut pager).unwrap();
+    assert!(!ids.contains(&id));
+
+    let mut expected = vec![ids[0], ids[1], ids[18], ids[19]];
+    expected.extend((2..18).rev().map(|n| ids[n]));
+    expected.push(id);
+
+    assert_eq!(lookup_all(&mut pager, &mut tree), expected);
+}
+
+#[test]
+fn names_root() {
+    let mut pager = Pager::new(setup_container());
+    let mut tree = Tree::<MemoryBackend>::new(NUM_DIRECT);
+
+    let mut names = NameIndex::create(&mut pager).unwrap();
+    names.insert(&mut pager, "f1", 0).unwrap();
+
+    tree.set_names_root(*names.first());
+    assert!(tree.has_names());
+    assert!(tree.names().is_none());
+
+    let names = tree.names_mut(&mut pager).unwrap().unwrap();
+    assert_eq!(names.get("f1"), Some(0));
+    assert!(tree.names().is_some());
+}
+
+#[test]
+fn warm_up_names() {
+    let mut pager = Pager::new(setup_container());
+    let mut tree = Tree::<MemoryBackend>::new(NUM_DIRECT);
+
+    let names = NameIndex::create(&mut pager).unwrap();
+
+    tree.set_names_root(*names.first());
+    tree.warm_up(&mut pager).unwrap();
+    assert_eq!(tree.names().unwrap().len(), 0);
+}
+
+#[test]
+fn free_anchor() {
+    let mut pager = Pager::new(setup_container());
+    let mut tree = Tree::<MemoryBackend>::new(NUM_DIRECT);
+
+    let mut free = FreeList::create(&mut pager).unwrap();
+    let id = pager.aquire().unwrap();
+    free.push(&mut pager, id).unwrap();
+
+    tree.set_free_anchor(*free.anchor());
+    assert!(tree.has_free_list());
+    assert!(tree.free_list().is_none());
+
+    assert_eq!(*tree.aquire(&mut pager).unwrap(), id);
+    assert!(tree.free_list().unwrap().is_empty());
+}
+
+#[test]
+fn warm_up_free_anchor() {
+    let mut pager = Pager::new(setup_container());
+    let mut tree = Tree::<MemoryBackend>::new(NUM_DIRECT);
+
+    let free = FreeList::create(&mut pager).unwrap();
+
+    tree.set_free_anchor(*free.anchor());
+    tree.warm_up(&mut pager).unwrap();
+    assert!(tree.free_list().unwrap().is_empty());
+}
+
+#[test]
+fn warm_up_empty() {
+    let mut pager = Pager::new(setup_container_with_bsize(BSIZE));
+    let mut tree = Tree::<MemoryBackend>::new(NUM_DIRECT);
+
+    tree.warm_up(&mut pager).unwrap();
+    assert!(tree.cache.is_empty());
+}
+
+#[test]
+fn warm_up() {
+    let mut pager = Pager::new(setup_container_with_bsize(BSIZE));
+    let mut tree = Tree::<MemoryBackend>::new(NUM_DIRECT);
+
+    for _ in 0..20 {
+        tree.aquire(&mut pager).unwrap();
+    }
+
+    let codec = Codec::current();
+    let mut writer = codec.writer(vec![]);
+    tree.write(&codec, &mut writer).unwrap();
+
+    let buf = writer.into_target();
+    let mut tree = Tree::<MemoryBackend>::read(&codec, &mut codec.reader(&buf)).unwrap();
+    assert!(tree.cache.is_empty());
+
+    tree.warm_up(&mut pager).unwrap();
+    assert_eq!(tree.cache.len(), 3);
+    assert_eq!(tree.cache[0].id(), &tree.t_indirect);
+}
+
+#[test]
+fn send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<Tree<MemoryBackend>>();
+}
+
+#[test]
+fn aquire_twice() {
+    let mut pager = Pager::new(setup_container_with_bsize(BSIZE));
+    let mut tree = Tree::<MemoryBackend>::new(NUM_DIRECT);
+    let mut ids = vec![];
+
+    for _ in 0..14 {
+        ids.push(*tree.aquire(&mut pager).unwrap());
+    }
+
+    // pretend the last direct and indirect blocks were not aquired, the
+    // existing blocks are returned
+    for nblocks in [11, 13] {
+        tree.nblocks = nblocks;
+
+        let id = *tree.aquire(&mut pager).unwrap();
+        assert_eq!(id, ids[nblocks as usize]);
+        assert_eq!(tree.nblocks, nblocks);
+    }
+}
+
+#[test]
+fn aquire_twice_strict() {
+    let mut pager = Pager::new(setup_container_with_bsize(BSIZE));
+    let mut tree = Tree::<MemoryBackend>::new(NUM_DIRECT);
+
+    tree.set_strict(true);
+
+    for _ in 0..14 {
+        tree.aquire(&mut pager).unwrap();
+    }
+
+    for nblocks in [11, 13] {
+        tree.nblocks = nblocks;
+
+        let err = tree.aquire(&mut pager).unwrap_err();
+        assert!(matches!(err, Error::TreeInconsistent(n) if n == nblocks));
+        assert_eq!(tree.nblocks, nblocks);
+    }
+}
+
+#[test]
+fn holes() {
+    let mut pager = Pager::new(setup_container_with_bsize(BSIZE));
+    let mut tree = Tree::<MemoryBackend>::new(NUM_DIRECT);
+    let mut ids = vec![];
+
+    // the holes span direct, indirect, double and triple indirect blocks
+    for idx in 0..26 {
+        if (10..20).contains(&idx) {
+            tree.aquire_hole(&mut pager).unwrap();
+            ids.push(Id::null());
+        } else {
+            ids.push(*tree.aquire_content(&mut pager).unwrap());
+        }
+    }
+
+    assert_eq!(tree.nblocks(), 26);
+    assert!(matches!(tree.aquire_hole(&mut pager), Err(Error::Full(0))));
+
+    for (idx, id) in ids.iter().enumerate() {
+        match tree.lookup(&mut pager, idx) {
+            Some(result) => assert_eq!(result.unwrap(), id),
+            None => assert!(id.is_null()),
+        }
+    }
+
+    let id = *tree.fill_hole(&mut pager, 15).unwrap();
+    assert!(!id.is_null());
+    assert_eq!(tree.nblocks(), 26);
+    assert_eq!(tree.lookup(&mut pager, 15).unwrap().unwrap(), &id);
+    assert!(tree.lookup(&mut pager, 16).is_none());
+
+    let removed = tree.remove(&mut pager, 14, 3).unwrap();
+    assert_eq!(removed, [Id::null(), id, Id::null()]);
+    assert_eq!(tree.nblocks(), 23);
+    assert!(tree.lookup(&mut pager, 16).is_none());
+    assert_eq!(tree.lookup(&mut pager, 17).unwrap().unwrap(), &ids[20]);
 }
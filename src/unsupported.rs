@@ -0,0 +1,121 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use log::{debug, warn};
+use nuts_container::backend::Backend;
+use std::fmt;
+
+use crate::error::{ArchiveResult, Error};
+
+/// A feature of an import source, which cannot be represented in the
+/// archive.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnsupportedFeature {
+    /// An entry type without a counterpart in the archive (e.g. a sparse
+    /// file). The whole entry is affected.
+    EntryType,
+
+    /// Extended attributes of an entry, they are not stored in the
+    /// archive.
+    ExtendedAttributes,
+
+    /// A user or group id, which does not fit into 32 bits. The owner is
+    /// not recorded.
+    Owner,
+}
+
+impl fmt::Display for UnsupportedFeature {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::EntryType => "entry type",
+            Self::ExtendedAttributes => "extended attributes",
+            Self::Owner => "owner",
+        };
+
+        fmt.write_str(s)
+    }
+}
+
+/// What happens, if an import source uses an [`UnsupportedFeature`].
+///
+/// See [`Archive::set_unsupported_action()`](crate::Archive::set_unsupported_action).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FeatureAction {
+    /// The feature is dropped silently.
+    Skip,
+
+    /// The feature is dropped and a warning is logged.
+    #[default]
+    Warn,
+
+    /// The import fails with an [`Error::Unsupported`] error.
+    Error,
+}
+
+/// The [`FeatureAction`] for each [`UnsupportedFeature`].
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct FeaturePolicy {
+    entry_type: FeatureAction,
+    xattrs: FeatureAction,
+    owner: FeatureAction,
+}
+
+impl FeaturePolicy {
+    pub(crate) fn set(&mut self, feature: UnsupportedFeature, action: FeatureAction) {
+        match feature {
+            UnsupportedFeature::EntryType => self.entry_type = action,
+            UnsupportedFeature::ExtendedAttributes => self.xattrs = action,
+            UnsupportedFeature::Owner => self.owner = action,
+        }
+    }
+
+    fn get(&self, feature: UnsupportedFeature) -> FeatureAction {
+        match feature {
+            UnsupportedFeature::EntryType => self.entry_type,
+            UnsupportedFeature::ExtendedAttributes => self.xattrs,
+            UnsupportedFeature::Owner => self.owner,
+        }
+    }
+
+    /// Applies the action for `feature` used by the entry `name`.
+    ///
+    /// Returns an [`Error::Unsupported`] error, if the import should fail.
+    /// The caller drops the feature otherwise.
+    #[cfg_attr(not(feature = "tar"), allow(dead_code))]
+    pub(crate) fn handle<B: Backend>(
+        &self,
+        feature: UnsupportedFeature,
+        name: &str,
+    ) -> ArchiveResult<(), B> {
+        match self.get(feature) {
+            FeatureAction::Skip => {
+                debug!("{}: skip unsupported {}", name, feature);
+                Ok(())
+            }
+            FeatureAction::Warn => {
+                warn!("{}: skip unsupported {}", name, feature);
+                Ok(())
+            }
+            FeatureAction::Error => Err(Error::Unsupported(name.to_string(), feature)),
+        }
+    }
+}
@@ -25,13 +25,14 @@ mod tests;
 
 use log::{debug, warn};
 use nuts_bytes::{FromBytes, Reader, ToBytes, Writer};
-use nuts_container::backend::Backend;
+use nuts_container::backend::{Backend, BlockId};
 use nuts_container::container::Container;
 use std::fmt;
 use thiserror::Error;
 
 use crate::error::{ArchiveResult, Error};
 use crate::magic::{validate_magic, Magic, MagicErrorFactory, MAGIC};
+use crate::pager::Pager;
 
 #[derive(Debug, Error)]
 #[error("invalid userdata")]
@@ -45,16 +46,37 @@ impl MagicErrorFactory for UserdataMagicError {
 
 // magic_type!(Magic, "invalid userdata-magic");
 
+/// Size of the digest of the token which froze the archive.
+pub const FREEZE_DIGEST_SIZE: usize = 32;
+
+/// Size of the length prefix in front of the userdata in an anchor block.
+const ANCHOR_PREFIX_SIZE: usize = 4;
+
 #[derive(FromBytes, ToBytes)]
 pub struct Userdata<B: Backend> {
     #[nuts_bytes(map_from_bytes = validate_magic::<UserdataMagicError>)]
     magic: Magic,
     pub id: B::Id,
+    #[nuts_bytes(skip)]
+    pub freeze: Option<[u8; FREEZE_DIGEST_SIZE]>,
+    #[nuts_bytes(skip)]
+    pub index: Option<B::Id>,
+    #[nuts_bytes(skip)]
+    pub free: Option<B::Id>,
+    #[nuts_bytes(skip)]
+    pub backup: Option<B::Id>,
 }
 
 impl<B: Backend> Userdata<B> {
     fn new(id: B::Id) -> Userdata<B> {
-        Userdata { magic: MAGIC, id }
+        Userdata {
+            magic: MAGIC,
+            id,
+            freeze: None,
+            index: None,
+            free: None,
+            backup: None,
+        }
     }
 
     pub fn create(container: &mut Container<B>, force: bool) -> ArchiveResult<Userdata<B>, B> {
@@ -65,23 +87,174 @@ impl<B: Backend> Userdata<B> {
         let id = container.aquire()?;
         let userdata = Userdata::<B>::new(id);
 
-        let mut writer = Writer::new(vec![]);
-
-        writer.write(&userdata)?;
-        container.update_userdata(&writer.into_target())?;
+        userdata.flush(container)?;
 
         debug!("userdata created: {:?}", userdata);
 
         Ok(userdata)
     }
 
+    /// Creates the userdata of an anchored archive.
+    ///
+    /// Instead of the userdata record of the container a new block is
+    /// aquired, which holds the userdata. The id of this anchor block is
+    /// returned together with the userdata. The userdata record of the
+    /// container is left untouched.
+    pub fn create_anchored(container: &mut Container<B>) -> ArchiveResult<(Userdata<B>, B::Id), B> {
+        let anchor = container.aquire()?;
+        let id = container.aquire()?;
+        let userdata = Userdata::<B>::new(id);
+
+        userdata.flush_anchored(container, &anchor)?;
+
+        debug!("userdata created: {:?}, anchor: {}", userdata, anchor);
+
+        Ok((userdata, anchor))
+    }
+
+    /// Loads the userdata from the location attached to `pager`.
+    ///
+    /// This is the [anchor block](Pager::anchor), if any, and the userdata
+    /// record of the container otherwise.
+    pub fn fetch(pager: &mut Pager<B>) -> ArchiveResult<Userdata<B>, B> {
+        match pager.anchor().cloned() {
+            Some(anchor) => Self::load_anchored(pager, &anchor),
+            None => Self::load(pager),
+        }
+    }
+
+    /// Writes the userdata to the location attached to `pager`.
+    ///
+    /// See [`Userdata::fetch()`].
+    pub fn store(&self, pager: &mut Pager<B>) -> ArchiveResult<(), B> {
+        match pager.anchor().cloned() {
+            Some(anchor) => self.flush_anchored(pager, &anchor),
+            None => self.flush(pager),
+        }
+    }
+
+    /// Writes the userdata into the container.
+    ///
+    /// The freeze digest is appended behind the serialized userdata, if the
+    /// archive is frozen. The id of the first block of the name index
+    /// follows the digest, if the archive has a name index. The anchor of
+    /// the free list of the tree and the backup copy of the header are the
+    /// last ones. Missing predecessors are filled up with a zero digest or a
+    /// null id. This way userdata of unfrozen archives without an index
+    /// keep their layout.
+    pub fn flush(&self, container: &mut Container<B>) -> ArchiveResult<(), B> {
+        container.update_userdata(&self.to_vec()?)?;
+
+        Ok(())
+    }
+
+    /// Writes the userdata into the anchor block `anchor`.
+    ///
+    /// The serialized userdata is prefixed with its length, the remaining
+    /// part of the block is zero. The layout of the userdata is the same as
+    /// for [`Userdata::flush()`].
+    pub fn flush_anchored(
+        &self,
+        container: &mut Container<B>,
+        anchor: &B::Id,
+    ) -> ArchiveResult<(), B> {
+        let bytes = self.to_vec()?;
+        let mut buf = (bytes.len() as u32).to_be_bytes().to_vec();
+
+        buf.extend_from_slice(&bytes);
+
+        if buf.len() > container.block_size() as usize {
+            return Err(Error::InvalidBlockSize);
+        }
+
+        buf.resize(container.block_size() as usize, 0);
+        container.write(anchor, &buf)?;
+
+        Ok(())
+    }
+
     pub fn load(container: &mut Container<B>) -> ArchiveResult<Userdata<B>, B> {
         if container.userdata().is_empty() {
             return Err(Error::InvalidUserdata(None));
         }
 
-        let mut reader = Reader::new(container.userdata());
-        let userdata = reader.read::<Userdata<B>>()?;
+        Self::from_slice(container.userdata())
+    }
+
+    /// Loads the userdata from the anchor block `anchor`.
+    ///
+    /// An anchor block without userdata is treated like an empty userdata
+    /// record.
+    pub fn load_anchored(
+        container: &mut Container<B>,
+        anchor: &B::Id,
+    ) -> ArchiveResult<Userdata<B>, B> {
+        let mut buf = vec![0; container.block_size() as usize];
+
+        container.read(anchor, &mut buf)?;
+
+        let mut prefix = [0; ANCHOR_PREFIX_SIZE];
+
+        prefix.copy_from_slice(&buf[..ANCHOR_PREFIX_SIZE]);
+
+        let len = u32::from_be_bytes(prefix) as usize;
+
+        if len == 0 || len > buf.len() - ANCHOR_PREFIX_SIZE {
+            return Err(Error::InvalidUserdata(None));
+        }
+
+        Self::from_slice(&buf[ANCHOR_PREFIX_SIZE..ANCHOR_PREFIX_SIZE + len])
+    }
+
+    fn to_vec(&self) -> ArchiveResult<Vec<u8>, B> {
+        let mut writer = Writer::new(vec![]);
+
+        writer.write(self)?;
+
+        let backup = self.backup.is_some();
+
+        if self.freeze.is_some() || self.index.is_some() || self.free.is_some() || backup {
+            writer.write(&self.freeze.unwrap_or([0; FREEZE_DIGEST_SIZE]))?;
+        }
+
+        if self.index.is_some() || self.free.is_some() || backup {
+            writer.write(&self.index.clone().unwrap_or_else(B::Id::null))?;
+        }
+
+        if self.free.is_some() || backup {
+            writer.write(&self.free.clone().unwrap_or_else(B::Id::null))?;
+        }
+
+        if let Some(id) = self.backup.as_ref() {
+            writer.write(id)?;
+        }
+
+        Ok(writer.into_target())
+    }
+
+    fn from_slice(bytes: &[u8]) -> ArchiveResult<Userdata<B>, B> {
+        let mut reader = Reader::new(bytes);
+        let mut userdata = reader.read::<Userdata<B>>()?;
+
+        if reader.as_ref().len() >= FREEZE_DIGEST_SIZE {
+            let digest: [u8; FREEZE_DIGEST_SIZE] = reader.read()?;
+
+            if digest != [0; FREEZE_DIGEST_SIZE] {
+                userdata.freeze = Some(digest);
+            }
+
+            if !reader.as_ref().is_empty() {
+                userdata.index = Some(reader.read::<B::Id>()?).filter(|id| !id.is_null());
+            }
+
+            if !reader.as_ref().is_empty() {
+                userdata.free = Some(reader.read::<B::Id>()?).filter(|id| !id.is_null());
+            }
+
+            if !reader.as_ref().is_empty() {
+                userdata.backup = Some(reader.read()?);
+            }
+        }
 
         debug!("userdata loaded: {:?}", userdata);
 
@@ -95,6 +268,12 @@ impl<B: Backend> Userdata<B> {
 
 impl<B: Backend> fmt::Debug for Userdata<B> {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt.debug_struct("Userdata").field("id", &self.id).finish()
+        fmt.debug_struct("Userdata")
+            .field("id", &self.id)
+            .field("frozen", &self.freeze.is_some())
+            .field("index", &self.index)
+            .field("free", &self.free)
+            .field("backup", &self.backup)
+            .finish()
     }
 }
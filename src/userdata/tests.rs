@@ -26,6 +26,7 @@ use nuts_container::memory::{Id, MemoryBackend};
 use crate::error::Error;
 use crate::tests::{into_error, setup_container};
 use crate::userdata::{Userdata, UserdataMagicError};
+use crate::Archive;
 
 const USERDATA: [u8; 16] = [
     b'n', b'u', b't', b's', b'-', b'a', b'r', b'c', b'h', b'i', b'v', b'e', 0, 0, 0, 1,
@@ -52,6 +53,7 @@ fn de_ok() {
     let userdata = reader.read::<Userdata<MemoryBackend>>().unwrap();
     assert_eq!(userdata.magic, *b"nuts-archive");
     assert_eq!(userdata.id.to_string(), "1");
+    assert!(userdata.freeze.is_none());
 }
 
 #[test]
@@ -86,6 +88,7 @@ fn create_userdata_forced() {
 
     assert_eq!(userdata.magic, *b"nuts-archive");
     assert_eq!(userdata.id.to_string(), "1");
+    assert!(userdata.freeze.is_none());
 }
 
 #[test]
@@ -95,6 +98,7 @@ fn create_no_userdata_unforced() {
 
     assert_eq!(userdata.magic, *b"nuts-archive");
     assert_eq!(userdata.id.to_string(), "1");
+    assert!(userdata.freeze.is_none());
 }
 
 #[test]
@@ -104,6 +108,7 @@ fn create_no_userdata_forced() {
 
     assert_eq!(userdata.magic, *b"nuts-archive");
     assert_eq!(userdata.id.to_string(), "1");
+    assert!(userdata.freeze.is_none());
 }
 
 #[test]
@@ -137,6 +142,7 @@ fn load_ok() {
 
     assert_eq!(userdata.magic, *b"nuts-archive");
     assert_eq!(userdata.id.to_string(), "1");
+    assert!(userdata.freeze.is_none());
 }
 
 #[test]
@@ -151,4 +157,216 @@ fn load_ok_attached() {
 
     assert_eq!(userdata.magic, *b"nuts-archive");
     assert_eq!(userdata.id.to_string(), "1");
+    assert!(userdata.freeze.is_none());
+}
+
+#[test]
+fn flush_freeze() {
+    let mut container = setup_container();
+    let mut userdata = Userdata::<MemoryBackend>::create(&mut container, false).unwrap();
+
+    userdata.freeze = Some([7; 32]);
+    userdata.flush(&mut container).unwrap();
+
+    assert_eq!(container.userdata(), [&USERDATA[..], &[7; 32]].concat());
+
+    let userdata = Userdata::<MemoryBackend>::load(&mut container).unwrap();
+    assert_eq!(userdata.id.to_string(), "1");
+    assert_eq!(userdata.freeze, Some([7; 32]));
+}
+
+#[test]
+fn flush_index() {
+    let mut container = setup_container();
+    let mut userdata = Userdata::<MemoryBackend>::create(&mut container, false).unwrap();
+
+    userdata.index = Some("2".parse::<Id>().unwrap());
+    userdata.flush(&mut container).unwrap();
+
+    assert_eq!(
+        container.userdata(),
+        [&USERDATA[..], &[0; 32], &[0, 0, 0, 2]].concat()
+    );
+
+    let userdata = Userdata::<MemoryBackend>::load(&mut container).unwrap();
+    assert_eq!(userdata.id.to_string(), "1");
+    assert!(userdata.freeze.is_none());
+    assert_eq!(userdata.index.unwrap().to_string(), "2");
+}
+
+#[test]
+fn flush_freeze_index() {
+    let mut container = setup_container();
+    let mut userdata = Userdata::<MemoryBackend>::create(&mut container, false).unwrap();
+
+    userdata.freeze = Some([7; 32]);
+    userdata.index = Some("2".parse::<Id>().unwrap());
+    userdata.flush(&mut container).unwrap();
+
+    assert_eq!(
+        container.userdata(),
+        [&USERDATA[..], &[7; 32], &[0, 0, 0, 2]].concat()
+    );
+
+    let userdata = Userdata::<MemoryBackend>::load(&mut container).unwrap();
+    assert_eq!(userdata.freeze, Some([7; 32]));
+    assert_eq!(userdata.index.unwrap().to_string(), "2");
+}
+
+#[test]
+fn flush_free() {
+    let mut container = setup_container();
+    let mut userdata = Userdata::<MemoryBackend>::create(&mut container, false).unwrap();
+
+    userdata.free = Some("3".parse::<Id>().unwrap());
+    userdata.flush(&mut container).unwrap();
+
+    assert_eq!(
+        container.userdata(),
+        [&USERDATA[..], &[0; 32], &[0xff; 4], &[0, 0, 0, 3]].concat()
+    );
+
+    let userdata = Userdata::<MemoryBackend>::load(&mut container).unwrap();
+    assert!(userdata.freeze.is_none());
+    assert!(userdata.index.is_none());
+    assert_eq!(userdata.free.unwrap().to_string(), "3");
+}
+
+#[test]
+fn flush_index_free() {
+    let mut container = setup_container();
+    let mut userdata = Userdata::<MemoryBackend>::create(&mut container, false).unwrap();
+
+    userdata.index = Some("2".parse::<Id>().unwrap());
+    userdata.free = Some("3".parse::<Id>().unwrap());
+    userdata.flush(&mut container).unwrap();
+
+    assert_eq!(
+        container.userdata(),
+        [&USERDATA[..], &[0; 32], &[0, 0, 0, 2], &[0, 0, 0, 3]].concat()
+    );
+
+    let userdata = Userdata::<MemoryBackend>::load(&mut container).unwrap();
+    assert_eq!(userdata.index.unwrap().to_string(), "2");
+    assert_eq!(userdata.free.unwrap().to_string(), "3");
+}
+
+#[test]
+fn create_anchored() {
+    let mut container = setup_container();
+
+    container.update_userdata(&[b'x'; 1]).unwrap();
+
+    let (userdata, anchor) = Userdata::<MemoryBackend>::create_anchored(&mut container).unwrap();
+
+    assert_eq!(anchor.to_string(), "1");
+    assert_eq!(userdata.id.to_string(), "2");
+    assert_eq!(container.userdata(), [b'x'; 1]);
+
+    let mut buf = vec![0; container.block_size() as usize];
+
+    container.read(&anchor, &mut buf).unwrap();
+    assert_eq!(buf[..4], [0, 0, 0, 16]);
+    assert_eq!(buf[4..20], [&USERDATA[..12], &[0, 0, 0, 2]].concat());
+    assert!(buf[20..].iter().all(|n| *n == 0));
+}
+
+#[test]
+fn flush_anchored() {
+    let mut container = setup_container();
+    let (mut userdata, anchor) =
+        Userdata::<MemoryBackend>::create_anchored(&mut container).unwrap();
+
+    userdata.freeze = Some([1; 32]);
+    userdata.free = Some("3".parse::<Id>().unwrap());
+    userdata.flush_anchored(&mut container, &anchor).unwrap();
+
+    assert!(container.userdata().is_empty());
+
+    let userdata = Userdata::<MemoryBackend>::load_anchored(&mut container, &anchor).unwrap();
+    assert_eq!(userdata.id.to_string(), "2");
+    assert_eq!(userdata.freeze, Some([1; 32]));
+    assert!(userdata.index.is_none());
+    assert_eq!(userdata.free.unwrap().to_string(), "3");
+}
+
+#[test]
+fn load_anchored_empty() {
+    let mut container = setup_container();
+    let anchor = container.aquire().unwrap();
+
+    container.write(&anchor, &[]).unwrap();
+
+    let err = Userdata::<MemoryBackend>::load_anchored(&mut container, &anchor).unwrap_err();
+    assert!(matches!(err, Error::InvalidUserdata(None)));
+}
+
+#[test]
+fn load_anchored_invalid() {
+    let mut container = setup_container();
+    let anchor = container.aquire().unwrap();
+
+    let mut bin = USERDATA;
+    bin[0] += 1;
+
+    container
+        .write(&anchor, &[&[0, 0, 0, 16], &bin[..]].concat())
+        .unwrap();
+
+    let err = Userdata::<MemoryBackend>::load_anchored(&mut container, &anchor).unwrap_err();
+    let err = into_error!(err, Error::InvalidUserdata).unwrap();
+    let err = into_error!(err, nuts_bytes::Error::Custom);
+    assert!(err.is::<UserdataMagicError>());
+}
+
+#[test]
+fn flush_backup() {
+    let mut container = setup_container();
+    let mut userdata = Userdata::<MemoryBackend>::create(&mut container, false).unwrap();
+
+    userdata.backup = Some("2".parse::<Id>().unwrap());
+    userdata.flush(&mut container).unwrap();
+
+    assert_eq!(
+        container.userdata(),
+        [
+            &USERDATA[..],
+            &[0; 32],
+            &[0xff; 4],
+            &[0xff; 4],
+            &[0, 0, 0, 2]
+        ]
+        .concat()
+    );
+
+    let userdata = Userdata::<MemoryBackend>::load(&mut container).unwrap();
+    assert!(userdata.index.is_none());
+    assert!(userdata.free.is_none());
+    assert_eq!(userdata.backup.unwrap().to_string(), "2");
+}
+
+#[test]
+fn backup_created_on_flush() {
+    let mut archive = Archive::create(setup_container(), false).unwrap();
+
+    // an archive created before the backup existed
+    let mut userdata = Userdata::fetch(&mut archive.pager).unwrap();
+    userdata.backup = None;
+    userdata.store(&mut archive.pager).unwrap();
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+    assert!(archive.pager.backup().is_none());
+
+    archive.append_file("f1").build().unwrap();
+
+    let backup = *archive.pager.backup().unwrap();
+    let userdata = Userdata::fetch(&mut archive.pager).unwrap();
+    assert_eq!(userdata.backup, Some(backup));
+
+    let primary = archive
+        .pager
+        .read_buf_raw(&archive.header_id)
+        .unwrap()
+        .to_vec();
+    assert_eq!(archive.pager.read_buf_raw(&backup).unwrap(), primary);
 }
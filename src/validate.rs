@@ -0,0 +1,157 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use nuts_container::backend::Backend;
+use std::collections::HashSet;
+
+use crate::entry::estimate_name_blocks;
+use crate::entry::immut::visible_names;
+use crate::error::ArchiveResult;
+use crate::Archive;
+
+/// The result of a dry-run import.
+///
+/// A report is created by [`Archive::validate_bundle()`],
+/// `Archive::validate_tar()` (`tar` feature) or `Archive::validate_zip()`
+/// (`zip` feature). The source is parsed completely, but the archive is not
+/// modified. The report tells what an import would do and whether it would
+/// succeed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportReport {
+    entries: u64,
+    content_size: u64,
+    blocks: u64,
+    available_blocks: u64,
+    collisions: Vec<String>,
+    unsupported: Vec<String>,
+}
+
+impl ImportReport {
+    /// Returns the number of entries, which would be appended.
+    pub fn entries(&self) -> u64 {
+        self.entries
+    }
+
+    /// Returns the total size of the content of the entries, which would be
+    /// appended.
+    pub fn content_size(&self) -> u64 {
+        self.content_size
+    }
+
+    /// Returns the number of blocks needed by the entries: a block for each
+    /// entry and the blocks for its content.
+    pub fn blocks(&self) -> u64 {
+        self.blocks
+    }
+
+    /// Returns the number of blocks, which can still be aquired by the
+    /// archive.
+    ///
+    /// Blocks of the list of free blocks are not taken into account, they
+    /// are reused before new blocks are aquired.
+    pub fn available_blocks(&self) -> u64 {
+        self.available_blocks
+    }
+
+    /// Tests whether the entries fit into the archive.
+    pub fn fits(&self) -> bool {
+        self.blocks <= self.available_blocks
+    }
+
+    /// Returns the names of entries, which already exist in the archive or
+    /// exist more than once in the source.
+    ///
+    /// An import appends the entries anyway, but
+    /// [`Archive::lookup()`](crate::Archive::lookup) still finds the first
+    /// entry with a name.
+    pub fn collisions(&self) -> &[String] {
+        &self.collisions
+    }
+
+    /// Returns the names of entries, which are not supported by the archive
+    /// and would be skipped by the import.
+    pub fn unsupported(&self) -> &[String] {
+        &self.unsupported
+    }
+
+    /// Tests whether the import would append all entries without any
+    /// collision and whether they fit into the archive.
+    pub fn is_clean(&self) -> bool {
+        self.fits() && self.collisions.is_empty() && self.unsupported.is_empty()
+    }
+}
+
+/// Collects the entries of an import source into an [`ImportReport`].
+pub(crate) struct ReportBuilder {
+    block_size: u64,
+    names: HashSet<String>,
+    report: ImportReport,
+}
+
+impl ReportBuilder {
+    pub(crate) fn new<B: Backend>(archive: &mut Archive<B>) -> ArchiveResult<ReportBuilder, B> {
+        let names = visible_names(&mut archive.pager, &mut archive.tree, 0)?
+            .into_iter()
+            .map(|(_, name)| name)
+            .collect();
+        let capacity = archive.tree.capacity(&archive.pager);
+
+        Ok(ReportBuilder {
+            block_size: archive.pager.block_size() as u64,
+            names,
+            report: ImportReport {
+                entries: 0,
+                content_size: 0,
+                blocks: 0,
+                available_blocks: capacity.saturating_sub(archive.tree.nblocks()),
+                collisions: vec![],
+                unsupported: vec![],
+            },
+        })
+    }
+
+    /// Records an entry with the given `name` and content `size`, which
+    /// would be appended.
+    pub(crate) fn add(&mut self, name: &str, size: u64) {
+        if !self.names.insert(name.to_string()) {
+            self.report.collisions.push(name.to_string());
+        }
+
+        self.report.entries += 1;
+        self.report.content_size += size;
+        self.report.blocks +=
+            1 + estimate_name_blocks(name.len(), self.block_size) + size.div_ceil(self.block_size);
+    }
+
+    /// Records an entry with the given `name`, which would be skipped.
+    #[cfg_attr(not(feature = "tar"), allow(dead_code))]
+    pub(crate) fn skip(&mut self, name: &str) {
+        self.report.unsupported.push(name.to_string());
+    }
+
+    pub(crate) fn build(self) -> ImportReport {
+        self.report
+    }
+}
@@ -0,0 +1,112 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use crate::tests::setup_container_with_bsize;
+use crate::validate::ReportBuilder;
+use crate::Archive;
+
+fn setup_archive() -> Archive<nuts_container::memory::MemoryBackend> {
+    let container = setup_container_with_bsize(512);
+    Archive::create(container, false).unwrap()
+}
+
+#[test]
+fn empty() {
+    let mut archive = setup_archive();
+    let report = ReportBuilder::new(&mut archive).unwrap().build();
+
+    assert_eq!(report.entries(), 0);
+    assert_eq!(report.content_size(), 0);
+    assert_eq!(report.blocks(), 0);
+    assert_eq!(
+        report.available_blocks(),
+        archive.tree.capacity(&archive.pager) - archive.tree.nblocks()
+    );
+    assert!(report.fits());
+    assert!(report.is_clean());
+}
+
+#[test]
+fn blocks() {
+    let mut archive = setup_archive();
+    let mut builder = ReportBuilder::new(&mut archive).unwrap();
+
+    builder.add("a", 0);
+    builder.add("b", 512);
+    builder.add("c", 513);
+
+    let report = builder.build();
+
+    assert_eq!(report.entries(), 3);
+    assert_eq!(report.content_size(), 1025);
+    assert_eq!(report.blocks(), 1 + 2 + 3);
+    assert!(report.is_clean());
+}
+
+#[test]
+fn collisions() {
+    let mut archive = setup_archive();
+
+    archive.append_file("a").build().unwrap();
+
+    let mut builder = ReportBuilder::new(&mut archive).unwrap();
+
+    builder.add("a", 0);
+    builder.add("b", 0);
+    builder.add("b", 0);
+
+    let report = builder.build();
+
+    assert_eq!(report.entries(), 3);
+    assert_eq!(report.collisions(), ["a", "b"]);
+    assert!(report.fits());
+    assert!(!report.is_clean());
+}
+
+#[test]
+fn unsupported() {
+    let mut archive = setup_archive();
+    let mut builder = ReportBuilder::new(&mut archive).unwrap();
+
+    builder.add("a", 0);
+    builder.skip("b");
+
+    let report = builder.build();
+
+    assert_eq!(report.entries(), 1);
+    assert_eq!(report.unsupported(), ["b"]);
+    assert!(!report.is_clean());
+}
+
+#[test]
+fn too_large() {
+    let mut archive = setup_archive();
+    let mut builder = ReportBuilder::new(&mut archive).unwrap();
+    let available = archive.tree.capacity(&archive.pager) - archive.tree.nblocks();
+
+    builder.add("a", (available - 1) * 512);
+    assert!(builder.report.fits());
+
+    builder.add("b", 0);
+    assert!(!builder.report.fits());
+    assert!(!builder.build().is_clean());
+}
@@ -0,0 +1,249 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use log::{debug, error};
+use nuts_container::backend::Backend;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::entry::immut::InnerEntry;
+use crate::error::Error;
+use crate::header::Header;
+use crate::pager::Pager;
+use crate::stats::IoStats;
+use crate::tree::Tree;
+
+/// A problem found by [`Archive::verify()`](crate::Archive::verify).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Problem {
+    /// The block at the given index of the tree cannot be read. The second
+    /// value is the error message.
+    Unreadable(u64, String),
+
+    /// The block at the first index is referenced again at the second
+    /// index of the tree.
+    Duplicate(u64, u64),
+
+    /// The entry at the given index cannot be loaded. The second value is
+    /// the error message. The entries behind it are not verified.
+    InvalidEntry(u64, String),
+
+    /// The content of the named entry needs more blocks than available in
+    /// the tree. The values are the name, the size of the entry and the
+    /// number of missing blocks.
+    Truncated(String, u64, u64),
+
+    /// The entries cover the first value of blocks, but the tree has the
+    /// second value of blocks.
+    Coverage(u64, u64),
+
+    /// The header counts the first value of entries, but the second value
+    /// of entries was found.
+    FileCount(u64, u64),
+
+    /// The content of the named file does not match its stored checksum.
+    Checksum(String),
+
+    /// The content of the named file cannot be read. The second value is
+    /// the error message.
+    Content(String, String),
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unreadable(idx, err) => write!(fmt, "block {} is unreadable: {}", idx, err),
+            Self::Duplicate(first, idx) => {
+                write!(fmt, "block {} is referenced again at {}", first, idx)
+            }
+            Self::InvalidEntry(idx, err) => write!(fmt, "invalid entry at {}: {}", idx, err),
+            Self::Truncated(name, size, missing) => write!(
+                fmt,
+                "{} has {} bytes, {} blocks are missing",
+                name, size, missing
+            ),
+            Self::Coverage(covered, nblocks) => write!(
+                fmt,
+                "entries cover {} blocks, the tree has {} blocks",
+                covered, nblocks
+            ),
+            Self::FileCount(header, found) => write!(
+                fmt,
+                "the header counts {} entries, {} were found",
+                header, found
+            ),
+            Self::Checksum(name) => write!(fmt, "{} does not match its checksum", name),
+            Self::Content(name, err) => write!(fmt, "cannot read {}: {}", name, err),
+        }
+    }
+}
+
+/// The result of [`Archive::verify()`](crate::Archive::verify).
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    /// Number of blocks, which were read
+    pub blocks: u64,
+
+    /// Number of entries, which were visited
+    pub entries: u64,
+
+    /// Number of files, whose content was verified against a checksum
+    pub verified: u64,
+
+    /// The problems found in the archive
+    pub problems: Vec<Problem>,
+
+    /// I/O statistics of the verification
+    pub stats: IoStats,
+}
+
+impl VerifyReport {
+    /// Returns `true`, if no problem was found.
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Reads all blocks referenced by the tree.
+fn verify_blocks<B: Backend>(pager: &mut Pager<B>, tree: &mut Tree<B>, report: &mut VerifyReport) {
+    let mut ids = HashMap::new();
+
+    for idx in 0..tree.nblocks() {
+        let id = match tree.lookup(pager, idx as usize) {
+            Some(Ok(id)) => id.clone(),
+            Some(Err(err)) => {
+                report
+                    .problems
+                    .push(Problem::Unreadable(idx, err.to_string()));
+                continue;
+            }
+            None => continue, // a hole of a sparse file
+        };
+
+        if let Some(first) = ids.insert(id.to_string(), idx) {
+            report.problems.push(Problem::Duplicate(first, idx));
+        }
+
+        match pager.read_buf_raw(&id) {
+            Ok(_) => report.blocks += 1,
+            Err(err) => {
+                error!("verify: block {} at {}: {}", id, idx, err);
+                report
+                    .problems
+                    .push(Problem::Unreadable(idx, err.to_string()));
+            }
+        }
+    }
+}
+
+/// Walks through all entries and verifies their content.
+fn verify_entries<B: Backend>(
+    pager: &mut Pager<B>,
+    header: &Header,
+    tree: &mut Tree<B>,
+    report: &mut VerifyReport,
+) {
+    let nblocks = tree.nblocks();
+    let mut nfiles = 0;
+    let mut end = 0;
+    let mut entry_opt = InnerEntry::first(pager, tree);
+
+    loop {
+        let mut entry = match entry_opt {
+            Some(Ok(entry)) => entry,
+            Some(Err(err)) => {
+                report
+                    .problems
+                    .push(Problem::InvalidEntry(end as u64, err.to_string()));
+                return;
+            }
+            None => break,
+        };
+
+        let name = entry.inner().name.clone();
+
+        report.entries += 1;
+
+        if !entry.inner().mode.is_internal() {
+            nfiles += 1;
+        }
+
+        if entry.end_idx() as u64 > nblocks {
+            let missing = entry.end_idx() as u64 - nblocks;
+
+            report.problems.push(Problem::Truncated(
+                name,
+                entry.inner().content_size(),
+                missing,
+            ));
+            end = entry.end_idx();
+
+            break;
+        }
+
+        match entry.verify() {
+            Ok(true) => report.verified += 1,
+            Ok(false) => {}
+            Err(Error::ChecksumMismatch(name)) => report.problems.push(Problem::Checksum(name)),
+            Err(err) => report
+                .problems
+                .push(Problem::Content(name, err.to_string())),
+        }
+
+        end = entry.end_idx();
+        entry_opt = entry.next();
+    }
+
+    if nfiles != header.nfiles {
+        report
+            .problems
+            .push(Problem::FileCount(header.nfiles, nfiles));
+    }
+
+    if end as u64 != nblocks {
+        report.problems.push(Problem::Coverage(end as u64, nblocks));
+    }
+}
+
+pub(crate) fn verify<B: Backend>(
+    pager: &mut Pager<B>,
+    header: &Header,
+    tree: &mut Tree<B>,
+) -> VerifyReport {
+    let mut report = VerifyReport::default();
+
+    verify_blocks(pager, tree, &mut report);
+    verify_entries(pager, header, tree, &mut report);
+
+    debug!(
+        "verify: {} blocks, {} entries, {} verified, {} problems",
+        report.blocks,
+        report.entries,
+        report.verified,
+        report.problems.len()
+    );
+
+    report
+}
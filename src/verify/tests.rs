@@ -0,0 +1,112 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use nuts_container::memory::MemoryBackend;
+
+use crate::tests::setup_container;
+use crate::verify::Problem;
+use crate::{Archive, Compression};
+
+fn setup_archive() -> Archive<MemoryBackend> {
+    let mut archive = Archive::create(setup_container(), false).unwrap();
+    let content = (0..3000).map(|n| (n % 251) as u8).collect::<Vec<_>>();
+
+    for (name, compression) in [("f1", Compression::None), ("f2", Compression::Deflate)] {
+        let mut builder = archive.append_file(name);
+        builder.set_hashed(true);
+        builder.set_compression(compression);
+        builder.with_content(&content).build().unwrap();
+    }
+
+    let mut entry = archive.append_file("f3").build().unwrap();
+    entry.write_zeroes(2000).unwrap();
+
+    archive.append_directory("d").build().unwrap();
+
+    archive
+}
+
+#[test]
+fn ok() {
+    let mut archive = setup_archive();
+    let report = archive.verify();
+
+    assert!(report.is_ok(), "{:?}", report.problems);
+    assert_eq!(report.entries, 4);
+    assert_eq!(report.verified, 2);
+
+    // the holes of f3 are not read
+    assert_eq!(report.blocks, archive.info().blocks - 3);
+}
+
+#[test]
+fn empty() {
+    let mut archive = Archive::create(setup_container(), false).unwrap();
+    let report = archive.verify();
+
+    assert!(report.is_ok());
+    assert_eq!(report.blocks, 0);
+    assert_eq!(report.entries, 0);
+}
+
+#[test]
+fn checksum() {
+    let mut archive = setup_archive();
+
+    // the second content block of f1
+    let id = *archive.tree.lookup(&mut archive.pager, 2).unwrap().unwrap();
+    archive.pager.write(&id, b"garbage").unwrap();
+
+    let report = archive.verify();
+
+    assert_eq!(report.problems, [Problem::Checksum("f1".to_string())]);
+    assert_eq!(report.verified, 1);
+}
+
+#[test]
+fn file_count() {
+    let mut archive = setup_archive();
+
+    archive.header.nfiles += 1;
+
+    let report = archive.verify();
+    assert_eq!(report.problems, [Problem::FileCount(5, 4)]);
+}
+
+#[test]
+fn coverage() {
+    let mut archive = setup_archive();
+    let nblocks = archive.info().blocks;
+
+    archive.tree.aquire_hole(&mut archive.pager).unwrap();
+
+    let report = archive.verify();
+    assert_eq!(report.problems, [Problem::Coverage(nblocks, nblocks + 1)]);
+    assert_eq!(
+        report.problems[0].to_string(),
+        format!(
+            "entries cover {} blocks, the tree has {} blocks",
+            nblocks,
+            nblocks + 1
+        )
+    );
+}
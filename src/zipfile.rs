@@ -0,0 +1,302 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+#[cfg(test)]
+mod tests;
+
+use ::zip::read::ZipFile;
+use ::zip::write::SimpleFileOptions;
+use ::zip::{CompressionMethod, DateTime as ZipDateTime, ZipArchive, ZipWriter};
+use chrono::{DateTime, Datelike, TimeZone, Timelike, Utc};
+use log::debug;
+use nuts_container::backend::Backend;
+use std::convert::TryInto;
+use std::io::{self, ErrorKind, Read, Seek, Write};
+use std::path::Path;
+
+use crate::entry::mode::Mode;
+use crate::entry::special::SpecialKind;
+use crate::entry::Inner;
+use crate::error::ArchiveResult;
+use crate::import::name_from_path;
+use crate::resume::{self, ImportCheckpoint};
+use crate::validate::{ImportReport, ReportBuilder};
+use crate::{Archive, Entry};
+
+/// Applies mode and modification time of the zip `file` to `builder`.
+macro_rules! set_attributes {
+    ($builder:ident, $file:ident) => {
+        if let Some(mode) = $file.unix_mode() {
+            $builder.set_unix_mode(mode);
+        }
+
+        if let Some(modified) = $file.last_modified().and_then(from_zip_time) {
+            $builder.set_modified(modified);
+        }
+    };
+}
+
+/// Converts the timestamp of a zip entry, which has no timezone, into UTC.
+fn from_zip_time(t: ZipDateTime) -> Option<DateTime<Utc>> {
+    Utc.with_ymd_and_hms(
+        t.year() as i32,
+        t.month() as u32,
+        t.day() as u32,
+        t.hour() as u32,
+        t.minute() as u32,
+        t.second() as u32,
+    )
+    .single()
+}
+
+fn to_zip_time(t: &DateTime<Utc>) -> Option<ZipDateTime> {
+    ZipDateTime::from_date_and_time(
+        t.year().try_into().ok()?,
+        t.month() as u8,
+        t.day() as u8,
+        t.hour() as u8,
+        t.minute() as u8,
+        t.second() as u8,
+    )
+    .ok()
+}
+
+/// Returns the kind of a special file described by the unix `mode`.
+///
+/// A zip file has no device numbers, a device gets the numbers `0`.
+fn special_kind(mode: Mode) -> Option<SpecialKind> {
+    if mode.is_fifo() {
+        Some(SpecialKind::Fifo)
+    } else if mode.is_socket() {
+        Some(SpecialKind::Socket)
+    } else if mode.is_char_device() {
+        Some(SpecialKind::CharDevice { major: 0, minor: 0 })
+    } else if mode.is_block_device() {
+        Some(SpecialKind::BlockDevice { major: 0, minor: 0 })
+    } else {
+        None
+    }
+}
+
+/// Appends the zip `file` to `archive`.
+fn append_entry<B: Backend>(
+    archive: &mut Archive<B>,
+    file: &mut ZipFile,
+    buf: &mut [u8],
+) -> ArchiveResult<(), B> {
+    let name = name_from_path(Path::new(file.name()))?;
+    let mode = file.unix_mode().and_then(Mode::from_unix);
+
+    if file.is_dir() || mode.is_some_and(|mode| mode.is_directory()) {
+        let mut builder = archive.append_directory(&name);
+        set_attributes!(builder, file);
+        builder.build()?;
+    } else if let Some(kind) = mode.and_then(special_kind) {
+        let mut builder = archive.append_special(&name, kind);
+        set_attributes!(builder, file);
+        builder.build()?;
+    } else if mode.is_some_and(|mode| mode.is_symlink()) {
+        let mut target = vec![];
+
+        file.read_to_end(&mut target)?;
+
+        let mut builder = archive.append_symlink(&name, target);
+        set_attributes!(builder, file);
+        builder.build()?;
+    } else {
+        let mut builder = archive.append_file(&name);
+        set_attributes!(builder, file);
+
+        let mut entry = builder.build()?;
+
+        loop {
+            let n = match file.read(buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            };
+
+            entry.write_all(&buf[..n])?;
+        }
+    }
+
+    debug!("zip: {} appended", name);
+
+    Ok(())
+}
+
+/// Appends all entries of the zip file read from `reader` to `archive`.
+///
+/// Returns the number of appended entries.
+pub(crate) fn append_zip<B: Backend, R: Read + Seek>(
+    archive: &mut Archive<B>,
+    reader: R,
+) -> ArchiveResult<u64, B> {
+    let mut zip = ZipArchive::new(reader).map_err(io::Error::from)?;
+    let mut buf = vec![0; archive.pager.block_size() as usize];
+
+    for idx in 0..zip.len() {
+        let mut file = zip.by_index(idx).map_err(io::Error::from)?;
+        append_entry(archive, &mut file, &mut buf)?;
+    }
+
+    Ok(zip.len() as u64)
+}
+
+/// Appends the entries of the zip file returned by `open` to `archive`,
+/// starting at `checkpoint`.
+///
+/// The zip file is re-opened with `open` and the entries before the
+/// checkpoint are skipped. See [`resume::import()`] for the retry logic.
+///
+/// Returns the number of appended entries.
+pub(crate) fn append_zip_resumable<B, R, F>(
+    archive: &mut Archive<B>,
+    checkpoint: &mut ImportCheckpoint,
+    retries: u32,
+    mut open: F,
+) -> ArchiveResult<u64, B>
+where
+    B: Backend,
+    R: Read + Seek,
+    F: FnMut() -> io::Result<R>,
+{
+    let mut buf = vec![0; archive.pager.block_size() as usize];
+
+    resume::import(
+        archive,
+        checkpoint,
+        retries,
+        |_| open(),
+        |archive, source, progress| {
+            let mut zip = ZipArchive::new(source).map_err(io::Error::from)?;
+            let start = progress.checkpoint().entries() as usize;
+
+            for idx in start..zip.len() {
+                let mut file = zip.by_index(idx).map_err(io::Error::from)?;
+
+                append_entry(archive, &mut file, &mut buf)?;
+                progress.commit(archive, true, 0);
+            }
+
+            Ok(())
+        },
+    )
+}
+
+/// Parses the zip file read from `reader` like [`append_zip()`] does,
+/// without modifying `archive`.
+pub(crate) fn validate_zip<B: Backend, R: Read + Seek>(
+    archive: &mut Archive<B>,
+    reader: R,
+) -> ArchiveResult<ImportReport, B> {
+    archive.header.check_mutable()?;
+
+    let mut zip = ZipArchive::new(reader).map_err(io::Error::from)?;
+    let mut report = ReportBuilder::new(archive)?;
+
+    for idx in 0..zip.len() {
+        let file = zip.by_index_raw(idx).map_err(io::Error::from)?;
+        let name = name_from_path(Path::new(file.name()))?;
+        let size = if file.is_dir() { 0 } else { file.size() };
+
+        report.add(&name, size);
+    }
+
+    Ok(report.build())
+}
+
+/// Creates the options of a zip entry with the access rights and
+/// modification time of `inner`.
+fn zip_options(inner: &Inner) -> SimpleFileOptions {
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .unix_permissions(inner.mode.to_unix() & 0o7777)
+        .large_file(inner.content_size() >= u32::MAX as u64);
+
+    match to_zip_time(inner.tstamps.modified()) {
+        Some(modified) => options.last_modified_time(modified),
+        None => options,
+    }
+}
+
+/// Writes all entries of `archive` as a zip file into `writer`.
+///
+/// Returns the number of entries written into the zip file.
+pub(crate) fn write_zip<B: Backend, W: Write + Seek>(
+    archive: &mut Archive<B>,
+    writer: W,
+) -> ArchiveResult<u64, B> {
+    let mut zip = ZipWriter::new(writer);
+    let mut buf = vec![0; archive.pager.block_size() as usize];
+    let mut nentries = 0;
+    let mut entry_opt = archive.first();
+
+    while let Some(result) = entry_opt {
+        let mut entry = result?;
+        let name = entry.name().to_string();
+        let options = zip_options(entry.inner());
+
+        match &mut entry {
+            Entry::File(file) => {
+                zip.start_file(name.as_str(), options)
+                    .map_err(io::Error::from)?;
+
+                loop {
+                    let n = file.read(&mut buf)?;
+
+                    if n == 0 {
+                        break;
+                    }
+
+                    zip.write_all(&buf[..n])?;
+                }
+            }
+            Entry::Directory(_) => {
+                zip.add_directory(name.as_str(), options)
+                    .map_err(io::Error::from)?;
+            }
+            Entry::Symlink(symlink) => {
+                let target = String::from_utf8_lossy(symlink.target_bytes());
+
+                zip.add_symlink(name.as_str(), target, options)
+                    .map_err(io::Error::from)?;
+            }
+            Entry::HardLink(_) | Entry::Special(_) => {
+                // zip has no types for hard links and special files
+                debug!("zip: skip {}", name);
+                entry_opt = entry.next();
+                continue;
+            }
+        }
+
+        debug!("zip: {} written", name);
+        nentries += 1;
+
+        entry_opt = entry.next();
+    }
+
+    zip.finish().map_err(io::Error::from)?.flush()?;
+
+    Ok(nentries)
+}
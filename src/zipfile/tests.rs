@@ -0,0 +1,282 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use ::zip::write::SimpleFileOptions;
+use ::zip::{DateTime as ZipDateTime, ZipArchive, ZipWriter};
+use std::io::{self, Cursor, ErrorKind, Read, Seek, SeekFrom, Write};
+
+use crate::error::Error;
+use crate::tests::setup_container_with_bsize;
+use crate::{Archive, ImportCheckpoint, Mode, SpecialKind};
+
+fn setup_archive() -> Archive<nuts_container::memory::MemoryBackend> {
+    let container = setup_container_with_bsize(512);
+    Archive::create(container, false).unwrap()
+}
+
+fn options(mode: u32) -> SimpleFileOptions {
+    SimpleFileOptions::default()
+        .unix_permissions(mode)
+        .last_modified_time(ZipDateTime::from_date_and_time(2023, 11, 14, 22, 13, 20).unwrap())
+}
+
+fn setup_zip() -> Vec<u8> {
+    let mut zip = ZipWriter::new(Cursor::new(vec![]));
+
+    zip.add_directory("d/", options(0o750)).unwrap();
+    zip.start_file("d/f", options(0o640)).unwrap();
+    zip.write_all(&[7; 1500]).unwrap();
+    zip.add_symlink("s", "d/f", options(0o777)).unwrap();
+
+    zip.finish().unwrap().into_inner()
+}
+
+/// A reader, which fails when a read starts at position `fail_at`.
+struct Failing {
+    cursor: Cursor<Vec<u8>>,
+    fail_at: u64,
+}
+
+impl Read for Failing {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.cursor.position() == self.fail_at {
+            Err(io::Error::from(ErrorKind::ConnectionReset))
+        } else {
+            self.cursor.read(buf)
+        }
+    }
+}
+
+impl Seek for Failing {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.cursor.seek(pos)
+    }
+}
+
+#[test]
+fn append() {
+    let mut archive = setup_archive();
+
+    let summary = archive.append_zip(Cursor::new(setup_zip())).unwrap();
+    assert_eq!(summary.entries, 3);
+    assert!(summary.stats.blocks_written >= 3);
+
+    let entry = archive.lookup("d").unwrap().unwrap();
+    assert!(entry.is_directory());
+    assert_eq!(entry.unix_mode() & 0o7777, 0o750);
+
+    let mut entry = archive.lookup("d/f").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.size(), 1500);
+    assert_eq!(entry.unix_mode() & 0o7777, 0o640);
+    assert_eq!(entry.modified().timestamp(), 1_700_000_000);
+    assert_eq!(entry.read_vec().unwrap(), [7; 1500]);
+
+    let entry = archive
+        .lookup("s")
+        .unwrap()
+        .unwrap()
+        .into_symlink()
+        .unwrap();
+    assert_eq!(entry.target(), "d/f");
+}
+
+#[test]
+fn append_special() {
+    let mut archive = setup_archive();
+    let mut zip = ZipWriter::new(Cursor::new(vec![]));
+
+    zip.start_file("p", options(0o640)).unwrap();
+
+    let mut buf = zip.finish().unwrap().into_inner();
+
+    // the zip writer stores a regular file, make it a FIFO
+    let pos = buf.windows(4).position(|w| w == b"PK\x01\x02").unwrap();
+    buf[pos + 38..pos + 42].copy_from_slice(&(0o010640u32 << 16).to_le_bytes());
+
+    assert_eq!(archive.append_zip(Cursor::new(buf)).unwrap().entries, 1);
+
+    let entry = archive.lookup("p").unwrap().unwrap();
+    assert_eq!(entry.mode(), Mode::from_unix(0o010640).unwrap());
+
+    let entry = entry.into_special().unwrap();
+    assert_eq!(entry.kind(), SpecialKind::Fifo);
+}
+
+#[test]
+fn append_parent_dir() {
+    let mut archive = setup_archive();
+    let mut zip = ZipWriter::new(Cursor::new(vec![]));
+
+    zip.start_file("../bad", options(0o644)).unwrap();
+
+    let buf = zip.finish().unwrap().into_inner();
+
+    let err = archive.append_zip(Cursor::new(buf)).unwrap_err();
+    assert!(matches!(err, Error::Io(ref err) if err.kind() == ErrorKind::InvalidInput));
+    assert!(archive.first().is_none());
+}
+
+#[test]
+fn validate() {
+    let mut archive = setup_archive();
+
+    archive.append_file("s").build().unwrap();
+
+    let report = archive.validate_zip(Cursor::new(setup_zip())).unwrap();
+
+    assert_eq!(report.entries(), 3);
+    assert_eq!(report.content_size(), 1500 + 3);
+    assert_eq!(report.collisions(), ["s"]);
+    assert!(report.fits());
+
+    let entry = archive.first().unwrap().unwrap();
+    assert_eq!(entry.name(), "s");
+    assert!(entry.next().is_none());
+}
+
+#[test]
+fn write() {
+    let mut archive = setup_archive();
+    let mut buf = Cursor::new(vec![]);
+
+    archive.append_zip(Cursor::new(setup_zip())).unwrap();
+    archive.append_hardlink("h", "d/f").build().unwrap();
+    archive
+        .append_special("null", SpecialKind::CharDevice { major: 1, minor: 3 })
+        .build()
+        .unwrap();
+
+    assert_eq!(archive.write_zip(&mut buf).unwrap(), 3);
+
+    let mut zip = ZipArchive::new(buf).unwrap();
+    assert_eq!(zip.len(), 3);
+
+    let file = zip.by_index(0).unwrap();
+    assert!(file.is_dir());
+    assert_eq!(file.name(), "d/");
+    assert_eq!(file.unix_mode().unwrap() & 0o7777, 0o750);
+    drop(file);
+
+    let mut file = zip.by_index(1).unwrap();
+    let mut content = vec![];
+    assert_eq!(file.name(), "d/f");
+    assert_eq!(file.unix_mode().unwrap() & 0o7777, 0o640);
+    assert_eq!(
+        file.last_modified(),
+        Some(ZipDateTime::from_date_and_time(2023, 11, 14, 22, 13, 20).unwrap())
+    );
+    file.read_to_end(&mut content).unwrap();
+    assert_eq!(content, [7; 1500]);
+    drop(file);
+
+    let mut file = zip.by_index(2).unwrap();
+    let mut target = String::new();
+    assert!(file.is_symlink());
+    assert_eq!(file.name(), "s");
+    file.read_to_string(&mut target).unwrap();
+    assert_eq!(target, "d/f");
+}
+
+#[test]
+fn round_trip() {
+    let mut archive = setup_archive();
+    let mut other = setup_archive();
+    let mut buf = Cursor::new(vec![]);
+
+    archive.append_zip(Cursor::new(setup_zip())).unwrap();
+    archive.write_zip(&mut buf).unwrap();
+
+    buf.set_position(0);
+    assert_eq!(other.append_zip(buf).unwrap().entries, 3);
+    assert_eq!(other.manifest().unwrap(), archive.manifest().unwrap());
+}
+
+#[test]
+fn resume() {
+    let mut archive = setup_archive();
+    let zip = setup_zip();
+    let data_start = ZipArchive::new(Cursor::new(zip.clone()))
+        .unwrap()
+        .by_name("d/f")
+        .unwrap()
+        .data_start();
+    let mut checkpoint = ImportCheckpoint::default();
+    let mut nopen = 0;
+
+    let n = archive
+        .append_zip_resumable(&mut checkpoint, 1, || {
+            nopen += 1;
+
+            Ok(Failing {
+                cursor: Cursor::new(zip.clone()),
+                fail_at: if nopen == 1 { data_start } else { u64::MAX },
+            })
+        })
+        .unwrap();
+
+    assert_eq!(n.entries, 3);
+    assert_eq!(nopen, 2);
+    assert_eq!(checkpoint, ImportCheckpoint::new(3, 0));
+
+    let mut entry = archive.first().unwrap().unwrap();
+    let mut names = vec![];
+
+    loop {
+        names.push(entry.name().to_string());
+
+        entry = match entry.next() {
+            Some(entry) => entry.unwrap(),
+            None => break,
+        };
+    }
+
+    assert_eq!(names, ["d", "d/f", "s"]);
+
+    let mut entry = archive.lookup("d/f").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), [7; 1500]);
+}
+
+#[test]
+fn resume_give_up() {
+    let mut archive = setup_archive();
+    let zip = setup_zip();
+    let data_start = ZipArchive::new(Cursor::new(zip.clone()))
+        .unwrap()
+        .by_name("d/f")
+        .unwrap()
+        .data_start();
+    let mut checkpoint = ImportCheckpoint::default();
+
+    let err = archive
+        .append_zip_resumable(&mut checkpoint, 2, || {
+            Ok(Failing {
+                cursor: Cursor::new(zip.clone()),
+                fail_at: data_start,
+            })
+        })
+        .unwrap_err();
+
+    assert!(matches!(err, Error::Io(ref err) if err.kind() == ErrorKind::ConnectionReset));
+    assert_eq!(checkpoint, ImportCheckpoint::new(1, 0));
+    assert!(archive.lookup("d").is_some());
+    assert!(archive.lookup("d/f").is_none());
+}
@@ -0,0 +1,99 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::{Archive, Error};
+use nuts_container::container::Container;
+use nuts_container::memory::{Id, MemoryBackend};
+
+use common::setup_container;
+
+// the first blocks of a new archive: the header and its backup
+fn header_id() -> Id {
+    "1".parse().unwrap()
+}
+
+fn backup_id() -> Id {
+    "2".parse().unwrap()
+}
+
+fn setup_archive() -> Container<MemoryBackend> {
+    let mut archive = Archive::create(setup_container(), false).unwrap();
+
+    archive
+        .append_file("f1")
+        .with_content(&[1; 1000])
+        .build()
+        .unwrap();
+    archive.append_directory("d1").build().unwrap();
+
+    archive.into_container()
+}
+
+#[test]
+fn header_damaged() {
+    let mut container = setup_archive();
+
+    container.write(&header_id(), b"garbage").unwrap();
+
+    let summary = Archive::peek(&mut container).unwrap();
+    assert_eq!(summary.files, 2);
+
+    let mut archive = Archive::open(container).unwrap();
+
+    let mut entry = archive.lookup("f1").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), [1; 1000]);
+
+    // the next flush repairs the header block
+    archive.append_file("f2").build().unwrap();
+
+    let mut container = archive.into_container();
+    let mut primary = [0; 512];
+    let mut backup = [0; 512];
+
+    container.read(&header_id(), &mut primary).unwrap();
+    container.read(&backup_id(), &mut backup).unwrap();
+    assert_eq!(primary, backup);
+
+    assert_eq!(Archive::open(container).unwrap().info().files, 3);
+}
+
+#[test]
+fn backup_damaged() {
+    let mut container = setup_archive();
+
+    container.write(&backup_id(), b"garbage").unwrap();
+
+    assert_eq!(Archive::open(container).unwrap().info().files, 2);
+}
+
+#[test]
+fn both_damaged() {
+    let mut container = setup_archive();
+
+    container.write(&header_id(), &[0; 512]).unwrap();
+    container.write(&backup_id(), &[0; 512]).unwrap();
+
+    let err = Archive::open(container).err().unwrap();
+    assert!(matches!(err, Error::InvalidHeader(_)));
+}
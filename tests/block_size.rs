@@ -0,0 +1,96 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::{Archive, Error};
+use nuts_container::memory::MemoryBackend;
+
+use common::setup_container_with_bsize;
+
+#[test]
+fn min_block_size() {
+    assert_eq!(Archive::<MemoryBackend>::min_block_size(), 106);
+}
+
+#[test]
+fn too_small() {
+    let result = Archive::create(setup_container_with_bsize(105), false);
+    assert!(matches!(result, Err(Error::BlockSizeTooSmall(105, 106))));
+}
+
+#[test]
+fn smallest() {
+    let mut archive = Archive::create(setup_container_with_bsize(106), false).unwrap();
+
+    let mut entry = archive.append_file("f1").build().unwrap();
+    entry.write_all(b"content").unwrap();
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    let mut entry = archive.lookup("f1").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), b"content");
+}
+
+#[test]
+fn long_name() {
+    let mut archive = Archive::create(setup_container_with_bsize(106), false).unwrap();
+
+    let mut entry = archive.append_file("x".repeat(500)).build().unwrap();
+    entry.write_all(b"content").unwrap();
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    let mut entry = archive.first().unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.name(), "x".repeat(500));
+    assert_eq!(entry.read_vec().unwrap(), b"content");
+}
+
+#[test]
+fn name_too_long() {
+    let mut archive = Archive::create(setup_container_with_bsize(106), false).unwrap();
+
+    // the metadata leaves no room, even if the name is moved out
+    let mut builder = archive.append_file("x".repeat(100));
+    builder.set_metadata("k", "v".repeat(30));
+
+    let max = match builder.build() {
+        Err(Error::NameTooLong(max)) => max,
+        Err(err) => panic!("unexpected error: {}", err),
+        Ok(_) => panic!("name accepted"),
+    };
+
+    let mut builder = archive.append_file("x".repeat(max + 1));
+    builder.set_metadata("k", "v".repeat(30));
+
+    let result = builder.build();
+    assert!(matches!(result, Err(Error::NameTooLong(n)) if n == max));
+
+    // nothing was appended
+    assert_eq!(archive.info().files, 0);
+    assert_eq!(archive.info().blocks, 0);
+
+    archive.append_file("x".repeat(max)).build().unwrap();
+
+    let entry = archive.first().unwrap().unwrap();
+    assert_eq!(entry.name(), "x".repeat(max));
+}
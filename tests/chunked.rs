@@ -0,0 +1,143 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::{Archive, Chunker, Error};
+use nuts_container::memory::MemoryBackend;
+
+fn setup_archive() -> Archive<MemoryBackend> {
+    let mut archive = common::setup_archive();
+
+    archive.set_chunker(Chunker::new(256, 1024, 4096));
+
+    archive
+}
+
+fn random(len: usize, seed: u64) -> Vec<u8> {
+    let mut state = seed;
+
+    (0..len)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 56) as u8
+        })
+        .collect()
+}
+
+#[test]
+fn round_trip() {
+    let mut archive = setup_archive();
+    let content = random(50_000, 1);
+
+    let stats = archive.append_chunked("f", content.as_slice()).unwrap();
+
+    assert_eq!(stats.size, content.len() as u64);
+    assert_eq!(stats.new_chunks, stats.chunks);
+    assert_eq!(stats.new_bytes, stats.size);
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+    let mut buf = vec![];
+
+    assert_eq!(
+        archive.read_chunked("f", &mut buf).unwrap(),
+        content.len() as u64
+    );
+    assert_eq!(buf, content);
+}
+
+#[test]
+fn empty() {
+    let mut archive = setup_archive();
+
+    let stats = archive.append_chunked("f", [].as_slice()).unwrap();
+    assert_eq!(stats.chunks, 0);
+
+    let mut buf = vec![];
+    assert_eq!(archive.read_chunked("f", &mut buf).unwrap(), 0);
+}
+
+#[test]
+fn dedup() {
+    let mut archive = setup_archive();
+    let content = random(50_000, 2);
+    let mut shifted = random(100, 3);
+
+    shifted.extend_from_slice(&content);
+
+    let first = archive.append_chunked("v1", content.as_slice()).unwrap();
+    let blocks = archive.info().blocks;
+
+    let second = archive.append_chunked("v2", shifted.as_slice()).unwrap();
+
+    // only the chunks around the inserted bytes are new
+    assert!(second.new_chunks <= 2);
+    assert!(second.new_bytes < second.size / 10);
+    assert!(archive.info().blocks - blocks < blocks / 10);
+
+    let mut buf = vec![];
+    archive.read_chunked("v2", &mut buf).unwrap();
+    assert_eq!(buf, shifted);
+
+    let mut buf = vec![];
+    archive.read_chunked("v1", &mut buf).unwrap();
+    assert_eq!(buf, content);
+
+    // an identical file adds no chunks
+    let third = archive.append_chunked("v3", content.as_slice()).unwrap();
+    assert_eq!(third.chunks, first.chunks);
+    assert_eq!(third.new_chunks, 0);
+}
+
+#[test]
+fn hidden() {
+    let mut archive = setup_archive();
+
+    archive.append_file("f1").build().unwrap();
+    archive
+        .append_chunked("c", random(10_000, 4).as_slice())
+        .unwrap();
+    archive.append_file("f2").build().unwrap();
+
+    assert_eq!(archive.info().files, 2);
+
+    let entry = archive.first().unwrap().unwrap();
+    assert_eq!(entry.name(), "f1");
+
+    let entry = entry.next().unwrap().unwrap();
+    assert_eq!(entry.name(), "f2");
+    assert!(entry.next().is_none());
+
+    assert!(archive.lookup("c").is_none());
+}
+
+#[test]
+fn not_found() {
+    let mut archive = setup_archive();
+
+    archive.append_file("f").build().unwrap();
+
+    let err = archive.read_chunked("f", &mut vec![]).unwrap_err();
+    assert!(matches!(err, Error::NotFound(ref name) if name == "f"));
+}
@@ -0,0 +1,178 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use nuts_archive::{Archive, Error, RewriteError};
+use nuts_container::container::{Cipher, Container, CreateOptionsBuilder, OpenOptionsBuilder};
+use nuts_container::memory::MemoryBackend;
+
+fn setup_container(cipher: Cipher, bsize: u32) -> Container<MemoryBackend> {
+    setup_container_with_password(cipher, bsize, b"123")
+}
+
+fn setup_container_with_password(
+    cipher: Cipher,
+    bsize: u32,
+    password: &'static [u8],
+) -> Container<MemoryBackend> {
+    let backend = MemoryBackend::new_with_bsize(bsize);
+    let options = CreateOptionsBuilder::new(cipher)
+        .with_password_callback(move || Ok(password.to_vec()))
+        .build::<MemoryBackend>()
+        .unwrap();
+
+    Container::create(backend, options).unwrap()
+}
+
+fn setup_archive() -> Archive<MemoryBackend> {
+    fill_archive(Archive::create(setup_container(Cipher::None, 512), false).unwrap())
+}
+
+fn fill_archive(mut archive: Archive<MemoryBackend>) -> Archive<MemoryBackend> {
+    archive.append_directory("d1").build().unwrap();
+    archive
+        .append_file("d1/f1")
+        .with_content(&[1; 3000])
+        .build()
+        .unwrap();
+
+    let mut entry = archive.append_file("f2").build().unwrap();
+    entry.write_zeroes(2048).unwrap();
+    entry.write_all(&[2; 100]).unwrap();
+
+    archive.append_symlink("s1", "f2").build().unwrap();
+
+    archive
+}
+
+#[test]
+fn empty() {
+    let mut archive = Archive::create(setup_container(Cipher::None, 512), false).unwrap();
+    let mut target = archive
+        .clone_to(setup_container(Cipher::None, 512))
+        .unwrap();
+
+    assert_eq!(target.info().blocks, 0);
+    assert!(target.first().is_none());
+}
+
+#[test]
+fn entries() {
+    let mut archive = setup_archive();
+    let target = archive
+        .clone_to(setup_container(Cipher::None, 512))
+        .unwrap();
+
+    let info = archive.info();
+    let mut target = Archive::open(target.into_container()).unwrap();
+    let target_info = target.info();
+
+    assert_eq!(target_info.blocks, info.blocks);
+    assert_eq!(target_info.files, 4);
+    assert_eq!(
+        target_info.created.timestamp_millis(),
+        info.created.timestamp_millis()
+    );
+    assert_eq!(
+        target_info.modified.timestamp_millis(),
+        info.modified.timestamp_millis()
+    );
+    assert!(target.verify().is_ok());
+
+    let mut file = target
+        .lookup("d1/f1")
+        .unwrap()
+        .unwrap()
+        .into_file()
+        .unwrap();
+    assert_eq!(file.read_vec().unwrap(), [1; 3000]);
+
+    let mut file = target.lookup("f2").unwrap().unwrap().into_file().unwrap();
+    let content = file.read_vec().unwrap();
+    assert_eq!(content[..2048], [0; 2048]);
+    assert_eq!(content[2048..], [2; 100]);
+
+    let entry = target.lookup("s1").unwrap().unwrap();
+    assert_eq!(entry.into_symlink().unwrap().target(), "f2");
+}
+
+#[test]
+fn rotate_key() {
+    let container = setup_container(Cipher::Aes128Gcm, 512);
+    let mut archive = fill_archive(Archive::create(container, false).unwrap());
+    let target = archive
+        .clone_to(setup_container_with_password(
+            Cipher::Aes128Gcm,
+            512,
+            b"456",
+        ))
+        .unwrap();
+
+    let backend = target.into_container().into_backend();
+    let options = OpenOptionsBuilder::new()
+        .with_password_callback(|| Ok(b"456".to_vec()))
+        .build::<MemoryBackend>()
+        .unwrap();
+    let container = Container::<MemoryBackend>::open(backend, options).unwrap();
+    let mut target = Archive::open(container).unwrap();
+
+    assert_eq!(target.info().files, 4);
+
+    let mut file = target
+        .lookup("d1/f1")
+        .unwrap()
+        .unwrap()
+        .into_file()
+        .unwrap();
+    assert_eq!(file.read_vec().unwrap(), [1; 3000]);
+}
+
+#[test]
+fn index_and_freeze() {
+    let mut archive = setup_archive();
+
+    archive.create_index().unwrap();
+    archive.freeze("token").unwrap();
+
+    let target = archive
+        .clone_to(setup_container(Cipher::None, 512))
+        .unwrap();
+    let mut target = Archive::open(target.into_container()).unwrap();
+
+    assert!(target.is_frozen());
+    assert!(target.has_index());
+    assert!(target.lookup_indexed("f2").is_some());
+
+    target.unfreeze("token").unwrap();
+    target.append_file("f3").build().unwrap();
+    assert_eq!(target.info().files, 5);
+}
+
+#[test]
+fn block_size_mismatch() {
+    let mut archive = setup_archive();
+    let result = archive.clone_to(setup_container(Cipher::None, 1024));
+
+    assert!(matches!(
+        result,
+        Err(RewriteError::Target(Error::BlockSizeMismatch(1024, 512)))
+    ));
+}
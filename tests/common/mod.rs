@@ -0,0 +1,48 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+// Not every test uses all of the helpers.
+#![allow(dead_code)]
+
+use nuts_archive::Archive;
+use nuts_container::container::{Cipher, Container, CreateOptionsBuilder};
+use nuts_container::memory::MemoryBackend;
+
+/// Creates an unencrypted container with the given block size.
+pub fn setup_container_with_bsize(bsize: u32) -> Container<MemoryBackend> {
+    let backend = MemoryBackend::new_with_bsize(bsize);
+    let options = CreateOptionsBuilder::new(Cipher::None)
+        .build::<MemoryBackend>()
+        .unwrap();
+
+    Container::create(backend, options).unwrap()
+}
+
+/// Creates an unencrypted container with a block size of 512 bytes.
+pub fn setup_container() -> Container<MemoryBackend> {
+    setup_container_with_bsize(512)
+}
+
+/// Creates an empty archive in a container created by [`setup_container()`].
+pub fn setup_archive() -> Archive<MemoryBackend> {
+    Archive::create(setup_container(), false).unwrap()
+}
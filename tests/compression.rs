@@ -0,0 +1,307 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::{
+    Archive, Compression, EntryStreamReader, EntryStreamWriter, Error, FileBuilder,
+};
+use nuts_container::memory::MemoryBackend;
+use sha2::{Digest, Sha256};
+use std::io::SeekFrom;
+
+use common::{setup_archive, setup_container_with_bsize};
+
+fn content() -> Vec<u8> {
+    (0..1000)
+        .flat_map(|n| format!("line {} of a text-heavy backup\n", n % 50).into_bytes())
+        .collect()
+}
+
+fn compressed(builder: &mut FileBuilder<MemoryBackend>) {
+    builder.set_compression(Compression::Deflate);
+}
+
+#[test]
+fn round_trip() {
+    let mut archive = setup_archive();
+    let content = content();
+
+    let mut builder = archive.append_file("f1");
+    compressed(&mut builder);
+
+    let mut entry = builder.build().unwrap();
+    entry.write_all(&content).unwrap();
+    archive.append_file("f2").build().unwrap();
+
+    // 32 kB of text in a few blocks
+    assert!(archive.info().blocks < 10);
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    let mut entry = archive.lookup("f1").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.size(), content.len() as u64);
+    assert_eq!(entry.read_vec().unwrap(), content);
+
+    let entry = archive.first().unwrap().unwrap();
+    let entry = entry.next().unwrap().unwrap();
+    assert_eq!(entry.name(), "f2");
+}
+
+#[test]
+fn small_reads() {
+    let mut archive = setup_archive();
+    let content = content();
+
+    let mut builder = archive.append_file("f");
+    compressed(&mut builder);
+
+    let mut entry = builder.build().unwrap();
+
+    for chunk in content.chunks(100) {
+        entry.write_all(chunk).unwrap();
+    }
+
+    let mut entry = archive.lookup("f").unwrap().unwrap().into_file().unwrap();
+    let mut buf = [0; 7];
+    let mut result = vec![];
+
+    loop {
+        match entry.read(&mut buf).unwrap() {
+            0 => break,
+            n => result.extend_from_slice(&buf[..n]),
+        }
+    }
+
+    assert_eq!(result, content);
+}
+
+#[test]
+fn with_content() {
+    let mut archive = setup_archive();
+    let content = content();
+
+    let mut builder = archive.append_file("f");
+    compressed(&mut builder);
+    builder.with_content(&content).build().unwrap();
+
+    let mut entry = archive.lookup("f").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), content);
+}
+
+#[test]
+fn seek_and_read_at() {
+    let mut archive = setup_archive();
+    let content = content();
+
+    let mut builder = archive.append_file("f");
+    compressed(&mut builder);
+    builder.with_content(&content).build().unwrap();
+
+    let mut entry = archive.lookup("f").unwrap().unwrap().into_file().unwrap();
+    let mut buf = [0; 100];
+
+    assert_eq!(entry.seek(SeekFrom::Start(20000)).unwrap(), 20000);
+    entry.read_all(&mut buf).unwrap();
+    assert_eq!(buf, content[20000..20100]);
+
+    assert_eq!(entry.seek(SeekFrom::Start(100)).unwrap(), 100);
+    entry.read_all(&mut buf).unwrap();
+    assert_eq!(buf, content[100..200]);
+
+    assert_eq!(entry.read_at(30000, &mut buf).unwrap(), 100);
+    assert_eq!(buf, content[30000..30100]);
+
+    // the read position is not changed
+    entry.read_all(&mut buf).unwrap();
+    assert_eq!(buf, content[200..300]);
+
+    let end = content.len() as u64;
+    assert_eq!(entry.seek(SeekFrom::End(-10)).unwrap(), end - 10);
+    assert_eq!(entry.read_vec().unwrap(), content[content.len() - 10..]);
+}
+
+#[test]
+fn hashed() {
+    let mut archive = setup_archive();
+    let content = content();
+
+    let mut builder = archive.append_file("f");
+    compressed(&mut builder);
+    builder.set_hashed(true);
+
+    let mut entry = builder.build().unwrap();
+    entry.write_all(&content).unwrap();
+
+    let expected: [u8; 32] = Sha256::digest(&content).into();
+    assert_eq!(entry.current_hash(), Some(expected));
+
+    let entry = archive.lookup("f").unwrap().unwrap();
+    assert_eq!(entry.stored_checksum(), Some(&expected));
+}
+
+#[test]
+fn append() {
+    let mut archive = setup_archive();
+    let content = content();
+    let (first, second) = content.split_at(10000);
+
+    let mut builder = archive.append_file("f");
+    compressed(&mut builder);
+
+    let mut entry = builder.build().unwrap();
+    entry.write_all(first).unwrap();
+
+    let mut entry = archive.open_mut("f").unwrap();
+    entry.write_all(second).unwrap();
+
+    let mut entry = archive.lookup("f").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.size(), content.len() as u64);
+    assert_eq!(entry.read_vec().unwrap(), content);
+}
+
+#[test]
+fn random_access_write() {
+    let mut archive = setup_archive();
+
+    let mut builder = archive.append_file("f");
+    compressed(&mut builder);
+
+    let mut entry = builder.build().unwrap();
+    entry.write_all(b"content").unwrap();
+
+    let err = entry.write_at(0, b"x").unwrap_err();
+    assert!(matches!(err, Error::Compressed(ref name) if name == "f"));
+
+    let err = entry.set_len(1).unwrap_err();
+    assert!(matches!(err, Error::Compressed(ref name) if name == "f"));
+
+    let err = archive.truncate("f", 1).unwrap_err();
+    assert!(matches!(err, Error::Compressed(ref name) if name == "f"));
+
+    let mut builder = archive.append_file("g");
+    compressed(&mut builder);
+
+    let result = builder.build_multipart(10);
+    assert!(matches!(result, Err(Error::Compressed(ref name)) if name == "g"));
+
+    let mut entry = archive.lookup("f").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), b"content");
+}
+
+#[test]
+fn stream() {
+    let mut archive = setup_archive();
+    let content = content();
+
+    let mut builder = archive.append_file("f");
+    compressed(&mut builder);
+    builder.with_content(&content).build().unwrap();
+
+    let mut writer = EntryStreamWriter::new(vec![]).unwrap();
+    let mut entry = archive.first().unwrap().unwrap();
+
+    writer.write_entry(&mut entry).unwrap();
+
+    let buf = writer.finish().unwrap();
+    let mut target = setup_archive();
+
+    EntryStreamReader::new(buf.as_slice())
+        .unwrap()
+        .import(&mut target)
+        .unwrap();
+
+    // the content is compressed again
+    assert!(target.info().blocks < 10);
+
+    let mut entry = target.lookup("f").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), content);
+}
+
+#[test]
+fn full() {
+    let container = setup_container_with_bsize(106);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    // leaves less than 200 blocks for content
+    archive.set_reserved_capacity(99);
+
+    let mut seed = 1u32;
+    let random = (0..30000)
+        .map(|_| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) as u8
+        })
+        .collect::<Vec<_>>();
+
+    let mut builder = archive.append_file("f");
+    compressed(&mut builder);
+
+    let mut entry = builder.build().unwrap();
+    entry.write_all(&random[..10000]).unwrap();
+
+    let err = entry.write_all(&random[10000..]).unwrap_err();
+    assert!(matches!(err, Error::Full(0)));
+
+    // the compressed content is cut off behind the last successful write
+    let mut entry = archive.lookup("f").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.size(), 10000);
+    assert_eq!(entry.read_vec().unwrap(), random[..10000]);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn zstd() {
+    let mut archive = setup_archive();
+    let content = content();
+    let (first, second) = content.split_at(10000);
+
+    let mut builder = archive.append_file("f");
+    builder.set_compression(Compression::Zstd(19));
+
+    let mut entry = builder.build().unwrap();
+
+    for chunk in first.chunks(2000) {
+        entry.write_all(chunk).unwrap();
+    }
+
+    // continued with a new compressor
+    let mut entry = archive.open_mut("f").unwrap();
+    entry.write_all(second).unwrap();
+
+    assert!(archive.info().blocks < 10);
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    let mut entry = archive.lookup("f").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.size(), content.len() as u64);
+    assert_eq!(entry.read_vec().unwrap(), content);
+
+    let mut buf = [0; 100];
+
+    assert_eq!(entry.seek(SeekFrom::Start(20000)).unwrap(), 20000);
+    entry.read_all(&mut buf).unwrap();
+    assert_eq!(buf, content[20000..20100]);
+
+    assert_eq!(entry.read_at(100, &mut buf).unwrap(), 100);
+    assert_eq!(buf, content[100..200]);
+}
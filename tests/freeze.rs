@@ -0,0 +1,112 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::{Archive, Error};
+
+use common::setup_archive;
+
+#[test]
+fn frozen() {
+    let mut archive = setup_archive();
+
+    archive.append_file("f1").build().unwrap();
+    archive.freeze("secret").unwrap();
+    assert!(archive.is_frozen());
+
+    let result = archive.append_file("f2").build();
+    assert!(matches!(result, Err(Error::Frozen)));
+
+    let err = archive.append_directory("d1").build().unwrap_err();
+    assert!(matches!(err, Error::Frozen));
+
+    let err = archive.append_symlink("s1", "f1").build().unwrap_err();
+    assert!(matches!(err, Error::Frozen));
+
+    // reading is still possible
+    assert_eq!(archive.first().unwrap().unwrap().name(), "f1");
+    assert_eq!(archive.info().files, 1);
+}
+
+#[test]
+fn freeze_twice() {
+    let mut archive = setup_archive();
+
+    archive.freeze("secret").unwrap();
+
+    let err = archive.freeze("secret").unwrap_err();
+    assert!(matches!(err, Error::Frozen));
+}
+
+#[test]
+fn persistent() {
+    let mut archive = setup_archive();
+
+    archive.freeze("secret").unwrap();
+
+    let mut container = archive.into_container();
+    assert!(Archive::peek(&mut container).unwrap().frozen);
+
+    let mut archive = Archive::open(container).unwrap();
+    assert!(archive.is_frozen());
+
+    let result = archive.append_file("f1").build();
+    assert!(matches!(result, Err(Error::Frozen)));
+}
+
+#[test]
+fn unfreeze() {
+    let mut archive = setup_archive();
+
+    archive.freeze("secret").unwrap();
+    archive.unfreeze("secret").unwrap();
+    assert!(!archive.is_frozen());
+
+    archive.append_file("f1").build().unwrap();
+
+    let mut container = archive.into_container();
+    assert!(!Archive::peek(&mut container).unwrap().frozen);
+
+    let archive = Archive::open(container).unwrap();
+    assert!(!archive.is_frozen());
+    assert_eq!(archive.info().files, 1);
+}
+
+#[test]
+fn unfreeze_invalid_token() {
+    let mut archive = setup_archive();
+
+    archive.freeze("secret").unwrap();
+
+    let err = archive.unfreeze("guess").unwrap_err();
+    assert!(matches!(err, Error::InvalidFreezeToken));
+    assert!(archive.is_frozen());
+}
+
+#[test]
+fn unfreeze_not_frozen() {
+    let mut archive = setup_archive();
+
+    archive.unfreeze("secret").unwrap();
+    assert!(!archive.is_frozen());
+}
@@ -0,0 +1,124 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::{Archive, EntryStreamReader, EntryStreamWriter, Index};
+use nuts_container::memory::MemoryBackend;
+
+use common::setup_archive;
+
+fn setup_linked() -> Archive<MemoryBackend> {
+    let mut archive = setup_archive();
+
+    archive
+        .append_file("f1")
+        .build()
+        .unwrap()
+        .write_all(&[1; 1000])
+        .unwrap();
+    archive.append_hardlink("h1", "f1").build().unwrap();
+
+    archive
+}
+
+#[test]
+fn entry() {
+    let archive = setup_linked();
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    let entry = archive.lookup("h1").unwrap().unwrap();
+    assert!(entry.is_hardlink());
+    assert!(!entry.is_file());
+    assert!(entry.as_hardlink().is_some());
+    assert_eq!(entry.to_string_rwx(), "hrwxr-xr-x");
+    assert_eq!(entry.unix_mode(), 0o100755);
+
+    let link = entry.into_hardlink().unwrap();
+    assert_eq!(link.name(), "h1");
+    assert_eq!(link.target(), "f1");
+
+    let target = link.target().to_string();
+    let mut file = archive
+        .lookup(target)
+        .unwrap()
+        .unwrap()
+        .into_file()
+        .unwrap();
+    assert_eq!(file.read_vec().unwrap(), [1; 1000]);
+}
+
+#[test]
+fn no_duplicated_content() {
+    let mut archive = setup_archive();
+
+    archive
+        .append_file("f1")
+        .build()
+        .unwrap()
+        .write_all(&[1; 1000])
+        .unwrap();
+    let nblocks = archive.info().blocks;
+
+    archive.append_hardlink("h1", "f1").build().unwrap();
+    assert_eq!(archive.info().blocks, nblocks + 2);
+}
+
+#[test]
+fn index() {
+    let mut archive = setup_linked();
+
+    let mut buf = vec![];
+    archive.export_index(&mut buf).unwrap();
+
+    let index = Index::import(buf.as_slice()).unwrap();
+    let entry = index.lookup("h1").unwrap();
+    assert!(entry.is_hardlink());
+    assert_eq!(entry.target(), Some("f1"));
+}
+
+#[test]
+fn stream() {
+    let mut archive = setup_linked();
+    let mut writer = EntryStreamWriter::new(vec![]).unwrap();
+
+    let mut entry = archive.first().unwrap().unwrap();
+    writer.write_entry(&mut entry).unwrap();
+    let mut entry = entry.next().unwrap().unwrap();
+    writer.write_entry(&mut entry).unwrap();
+
+    let buf = writer.finish().unwrap();
+    let mut target = setup_archive();
+
+    EntryStreamReader::new(buf.as_slice())
+        .unwrap()
+        .import(&mut target)
+        .unwrap();
+
+    let link = target
+        .lookup("h1")
+        .unwrap()
+        .unwrap()
+        .into_hardlink()
+        .unwrap();
+    assert_eq!(link.target(), "f1");
+}
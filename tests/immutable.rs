@@ -0,0 +1,140 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::{Archive, Error};
+use nuts_container::memory::MemoryBackend;
+
+use common::setup_archive;
+
+fn setup_immutable() -> Archive<MemoryBackend> {
+    let mut archive = setup_archive();
+
+    archive
+        .append_file("f1")
+        .build()
+        .unwrap()
+        .write_all(b"abc")
+        .unwrap();
+    archive.set_immutable("f1", true).unwrap();
+
+    archive
+}
+
+#[test]
+fn set_immutable() {
+    let mut archive = setup_immutable();
+
+    let entry = archive.lookup("f1").unwrap().unwrap();
+    assert!(entry.is_immutable());
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+    assert!(archive.first().unwrap().unwrap().is_immutable());
+
+    archive.set_immutable("f1", false).unwrap();
+    assert!(!archive.first().unwrap().unwrap().is_immutable());
+}
+
+#[test]
+fn builder() {
+    let mut archive = setup_archive();
+
+    let mut builder = archive.append_file("f1");
+    builder.set_immutable(true);
+    builder.build().unwrap().write_all(b"abc").unwrap();
+
+    archive.append_directory("d1").build().unwrap();
+
+    let entry = archive.first().unwrap().unwrap();
+    assert!(entry.is_immutable());
+
+    let err = archive.remove("f1").unwrap_err();
+    assert!(matches!(err, Error::Immutable(name) if name == "f1"));
+
+    archive.remove("d1").unwrap();
+}
+
+#[test]
+fn remove() {
+    let mut archive = setup_immutable();
+
+    let err = archive.remove("f1").unwrap_err();
+    assert!(matches!(err, Error::Immutable(name) if name == "f1"));
+    assert!(archive.contains("f1").unwrap());
+
+    archive.force_remove("f1").unwrap();
+    assert!(!archive.contains("f1").unwrap());
+}
+
+#[test]
+fn truncate() {
+    let mut archive = setup_immutable();
+
+    for len in [0, 10] {
+        let err = archive.truncate("f1", len).unwrap_err();
+        assert!(matches!(err, Error::Immutable(name) if name == "f1"));
+    }
+
+    archive.force_truncate("f1", 1).unwrap();
+    archive.force_truncate("f1", 5).unwrap();
+
+    let mut entry = archive.first().unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), b"a\0\0\0\0");
+    assert!(entry.is_immutable());
+}
+
+#[test]
+fn open_mut() {
+    let mut archive = setup_immutable();
+
+    let err = archive.open_mut("f1").err().unwrap();
+    assert!(matches!(err, Error::Immutable(name) if name == "f1"));
+
+    archive
+        .force_open_mut("f1")
+        .unwrap()
+        .write_all(b"def")
+        .unwrap();
+
+    let mut entry = archive.first().unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), b"abcdef");
+}
+
+#[test]
+fn frozen() {
+    let mut archive = setup_archive();
+
+    archive.append_file("f1").build().unwrap();
+    archive.freeze("token").unwrap();
+
+    let err = archive.set_immutable("f1", true).unwrap_err();
+    assert!(matches!(err, Error::Frozen));
+}
+
+#[test]
+fn not_found() {
+    let mut archive = setup_archive();
+
+    let err = archive.set_immutable("f1", true).unwrap_err();
+    assert!(matches!(err, Error::NotFound(name) if name == "f1"));
+}
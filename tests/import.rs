@@ -0,0 +1,105 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::Error;
+use std::fs;
+use std::io::ErrorKind;
+use tempdir::TempDir;
+
+use common::setup_archive;
+
+#[test]
+fn file() {
+    let tmp_dir = TempDir::new("nuts-archive").unwrap();
+    let path = tmp_dir.path().join("foo");
+    let content = (0..1300).map(|n| (n % 251) as u8).collect::<Vec<_>>();
+
+    fs::write(&path, &content).unwrap();
+
+    let mut archive = setup_archive();
+    archive.append_from_path(&path).unwrap();
+
+    let name = path.to_str().unwrap().trim_start_matches('/');
+    let entry = archive.lookup(name).unwrap().unwrap();
+    let modified = fs::metadata(&path).unwrap().modified().unwrap();
+
+    assert_eq!(entry.size(), 1300);
+    // timestamps are stored with millisecond precision
+    assert_eq!(
+        entry.modified().timestamp_millis(),
+        chrono::DateTime::<chrono::Utc>::from(modified).timestamp_millis()
+    );
+
+    let mut file = entry.into_file().unwrap();
+    assert_eq!(file.read_vec().unwrap(), content);
+}
+
+#[cfg(unix)]
+#[test]
+fn unix_mode() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tmp_dir = TempDir::new("nuts-archive").unwrap();
+    let path = tmp_dir.path().join("foo");
+
+    fs::write(&path, b"foo").unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+    let mut archive = setup_archive();
+    archive.append_from_path(&path).unwrap();
+
+    let entry = archive.first().unwrap().unwrap();
+    assert_eq!(entry.unix_mode(), 0o100640);
+    assert!(entry.uid().is_some());
+    assert!(entry.gid().is_some());
+}
+
+#[test]
+fn directory() {
+    let tmp_dir = TempDir::new("nuts-archive").unwrap();
+    let mut archive = setup_archive();
+
+    let err = archive.append_from_path(tmp_dir.path()).unwrap_err();
+    assert!(matches!(err, Error::NotFile(_)));
+    assert!(archive.first().is_none());
+}
+
+#[test]
+fn not_found() {
+    let tmp_dir = TempDir::new("nuts-archive").unwrap();
+    let mut archive = setup_archive();
+
+    let err = archive
+        .append_from_path(tmp_dir.path().join("foo"))
+        .unwrap_err();
+    assert!(matches!(err, Error::Io(ref err) if err.kind() == ErrorKind::NotFound));
+}
+
+#[test]
+fn parent_dir() {
+    let mut archive = setup_archive();
+
+    let err = archive.append_from_path("../foo").unwrap_err();
+    assert!(matches!(err, Error::Io(ref err) if err.kind() == ErrorKind::InvalidInput));
+}
@@ -0,0 +1,163 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::Archive;
+
+use common::setup_archive;
+
+fn long_name(depth: usize) -> String {
+    (0..depth)
+        .map(|n| format!("directory-{:04}", n))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[test]
+fn file() {
+    let mut archive = setup_archive();
+    let name = long_name(150);
+
+    assert!(name.len() > 2000);
+
+    let mut entry = archive.append_file(&name).build().unwrap();
+    entry.write_all(&[1; 1000]).unwrap();
+    archive.append_file("f").build().unwrap();
+
+    // entry + 5 name blocks + 2 content blocks, entry
+    assert_eq!(archive.info().blocks, 9);
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    let mut entry = archive.lookup(&name).unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.name(), name);
+    assert_eq!(entry.read_vec().unwrap(), [1; 1000]);
+
+    let entry = archive.first().unwrap().unwrap();
+    assert_eq!(entry.name(), name);
+
+    let entry = entry.next().unwrap().unwrap();
+    assert_eq!(entry.name(), "f");
+    assert!(entry.next().is_none());
+}
+
+#[test]
+fn directory_and_symlink() {
+    let mut archive = setup_archive();
+    let name = long_name(100);
+
+    archive.append_directory(&name).build().unwrap();
+    archive
+        .append_symlink(format!("{}/link", name), "target")
+        .build()
+        .unwrap();
+
+    let entry = archive.lookup(&name).unwrap().unwrap();
+    assert!(entry.is_directory());
+
+    let entry = archive.lookup(format!("{}/link", name)).unwrap().unwrap();
+    assert_eq!(entry.into_symlink().unwrap().target(), "target");
+}
+
+#[test]
+fn directory_children() {
+    let mut archive = setup_archive();
+    let name = long_name(50);
+
+    assert!(name.len() > 700);
+
+    archive.append_directory(&name).build().unwrap();
+    archive.append_file(format!("{}/f1", name)).build().unwrap();
+    archive.append_file(format!("{}/f2", name)).build().unwrap();
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    let entry = archive.lookup(&name).unwrap().unwrap();
+    assert_eq!(entry.as_directory().unwrap().children(), 2);
+
+    archive.remove(format!("{}/f2", name)).unwrap();
+
+    let entry = archive.lookup(&name).unwrap().unwrap();
+    assert_eq!(entry.name(), name);
+    assert_eq!(entry.as_directory().unwrap().children(), 1);
+}
+
+#[test]
+fn append_content() {
+    let mut archive = setup_archive();
+    let name = long_name(50);
+
+    let mut entry = archive.append_file(&name).build().unwrap();
+    entry.write_all(&[1; 600]).unwrap();
+
+    archive.truncate(&name, 100).unwrap();
+
+    let mut entry = archive.lookup(&name).unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), [1; 100]);
+}
+
+#[test]
+fn remove() {
+    let mut archive = setup_archive();
+    let name = long_name(100);
+
+    archive.append_file("f1").build().unwrap();
+    let mut entry = archive.append_file(&name).build().unwrap();
+    entry.write_all(b"content").unwrap();
+    archive.append_file("f2").build().unwrap();
+
+    archive.remove(&name).unwrap();
+
+    assert!(archive.lookup(&name).is_none());
+    assert!(archive.lookup("f2").is_some());
+    assert_eq!(archive.info().blocks, 2);
+    assert_eq!(archive.info().files, 2);
+}
+
+#[test]
+fn rename_grows_and_shrinks() {
+    let mut archive = setup_archive();
+    let (short, long) = (long_name(50), long_name(150));
+
+    let mut entry = archive.append_file(&short).build().unwrap();
+    entry.write_all(b"content").unwrap();
+    archive.append_file("f").build().unwrap();
+
+    archive.rename(&short, &long).unwrap();
+
+    let mut entry = archive.lookup(&long).unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), b"content");
+    assert!(archive.lookup("f").is_some());
+
+    archive.rename(&long, &short).unwrap();
+
+    let mut entry = archive
+        .lookup(&short)
+        .unwrap()
+        .unwrap()
+        .into_file()
+        .unwrap();
+    assert_eq!(entry.read_vec().unwrap(), b"content");
+    assert!(archive.lookup("f").is_some());
+    assert_eq!(archive.info().blocks, 5);
+}
@@ -181,3 +181,135 @@ fn three_entries() {
 
     assert!(archive.lookup("no.such.entry").is_none());
 }
+
+#[test]
+fn contains() {
+    let tmp_dir = setup_archive();
+    let mut archive = open_archive(tmp_dir);
+
+    assert!(!archive.contains("f1.txt").unwrap());
+
+    {
+        let mut entry = archive.append_file("d1/f1.txt").build().unwrap();
+        entry.write_all(b"some content").unwrap();
+    }
+
+    archive
+        .append_symlink("s1.txt", "d1/f1.txt")
+        .build()
+        .unwrap();
+
+    assert!(archive.contains("d1/f1.txt").unwrap());
+    assert!(archive.contains("s1.txt").unwrap());
+    assert!(!archive.contains("d1").unwrap());
+    assert!(!archive.contains("f1.txt").unwrap());
+}
+
+#[test]
+fn exists_prefix() {
+    let tmp_dir = setup_archive();
+    let mut archive = open_archive(tmp_dir);
+
+    assert!(!archive.exists_prefix("").unwrap());
+
+    archive.append_file("d1/d2/f1.txt").build().unwrap();
+
+    assert!(archive.exists_prefix("").unwrap());
+    assert!(archive.exists_prefix("d1/").unwrap());
+    assert!(archive.exists_prefix("d1/d2/f").unwrap());
+    assert!(archive.exists_prefix("d1/d2/f1.txt").unwrap());
+    assert!(!archive.exists_prefix("d1/d3").unwrap());
+    assert!(!archive.exists_prefix("d1/d2/f1.txt.bak").unwrap());
+}
+
+#[test]
+fn lookup_indexed() {
+    let tmp_dir = setup_archive();
+    let mut archive = open_archive(tmp_dir);
+
+    {
+        let mut entry = archive.append_file("f1.txt").build().unwrap();
+        entry.write_all(b"some content").unwrap();
+    }
+
+    assert!(!archive.has_index());
+    assert!(archive.lookup_indexed("f1.txt").unwrap().unwrap().is_file());
+
+    archive.create_index().unwrap();
+    assert!(archive.has_index());
+
+    archive.append_directory("d1").build().unwrap();
+    archive.append_symlink("s1.txt", "f1.txt").build().unwrap();
+
+    let entry = archive.lookup_indexed("f1.txt").unwrap().unwrap();
+    assert_eq!(
+        entry.into_file().unwrap().read_vec().unwrap(),
+        b"some content"
+    );
+
+    assert!(archive
+        .lookup_indexed("d1")
+        .unwrap()
+        .unwrap()
+        .is_directory());
+    assert!(archive
+        .lookup_indexed("s1.txt")
+        .unwrap()
+        .unwrap()
+        .is_symlink());
+    assert!(archive.lookup_indexed("no.such.entry").is_none());
+}
+
+#[test]
+fn lookup_indexed_reopen() {
+    let tmp_dir = setup_archive();
+    let mut archive = open_archive(tmp_dir);
+
+    archive.create_index().unwrap();
+    archive.append_file("f1.txt").build().unwrap();
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+    assert!(archive.has_index());
+
+    archive.append_file("f2.txt").build().unwrap();
+
+    let entry = archive.lookup_indexed("f1.txt").unwrap().unwrap();
+    assert_eq!(entry.name(), "f1.txt");
+
+    let entry = archive.lookup_indexed("f2.txt").unwrap().unwrap();
+    assert_eq!(entry.name(), "f2.txt");
+}
+
+#[test]
+fn lookup_indexed_mapped() {
+    let tmp_dir = setup_archive();
+    let mut archive = open_archive(tmp_dir);
+
+    archive.create_index().unwrap();
+    archive.set_index_mapping(true);
+
+    for n in 0..20 {
+        let mut entry = archive.append_file(format!("f{}", n)).build().unwrap();
+        entry.write_all(&[n as u8; 1000]).unwrap();
+    }
+
+    for n in 0..20 {
+        let entry = archive.lookup_indexed(format!("f{}", n)).unwrap().unwrap();
+        let mut file = entry.into_file().unwrap();
+
+        assert_eq!(file.read_vec().unwrap(), [n as u8; 1000]);
+    }
+
+    // the mapping is refreshed after the archive was modified
+    archive.remove("f0").unwrap();
+    archive.append_file("f20").build().unwrap();
+
+    assert!(archive.lookup_indexed("f0").is_none());
+
+    let entry = archive.lookup_indexed("f1").unwrap().unwrap();
+    let mut file = entry.into_file().unwrap();
+    assert_eq!(file.read_vec().unwrap(), [1; 1000]);
+
+    let entry = archive.lookup_indexed("f20").unwrap().unwrap();
+    assert_eq!(entry.size(), 0);
+}
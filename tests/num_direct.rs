@@ -0,0 +1,120 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::{Archive, Error};
+use nuts_container::memory::MemoryBackend;
+
+use common::setup_container_with_bsize;
+
+fn fill_archive(archive: &mut Archive<MemoryBackend>) {
+    archive.append_directory("d").build().unwrap();
+    archive
+        .append_file("d/f")
+        .with_content(&[1; 3000])
+        .build()
+        .unwrap();
+}
+
+fn assert_content(archive: &mut Archive<MemoryBackend>) {
+    assert!(archive.lookup("d").unwrap().unwrap().is_directory());
+
+    let mut entry = archive.lookup("d/f").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), [1; 3000]);
+}
+
+#[test]
+fn default() {
+    let mut archive = Archive::create(setup_container_with_bsize(512), false).unwrap();
+
+    fill_archive(&mut archive);
+    assert_eq!(archive.info().num_direct, 12);
+
+    let mut container = archive.into_container();
+
+    // readable by versions without a configurable number of direct blocks
+    assert_eq!(Archive::peek(&mut container).unwrap().revision, 1);
+}
+
+#[test]
+fn fewer() {
+    let container = setup_container_with_bsize(512);
+    let mut archive = Archive::create_with_num_direct(container, false, 2).unwrap();
+
+    fill_archive(&mut archive);
+    assert_eq!(archive.info().num_direct, 2);
+
+    let mut container = archive.into_container();
+    assert_eq!(Archive::peek(&mut container).unwrap().revision, 2);
+
+    let mut archive = Archive::open(container).unwrap();
+
+    assert_eq!(archive.info().num_direct, 2);
+    assert_content(&mut archive);
+}
+
+#[test]
+fn none() {
+    let container = setup_container_with_bsize(512);
+    let mut archive = Archive::create_with_num_direct(container, false, 0).unwrap();
+
+    fill_archive(&mut archive);
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    assert_eq!(archive.info().num_direct, 0);
+    assert_content(&mut archive);
+}
+
+#[test]
+fn more() {
+    let container = setup_container_with_bsize(512);
+    let mut archive = Archive::create_with_num_direct(container, false, 64).unwrap();
+
+    fill_archive(&mut archive);
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    assert_eq!(archive.info().num_direct, 64);
+    assert_content(&mut archive);
+}
+
+#[test]
+fn too_many() {
+    let result = Archive::create_with_num_direct(setup_container_with_bsize(512), false, 200);
+    assert!(matches!(result, Err(Error::BlockSizeTooSmall(512, n)) if n > 512));
+}
+
+#[test]
+fn clone() {
+    let container = setup_container_with_bsize(512);
+    let mut archive = Archive::create_with_num_direct(container, false, 3).unwrap();
+
+    fill_archive(&mut archive);
+
+    let clone = archive.clone_to(setup_container_with_bsize(512)).unwrap();
+    let mut clone = Archive::open(clone.into_container()).unwrap();
+
+    assert_eq!(clone.info().num_direct, 3);
+    assert_content(&mut clone);
+}
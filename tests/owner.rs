@@ -0,0 +1,60 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::{Archive, Index};
+
+use common::setup_archive;
+
+#[test]
+fn round_trip() {
+    let mut archive = setup_archive();
+
+    let mut builder = archive.append_file("f1");
+    builder.set_uid(Some(0));
+    builder.set_gid(Some(0));
+    builder.build().unwrap();
+
+    let mut builder = archive.append_directory("d1");
+    builder.set_uid(Some(1000));
+    builder.build().unwrap();
+
+    archive.append_symlink("l1", "f1").build().unwrap();
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    let entry = archive.lookup("f1").unwrap().unwrap();
+    assert_eq!((entry.uid(), entry.gid()), (Some(0), Some(0)));
+
+    let entry = archive.lookup("d1").unwrap().unwrap();
+    assert_eq!((entry.uid(), entry.gid()), (Some(1000), None));
+
+    let entry = archive.lookup("l1").unwrap().unwrap();
+    assert_eq!((entry.uid(), entry.gid()), (None, None));
+
+    let mut buf = vec![];
+    archive.export_index(&mut buf).unwrap();
+
+    let index = Index::import(buf.as_slice()).unwrap();
+    assert_eq!(index.lookup("d1").unwrap().uid(), Some(1000));
+}
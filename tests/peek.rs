@@ -0,0 +1,72 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::{Archive, Error};
+
+use common::setup_container;
+
+#[test]
+fn no_archive() {
+    let mut container = setup_container();
+    let err = Archive::peek(&mut container).unwrap_err();
+
+    assert!(matches!(err, Error::InvalidUserdata(None)));
+}
+
+#[test]
+fn empty() {
+    let archive = Archive::create(setup_container(), false).unwrap();
+
+    let mut container = archive.into_container();
+    let summary = Archive::peek(&mut container).unwrap();
+    let info = Archive::open(container).unwrap().info();
+
+    assert_eq!(summary.revision, 1);
+    assert_eq!(summary.block_size, 512);
+    assert_eq!(summary.created, info.created);
+    assert_eq!(summary.modified, info.modified);
+    assert_eq!(summary.blocks, 0);
+    assert_eq!(summary.files, 0);
+}
+
+#[test]
+fn entries() {
+    let mut archive = Archive::create(setup_container(), false).unwrap();
+
+    let mut entry = archive.append_file("f1").build().unwrap();
+    entry.write_all(&[1; 1000]).unwrap();
+
+    archive.append_directory("d1").build().unwrap();
+
+    let mut container = archive.into_container();
+    let summary = Archive::peek(&mut container).unwrap();
+
+    // the container is still usable
+    let info = Archive::open(container).unwrap().info();
+
+    assert_eq!(summary.modified, info.modified);
+    assert_eq!(summary.blocks, 4);
+    assert_eq!(summary.files, 2);
+    assert_eq!(info.files, 2);
+}
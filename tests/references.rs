@@ -0,0 +1,147 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::{Archive, Error};
+use nuts_container::memory::MemoryBackend;
+
+use common::setup_archive;
+
+fn setup_referenced() -> Archive<MemoryBackend> {
+    let mut archive = setup_archive();
+
+    archive
+        .append_file("f1")
+        .build()
+        .unwrap()
+        .write_all(b"abc")
+        .unwrap();
+    assert_eq!(archive.register_reference("f1").unwrap(), 1);
+
+    archive
+}
+
+#[test]
+fn register() {
+    let mut archive = setup_referenced();
+
+    assert_eq!(archive.register_reference("f1").unwrap(), 2);
+
+    let entry = archive.lookup("f1").unwrap().unwrap();
+    assert_eq!(entry.external_refs(), 2);
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+    assert_eq!(archive.first().unwrap().unwrap().external_refs(), 2);
+
+    assert_eq!(archive.unregister_reference("f1").unwrap(), 1);
+    assert_eq!(archive.unregister_reference("f1").unwrap(), 0);
+    assert_eq!(archive.first().unwrap().unwrap().external_refs(), 0);
+
+    let err = archive.unregister_reference("f1").unwrap_err();
+    assert!(matches!(err, Error::NoReference(name) if name == "f1"));
+}
+
+#[test]
+fn remove() {
+    let mut archive = setup_referenced();
+
+    let err = archive.remove("f1").unwrap_err();
+    assert!(matches!(err, Error::Referenced(name) if name == "f1"));
+
+    let err = archive.force_remove("f1").unwrap_err();
+    assert!(matches!(err, Error::Referenced(name) if name == "f1"));
+    assert!(archive.contains("f1").unwrap());
+
+    archive.unregister_reference("f1").unwrap();
+    archive.remove("f1").unwrap();
+    assert!(!archive.contains("f1").unwrap());
+}
+
+#[test]
+fn truncate() {
+    let mut archive = setup_referenced();
+
+    for len in [0, 10] {
+        let err = archive.truncate("f1", len).unwrap_err();
+        assert!(matches!(err, Error::Referenced(name) if name == "f1"));
+
+        let err = archive.force_truncate("f1", len).unwrap_err();
+        assert!(matches!(err, Error::Referenced(name) if name == "f1"));
+    }
+
+    let mut entry = archive.first().unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), b"abc");
+}
+
+#[test]
+fn open_mut() {
+    let mut archive = setup_referenced();
+
+    let err = archive.open_mut("f1").err().unwrap();
+    assert!(matches!(err, Error::Referenced(name) if name == "f1"));
+
+    let err = archive.force_open_mut("f1").err().unwrap();
+    assert!(matches!(err, Error::Referenced(name) if name == "f1"));
+}
+
+#[test]
+fn rollback() {
+    let mut archive = setup_archive();
+
+    archive.savepoint("s1");
+    archive.append_file("f1").build().unwrap();
+    archive.savepoint("s2");
+    archive.append_file("f2").build().unwrap();
+    archive.register_reference("f1").unwrap();
+
+    let err = archive.rollback_to("s1").unwrap_err();
+    assert!(matches!(err, Error::Referenced(name) if name == "f1"));
+    assert_eq!(archive.savepoints(), ["s1", "s2"]);
+    assert!(archive.contains("f2").unwrap());
+
+    archive.rollback_to("s2").unwrap();
+    assert!(archive.contains("f1").unwrap());
+    assert!(!archive.contains("f2").unwrap());
+}
+
+#[test]
+fn frozen() {
+    let mut archive = setup_archive();
+
+    archive.append_file("f1").build().unwrap();
+    archive.freeze("token").unwrap();
+
+    let err = archive.register_reference("f1").unwrap_err();
+    assert!(matches!(err, Error::Frozen));
+}
+
+#[test]
+fn not_found() {
+    let mut archive = setup_archive();
+
+    let err = archive.register_reference("f1").unwrap_err();
+    assert!(matches!(err, Error::NotFound(name) if name == "f1"));
+
+    let err = archive.unregister_reference("f1").unwrap_err();
+    assert!(matches!(err, Error::NotFound(name) if name == "f1"));
+}
@@ -0,0 +1,213 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::{Archive, Error};
+use nuts_container::memory::MemoryBackend;
+
+use common::setup_archive;
+
+fn names(archive: &mut Archive<MemoryBackend>) -> Vec<String> {
+    let mut names = vec![];
+    let mut entry_opt = archive.first();
+
+    while let Some(entry) = entry_opt {
+        let entry = entry.unwrap();
+
+        names.push(entry.name().to_string());
+        entry_opt = entry.next();
+    }
+
+    names
+}
+
+#[test]
+fn remove() {
+    let mut archive = setup_archive();
+
+    // crosses into the double indirect blocks
+    for i in 0..80 {
+        let mut entry = archive.append_file(format!("f{}", i)).build().unwrap();
+        entry
+            .write_all(format!("content {}", i).as_bytes())
+            .unwrap();
+    }
+
+    archive.create_index().unwrap();
+    archive.remove("f3").unwrap();
+
+    let expected = (0..80)
+        .filter(|i| *i != 3)
+        .map(|i| format!("f{}", i))
+        .collect::<Vec<_>>();
+
+    assert_eq!(names(&mut archive), expected);
+    assert_eq!(archive.info().files, 79);
+    assert!(!archive.contains("f3").unwrap());
+    assert!(archive.lookup_indexed("f3").is_none());
+
+    let mut entry = archive
+        .lookup_indexed("f79")
+        .unwrap()
+        .unwrap()
+        .into_file()
+        .unwrap();
+    assert_eq!(entry.read_vec().unwrap(), b"content 79");
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    assert_eq!(names(&mut archive), expected);
+    assert_eq!(archive.info().files, 79);
+    assert!(archive.has_index());
+    assert!(archive.lookup_indexed("f3").is_none());
+
+    let mut entry = archive.append_file("f80").build().unwrap();
+    entry.write_all(b"content 80").unwrap();
+
+    let mut entry = archive.lookup("f80").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), b"content 80");
+}
+
+#[test]
+fn remove_child() {
+    let mut archive = setup_archive();
+
+    archive.append_directory("d").build().unwrap();
+    archive.append_file("d/f1").build().unwrap();
+    archive.append_file("d/f2").build().unwrap();
+
+    archive.remove("d/f1").unwrap();
+
+    let dir = archive
+        .lookup("d")
+        .unwrap()
+        .unwrap()
+        .into_directory()
+        .unwrap();
+    assert_eq!(dir.children(), 1);
+    assert_eq!(names(&mut archive), ["d", "d/f2"]);
+}
+
+#[test]
+fn not_found() {
+    let mut archive = setup_archive();
+
+    archive.append_file("f1").build().unwrap();
+
+    let err = archive.remove("f2").unwrap_err();
+    assert!(matches!(err, Error::NotFound(name) if name == "f2"));
+}
+
+#[test]
+fn frozen() {
+    let mut archive = setup_archive();
+
+    archive.append_file("f1").build().unwrap();
+    archive.freeze("secret").unwrap();
+
+    let err = archive.remove("f1").unwrap_err();
+    assert!(matches!(err, Error::Frozen));
+    assert_eq!(names(&mut archive), ["f1"]);
+}
+
+#[test]
+fn hardlink_target() {
+    let mut archive = setup_archive();
+
+    archive.append_file("f1").build().unwrap();
+    archive.append_hardlink("l1", "f1").build().unwrap();
+
+    let err = archive.force_remove("f1").unwrap_err();
+    assert!(matches!(err, Error::Referenced(name) if name == "f1"));
+    assert_eq!(names(&mut archive), ["f1", "l1"]);
+
+    // the link itself can be removed, then the target as well
+    archive.remove("l1").unwrap();
+    archive.remove("f1").unwrap();
+    assert!(names(&mut archive).is_empty());
+}
+
+#[test]
+fn attachments() {
+    let mut archive = setup_archive();
+
+    archive.append_file("f1").build().unwrap();
+    archive.append_file("f2").build().unwrap();
+    archive.attach("f1", "a1", b"abc").unwrap();
+    archive.attach("f2", "a2", b"def").unwrap();
+    archive.create_index().unwrap();
+
+    archive.remove("f1").unwrap();
+
+    assert_eq!(names(&mut archive), ["f2"]);
+    assert!(archive.attachments("f1").unwrap().is_empty());
+
+    let attachments = archive.attachments("f2").unwrap();
+    assert_eq!(attachments.len(), 1);
+    assert_eq!(attachments[0].data(), b"def");
+
+    let entry = archive.lookup_indexed("f2").unwrap().unwrap();
+    assert_eq!(entry.name(), "f2");
+}
+
+#[test]
+fn duplicate_indexed() {
+    let mut archive = setup_archive();
+
+    archive
+        .append_file("f")
+        .build()
+        .unwrap()
+        .write_all(b"first")
+        .unwrap();
+    archive.append_file("g").build().unwrap();
+    archive
+        .append_file("f")
+        .build()
+        .unwrap()
+        .write_all(b"second")
+        .unwrap();
+    archive.create_index().unwrap();
+
+    archive.remove("f").unwrap();
+
+    let mut entry = archive
+        .lookup_indexed("f")
+        .unwrap()
+        .unwrap()
+        .into_file()
+        .unwrap();
+    assert_eq!(entry.read_vec().unwrap(), b"second");
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    assert_eq!(archive.lookup_indexed("g").unwrap().unwrap().name(), "g");
+
+    let mut entry = archive
+        .lookup_indexed("f")
+        .unwrap()
+        .unwrap()
+        .into_file()
+        .unwrap();
+    assert_eq!(entry.read_vec().unwrap(), b"second");
+}
@@ -0,0 +1,164 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::{Archive, Error};
+
+use common::setup_archive;
+
+#[test]
+fn rename() {
+    let mut archive = setup_archive();
+
+    archive
+        .append_file("f1")
+        .build()
+        .unwrap()
+        .write_all(b"foo")
+        .unwrap();
+    archive.append_file("f2").build().unwrap();
+
+    archive.rename("f1", "f3").unwrap();
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    assert!(archive.lookup("f1").is_none());
+
+    let entry = archive.first().unwrap().unwrap();
+    assert_eq!(entry.name(), "f3");
+    assert!(entry.previous_names().is_empty());
+    assert_eq!(entry.into_file().unwrap().read_vec().unwrap(), b"foo");
+
+    assert_eq!(archive.info().files, 2);
+}
+
+#[test]
+fn not_found() {
+    let mut archive = setup_archive();
+
+    let err = archive.rename("f1", "f2").unwrap_err();
+    assert!(matches!(err, Error::NotFound(ref name) if name == "f1"));
+}
+
+#[test]
+fn immutable() {
+    let mut archive = setup_archive();
+
+    archive.append_file("f1").build().unwrap();
+    archive.set_immutable("f1", true).unwrap();
+
+    let err = archive.rename("f1", "f2").unwrap_err();
+    assert!(matches!(err, Error::Immutable(ref name) if name == "f1"));
+}
+
+#[test]
+fn long_name() {
+    let mut archive = setup_archive();
+    let long = "x".repeat(1000);
+
+    let mut entry = archive.append_file("f1").build().unwrap();
+    entry.write_all(b"123").unwrap();
+    archive.append_file("f2").build().unwrap();
+
+    archive.rename("f1", &long).unwrap();
+
+    let mut entry = archive.lookup(&long).unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), b"123");
+    assert!(archive.lookup("f2").is_some());
+
+    archive.rename(&long, "f1").unwrap();
+
+    let mut entry = archive.lookup("f1").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), b"123");
+    assert!(archive.lookup("f2").is_some());
+    assert_eq!(archive.info().blocks, 3);
+}
+
+#[test]
+fn children() {
+    let mut archive = setup_archive();
+
+    archive.append_directory("d1").build().unwrap();
+    archive.append_directory("d2").build().unwrap();
+    archive.append_file("d1/f").build().unwrap();
+
+    archive.rename("d1/f", "d2/f").unwrap();
+
+    let d1 = archive.lookup("d1").unwrap().unwrap();
+    assert_eq!(d1.into_directory().unwrap().children(), 0);
+
+    let d2 = archive.lookup("d2").unwrap().unwrap();
+    assert_eq!(d2.into_directory().unwrap().children(), 1);
+}
+
+#[test]
+fn indexed() {
+    let mut archive = setup_archive();
+
+    archive.create_index().unwrap();
+    archive.append_file("f1").build().unwrap();
+
+    archive.rename("f1", "f2").unwrap();
+
+    assert!(archive.lookup_indexed("f1").is_none());
+    assert_eq!(archive.lookup_indexed("f2").unwrap().unwrap().name(), "f2");
+}
+
+#[test]
+fn history() {
+    let mut archive = setup_archive();
+
+    archive.set_rename_history(2);
+    archive.append_file("f1").build().unwrap();
+
+    archive.rename("f1", "f2").unwrap();
+    archive.rename("f2", "f3").unwrap();
+    archive.rename("f3", "f4").unwrap();
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    let entry = archive.lookup("f4").unwrap().unwrap();
+    assert_eq!(entry.previous_names(), ["f2", "f3"]);
+
+    assert!(archive.lookup_renamed("f1").is_none());
+
+    let entry = archive.lookup_renamed("f2").unwrap().unwrap();
+    assert_eq!(entry.name(), "f4");
+
+    let entry = archive.lookup_renamed("f4").unwrap().unwrap();
+    assert_eq!(entry.name(), "f4");
+}
+
+#[test]
+fn lookup_renamed_prefers_name() {
+    let mut archive = setup_archive();
+
+    archive.set_rename_history(1);
+    archive.append_file("f1").build().unwrap();
+    archive.rename("f1", "f2").unwrap();
+    archive.append_file("f1").build().unwrap();
+
+    let entry = archive.lookup_renamed("f1").unwrap().unwrap();
+    assert_eq!(entry.name(), "f1");
+    assert!(entry.previous_names().is_empty());
+}
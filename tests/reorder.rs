@@ -0,0 +1,191 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::{Archive, Entry, Error};
+use nuts_container::memory::MemoryBackend;
+
+use common::setup_archive;
+
+fn append_file(archive: &mut Archive<MemoryBackend>, name: &str, content: &[u8]) {
+    archive
+        .append_file(name)
+        .build()
+        .unwrap()
+        .write_all(content)
+        .unwrap();
+}
+
+fn entries(archive: &mut Archive<MemoryBackend>) -> Vec<(String, Vec<u8>)> {
+    let mut entries = vec![];
+    let mut entry_opt = archive.first();
+
+    while let Some(result) = entry_opt {
+        let mut entry = result.unwrap();
+        let name = entry.name().to_string();
+
+        match &mut entry {
+            Entry::File(file) => entries.push((name, file.read_vec().unwrap())),
+            _ => panic!("not a file: {}", name),
+        }
+
+        entry_opt = entry.next();
+    }
+
+    entries
+}
+
+fn expected(entries: &[(&str, &[u8])]) -> Vec<(String, Vec<u8>)> {
+    entries
+        .iter()
+        .map(|(name, content)| (name.to_string(), content.to_vec()))
+        .collect()
+}
+
+fn setup_files(archive: &mut Archive<MemoryBackend>) {
+    append_file(archive, "f1", b"foo");
+    append_file(archive, "f2", &[2; 1200]);
+    append_file(archive, "f3", b"");
+    append_file(archive, "f4", &[4; 600]);
+}
+
+#[test]
+fn reorder() {
+    let mut archive = setup_archive();
+
+    setup_files(&mut archive);
+    archive.reorder(&["f4", "f2"]).unwrap();
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    assert_eq!(
+        entries(&mut archive),
+        expected(&[
+            ("f4", &[4; 600]),
+            ("f2", &[2; 1200]),
+            ("f1", b"foo"),
+            ("f3", b""),
+        ])
+    );
+}
+
+#[test]
+fn unchanged() {
+    let mut archive = setup_archive();
+
+    setup_files(&mut archive);
+    archive.reorder(&["f1", "f2"]).unwrap();
+    archive.reorder(&[] as &[&str]).unwrap();
+
+    assert_eq!(
+        entries(&mut archive),
+        expected(&[
+            ("f1", b"foo"),
+            ("f2", &[2; 1200]),
+            ("f3", b""),
+            ("f4", &[4; 600]),
+        ])
+    );
+}
+
+#[test]
+fn duplicates() {
+    let mut archive = setup_archive();
+
+    append_file(&mut archive, "f", b"1");
+    append_file(&mut archive, "g", b"2");
+    append_file(&mut archive, "f", b"3");
+
+    archive.reorder(&["g", "f", "f"]).unwrap();
+
+    assert_eq!(
+        entries(&mut archive),
+        expected(&[("g", b"2"), ("f", b"1"), ("f", b"3")])
+    );
+
+    let err = archive.reorder(&["f", "f", "f"]).unwrap_err();
+    assert!(matches!(err, Error::NotFound(ref name) if name == "f"));
+}
+
+#[test]
+fn not_found() {
+    let mut archive = setup_archive();
+
+    setup_files(&mut archive);
+
+    let err = archive.reorder(&["f4", "xxx"]).unwrap_err();
+    assert!(matches!(err, Error::NotFound(ref name) if name == "xxx"));
+
+    assert_eq!(entries(&mut archive)[0].0, "f1");
+}
+
+#[test]
+fn index() {
+    let mut archive = setup_archive();
+
+    setup_files(&mut archive);
+    archive.create_index().unwrap();
+    archive.reorder(&["f4", "f3"]).unwrap();
+
+    for (name, content) in [("f1", &b"foo"[..]), ("f2", &[2; 1200]), ("f4", &[4; 600])] {
+        let entry = archive.lookup_indexed(name).unwrap().unwrap();
+        assert_eq!(entry.into_file().unwrap().read_vec().unwrap(), content);
+    }
+}
+
+#[test]
+fn attachments() {
+    let mut archive = setup_archive();
+
+    setup_files(&mut archive);
+    archive.attach("f2", "note", b"bar").unwrap();
+    archive.reorder(&["f4"]).unwrap();
+
+    assert_eq!(entries(&mut archive)[0].0, "f4");
+
+    let attachments = archive.attachments("f2").unwrap();
+    assert_eq!(attachments.len(), 1);
+    assert_eq!(attachments[0].data(), b"bar");
+}
+
+#[test]
+fn savepoints() {
+    let mut archive = setup_archive();
+
+    setup_files(&mut archive);
+    archive.savepoint("s");
+    archive.reorder(&["f4"]).unwrap();
+
+    assert!(archive.savepoints().is_empty());
+}
+
+#[test]
+fn frozen() {
+    let mut archive = setup_archive();
+
+    setup_files(&mut archive);
+    archive.freeze(b"token").unwrap();
+
+    let err = archive.reorder(&["f4"]).unwrap_err();
+    assert!(matches!(err, Error::Frozen));
+}
@@ -0,0 +1,164 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use chrono::{TimeZone, Utc};
+use nuts_archive::{Archive, Group, RewriteError};
+use nuts_container::memory::MemoryBackend;
+
+use common::setup_container_with_bsize;
+
+/// Returns the number of blocks allocated in the backend of the archive.
+fn allocated(archive: Archive<MemoryBackend>) -> u32 {
+    let mut backend = archive.into_container().into_backend();
+    let id = backend.insert().unwrap();
+
+    id.to_string().parse::<u32>().unwrap() - 1
+}
+
+#[test]
+fn empty() {
+    let mut archive = Archive::create(setup_container_with_bsize(512), false).unwrap();
+    let mut target = archive.rewrite_to(setup_container_with_bsize(128)).unwrap();
+
+    assert_eq!(target.info().files, 0);
+    assert!(target.first().is_none());
+}
+
+#[test]
+fn entries() {
+    let content = (0..1000).map(|n| n as u8).collect::<Vec<u8>>();
+    let mut archive = Archive::create(setup_container_with_bsize(512), false).unwrap();
+
+    {
+        let mut builder = archive.append_file("f1");
+        builder.set_modified(Utc.timestamp_millis_opt(4711).unwrap());
+        builder.set_executable(Group::Other, false);
+
+        let mut entry = builder.build().unwrap();
+        entry.write_all(&content).unwrap();
+    }
+
+    archive.append_directory("d1").build().unwrap();
+    archive.append_symlink("s1", "f1").build().unwrap();
+    archive.append_file("f2").build().unwrap();
+
+    let mut target = archive.rewrite_to(setup_container_with_bsize(128)).unwrap();
+
+    assert_eq!(target.info().files, 4);
+
+    let entry = target.first().unwrap().unwrap();
+    assert_eq!(entry.name(), "f1");
+    assert_eq!(entry.size(), 1000);
+    assert_eq!(entry.modified(), &Utc.timestamp_millis_opt(4711).unwrap());
+    assert!(entry.can_execute(Group::User));
+    assert!(!entry.can_execute(Group::Other));
+
+    let mut file = entry.into_file().unwrap();
+    assert_eq!(file.read_vec().unwrap(), content);
+
+    let entry = target.first().unwrap().unwrap();
+    let entry = entry.next().unwrap().unwrap();
+    assert!(entry.is_directory());
+    assert_eq!(entry.name(), "d1");
+
+    let entry = entry.next().unwrap().unwrap();
+    assert_eq!(entry.into_symlink().unwrap().target(), "f1");
+
+    let entry = target.lookup("f2").unwrap().unwrap();
+    assert!(entry.is_file());
+    assert_eq!(entry.size(), 0);
+    assert!(entry.next().is_none());
+}
+
+#[test]
+fn invalid_block_size() {
+    let mut archive = Archive::create(setup_container_with_bsize(512), false).unwrap();
+    let result = archive.rewrite_to(setup_container_with_bsize(16));
+
+    assert!(matches!(
+        result,
+        Err(RewriteError::Target(
+            nuts_archive::Error::BlockSizeTooSmall(16, 106)
+        ))
+    ));
+}
+
+#[test]
+fn num_direct() {
+    let container = setup_container_with_bsize(512);
+    let mut archive = Archive::create_with_num_direct(container, false, 2).unwrap();
+
+    archive.append_file("f1").build().unwrap();
+
+    let target = archive.rewrite_to(setup_container_with_bsize(256)).unwrap();
+    assert_eq!(target.info().num_direct, 2);
+}
+
+#[test]
+fn index_and_freeze() {
+    let mut archive = Archive::create(setup_container_with_bsize(512), false).unwrap();
+
+    archive.append_file("f1").build().unwrap();
+    archive.append_file("f2").build().unwrap();
+    archive.create_index().unwrap();
+    archive.freeze("token").unwrap();
+
+    let target = archive.rewrite_to(setup_container_with_bsize(256)).unwrap();
+    let mut target = Archive::open(target.into_container()).unwrap();
+
+    assert!(target.is_frozen());
+    assert!(target.has_index());
+    assert!(target.lookup_indexed("f2").is_some());
+
+    target.unfreeze("token").unwrap();
+    target.append_file("f3").build().unwrap();
+    assert_eq!(target.info().files, 3);
+}
+
+#[test]
+fn sparse() {
+    let mut archive = Archive::create(setup_container_with_bsize(512), false).unwrap();
+
+    {
+        let mut entry = archive.append_file("f1").build().unwrap();
+
+        entry.write_all(&[1; 512]).unwrap();
+        entry.write_zeroes(100 * 512).unwrap();
+        entry.write_all(&[2; 100]).unwrap();
+    }
+
+    let mut target = archive.rewrite_to(setup_container_with_bsize(512)).unwrap();
+
+    let entry = target.lookup("f1").unwrap().unwrap();
+    let mut file = entry.into_file().unwrap();
+    let mut expected = vec![1; 512];
+
+    expected.resize(101 * 512, 0);
+    expected.extend_from_slice(&[2; 100]);
+
+    assert_eq!(file.read_vec().unwrap(), expected);
+
+    // the 100 blocks of zeros are holes again
+    assert_eq!(allocated(target), 6);
+}
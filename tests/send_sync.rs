@@ -0,0 +1,48 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+use nuts_archive::{
+    ArchiveSummary, Error, ExtractPlan, Index, IndexEntry, Info, JobRecord, Manifest,
+    ManifestEntry, ParseModeError, TargetError,
+};
+use nuts_container::memory::MemoryBackend;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn owned_types() {
+    assert_send_sync::<ArchiveSummary>();
+    assert_send_sync::<ExtractPlan>();
+    assert_send_sync::<Index>();
+    assert_send_sync::<IndexEntry>();
+    assert_send_sync::<Info>();
+    assert_send_sync::<JobRecord>();
+    assert_send_sync::<Manifest>();
+    assert_send_sync::<ManifestEntry>();
+    assert_send_sync::<ParseModeError>();
+    assert_send_sync::<TargetError>();
+}
+
+#[test]
+fn error() {
+    assert_send_sync::<Error<MemoryBackend>>();
+}
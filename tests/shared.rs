@@ -0,0 +1,120 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::{Archive, Error};
+use nuts_container::container::Container;
+use nuts_container::memory::{Id, MemoryBackend};
+
+use common::setup_container;
+
+/// Simulates another payload, which keeps its own blocks and remembers the
+/// anchor of the archive in the userdata of the container.
+fn other_payload(container: &mut Container<MemoryBackend>, anchor: &Id) -> Vec<Id> {
+    let ids = (0..3)
+        .map(|n| {
+            let id = container.aquire().unwrap();
+            container.write(&id, &[n + 1; 512]).unwrap();
+            id
+        })
+        .collect();
+
+    container
+        .update_userdata(anchor.to_string().as_bytes())
+        .unwrap();
+
+    ids
+}
+
+#[test]
+fn anchor() {
+    let archive = Archive::create(setup_container(), false).unwrap();
+    assert!(archive.anchor().is_none());
+
+    let archive = Archive::create_anchored(setup_container()).unwrap();
+    assert!(archive.anchor().is_some());
+}
+
+#[test]
+fn userdata_untouched() {
+    let mut container = setup_container();
+
+    container.update_userdata(b"other").unwrap();
+
+    let mut archive = Archive::create_anchored(container).unwrap();
+
+    archive.append_file("f1").build().unwrap();
+    archive.freeze("token").unwrap();
+    archive.unfreeze("token").unwrap();
+
+    let container = archive.into_container();
+    assert_eq!(container.userdata(), b"other");
+    assert!(Archive::open(container).is_err());
+}
+
+#[test]
+fn coexist() {
+    let mut archive = Archive::create_anchored(setup_container()).unwrap();
+    let anchor = *archive.anchor().unwrap();
+
+    let mut entry = archive.append_file("f1").build().unwrap();
+    entry.write_all(&[1; 1000]).unwrap();
+
+    let mut container = archive.into_container();
+    let ids = other_payload(&mut container, &anchor);
+
+    let anchor = std::str::from_utf8(container.userdata())
+        .unwrap()
+        .parse::<Id>()
+        .unwrap();
+    let mut archive = Archive::open_anchored(container, &anchor).unwrap();
+
+    let mut entry = archive.append_file("f2").build().unwrap();
+    entry.write_all(&[2; 1000]).unwrap();
+
+    archive.remove("f1").unwrap();
+
+    let mut entry = archive.lookup("f2").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), [2; 1000]);
+
+    let mut container = archive.into_container();
+    let summary = Archive::peek_anchored(&mut container, &anchor).unwrap();
+    assert_eq!(summary.files, 1);
+
+    // the blocks of the other payload are not touched
+    for (n, id) in ids.iter().enumerate() {
+        let mut buf = [0; 512];
+
+        container.read(id, &mut buf).unwrap();
+        assert_eq!(buf, [n as u8 + 1; 512]);
+    }
+}
+
+#[test]
+fn open_no_anchor() {
+    let mut container = setup_container();
+    let id = container.aquire().unwrap();
+
+    let err = Archive::open_anchored(container, &id).err().unwrap();
+    assert!(matches!(err, Error::InvalidUserdata(None)));
+}
@@ -0,0 +1,205 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::{Archive, Compression};
+use nuts_container::memory::MemoryBackend;
+use sha2::{Digest, Sha256};
+use std::io::SeekFrom;
+
+use common::setup_archive;
+
+/// Returns the number of blocks allocated in the backend of the archive.
+fn allocated(archive: Archive<MemoryBackend>) -> u32 {
+    let mut backend = archive.into_container().into_backend();
+    let id = backend.insert().unwrap();
+
+    id.to_string().parse::<u32>().unwrap() - 1
+}
+
+fn expected() -> Vec<u8> {
+    let mut buf = b"abc".to_vec();
+
+    buf.resize(3 + 5000, 0);
+    buf.extend_from_slice(b"xyz");
+
+    buf
+}
+
+#[test]
+fn write_zeroes() {
+    let mut archive = setup_archive();
+
+    let mut entry = archive.append_file("f1").build().unwrap();
+    entry.write_all(b"abc").unwrap();
+    entry.write_zeroes(5000).unwrap();
+    entry.write_all(b"xyz").unwrap();
+    archive.append_file("f2").build().unwrap();
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    let mut entry = archive.lookup("f1").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.size(), 5006);
+    assert_eq!(entry.read_vec().unwrap(), expected());
+
+    let mut buf = [1; 600];
+    assert_eq!(entry.read_at(1000, &mut buf).unwrap(), 600);
+    assert_eq!(buf, [0; 600]);
+
+    assert_eq!(entry.seek(SeekFrom::Start(5001)).unwrap(), 5001);
+    assert_eq!(entry.read_vec().unwrap(), b"\0\0xyz");
+
+    let entry = archive.first().unwrap().unwrap();
+    let entry = entry.next().unwrap().unwrap();
+    assert_eq!(entry.name(), "f2");
+}
+
+#[test]
+fn not_allocated() {
+    let mut sparse = setup_archive();
+
+    let mut entry = sparse.append_file("f").build().unwrap();
+    entry.write_all(b"abc").unwrap();
+    entry.write_zeroes(5000).unwrap();
+    entry.write_all(b"xyz").unwrap();
+
+    let mut dense = setup_archive();
+    dense
+        .append_file("f")
+        .with_content(&expected())
+        .build()
+        .unwrap();
+
+    assert_eq!(sparse.info().blocks, dense.info().blocks);
+
+    // 512 - 3 zeros fill the first block, the next 8 blocks are holes
+    assert_eq!(allocated(dense) - allocated(sparse), 8);
+}
+
+#[test]
+fn set_len() {
+    let mut archive = setup_archive();
+
+    let mut entry = archive.append_file("f").build().unwrap();
+    entry.write_all(b"abc").unwrap();
+    entry.set_len(5003).unwrap();
+    entry.write_all(b"xyz").unwrap();
+
+    // cut off in the middle of a hole, the partial block is allocated
+    entry.set_len(2000).unwrap();
+    entry.write_all(b"x").unwrap();
+
+    let mut expected = expected();
+    expected.truncate(2000);
+    expected.push(b'x');
+
+    let mut entry = archive.lookup("f").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), expected);
+}
+
+#[test]
+fn write_at() {
+    let mut archive = setup_archive();
+
+    let mut entry = archive.append_file("f").build().unwrap();
+    entry.write_all(b"abc").unwrap();
+    entry.write_zeroes(5000).unwrap();
+    entry.write_all(b"xyz").unwrap();
+
+    assert_eq!(entry.write_at(2000, b"hole").unwrap(), 4);
+
+    let mut expected = expected();
+    expected[2000..2004].copy_from_slice(b"hole");
+
+    let mut entry = archive.lookup("f").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), expected);
+}
+
+#[test]
+fn ends_with_hole() {
+    let mut archive = setup_archive();
+
+    let mut entry = archive.append_file("f").build().unwrap();
+    entry.write_zeroes(1024).unwrap();
+
+    let mut entry = archive.open_mut("f").unwrap();
+    entry.write_all(b"abc").unwrap();
+
+    let mut expected = vec![0; 1024];
+    expected.extend_from_slice(b"abc");
+
+    let mut entry = archive.lookup("f").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), expected);
+}
+
+#[test]
+fn remove() {
+    let mut archive = setup_archive();
+
+    let mut entry = archive.append_file("f1").build().unwrap();
+    entry.write_all(b"abc").unwrap();
+    entry.write_zeroes(5000).unwrap();
+    entry.write_all(b"xyz").unwrap();
+    archive
+        .append_file("f2")
+        .with_content(b"content")
+        .build()
+        .unwrap();
+
+    archive.remove("f1").unwrap();
+
+    let mut entry = archive.lookup("f2").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), b"content");
+}
+
+#[test]
+fn hashed() {
+    let mut archive = setup_archive();
+
+    let mut builder = archive.append_file("f");
+    builder.set_hashed(true);
+
+    let mut entry = builder.build().unwrap();
+    entry.write_all(b"abc").unwrap();
+    entry.write_zeroes(5000).unwrap();
+    entry.write_all(b"xyz").unwrap();
+
+    let expected: [u8; 32] = Sha256::digest(expected()).into();
+    assert_eq!(entry.current_hash(), Some(expected));
+}
+
+#[test]
+fn compressed() {
+    let mut archive = setup_archive();
+
+    let mut builder = archive.append_file("f");
+    builder.set_compression(Compression::Deflate);
+
+    let mut entry = builder.build().unwrap();
+    entry.write_all(b"abc").unwrap();
+    entry.write_zeroes(5000).unwrap();
+    entry.write_all(b"xyz").unwrap();
+
+    let mut entry = archive.lookup("f").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), expected());
+}
@@ -0,0 +1,127 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::{Archive, EntryStreamReader, EntryStreamWriter, Index, SpecialKind};
+use nuts_container::memory::MemoryBackend;
+
+use common::setup_archive;
+
+const KINDS: [(&str, SpecialKind, &str, u32); 4] = [
+    ("fifo", SpecialKind::Fifo, "prw-r--r--", 0o010644),
+    (
+        "null",
+        SpecialKind::CharDevice { major: 1, minor: 3 },
+        "crw-r--r--",
+        0o020644,
+    ),
+    (
+        "sda",
+        SpecialKind::BlockDevice { major: 8, minor: 0 },
+        "brw-r--r--",
+        0o060644,
+    ),
+    ("sock", SpecialKind::Socket, "srw-r--r--", 0o140644),
+];
+
+fn setup_special() -> Archive<MemoryBackend> {
+    let mut archive = setup_archive();
+
+    for (name, kind, _, _) in KINDS {
+        let mut builder = archive.append_special(name, kind);
+        builder.set_unix_mode(0o644);
+        builder.build().unwrap();
+    }
+
+    archive
+}
+
+#[test]
+fn entries() {
+    let archive = setup_special();
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    assert_eq!(archive.info().files, 4);
+
+    for (name, kind, rwx, unix) in KINDS {
+        let entry = archive.lookup(name).unwrap().unwrap();
+
+        assert!(entry.is_special());
+        assert!(!entry.is_file());
+        assert_eq!(entry.to_string_rwx(), rwx);
+        assert_eq!(entry.unix_mode(), unix);
+
+        let special = entry.into_special().unwrap();
+        assert_eq!(special.name(), name);
+        assert_eq!(special.kind(), kind);
+    }
+}
+
+#[test]
+fn index() {
+    let mut archive = setup_special();
+
+    let mut buf = vec![];
+    archive.export_index(&mut buf).unwrap();
+
+    let index = Index::import(buf.as_slice()).unwrap();
+
+    for (name, _, _, unix) in KINDS {
+        let entry = index.lookup(name).unwrap();
+
+        assert!(entry.is_special());
+        assert_eq!(entry.unix_mode(), unix);
+    }
+}
+
+#[test]
+fn stream() {
+    let mut archive = setup_special();
+    let mut writer = EntryStreamWriter::new(vec![]).unwrap();
+    let mut entry_opt = archive.first();
+
+    while let Some(result) = entry_opt {
+        let mut entry = result.unwrap();
+
+        writer.write_entry(&mut entry).unwrap();
+        entry_opt = entry.next();
+    }
+
+    let buf = writer.finish().unwrap();
+    let mut target = setup_archive();
+
+    EntryStreamReader::new(buf.as_slice())
+        .unwrap()
+        .import(&mut target)
+        .unwrap();
+
+    for (name, kind, _, _) in KINDS {
+        let special = target
+            .lookup(name)
+            .unwrap()
+            .unwrap()
+            .into_special()
+            .unwrap();
+        assert_eq!(special.kind(), kind);
+    }
+}
@@ -0,0 +1,58 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use chrono::{TimeZone, Utc};
+use nuts_archive::Archive;
+
+use common::setup_archive;
+
+#[test]
+fn round_trip() {
+    let created = Utc.timestamp_millis_opt(1_000_000_001_001).unwrap();
+    let changed = Utc.timestamp_millis_opt(1_000_000_002_002).unwrap();
+    let modified = Utc.timestamp_millis_opt(1_000_000_003_003).unwrap();
+
+    let mut archive = setup_archive();
+
+    let mut builder = archive.append_file("f1");
+    builder.set_created(created);
+    builder.set_changed(changed);
+    builder.set_modified(modified);
+    builder.build().unwrap().write_all(b"abc").unwrap();
+
+    let mut builder = archive.append_directory("d1");
+    builder.set_modified(modified);
+    builder.build().unwrap();
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    let entry = archive.lookup("f1").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(*entry.created(), created);
+    assert_eq!(*entry.changed(), changed);
+    assert_eq!(*entry.modified(), modified);
+    assert!(*entry.appended() > modified);
+
+    let entry = archive.lookup("d1").unwrap().unwrap();
+    assert_eq!(*entry.modified(), modified);
+}
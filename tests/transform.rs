@@ -0,0 +1,334 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::{Archive, Compression, Error, FileBuilder, Transform};
+use nuts_container::memory::MemoryBackend;
+use sha2::{Digest, Sha256};
+use std::io::{self, SeekFrom};
+
+use common::setup_container_with_bsize;
+
+/// Adds a checksum to each chunk and flips the bits of the content.
+struct Seal;
+
+impl Transform for Seal {
+    fn name(&self) -> &str {
+        "seal"
+    }
+
+    fn encode(&self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        let sum = chunk.iter().fold(0u8, |acc, b| acc.wrapping_add(*b));
+
+        Ok(chunk.iter().map(|b| !b).chain([sum]).collect())
+    }
+
+    fn decode(&self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        let (sum, data) = chunk
+            .split_last()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "empty chunk"))?;
+        let data = data.iter().map(|b| !b).collect::<Vec<_>>();
+
+        if data.iter().fold(0u8, |acc, b| acc.wrapping_add(*b)) == *sum {
+            Ok(data)
+        } else {
+            Err(io::Error::new(io::ErrorKind::InvalidData, "bad checksum"))
+        }
+    }
+}
+
+/// Replaces the digits of the content with `#`.
+struct Redact;
+
+impl Transform for Redact {
+    fn name(&self) -> &str {
+        "redact"
+    }
+
+    fn encode(&self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        Ok(chunk
+            .iter()
+            .map(|b| if b.is_ascii_digit() { b'#' } else { *b })
+            .collect())
+    }
+
+    fn decode(&self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        Ok(chunk.to_vec())
+    }
+}
+
+fn setup_archive() -> Archive<MemoryBackend> {
+    let mut archive = common::setup_archive();
+
+    archive.register_transform(Seal);
+    archive.register_transform(Redact);
+
+    archive
+}
+
+fn content() -> Vec<u8> {
+    (0..1000)
+        .flat_map(|n| format!("line {} of a text-heavy backup\n", n % 50).into_bytes())
+        .collect()
+}
+
+fn sealed(builder: &mut FileBuilder<MemoryBackend>) {
+    builder.add_transform("seal");
+}
+
+#[test]
+fn round_trip() {
+    let mut archive = setup_archive();
+    let content = content();
+
+    let mut builder = archive.append_file("f1");
+    sealed(&mut builder);
+
+    let mut entry = builder.build().unwrap();
+
+    for chunk in content.chunks(700) {
+        entry.write_all(chunk).unwrap();
+    }
+
+    archive.append_file("f2").build().unwrap();
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+    archive.register_transform(Seal);
+
+    let mut entry = archive.lookup("f1").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.size(), content.len() as u64);
+    assert_eq!(entry.read_vec().unwrap(), content);
+
+    let entry = archive.first().unwrap().unwrap();
+    let entry = entry.next().unwrap().unwrap();
+    assert_eq!(entry.name(), "f2");
+}
+
+#[test]
+fn redact() {
+    let mut archive = setup_archive();
+
+    let mut builder = archive.append_file("f");
+    builder.add_transform("redact");
+    builder.with_content(b"pin 1234, tan 5678").build().unwrap();
+
+    let mut entry = archive.lookup("f").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.size(), 18);
+    assert_eq!(entry.read_vec().unwrap(), b"pin ####, tan ####");
+}
+
+#[test]
+fn compressed() {
+    let mut archive = setup_archive();
+    let content = content();
+
+    let mut builder = archive.append_file("f");
+    builder.set_compression(Compression::Deflate);
+    sealed(&mut builder);
+
+    let mut entry = builder.build().unwrap();
+
+    for chunk in content.chunks(2000) {
+        entry.write_all(chunk).unwrap();
+    }
+
+    // the transformation sees the compressed content
+    assert!(archive.info().blocks < 10);
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+    archive.register_transform(Seal);
+
+    let mut entry = archive.lookup("f").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.size(), content.len() as u64);
+    assert_eq!(entry.read_vec().unwrap(), content);
+}
+
+#[test]
+fn seek_and_read_at() {
+    let mut archive = setup_archive();
+    let content = content();
+
+    let mut builder = archive.append_file("f");
+    sealed(&mut builder);
+
+    let mut entry = builder.build().unwrap();
+
+    for chunk in content.chunks(1000) {
+        entry.write_all(chunk).unwrap();
+    }
+
+    let mut entry = archive.lookup("f").unwrap().unwrap().into_file().unwrap();
+    let mut buf = [0; 100];
+
+    assert_eq!(entry.seek(SeekFrom::Start(20000)).unwrap(), 20000);
+    entry.read_all(&mut buf).unwrap();
+    assert_eq!(buf, content[20000..20100]);
+
+    assert_eq!(entry.seek(SeekFrom::Start(100)).unwrap(), 100);
+    entry.read_all(&mut buf).unwrap();
+    assert_eq!(buf, content[100..200]);
+
+    assert_eq!(entry.read_at(30000, &mut buf).unwrap(), 100);
+    assert_eq!(buf, content[30000..30100]);
+
+    let end = content.len() as u64;
+    assert_eq!(entry.seek(SeekFrom::End(-10)).unwrap(), end - 10);
+    assert_eq!(entry.read_vec().unwrap(), content[content.len() - 10..]);
+}
+
+#[test]
+fn hashed() {
+    let mut archive = setup_archive();
+    let content = content();
+
+    let mut builder = archive.append_file("f");
+    sealed(&mut builder);
+    builder.set_hashed(true);
+
+    let mut entry = builder.build().unwrap();
+    entry.write_all(&content).unwrap();
+
+    let expected: [u8; 32] = Sha256::digest(&content).into();
+    assert_eq!(entry.current_hash(), Some(expected));
+}
+
+#[test]
+fn append() {
+    let mut archive = setup_archive();
+    let content = content();
+    let (first, second) = content.split_at(10000);
+
+    let mut builder = archive.append_file("f");
+    sealed(&mut builder);
+    builder.with_content(first).build().unwrap();
+
+    let mut entry = archive.open_mut("f").unwrap();
+    entry.write_all(second).unwrap();
+
+    let mut entry = archive.lookup("f").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.size(), content.len() as u64);
+    assert_eq!(entry.read_vec().unwrap(), content);
+}
+
+#[test]
+fn unknown() {
+    let mut archive = setup_archive();
+
+    let mut builder = archive.append_file("f");
+    builder.add_transform("encrypt");
+
+    let err = builder.build().err().unwrap();
+    assert!(matches!(err, Error::UnknownTransform(ref name) if name == "encrypt"));
+    assert_eq!(archive.info().blocks, 0);
+
+    let mut builder = archive.append_file("f");
+    sealed(&mut builder);
+    builder.with_content(b"content").build().unwrap();
+
+    // the registration is not persisted
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    let mut entry = archive.lookup("f").unwrap().unwrap().into_file().unwrap();
+    let err = entry.read_vec().unwrap_err();
+    assert!(matches!(err, Error::UnknownTransform(ref name) if name == "seal"));
+
+    let err = archive.open_mut("f").err().unwrap();
+    assert!(matches!(err, Error::UnknownTransform(ref name) if name == "seal"));
+
+    archive.register_transform(Seal);
+
+    let mut entry = archive.lookup("f").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), b"content");
+}
+
+#[test]
+fn random_access_write() {
+    let mut archive = setup_archive();
+
+    let mut builder = archive.append_file("f");
+    sealed(&mut builder);
+
+    let mut entry = builder.build().unwrap();
+    entry.write_all(b"content").unwrap();
+
+    let err = entry.write_at(0, b"x").unwrap_err();
+    assert!(matches!(err, Error::Transformed(ref name) if name == "f"));
+
+    let err = entry.set_len(1).unwrap_err();
+    assert!(matches!(err, Error::Transformed(ref name) if name == "f"));
+
+    let err = archive.truncate("f", 1).unwrap_err();
+    assert!(matches!(err, Error::Transformed(ref name) if name == "f"));
+
+    let mut builder = archive.append_file("g");
+    sealed(&mut builder);
+
+    let result = builder.build_multipart(10);
+    assert!(matches!(result, Err(Error::Transformed(ref name)) if name == "g"));
+
+    let mut entry = archive.lookup("f").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), b"content");
+}
+
+#[test]
+fn rewrite() {
+    let mut archive = setup_archive();
+    let content = content();
+
+    let mut builder = archive.append_file("f");
+    sealed(&mut builder);
+    builder.with_content(&content).build().unwrap();
+
+    let container = setup_container_with_bsize(1024);
+    let mut target = archive.rewrite_to(container).ok().unwrap();
+
+    let mut entry = target.lookup("f").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), content);
+}
+
+#[test]
+fn full() {
+    let container = setup_container_with_bsize(106);
+    let mut archive = Archive::create(container, false).unwrap();
+
+    archive.register_transform(Seal);
+
+    // leaves less than 200 blocks for content
+    archive.set_reserved_capacity(99);
+
+    let content = content();
+
+    let mut builder = archive.append_file("f");
+    sealed(&mut builder);
+
+    let mut entry = builder.build().unwrap();
+    entry.write_all(&content[..10000]).unwrap();
+
+    let err = entry.write_all(&content[10000..]).unwrap_err();
+    assert!(matches!(err, Error::Full(0)));
+
+    // the transformed content is cut off behind the last successful write
+    let mut entry = archive.lookup("f").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(entry.size(), 10000);
+    assert_eq!(entry.read_vec().unwrap(), content[..10000]);
+}
@@ -0,0 +1,319 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::{Archive, Error};
+use nuts_container::memory::MemoryBackend;
+
+fn setup_archive() -> Archive<MemoryBackend> {
+    let mut archive = common::setup_archive();
+
+    archive.set_trash(true);
+    archive
+}
+
+fn names(archive: &mut Archive<MemoryBackend>) -> Vec<String> {
+    let mut names = vec![];
+    let mut entry_opt = archive.first();
+
+    while let Some(entry) = entry_opt {
+        let entry = entry.unwrap();
+
+        names.push(entry.name().to_string());
+        entry_opt = entry.next();
+    }
+
+    names
+}
+
+#[test]
+fn undelete() {
+    let mut archive = setup_archive();
+
+    archive.append_directory("d").build().unwrap();
+    archive
+        .append_file("d/f1")
+        .with_content(&[1; 1000])
+        .build()
+        .unwrap();
+    archive.append_file("f2").build().unwrap();
+    archive.attach("d/f1", "note", b"xyz").unwrap();
+    archive.create_index().unwrap();
+
+    archive.remove("d/f1").unwrap();
+
+    assert_eq!(names(&mut archive), ["d", "f2"]);
+    assert_eq!(archive.info().files, 2);
+    assert_eq!(archive.trashed().unwrap(), ["d/f1"]);
+    assert!(archive.lookup("d/f1").is_none());
+    assert!(archive.lookup_indexed("d/f1").is_none());
+
+    let dir = archive
+        .lookup("d")
+        .unwrap()
+        .unwrap()
+        .into_directory()
+        .unwrap();
+    assert_eq!(dir.children(), 0);
+
+    // the trash survives the archive
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+    assert_eq!(archive.trashed().unwrap(), ["d/f1"]);
+
+    archive.undelete("d/f1").unwrap();
+
+    assert_eq!(names(&mut archive), ["d", "d/f1", "f2"]);
+    assert_eq!(archive.info().files, 3);
+    assert!(archive.trashed().unwrap().is_empty());
+    assert!(archive.verify().is_ok());
+
+    let mut file = archive
+        .lookup_indexed("d/f1")
+        .unwrap()
+        .unwrap()
+        .into_file()
+        .unwrap();
+    assert_eq!(file.read_vec().unwrap(), [1; 1000]);
+    assert_eq!(archive.attachments("d/f1").unwrap().len(), 1);
+
+    let dir = archive
+        .lookup("d")
+        .unwrap()
+        .unwrap()
+        .into_directory()
+        .unwrap();
+    assert_eq!(dir.children(), 1);
+}
+
+#[test]
+fn undelete_last() {
+    let mut archive = setup_archive();
+
+    archive
+        .append_file("f1")
+        .with_content(b"1")
+        .build()
+        .unwrap();
+    archive.remove("f1").unwrap();
+    archive
+        .append_file("f1")
+        .with_content(b"2")
+        .build()
+        .unwrap();
+    archive.remove("f1").unwrap();
+    archive
+        .append_file("f1")
+        .with_content(b"3")
+        .build()
+        .unwrap();
+
+    assert_eq!(archive.trashed().unwrap(), ["f1", "f1"]);
+
+    // restored in front of the visible entry
+    archive.undelete("f1").unwrap();
+
+    let mut file = archive.lookup("f1").unwrap().unwrap().into_file().unwrap();
+    assert_eq!(file.read_vec().unwrap(), b"2");
+    assert_eq!(archive.info().files, 2);
+}
+
+#[test]
+fn empty_trash() {
+    let mut archive = setup_archive();
+
+    archive
+        .append_file("f1")
+        .with_content(&[1; 1000])
+        .build()
+        .unwrap();
+    archive.append_file("f2").build().unwrap();
+    archive.attach("f1", "note", b"xyz").unwrap();
+    archive.append_file("f1").build().unwrap();
+    archive.attach("f1", "note", b"abc").unwrap();
+
+    archive.remove("f1").unwrap();
+    archive.remove("f2").unwrap();
+
+    assert_eq!(archive.empty_trash().unwrap(), 2);
+    assert!(archive.trashed().unwrap().is_empty());
+    assert_eq!(names(&mut archive), ["f1"]);
+    assert_eq!(archive.info().files, 1);
+    assert!(archive.verify().is_ok());
+
+    // the attachment of the visible entry is kept
+    let attachments = archive.attachments("f1").unwrap();
+    assert_eq!(attachments.len(), 1);
+    assert_eq!(attachments[0].data(), b"abc");
+
+    assert!(matches!(
+        archive.undelete("f1").unwrap_err(),
+        Error::NotFound(ref name) if name == "f1"
+    ));
+}
+
+#[test]
+fn hardlink_target() {
+    let mut archive = setup_archive();
+
+    archive.append_file("f1").build().unwrap();
+    archive.append_hardlink("l1", "f1").build().unwrap();
+
+    let err = archive.remove("f1").unwrap_err();
+    assert!(matches!(err, Error::Referenced(ref name) if name == "f1"));
+    assert!(archive.trashed().unwrap().is_empty());
+}
+
+#[test]
+fn frozen() {
+    let mut archive = setup_archive();
+
+    archive.append_file("f1").build().unwrap();
+    archive.remove("f1").unwrap();
+    archive.freeze("token").unwrap();
+
+    assert!(matches!(archive.undelete("f1").unwrap_err(), Error::Frozen));
+    assert!(matches!(archive.empty_trash().unwrap_err(), Error::Frozen));
+}
+
+#[test]
+fn evict_when_full() {
+    let mut archive = Archive::create(common::setup_container_with_bsize(128), false).unwrap();
+
+    archive.set_trash(true);
+    archive.set_reserved_capacity(99);
+    archive.create_index().unwrap();
+
+    archive
+        .append_file("f1")
+        .with_content(&[1; 1000])
+        .build()
+        .unwrap();
+
+    let err = archive
+        .append_file("f2")
+        .with_content(&[2; 1 << 16])
+        .build()
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::Full(n) if n > 0 && n < 1 << 16));
+
+    archive.remove("f1").unwrap();
+    archive.remove("f2").unwrap();
+    archive.savepoint("s1");
+    assert_eq!(archive.trashed().unwrap(), ["f1", "f2"]);
+
+    // f1 is evicted first, f2 while the content is written
+    archive
+        .append_file("f3")
+        .with_content(&[3; 2000])
+        .build()
+        .unwrap();
+    assert!(archive.trashed().unwrap().is_empty());
+    assert!(archive.savepoints().is_empty());
+    assert_eq!(names(&mut archive), ["f3"]);
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    let entry = archive.lookup_indexed("f3").unwrap().unwrap();
+    let mut entry = entry.into_file().unwrap();
+    assert_eq!(entry.read_vec().unwrap(), [3; 2000]);
+
+    assert!(archive.verify().is_ok());
+    assert!(archive.check_and_repair().unwrap().is_clean());
+}
+
+#[test]
+fn evict_oldest() {
+    let mut archive = Archive::create(common::setup_container_with_bsize(128), false).unwrap();
+
+    archive.set_trash(true);
+    archive.set_reserved_capacity(99);
+
+    let err = archive
+        .append_file("f1")
+        .with_content(&[1; 1 << 16])
+        .build()
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::Full(_)));
+
+    archive.append_file("f2").build().unwrap();
+    archive.remove("f1").unwrap();
+    archive.remove("f2").unwrap();
+
+    // f2 stays in the trash, there is enough room after evicting f1
+    archive
+        .append_file("f3")
+        .with_content(&[3; 1000])
+        .build()
+        .unwrap();
+    assert_eq!(archive.trashed().unwrap(), ["f2"]);
+    assert_eq!(names(&mut archive), ["f3"]);
+}
+
+#[test]
+fn full_with_empty_trash() {
+    let mut archive = Archive::create(common::setup_container_with_bsize(128), false).unwrap();
+
+    archive.set_trash(true);
+    archive.set_reserved_capacity(99);
+
+    let err = archive
+        .append_file("f1")
+        .with_content(&[1; 1 << 16])
+        .build()
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::Full(_)));
+    assert!(archive.trashed().unwrap().is_empty());
+}
+
+#[test]
+fn no_eviction_in_group() {
+    let mut archive = Archive::create(common::setup_container_with_bsize(128), false).unwrap();
+
+    archive.set_trash(true);
+    archive.set_reserved_capacity(99);
+
+    let err = archive
+        .append_file("f1")
+        .with_content(&[1; 1 << 16])
+        .build()
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::Full(_)));
+
+    archive.remove("f1").unwrap();
+
+    let mut group = archive.append_group().unwrap();
+    let err = group
+        .append_file("f2")
+        .with_content(&[2; 1000])
+        .build()
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::Full(0)));
+    group.rollback().unwrap();
+
+    assert_eq!(archive.trashed().unwrap(), ["f1"]);
+}
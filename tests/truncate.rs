@@ -0,0 +1,184 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::{Archive, Error};
+use nuts_container::memory::MemoryBackend;
+
+use common::setup_archive;
+
+fn content(n: usize) -> Vec<u8> {
+    (0..n).map(|n| (n % 251) as u8).collect()
+}
+
+fn read_content(archive: &mut Archive<MemoryBackend>, name: &str) -> Vec<u8> {
+    let mut entry = archive.lookup(name).unwrap().unwrap().into_file().unwrap();
+    entry.read_vec().unwrap()
+}
+
+#[test]
+fn shrink() {
+    let mut archive = setup_archive();
+
+    for name in ["f1", "f2", "f3"].iter() {
+        let mut entry = archive.append_file(name).build().unwrap();
+        entry.write_all(&content(1300)).unwrap();
+    }
+
+    archive.create_index().unwrap();
+    archive.truncate("f2", 100).unwrap();
+
+    assert_eq!(read_content(&mut archive, "f1"), content(1300));
+    assert_eq!(read_content(&mut archive, "f2"), content(100));
+    assert_eq!(read_content(&mut archive, "f3"), content(1300));
+    assert_eq!(archive.info().blocks, 10);
+
+    let mut entry = archive
+        .lookup_indexed("f3")
+        .unwrap()
+        .unwrap()
+        .into_file()
+        .unwrap();
+    assert_eq!(entry.read_vec().unwrap(), content(1300));
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    assert_eq!(read_content(&mut archive, "f2"), content(100));
+    assert_eq!(read_content(&mut archive, "f3"), content(1300));
+}
+
+#[test]
+fn extend() {
+    let mut archive = setup_archive();
+
+    let mut entry = archive.append_file("f1").build().unwrap();
+    entry.write_all(&content(100)).unwrap();
+
+    archive.truncate("f1", 600).unwrap();
+
+    let mut expected = content(100);
+    expected.resize(600, 0);
+
+    assert_eq!(read_content(&mut archive, "f1"), expected);
+}
+
+#[test]
+fn extend_not_last() {
+    let mut archive = setup_archive();
+
+    let mut entry = archive.append_file("f1").build().unwrap();
+    entry.write_all(&content(100)).unwrap();
+
+    let mut entry = archive.append_file("f2").build().unwrap();
+    entry.write_all(&content(600)).unwrap();
+    entry.write_zeroes(1024).unwrap();
+    entry.write_all(&content(100)).unwrap();
+
+    archive.append_directory("d").build().unwrap();
+    archive.create_index().unwrap();
+
+    // the third block of f2 is a hole
+    assert_eq!(archive.info().blocks, 8);
+
+    archive.truncate("f1", 1300).unwrap();
+    assert_eq!(archive.info().blocks, 10);
+
+    let mut expected = content(100);
+    expected.resize(1300, 0);
+
+    let mut f2 = content(600);
+    f2.resize(1624, 0);
+    f2.extend(content(100));
+
+    assert_eq!(read_content(&mut archive, "f1"), expected);
+    assert_eq!(read_content(&mut archive, "f2"), f2);
+
+    let entry = archive.lookup_indexed("d").unwrap().unwrap();
+    assert!(entry.is_directory());
+
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    assert_eq!(read_content(&mut archive, "f1"), expected);
+    assert_eq!(read_content(&mut archive, "f2"), f2);
+    assert!(archive.verify().is_ok());
+
+    // the holes are removed again
+    archive.truncate("f1", 100).unwrap();
+    assert_eq!(archive.info().blocks, 8);
+    assert_eq!(read_content(&mut archive, "f2"), f2);
+}
+
+#[test]
+fn extend_not_last_full() {
+    let mut archive = setup_archive();
+
+    let mut entry = archive.append_file("f1").build().unwrap();
+    entry.write_all(&content(100)).unwrap();
+    archive.append_file("f2").build().unwrap();
+
+    let err = archive.truncate("f1", 1 << 40).unwrap_err();
+    assert!(matches!(err, Error::Full(0)));
+
+    // content cannot use the reserved capacity
+    archive.set_reserved_capacity(100);
+
+    let err = archive.truncate("f1", 1300).unwrap_err();
+    assert!(matches!(err, Error::Full(0)));
+
+    // nothing changes
+    let mut archive = Archive::open(archive.into_container()).unwrap();
+
+    assert_eq!(archive.info().blocks, 3);
+    assert_eq!(read_content(&mut archive, "f1"), content(100));
+    assert!(archive.lookup("f2").is_some());
+    assert!(archive.verify().is_ok());
+}
+
+#[test]
+fn not_file() {
+    let mut archive = setup_archive();
+
+    archive.append_directory("d1").build().unwrap();
+
+    let err = archive.truncate("d1", 0).unwrap_err();
+    assert!(matches!(err, Error::NotFile(name) if name == "d1"));
+}
+
+#[test]
+fn not_found() {
+    let mut archive = setup_archive();
+
+    let err = archive.truncate("f1", 0).unwrap_err();
+    assert!(matches!(err, Error::NotFound(name) if name == "f1"));
+}
+
+#[test]
+fn frozen() {
+    let mut archive = setup_archive();
+
+    archive.append_file("f1").build().unwrap();
+    archive.freeze("secret").unwrap();
+
+    let err = archive.truncate("f1", 0).unwrap_err();
+    assert!(matches!(err, Error::Frozen));
+}
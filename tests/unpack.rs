@@ -0,0 +1,384 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::{Error, PathPolicy, SpecialKind};
+use std::fs;
+use tempdir::TempDir;
+
+use common::setup_archive;
+
+fn content() -> Vec<u8> {
+    (0..1300).map(|n| (n % 251) as u8).collect()
+}
+
+#[test]
+fn files() {
+    let tmp_dir = TempDir::new("nuts-archive").unwrap();
+    let mut archive = setup_archive();
+
+    archive.append_directory("d").build().unwrap();
+    archive
+        .append_file("d/f1")
+        .build()
+        .unwrap()
+        .write_all(&content())
+        .unwrap();
+    archive.append_file("x/y/f2").build().unwrap();
+
+    archive.unpack(tmp_dir.path()).unwrap();
+
+    assert!(tmp_dir.path().join("d").is_dir());
+    assert_eq!(fs::read(tmp_dir.path().join("d/f1")).unwrap(), content());
+    assert!(tmp_dir.path().join("x/y").is_dir());
+    assert!(fs::read(tmp_dir.path().join("x/y/f2")).unwrap().is_empty());
+}
+
+#[test]
+fn overwrite() {
+    let tmp_dir = TempDir::new("nuts-archive").unwrap();
+    let mut archive = setup_archive();
+
+    fs::write(tmp_dir.path().join("f"), vec![1; 2000]).unwrap();
+
+    archive
+        .append_file("f")
+        .build()
+        .unwrap()
+        .write_all(b"foo")
+        .unwrap();
+
+    archive.unpack(tmp_dir.path()).unwrap();
+
+    assert_eq!(fs::read(tmp_dir.path().join("f")).unwrap(), b"foo");
+}
+
+#[test]
+fn modified() {
+    let tmp_dir = TempDir::new("nuts-archive").unwrap();
+    let mut archive = setup_archive();
+    let modified = chrono::DateTime::from_timestamp(1_000_000_000, 0).unwrap();
+
+    let mut builder = archive.append_file("f");
+    builder.set_modified(modified);
+    builder.build().unwrap();
+
+    archive.unpack(tmp_dir.path()).unwrap();
+
+    let meta = fs::metadata(tmp_dir.path().join("f")).unwrap();
+    assert_eq!(
+        chrono::DateTime::<chrono::Utc>::from(meta.modified().unwrap()),
+        modified
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn links() {
+    let tmp_dir = TempDir::new("nuts-archive").unwrap();
+    let mut archive = setup_archive();
+
+    archive
+        .append_file("f")
+        .build()
+        .unwrap()
+        .write_all(b"foo")
+        .unwrap();
+    archive.append_symlink("s", "f").build().unwrap();
+    archive.append_hardlink("h", "f").build().unwrap();
+    archive
+        .append_special("p", SpecialKind::Fifo)
+        .build()
+        .unwrap();
+
+    archive.unpack(tmp_dir.path()).unwrap();
+
+    let path = tmp_dir.path();
+    assert_eq!(fs::read_link(path.join("s")).unwrap().to_str(), Some("f"));
+    assert_eq!(fs::read(path.join("s")).unwrap(), b"foo");
+    assert_eq!(fs::read(path.join("h")).unwrap(), b"foo");
+
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        let f = fs::metadata(path.join("f")).unwrap();
+        let h = fs::metadata(path.join("h")).unwrap();
+
+        assert_eq!(f.ino(), h.ino());
+        assert_eq!(f.nlink(), 2);
+    }
+
+    assert!(!path.join("p").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_replaced_by_file() {
+    let tmp_dir = TempDir::new("nuts-archive").unwrap();
+    let mut archive = setup_archive();
+
+    fs::write(tmp_dir.path().join("victim"), b"original").unwrap();
+
+    archive.append_symlink("evil", "victim").build().unwrap();
+    archive
+        .append_file("evil")
+        .build()
+        .unwrap()
+        .write_all(b"foo")
+        .unwrap();
+
+    archive.unpack(tmp_dir.path()).unwrap();
+
+    // the file replaces the symlink, it is not written through the link
+    let path = tmp_dir.path().join("evil");
+    assert!(fs::symlink_metadata(&path).unwrap().is_file());
+    assert_eq!(fs::read(&path).unwrap(), b"foo");
+    assert_eq!(
+        fs::read(tmp_dir.path().join("victim")).unwrap(),
+        b"original"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn unix_mode() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tmp_dir = TempDir::new("nuts-archive").unwrap();
+    let mut archive = setup_archive();
+
+    let mut builder = archive.append_directory("d");
+    builder.set_unix_mode(0o555);
+    builder.build().unwrap();
+
+    let mut builder = archive.append_file("d/f");
+    builder.set_unix_mode(0o640);
+    builder.build().unwrap();
+
+    archive.unpack(tmp_dir.path()).unwrap();
+
+    let d = fs::metadata(tmp_dir.path().join("d")).unwrap();
+    let f = fs::metadata(tmp_dir.path().join("d/f")).unwrap();
+
+    assert_eq!(d.permissions().mode() & 0o7777, 0o555);
+    assert_eq!(f.permissions().mode() & 0o7777, 0o640);
+
+    // make the directory writable again, so it can be cleaned up
+    fs::set_permissions(tmp_dir.path().join("d"), fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+#[test]
+fn file_unpack_to() {
+    let tmp_dir = TempDir::new("nuts-archive").unwrap();
+    let mut archive = setup_archive();
+
+    archive
+        .append_file("f")
+        .build()
+        .unwrap()
+        .write_all(&content())
+        .unwrap();
+
+    let mut file = archive.lookup("f").unwrap().unwrap().into_file().unwrap();
+    let path = tmp_dir.path().join("x/y");
+
+    // starts from the beginning, regardless of the read position
+    file.read(&mut [0; 100]).unwrap();
+    file.unpack_to(&path).unwrap();
+
+    assert_eq!(fs::read(&path).unwrap(), content());
+}
+
+#[test]
+fn directory_unpack_to() {
+    let tmp_dir = TempDir::new("nuts-archive").unwrap();
+    let mut archive = setup_archive();
+
+    archive.append_directory("d").build().unwrap();
+    archive.append_file("d/f").build().unwrap();
+
+    let dir = archive
+        .lookup("d")
+        .unwrap()
+        .unwrap()
+        .into_directory()
+        .unwrap();
+    let path = tmp_dir.path().join("x/d");
+
+    dir.unpack_to(&path).unwrap();
+
+    assert!(path.is_dir());
+    assert_eq!(fs::read_dir(&path).unwrap().count(), 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_unpack_to() {
+    let tmp_dir = TempDir::new("nuts-archive").unwrap();
+    let mut archive = setup_archive();
+
+    archive.append_symlink("s", "target").build().unwrap();
+
+    let symlink = archive
+        .lookup("s")
+        .unwrap()
+        .unwrap()
+        .into_symlink()
+        .unwrap();
+    let path = tmp_dir.path().join("s");
+
+    fs::write(&path, b"foo").unwrap();
+    symlink.unpack_to(&path).unwrap();
+
+    assert_eq!(fs::read_link(&path).unwrap().to_str(), Some("target"));
+}
+
+#[test]
+fn unsafe_path() {
+    let tmp_dir = TempDir::new("nuts-archive").unwrap();
+    let dest = tmp_dir.path().join("dest");
+    let mut archive = setup_archive();
+
+    archive
+        .append_file("../evil")
+        .build()
+        .unwrap()
+        .write_all(b"foo")
+        .unwrap();
+
+    let err = archive.unpack(&dest).unwrap_err();
+    assert!(matches!(err, Error::UnsafePath(ref name) if name == "../evil"));
+    assert!(!tmp_dir.path().join("evil").exists());
+
+    archive.set_path_policy(PathPolicy::Sanitize);
+    archive.unpack(&dest).unwrap();
+
+    assert!(!tmp_dir.path().join("evil").exists());
+    assert_eq!(fs::read(dest.join("evil")).unwrap(), b"foo");
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_parent() {
+    let tmp_dir = TempDir::new("nuts-archive").unwrap();
+    let dest = tmp_dir.path().join("dest");
+    let outside = tmp_dir.path().join("outside");
+    let mut archive = setup_archive();
+
+    fs::create_dir(&dest).unwrap();
+    fs::create_dir(&outside).unwrap();
+    std::os::unix::fs::symlink(&outside, dest.join("s")).unwrap();
+
+    archive.append_file("s/evil").build().unwrap();
+
+    let err = archive.unpack(&dest).unwrap_err();
+    assert!(matches!(err, Error::UnsafePath(ref name) if name == "s/evil"));
+    assert!(!outside.join("evil").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_directory() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tmp_dir = TempDir::new("nuts-archive").unwrap();
+    let dest = tmp_dir.path().join("dest");
+    let outside = tmp_dir.path().join("outside");
+    let mut archive = setup_archive();
+
+    fs::create_dir(&dest).unwrap();
+    fs::create_dir(&outside).unwrap();
+    fs::set_permissions(&outside, fs::Permissions::from_mode(0o755)).unwrap();
+    std::os::unix::fs::symlink(&outside, dest.join("d")).unwrap();
+
+    let mut builder = archive.append_directory("d");
+    builder.set_unix_mode(0o700);
+    builder.build().unwrap();
+
+    // the access rights are not applied through the symlink
+    let err = archive.unpack(&dest).unwrap_err();
+    assert!(matches!(err, Error::UnsafePath(ref name) if name == "d"));
+
+    let mode = fs::metadata(&outside).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o755);
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_target() {
+    let tmp_dir = TempDir::new("nuts-archive").unwrap();
+    let dest = tmp_dir.path().join("dest");
+    let victim = tmp_dir.path().join("victim");
+    let mut archive = setup_archive();
+
+    fs::write(&victim, b"original").unwrap();
+
+    archive
+        .append_symlink("evil", victim.to_str().unwrap())
+        .build()
+        .unwrap();
+    archive.append_symlink("up", "../victim").build().unwrap();
+
+    let err = archive.unpack(&dest).unwrap_err();
+    assert!(matches!(err, Error::UnsafePath(ref name) if name == "evil"));
+    assert!(!dest.join("evil").exists());
+
+    archive.set_path_policy(PathPolicy::Sanitize);
+    archive.unpack(&dest).unwrap();
+
+    // both symlinks stay below the destination
+    let expected = victim.strip_prefix("/").unwrap();
+    assert_eq!(fs::read_link(dest.join("evil")).unwrap(), expected);
+    assert_eq!(
+        fs::read_link(dest.join("up")).unwrap().to_str(),
+        Some("victim")
+    );
+    assert_eq!(fs::read(&victim).unwrap(), b"original");
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_chain() {
+    let tmp_dir = TempDir::new("nuts-archive").unwrap();
+    let dest = tmp_dir.path().join("dest");
+    let mut archive = setup_archive();
+
+    // dir/b/.. is the parent of dest, not dir
+    archive.append_symlink("dir/b", "..").build().unwrap();
+    archive
+        .append_symlink("dir/a", "b/../outside")
+        .build()
+        .unwrap();
+
+    let err = archive.unpack(&dest).unwrap_err();
+    assert!(matches!(err, Error::UnsafePath(ref name) if name == "dir/a"));
+    assert!(!dest.join("dir/a").exists());
+
+    archive.set_path_policy(PathPolicy::Sanitize);
+    archive.unpack(&dest).unwrap();
+
+    assert_eq!(
+        fs::read_link(dest.join("dir/a")).unwrap().to_str(),
+        Some("outside")
+    );
+}
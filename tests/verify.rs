@@ -0,0 +1,97 @@
+// MIT License
+//
+// Copyright (c) 2023 Robin Doer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+
+mod common;
+
+use nuts_archive::{Archive, Compression};
+use nuts_container::memory::MemoryBackend;
+use std::io::SeekFrom;
+
+use common::setup_archive;
+
+fn content() -> Vec<u8> {
+    (0..3000).map(|n| (n % 251) as u8).collect()
+}
+
+fn append_hashed(archive: &mut Archive<MemoryBackend>, name: &str, compression: Compression) {
+    let mut builder = archive.append_file(name);
+    builder.set_hashed(true);
+    builder.set_compression(compression);
+    builder.with_content(&content()).build().unwrap();
+}
+
+#[test]
+fn verify() {
+    let mut archive = setup_archive();
+
+    append_hashed(&mut archive, "f1", Compression::None);
+    append_hashed(&mut archive, "f2", Compression::Deflate);
+    archive
+        .append_file("f3")
+        .with_content(&content())
+        .build()
+        .unwrap();
+
+    for name in ["f1", "f2"] {
+        let mut entry = archive.lookup(name).unwrap().unwrap().into_file().unwrap();
+        let mut buf = [0; 10];
+
+        entry.seek(SeekFrom::Start(1000)).unwrap();
+        assert!(entry.verify().unwrap());
+
+        // the read position is not changed
+        entry.read_all(&mut buf).unwrap();
+        assert_eq!(buf, content()[1000..1010]);
+    }
+
+    let mut entry = archive.lookup("f3").unwrap().unwrap().into_file().unwrap();
+    assert!(!entry.verify().unwrap());
+}
+
+#[test]
+fn verified_reads() {
+    let mut archive = setup_archive();
+
+    append_hashed(&mut archive, "f", Compression::None);
+
+    let mut entry = archive.lookup("f").unwrap().unwrap().into_file().unwrap();
+    entry.set_verified(true);
+
+    let mut buf = [0; 7];
+    let mut result = vec![];
+
+    loop {
+        match entry.read(&mut buf).unwrap() {
+            0 => break,
+            n => result.extend_from_slice(&buf[..n]),
+        }
+    }
+
+    assert_eq!(result, content());
+
+    // read again after seeking
+    entry.seek(SeekFrom::Start(100)).unwrap();
+    assert_eq!(entry.read_vec().unwrap(), content()[100..]);
+
+    entry.seek(SeekFrom::Start(0)).unwrap();
+    assert_eq!(entry.read_vec().unwrap(), content());
+}